@@ -1,5 +1,5 @@
 // SPDX-License-Identifier: MPL-2.0
 
-mod common;
+pub(crate) mod common;
 pub mod depay;
 pub mod pay;
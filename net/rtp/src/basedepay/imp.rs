@@ -912,6 +912,11 @@ impl RtpBaseDepay2 {
 
                 *self.stats.lock().unwrap() = None;
             }
+            gst::EventView::CustomDownstream(ev) => {
+                if let Some(s) = ev.structure().filter(|s| s.name() == "GstRTPPacketLost") {
+                    self.packet_lost(s, event.seqnum());
+                }
+            }
             _ => (),
         }
 
@@ -1752,8 +1757,23 @@ impl RtpBaseDepay2 {
 
         let mut state = self.state.borrow_mut();
         state.pending_packets.clear();
+        self.set_out_of_band_buffers_metadata(&mut state);
+        drop(state);
+
+        // Forward all buffers
+        let res = self.finish_pending_buffers();
+
+        self.flush();
+
+        res
+    }
+
+    /// Updates all pending buffers without metadata with the last buffer's PTS/DTS.
+    fn set_out_of_band_buffers_metadata(&self, state: &mut State) {
+        if state.pending_buffers.iter().all(|b| b.metadata_set) {
+            return;
+        }
 
-        // Update all pending buffers without metadata with the last buffer's PTS/DTS
         let last_pts = state.last_pts;
         let last_dts = state.last_dts;
         let mut discont_pending = state.discont_pending;
@@ -1775,14 +1795,54 @@ impl RtpBaseDepay2 {
                 discont_pending = false;
             }
         }
+    }
+
+    /// Sends a gap event downstream for a `GstRTPPacketLost` event from the jitterbuffer so that
+    /// downstream decoders can conceal the loss.
+    ///
+    /// All buffers that are still queued were produced from packets before the lost ones and are
+    /// pushed first, followed by the pending segment event if there is one. The original event is
+    /// forwarded afterwards by the caller.
+    fn packet_lost(&self, s: &gst::StructureRef, seqnum: gst::Seqnum) {
+        let Ok(timestamp) = s.get::<gst::ClockTime>("timestamp") else {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Lost event without timestamp, not sending gap"
+            );
+            return;
+        };
+        let duration = s.get::<gst::ClockTime>("duration").ok();
+
+        let mut state = self.state.borrow_mut();
+        self.set_out_of_band_buffers_metadata(&mut state);
         drop(state);
 
-        // Forward all buffers
-        let res = self.finish_pending_buffers();
+        if let Err(err) = self.finish_pending_buffers() {
+            gst::debug!(CAT, imp = self, "Failed finishing pending buffers: {err:?}");
+        }
 
-        self.flush();
+        let mut state = self.state.borrow_mut();
+        let segment_event = self.retrieve_pending_segment_event(&mut state);
+        if state.segment.is_none() || state.pending_segment {
+            gst::debug!(CAT, imp = self, "No segment yet, not sending gap");
+            return;
+        }
 
-        res
+        // The jitterbuffer timestamps lost packets on the same timeline as the packets, which is
+        // also where queued buffers get their timestamps from, so the gap can be used as is.
+        let gap = gst::event::Gap::builder(timestamp)
+            .duration(duration)
+            .seqnum(seqnum)
+            .build();
+        drop(state);
+
+        if let Some(segment_event) = segment_event {
+            let _ = self.src_pad.push_event(segment_event);
+        }
+
+        gst::debug!(CAT, imp = self, "Packets lost, sending gap event {gap:?}");
+        let _ = self.src_pad.push_event(gap);
     }
 
     fn flush(&self) {
@@ -13,6 +13,9 @@ use gst::{glib, prelude::*, subclass::prelude::*};
 
 pub mod imp;
 
+#[cfg(test)]
+mod tests;
+
 glib::wrapper! {
     pub struct RtpBaseDepay2(ObjectSubclass<imp::RtpBaseDepay2>)
         @extends gst::Element, gst::Object;
@@ -67,8 +70,8 @@ pub trait RtpBaseDepay2Ext: IsA<RtpBaseDepay2> + 'static {
     ///
     /// If passing `OutOfBand` then the buffer is assumed to be produced using some other data,
     /// e.g. from the caps, and not associated with any packets. In that case it will be pushed
-    /// right before the next buffer with the timestamp of that buffer, or at EOS with the
-    /// timestamp of the previous buffer.
+    /// right before the next buffer with the timestamp of that buffer, or at EOS or when packets
+    /// were lost with the timestamp of the previous buffer.
     ///
     /// In all other cases a seqnum range is provided and needs to be valid.
     ///
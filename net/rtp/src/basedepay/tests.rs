@@ -0,0 +1,217 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::sync::{Arc, Mutex};
+
+use gst::{glib, prelude::*};
+use gst_check::Harness;
+
+mod imp {
+    use gst::{glib, subclass::prelude::*};
+
+    use std::sync::LazyLock;
+
+    use crate::basedepay::{PacketToBufferRelation, RtpBaseDepay2Ext};
+
+    /// Depayloader that keeps all its output queued as out-of-band buffers until the base class
+    /// has to push them.
+    #[derive(Default)]
+    pub struct QueueingDepay;
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for QueueingDepay {
+        const NAME: &'static str = "GstRtpTestQueueingDepay";
+        type Type = super::QueueingDepay;
+        type ParentType = crate::basedepay::RtpBaseDepay2;
+    }
+
+    impl ObjectImpl for QueueingDepay {}
+
+    impl GstObjectImpl for QueueingDepay {}
+
+    impl ElementImpl for QueueingDepay {
+        fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+            static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> =
+                LazyLock::new(|| {
+                    gst::subclass::ElementMetadata::new(
+                        "RTP Test Queueing Depayloader",
+                        "Codec/Depayloader/Network/RTP",
+                        "Queues all depayloaded data as out-of-band buffers",
+                        "agent <agent@local>",
+                    )
+                });
+
+            Some(&*ELEMENT_METADATA)
+        }
+
+        fn pad_templates() -> &'static [gst::PadTemplate] {
+            static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+                let sink_pad_template = gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("application/x-rtp").build(),
+                )
+                .unwrap();
+
+                let src_pad_template = gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("application/x-test").build(),
+                )
+                .unwrap();
+
+                vec![src_pad_template, sink_pad_template]
+            });
+
+            PAD_TEMPLATES.as_ref()
+        }
+    }
+
+    impl crate::basedepay::RtpBaseDepay2Impl for QueueingDepay {
+        fn set_sink_caps(&self, _caps: &gst::Caps) -> bool {
+            self.obj()
+                .set_src_caps(&gst::Caps::builder("application/x-test").build());
+
+            true
+        }
+
+        fn handle_packet(
+            &self,
+            packet: &crate::basedepay::Packet,
+        ) -> Result<gst::FlowSuccess, gst::FlowError> {
+            self.obj()
+                .queue_buffer(PacketToBufferRelation::OutOfBand, packet.payload_buffer())
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct QueueingDepay(ObjectSubclass<imp::QueueingDepay>)
+        @extends crate::basedepay::RtpBaseDepay2, gst::Element, gst::Object;
+}
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+    });
+}
+
+fn packet_lost_event(timestamp: gst::ClockTime, duration: gst::ClockTime) -> gst::Event {
+    gst::event::CustomDownstream::builder(
+        gst::Structure::builder("GstRTPPacketLost")
+            .field("seqnum", 0u32)
+            .field("timestamp", timestamp)
+            .field("duration", duration)
+            .build(),
+    )
+    .build()
+}
+
+#[test]
+fn test_packet_lost_gap_ordering() {
+    init();
+
+    let depay = glib::Object::new::<QueueingDepay>();
+
+    // Record the order in which data leaves the depayloader
+    let items = Arc::new(Mutex::new(Vec::<String>::new()));
+    depay.static_pad("src").unwrap().add_probe(
+        gst::PadProbeType::BUFFER | gst::PadProbeType::EVENT_DOWNSTREAM,
+        {
+            let items = items.clone();
+            move |_pad, info| {
+                let item = match info.data {
+                    Some(gst::PadProbeData::Buffer(_)) => Some(String::from("buffer")),
+                    Some(gst::PadProbeData::Event(ref event)) => match event.view() {
+                        gst::EventView::Caps(_) => Some(String::from("caps")),
+                        gst::EventView::Segment(_) => Some(String::from("segment")),
+                        gst::EventView::Gap(gap) => {
+                            let (timestamp, duration) = gap.get();
+                            Some(format!("gap {timestamp} {}", duration.display()))
+                        }
+                        gst::EventView::CustomDownstream(ev)
+                            if ev
+                                .structure()
+                                .is_some_and(|s| s.name() == "GstRTPPacketLost") =>
+                        {
+                            Some(String::from("lost"))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some(item) = item {
+                    items.lock().unwrap().push(item);
+                }
+                gst::PadProbeReturn::Ok
+            }
+        },
+    );
+
+    let mut h = Harness::with_element(&depay, Some("sink"), Some("src"));
+    h.set_src_caps(
+        gst::Caps::builder("application/x-rtp")
+            .field("media", "audio")
+            .field("clock-rate", 8000i32)
+            .field("encoding-name", "X-TEST")
+            .build(),
+    );
+    h.play();
+
+    // The segment is only sent once there is output, so it has to go out before the gap
+    assert!(h.push_event(packet_lost_event(
+        gst::ClockTime::ZERO,
+        gst::ClockTime::from_mseconds(20)
+    )));
+    assert_eq!(
+        *items.lock().unwrap(),
+        [
+            "caps",
+            "segment",
+            "gap 0:00:00.000000000 0:00:00.020000000",
+            "lost"
+        ]
+    );
+    items.lock().unwrap().clear();
+
+    // The depayloader keeps the buffer for this packet queued, and it has to go out before the gap
+    let data = rtp_types::RtpPacketBuilder::new()
+        .payload_type(96)
+        .ssrc(0x12345678)
+        .sequence_number(2)
+        .timestamp(320)
+        .payload([1u8, 2, 3, 4].as_slice())
+        .write_vec()
+        .unwrap();
+    let mut buffer = gst::Buffer::from_mut_slice(data);
+    buffer
+        .get_mut()
+        .unwrap()
+        .set_pts(gst::ClockTime::from_mseconds(40));
+    h.push(buffer).unwrap();
+    assert!(items.lock().unwrap().is_empty());
+
+    assert!(h.push_event(packet_lost_event(
+        gst::ClockTime::from_mseconds(60),
+        gst::ClockTime::from_mseconds(20)
+    )));
+    assert_eq!(
+        *items.lock().unwrap(),
+        ["buffer", "gap 0:00:00.060000000 0:00:00.020000000", "lost"]
+    );
+
+    assert_eq!(h.buffers_in_queue(), 1);
+    let buffer = h.pull().unwrap();
+    assert_eq!(buffer.map_readable().unwrap().as_slice(), [1, 2, 3, 4]);
+}
@@ -0,0 +1,624 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtphdrextdependencydescriptor
+ * @see_also: rtplayerselector, rtpvp9pay2, rtpav1pay
+ *
+ * RTP header extension writing the [Dependency Descriptor] of scalable VP9 and AV1 streams. It
+ * describes the spatial and temporal layer of each frame and the frames it depends on, which
+ * allows receivers and SFUs to decode or forward a subset of the layers without having to parse
+ * the codec specific payload.
+ *
+ * The layer structure is configured via
+ * #rtphdrextdependencydescriptor:scalability-mode and sent with every keyframe. The layer of each
+ * packet is taken from the payload: the layer index of the VP9 payload descriptor, or the OBU
+ * extension header for AV1. Frames that do not follow the reference pattern of the configured
+ * scalability mode are signalled with their actual frame dependencies.
+ *
+ * For AV1, a new frame is assumed to start after a packet with the marker bit set or when the
+ * spatial layer changes, and the end of a frame is only signalled for the last packet of a
+ * temporal unit.
+ *
+ * Reading the extension is not implemented.
+ *
+ * [Dependency Descriptor]: https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use anyhow::bail;
+use bitstream_io::{BigEndian, BitWrite as _, BitWriter};
+use gst::{glib, prelude::*, subclass::prelude::*};
+use gst_rtp::subclass::prelude::*;
+use std::{
+    io,
+    sync::{LazyLock, Mutex},
+};
+
+use crate::layerselector::imp::{Codec, PacketInfo};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtphdrextdependencydescriptor",
+        gst::DebugColorFlags::empty(),
+        Some("RTP Dependency Descriptor Header Extension"),
+    )
+});
+
+pub const URI: &str =
+    "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension";
+
+const DEFAULT_SCALABILITY_MODE: &str = "L1T1";
+
+/// Largest frame dependency that can be signalled per frame
+const MAX_FDIFF: u16 = 1 << 12;
+
+/// Size of the descriptor of packets without the template dependency structure: mandatory fields,
+/// extended flags and up to two custom frame dependencies
+const MAX_SIZE_WITHOUT_STRUCTURE: usize = 8;
+
+/// Decode target indications
+const DTI_NOT_PRESENT: u8 = 0;
+const DTI_DISCARDABLE: u8 = 1;
+const DTI_SWITCH: u8 = 2;
+const DTI_REQUIRED: u8 = 3;
+
+/// Parses a scalability mode of the form `LxTy` into the number of spatial and temporal layers.
+pub(crate) fn parse_scalability_mode(mode: &str) -> Result<(u8, u8), anyhow::Error> {
+    let layers = mode
+        .strip_prefix('L')
+        .and_then(|mode| mode.split_once('T'))
+        .and_then(|(spatial, temporal)| Some((spatial.parse().ok()?, temporal.parse().ok()?)));
+
+    match layers {
+        Some((spatial @ 1..=3, temporal @ 1..=3)) => Ok((spatial, temporal)),
+        _ => bail!("Unsupported scalability mode {mode}"),
+    }
+}
+
+/// Temporal layer ids of the pictures of one period of the temporal layer pattern.
+fn temporal_pattern(temporal_layers: u8) -> &'static [u8] {
+    match temporal_layers {
+        1 => &[0],
+        2 => &[0, 1],
+        _ => &[0, 2, 1, 2],
+    }
+}
+
+/// Distance in pictures from a picture of temporal layer `temporal_layer` to the picture it
+/// references in the temporal layer pattern.
+fn temporal_distance(temporal_layers: u8, temporal_layer: u8) -> u16 {
+    let pattern = temporal_pattern(temporal_layers);
+    if temporal_layer == 0 {
+        return pattern.len() as u16;
+    }
+
+    let pos = pattern.iter().position(|&id| id == temporal_layer).unwrap();
+    let reference = pattern[..pos]
+        .iter()
+        .rposition(|&id| id < temporal_layer)
+        .unwrap();
+
+    (pos - reference) as u16
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Template {
+    spatial_layer: u8,
+    temporal_layer: u8,
+    /// Template of the frames of a keyframe picture
+    key: bool,
+    fdiffs: Vec<u16>,
+    dtis: Vec<u8>,
+}
+
+/// Template dependency structure of a scalability mode.
+///
+/// Each spatial layer has a template for the frames of keyframe pictures, which only depend on
+/// the lower spatial layer, and one template per temporal layer, which depend on the previous
+/// frame of the temporal layer pattern and the lower spatial layer. There is one decode target per
+/// spatial and temporal layer.
+#[derive(Debug, Clone)]
+struct Structure {
+    spatial_layers: u8,
+    temporal_layers: u8,
+    templates: Vec<Template>,
+}
+
+impl Structure {
+    fn new(spatial_layers: u8, temporal_layers: u8) -> Self {
+        let mut templates = Vec::new();
+
+        for spatial_layer in 0..spatial_layers {
+            templates.push(Template {
+                spatial_layer,
+                temporal_layer: 0,
+                key: true,
+                fdiffs: if spatial_layer == 0 { vec![] } else { vec![1] },
+                dtis: vec![],
+            });
+
+            for temporal_layer in 0..temporal_layers {
+                let mut fdiffs = vec![
+                    temporal_distance(temporal_layers, temporal_layer) * spatial_layers as u16,
+                ];
+                if spatial_layer > 0 {
+                    fdiffs.push(1);
+                }
+
+                templates.push(Template {
+                    spatial_layer,
+                    temporal_layer,
+                    key: false,
+                    fdiffs,
+                    dtis: vec![],
+                });
+            }
+        }
+
+        for template in &mut templates {
+            template.dtis = (0..spatial_layers)
+                .flat_map(|spatial_layer| {
+                    (0..temporal_layers).map(move |temporal_layer| (spatial_layer, temporal_layer))
+                })
+                .map(|(spatial_layer, temporal_layer)| {
+                    if template.spatial_layer > spatial_layer
+                        || template.temporal_layer > temporal_layer
+                    {
+                        DTI_NOT_PRESENT
+                    } else if template.key {
+                        DTI_SWITCH
+                    } else if template.temporal_layer == temporal_layer
+                        && template.temporal_layer > 0
+                        && template.spatial_layer == spatial_layer
+                    {
+                        DTI_DISCARDABLE
+                    } else {
+                        DTI_REQUIRED
+                    }
+                })
+                .collect();
+        }
+
+        Structure {
+            spatial_layers,
+            temporal_layers,
+            templates,
+        }
+    }
+
+    fn decode_target_count(&self) -> u32 {
+        self.spatial_layers as u32 * self.temporal_layers as u32
+    }
+
+    fn write<W: io::Write>(&self, w: &mut BitWriter<W, BigEndian>) -> io::Result<()> {
+        let decode_target_count = self.decode_target_count();
+
+        // template_id_offset
+        w.write(6, 0u8)?;
+        // dt_cnt_minus_one
+        w.write(5, decode_target_count - 1)?;
+
+        // template_layers: next_layer_idc of each template
+        for (template, next) in self
+            .templates
+            .iter()
+            .zip(self.templates.iter().skip(1).map(Some).chain([None]))
+        {
+            let next_layer_idc = match next {
+                None => 3u8,
+                Some(next) if next.spatial_layer > template.spatial_layer => 2,
+                Some(next) if next.temporal_layer > template.temporal_layer => 1,
+                Some(_) => 0,
+            };
+            w.write(2, next_layer_idc)?;
+        }
+
+        // template_dtis
+        for template in &self.templates {
+            for &dti in &template.dtis {
+                w.write(2, dti)?;
+            }
+        }
+
+        // template_fdiffs
+        for template in &self.templates {
+            for &fdiff in &template.fdiffs {
+                w.write_bit(true)?;
+                w.write(4, fdiff - 1)?;
+            }
+            w.write_bit(false)?;
+        }
+
+        // template_chains: chain_cnt = ns(dt_cnt + 1) = 0
+        let n = decode_target_count + 1;
+        let width = u32::BITS - n.leading_zeros();
+        w.write(width - 1, 0u32)?;
+
+        // resolutions_present_flag
+        w.write_bit(false)?;
+
+        Ok(())
+    }
+
+    /// Size of the structure in bytes, rounded up.
+    fn size(&self) -> usize {
+        let mut w = BitWriter::endian(Vec::new(), BigEndian);
+        self.write(&mut w).unwrap();
+        w.byte_align().unwrap();
+        w.into_writer().len()
+    }
+
+    /// Template for a frame of the given layers, preferring one with matching frame dependencies.
+    fn template(&self, spatial_layer: u8, temporal_layer: u8, fdiffs: &[u16]) -> (usize, bool) {
+        let mut fallback = 0;
+        for (id, template) in self.templates.iter().enumerate() {
+            if template.spatial_layer != spatial_layer || template.temporal_layer != temporal_layer
+            {
+                continue;
+            }
+            if template.fdiffs == fdiffs {
+                return (id, true);
+            }
+            if !template.key {
+                fallback = id;
+            }
+        }
+
+        (fallback, false)
+    }
+}
+
+#[derive(Debug)]
+struct Frame {
+    frame_number: u16,
+    spatial_layer: u8,
+    template_id: u8,
+    /// Frame dependencies if they differ from the template
+    custom_fdiffs: Option<Vec<u16>>,
+    /// The template dependency structure still has to be sent with this frame
+    attach_structure: bool,
+}
+
+#[derive(Debug)]
+struct State {
+    codec: Option<Codec>,
+    structure: Structure,
+    structure_size: usize,
+    frame: Option<Frame>,
+    /// Frame number of the last frame of each spatial and temporal layer
+    last_frames: [[Option<u16>; 3]; 3],
+    last_marker: bool,
+}
+
+impl State {
+    fn new(spatial_layers: u8, temporal_layers: u8) -> Self {
+        let structure = Structure::new(spatial_layers, temporal_layers);
+        let structure_size = structure.size();
+
+        State {
+            codec: None,
+            structure,
+            structure_size,
+            frame: None,
+            last_frames: [[None; 3]; 3],
+            last_marker: true,
+        }
+    }
+
+    fn start_frame(&mut self, info: &PacketInfo) {
+        let spatial_layer = info.spatial_layer.min(self.structure.spatial_layers - 1);
+        let temporal_layer = info.temporal_layer.min(self.structure.temporal_layers - 1);
+        let keyframe = info.keyframe && spatial_layer == 0;
+
+        let frame_number = self
+            .frame
+            .as_ref()
+            .map_or(0, |frame| frame.frame_number.wrapping_add(1));
+
+        if keyframe {
+            self.last_frames = [[None; 3]; 3];
+        }
+
+        let mut fdiffs = Vec::with_capacity(2);
+
+        // Previous frame of the same spatial layer and a lower temporal layer, or of the base
+        // temporal layer for frames of the base temporal layer
+        let references =
+            &self.last_frames[spatial_layer as usize][..(temporal_layer as usize).max(1)];
+        if let Some(fdiff) = references
+            .iter()
+            .flatten()
+            .map(|&reference| frame_number.wrapping_sub(reference))
+            .filter(|&fdiff| fdiff > 0 && fdiff <= MAX_FDIFF)
+            .min()
+        {
+            fdiffs.push(fdiff);
+        }
+
+        // Lower spatial layer of the same picture
+        if spatial_layer > 0
+            && self.frame.as_ref().is_some_and(|frame| {
+                frame.frame_number == frame_number.wrapping_sub(1)
+                    && frame.spatial_layer + 1 == spatial_layer
+            })
+        {
+            fdiffs.push(1);
+        }
+
+        let (template_id, matches) =
+            self.structure
+                .template(spatial_layer, temporal_layer, &fdiffs);
+
+        gst::trace!(
+            CAT,
+            "Frame {frame_number} of layer S{spatial_layer}T{temporal_layer} with dependencies {fdiffs:?} uses template {template_id}",
+        );
+
+        self.last_frames[spatial_layer as usize][temporal_layer as usize] = Some(frame_number);
+        self.frame = Some(Frame {
+            frame_number,
+            spatial_layer,
+            template_id: template_id as u8,
+            custom_fdiffs: (!matches).then_some(fdiffs),
+            attach_structure: keyframe,
+        });
+    }
+
+    fn write_descriptor(
+        &mut self,
+        start_of_frame: bool,
+        end_of_frame: bool,
+    ) -> io::Result<Vec<u8>> {
+        let frame = self.frame.as_mut().unwrap();
+        let attach_structure = std::mem::take(&mut frame.attach_structure);
+
+        let mut w = BitWriter::endian(Vec::with_capacity(8), BigEndian);
+
+        w.write_bit(start_of_frame)?;
+        w.write_bit(end_of_frame)?;
+        w.write(6, frame.template_id)?;
+        w.write(16, frame.frame_number)?;
+
+        if attach_structure || frame.custom_fdiffs.is_some() {
+            // template_dependency_structure_present_flag
+            w.write_bit(attach_structure)?;
+            // active_decode_targets_present_flag
+            w.write_bit(false)?;
+            // custom_dtis_flag
+            w.write_bit(false)?;
+            // custom_fdiffs_flag
+            w.write_bit(frame.custom_fdiffs.is_some())?;
+            // custom_chains_flag
+            w.write_bit(false)?;
+
+            if attach_structure {
+                self.structure.write(&mut w)?;
+            }
+
+            if let Some(ref fdiffs) = frame.custom_fdiffs {
+                for &fdiff in fdiffs {
+                    let size = match fdiff - 1 {
+                        0..=0xf => 1u32,
+                        0x10..=0xff => 2,
+                        _ => 3,
+                    };
+                    w.write(2, size)?;
+                    w.write(4 * size, fdiff - 1)?;
+                }
+                w.write(2, 0u8)?;
+            }
+        }
+
+        w.byte_align()?;
+
+        Ok(w.into_writer())
+    }
+}
+
+struct Settings {
+    scalability_mode: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            scalability_mode: String::from(DEFAULT_SCALABILITY_MODE),
+        }
+    }
+}
+
+pub struct RtpHeaderExtensionDependencyDescriptor {
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl Default for RtpHeaderExtensionDependencyDescriptor {
+    fn default() -> Self {
+        Self {
+            settings: Mutex::default(),
+            state: Mutex::new(State::new(1, 1)),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpHeaderExtensionDependencyDescriptor {
+    const NAME: &'static str = "GstRtpHeaderExtensionDependencyDescriptor";
+    type Type = super::RtpHeaderExtensionDependencyDescriptor;
+    type ParentType = gst_rtp::RTPHeaderExtension;
+}
+
+impl ObjectImpl for RtpHeaderExtensionDependencyDescriptor {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![glib::ParamSpecString::builder("scalability-mode")
+                .nick("Scalability Mode")
+                .blurb("Number of spatial and temporal layers of the stream, e.g. L1T3")
+                .default_value(Some(DEFAULT_SCALABILITY_MODE))
+                .mutable_playing()
+                .build()]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "scalability-mode" => {
+                let mode = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| String::from(DEFAULT_SCALABILITY_MODE));
+                let (spatial_layers, temporal_layers) = match parse_scalability_mode(&mode) {
+                    Ok(layers) => layers,
+                    Err(err) => {
+                        gst::error!(CAT, imp = self, "{err}");
+                        return;
+                    }
+                };
+
+                // The new structure is sent with the next keyframe
+                let mut state = self.state.lock().unwrap();
+                let codec = state.codec;
+                *state = State::new(spatial_layers, temporal_layers);
+                state.codec = codec;
+                drop(state);
+
+                self.settings.lock().unwrap().scalability_mode = mode;
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "scalability-mode" => {
+                let settings = self.settings.lock().unwrap();
+                settings.scalability_mode.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpHeaderExtensionDependencyDescriptor {}
+
+impl ElementImpl for RtpHeaderExtensionDependencyDescriptor {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP Dependency Descriptor Header Extension",
+                "Network/Extension/RTPHeader",
+                "Writes the dependency descriptor of scalable video streams",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+}
+
+impl RTPHeaderExtensionImpl for RtpHeaderExtensionDependencyDescriptor {
+    const URI: &'static str = URI;
+
+    fn supported_flags(&self) -> gst_rtp::RTPHeaderExtensionFlags {
+        gst_rtp::RTPHeaderExtensionFlags::ONE_BYTE | gst_rtp::RTPHeaderExtensionFlags::TWO_BYTE
+    }
+
+    fn max_size(&self, input: &gst::BufferRef) -> usize {
+        if input.flags().contains(gst::BufferFlags::DELTA_UNIT) {
+            MAX_SIZE_WITHOUT_STRUCTURE
+        } else {
+            MAX_SIZE_WITHOUT_STRUCTURE + self.state.lock().unwrap().structure_size
+        }
+    }
+
+    fn write(
+        &self,
+        _input: &gst::BufferRef,
+        _write_flags: gst_rtp::RTPHeaderExtensionFlags,
+        output: &gst::BufferRef,
+        output_data: &mut [u8],
+    ) -> Result<usize, gst::LoggableError> {
+        let mut state = self.state.lock().unwrap();
+
+        let Some(codec) = state.codec else {
+            return Err(gst::loggable_error!(CAT, "No codec configured"));
+        };
+
+        let map = output
+            .map_readable()
+            .map_err(|_| gst::loggable_error!(CAT, "Failed to map output buffer"))?;
+        let packet = rtp_types::RtpPacket::parse(&map)
+            .map_err(|err| gst::loggable_error!(CAT, "Failed to parse RTP packet: {err:?}"))?;
+        let marker = packet.marker_bit();
+        let info = PacketInfo::parse(codec, packet.payload())
+            .map_err(|err| gst::loggable_error!(CAT, "Failed to parse payload: {err}"))?;
+        drop(map);
+
+        let (start_of_frame, end_of_frame) = match codec {
+            Codec::Av1 => (
+                state.last_marker
+                    || info.start_of_frame
+                        && state.frame.as_ref().is_some_and(|frame| {
+                            frame.spatial_layer
+                                != info.spatial_layer.min(state.structure.spatial_layers - 1)
+                        }),
+                marker,
+            ),
+            _ => (info.start_of_frame, info.end_of_frame),
+        };
+        state.last_marker = marker;
+
+        if start_of_frame {
+            state.start_frame(&info);
+        } else if state.frame.is_none() {
+            gst::trace!(CAT, imp = self, "Waiting for the start of a frame");
+            return Ok(0);
+        }
+
+        let descriptor = state
+            .write_descriptor(start_of_frame, end_of_frame)
+            .map_err(|err| gst::loggable_error!(CAT, "Failed to write descriptor: {err}"))?;
+
+        if descriptor.len() > output_data.len() {
+            return Err(gst::loggable_error!(
+                CAT,
+                "Descriptor of {} bytes does not fit into {} bytes",
+                descriptor.len(),
+                output_data.len()
+            ));
+        }
+        output_data[..descriptor.len()].copy_from_slice(&descriptor);
+
+        Ok(descriptor.len())
+    }
+
+    fn read(
+        &self,
+        _read_flags: gst_rtp::RTPHeaderExtensionFlags,
+        _input_data: &[u8],
+        _output: &mut gst::BufferRef,
+    ) -> Result<(), gst::LoggableError> {
+        Ok(())
+    }
+
+    fn set_non_rtp_sink_caps(&self, caps: &gst::Caps) -> Result<(), gst::LoggableError> {
+        let codec = match caps.structure(0).map(|s| s.name().as_str()) {
+            Some("video/x-vp9") => Codec::Vp9,
+            Some("video/x-av1") => Codec::Av1,
+            _ => return Err(gst::loggable_error!(CAT, "Unsupported caps {caps}")),
+        };
+
+        self.state.lock().unwrap().codec = Some(codec);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtphdrextdependencydescriptor test");
+    });
+}
+
+fn extension(scalability_mode: &str) -> gst_rtp::RTPHeaderExtension {
+    let ext = gst_rtp::RTPHeaderExtension::create_from_uri(super::imp::URI).unwrap();
+    ext.set_id(1);
+    ext.set_property("scalability-mode", scalability_mode);
+    assert!(ext.set_non_rtp_sink_caps(&gst::Caps::new_empty_simple("video/x-vp9")));
+    ext
+}
+
+/// Writes the descriptor for a single packet VP9 frame without layer index.
+fn write(ext: &gst_rtp::RTPHeaderExtension, keyframe: bool) -> Vec<u8> {
+    // B and E bits, and the P bit for inter-picture predicted frames
+    let payload = [if keyframe { 0x0c } else { 0x4c }, 0, 0, 0];
+    let data = rtp_types::RtpPacketBuilder::new()
+        .payload_type(96)
+        .marker_bit(true)
+        .payload(payload.as_slice())
+        .write_vec()
+        .unwrap();
+    let output = gst::Buffer::from_mut_slice(data);
+
+    let mut input = gst::Buffer::new();
+    if !keyframe {
+        input
+            .get_mut()
+            .unwrap()
+            .set_flags(gst::BufferFlags::DELTA_UNIT);
+    }
+
+    let mut descriptor = vec![0; ext.max_size(&input)];
+    let len = ext
+        .write(
+            &input,
+            gst_rtp::RTPHeaderExtensionFlags::TWO_BYTE,
+            &output,
+            &mut descriptor,
+        )
+        .unwrap();
+    descriptor.truncate(len);
+
+    descriptor
+}
+
+#[test]
+fn test_template_structure() {
+    init();
+
+    let ext = extension("L1T1");
+
+    // Keyframe with the template dependency structure
+    assert_eq!(
+        write(&ext, true),
+        [0xc0, 0x00, 0x00, 0x80, 0x00, 0x3b, 0x40, 0x00]
+    );
+    // Delta frame matching the template of the base layer
+    assert_eq!(write(&ext, false), [0xc1, 0x00, 0x01]);
+}
+
+#[test]
+fn test_custom_fdiffs() {
+    init();
+
+    let ext = extension("L1T3");
+
+    let keyframe = write(&ext, true);
+    assert_eq!(keyframe[..3], [0xc0, 0x00, 0x00]);
+    // The template dependency structure is present
+    assert_eq!(keyframe[3] & 0x80, 0x80);
+
+    // Without layer index all frames are in the base layer and reference the previous frame,
+    // while the template of the base layer references the frame 4 pictures before
+    assert_eq!(write(&ext, false), [0xc1, 0x00, 0x01, 0x12, 0x00]);
+}
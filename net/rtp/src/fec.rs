@@ -0,0 +1,402 @@
+// GStreamer RTP Forward Error Correction helpers
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! XOR parity based forward error correction shared by the ULPFEC (RFC 5109) and FlexFEC
+//! (RFC 8627) elements.
+//!
+//! Both schemes protect a set of media packets with a single repair packet that carries the XOR
+//! of the RTP header fields that can't be derived otherwise, the XOR of the packet lengths and the
+//! XOR of everything following the fixed 12 byte RTP header. A single missing packet of the set
+//! can be recovered once all other packets and the repair packet are available.
+
+use std::collections::{HashMap, VecDeque};
+
+use smallvec::SmallVec;
+
+/// Length of the fixed RTP header, without CSRCs.
+pub const RTP_HEADER_LEN: usize = 12;
+
+/// Number of media packets kept around per decoder for recovering packets.
+const MAX_STORED_MEDIA_PACKETS: usize = 1024;
+/// Number of repair packets kept around per decoder while waiting for missing packets.
+const MAX_PENDING_REPAIR_PACKETS: usize = 256;
+
+/// Accumulated parity over a set of RTP packets.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Parity {
+    /// XOR of the first byte of the RTP headers (version, padding, extension, CSRC count).
+    pub first_byte: u8,
+    /// XOR of the second byte of the RTP headers (marker, payload type).
+    pub second_byte: u8,
+    /// XOR of the RTP timestamps.
+    pub timestamp: u32,
+    /// XOR of the packet lengths without the fixed RTP header.
+    pub length: u16,
+    /// XOR of everything after the fixed RTP header, zero-padded to the longest packet.
+    pub payload: Vec<u8>,
+}
+
+impl Parity {
+    /// Adds the RTP packet to the parity.
+    ///
+    /// The packet must be at least `RTP_HEADER_LEN` bytes long.
+    pub fn add(&mut self, packet: &[u8]) {
+        assert!(packet.len() >= RTP_HEADER_LEN);
+
+        self.first_byte ^= packet[0];
+        self.second_byte ^= packet[1];
+        self.timestamp ^= u32::from_be_bytes([packet[4], packet[5], packet[6], packet[7]]);
+        self.length ^= (packet.len() - RTP_HEADER_LEN) as u16;
+
+        let data = &packet[RTP_HEADER_LEN..];
+        if self.payload.len() < data.len() {
+            self.payload.resize(data.len(), 0);
+        }
+        for (p, d) in self.payload.iter_mut().zip(data) {
+            *p ^= *d;
+        }
+    }
+
+    /// Reconstructs the single missing packet once all other protected packets were added to
+    /// the parity of the repair packet.
+    ///
+    /// Returns `None` if the repair packet does not cover the whole missing packet.
+    pub fn recover(&self, seqnum: u16, ssrc: u32) -> Option<Vec<u8>> {
+        let length = self.length as usize;
+        if length > self.payload.len() {
+            return None;
+        }
+
+        let mut packet = Vec::with_capacity(RTP_HEADER_LEN + length);
+        // The version is not protected and always 2
+        packet.push(0x80 | (self.first_byte & 0x3f));
+        packet.push(self.second_byte);
+        packet.extend_from_slice(&seqnum.to_be_bytes());
+        packet.extend_from_slice(&self.timestamp.to_be_bytes());
+        packet.extend_from_slice(&ssrc.to_be_bytes());
+        packet.extend_from_slice(&self.payload[..length]);
+
+        Some(packet)
+    }
+}
+
+/// Sequence number of an RTP packet.
+pub fn packet_seqnum(packet: &[u8]) -> u16 {
+    u16::from_be_bytes([packet[2], packet[3]])
+}
+
+/// SSRC of an RTP packet.
+pub fn packet_ssrc(packet: &[u8]) -> u32 {
+    u32::from_be_bytes([packet[8], packet[9], packet[10], packet[11]])
+}
+
+/// A received repair packet and the media packets it protects.
+#[derive(Debug, Clone)]
+pub struct RepairPacket {
+    /// SSRC of the protected media packets.
+    pub ssrc: u32,
+    /// Sequence numbers of the protected media packets.
+    pub seqnums: Vec<u16>,
+    /// Parity over the protected media packets.
+    pub parity: Parity,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DecoderStats {
+    /// Number of repair packets received.
+    pub repair_packets: u64,
+    /// Number of media packets recovered.
+    pub recovered: u64,
+    /// Number of protected media packets that could not be recovered.
+    pub unrecovered: u64,
+}
+
+/// Recovers missing media packets from received media and repair packets.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    media: HashMap<(u32, u16), Vec<u8>>,
+    media_order: VecDeque<(u32, u16)>,
+    pending: VecDeque<RepairPacket>,
+    unrecovered: VecDeque<(u32, u16)>,
+    stats: DecoderStats,
+}
+
+impl Decoder {
+    pub fn stats(&self) -> DecoderStats {
+        self.stats
+    }
+
+    /// Whether the media packet with the given SSRC and sequence number is known, either because
+    /// it was received or recovered.
+    pub fn has_media(&self, ssrc: u32, seqnum: u16) -> bool {
+        self.media.contains_key(&(ssrc, seqnum))
+    }
+
+    /// Stores a received media packet and returns all packets that could be recovered with it.
+    pub fn push_media(&mut self, packet: &[u8]) -> Vec<Vec<u8>> {
+        if packet.len() < RTP_HEADER_LEN {
+            return vec![];
+        }
+
+        if !self.store(packet.to_vec()) {
+            return vec![];
+        }
+
+        self.recover()
+    }
+
+    /// Stores a received repair packet and returns all packets that could be recovered with it.
+    pub fn push_repair(&mut self, repair: RepairPacket) -> Vec<Vec<u8>> {
+        self.stats.repair_packets += 1;
+
+        if repair.seqnums.is_empty() {
+            return vec![];
+        }
+
+        self.pending.push_back(repair);
+        while self.pending.len() > MAX_PENDING_REPAIR_PACKETS {
+            let repair = self.pending.pop_front().unwrap();
+            self.expire(&repair);
+        }
+
+        self.recover()
+    }
+
+    /// Drops all stored packets, e.g. after a flush.
+    pub fn reset(&mut self) {
+        let stats = self.stats;
+        *self = Decoder {
+            stats,
+            ..Default::default()
+        };
+    }
+
+    fn store(&mut self, packet: Vec<u8>) -> bool {
+        let key = (packet_ssrc(&packet), packet_seqnum(&packet));
+        if self.media.contains_key(&key) {
+            return false;
+        }
+
+        self.media.insert(key, packet);
+        self.media_order.push_back(key);
+        while self.media_order.len() > MAX_STORED_MEDIA_PACKETS {
+            let key = self.media_order.pop_front().unwrap();
+            self.media.remove(&key);
+        }
+
+        true
+    }
+
+    /// Counts the packets that are still missing for a repair packet that is dropped.
+    fn expire(&mut self, repair: &RepairPacket) {
+        for &seqnum in &repair.seqnums {
+            let key = (repair.ssrc, seqnum);
+            if self.media.contains_key(&key) || self.unrecovered.contains(&key) {
+                continue;
+            }
+
+            self.stats.unrecovered += 1;
+            self.unrecovered.push_back(key);
+            if self.unrecovered.len() > MAX_PENDING_REPAIR_PACKETS {
+                self.unrecovered.pop_front();
+            }
+        }
+    }
+
+    /// Tries to recover missing packets with the pending repair packets until no further packet
+    /// can be recovered. Recovering a packet can make another repair packet usable, e.g. with
+    /// 2-D parity.
+    fn recover(&mut self) -> Vec<Vec<u8>> {
+        let mut recovered = vec![];
+
+        loop {
+            let mut progress = false;
+
+            let mut idx = 0;
+            while idx < self.pending.len() {
+                let repair = &self.pending[idx];
+                let missing = repair
+                    .seqnums
+                    .iter()
+                    .copied()
+                    .filter(|&seqnum| !self.media.contains_key(&(repair.ssrc, seqnum)))
+                    .take(2)
+                    .collect::<SmallVec<[u16; 2]>>();
+
+                let missing_seqnum = match missing.as_slice() {
+                    // Nothing left to recover with this one
+                    [] => {
+                        self.pending.remove(idx);
+                        continue;
+                    }
+                    [missing_seqnum] => *missing_seqnum,
+                    _ => {
+                        idx += 1;
+                        continue;
+                    }
+                };
+
+                let repair = self.pending.remove(idx).unwrap();
+                let mut parity = repair.parity.clone();
+                for &seqnum in &repair.seqnums {
+                    if seqnum != missing_seqnum {
+                        parity.add(&self.media[&(repair.ssrc, seqnum)]);
+                    }
+                }
+
+                // The repair packet might only protect a prefix of the packets
+                let protected_len = repair.parity.payload.len();
+                match parity
+                    .recover(missing_seqnum, repair.ssrc)
+                    .filter(|packet| packet.len() - RTP_HEADER_LEN <= protected_len)
+                {
+                    Some(packet) => {
+                        self.stats.recovered += 1;
+                        self.store(packet.clone());
+                        recovered.push(packet);
+                        progress = true;
+                    }
+                    None => self.expire(&repair),
+                }
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        recovered
+    }
+}
+
+/// Bit mask of protected packets relative to a base sequence number, most significant bit first.
+pub fn seqnums_from_mask(base: u16, mask: u64, mask_bits: u32) -> impl Iterator<Item = u16> {
+    (0..mask_bits)
+        .filter(move |i| mask & (1 << (mask_bits - 1 - i)) != 0)
+        .map(move |i| base.wrapping_add(i as u16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(seqnum: u16, ts: u32, marker: bool, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x80, if marker { 0x80 | 96 } else { 96 }];
+        packet.extend_from_slice(&seqnum.to_be_bytes());
+        packet.extend_from_slice(&ts.to_be_bytes());
+        packet.extend_from_slice(&0x12345678u32.to_be_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn repair(packets: &[Vec<u8>]) -> RepairPacket {
+        let mut parity = Parity::default();
+        for packet in packets {
+            parity.add(packet);
+        }
+
+        RepairPacket {
+            ssrc: 0x12345678,
+            seqnums: packets.iter().map(|p| packet_seqnum(p)).collect(),
+            parity,
+        }
+    }
+
+    #[test]
+    fn recover_single() {
+        let packets = [
+            packet(10, 100, false, &[1, 2, 3]),
+            packet(11, 100, true, &[4, 5, 6, 7, 8]),
+            packet(12, 200, false, &[9]),
+        ];
+
+        for missing in 0..packets.len() {
+            let mut decoder = Decoder::default();
+            for (i, packet) in packets.iter().enumerate() {
+                if i != missing {
+                    assert!(decoder.push_media(packet).is_empty());
+                }
+            }
+            let recovered = decoder.push_repair(repair(&packets));
+            assert_eq!(recovered, vec![packets[missing].clone()]);
+            assert_eq!(decoder.stats().recovered, 1);
+        }
+    }
+
+    #[test]
+    fn recover_after_repair() {
+        let packets = [
+            packet(10, 100, false, &[1, 2, 3]),
+            packet(11, 100, true, &[4, 5, 6, 7, 8]),
+        ];
+
+        let mut decoder = Decoder::default();
+        assert!(decoder.push_repair(repair(&packets)).is_empty());
+        assert_eq!(decoder.push_media(&packets[1]), vec![packets[0].clone()]);
+    }
+
+    #[test]
+    fn recover_two_dimensional() {
+        // 2x2 matrix with two rows and two columns, the first row is lost completely
+        let packets = [
+            packet(0, 0, false, &[1]),
+            packet(1, 0, false, &[2, 3]),
+            packet(2, 0, false, &[4, 5, 6]),
+            packet(3, 0, true, &[7]),
+        ];
+
+        let mut decoder = Decoder::default();
+        assert!(decoder.push_media(&packets[2]).is_empty());
+        assert!(decoder.push_media(&packets[3]).is_empty());
+        assert!(decoder
+            .push_repair(repair(&[packets[0].clone(), packets[1].clone()]))
+            .is_empty());
+        assert!(decoder
+            .push_repair(repair(&[packets[2].clone(), packets[3].clone()]))
+            .is_empty());
+        // The column recovers the first packet, which makes the first row usable
+        let recovered = decoder.push_repair(repair(&[packets[0].clone(), packets[2].clone()]));
+        assert_eq!(recovered, vec![packets[0].clone(), packets[1].clone()]);
+        assert!(decoder
+            .push_repair(repair(&[packets[1].clone(), packets[3].clone()]))
+            .is_empty());
+        assert_eq!(decoder.stats().recovered, 2);
+    }
+
+    #[test]
+    fn unrecoverable() {
+        let packets = [
+            packet(10, 100, false, &[1, 2, 3]),
+            packet(11, 100, true, &[4, 5, 6, 7, 8]),
+            packet(12, 200, false, &[9]),
+        ];
+
+        let mut decoder = Decoder::default();
+        decoder.push_media(&packets[0]);
+        assert!(decoder.push_repair(repair(&packets)).is_empty());
+        // Fill up the pending repair packets with unrecoverable ones until the first is dropped
+        for i in 0..MAX_PENDING_REPAIR_PACKETS as u16 {
+            let seqnum = 1000 + 2 * i;
+            decoder.push_repair(repair(&[
+                packet(seqnum, 0, false, &[]),
+                packet(seqnum + 1, 0, false, &[]),
+            ]));
+        }
+        assert_eq!(decoder.stats().unrecovered, 2);
+    }
+
+    #[test]
+    fn mask() {
+        assert_eq!(
+            seqnums_from_mask(65534, 0b1011 << 12, 16).collect::<Vec<_>>(),
+            vec![65534, 0, 1]
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpflexfecdec
+ * @see_also: rtpflexfecenc, rtprecv
+ *
+ * Recover lost packets from flexible forward error correction packets as per
+ * [RFC 8627][rfc-8627].
+ *
+ * Repair packets with the configured payload type are consumed and media packets are passed
+ * through. Recovered packets are pushed as soon as they can be reconstructed, which usually
+ * happens after later packets were already forwarded. The element is therefore placed before
+ * `rtprecv`, whose jitterbuffer puts the recovered packets back into order.
+ *
+ * Both the fixed L/D header for row and column parity (1-D and 2-D) and the flexible bit mask are
+ * supported, as long as a repair packet only protects a single SSRC. Retransmission repair
+ * packets are not supported.
+ *
+ * [rfc-8627]: https://www.rfc-editor.org/rfc/rfc8627.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc port=5004 caps='application/x-rtp,media=video,payload=96,clock-rate=90000,encoding-name=VP8' ! rtpflexfecdec pt=124 ! rtprecv name=rtprecv rtp-id=0 latency=200 \
+ *     rtprecv. ! rtpvp8depay2 ! vp8dec ! videoconvert ! autovideosink
+ * ]| This will receive a VP8 stream with FlexFEC protection.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, prelude::*, subclass::prelude::*};
+use rtp_types::RtpPacket;
+use std::sync::{LazyLock, Mutex};
+
+use crate::fec::{packet_seqnum, Decoder};
+use crate::flexfec::parse_payload;
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpflexfecdec",
+        gst::DebugColorFlags::empty(),
+        Some("RTP FlexFEC Decoder"),
+    )
+});
+
+const DEFAULT_PT: u32 = 255;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    pt: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { pt: DEFAULT_PT }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    decoder: Decoder,
+}
+
+pub struct RtpFlexFecDec {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl RtpFlexFecDec {
+    fn sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = *self.settings.lock().unwrap();
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, imp = self, "Failed to map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        let rtp = match RtpPacket::parse(&map) {
+            Ok(rtp) => rtp,
+            Err(err) => {
+                // Might be RTCP in case of rtcp-mux, which is passed through
+                gst::trace!(CAT, imp = self, "Passing through non-RTP packet: {err:?}");
+                drop(map);
+                return self.srcpad.push(buffer);
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+
+        let (recovered, forward) = if settings.pt <= 127 && rtp.payload_type() == settings.pt as u8
+        {
+            let csrcs = rtp.csrc().collect::<Vec<_>>();
+            match parse_payload(rtp.payload(), &csrcs) {
+                Some(repair) => {
+                    gst::trace!(
+                        CAT,
+                        imp = self,
+                        "Received repair packet {} protecting {:?}",
+                        rtp.sequence_number(),
+                        repair.seqnums
+                    );
+                    (state.decoder.push_repair(repair), false)
+                }
+                None => {
+                    gst::debug!(
+                        CAT,
+                        imp = self,
+                        "Dropping unsupported repair packet {}",
+                        rtp.sequence_number()
+                    );
+                    (vec![], false)
+                }
+            }
+        } else if state.decoder.has_media(rtp.ssrc(), rtp.sequence_number()) {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Dropping already recovered packet {}",
+                rtp.sequence_number()
+            );
+            (vec![], false)
+        } else {
+            (state.decoder.push_media(&map), true)
+        };
+        drop(state);
+        drop(map);
+
+        let mut buffers = Vec::with_capacity(recovered.len());
+        for packet in recovered {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Recovered packet {}",
+                packet_seqnum(&packet)
+            );
+
+            let mut recovered_buffer = gst::Buffer::from_mut_slice(packet);
+            buffer
+                .copy_into(
+                    recovered_buffer.get_mut().unwrap(),
+                    gst::BufferCopyFlags::METADATA,
+                    ..,
+                )
+                .map_err(|err| {
+                    gst::error!(CAT, imp = self, "Failed to copy buffer metadata: {err:?}");
+                    gst::FlowError::Error
+                })?;
+            buffers.push(recovered_buffer);
+        }
+
+        if forward {
+            self.srcpad.push(buffer)?;
+        }
+
+        for buffer in buffers {
+            self.srcpad.push(buffer)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        if let gst::EventView::FlushStop(_) = event.view() {
+            self.state.lock().unwrap().decoder.reset();
+        }
+
+        gst::Pad::event_default(pad, Some(&*self.obj()), event)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpFlexFecDec {
+    const NAME: &'static str = "GstRtpFlexFecDec";
+    type Type = super::RtpFlexFecDec;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_from_template(&templ)
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.sink_event(pad, event))
+            })
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_from_template(&templ)
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            settings: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpFlexFecDec {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("pt")
+                    .nick("Payload Type")
+                    .blurb("The payload type of repair packets (255 = disabled)")
+                    .maximum(255)
+                    .default_value(DEFAULT_PT)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Various statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "pt" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.pt = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "pt" => {
+                let settings = self.settings.lock().unwrap();
+                settings.pt.to_value()
+            }
+            "stats" => {
+                let state = self.state.lock().unwrap();
+                let stats = state.decoder.stats();
+                gst::Structure::builder("application/x-rtp-flexfecdec-stats")
+                    .field("fec-packets", stats.repair_packets)
+                    .field("recovered-packets", stats.recovered)
+                    .field("unrecovered-packets", stats.unrecovered)
+                    .build()
+                    .to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpFlexFecDec {}
+
+impl ElementImpl for RtpFlexFecDec {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP FlexFEC Decoder",
+                "Codec/Decoder/Network/RTP",
+                "Recovers lost packets from FlexFEC repair packets (RFC 8627)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let caps = gst::Caps::new_empty_simple("application/x-rtp");
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        let ret = self.parent_change_state(transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            *self.state.lock().unwrap() = State::default();
+        }
+
+        Ok(ret)
+    }
+}
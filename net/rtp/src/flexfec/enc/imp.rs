@@ -0,0 +1,475 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpflexfecenc
+ * @see_also: rtpflexfecdec, rtpsend
+ *
+ * Generate flexible forward error correction packets as per [RFC 8627][rfc-8627].
+ *
+ * The media packets are arranged in a matrix of #rtpflexfecenc:columns columns and
+ * #rtpflexfecenc:rows rows, as in SMPTE 2022-1. Row repair packets protect the consecutive
+ * packets of each row and column repair packets protect the packets of each column once the
+ * matrix is complete. Enabling only one of them results in 1-D parity, enabling both in 2-D
+ * parity. Column repair packets require at least two rows.
+ *
+ * Repair packets are inserted into the stream after the media packets with the configured payload
+ * type on a separate SSRC. When passing the stream to `rtpsend`, the payload type has to be added
+ * to the `pt-map` of the session.
+ *
+ * [rfc-8627]: https://www.rfc-editor.org/rfc/rfc8627.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 videotestsrc ! vp8enc ! rtpvp8pay2 ! rtpflexfecenc pt=124 columns=5 rows=5 enable-column-fec=true ! udpsink host=127.0.0.1 port=5004
+ * ]| This will send a VP8 stream with 2-D FlexFEC protection over a 5x5 matrix to localhost
+ * port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, prelude::*, subclass::prelude::*};
+use rtp_types::{RtpPacket, RtpPacketBuilder};
+use std::sync::{LazyLock, Mutex};
+
+use crate::fec::Parity;
+use crate::flexfec::write_fixed_payload;
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpflexfecenc",
+        gst::DebugColorFlags::empty(),
+        Some("RTP FlexFEC Encoder"),
+    )
+});
+
+const DEFAULT_PT: u32 = 255;
+const DEFAULT_SSRC: u32 = 0;
+const DEFAULT_COLUMNS: u32 = 10;
+const DEFAULT_ROWS: u32 = 10;
+const DEFAULT_ENABLE_ROW_FEC: bool = true;
+const DEFAULT_ENABLE_COLUMN_FEC: bool = false;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    pt: u32,
+    ssrc: u32,
+    columns: u32,
+    rows: u32,
+    enable_row_fec: bool,
+    enable_column_fec: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            pt: DEFAULT_PT,
+            ssrc: DEFAULT_SSRC,
+            columns: DEFAULT_COLUMNS,
+            rows: DEFAULT_ROWS,
+            enable_row_fec: DEFAULT_ENABLE_ROW_FEC,
+            enable_column_fec: DEFAULT_ENABLE_COLUMN_FEC,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// SSRC of the repair packets.
+    fec_ssrc: u32,
+    /// Next repair packet sequence number.
+    fec_seqnum: u16,
+    /// SSRC of the protected media packets.
+    media_ssrc: Option<u32>,
+    /// Next expected media sequence number.
+    next_seqnum: Option<u16>,
+    /// Sequence number of the first packet of the matrix.
+    base: u16,
+    /// Position of the next packet in the matrix.
+    position: usize,
+    row: Parity,
+    columns: Vec<Parity>,
+    protected_packets: u64,
+    row_fec_packets: u64,
+    column_fec_packets: u64,
+}
+
+impl State {
+    fn reset_matrix(&mut self) {
+        self.position = 0;
+        self.row = Parity::default();
+        self.columns.clear();
+    }
+}
+
+/// A repair packet that is ready to be sent.
+struct Repair {
+    base: u16,
+    columns: u8,
+    rows: u8,
+    parity: Parity,
+}
+
+pub struct RtpFlexFecEnc {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl RtpFlexFecEnc {
+    fn sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = *self.settings.lock().unwrap();
+        if settings.pt > 127 || !(settings.enable_row_fec || settings.enable_column_fec) {
+            return self.srcpad.push(buffer);
+        }
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, imp = self, "Failed to map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        let rtp = match RtpPacket::parse(&map) {
+            Ok(rtp) => rtp,
+            Err(err) => {
+                gst::warning!(CAT, imp = self, "Dropping invalid RTP packet: {err:?}");
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        let columns = settings.columns as usize;
+        let rows = settings.rows as usize;
+        let seqnum = rtp.sequence_number();
+
+        let mut state = self.state.lock().unwrap();
+
+        // The matrix only covers consecutive packets of a single SSRC
+        if state.media_ssrc != Some(rtp.ssrc()) || state.next_seqnum != Some(seqnum) {
+            if state.position > 0 {
+                gst::debug!(CAT, imp = self, "Discontinuity, dropping current matrix");
+            }
+            state.reset_matrix();
+            state.media_ssrc = Some(rtp.ssrc());
+        }
+        state.next_seqnum = Some(seqnum.wrapping_add(1));
+
+        if state.position == 0 {
+            state.base = seqnum;
+            if settings.enable_column_fec {
+                state.columns = vec![Parity::default(); columns];
+            }
+        }
+
+        let column = state.position % columns;
+        state.protected_packets += 1;
+
+        let mut repairs = vec![];
+        if settings.enable_row_fec {
+            state.row.add(&map);
+            if column == columns - 1 {
+                repairs.push(Repair {
+                    base: seqnum.wrapping_sub(columns as u16 - 1),
+                    columns: columns as u8,
+                    rows: 0,
+                    parity: std::mem::take(&mut state.row),
+                });
+                state.row_fec_packets += 1;
+            }
+        }
+
+        if settings.enable_column_fec {
+            state.columns[column].add(&map);
+        }
+
+        state.position += 1;
+        if state.position >= columns * rows {
+            if settings.enable_column_fec && rows > 1 {
+                let base = state.base;
+                for (i, parity) in std::mem::take(&mut state.columns).into_iter().enumerate() {
+                    repairs.push(Repair {
+                        base: base.wrapping_add(i as u16),
+                        columns: columns as u8,
+                        rows: rows as u8,
+                        parity,
+                    });
+                    state.column_fec_packets += 1;
+                }
+            }
+            state.reset_matrix();
+        }
+
+        let mut packets = vec![];
+        for repair in repairs {
+            let payload =
+                write_fixed_payload(&repair.parity, repair.base, repair.columns, repair.rows);
+            let fec_seqnum = state.fec_seqnum;
+            state.fec_seqnum = fec_seqnum.wrapping_add(1);
+
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Generated repair packet {fec_seqnum} with base {} L {} D {}",
+                repair.base,
+                repair.columns,
+                repair.rows,
+            );
+
+            let packet = RtpPacketBuilder::<&[u8], &[u8]>::new()
+                .payload_type(settings.pt as u8)
+                .ssrc(state.fec_ssrc)
+                .sequence_number(fec_seqnum)
+                .timestamp(rtp.timestamp())
+                .add_csrc(rtp.ssrc())
+                .payload(payload.as_slice())
+                .write_vec()
+                .map_err(|err| {
+                    gst::error!(CAT, imp = self, "Failed to write repair packet: {err:?}");
+                    gst::FlowError::Error
+                })?;
+            packets.push(packet);
+        }
+        drop(state);
+        drop(map);
+
+        let (pts, dts) = (buffer.pts(), buffer.dts());
+        self.srcpad.push(buffer)?;
+
+        for packet in packets {
+            let mut buffer = gst::Buffer::from_mut_slice(packet);
+            {
+                let buffer = buffer.get_mut().unwrap();
+                buffer.set_pts(pts);
+                buffer.set_dts(dts);
+            }
+            self.srcpad.push(buffer)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        if let gst::EventView::FlushStop(_) = event.view() {
+            let mut state = self.state.lock().unwrap();
+            state.reset_matrix();
+            state.next_seqnum = None;
+        }
+
+        gst::Pad::event_default(pad, Some(&*self.obj()), event)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpFlexFecEnc {
+    const NAME: &'static str = "GstRtpFlexFecEnc";
+    type Type = super::RtpFlexFecEnc;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_from_template(&templ)
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.sink_event(pad, event))
+            })
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_from_template(&templ)
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            settings: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpFlexFecEnc {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("pt")
+                    .nick("Payload Type")
+                    .blurb("The payload type of repair packets (255 = disabled)")
+                    .maximum(255)
+                    .default_value(DEFAULT_PT)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("ssrc")
+                    .nick("SSRC")
+                    .blurb("The SSRC of repair packets (0 = random)")
+                    .default_value(DEFAULT_SSRC)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("columns")
+                    .nick("Columns")
+                    .blurb("Number of columns of the matrix (L)")
+                    .minimum(1)
+                    .maximum(255)
+                    .default_value(DEFAULT_COLUMNS)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("rows")
+                    .nick("Rows")
+                    .blurb("Number of rows of the matrix (D)")
+                    .minimum(1)
+                    .maximum(255)
+                    .default_value(DEFAULT_ROWS)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("enable-row-fec")
+                    .nick("Enable Row FEC")
+                    .blurb("Generate repair packets for each row of the matrix")
+                    .default_value(DEFAULT_ENABLE_ROW_FEC)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("enable-column-fec")
+                    .nick("Enable Column FEC")
+                    .blurb("Generate repair packets for each column of the matrix")
+                    .default_value(DEFAULT_ENABLE_COLUMN_FEC)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Various statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "pt" => settings.pt = value.get().expect("type checked upstream"),
+            "ssrc" => settings.ssrc = value.get().expect("type checked upstream"),
+            "columns" => settings.columns = value.get().expect("type checked upstream"),
+            "rows" => settings.rows = value.get().expect("type checked upstream"),
+            "enable-row-fec" => {
+                settings.enable_row_fec = value.get().expect("type checked upstream")
+            }
+            "enable-column-fec" => {
+                settings.enable_column_fec = value.get().expect("type checked upstream")
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        if pspec.name() == "stats" {
+            let state = self.state.lock().unwrap();
+            return gst::Structure::builder("application/x-rtp-flexfecenc-stats")
+                .field("protected-packets", state.protected_packets)
+                .field("row-fec-packets", state.row_fec_packets)
+                .field("column-fec-packets", state.column_fec_packets)
+                .build()
+                .to_value();
+        }
+
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "pt" => settings.pt.to_value(),
+            "ssrc" => settings.ssrc.to_value(),
+            "columns" => settings.columns.to_value(),
+            "rows" => settings.rows.to_value(),
+            "enable-row-fec" => settings.enable_row_fec.to_value(),
+            "enable-column-fec" => settings.enable_column_fec.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpFlexFecEnc {}
+
+impl ElementImpl for RtpFlexFecEnc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP FlexFEC Encoder",
+                "Codec/Encoder/Network/RTP",
+                "Generates FlexFEC repair packets (RFC 8627)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let caps = gst::Caps::new_empty_simple("application/x-rtp");
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        if transition == gst::StateChange::ReadyToPaused {
+            let settings = *self.settings.lock().unwrap();
+            *self.state.lock().unwrap() = State {
+                fec_ssrc: if settings.ssrc == 0 {
+                    rand::random()
+                } else {
+                    settings.ssrc
+                },
+                fec_seqnum: rand::random(),
+                ..Default::default()
+            };
+        }
+
+        self.parent_change_state(transition)
+    }
+}
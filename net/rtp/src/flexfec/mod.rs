@@ -0,0 +1,127 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Flexible forward error correction (FlexFEC) as specified in RFC 8627.
+//!
+//! Repair packets are sent on a separate SSRC with their own sequence numbers and carry the SSRC
+//! of the protected stream in their CSRC list. Row and column parity over a matrix of L columns
+//! and D rows of consecutive packets, as in SMPTE 2022-1, is signalled with the fixed L/D
+//! header. Arbitrary sets of packets are signalled with the flexible bit mask.
+
+use crate::fec::{Parity, RepairPacket};
+
+pub mod dec;
+pub mod enc;
+
+#[cfg(test)]
+mod tests;
+
+/// Length of the FlexFEC header with fixed L/D fields.
+const FIXED_HEADER_LEN: usize = 12;
+
+/// Creates the payload of a repair packet with a fixed L/D header.
+///
+/// With `rows` smaller than 2 the packet protects `columns` consecutive packets starting at
+/// `base`, otherwise it protects `rows` packets spaced `columns` apart.
+pub(crate) fn write_fixed_payload(parity: &Parity, base: u16, columns: u8, rows: u8) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(FIXED_HEADER_LEN + parity.payload.len());
+    // R bit is zero, F bit is set
+    payload.push(0x40 | (parity.first_byte & 0x3f));
+    payload.push(parity.second_byte);
+    payload.extend_from_slice(&parity.length.to_be_bytes());
+    payload.extend_from_slice(&parity.timestamp.to_be_bytes());
+    payload.extend_from_slice(&base.to_be_bytes());
+    payload.push(columns);
+    payload.push(rows);
+    payload.extend_from_slice(&parity.payload);
+
+    payload
+}
+
+/// Sequence numbers of the packets protected by a fixed L/D header.
+pub(crate) fn fixed_seqnums(base: u16, columns: u8, rows: u8) -> Vec<u16> {
+    if rows < 2 {
+        (0..columns as u16).map(|i| base.wrapping_add(i)).collect()
+    } else {
+        (0..rows as u16)
+            .map(|i| base.wrapping_add(i * columns as u16))
+            .collect()
+    }
+}
+
+/// Parses the payload of a repair packet.
+///
+/// `protected_ssrcs` is the CSRC list of the repair packet. Only repair packets protecting a single
+/// SSRC are supported. Returns `None` for invalid or unsupported packets, including
+/// retransmissions.
+pub(crate) fn parse_payload(payload: &[u8], protected_ssrcs: &[u32]) -> Option<RepairPacket> {
+    let &[ssrc] = protected_ssrcs else {
+        return None;
+    };
+
+    if payload.len() < FIXED_HEADER_LEN || payload[0] & 0x80 != 0 {
+        return None;
+    }
+
+    let length = u16::from_be_bytes([payload[2], payload[3]]);
+    let timestamp = u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]);
+    let base = u16::from_be_bytes([payload[8], payload[9]]);
+
+    let (seqnums, header_len) = if payload[0] & 0x40 != 0 {
+        let (columns, rows) = (payload[10], payload[11]);
+        if columns == 0 {
+            return None;
+        }
+        (fixed_seqnums(base, columns, rows), FIXED_HEADER_LEN)
+    } else {
+        // Up to three mask fields of 15, 31 and 63 bits, each with the k bit set on the last one
+        let mut seqnums = vec![];
+        let mut pos = 10;
+        let mut offset = 0u16;
+        for field_len in [2, 4, 8] {
+            let field = payload.get(pos..pos + field_len)?;
+            let mut bytes = [0u8; 8];
+            bytes[8 - field_len..].copy_from_slice(field);
+            let value = u64::from_be_bytes(bytes);
+            let bits = field_len as u32 * 8 - 1;
+
+            seqnums.extend(
+                (0..bits)
+                    .filter(|i| value & (1 << (bits - 1 - i)) != 0)
+                    .map(|i| base.wrapping_add(offset + i as u16)),
+            );
+            offset += bits as u16;
+            pos += field_len;
+
+            let last = value & (1 << bits) != 0;
+            if last {
+                break;
+            } else if field_len == 8 {
+                return None;
+            }
+        }
+        (seqnums, pos)
+    };
+
+    if seqnums.is_empty() {
+        return None;
+    }
+
+    Some(RepairPacket {
+        ssrc,
+        seqnums,
+        parity: Parity {
+            first_byte: payload[0] & 0x3f,
+            second_byte: payload[1],
+            timestamp,
+            length,
+            payload: payload[header_len..].to_vec(),
+        },
+    })
+}
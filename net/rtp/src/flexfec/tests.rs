@@ -0,0 +1,177 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::collections::BTreeMap;
+
+use gst_check::Harness;
+
+use crate::fec::Parity;
+use crate::flexfec::{fixed_seqnums, parse_payload, write_fixed_payload};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtpflexfec test");
+    });
+}
+
+const MEDIA_SSRC: u32 = 0x12345678;
+const FEC_SSRC: u32 = 0x87654321;
+
+fn packet(seqnum: u16, timestamp: u32, payload: &[u8]) -> gst::Buffer {
+    let packet = rtp_types::RtpPacketBuilder::new()
+        .payload_type(96)
+        .ssrc(MEDIA_SSRC)
+        .sequence_number(seqnum)
+        .timestamp(timestamp)
+        .payload(payload)
+        .write_vec()
+        .unwrap();
+
+    gst::Buffer::from_mut_slice(packet)
+}
+
+#[test]
+fn test_fixed_payload() {
+    assert_eq!(fixed_seqnums(65534, 4, 0), vec![65534, 65535, 0, 1]);
+    assert_eq!(fixed_seqnums(65534, 4, 3), vec![65534, 2, 6]);
+
+    let parity = Parity {
+        first_byte: 0x80,
+        second_byte: 96,
+        timestamp: 1234,
+        length: 3,
+        payload: vec![1, 2, 3],
+    };
+    let payload = write_fixed_payload(&parity, 100, 5, 0);
+    assert_eq!(&payload[..2], &[0x40, 96]);
+
+    let repair = parse_payload(&payload, &[MEDIA_SSRC]).unwrap();
+    assert_eq!(repair.ssrc, MEDIA_SSRC);
+    assert_eq!(repair.seqnums, vec![100, 101, 102, 103, 104]);
+    assert_eq!(repair.parity.first_byte, 0);
+    assert_eq!(repair.parity.second_byte, 96);
+    assert_eq!(repair.parity.timestamp, 1234);
+    assert_eq!(repair.parity.length, 3);
+    assert_eq!(repair.parity.payload, vec![1, 2, 3]);
+
+    // Multiple protected SSRCs are not supported
+    assert!(parse_payload(&payload, &[MEDIA_SSRC, FEC_SSRC]).is_none());
+    assert!(parse_payload(&payload, &[]).is_none());
+}
+
+#[test]
+fn test_flexible_mask() {
+    let header = [0x00, 96, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64];
+
+    // Single 15 bit mask protecting the first and the last packet
+    let mut payload = header.to_vec();
+    payload.extend_from_slice(&[0xc0, 0x01]);
+    let repair = parse_payload(&payload, &[MEDIA_SSRC]).unwrap();
+    assert_eq!(repair.seqnums, vec![100, 114]);
+
+    // 46 bit mask protecting the first packet of the second field
+    let mut payload = header.to_vec();
+    payload.extend_from_slice(&[0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0xaa]);
+    let repair = parse_payload(&payload, &[MEDIA_SSRC]).unwrap();
+    assert_eq!(repair.seqnums, vec![115]);
+    assert_eq!(repair.parity.payload, vec![0xaa]);
+
+    // Last field without the k bit
+    let mut payload = header.to_vec();
+    payload.extend_from_slice(&[0; 14]);
+    assert!(parse_payload(&payload, &[MEDIA_SSRC]).is_none());
+}
+
+#[test]
+fn test_flexfec_2d_recovery() {
+    init();
+
+    let caps = "application/x-rtp,media=video,payload=96,clock-rate=90000,encoding-name=VP8";
+
+    let enc = gst::ElementFactory::make("rtpflexfecenc")
+        .property("pt", 124u32)
+        .property("ssrc", FEC_SSRC)
+        .property("columns", 3u32)
+        .property("rows", 3u32)
+        .property("enable-column-fec", true)
+        .build()
+        .unwrap();
+    let mut enc = Harness::with_element(&enc, Some("sink"), Some("src"));
+    enc.set_src_caps_str(caps);
+    enc.play();
+
+    let packets = (0..9u16)
+        .map(|i| packet(1000 + i, 3000 * i as u32, &vec![i as u8; 5 + i as usize]))
+        .collect::<Vec<_>>();
+    for packet in &packets {
+        enc.push(packet.clone()).unwrap();
+    }
+
+    // 9 media packets, 3 row and 3 column repair packets
+    let mut sent = vec![];
+    while let Some(buffer) = enc.try_pull() {
+        sent.push(buffer);
+    }
+    assert_eq!(sent.len(), 15);
+
+    let stats = enc.element().unwrap().property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u64>("protected-packets").unwrap(), 9);
+    assert_eq!(stats.get::<u64>("row-fec-packets").unwrap(), 3);
+    assert_eq!(stats.get::<u64>("column-fec-packets").unwrap(), 3);
+
+    let mut dec = Harness::new("rtpflexfecdec");
+    dec.element().unwrap().set_property("pt", 124u32);
+    dec.set_src_caps_str(caps);
+    dec.play();
+
+    // Lose the whole first row and the first row repair packet, the column repair packets recover
+    // the first row
+    let lost = [1000, 1001, 1002];
+    let mut first_row_repair = true;
+    for buffer in &sent {
+        let lose = {
+            let map = buffer.map_readable().unwrap();
+            let rtp = rtp_types::RtpPacket::parse(&map).unwrap();
+            if rtp.ssrc() == MEDIA_SSRC {
+                lost.contains(&rtp.sequence_number())
+            } else {
+                std::mem::take(&mut first_row_repair)
+            }
+        };
+        if !lose {
+            dec.push(buffer.clone()).unwrap();
+        }
+    }
+
+    let mut received = BTreeMap::new();
+    while let Some(buffer) = dec.try_pull() {
+        let map = buffer.map_readable().unwrap();
+        let rtp = rtp_types::RtpPacket::parse(&map).unwrap();
+        assert_eq!(rtp.ssrc(), MEDIA_SSRC);
+        received.insert(rtp.sequence_number(), map.to_vec());
+    }
+
+    let expected = packets
+        .iter()
+        .map(|packet| {
+            let map = packet.map_readable().unwrap();
+            let rtp = rtp_types::RtpPacket::parse(&map).unwrap();
+            (rtp.sequence_number(), map.to_vec())
+        })
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(received, expected);
+
+    let stats = dec.element().unwrap().property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u64>("fec-packets").unwrap(), 5);
+    assert_eq!(stats.get::<u64>("recovered-packets").unwrap(), 3);
+}
@@ -0,0 +1,128 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpg722pay2
+ * @see_also: rtpg722depay2, avenc_g722, avdec_g722
+ *
+ * Payloads G.722 encoded audio into RTP packets as per [RFC 3551][rfc-3551].
+ *
+ * For historical reasons the RTP clock rate of G.722 is 8000 Hz although the audio is sampled
+ * at 16000 Hz. Each byte of G.722 data corresponds to one tick of the RTP clock.
+ *
+ * [rfc-3551]: https://www.rfc-editor.org/rfc/rfc3551.html#section-4.5.2
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 audiotestsrc wave=ticks ! audio/x-raw,rate=16000,channels=1 ! avenc_g722 ! rtpg722pay2 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will generate a G.722 audio test signal and payload it as RTP and send it out
+ * as UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, subclass::prelude::*};
+
+use std::sync::LazyLock;
+
+use crate::{baseaudiopay::RtpBaseAudioPay2Ext, basepay::RtpBasePay2Ext};
+
+/// RTP clock rate of G.722, which is half the actual sample rate.
+pub(crate) const CLOCK_RATE: i32 = 8000;
+
+#[derive(Default)]
+pub struct RtpG722Pay;
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpG722Pay {
+    const NAME: &'static str = "GstRtpG722Pay2";
+    type Type = super::RtpG722Pay;
+    type ParentType = crate::baseaudiopay::RtpBaseAudioPay2;
+}
+
+impl ObjectImpl for RtpG722Pay {}
+
+impl GstObjectImpl for RtpG722Pay {}
+
+impl ElementImpl for RtpG722Pay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP G.722 Payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload G.722 Audio into RTP packets (RFC 3551)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("audio/G722")
+                    .field("channels", 1i32)
+                    .field("rate", 16000i32)
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder_full()
+                    .structure(
+                        gst::Structure::builder("application/x-rtp")
+                            .field("media", "audio")
+                            .field("payload", 9i32)
+                            .field("clock-rate", CLOCK_RATE)
+                            .build(),
+                    )
+                    .structure(
+                        gst::Structure::builder("application/x-rtp")
+                            .field("media", "audio")
+                            .field("encoding-name", "G722")
+                            .field("clock-rate", CLOCK_RATE)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpG722Pay {
+    const DEFAULT_PT: u8 = 9;
+
+    fn set_sink_caps(&self, _caps: &gst::Caps) -> bool {
+        let src_caps = gst::Caps::builder("application/x-rtp")
+            .field("media", "audio")
+            .field("encoding-name", "G722")
+            .field("clock-rate", CLOCK_RATE)
+            .build();
+
+        self.obj().set_src_caps(&src_caps);
+        // Every byte contains two 4 bit samples at 16000 Hz, i.e. one tick of the 8000 Hz clock
+        self.obj().set_bpf(1);
+
+        true
+    }
+}
+
+impl crate::baseaudiopay::RtpBaseAudioPay2Impl for RtpG722Pay {}
@@ -0,0 +1,153 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::tests::{run_test_pipeline, ExpectedBuffer, ExpectedPacket, Source};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtpg722 test");
+    });
+}
+
+fn g722_buffers(num_buffers: usize, jump_after: Option<u64>) -> (gst::Caps, Vec<gst::Buffer>) {
+    let caps = gst::Caps::builder("audio/G722")
+        .field("channels", 1i32)
+        .field("rate", 16000i32)
+        .build();
+
+    let mut buffers = Vec::with_capacity(num_buffers);
+    let mut pos = 0;
+    for _ in 0..num_buffers {
+        // 400 bytes are 800 samples at 16kHz, i.e. 50ms
+        let mut buffer = gst::Buffer::with_size(400).unwrap();
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(gst::ClockTime::from_mseconds(pos / 8));
+        }
+        buffers.push(buffer);
+
+        pos += 400;
+        if Some(pos) == jump_after {
+            pos += 80000;
+        }
+    }
+
+    (caps, buffers)
+}
+
+#[test]
+fn test_g722() {
+    init();
+
+    let (caps, buffers) = g722_buffers(100, None);
+    let pay = "rtpg722pay2";
+    let depay = "rtpg722depay2";
+
+    // The RTP clock rate is 8000 Hz while the audio has 16000 Hz
+    let mut expected_pay = Vec::with_capacity(100);
+    for i in 0..100 {
+        expected_pay.push(vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 50))
+            .size(412)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .pt(9)
+            .rtp_time(((i * 400) & 0xffff_ffff) as u32)
+            .marker_bit(i == 0)
+            .build()]);
+    }
+
+    let mut expected_depay = Vec::with_capacity(100);
+    for i in 0..100 {
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 50))
+            .duration(gst::ClockTime::from_mseconds(50))
+            .size(400)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::RESYNC
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .build()]);
+    }
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_g722_discont() {
+    init();
+
+    // First 5 buffers are normal, then a 10s jump
+    let (caps, buffers) = g722_buffers(10, Some(2000));
+    let pay = "rtpg722pay2 discont-wait=25000000";
+    let depay = "rtpg722depay2";
+
+    let mut expected_pay = Vec::with_capacity(10);
+    let mut pos = 0;
+    for _ in 0..10 {
+        expected_pay.push(vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(pos / 8))
+            .size(412)
+            .flags(if pos == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else if pos == 82000 {
+                gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .pt(9)
+            .rtp_time((pos & 0xffff_ffff) as u32)
+            .marker_bit(pos == 0 || pos == 82000)
+            .build()]);
+
+        pos += 400;
+        if pos == 2000 {
+            pos += 80000;
+        }
+    }
+
+    let mut expected_depay = Vec::with_capacity(10);
+    for packets in &expected_pay {
+        for packet in packets {
+            expected_depay.push(vec![ExpectedBuffer::builder()
+                .pts(packet.pts)
+                .maybe_size(packet.size.map(|size| size - 12))
+                .flags(if packet.pts.is_zero() {
+                    gst::BufferFlags::DISCONT | gst::BufferFlags::RESYNC
+                } else if packet.flags.contains(gst::BufferFlags::MARKER) {
+                    gst::BufferFlags::RESYNC
+                } else {
+                    gst::BufferFlags::empty()
+                })
+                .build()]);
+        }
+    }
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
@@ -0,0 +1,486 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtph264depay2
+ * @see_also: rtph264pay2, h264parse, avdec_h264
+ *
+ * Depayload an H.264 video stream from RTP packets as per [RFC 6184][rfc-6184].
+ *
+ * Single NAL unit, STAP-A and FU-A packets are supported. The output is an H.264 byte-stream
+ * with one access unit per buffer. If the caps contain `sprop-parameter-sets` then these are
+ * inserted before every IDR frame that does not already contain an SPS.
+ *
+ * [rfc-6184]: https://www.rfc-editor.org/rfc/rfc6184.html
+ *
+ * ## Example pipeline
+ *
+ * ```shell
+ * gst-launch-1.0 udpsrc address=127.0.0.1 port=5004 caps='application/x-rtp,media=video,clock-rate=90000,encoding-name=H264' ! rtpjitterbuffer latency=100 ! rtph264depay2 ! decodebin3 ! videoconvertscale ! autovideosink
+ * ```
+ *
+ * This will depayload and decode an incoming RTP H.264 video stream. You can use the
+ * #rtph264pay2 and #x264enc elements to create such an RTP stream.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use std::{mem, sync::Mutex};
+
+use atomic_refcell::AtomicRefCell;
+
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+use std::sync::LazyLock;
+
+use crate::basedepay::{PacketToBufferRelation, RtpBaseDepay2Ext};
+use crate::h264::nal_type;
+use crate::h26x::{parse_sprop, START_CODE};
+
+#[derive(Clone, Default)]
+struct Settings {
+    request_keyframe: bool,
+    wait_for_keyframe: bool,
+}
+
+struct State {
+    /// Parameter sets from the `sprop-parameter-sets` caps field.
+    sprop_parameter_sets: Vec<Vec<u8>>,
+
+    /// Extended RTP timestamp of the current access unit, if any.
+    au_timestamp: Option<u64>,
+    /// First and last extended seqnum of the packets of the current access unit.
+    au_start_ext_seqnum: u64,
+    au_end_ext_seqnum: u64,
+    /// Byte-stream data of the current access unit.
+    au_data: Vec<u8>,
+    au_is_keyframe: bool,
+    au_has_sps: bool,
+
+    /// Offset into `au_data` where the NAL unit that is currently reassembled from FU-A packets
+    /// starts.
+    fu_start: Option<usize>,
+
+    /// Set to `true` until a keyframe was output, initially and after packet loss.
+    waiting_for_keyframe: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            sprop_parameter_sets: Vec::new(),
+            au_timestamp: None,
+            au_start_ext_seqnum: 0,
+            au_end_ext_seqnum: 0,
+            au_data: Vec::new(),
+            au_is_keyframe: false,
+            au_has_sps: false,
+            fu_start: None,
+            waiting_for_keyframe: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RtpH264Depay {
+    state: AtomicRefCell<State>,
+    settings: Mutex<Settings>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtph264depay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP H.264 Depayloader"),
+    )
+});
+
+impl RtpH264Depay {
+    fn reset(&self, state: &mut State) {
+        gst::debug!(CAT, imp = self, "resetting state");
+
+        // The parameter sets from the caps stay valid
+        let sprop_parameter_sets = mem::take(&mut state.sprop_parameter_sets);
+        *state = State::default();
+        state.sprop_parameter_sets = sprop_parameter_sets;
+    }
+
+    /// Appends a complete NAL unit to the current access unit.
+    fn push_nal(&self, state: &mut State, nal: &[u8]) {
+        if nal.is_empty() {
+            return;
+        }
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Received NAL unit of type {} and size {}",
+            nal[0] & 0x1f,
+            nal.len()
+        );
+
+        self.update_au_flags(state, nal[0]);
+        state.au_data.extend_from_slice(&START_CODE);
+        state.au_data.extend_from_slice(nal);
+    }
+
+    fn update_au_flags(&self, state: &mut State, nal_header: u8) {
+        match nal_header & 0x1f {
+            nal_type::IDR => state.au_is_keyframe = true,
+            nal_type::SPS => state.au_has_sps = true,
+            _ => (),
+        }
+    }
+
+    /// Finishes the current access unit, if any, and queues it for output.
+    fn finish_au(
+        &self,
+        settings: &Settings,
+        state: &mut State,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        if state.au_timestamp.take().is_none() {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        if let Some(fu_start) = state.fu_start.take() {
+            gst::warning!(CAT, imp = self, "Dropping incomplete FU-A NAL unit");
+            state.au_data.truncate(fu_start);
+        }
+
+        let seqnums = state.au_start_ext_seqnum..=state.au_end_ext_seqnum;
+        let mut au_data = mem::take(&mut state.au_data);
+        let is_keyframe = mem::take(&mut state.au_is_keyframe);
+        let has_sps = mem::take(&mut state.au_has_sps);
+
+        if au_data.is_empty() {
+            gst::debug!(CAT, imp = self, "Dropping empty access unit");
+            self.obj().drop_packets(seqnums);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        if !is_keyframe && state.waiting_for_keyframe {
+            if settings.request_keyframe {
+                gst::debug!(CAT, imp = self, "Requesting keyframe from upstream");
+                let event = gst_video::UpstreamForceKeyUnitEvent::builder()
+                    .all_headers(true)
+                    .build();
+                let _ = self.obj().sink_pad().push_event(event);
+            }
+
+            if settings.wait_for_keyframe {
+                gst::trace!(CAT, imp = self, "Waiting for keyframe");
+                self.obj().drop_packets(seqnums);
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        }
+
+        if is_keyframe {
+            state.waiting_for_keyframe = false;
+
+            if !has_sps && !state.sprop_parameter_sets.is_empty() {
+                gst::trace!(CAT, imp = self, "Inserting parameter sets from caps");
+
+                let mut data = Vec::with_capacity(
+                    au_data.len()
+                        + state
+                            .sprop_parameter_sets
+                            .iter()
+                            .map(|nal| START_CODE.len() + nal.len())
+                            .sum::<usize>(),
+                );
+                for nal in &state.sprop_parameter_sets {
+                    data.extend_from_slice(&START_CODE);
+                    data.extend_from_slice(nal);
+                }
+                data.extend_from_slice(&au_data);
+                au_data = data;
+            }
+        }
+
+        let mut buffer = gst::Buffer::from_mut_slice(au_data);
+        {
+            let buffer = buffer.get_mut().unwrap();
+
+            if !is_keyframe {
+                buffer.set_flags(gst::BufferFlags::DELTA_UNIT);
+                gst::trace!(CAT, imp = self, "Finishing delta-frame");
+            } else {
+                gst::trace!(CAT, imp = self, "Finishing keyframe");
+            }
+
+            // Set MARKER flag on the output so that the parser knows that this buffer ends a full
+            // access unit.
+            buffer.set_flags(gst::BufferFlags::MARKER);
+        }
+
+        self.obj()
+            .queue_buffer(PacketToBufferRelation::Seqnums(seqnums), buffer)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpH264Depay {
+    const NAME: &'static str = "GstRtpH264Depay2";
+    type Type = super::RtpH264Depay;
+    type ParentType = crate::basedepay::RtpBaseDepay2;
+}
+
+impl ObjectImpl for RtpH264Depay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecBoolean::builder("request-keyframe")
+                    .nick("Request Keyframe")
+                    .blurb("Request new keyframe when packet loss is detected")
+                    .default_value(Settings::default().request_keyframe)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("wait-for-keyframe")
+                    .nick("Wait For Keyframe")
+                    .blurb("Wait for the next keyframe after packet loss")
+                    .default_value(Settings::default().wait_for_keyframe)
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "request-keyframe" => {
+                self.settings.lock().unwrap().request_keyframe = value.get().unwrap();
+            }
+            "wait-for-keyframe" => {
+                self.settings.lock().unwrap().wait_for_keyframe = value.get().unwrap();
+            }
+            _ => unimplemented!(),
+        };
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "request-keyframe" => self.settings.lock().unwrap().request_keyframe.to_value(),
+            "wait-for-keyframe" => self.settings.lock().unwrap().wait_for_keyframe.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpH264Depay {}
+
+impl ElementImpl for RtpH264Depay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP H.264 Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Depayload H.264 from RTP packets",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("clock-rate", 90_000i32)
+                    .field("encoding-name", "H264")
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("video/x-h264")
+                    .field("stream-format", "byte-stream")
+                    .field("alignment", "au")
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpH264Depay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["video"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let s = caps.structure(0).unwrap();
+
+        if let Ok(packetization_mode) = s.get::<&str>("packetization-mode") {
+            if packetization_mode == "2" {
+                gst::error!(CAT, imp = self, "Interleaved mode is not supported");
+                return false;
+            }
+        }
+
+        let sprop_parameter_sets = s
+            .get::<&str>("sprop-parameter-sets")
+            .map(parse_sprop)
+            .unwrap_or_default();
+        gst::debug!(
+            CAT,
+            imp = self,
+            "Got {} parameter sets from caps",
+            sprop_parameter_sets.len()
+        );
+        self.state.borrow_mut().sprop_parameter_sets = sprop_parameter_sets;
+
+        self.obj()
+            .set_src_caps(&self.obj().src_pad().pad_template_caps());
+
+        true
+    }
+
+    fn drain(&self) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let mut state = self.state.borrow_mut();
+
+        self.finish_au(&settings, &mut state)
+    }
+
+    fn flush(&self) {
+        let mut state = self.state.borrow_mut();
+        self.reset(&mut state);
+    }
+
+    fn handle_packet(
+        &self,
+        packet: &crate::basedepay::Packet,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+
+        gst::trace!(CAT, imp = self, "Handling RTP packet {packet:?}");
+        let mut state = self.state.borrow_mut();
+
+        if packet.discont() {
+            // Anything pending was already drained by the base class
+            gst::debug!(CAT, imp = self, "Discontinuity, waiting for next keyframe");
+            state.waiting_for_keyframe = true;
+        }
+
+        // A new timestamp starts a new access unit even if the marker bit of the previous one was
+        // lost.
+        if state
+            .au_timestamp
+            .is_some_and(|ts| ts != packet.ext_timestamp())
+        {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Timestamp changed without marker bit, finishing access unit"
+            );
+            self.finish_au(&settings, &mut state)?;
+        }
+
+        if state.au_timestamp.is_none() {
+            state.au_timestamp = Some(packet.ext_timestamp());
+            state.au_start_ext_seqnum = packet.ext_seqnum();
+        }
+        state.au_end_ext_seqnum = packet.ext_seqnum();
+
+        let payload = packet.payload();
+        match payload.first().map(|b| b & 0x1f) {
+            None => {
+                gst::warning!(CAT, imp = self, "Empty RTP packet");
+            }
+            Some(1..=23) => {
+                self.push_nal(&mut state, payload);
+            }
+            Some(nal_type::STAP_A) => {
+                let mut data = &payload[1..];
+                while let [a, b, rest @ ..] = data {
+                    let size = u16::from_be_bytes([*a, *b]) as usize;
+                    if rest.len() < size {
+                        gst::warning!(CAT, imp = self, "Truncated STAP-A packet");
+                        break;
+                    }
+
+                    self.push_nal(&mut state, &rest[..size]);
+                    data = &rest[size..];
+                }
+            }
+            Some(nal_type::FU_A) => {
+                if let [fu_indicator, fu_header, fragment @ ..] = payload {
+                    let (fu_indicator, fu_header) = (*fu_indicator, *fu_header);
+                    let start = fu_header & 0x80 != 0;
+                    let end = fu_header & 0x40 != 0;
+
+                    if start {
+                        if let Some(fu_start) = state.fu_start.take() {
+                            gst::warning!(CAT, imp = self, "Dropping incomplete FU-A NAL unit");
+                            state.au_data.truncate(fu_start);
+                        }
+
+                        let nal_header = (fu_indicator & 0xe0) | (fu_header & 0x1f);
+                        self.update_au_flags(&mut state, nal_header);
+
+                        state.fu_start = Some(state.au_data.len());
+                        state.au_data.extend_from_slice(&START_CODE);
+                        state.au_data.push(nal_header);
+                        state.au_data.extend_from_slice(fragment);
+                    } else if state.fu_start.is_some() {
+                        state.au_data.extend_from_slice(fragment);
+                    } else {
+                        gst::debug!(CAT, imp = self, "Missing start of FU-A NAL unit");
+                    }
+
+                    if end && state.fu_start.take().is_some() {
+                        gst::trace!(CAT, imp = self, "Finished FU-A NAL unit");
+                    }
+                } else {
+                    gst::warning!(CAT, imp = self, "Truncated FU-A packet");
+                }
+            }
+            Some(nal_type::STAP_B | nal_type::MTAP16 | nal_type::MTAP24 | nal_type::FU_B) => {
+                gst::warning!(
+                    CAT,
+                    imp = self,
+                    "Interleaved mode packets are not supported"
+                );
+            }
+            Some(nal_unit_type) => {
+                gst::warning!(CAT, imp = self, "Unsupported NAL unit type {nal_unit_type}");
+            }
+        }
+
+        // The marker bit is set for the last packet of an access unit.
+        if packet.marker_bit() {
+            self.finish_au(&settings, &mut state)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
@@ -0,0 +1,40 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+pub mod depay;
+pub mod pay;
+
+#[cfg(test)]
+mod tests;
+
+/// NAL unit types that are relevant for payloading and depayloading.
+pub(crate) mod nal_type {
+    pub const IDR: u8 = 5;
+    pub const SPS: u8 = 7;
+    pub const PPS: u8 = 8;
+    pub const AUD: u8 = 9;
+    pub const STAP_A: u8 = 24;
+    pub const STAP_B: u8 = 25;
+    pub const MTAP16: u8 = 26;
+    pub const MTAP24: u8 = 27;
+    pub const FU_A: u8 = 28;
+    pub const FU_B: u8 = 29;
+}
+
+/// Returns the id of an SPS or PPS NAL unit.
+pub(crate) fn parameter_set_id(nal: &[u8]) -> Option<u32> {
+    let rbsp = crate::h26x::remove_emulation_prevention(nal.get(1..)?);
+
+    match nal[0] & 0x1f {
+        // seq_parameter_set_id follows profile_idc, the constraint flags and level_idc
+        nal_type::SPS => crate::h26x::read_ue(&rbsp, 24),
+        nal_type::PPS => crate::h26x::read_ue(&rbsp, 0),
+        _ => None,
+    }
+}
@@ -0,0 +1,593 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtph264pay2
+ * @see_also: rtph264depay2, x264enc, h264parse
+ *
+ * Payload an H.264 video stream into RTP packets as per [RFC 6184][rfc-6184].
+ *
+ * Only non-interleaved mode (`packetization-mode=1`) is supported. NAL units of an access unit
+ * are sent in Single NAL unit packets, aggregated into STAP-A packets or fragmented into FU-A
+ * packets, and the marker bit is set on the last packet of each access unit.
+ *
+ * All SPS and PPS are signalled in the `sprop-parameter-sets` caps field and can additionally be
+ * inserted in-band before IDR frames with the #rtph264pay2:config-interval property.
+ *
+ * [rfc-6184]: https://www.rfc-editor.org/rfc/rfc6184.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 videotestsrc ! video/x-raw,width=1280,height=720,format=I420 ! timeoverlay font-desc=Sans,22 ! x264enc tune=zerolatency ! h264parse ! rtph264pay2 config-interval=-1 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will create and payload an H.264 video stream with a test pattern and
+ * send it out via UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+use smallvec::SmallVec;
+use std::{cmp, sync::Mutex};
+
+use std::sync::LazyLock;
+
+use crate::{
+    basepay::RtpBasePay2Ext,
+    h264::{nal_type, parameter_set_id, pay::AggregateMode},
+    h26x::{format_sprop, split_nal_units, NalFormat, ParameterSets},
+};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtph264pay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP H.264 Payloader"),
+    )
+});
+
+const DEFAULT_CONFIG_INTERVAL: i32 = 0;
+
+#[derive(Clone)]
+struct Settings {
+    aggregate_mode: AggregateMode,
+    config_interval: i32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            aggregate_mode: AggregateMode::default(),
+            config_interval: DEFAULT_CONFIG_INTERVAL,
+        }
+    }
+}
+
+struct State {
+    /// Format of the NAL units in the input buffers.
+    nal_format: NalFormat,
+    /// Last SPS and PPS for each id, either from the caps or from the stream.
+    sps: ParameterSets,
+    pps: ParameterSets,
+    /// PTS of the last access unit that contained SPS and PPS.
+    last_config_pts: Option<gst::ClockTime>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            nal_format: NalFormat::ByteStream,
+            sps: ParameterSets::default(),
+            pps: ParameterSets::default(),
+            last_config_pts: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RtpH264Pay {
+    settings: Mutex<Settings>,
+    state: AtomicRefCell<State>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpH264Pay {
+    const NAME: &'static str = "GstRtpH264Pay2";
+    type Type = super::RtpH264Pay;
+    type ParentType = crate::basepay::RtpBasePay2;
+}
+
+impl ObjectImpl for RtpH264Pay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecEnum::builder::<AggregateMode>("aggregate-mode")
+                    .nick("Aggregate Mode")
+                    .blurb("Whether to aggregate NAL units of an access unit into STAP-A packets")
+                    .default_value(Settings::default().aggregate_mode)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecInt::builder("config-interval")
+                    .nick("Config Interval")
+                    .blurb("Send SPS and PPS in-band before IDR frames at this interval in seconds (0 = disabled, -1 = with every IDR frame)")
+                    .default_value(DEFAULT_CONFIG_INTERVAL)
+                    .minimum(-1)
+                    .maximum(3600)
+                    .mutable_playing()
+                    .build(),
+            ]
+        });
+
+        &PROPERTIES
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "aggregate-mode" => {
+                self.settings.lock().unwrap().aggregate_mode = value.get().unwrap();
+            }
+            "config-interval" => {
+                self.settings.lock().unwrap().config_interval = value.get().unwrap();
+            }
+            _ => unimplemented!(),
+        };
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "aggregate-mode" => self.settings.lock().unwrap().aggregate_mode.to_value(),
+            "config-interval" => self.settings.lock().unwrap().config_interval.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpH264Pay {}
+
+impl ElementImpl for RtpH264Pay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP H.264 payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload H.264 as RTP packets",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("video/x-h264")
+                    .field("stream-format", gst::List::new(["avc", "byte-stream"]))
+                    .field("alignment", "au")
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("clock-rate", 90_000i32)
+                    .field("encoding-name", "H264")
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpH264Pay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["video"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        gst::debug!(CAT, imp = self, "received caps {caps:?}");
+
+        let s = caps.structure(0).unwrap();
+        let mut state = self.state.borrow_mut();
+
+        if s.get::<&str>("stream-format") == Ok("avc") {
+            let Ok(codec_data) = s.get::<&gst::BufferRef>("codec_data") else {
+                gst::error!(CAT, imp = self, "No codec_data in avc caps");
+                return false;
+            };
+            let Ok(map) = codec_data.map_readable() else {
+                gst::error!(CAT, imp = self, "Failed to map codec_data");
+                return false;
+            };
+            let Some(avcc) = parse_avcc(&map) else {
+                gst::error!(CAT, imp = self, "Failed to parse codec_data");
+                return false;
+            };
+
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Using NAL length size {}, {} SPS, {} PPS",
+                avcc.nal_length_size,
+                avcc.sps.len(),
+                avcc.pps.len(),
+            );
+
+            state.nal_format = NalFormat::LengthPrefixed(avcc.nal_length_size);
+            for nal in avcc.sps.iter().chain(avcc.pps.iter()) {
+                self.store_parameter_set(&mut state, nal);
+            }
+        } else {
+            state.nal_format = NalFormat::ByteStream;
+        }
+
+        self.update_src_caps(&state);
+
+        true
+    }
+
+    fn handle_buffer(
+        &self,
+        buffer: &gst::Buffer,
+        id: u64,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let mut state = self.state.borrow_mut();
+
+        let max_payload_size = self.obj().max_payload_size() as usize;
+        // Need space for at least the FU-A indicator and header plus one byte of payload.
+        if max_payload_size < 3 {
+            gst::error!(CAT, imp = self, "Too small MTU configured for stream");
+            gst::element_imp_error!(
+                self,
+                gst::LibraryError::Settings,
+                ["Too small MTU configured for stream"]
+            );
+            return Err(gst::FlowError::Error);
+        }
+
+        gst::trace!(CAT, imp = self, "received buffer of size {}", buffer.size());
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::element_imp_error!(
+                self,
+                gst::ResourceError::Read,
+                ["Failed to map buffer readable"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        let nal_units = split_nal_units(&map, state.nal_format).map_err(|err| {
+            gst::element_imp_error!(
+                self,
+                gst::StreamError::Format,
+                ["Failed to parse NAL units: {err}"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        if nal_units.is_empty() {
+            gst::warning!(CAT, imp = self, "Access unit without NAL units");
+            self.obj().drop_buffers(..=id);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let mut has_sps = false;
+        let mut has_pps = false;
+        let mut is_idr = false;
+        let mut config_changed = false;
+        for nal in &nal_units {
+            match nal[0] & 0x1f {
+                nal_type::SPS => has_sps = true,
+                nal_type::PPS => has_pps = true,
+                nal_type::IDR => {
+                    is_idr = true;
+                    continue;
+                }
+                _ => continue,
+            }
+
+            config_changed |= self.store_parameter_set(&mut state, nal);
+        }
+
+        if config_changed {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "SPS / PPS changed, now {} SPS and {} PPS",
+                state.sps.len(),
+                state.pps.len(),
+            );
+            self.update_src_caps(&state);
+        }
+
+        let pts = buffer.pts();
+        let insert_config = is_idr
+            && !(has_sps && has_pps)
+            && match settings.config_interval {
+                0 => false,
+                -1 => true,
+                interval => state
+                    .last_config_pts
+                    .zip(pts)
+                    .and_then(|(last, pts)| pts.checked_sub(last))
+                    .map_or(true, |diff| {
+                        diff >= gst::ClockTime::from_seconds(interval as u64)
+                    }),
+            };
+        let config = if insert_config && !state.sps.is_empty() && !state.pps.is_empty() {
+            Some(
+                Iterator::chain(state.sps.iter(), state.pps.iter())
+                    .map(<[u8]>::to_vec)
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            None
+        };
+        if config.is_some() || (has_sps && has_pps) {
+            state.last_config_pts = pts;
+        }
+        drop(state);
+
+        let mut nals = SmallVec::<[&[u8]; 8]>::with_capacity(
+            nal_units.len() + config.as_ref().map_or(0, Vec::len),
+        );
+        nals.extend_from_slice(&nal_units);
+        if let Some(ref config) = config {
+            gst::trace!(CAT, imp = self, "Inserting SPS / PPS before IDR frame");
+
+            // Parameter sets have to come after the access unit delimiter, if any
+            let pos = if nals[0][0] & 0x1f == nal_type::AUD {
+                1
+            } else {
+                0
+            };
+            nals.insert_many(pos, config.iter().map(Vec::as_slice));
+        }
+
+        let mut aggregate = SmallVec::<[&[u8]; 8]>::new();
+        // STAP-A header plus all NAL units with their size fields
+        let mut aggregate_size = 0;
+        for (idx, nal) in nals.iter().enumerate() {
+            let marker = idx == nals.len() - 1;
+
+            if settings.aggregate_mode == AggregateMode::ZeroLatency {
+                if !aggregate.is_empty() && aggregate_size + 2 + nal.len() > max_payload_size {
+                    self.queue_aggregate(&aggregate, id, false)?;
+                    aggregate.clear();
+                }
+
+                if aggregate.is_empty() {
+                    aggregate_size = 1;
+                }
+
+                if aggregate_size + 2 + nal.len() <= max_payload_size {
+                    aggregate.push(nal);
+                    aggregate_size += 2 + nal.len();
+
+                    if marker {
+                        self.queue_aggregate(&aggregate, id, true)?;
+                        aggregate.clear();
+                    }
+                    continue;
+                }
+            }
+
+            self.queue_nal(nal, max_payload_size, id, marker)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+impl RtpH264Pay {
+    fn update_src_caps(&self, state: &State) {
+        let mut caps_builder = gst::Caps::builder("application/x-rtp")
+            .field("media", "video")
+            .field("clock-rate", 90_000i32)
+            .field("encoding-name", "H264")
+            .field("packetization-mode", "1");
+
+        if let Some(sps) = state.sps.iter().next() {
+            if sps.len() >= 4 {
+                caps_builder = caps_builder.field(
+                    "profile-level-id",
+                    format!("{:02x}{:02x}{:02x}", sps[1], sps[2], sps[3]),
+                );
+            }
+        }
+
+        if !state.sps.is_empty() && !state.pps.is_empty() {
+            caps_builder = caps_builder.field(
+                "sprop-parameter-sets",
+                format_sprop(Iterator::chain(state.sps.iter(), state.pps.iter())),
+            );
+        }
+
+        self.obj().set_src_caps(&caps_builder.build());
+    }
+
+    /// Stores the SPS or PPS `nal` and returns `true` if this changed the parameter sets.
+    fn store_parameter_set(&self, state: &mut State, nal: &[u8]) -> bool {
+        let Some(id) = parameter_set_id(nal) else {
+            gst::warning!(CAT, imp = self, "Failed to parse parameter set id");
+            return false;
+        };
+
+        let parameter_sets = if nal[0] & 0x1f == nal_type::SPS {
+            &mut state.sps
+        } else {
+            &mut state.pps
+        };
+
+        parameter_sets.insert(id, nal)
+    }
+
+    /// Queues the given NAL units either as a Single NAL unit packet or as a STAP-A packet.
+    fn queue_aggregate(
+        &self,
+        nals: &[&[u8]],
+        id: u64,
+        marker: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        assert!(!nals.is_empty());
+
+        if nals.len() == 1 {
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Writing single NAL unit packet of size {}",
+                nals[0].len()
+            );
+            return self.obj().queue_packet(
+                id.into(),
+                rtp_types::RtpPacketBuilder::new()
+                    .marker_bit(marker)
+                    .payload(nals[0]),
+            );
+        }
+
+        // F bit is set if any NAL unit has it set, NRI is the maximum of all NAL units
+        let f = nals.iter().fold(0, |acc, nal| acc | (nal[0] & 0x80));
+        let nri = nals.iter().map(|nal| nal[0] & 0x60).max().unwrap();
+        let header = [f | nri | nal_type::STAP_A];
+        let sizes = nals
+            .iter()
+            .map(|nal| (nal.len() as u16).to_be_bytes())
+            .collect::<SmallVec<[[u8; 2]; 8]>>();
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Writing STAP-A packet with {} NAL units",
+            nals.len()
+        );
+
+        let mut builder = rtp_types::RtpPacketBuilder::new()
+            .marker_bit(marker)
+            .payload(header.as_slice());
+        for (nal, size) in Iterator::zip(nals.iter(), sizes.iter()) {
+            builder = builder.payload(size.as_slice()).payload(*nal);
+        }
+
+        self.obj().queue_packet(id.into(), builder)
+    }
+
+    /// Queues a single NAL unit, fragmenting it into FU-A packets if necessary.
+    fn queue_nal(
+        &self,
+        nal: &[u8],
+        max_payload_size: usize,
+        id: u64,
+        marker: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        if nal.len() <= max_payload_size {
+            return self.queue_aggregate(&[nal], id, marker);
+        }
+
+        let fu_indicator = (nal[0] & 0xe0) | nal_type::FU_A;
+        let nal_unit_type = nal[0] & 0x1f;
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Fragmenting NAL unit of size {} into FU-A packets",
+            nal.len()
+        );
+
+        let mut data = &nal[1..];
+        let mut first = true;
+        while !data.is_empty() {
+            let fragment_size = cmp::min(max_payload_size - 2, data.len());
+            let last = fragment_size == data.len();
+
+            let fu_header = ((first as u8) << 7) | ((last as u8) << 6) | nal_unit_type;
+            let header = [fu_indicator, fu_header];
+
+            self.obj().queue_packet(
+                id.into(),
+                rtp_types::RtpPacketBuilder::new()
+                    .marker_bit(marker && last)
+                    .payload(header.as_slice())
+                    .payload(&data[..fragment_size]),
+            )?;
+
+            data = &data[fragment_size..];
+            first = false;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+struct AvcDecoderConfiguration {
+    nal_length_size: usize,
+    sps: Vec<Vec<u8>>,
+    pps: Vec<Vec<u8>>,
+}
+
+/// Parses an `AVCDecoderConfigurationRecord` as defined in ISO/IEC 14496-15.
+fn parse_avcc(data: &[u8]) -> Option<AvcDecoderConfiguration> {
+    fn read_nal_units(data: &mut &[u8], count: usize) -> Option<Vec<Vec<u8>>> {
+        let mut nal_units = Vec::with_capacity(count);
+        for _ in 0..count {
+            let [a, b, rest @ ..] = *data else {
+                return None;
+            };
+            let size = u16::from_be_bytes([*a, *b]) as usize;
+            if rest.len() < size {
+                return None;
+            }
+            nal_units.push(rest[..size].to_vec());
+            *data = &rest[size..];
+        }
+
+        Some(nal_units)
+    }
+
+    if data.len() < 7 || data[0] != 1 {
+        return None;
+    }
+
+    let nal_length_size = (data[4] & 0x03) as usize + 1;
+    let num_sps = (data[5] & 0x1f) as usize;
+    let mut data = &data[6..];
+    let sps = read_nal_units(&mut data, num_sps)?;
+    let (&num_pps, mut data) = data.split_first()?;
+    let pps = read_nal_units(&mut data, num_pps as usize)?;
+
+    Some(AvcDecoderConfiguration {
+        nal_length_size,
+        sps,
+        pps,
+    })
+}
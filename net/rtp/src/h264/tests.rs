@@ -0,0 +1,460 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::tests::{run_test_pipeline, ExpectedBuffer, ExpectedPacket, Source};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtph264 test");
+    });
+}
+
+const SPS: &[u8] = &[0x67, 0x42, 0xc0, 0x1e, 0xd9, 0x00, 0xa0, 0x47, 0xfe, 0xc8];
+const PPS: &[u8] = &[0x68, 0xce, 0x3c, 0x80];
+
+/// Creates a NAL unit with the given header byte and size that contains no start code emulation.
+fn nal(header: u8, size: usize) -> Vec<u8> {
+    let mut nal = vec![0x11; size];
+    nal[0] = header;
+    nal
+}
+
+fn byte_stream_au(nals: &[&[u8]]) -> Vec<u8> {
+    nals.iter()
+        .flat_map(|nal| [0x00, 0x00, 0x00, 0x01].iter().chain(nal.iter()))
+        .copied()
+        .collect()
+}
+
+fn avc_au(nals: &[&[u8]]) -> Vec<u8> {
+    nals.iter()
+        .flat_map(|nal| {
+            (nal.len() as u32)
+                .to_be_bytes()
+                .into_iter()
+                .chain(nal.iter().copied())
+        })
+        .collect()
+}
+
+fn make_buffers(aus: Vec<Vec<u8>>) -> Vec<gst::Buffer> {
+    aus.into_iter()
+        .enumerate()
+        .map(|(i, au)| {
+            let mut buffer = gst::Buffer::from_mut_slice(au);
+            {
+                let buffer = buffer.get_mut().unwrap();
+                buffer.set_pts(gst::ClockTime::from_mseconds(i as u64 * 40));
+                if i == 0 {
+                    buffer.set_flags(gst::BufferFlags::DISCONT);
+                }
+            }
+            buffer
+        })
+        .collect()
+}
+
+#[test]
+fn test_h264() {
+    init();
+
+    let idr = nal(0x65, 3000);
+    let non_idr = nal(0x41, 100);
+    let idr_without_sps = nal(0x65, 1500);
+
+    let caps = gst::Caps::builder("video/x-h264")
+        .field("stream-format", "byte-stream")
+        .field("alignment", "au")
+        .build();
+    let buffers = make_buffers(vec![
+        byte_stream_au(&[SPS, PPS, &idr]),
+        byte_stream_au(&[&non_idr]),
+        byte_stream_au(&[&idr_without_sps]),
+    ]);
+
+    let pay = "rtph264pay2";
+    let depay = "rtph264depay2";
+
+    let expected_pay = vec![
+        vec![
+            // SPS and PPS are aggregated into a STAP-A packet
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::DISCONT)
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(12 + 1 + 2 + SPS.len() + 2 + PPS.len())
+                .build(),
+            // IDR is fragmented into three FU-A packets
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(0)
+                .marker_bit(true)
+                .size(12 + 2 + 2999 - 2 * 1386)
+                .build(),
+        ],
+        // Single NAL unit packet
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 100)
+            .build()],
+        vec![
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(80))
+                .rtp_time(7_200)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(80))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(7_200)
+                .marker_bit(true)
+                .size(12 + 2 + 1499 - 1386)
+                .build(),
+        ],
+    ];
+
+    let expected_depay = vec![
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(0))
+            .size(4 + SPS.len() + 4 + PPS.len() + 4 + 3000)
+            .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .size(4 + 100)
+            .flags(gst::BufferFlags::MARKER | gst::BufferFlags::DELTA_UNIT)
+            .build()],
+        // SPS and PPS from the caps are inserted before the IDR frame
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .size(4 + SPS.len() + 4 + PPS.len() + 4 + 1500)
+            .flags(gst::BufferFlags::MARKER)
+            .build()],
+    ];
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_h264_avc_config_interval() {
+    init();
+
+    let idr = nal(0x65, 500);
+    let non_idr = nal(0x41, 50);
+
+    let codec_data = [
+        &[0x01, SPS[1], SPS[2], SPS[3], 0xff, 0xe1][..],
+        &(SPS.len() as u16).to_be_bytes(),
+        SPS,
+        &[0x01u8],
+        &(PPS.len() as u16).to_be_bytes(),
+        PPS,
+    ]
+    .concat();
+
+    let caps = gst::Caps::builder("video/x-h264")
+        .field("stream-format", "avc")
+        .field("alignment", "au")
+        .field("codec_data", gst::Buffer::from_mut_slice(codec_data))
+        .build();
+    let buffers = make_buffers(vec![avc_au(&[&idr]), avc_au(&[&non_idr]), avc_au(&[&idr])]);
+
+    let pay = "rtph264pay2 aggregate-mode=none config-interval=-1";
+    let depay = "rtph264depay2";
+
+    let keyframe_packets = |pts: gst::ClockTime, rtp_time: u32, flags: gst::BufferFlags| {
+        vec![
+            ExpectedPacket::builder()
+                .pts(pts)
+                .flags(flags)
+                .rtp_time(rtp_time)
+                .marker_bit(false)
+                .size(12 + SPS.len())
+                .build(),
+            ExpectedPacket::builder()
+                .pts(pts)
+                .rtp_time(rtp_time)
+                .marker_bit(false)
+                .size(12 + PPS.len())
+                .build(),
+            ExpectedPacket::builder()
+                .pts(pts)
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(rtp_time)
+                .marker_bit(true)
+                .size(12 + 500)
+                .build(),
+        ]
+    };
+
+    let expected_pay = vec![
+        keyframe_packets(
+            gst::ClockTime::from_mseconds(0),
+            0,
+            gst::BufferFlags::DISCONT,
+        ),
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 50)
+            .build()],
+        keyframe_packets(
+            gst::ClockTime::from_mseconds(80),
+            7_200,
+            gst::BufferFlags::empty(),
+        ),
+    ];
+
+    let expected_depay = vec![
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(0))
+            .size(4 + SPS.len() + 4 + PPS.len() + 4 + 500)
+            .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .size(4 + 50)
+            .flags(gst::BufferFlags::MARKER | gst::BufferFlags::DELTA_UNIT)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .size(4 + SPS.len() + 4 + PPS.len() + 4 + 500)
+            .flags(gst::BufferFlags::MARKER)
+            .build()],
+    ];
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_h264_multiple_parameter_sets() {
+    init();
+
+    // SPS and PPS with id 1, after the ones with id 0
+    const SPS_1: &[u8] = &[0x67, 0x42, 0xc0, 0x1e, 0x40, 0x11];
+    const PPS_1: &[u8] = &[0x68, 0x5e, 0x3c, 0x80];
+
+    assert_eq!(super::parameter_set_id(SPS), Some(0));
+    assert_eq!(super::parameter_set_id(SPS_1), Some(1));
+    assert_eq!(super::parameter_set_id(PPS), Some(0));
+    assert_eq!(super::parameter_set_id(PPS_1), Some(1));
+
+    let idr = nal(0x65, 500);
+    let non_idr = nal(0x41, 50);
+
+    let caps = gst::Caps::builder("video/x-h264")
+        .field("stream-format", "byte-stream")
+        .field("alignment", "au")
+        .build();
+    let buffers = make_buffers(vec![
+        byte_stream_au(&[SPS_1, SPS, PPS_1, PPS, &idr]),
+        byte_stream_au(&[&non_idr]),
+        byte_stream_au(&[&idr]),
+    ]);
+
+    let pay = "rtph264pay2 aggregate-mode=none config-interval=-1";
+    let depay = "rtph264depay2";
+
+    // All parameter sets are sent in-band, ordered by their id when inserted by the payloader
+    let keyframe_packets = |pts: gst::ClockTime,
+                            rtp_time: u32,
+                            flags: gst::BufferFlags,
+                            parameter_sets: [&[u8]; 4]| {
+        let mut packets = parameter_sets
+            .iter()
+            .enumerate()
+            .map(|(i, nal)| {
+                ExpectedPacket::builder()
+                    .pts(pts)
+                    .flags(if i == 0 {
+                        flags
+                    } else {
+                        gst::BufferFlags::empty()
+                    })
+                    .rtp_time(rtp_time)
+                    .marker_bit(false)
+                    .size(12 + nal.len())
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        packets.push(
+            ExpectedPacket::builder()
+                .pts(pts)
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(rtp_time)
+                .marker_bit(true)
+                .size(12 + 500)
+                .build(),
+        );
+        packets
+    };
+
+    let expected_pay = vec![
+        keyframe_packets(
+            gst::ClockTime::from_mseconds(0),
+            0,
+            gst::BufferFlags::DISCONT,
+            [SPS_1, SPS, PPS_1, PPS],
+        ),
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 50)
+            .build()],
+        keyframe_packets(
+            gst::ClockTime::from_mseconds(80),
+            7_200,
+            gst::BufferFlags::empty(),
+            [SPS, SPS_1, PPS, PPS_1],
+        ),
+    ];
+
+    let parameter_sets_size = 4 + SPS.len() + 4 + SPS_1.len() + 4 + PPS.len() + 4 + PPS_1.len();
+    let expected_depay = vec![
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(0))
+            .size(parameter_sets_size + 4 + 500)
+            .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .size(4 + 50)
+            .flags(gst::BufferFlags::MARKER | gst::BufferFlags::DELTA_UNIT)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .size(parameter_sets_size + 4 + 500)
+            .flags(gst::BufferFlags::MARKER)
+            .build()],
+    ];
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_h264_wait_for_keyframe() {
+    init();
+
+    let idr = nal(0x65, 3000);
+    let non_idr = nal(0x41, 100);
+    let small_idr = nal(0x65, 100);
+
+    let caps = gst::Caps::builder("video/x-h264")
+        .field("stream-format", "byte-stream")
+        .field("alignment", "au")
+        .build();
+    let buffers = make_buffers(vec![
+        byte_stream_au(&[&idr]),
+        byte_stream_au(&[&non_idr]),
+        byte_stream_au(&[&small_idr]),
+    ]);
+
+    let pay = "rtph264pay2";
+    let depay = "rtph264depay2 wait-for-keyframe=true";
+
+    let expected_pay = vec![
+        vec![
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::DISCONT)
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            // Losing the middle fragment makes the whole keyframe undecodable
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .drop(true)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(0)
+                .marker_bit(true)
+                .size(12 + 2 + 2999 - 2 * 1386)
+                .build(),
+        ],
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 100)
+            .build()],
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(7_200)
+            .marker_bit(true)
+            .size(12 + 100)
+            .build()],
+    ];
+
+    // Only the next keyframe is output
+    let expected_depay = vec![vec![ExpectedBuffer::builder()
+        .pts(gst::ClockTime::from_mseconds(80))
+        .size(4 + 100)
+        .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+        .build()]];
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
@@ -0,0 +1,529 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtph265depay2
+ * @see_also: rtph265pay2, h265parse, avdec_h265
+ *
+ * Depayload an H.265 video stream from RTP packets as per [RFC 7798][rfc-7798].
+ *
+ * Single NAL unit, AP and FU packets are supported, including decoding order numbers if
+ * `sprop-max-don-diff` is signalled in the caps. NAL units are output in transmission order.
+ * The output is an H.265 byte-stream with one access unit per buffer. If the caps contain
+ * `sprop-vps`, `sprop-sps` and `sprop-pps` then these are inserted before every IRAP frame that
+ * does not already contain an SPS.
+ *
+ * [rfc-7798]: https://www.rfc-editor.org/rfc/rfc7798.html
+ *
+ * ## Example pipeline
+ *
+ * ```shell
+ * gst-launch-1.0 udpsrc address=127.0.0.1 port=5004 caps='application/x-rtp,media=video,clock-rate=90000,encoding-name=H265' ! rtpjitterbuffer latency=100 ! rtph265depay2 ! decodebin3 ! videoconvertscale ! autovideosink
+ * ```
+ *
+ * This will depayload and decode an incoming RTP H.265 video stream. You can use the
+ * #rtph265pay2 and #x265enc elements to create such an RTP stream.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use std::{mem, sync::Mutex};
+
+use atomic_refcell::AtomicRefCell;
+
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+use std::sync::LazyLock;
+
+use crate::basedepay::{PacketToBufferRelation, RtpBaseDepay2Ext};
+use crate::h265::{is_irap, nal_type, nal_unit_type};
+use crate::h26x::{parse_sprop, START_CODE};
+
+#[derive(Clone, Default)]
+struct Settings {
+    request_keyframe: bool,
+    wait_for_keyframe: bool,
+}
+
+struct State {
+    /// Parameter sets from the `sprop-vps`, `sprop-sps` and `sprop-pps` caps fields.
+    sprop_parameter_sets: Vec<Vec<u8>>,
+    /// Set if the packets contain decoding order numbers.
+    has_don: bool,
+
+    /// Extended RTP timestamp of the current access unit, if any.
+    au_timestamp: Option<u64>,
+    /// First and last extended seqnum of the packets of the current access unit.
+    au_start_ext_seqnum: u64,
+    au_end_ext_seqnum: u64,
+    /// Byte-stream data of the current access unit.
+    au_data: Vec<u8>,
+    au_is_keyframe: bool,
+    au_has_sps: bool,
+
+    /// Offset into `au_data` where the NAL unit that is currently reassembled from FU packets
+    /// starts.
+    fu_start: Option<usize>,
+
+    /// Set to `true` until a keyframe was output, initially and after packet loss.
+    waiting_for_keyframe: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            sprop_parameter_sets: Vec::new(),
+            has_don: false,
+            au_timestamp: None,
+            au_start_ext_seqnum: 0,
+            au_end_ext_seqnum: 0,
+            au_data: Vec::new(),
+            au_is_keyframe: false,
+            au_has_sps: false,
+            fu_start: None,
+            waiting_for_keyframe: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RtpH265Depay {
+    state: AtomicRefCell<State>,
+    settings: Mutex<Settings>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtph265depay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP H.265 Depayloader"),
+    )
+});
+
+impl RtpH265Depay {
+    fn reset(&self, state: &mut State) {
+        gst::debug!(CAT, imp = self, "resetting state");
+
+        // The configuration from the caps stays valid
+        let sprop_parameter_sets = mem::take(&mut state.sprop_parameter_sets);
+        let has_don = state.has_don;
+        *state = State::default();
+        state.sprop_parameter_sets = sprop_parameter_sets;
+        state.has_don = has_don;
+    }
+
+    /// Appends a complete NAL unit to the current access unit.
+    fn push_nal(&self, state: &mut State, nal: &[u8]) {
+        if nal.len() < 2 {
+            gst::warning!(CAT, imp = self, "Dropping too short NAL unit");
+            return;
+        }
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Received NAL unit of type {} and size {}",
+            nal_unit_type(nal[0]),
+            nal.len()
+        );
+
+        self.update_au_flags(state, nal[0]);
+        state.au_data.extend_from_slice(&START_CODE);
+        state.au_data.extend_from_slice(nal);
+    }
+
+    fn update_au_flags(&self, state: &mut State, nal_header: u8) {
+        match nal_unit_type(nal_header) {
+            t if is_irap(t) => state.au_is_keyframe = true,
+            nal_type::SPS => state.au_has_sps = true,
+            _ => (),
+        }
+    }
+
+    /// Finishes the current access unit, if any, and queues it for output.
+    fn finish_au(
+        &self,
+        settings: &Settings,
+        state: &mut State,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        if state.au_timestamp.take().is_none() {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        if let Some(fu_start) = state.fu_start.take() {
+            gst::warning!(CAT, imp = self, "Dropping incomplete FU NAL unit");
+            state.au_data.truncate(fu_start);
+        }
+
+        let seqnums = state.au_start_ext_seqnum..=state.au_end_ext_seqnum;
+        let mut au_data = mem::take(&mut state.au_data);
+        let is_keyframe = mem::take(&mut state.au_is_keyframe);
+        let has_sps = mem::take(&mut state.au_has_sps);
+
+        if au_data.is_empty() {
+            gst::debug!(CAT, imp = self, "Dropping empty access unit");
+            self.obj().drop_packets(seqnums);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        if !is_keyframe && state.waiting_for_keyframe {
+            if settings.request_keyframe {
+                gst::debug!(CAT, imp = self, "Requesting keyframe from upstream");
+                let event = gst_video::UpstreamForceKeyUnitEvent::builder()
+                    .all_headers(true)
+                    .build();
+                let _ = self.obj().sink_pad().push_event(event);
+            }
+
+            if settings.wait_for_keyframe {
+                gst::trace!(CAT, imp = self, "Waiting for keyframe");
+                self.obj().drop_packets(seqnums);
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        }
+
+        if is_keyframe {
+            state.waiting_for_keyframe = false;
+
+            if !has_sps && !state.sprop_parameter_sets.is_empty() {
+                gst::trace!(CAT, imp = self, "Inserting parameter sets from caps");
+
+                let mut data = Vec::with_capacity(
+                    au_data.len()
+                        + state
+                            .sprop_parameter_sets
+                            .iter()
+                            .map(|nal| START_CODE.len() + nal.len())
+                            .sum::<usize>(),
+                );
+                for nal in &state.sprop_parameter_sets {
+                    data.extend_from_slice(&START_CODE);
+                    data.extend_from_slice(nal);
+                }
+                data.extend_from_slice(&au_data);
+                au_data = data;
+            }
+        }
+
+        let mut buffer = gst::Buffer::from_mut_slice(au_data);
+        {
+            let buffer = buffer.get_mut().unwrap();
+
+            if !is_keyframe {
+                buffer.set_flags(gst::BufferFlags::DELTA_UNIT);
+                gst::trace!(CAT, imp = self, "Finishing delta-frame");
+            } else {
+                gst::trace!(CAT, imp = self, "Finishing keyframe");
+            }
+
+            // Set MARKER flag on the output so that the parser knows that this buffer ends a full
+            // access unit.
+            buffer.set_flags(gst::BufferFlags::MARKER);
+        }
+
+        self.obj()
+            .queue_buffer(PacketToBufferRelation::Seqnums(seqnums), buffer)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpH265Depay {
+    const NAME: &'static str = "GstRtpH265Depay2";
+    type Type = super::RtpH265Depay;
+    type ParentType = crate::basedepay::RtpBaseDepay2;
+}
+
+impl ObjectImpl for RtpH265Depay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecBoolean::builder("request-keyframe")
+                    .nick("Request Keyframe")
+                    .blurb("Request new keyframe when packet loss is detected")
+                    .default_value(Settings::default().request_keyframe)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("wait-for-keyframe")
+                    .nick("Wait For Keyframe")
+                    .blurb("Wait for the next keyframe after packet loss")
+                    .default_value(Settings::default().wait_for_keyframe)
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "request-keyframe" => {
+                self.settings.lock().unwrap().request_keyframe = value.get().unwrap();
+            }
+            "wait-for-keyframe" => {
+                self.settings.lock().unwrap().wait_for_keyframe = value.get().unwrap();
+            }
+            _ => unimplemented!(),
+        };
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "request-keyframe" => self.settings.lock().unwrap().request_keyframe.to_value(),
+            "wait-for-keyframe" => self.settings.lock().unwrap().wait_for_keyframe.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpH265Depay {}
+
+impl ElementImpl for RtpH265Depay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP H.265 Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Depayload H.265 from RTP packets",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("clock-rate", 90_000i32)
+                    .field("encoding-name", "H265")
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("video/x-h265")
+                    .field("stream-format", "byte-stream")
+                    .field("alignment", "au")
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpH265Depay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["video"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let s = caps.structure(0).unwrap();
+
+        let max_don_diff = match s.get::<&str>("sprop-max-don-diff") {
+            Ok(max_don_diff) => match max_don_diff.trim().parse::<u16>() {
+                Ok(max_don_diff) => max_don_diff,
+                Err(_) => {
+                    gst::error!(CAT, imp = self, "Invalid sprop-max-don-diff {max_don_diff}");
+                    return false;
+                }
+            },
+            Err(_) => 0,
+        };
+
+        let sprop_parameter_sets = ["sprop-vps", "sprop-sps", "sprop-pps"]
+            .into_iter()
+            .filter_map(|field| s.get::<&str>(field).ok())
+            .flat_map(parse_sprop)
+            .collect::<Vec<_>>();
+        gst::debug!(
+            CAT,
+            imp = self,
+            "Got {} parameter sets from caps, max DON diff {max_don_diff}",
+            sprop_parameter_sets.len()
+        );
+
+        {
+            let mut state = self.state.borrow_mut();
+            state.sprop_parameter_sets = sprop_parameter_sets;
+            state.has_don = max_don_diff > 0;
+        }
+
+        self.obj()
+            .set_src_caps(&self.obj().src_pad().pad_template_caps());
+
+        true
+    }
+
+    fn drain(&self) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let mut state = self.state.borrow_mut();
+
+        self.finish_au(&settings, &mut state)
+    }
+
+    fn flush(&self) {
+        let mut state = self.state.borrow_mut();
+        self.reset(&mut state);
+    }
+
+    fn handle_packet(
+        &self,
+        packet: &crate::basedepay::Packet,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+
+        gst::trace!(CAT, imp = self, "Handling RTP packet {packet:?}");
+        let mut state = self.state.borrow_mut();
+
+        if packet.discont() {
+            // Anything pending was already drained by the base class
+            gst::debug!(CAT, imp = self, "Discontinuity, waiting for next keyframe");
+            state.waiting_for_keyframe = true;
+        }
+
+        // A new timestamp starts a new access unit even if the marker bit of the previous one was
+        // lost.
+        if state
+            .au_timestamp
+            .is_some_and(|ts| ts != packet.ext_timestamp())
+        {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Timestamp changed without marker bit, finishing access unit"
+            );
+            self.finish_au(&settings, &mut state)?;
+        }
+
+        if state.au_timestamp.is_none() {
+            state.au_timestamp = Some(packet.ext_timestamp());
+            state.au_start_ext_seqnum = packet.ext_seqnum();
+        }
+        state.au_end_ext_seqnum = packet.ext_seqnum();
+
+        // Size of the DONL / DOND fields if decoding order numbers are used
+        let (donl_size, dond_size) = if state.has_don { (2, 1) } else { (0, 0) };
+
+        let payload = packet.payload();
+        match payload {
+            [] | [_] => {
+                gst::warning!(CAT, imp = self, "Too short RTP packet");
+            }
+            [h0, h1, rest @ ..] => match nal_unit_type(*h0) {
+                0..=47 => {
+                    if let Some(rest) = rest.get(donl_size..) {
+                        let nal = [&[*h0, *h1][..], rest].concat();
+                        self.push_nal(&mut state, &nal);
+                    } else {
+                        gst::warning!(CAT, imp = self, "Truncated single NAL unit packet");
+                    }
+                }
+                nal_type::AP => {
+                    let mut data = rest;
+                    let mut first = true;
+                    loop {
+                        let Some(d) = data.get(if first { donl_size } else { dond_size }..) else {
+                            gst::warning!(CAT, imp = self, "Truncated AP packet");
+                            break;
+                        };
+                        let [s0, s1, rest @ ..] = d else {
+                            if !d.is_empty() {
+                                gst::warning!(CAT, imp = self, "Truncated AP packet");
+                            }
+                            break;
+                        };
+                        let size = u16::from_be_bytes([*s0, *s1]) as usize;
+                        if rest.len() < size {
+                            gst::warning!(CAT, imp = self, "Truncated AP packet");
+                            break;
+                        }
+
+                        self.push_nal(&mut state, &rest[..size]);
+                        data = &rest[size..];
+                        first = false;
+
+                        if data.is_empty() {
+                            break;
+                        }
+                    }
+                }
+                nal_type::FU => {
+                    if let [fu_header, fragment @ ..] = rest {
+                        let start = fu_header & 0x80 != 0;
+                        let end = fu_header & 0x40 != 0;
+
+                        if start {
+                            if let Some(fu_start) = state.fu_start.take() {
+                                gst::warning!(CAT, imp = self, "Dropping incomplete FU NAL unit");
+                                state.au_data.truncate(fu_start);
+                            }
+
+                            let nal_header = [(h0 & 0x81) | ((fu_header & 0x3f) << 1), *h1];
+                            self.update_au_flags(&mut state, nal_header[0]);
+
+                            if let Some(fragment) = fragment.get(donl_size..) {
+                                state.fu_start = Some(state.au_data.len());
+                                state.au_data.extend_from_slice(&START_CODE);
+                                state.au_data.extend_from_slice(&nal_header);
+                                state.au_data.extend_from_slice(fragment);
+                            } else {
+                                gst::warning!(CAT, imp = self, "Truncated FU packet");
+                            }
+                        } else if state.fu_start.is_some() {
+                            state.au_data.extend_from_slice(fragment);
+                        } else {
+                            gst::debug!(CAT, imp = self, "Missing start of FU NAL unit");
+                        }
+
+                        if end && state.fu_start.take().is_some() {
+                            gst::trace!(CAT, imp = self, "Finished FU NAL unit");
+                        }
+                    } else {
+                        gst::warning!(CAT, imp = self, "Truncated FU packet");
+                    }
+                }
+                nal_type::PACI => {
+                    gst::warning!(CAT, imp = self, "PACI packets are not supported");
+                }
+                nal_unit_type => {
+                    gst::warning!(CAT, imp = self, "Unsupported NAL unit type {nal_unit_type}");
+                }
+            },
+        }
+
+        // The marker bit is set for the last packet of an access unit.
+        if packet.marker_bit() {
+            self.finish_au(&settings, &mut state)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
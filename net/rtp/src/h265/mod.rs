@@ -0,0 +1,69 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+pub mod depay;
+pub mod pay;
+
+#[cfg(test)]
+mod tests;
+
+/// NAL unit types that are relevant for payloading and depayloading.
+pub(crate) mod nal_type {
+    pub const BLA_W_LP: u8 = 16;
+    pub const CRA_NUT: u8 = 21;
+    pub const VPS: u8 = 32;
+    pub const SPS: u8 = 33;
+    pub const PPS: u8 = 34;
+    pub const AUD: u8 = 35;
+    pub const AP: u8 = 48;
+    pub const FU: u8 = 49;
+    pub const PACI: u8 = 50;
+}
+
+/// Returns the NAL unit type from the first byte of the NAL unit header.
+pub(crate) fn nal_unit_type(nal_header: u8) -> u8 {
+    (nal_header >> 1) & 0x3f
+}
+
+/// Returns `true` if the NAL unit type is an IRAP picture, i.e. a keyframe.
+pub(crate) fn is_irap(nal_unit_type: u8) -> bool {
+    (nal_type::BLA_W_LP..=nal_type::CRA_NUT).contains(&nal_unit_type)
+}
+
+/// Returns the id of a VPS, SPS or PPS NAL unit.
+pub(crate) fn parameter_set_id(nal: &[u8]) -> Option<u32> {
+    use crate::h26x::{read_bit, read_ue, remove_emulation_prevention};
+
+    let rbsp = remove_emulation_prevention(nal.get(2..)?);
+
+    match nal_unit_type(nal[0]) {
+        nal_type::VPS => rbsp.first().map(|b| (b >> 4) as u32),
+        nal_type::SPS => {
+            // sps_seq_parameter_set_id follows the VPS id, max sub layers, temporal id nesting
+            // flag and profile_tier_level(), whose size depends on the number of sub layers
+            let max_sub_layers_minus1 = ((rbsp.first()? >> 1) & 0x07) as usize;
+            let mut pos = 8 + 96;
+            if max_sub_layers_minus1 > 0 {
+                let flags_pos = pos;
+                pos += 16;
+                for i in 0..max_sub_layers_minus1 {
+                    if read_bit(&rbsp, flags_pos + 2 * i)? {
+                        pos += 88;
+                    }
+                    if read_bit(&rbsp, flags_pos + 2 * i + 1)? {
+                        pos += 8;
+                    }
+                }
+            }
+            read_ue(&rbsp, pos)
+        }
+        nal_type::PPS => read_ue(&rbsp, 0),
+        _ => None,
+    }
+}
@@ -0,0 +1,715 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtph265pay2
+ * @see_also: rtph265depay2, x265enc, h265parse
+ *
+ * Payload an H.265 video stream into RTP packets as per [RFC 7798][rfc-7798].
+ *
+ * NAL units of an access unit are sent in Single NAL unit packets, aggregated into AP packets or
+ * fragmented into FU packets, and the marker bit is set on the last packet of each access unit.
+ * NAL units are always sent in decoding order. If #rtph265pay2:max-don-diff is non-zero, it is
+ * signalled as `sprop-max-don-diff` in the caps and all packets carry decoding order numbers in
+ * DONL / DOND fields, otherwise no decoding order numbers are sent.
+ *
+ * All VPS, SPS and PPS are signalled in the `sprop-vps`, `sprop-sps` and `sprop-pps` caps fields
+ * and can additionally be inserted in-band before IRAP frames with the
+ * #rtph265pay2:config-interval property.
+ *
+ * [rfc-7798]: https://www.rfc-editor.org/rfc/rfc7798.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 videotestsrc ! video/x-raw,width=1280,height=720,format=I420 ! timeoverlay font-desc=Sans,22 ! x265enc tune=zerolatency ! h265parse ! rtph265pay2 config-interval=-1 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will create and payload an H.265 video stream with a test pattern and
+ * send it out via UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+use smallvec::SmallVec;
+use std::{cmp, sync::Mutex};
+
+use std::sync::LazyLock;
+
+use crate::{
+    basepay::RtpBasePay2Ext,
+    h265::{is_irap, nal_type, nal_unit_type, parameter_set_id, pay::AggregateMode},
+    h26x::{format_sprop, remove_emulation_prevention, split_nal_units, NalFormat, ParameterSets},
+};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtph265pay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP H.265 Payloader"),
+    )
+});
+
+const DEFAULT_CONFIG_INTERVAL: i32 = 0;
+const DEFAULT_MAX_DON_DIFF: u32 = 0;
+
+#[derive(Clone)]
+struct Settings {
+    aggregate_mode: AggregateMode,
+    config_interval: i32,
+    max_don_diff: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            aggregate_mode: AggregateMode::default(),
+            config_interval: DEFAULT_CONFIG_INTERVAL,
+            max_don_diff: DEFAULT_MAX_DON_DIFF,
+        }
+    }
+}
+
+struct State {
+    /// Format of the NAL units in the input buffers.
+    nal_format: NalFormat,
+    /// Last VPS, SPS and PPS for each id, either from the caps or from the stream.
+    vps: ParameterSets,
+    sps: ParameterSets,
+    pps: ParameterSets,
+    /// PTS of the last access unit that contained VPS, SPS and PPS.
+    last_config_pts: Option<gst::ClockTime>,
+    /// Decoding order number of the next NAL unit.
+    don: u16,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            nal_format: NalFormat::ByteStream,
+            vps: ParameterSets::default(),
+            sps: ParameterSets::default(),
+            pps: ParameterSets::default(),
+            last_config_pts: None,
+            don: 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RtpH265Pay {
+    settings: Mutex<Settings>,
+    state: AtomicRefCell<State>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpH265Pay {
+    const NAME: &'static str = "GstRtpH265Pay2";
+    type Type = super::RtpH265Pay;
+    type ParentType = crate::basepay::RtpBasePay2;
+}
+
+impl ObjectImpl for RtpH265Pay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecEnum::builder::<AggregateMode>("aggregate-mode")
+                    .nick("Aggregate Mode")
+                    .blurb("Whether to aggregate NAL units of an access unit into AP packets")
+                    .default_value(Settings::default().aggregate_mode)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecInt::builder("config-interval")
+                    .nick("Config Interval")
+                    .blurb("Send VPS, SPS and PPS in-band before IRAP frames at this interval in seconds (0 = disabled, -1 = with every IRAP frame)")
+                    .default_value(DEFAULT_CONFIG_INTERVAL)
+                    .minimum(-1)
+                    .maximum(3600)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("max-don-diff")
+                    .nick("Max DON Diff")
+                    .blurb("Signal this sprop-max-don-diff and send decoding order numbers in all packets (0 = disabled)")
+                    .default_value(DEFAULT_MAX_DON_DIFF)
+                    .maximum(32767)
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        &PROPERTIES
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "aggregate-mode" => {
+                self.settings.lock().unwrap().aggregate_mode = value.get().unwrap();
+            }
+            "config-interval" => {
+                self.settings.lock().unwrap().config_interval = value.get().unwrap();
+            }
+            "max-don-diff" => {
+                self.settings.lock().unwrap().max_don_diff = value.get().unwrap();
+            }
+            _ => unimplemented!(),
+        };
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "aggregate-mode" => self.settings.lock().unwrap().aggregate_mode.to_value(),
+            "config-interval" => self.settings.lock().unwrap().config_interval.to_value(),
+            "max-don-diff" => self.settings.lock().unwrap().max_don_diff.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpH265Pay {}
+
+impl ElementImpl for RtpH265Pay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP H.265 payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload H.265 as RTP packets",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("video/x-h265")
+                    .field(
+                        "stream-format",
+                        gst::List::new(["hvc1", "hev1", "byte-stream"]),
+                    )
+                    .field("alignment", "au")
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("clock-rate", 90_000i32)
+                    .field("encoding-name", "H265")
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpH265Pay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["video"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        gst::debug!(CAT, imp = self, "received caps {caps:?}");
+
+        let s = caps.structure(0).unwrap();
+        let mut state = self.state.borrow_mut();
+
+        if matches!(s.get::<&str>("stream-format"), Ok("hvc1" | "hev1")) {
+            let Ok(codec_data) = s.get::<&gst::BufferRef>("codec_data") else {
+                gst::error!(CAT, imp = self, "No codec_data in hvc1 / hev1 caps");
+                return false;
+            };
+            let Ok(map) = codec_data.map_readable() else {
+                gst::error!(CAT, imp = self, "Failed to map codec_data");
+                return false;
+            };
+            let Some(hvcc) = parse_hvcc(&map) else {
+                gst::error!(CAT, imp = self, "Failed to parse codec_data");
+                return false;
+            };
+
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Using NAL length size {}, {} VPS, {} SPS, {} PPS",
+                hvcc.nal_length_size,
+                hvcc.vps.len(),
+                hvcc.sps.len(),
+                hvcc.pps.len(),
+            );
+
+            state.nal_format = NalFormat::LengthPrefixed(hvcc.nal_length_size);
+            for nal in hvcc.vps.iter().chain(&hvcc.sps).chain(&hvcc.pps) {
+                self.store_parameter_set(&mut state, nal);
+            }
+        } else {
+            state.nal_format = NalFormat::ByteStream;
+        }
+
+        self.update_src_caps(&state);
+
+        true
+    }
+
+    fn handle_buffer(
+        &self,
+        buffer: &gst::Buffer,
+        id: u64,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let mut state = self.state.borrow_mut();
+
+        let max_payload_size = self.obj().max_payload_size() as usize;
+        // Need space for at least the FU payload and FU header, the DONL field if decoding order
+        // numbers are used, plus one byte of payload.
+        let donl_size = if settings.max_don_diff > 0 { 2 } else { 0 };
+        if max_payload_size < 4 + donl_size {
+            gst::error!(CAT, imp = self, "Too small MTU configured for stream");
+            gst::element_imp_error!(
+                self,
+                gst::LibraryError::Settings,
+                ["Too small MTU configured for stream"]
+            );
+            return Err(gst::FlowError::Error);
+        }
+
+        gst::trace!(CAT, imp = self, "received buffer of size {}", buffer.size());
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::element_imp_error!(
+                self,
+                gst::ResourceError::Read,
+                ["Failed to map buffer readable"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        let mut nal_units = split_nal_units(&map, state.nal_format).map_err(|err| {
+            gst::element_imp_error!(
+                self,
+                gst::StreamError::Format,
+                ["Failed to parse NAL units: {err}"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        // Every NAL unit has a 2 byte header
+        nal_units.retain(|nal| {
+            if nal.len() < 2 {
+                gst::warning!(CAT, imp = self, "Dropping too short NAL unit");
+                return false;
+            }
+            true
+        });
+
+        if nal_units.is_empty() {
+            gst::warning!(CAT, imp = self, "Access unit without NAL units");
+            self.obj().drop_buffers(..=id);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let mut has_vps = false;
+        let mut has_sps = false;
+        let mut has_pps = false;
+        let mut is_keyframe = false;
+        let mut config_changed = false;
+        for nal in &nal_units {
+            match nal_unit_type(nal[0]) {
+                nal_type::VPS => has_vps = true,
+                nal_type::SPS => has_sps = true,
+                nal_type::PPS => has_pps = true,
+                t if is_irap(t) => {
+                    is_keyframe = true;
+                    continue;
+                }
+                _ => continue,
+            }
+
+            config_changed |= self.store_parameter_set(&mut state, nal);
+        }
+
+        if config_changed {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "VPS / SPS / PPS changed, now {} VPS, {} SPS and {} PPS",
+                state.vps.len(),
+                state.sps.len(),
+                state.pps.len(),
+            );
+            self.update_src_caps(&state);
+        }
+
+        let has_config = has_vps && has_sps && has_pps;
+        let pts = buffer.pts();
+        let insert_config = is_keyframe
+            && !has_config
+            && match settings.config_interval {
+                0 => false,
+                -1 => true,
+                interval => state
+                    .last_config_pts
+                    .zip(pts)
+                    .and_then(|(last, pts)| pts.checked_sub(last))
+                    .map_or(true, |diff| {
+                        diff >= gst::ClockTime::from_seconds(interval as u64)
+                    }),
+            };
+        let config = if insert_config && has_parameter_sets(&state) {
+            Some(
+                state
+                    .vps
+                    .iter()
+                    .chain(state.sps.iter())
+                    .chain(state.pps.iter())
+                    .map(<[u8]>::to_vec)
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            None
+        };
+        if config.is_some() || has_config {
+            state.last_config_pts = pts;
+        }
+
+        let mut nals = SmallVec::<[&[u8]; 8]>::with_capacity(
+            nal_units.len() + config.as_ref().map_or(0, Vec::len),
+        );
+        nals.extend_from_slice(&nal_units);
+        if let Some(ref config) = config {
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Inserting VPS / SPS / PPS before IRAP frame"
+            );
+
+            // Parameter sets have to come after the access unit delimiter, if any
+            let pos = if nal_unit_type(nals[0][0]) == nal_type::AUD {
+                1
+            } else {
+                0
+            };
+            nals.insert_many(pos, config.iter().map(Vec::as_slice));
+        }
+
+        // NAL units are sent in decoding order so their DONs are consecutive
+        let first_don = state.don;
+        state.don = state.don.wrapping_add(nals.len() as u16);
+        drop(state);
+        let don =
+            |idx: usize| (settings.max_don_diff > 0).then(|| first_don.wrapping_add(idx as u16));
+
+        let mut aggregate = SmallVec::<[&[u8]; 8]>::new();
+        let mut aggregate_don = None;
+        // AP header plus all NAL units with their DONL / DOND and size fields
+        let mut aggregate_size = 0;
+        for (idx, nal) in nals.iter().enumerate() {
+            let marker = idx == nals.len() - 1;
+
+            if settings.aggregate_mode == AggregateMode::ZeroLatency {
+                // DOND for all but the first NAL unit
+                let dond_size = donl_size / 2;
+                if !aggregate.is_empty()
+                    && aggregate_size + dond_size + 2 + nal.len() > max_payload_size
+                {
+                    self.queue_aggregate(&aggregate, aggregate_don, id, false)?;
+                    aggregate.clear();
+                }
+
+                let nal_size = if aggregate.is_empty() {
+                    aggregate_size = 2;
+                    aggregate_don = don(idx);
+                    donl_size + 2 + nal.len()
+                } else {
+                    dond_size + 2 + nal.len()
+                };
+
+                if aggregate_size + nal_size <= max_payload_size {
+                    aggregate.push(nal);
+                    aggregate_size += nal_size;
+
+                    if marker {
+                        self.queue_aggregate(&aggregate, aggregate_don, id, true)?;
+                        aggregate.clear();
+                    }
+                    continue;
+                }
+            }
+
+            self.queue_nal(nal, don(idx), max_payload_size, id, marker)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+impl RtpH265Pay {
+    fn update_src_caps(&self, state: &State) {
+        let mut caps_builder = gst::Caps::builder("application/x-rtp")
+            .field("media", "video")
+            .field("clock-rate", 90_000i32)
+            .field("encoding-name", "H265");
+
+        if let Some(sps) = state.sps.iter().next() {
+            // profile_tier_level() follows the 2 byte NAL unit header and one byte with the VPS
+            // id, max sub layers and temporal id nesting flag.
+            let rbsp = remove_emulation_prevention(&sps[2..]);
+            if let Some(ptl) = rbsp.get(1..13) {
+                caps_builder = caps_builder
+                    .field("profile-id", (ptl[0] & 0x1f).to_string())
+                    .field("tier-flag", ((ptl[0] >> 5) & 0x01).to_string())
+                    .field("level-id", ptl[11].to_string());
+            }
+        }
+
+        if has_parameter_sets(state) {
+            caps_builder = caps_builder
+                .field("sprop-vps", format_sprop(state.vps.iter()))
+                .field("sprop-sps", format_sprop(state.sps.iter()))
+                .field("sprop-pps", format_sprop(state.pps.iter()));
+        }
+
+        let max_don_diff = self.settings.lock().unwrap().max_don_diff;
+        if max_don_diff > 0 {
+            caps_builder = caps_builder.field("sprop-max-don-diff", max_don_diff.to_string());
+        }
+
+        self.obj().set_src_caps(&caps_builder.build());
+    }
+
+    /// Stores the VPS, SPS or PPS `nal` and returns `true` if this changed the parameter sets.
+    fn store_parameter_set(&self, state: &mut State, nal: &[u8]) -> bool {
+        let Some(id) = parameter_set_id(nal) else {
+            gst::warning!(CAT, imp = self, "Failed to parse parameter set id");
+            return false;
+        };
+
+        let parameter_sets = match nal_unit_type(nal[0]) {
+            nal_type::VPS => &mut state.vps,
+            nal_type::SPS => &mut state.sps,
+            _ => &mut state.pps,
+        };
+
+        parameter_sets.insert(id, nal)
+    }
+
+    /// Queues the given NAL units either as a Single NAL unit packet or as an AP packet.
+    ///
+    /// If `don` is set it is the decoding order number of the first NAL unit and the following
+    /// NAL units are expected to have consecutive decoding order numbers.
+    fn queue_aggregate(
+        &self,
+        nals: &[&[u8]],
+        don: Option<u16>,
+        id: u64,
+        marker: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        assert!(!nals.is_empty());
+
+        let donl = don.map(u16::to_be_bytes);
+
+        if nals.len() == 1 {
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Writing single NAL unit packet of size {}",
+                nals[0].len()
+            );
+
+            let builder = rtp_types::RtpPacketBuilder::new().marker_bit(marker);
+            let builder = match donl {
+                Some(ref donl) => builder
+                    .payload(&nals[0][..2])
+                    .payload(donl.as_slice())
+                    .payload(&nals[0][2..]),
+                None => builder.payload(nals[0]),
+            };
+
+            return self.obj().queue_packet(id.into(), builder);
+        }
+
+        // F bit is set if any NAL unit has it set, LayerId and TID are the lowest of all NAL units
+        let f = nals.iter().fold(0, |acc, nal| acc | (nal[0] & 0x80));
+        let layer_id = nals
+            .iter()
+            .map(|nal| ((nal[0] & 0x01) << 5) | (nal[1] >> 3))
+            .min()
+            .unwrap();
+        let tid = nals.iter().map(|nal| nal[1] & 0x07).min().unwrap();
+        let header = [
+            f | (nal_type::AP << 1) | (layer_id >> 5),
+            ((layer_id & 0x1f) << 3) | tid,
+        ];
+        let sizes = nals
+            .iter()
+            .map(|nal| (nal.len() as u16).to_be_bytes())
+            .collect::<SmallVec<[[u8; 2]; 8]>>();
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Writing AP packet with {} NAL units",
+            nals.len()
+        );
+
+        let mut builder = rtp_types::RtpPacketBuilder::new()
+            .marker_bit(marker)
+            .payload(header.as_slice());
+        if let Some(ref donl) = donl {
+            builder = builder.payload(donl.as_slice());
+        }
+        // DOND is the DON difference minus 1, i.e. 0 for consecutive NAL units
+        let dond = [0u8];
+        for (idx, (nal, size)) in Iterator::zip(nals.iter(), sizes.iter()).enumerate() {
+            if donl.is_some() && idx > 0 {
+                builder = builder.payload(dond.as_slice());
+            }
+            builder = builder.payload(size.as_slice()).payload(*nal);
+        }
+
+        self.obj().queue_packet(id.into(), builder)
+    }
+
+    /// Queues a single NAL unit, fragmenting it into FU packets if necessary.
+    fn queue_nal(
+        &self,
+        nal: &[u8],
+        don: Option<u16>,
+        max_payload_size: usize,
+        id: u64,
+        marker: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let donl = don.map(u16::to_be_bytes);
+        let donl_size = donl.map_or(0, |donl| donl.len());
+
+        if donl_size + nal.len() <= max_payload_size {
+            return self.queue_aggregate(&[nal], don, id, marker);
+        }
+
+        // Same header as the NAL unit, only with the type replaced
+        let payload_header = [(nal[0] & 0x81) | (nal_type::FU << 1), nal[1]];
+        let nal_unit_type = nal_unit_type(nal[0]);
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Fragmenting NAL unit of size {} into FU packets",
+            nal.len()
+        );
+
+        let mut data = &nal[2..];
+        let mut first = true;
+        while !data.is_empty() {
+            // Only the first fragment carries the DONL field
+            let donl = donl.as_ref().filter(|_| first);
+            let header_size = 3 + donl.map_or(0, |donl| donl.len());
+            let fragment_size = cmp::min(max_payload_size - header_size, data.len());
+            let last = fragment_size == data.len();
+
+            let fu_header = [((first as u8) << 7) | ((last as u8) << 6) | nal_unit_type];
+
+            let mut builder = rtp_types::RtpPacketBuilder::new()
+                .marker_bit(marker && last)
+                .payload(payload_header.as_slice())
+                .payload(fu_header.as_slice());
+            if let Some(donl) = donl {
+                builder = builder.payload(donl.as_slice());
+            }
+
+            self.obj()
+                .queue_packet(id.into(), builder.payload(&data[..fragment_size]))?;
+
+            data = &data[fragment_size..];
+            first = false;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+/// Returns `true` if at least one VPS, SPS and PPS is known.
+fn has_parameter_sets(state: &State) -> bool {
+    !state.vps.is_empty() && !state.sps.is_empty() && !state.pps.is_empty()
+}
+
+struct HevcDecoderConfiguration {
+    nal_length_size: usize,
+    vps: Vec<Vec<u8>>,
+    sps: Vec<Vec<u8>>,
+    pps: Vec<Vec<u8>>,
+}
+
+/// Parses an `HEVCDecoderConfigurationRecord` as defined in ISO/IEC 14496-15.
+fn parse_hvcc(data: &[u8]) -> Option<HevcDecoderConfiguration> {
+    if data.len() < 23 || data[0] != 1 {
+        return None;
+    }
+
+    let mut config = HevcDecoderConfiguration {
+        nal_length_size: (data[21] & 0x03) as usize + 1,
+        vps: Vec::new(),
+        sps: Vec::new(),
+        pps: Vec::new(),
+    };
+
+    let num_arrays = data[22];
+    let mut data = &data[23..];
+    for _ in 0..num_arrays {
+        let [array_type, n0, n1, rest @ ..] = data else {
+            return None;
+        };
+        let num_nalus = u16::from_be_bytes([*n0, *n1]);
+        data = rest;
+
+        for _ in 0..num_nalus {
+            let [s0, s1, rest @ ..] = data else {
+                return None;
+            };
+            let size = u16::from_be_bytes([*s0, *s1]) as usize;
+            if rest.len() < size {
+                return None;
+            }
+
+            let nal = rest[..size].to_vec();
+            match array_type & 0x3f {
+                nal_type::VPS => config.vps.push(nal),
+                nal_type::SPS => config.sps.push(nal),
+                nal_type::PPS => config.pps.push(nal),
+                _ => (),
+            }
+            data = &rest[size..];
+        }
+    }
+
+    Some(config)
+}
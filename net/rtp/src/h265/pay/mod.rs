@@ -0,0 +1,49 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+
+pub mod imp;
+
+glib::wrapper! {
+    pub struct RtpH265Pay(ObjectSubclass<imp::RtpH265Pay>)
+        @extends crate::basepay::RtpBasePay2, gst::Element, gst::Object;
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    #[cfg(feature = "doc")]
+    {
+        AggregateMode::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    }
+
+    gst::Element::register(
+        Some(plugin),
+        "rtph265pay2",
+        gst::Rank::MARGINAL,
+        RtpH265Pay::static_type(),
+    )
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, glib::Enum, Default)]
+#[enum_type(name = "GstRtpH265Pay2AggregateMode")]
+#[repr(i32)]
+pub enum AggregateMode {
+    #[enum_value(
+        name = "Send every NAL unit in its own packet or fragments",
+        nick = "none"
+    )]
+    None,
+    #[default]
+    #[enum_value(
+        name = "Aggregate NAL units of the same access unit into AP packets",
+        nick = "zero-latency"
+    )]
+    ZeroLatency,
+}
@@ -0,0 +1,409 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::tests::{run_test_pipeline, ExpectedBuffer, ExpectedPacket, Source};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtph265 test");
+    });
+}
+
+const VPS: &[u8] = &[0x40, 0x01, 0x0c, 0x01, 0xff, 0xff];
+const SPS: &[u8] = &[
+    0x42, 0x01, 0x01, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03,
+    0x00, 0x5d, 0xa0,
+];
+const PPS: &[u8] = &[0x44, 0x01, 0xc1, 0x72];
+
+/// NAL unit header of an IDR_W_RADL NAL unit.
+const IDR: [u8; 2] = [0x26, 0x01];
+/// NAL unit header of a TRAIL_R NAL unit.
+const TRAIL: [u8; 2] = [0x02, 0x01];
+
+/// Creates a NAL unit with the given header and size that contains no start code emulation.
+fn nal(header: [u8; 2], size: usize) -> Vec<u8> {
+    let mut nal = vec![0x11; size];
+    nal[..2].copy_from_slice(&header);
+    nal
+}
+
+fn byte_stream_au(nals: &[&[u8]]) -> Vec<u8> {
+    nals.iter()
+        .flat_map(|nal| [0x00, 0x00, 0x00, 0x01].iter().chain(nal.iter()))
+        .copied()
+        .collect()
+}
+
+fn hvc1_au(nals: &[&[u8]]) -> Vec<u8> {
+    nals.iter()
+        .flat_map(|nal| {
+            (nal.len() as u32)
+                .to_be_bytes()
+                .into_iter()
+                .chain(nal.iter().copied())
+        })
+        .collect()
+}
+
+fn make_buffers(aus: Vec<Vec<u8>>) -> Vec<gst::Buffer> {
+    aus.into_iter()
+        .enumerate()
+        .map(|(i, au)| {
+            let mut buffer = gst::Buffer::from_mut_slice(au);
+            {
+                let buffer = buffer.get_mut().unwrap();
+                buffer.set_pts(gst::ClockTime::from_mseconds(i as u64 * 40));
+                if i == 0 {
+                    buffer.set_flags(gst::BufferFlags::DISCONT);
+                }
+            }
+            buffer
+        })
+        .collect()
+}
+
+#[test]
+fn test_h265() {
+    init();
+
+    let idr = nal(IDR, 3000);
+    let trail = nal(TRAIL, 100);
+    let idr_without_parameter_sets = nal(IDR, 1500);
+
+    let caps = gst::Caps::builder("video/x-h265")
+        .field("stream-format", "byte-stream")
+        .field("alignment", "au")
+        .build();
+    let buffers = make_buffers(vec![
+        byte_stream_au(&[VPS, SPS, PPS, &idr]),
+        byte_stream_au(&[&trail]),
+        byte_stream_au(&[&idr_without_parameter_sets]),
+    ]);
+
+    let pay = "rtph265pay2";
+    let depay = "rtph265depay2";
+
+    let expected_pay = vec![
+        vec![
+            // VPS, SPS and PPS are aggregated into an AP packet
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::DISCONT)
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(12 + 2 + 2 + VPS.len() + 2 + SPS.len() + 2 + PPS.len())
+                .build(),
+            // IDR is fragmented into three FU packets
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(0)
+                .marker_bit(true)
+                .size(12 + 3 + 2998 - 2 * 1385)
+                .build(),
+        ],
+        // Single NAL unit packet
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 100)
+            .build()],
+        vec![
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(80))
+                .rtp_time(7_200)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(80))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(7_200)
+                .marker_bit(true)
+                .size(12 + 3 + 1498 - 1385)
+                .build(),
+        ],
+    ];
+
+    let parameter_sets_size = 4 + VPS.len() + 4 + SPS.len() + 4 + PPS.len();
+    let expected_depay = vec![
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(0))
+            .size(parameter_sets_size + 4 + 3000)
+            .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .size(4 + 100)
+            .flags(gst::BufferFlags::MARKER | gst::BufferFlags::DELTA_UNIT)
+            .build()],
+        // VPS, SPS and PPS from the caps are inserted before the IRAP frame
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .size(parameter_sets_size + 4 + 1500)
+            .flags(gst::BufferFlags::MARKER)
+            .build()],
+    ];
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_h265_parameter_set_id() {
+    assert_eq!(super::parameter_set_id(VPS), Some(0));
+    assert_eq!(super::parameter_set_id(SPS), Some(0));
+    assert_eq!(super::parameter_set_id(PPS), Some(0));
+    assert_eq!(super::parameter_set_id(&[0x44, 0x01, 0x41, 0x72]), Some(1));
+
+    // SPS with two sub layers, where the second one has a level but no profile
+    let mut sps = vec![0x42, 0x01, 0x03];
+    sps.extend_from_slice(&[0x11; 12]);
+    sps.extend_from_slice(&[0x40, 0x00, 0x5d, 0x40]);
+    assert_eq!(super::parameter_set_id(&sps), Some(1));
+}
+
+#[test]
+fn test_h265_donl() {
+    init();
+
+    let idr = nal(IDR, 3000);
+    let trail = nal(TRAIL, 100);
+    let idr_without_parameter_sets = nal(IDR, 1500);
+
+    let caps = gst::Caps::builder("video/x-h265")
+        .field("stream-format", "byte-stream")
+        .field("alignment", "au")
+        .build();
+    let buffers = make_buffers(vec![
+        byte_stream_au(&[VPS, SPS, PPS, &idr]),
+        byte_stream_au(&[&trail]),
+        byte_stream_au(&[&idr_without_parameter_sets]),
+    ]);
+
+    let pay = "rtph265pay2 max-don-diff=1";
+    let depay = "rtph265depay2";
+
+    let expected_pay = vec![
+        vec![
+            // DONL before the first and DOND before the other NAL units of the AP packet
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::DISCONT)
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(12 + 2 + 2 + 2 + VPS.len() + 1 + 2 + SPS.len() + 1 + 2 + PPS.len())
+                .build(),
+            // Only the first FU packet has a DONL field
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(0)
+                .marker_bit(true)
+                .size(12 + 3 + 2998 - 1383 - 1385)
+                .build(),
+        ],
+        // Single NAL unit packet with DONL field
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 2 + 2 + 98)
+            .build()],
+        vec![
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(80))
+                .rtp_time(7_200)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(80))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(7_200)
+                .marker_bit(true)
+                .size(12 + 3 + 1498 - 1383)
+                .build(),
+        ],
+    ];
+
+    // The depayloader strips the DONL / DOND fields again
+    let parameter_sets_size = 4 + VPS.len() + 4 + SPS.len() + 4 + PPS.len();
+    let expected_depay = vec![
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(0))
+            .size(parameter_sets_size + 4 + 3000)
+            .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .size(4 + 100)
+            .flags(gst::BufferFlags::MARKER | gst::BufferFlags::DELTA_UNIT)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .size(parameter_sets_size + 4 + 1500)
+            .flags(gst::BufferFlags::MARKER)
+            .build()],
+    ];
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_h265_hvc1_config_interval() {
+    init();
+
+    let idr = nal(IDR, 500);
+    let trail = nal(TRAIL, 50);
+
+    let mut codec_data = vec![0u8; 23];
+    codec_data[0] = 1;
+    // 4 byte NAL unit lengths
+    codec_data[21] = 0x03;
+    // VPS, SPS and PPS arrays
+    codec_data[22] = 3;
+    for (array_type, nal) in [(0x20u8, VPS), (0x21, SPS), (0x22, PPS)] {
+        codec_data.push(array_type);
+        codec_data.extend_from_slice(&1u16.to_be_bytes());
+        codec_data.extend_from_slice(&(nal.len() as u16).to_be_bytes());
+        codec_data.extend_from_slice(nal);
+    }
+
+    let caps = gst::Caps::builder("video/x-h265")
+        .field("stream-format", "hvc1")
+        .field("alignment", "au")
+        .field("codec_data", gst::Buffer::from_mut_slice(codec_data))
+        .build();
+    let buffers = make_buffers(vec![hvc1_au(&[&idr]), hvc1_au(&[&trail]), hvc1_au(&[&idr])]);
+
+    let pay = "rtph265pay2 aggregate-mode=none config-interval=-1";
+    let depay = "rtph265depay2";
+
+    let keyframe_packets = |pts: gst::ClockTime, rtp_time: u32, flags: gst::BufferFlags| {
+        vec![
+            ExpectedPacket::builder()
+                .pts(pts)
+                .flags(flags)
+                .rtp_time(rtp_time)
+                .marker_bit(false)
+                .size(12 + VPS.len())
+                .build(),
+            ExpectedPacket::builder()
+                .pts(pts)
+                .rtp_time(rtp_time)
+                .marker_bit(false)
+                .size(12 + SPS.len())
+                .build(),
+            ExpectedPacket::builder()
+                .pts(pts)
+                .rtp_time(rtp_time)
+                .marker_bit(false)
+                .size(12 + PPS.len())
+                .build(),
+            ExpectedPacket::builder()
+                .pts(pts)
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(rtp_time)
+                .marker_bit(true)
+                .size(12 + 500)
+                .build(),
+        ]
+    };
+
+    let expected_pay = vec![
+        keyframe_packets(
+            gst::ClockTime::from_mseconds(0),
+            0,
+            gst::BufferFlags::DISCONT,
+        ),
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 50)
+            .build()],
+        keyframe_packets(
+            gst::ClockTime::from_mseconds(80),
+            7_200,
+            gst::BufferFlags::empty(),
+        ),
+    ];
+
+    let parameter_sets_size = 4 + VPS.len() + 4 + SPS.len() + 4 + PPS.len();
+    let expected_depay = vec![
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(0))
+            .size(parameter_sets_size + 4 + 500)
+            .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .size(4 + 50)
+            .flags(gst::BufferFlags::MARKER | gst::BufferFlags::DELTA_UNIT)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .size(parameter_sets_size + 4 + 500)
+            .flags(gst::BufferFlags::MARKER)
+            .build()],
+    ];
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
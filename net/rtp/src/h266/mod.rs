@@ -0,0 +1,41 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+pub mod depay;
+pub mod pay;
+
+#[cfg(test)]
+mod tests;
+
+/// NAL unit types that are relevant for payloading and depayloading.
+pub(crate) mod nal_type {
+    pub const IDR_W_RADL: u8 = 7;
+    pub const CRA_NUT: u8 = 9;
+    pub const VPS: u8 = 14;
+    pub const SPS: u8 = 15;
+    pub const PPS: u8 = 16;
+    pub const AUD: u8 = 20;
+    pub const AP: u8 = 28;
+    pub const FU: u8 = 29;
+}
+
+/// Returns the NAL unit type from the second byte of the NAL unit header.
+pub(crate) fn nal_unit_type(nal_header: u8) -> u8 {
+    nal_header >> 3
+}
+
+/// Returns `true` if the NAL unit type is an IRAP picture, i.e. a keyframe.
+pub(crate) fn is_irap(nal_unit_type: u8) -> bool {
+    (nal_type::IDR_W_RADL..=nal_type::CRA_NUT).contains(&nal_unit_type)
+}
+
+/// Returns `true` if the NAL unit type is a VCL NAL unit, i.e. contains slice data.
+pub(crate) fn is_vcl(nal_unit_type: u8) -> bool {
+    nal_unit_type <= 11
+}
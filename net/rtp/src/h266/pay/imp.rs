@@ -0,0 +1,530 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtph266pay2
+ * @see_also: rtph266depay2, h266parse, vvdec
+ *
+ * Payload an H.266 video stream into RTP packets as per [RFC 9328][rfc-9328].
+ *
+ * NAL units of an access unit are sent in Single NAL unit packets, aggregated into AP packets or
+ * fragmented into FU packets, and the marker bit is set on the last packet of each access unit.
+ * Decoding order numbers are not used, i.e. `sprop-max-don-diff` is always 0.
+ *
+ * The VPS, SPS and PPS are signalled in the `sprop-vps`, `sprop-sps` and `sprop-pps` caps fields
+ * and can additionally be inserted in-band before IRAP frames with the
+ * #rtph266pay2:config-interval property. The VPS is optional for H.266 streams.
+ *
+ * [rfc-9328]: https://www.rfc-editor.org/rfc/rfc9328.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 filesrc location=test.266 ! h266parse ! video/x-h266,stream-format=byte-stream,alignment=au ! rtph266pay2 config-interval=-1 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will payload an H.266 video stream from a file and send it out via UDP to localhost
+ * port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+use smallvec::SmallVec;
+use std::{cmp, sync::Mutex};
+
+use std::sync::LazyLock;
+
+use crate::{
+    basepay::RtpBasePay2Ext,
+    h266::{is_irap, is_vcl, nal_type, nal_unit_type, pay::AggregateMode},
+    h26x::{format_sprop, remove_emulation_prevention, split_nal_units, NalFormat},
+};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtph266pay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP H.266 Payloader"),
+    )
+});
+
+const DEFAULT_CONFIG_INTERVAL: i32 = 0;
+
+#[derive(Clone)]
+struct Settings {
+    aggregate_mode: AggregateMode,
+    config_interval: i32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            aggregate_mode: AggregateMode::default(),
+            config_interval: DEFAULT_CONFIG_INTERVAL,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Last VPS, SPS and PPS from the stream.
+    vps: Option<Vec<u8>>,
+    sps: Option<Vec<u8>>,
+    pps: Option<Vec<u8>>,
+    /// PTS of the last access unit that contained SPS and PPS.
+    last_config_pts: Option<gst::ClockTime>,
+}
+
+#[derive(Default)]
+pub struct RtpH266Pay {
+    settings: Mutex<Settings>,
+    state: AtomicRefCell<State>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpH266Pay {
+    const NAME: &'static str = "GstRtpH266Pay2";
+    type Type = super::RtpH266Pay;
+    type ParentType = crate::basepay::RtpBasePay2;
+}
+
+impl ObjectImpl for RtpH266Pay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecEnum::builder::<AggregateMode>("aggregate-mode")
+                    .nick("Aggregate Mode")
+                    .blurb("Whether to aggregate NAL units of an access unit into AP packets")
+                    .default_value(Settings::default().aggregate_mode)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecInt::builder("config-interval")
+                    .nick("Config Interval")
+                    .blurb("Send VPS, SPS and PPS in-band before IRAP frames at this interval in seconds (0 = disabled, -1 = with every IRAP frame)")
+                    .default_value(DEFAULT_CONFIG_INTERVAL)
+                    .minimum(-1)
+                    .maximum(3600)
+                    .mutable_playing()
+                    .build(),
+            ]
+        });
+
+        &PROPERTIES
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "aggregate-mode" => {
+                self.settings.lock().unwrap().aggregate_mode = value.get().unwrap();
+            }
+            "config-interval" => {
+                self.settings.lock().unwrap().config_interval = value.get().unwrap();
+            }
+            _ => unimplemented!(),
+        };
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "aggregate-mode" => self.settings.lock().unwrap().aggregate_mode.to_value(),
+            "config-interval" => self.settings.lock().unwrap().config_interval.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpH266Pay {}
+
+impl ElementImpl for RtpH266Pay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP H.266 payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload H.266 as RTP packets",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("video/x-h266")
+                    .field("stream-format", "byte-stream")
+                    .field("alignment", "au")
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("clock-rate", 90_000i32)
+                    .field("encoding-name", "H266")
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpH266Pay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["video"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        gst::debug!(CAT, imp = self, "received caps {caps:?}");
+
+        let state = self.state.borrow();
+        self.update_src_caps(&state);
+
+        true
+    }
+
+    fn handle_buffer(
+        &self,
+        buffer: &gst::Buffer,
+        id: u64,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let mut state = self.state.borrow_mut();
+
+        let max_payload_size = self.obj().max_payload_size() as usize;
+        // Need space for at least the FU payload and FU header plus one byte of payload.
+        if max_payload_size < 4 {
+            gst::error!(CAT, imp = self, "Too small MTU configured for stream");
+            gst::element_imp_error!(
+                self,
+                gst::LibraryError::Settings,
+                ["Too small MTU configured for stream"]
+            );
+            return Err(gst::FlowError::Error);
+        }
+
+        gst::trace!(CAT, imp = self, "received buffer of size {}", buffer.size());
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::element_imp_error!(
+                self,
+                gst::ResourceError::Read,
+                ["Failed to map buffer readable"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        let mut nal_units = split_nal_units(&map, NalFormat::ByteStream).map_err(|err| {
+            gst::element_imp_error!(
+                self,
+                gst::StreamError::Format,
+                ["Failed to parse NAL units: {err}"]
+            );
+
+            gst::FlowError::Error
+        })?;
+
+        // Every NAL unit has a 2 byte header
+        nal_units.retain(|nal| {
+            if nal.len() < 2 {
+                gst::warning!(CAT, imp = self, "Dropping too short NAL unit");
+                return false;
+            }
+            true
+        });
+
+        if nal_units.is_empty() {
+            gst::warning!(CAT, imp = self, "Access unit without NAL units");
+            self.obj().drop_buffers(..=id);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let mut has_sps = false;
+        let mut has_pps = false;
+        let mut is_keyframe = false;
+        let mut config_changed = false;
+        for nal in &nal_units {
+            let param_set = match nal_unit_type(nal[1]) {
+                nal_type::VPS => &mut state.vps,
+                nal_type::SPS => {
+                    has_sps = true;
+                    &mut state.sps
+                }
+                nal_type::PPS => {
+                    has_pps = true;
+                    &mut state.pps
+                }
+                t if is_irap(t) => {
+                    is_keyframe = true;
+                    continue;
+                }
+                _ => continue,
+            };
+
+            if param_set.as_deref() != Some(*nal) {
+                *param_set = Some(nal.to_vec());
+                config_changed = true;
+            }
+        }
+
+        if config_changed {
+            gst::debug!(CAT, imp = self, "VPS / SPS / PPS changed");
+            self.update_src_caps(&state);
+        }
+
+        let has_config = has_sps && has_pps;
+        let pts = buffer.pts();
+        let insert_config = is_keyframe
+            && !has_config
+            && match settings.config_interval {
+                0 => false,
+                -1 => true,
+                interval => state
+                    .last_config_pts
+                    .zip(pts)
+                    .and_then(|(last, pts)| pts.checked_sub(last))
+                    .map_or(true, |diff| {
+                        diff >= gst::ClockTime::from_seconds(interval as u64)
+                    }),
+            };
+        let config = if insert_config {
+            match (&state.sps, &state.pps) {
+                (Some(sps), Some(pps)) => Some(
+                    state
+                        .vps
+                        .iter()
+                        .chain([sps, pps])
+                        .cloned()
+                        .collect::<SmallVec<[Vec<u8>; 3]>>(),
+                ),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        if config.is_some() || has_config {
+            state.last_config_pts = pts;
+        }
+        drop(state);
+
+        let mut nals = SmallVec::<[&[u8]; 8]>::with_capacity(nal_units.len() + 3);
+        nals.extend_from_slice(&nal_units);
+        if let Some(ref config) = config {
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Inserting VPS / SPS / PPS before IRAP frame"
+            );
+
+            // Parameter sets have to come after the access unit delimiter, if any
+            let pos = if nal_unit_type(nals[0][1]) == nal_type::AUD {
+                1
+            } else {
+                0
+            };
+            nals.insert_many(pos, config.iter().map(Vec::as_slice));
+        }
+
+        // The P bit of the FU header is set for the last VCL NAL unit of the picture
+        let last_vcl_idx = nals.iter().rposition(|nal| is_vcl(nal_unit_type(nal[1])));
+
+        let mut aggregate = SmallVec::<[&[u8]; 8]>::new();
+        // AP header plus all NAL units with their size fields
+        let mut aggregate_size = 0;
+        for (idx, nal) in nals.iter().enumerate() {
+            let marker = idx == nals.len() - 1;
+
+            if settings.aggregate_mode == AggregateMode::ZeroLatency {
+                if !aggregate.is_empty() && aggregate_size + 2 + nal.len() > max_payload_size {
+                    self.queue_aggregate(&aggregate, id, false)?;
+                    aggregate.clear();
+                }
+
+                if aggregate.is_empty() {
+                    aggregate_size = 2;
+                }
+
+                if aggregate_size + 2 + nal.len() <= max_payload_size {
+                    aggregate.push(nal);
+                    aggregate_size += 2 + nal.len();
+
+                    if marker {
+                        self.queue_aggregate(&aggregate, id, true)?;
+                        aggregate.clear();
+                    }
+                    continue;
+                }
+            }
+
+            self.queue_nal(nal, max_payload_size, id, marker, Some(idx) == last_vcl_idx)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+impl RtpH266Pay {
+    fn update_src_caps(&self, state: &State) {
+        let mut caps_builder = gst::Caps::builder("application/x-rtp")
+            .field("media", "video")
+            .field("clock-rate", 90_000i32)
+            .field("encoding-name", "H266");
+
+        if let Some(ref sps) = state.sps {
+            // The SPS starts with 2 bytes containing the SPS and VPS ids, max sub layers,
+            // chroma format, CTU size and whether profile_tier_level() follows.
+            let rbsp = remove_emulation_prevention(&sps[2..]);
+            if let Some(ptl) = rbsp
+                .get(..4)
+                .filter(|rbsp| rbsp[1] & 0x01 != 0)
+                .map(|rbsp| &rbsp[2..])
+            {
+                caps_builder = caps_builder
+                    .field("profile-id", (ptl[0] >> 1).to_string())
+                    .field("tier-flag", (ptl[0] & 0x01).to_string())
+                    .field("level-id", ptl[1].to_string());
+            }
+        }
+
+        if let (Some(sps), Some(pps)) = (&state.sps, &state.pps) {
+            if let Some(ref vps) = state.vps {
+                caps_builder = caps_builder.field("sprop-vps", format_sprop([vps.as_slice()]));
+            }
+            caps_builder = caps_builder
+                .field("sprop-sps", format_sprop([sps.as_slice()]))
+                .field("sprop-pps", format_sprop([pps.as_slice()]));
+        }
+
+        self.obj().set_src_caps(&caps_builder.build());
+    }
+
+    /// Queues the given NAL units either as a Single NAL unit packet or as an AP packet.
+    fn queue_aggregate(
+        &self,
+        nals: &[&[u8]],
+        id: u64,
+        marker: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        assert!(!nals.is_empty());
+
+        if nals.len() == 1 {
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Writing single NAL unit packet of size {}",
+                nals[0].len()
+            );
+            return self.obj().queue_packet(
+                id.into(),
+                rtp_types::RtpPacketBuilder::new()
+                    .marker_bit(marker)
+                    .payload(nals[0]),
+            );
+        }
+
+        // F bit is set if any NAL unit has it set, LayerId and TID are the lowest of all NAL units
+        let f = nals.iter().fold(0, |acc, nal| acc | (nal[0] & 0x80));
+        let layer_id = nals.iter().map(|nal| nal[0] & 0x3f).min().unwrap();
+        let tid = nals.iter().map(|nal| nal[1] & 0x07).min().unwrap();
+        let header = [f | layer_id, (nal_type::AP << 3) | tid];
+        let sizes = nals
+            .iter()
+            .map(|nal| (nal.len() as u16).to_be_bytes())
+            .collect::<SmallVec<[[u8; 2]; 8]>>();
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Writing AP packet with {} NAL units",
+            nals.len()
+        );
+
+        let mut builder = rtp_types::RtpPacketBuilder::new()
+            .marker_bit(marker)
+            .payload(header.as_slice());
+        for (nal, size) in Iterator::zip(nals.iter(), sizes.iter()) {
+            builder = builder.payload(size.as_slice()).payload(*nal);
+        }
+
+        self.obj().queue_packet(id.into(), builder)
+    }
+
+    /// Queues a single NAL unit, fragmenting it into FU packets if necessary.
+    fn queue_nal(
+        &self,
+        nal: &[u8],
+        max_payload_size: usize,
+        id: u64,
+        marker: bool,
+        last_vcl: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        if nal.len() <= max_payload_size {
+            return self.queue_aggregate(&[nal], id, marker);
+        }
+
+        // Same header as the NAL unit, only with the type replaced
+        let payload_header = [nal[0], (nal_type::FU << 3) | (nal[1] & 0x07)];
+        let nal_unit_type = nal_unit_type(nal[1]);
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Fragmenting NAL unit of size {} into FU packets",
+            nal.len()
+        );
+
+        let mut data = &nal[2..];
+        let mut first = true;
+        while !data.is_empty() {
+            let fragment_size = cmp::min(max_payload_size - 3, data.len());
+            let last = fragment_size == data.len();
+
+            let fu_header = [((first as u8) << 7)
+                | ((last as u8) << 6)
+                | (((last && last_vcl) as u8) << 5)
+                | nal_unit_type];
+
+            self.obj().queue_packet(
+                id.into(),
+                rtp_types::RtpPacketBuilder::new()
+                    .marker_bit(marker && last)
+                    .payload(payload_header.as_slice())
+                    .payload(fu_header.as_slice())
+                    .payload(&data[..fragment_size]),
+            )?;
+
+            data = &data[fragment_size..];
+            first = false;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
@@ -0,0 +1,259 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::tests::{run_test_pipeline, ExpectedBuffer, ExpectedPacket, Source};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtph266 test");
+    });
+}
+
+const VPS: &[u8] = &[0x00, 0x71, 0x11, 0x11];
+const SPS: &[u8] = &[0x00, 0x79, 0x00, 0x8d, 0x02, 0x33, 0x11, 0x11];
+const PPS: &[u8] = &[0x00, 0x81, 0x11, 0x11];
+
+/// NAL unit header of an IDR_W_RADL NAL unit.
+const IDR: [u8; 2] = [0x00, 0x39];
+/// NAL unit header of a TRAIL NAL unit.
+const TRAIL: [u8; 2] = [0x00, 0x01];
+
+/// Creates a NAL unit with the given header and size that contains no start code emulation.
+fn nal(header: [u8; 2], size: usize) -> Vec<u8> {
+    let mut nal = vec![0x11; size];
+    nal[..2].copy_from_slice(&header);
+    nal
+}
+
+fn byte_stream_au(nals: &[&[u8]]) -> Vec<u8> {
+    nals.iter()
+        .flat_map(|nal| [0x00, 0x00, 0x00, 0x01].iter().chain(nal.iter()))
+        .copied()
+        .collect()
+}
+
+fn make_buffers(aus: Vec<Vec<u8>>) -> Vec<gst::Buffer> {
+    aus.into_iter()
+        .enumerate()
+        .map(|(i, au)| {
+            let mut buffer = gst::Buffer::from_mut_slice(au);
+            {
+                let buffer = buffer.get_mut().unwrap();
+                buffer.set_pts(gst::ClockTime::from_mseconds(i as u64 * 40));
+                if i == 0 {
+                    buffer.set_flags(gst::BufferFlags::DISCONT);
+                }
+            }
+            buffer
+        })
+        .collect()
+}
+
+fn caps() -> gst::Caps {
+    gst::Caps::builder("video/x-h266")
+        .field("stream-format", "byte-stream")
+        .field("alignment", "au")
+        .build()
+}
+
+#[test]
+fn test_h266() {
+    init();
+
+    let idr = nal(IDR, 3000);
+    let trail = nal(TRAIL, 100);
+    let idr_without_parameter_sets = nal(IDR, 1500);
+
+    let buffers = make_buffers(vec![
+        byte_stream_au(&[VPS, SPS, PPS, &idr]),
+        byte_stream_au(&[&trail]),
+        byte_stream_au(&[&idr_without_parameter_sets]),
+    ]);
+
+    let pay = "rtph266pay2";
+    let depay = "rtph266depay2";
+
+    let expected_pay = vec![
+        vec![
+            // VPS, SPS and PPS are aggregated into an AP packet
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::DISCONT)
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(12 + 2 + 2 + VPS.len() + 2 + SPS.len() + 2 + PPS.len())
+                .build(),
+            // IDR is fragmented into three FU packets
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .rtp_time(0)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(0))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(0)
+                .marker_bit(true)
+                .size(12 + 3 + 2998 - 2 * 1385)
+                .build(),
+        ],
+        // Single NAL unit packet
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 100)
+            .build()],
+        vec![
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(80))
+                .rtp_time(7_200)
+                .marker_bit(false)
+                .size(1400)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(80))
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(7_200)
+                .marker_bit(true)
+                .size(12 + 3 + 1498 - 1385)
+                .build(),
+        ],
+    ];
+
+    let parameter_sets_size = 4 + VPS.len() + 4 + SPS.len() + 4 + PPS.len();
+    let expected_depay = vec![
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(0))
+            .size(parameter_sets_size + 4 + 3000)
+            .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .size(4 + 100)
+            .flags(gst::BufferFlags::MARKER | gst::BufferFlags::DELTA_UNIT)
+            .build()],
+        // VPS, SPS and PPS from the caps are inserted before the IRAP frame
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .size(parameter_sets_size + 4 + 1500)
+            .flags(gst::BufferFlags::MARKER)
+            .build()],
+    ];
+
+    run_test_pipeline(
+        Source::Buffers(caps(), buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_h266_config_interval() {
+    init();
+
+    let idr = nal(IDR, 500);
+    let trail = nal(TRAIL, 50);
+
+    // The VPS is optional and not used here
+    let buffers = make_buffers(vec![
+        byte_stream_au(&[SPS, PPS, &idr]),
+        byte_stream_au(&[&trail]),
+        byte_stream_au(&[&idr]),
+    ]);
+
+    let pay = "rtph266pay2 aggregate-mode=none config-interval=-1";
+    let depay = "rtph266depay2";
+
+    let keyframe_packets = |pts: gst::ClockTime, rtp_time: u32, flags: gst::BufferFlags| {
+        vec![
+            ExpectedPacket::builder()
+                .pts(pts)
+                .flags(flags)
+                .rtp_time(rtp_time)
+                .marker_bit(false)
+                .size(12 + SPS.len())
+                .build(),
+            ExpectedPacket::builder()
+                .pts(pts)
+                .rtp_time(rtp_time)
+                .marker_bit(false)
+                .size(12 + PPS.len())
+                .build(),
+            ExpectedPacket::builder()
+                .pts(pts)
+                .flags(gst::BufferFlags::MARKER)
+                .rtp_time(rtp_time)
+                .marker_bit(true)
+                .size(12 + 500)
+                .build(),
+        ]
+    };
+
+    let expected_pay = vec![
+        keyframe_packets(
+            gst::ClockTime::from_mseconds(0),
+            0,
+            gst::BufferFlags::DISCONT,
+        ),
+        vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .flags(gst::BufferFlags::MARKER)
+            .rtp_time(3_600)
+            .marker_bit(true)
+            .size(12 + 50)
+            .build()],
+        keyframe_packets(
+            gst::ClockTime::from_mseconds(80),
+            7_200,
+            gst::BufferFlags::empty(),
+        ),
+    ];
+
+    let parameter_sets_size = 4 + SPS.len() + 4 + PPS.len();
+    let expected_depay = vec![
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(0))
+            .size(parameter_sets_size + 4 + 500)
+            .flags(gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(40))
+            .size(4 + 50)
+            .flags(gst::BufferFlags::MARKER | gst::BufferFlags::DELTA_UNIT)
+            .build()],
+        vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(80))
+            .size(parameter_sets_size + 4 + 500)
+            .flags(gst::BufferFlags::MARKER)
+            .build()],
+    ];
+
+    run_test_pipeline(
+        Source::Buffers(caps(), buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
@@ -0,0 +1,268 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Helpers shared by the H.264 / H.265 payloaders and depayloaders.
+
+use smallvec::SmallVec;
+
+/// Start code that is prepended to each NAL unit in byte-stream output.
+pub const START_CODE: [u8; 4] = [0x00, 0x00, 0x00, 0x01];
+
+/// Format of the NAL units in a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NalFormat {
+    /// Annex B byte-stream with start codes.
+    ByteStream,
+    /// Each NAL unit is prefixed with its length, with the given size of the length field in
+    /// bytes (`avc`, `hvc1` and `hev1` stream formats).
+    LengthPrefixed(usize),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum NalError {
+    #[error("Truncated NAL unit length at offset {0}")]
+    TruncatedLength(usize),
+    #[error("NAL unit of size {size} at offset {offset} exceeds buffer")]
+    TruncatedNalUnit { offset: usize, size: usize },
+}
+
+/// Splits `data` into the contained NAL units, without start codes or length prefixes.
+///
+/// Empty NAL units are skipped.
+pub fn split_nal_units(data: &[u8], format: NalFormat) -> Result<SmallVec<[&[u8]; 8]>, NalError> {
+    let mut nal_units = SmallVec::new();
+
+    match format {
+        NalFormat::ByteStream => {
+            let mut data = data;
+            while let Some(pos) = find_start_code(data) {
+                let rest = &data[pos + 3..];
+                let end = find_start_code(rest).unwrap_or(rest.len());
+
+                // Strip trailing zero bytes, which are either the first byte of a 4-byte start
+                // code or trailing_zero_8bits.
+                let mut nal = &rest[..end];
+                while let [head @ .., 0] = nal {
+                    nal = head;
+                }
+
+                if !nal.is_empty() {
+                    nal_units.push(nal);
+                }
+                data = &rest[end..];
+            }
+        }
+        NalFormat::LengthPrefixed(length_size) => {
+            let mut offset = 0;
+            while offset < data.len() {
+                if data.len() - offset < length_size {
+                    return Err(NalError::TruncatedLength(offset));
+                }
+
+                let size = data[offset..][..length_size]
+                    .iter()
+                    .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+                offset += length_size;
+
+                if data.len() - offset < size {
+                    return Err(NalError::TruncatedNalUnit { offset, size });
+                }
+
+                if size > 0 {
+                    nal_units.push(&data[offset..][..size]);
+                }
+                offset += size;
+            }
+        }
+    }
+
+    Ok(nal_units)
+}
+
+/// Returns the position of the first 3-byte start code in `data`.
+fn find_start_code(data: &[u8]) -> Option<usize> {
+    data.windows(3).position(|w| w == [0x00, 0x00, 0x01])
+}
+
+/// Removes the emulation prevention bytes from a NAL unit to get its RBSP.
+pub fn remove_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(data.len());
+    let mut zeroes = 0;
+
+    for &b in data {
+        if zeroes >= 2 && b == 0x03 {
+            zeroes = 0;
+            continue;
+        }
+
+        if b == 0x00 {
+            zeroes += 1;
+        } else {
+            zeroes = 0;
+        }
+        rbsp.push(b);
+    }
+
+    rbsp
+}
+
+/// Returns the bit at bit offset `pos` of `data`, starting with the most significant bit.
+pub fn read_bit(data: &[u8], pos: usize) -> Option<bool> {
+    data.get(pos / 8).map(|b| (b >> (7 - pos % 8)) & 0x01 != 0)
+}
+
+/// Reads the unsigned Exp-Golomb code `ue(v)` at bit offset `pos` of `data`.
+pub fn read_ue(data: &[u8], pos: usize) -> Option<u32> {
+    let leading_zeros = (0..32).find(|i| read_bit(data, pos + i) != Some(false))?;
+    if !read_bit(data, pos + leading_zeros)? {
+        return None;
+    }
+
+    let mut value = 1u64;
+    for i in 0..leading_zeros {
+        value = (value << 1) | read_bit(data, pos + leading_zeros + 1 + i)? as u64;
+    }
+
+    u32::try_from(value - 1).ok()
+}
+
+/// Parameter sets of one type, e.g. all SPS of a stream, sorted by their id.
+#[derive(Debug, Default, Clone)]
+pub struct ParameterSets(Vec<(u32, Vec<u8>)>);
+
+impl ParameterSets {
+    /// Stores the parameter set `nal` with the given `id`, replacing a previous one with the same
+    /// id. Returns `true` if the parameter sets changed.
+    pub fn insert(&mut self, id: u32, nal: &[u8]) -> bool {
+        match self.0.binary_search_by_key(&id, |(id, _)| *id) {
+            Ok(idx) if self.0[idx].1 == nal => false,
+            Ok(idx) => {
+                self.0[idx].1 = nal.to_vec();
+                true
+            }
+            Err(idx) => {
+                self.0.insert(idx, (id, nal.to_vec()));
+                true
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.0.iter().map(|(_, nal)| nal.as_slice())
+    }
+}
+
+/// Parses a comma-separated list of base64 encoded NAL units as used by the
+/// `sprop-parameter-sets` / `sprop-vps` / `sprop-sps` / `sprop-pps` fields.
+pub fn parse_sprop(sprop: &str) -> Vec<Vec<u8>> {
+    sprop
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(gst::glib::base64_decode)
+        .filter(|nal| !nal.is_empty())
+        .collect()
+}
+
+/// Creates a comma-separated list of base64 encoded NAL units.
+pub fn format_sprop<'a>(nal_units: impl IntoIterator<Item = &'a [u8]>) -> String {
+    nal_units
+        .into_iter()
+        .map(|nal| gst::glib::base64_encode(nal).to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_byte_stream() {
+        let data = [
+            0x00, 0x00, 0x00, 0x01, 0x67, 0x01, 0x02, 0x00, 0x00, 0x01, 0x68, 0x03, 0x00, 0x00,
+            0x00, 0x00, 0x01, 0x65, 0x04, 0x05,
+        ];
+
+        let nal_units = split_nal_units(&data, NalFormat::ByteStream).unwrap();
+        assert_eq!(
+            nal_units.as_slice(),
+            &[
+                &[0x67, 0x01, 0x02][..],
+                &[0x68, 0x03][..],
+                &[0x65, 0x04, 0x05][..]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_length_prefixed() {
+        let data = [
+            0x00, 0x00, 0x00, 0x02, 0x67, 0x01, 0x00, 0x00, 0x00, 0x03, 0x65, 0x02, 0x03,
+        ];
+
+        let nal_units = split_nal_units(&data, NalFormat::LengthPrefixed(4)).unwrap();
+        assert_eq!(
+            nal_units.as_slice(),
+            &[&[0x67, 0x01][..], &[0x65, 0x02, 0x03][..]]
+        );
+
+        assert!(split_nal_units(&data[..12], NalFormat::LengthPrefixed(4)).is_err());
+    }
+
+    #[test]
+    fn test_remove_emulation_prevention() {
+        let data = [0x42, 0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03, 0x00, 0x03];
+        assert_eq!(
+            remove_emulation_prevention(&data),
+            [0x42, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03]
+        );
+    }
+
+    #[test]
+    fn test_read_ue() {
+        // 1, 010, 011, 00100, 0001000
+        let data = [0b1010_0110, 0b0100_0001, 0b0000_0000];
+        assert_eq!(read_ue(&data, 0), Some(0));
+        assert_eq!(read_ue(&data, 1), Some(1));
+        assert_eq!(read_ue(&data, 4), Some(2));
+        assert_eq!(read_ue(&data, 7), Some(3));
+        assert_eq!(read_ue(&data, 12), Some(7));
+        assert_eq!(read_ue(&data, 19), None);
+    }
+
+    #[test]
+    fn test_parameter_sets() {
+        let mut sets = ParameterSets::default();
+        assert!(sets.insert(1, &[0x68, 0x01]));
+        assert!(sets.insert(0, &[0x68, 0x00]));
+        assert!(!sets.insert(1, &[0x68, 0x01]));
+        assert!(sets.insert(1, &[0x68, 0x02]));
+        assert_eq!(
+            sets.iter().collect::<Vec<_>>(),
+            [&[0x68, 0x00][..], &[0x68, 0x02][..]]
+        );
+    }
+
+    #[test]
+    fn test_sprop() {
+        let nal_units = [&[0x67, 0x42, 0xc0, 0x1e][..], &[0x68, 0xce, 0x3c, 0x80][..]];
+        let sprop = format_sprop(nal_units);
+        assert_eq!(sprop, "Z0LAHg==,aM48gA==");
+        assert_eq!(parse_sprop(&sprop), nal_units);
+    }
+}
@@ -0,0 +1,574 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtphitlessmerge
+ * @see_also: rtprecv, ts-udpsrc
+ *
+ * Merges multiple identical RTP streams received over different network paths into a single
+ * stream as per [SMPTE ST 2022-7][st-2022-7] seamless protection switching.
+ *
+ * Each leg is connected to a separate `sink_%u` request pad. The first copy of each packet that
+ * arrives on any leg is forwarded, and copies of the same packet arriving later on other legs are
+ * dropped. Packets are identified by their SSRC and sequence number.
+ *
+ * Sequence numbers are remembered for #rtphitlessmerge:window-size packets. Packets that are
+ * older than that are dropped, so the window has to cover the maximum delay difference between
+ * the legs. Packets that were not received on a leg while they were inside the window are
+ * counted as lost for that leg.
+ *
+ * Per-leg statistics about received, forwarded, duplicate, late and lost packets as well as the
+ * delay of each leg compared to the fastest one are available via the #rtphitlessmerge:stats
+ * property.
+ *
+ * Non-RTP packets, e.g. RTCP in case of rtcp-mux, are passed through from all legs.
+ *
+ * [st-2022-7]: https://ieeexplore.ieee.org/document/8626013
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 ts-udpsrc address=239.1.1.1 port=5004 caps='application/x-rtp,media=video,clock-rate=90000,encoding-name=VP8' ! merge.sink_0 \
+ *     ts-udpsrc address=239.1.2.1 port=5004 caps='application/x-rtp,media=video,clock-rate=90000,encoding-name=VP8' ! merge.sink_1 \
+ *     rtphitlessmerge name=merge window-size=2048 ! rtprecv name=rtprecv rtp-id=0 latency=100 \
+ *     rtprecv. ! rtpvp8depay2 ! vp8dec ! videoconvert ! autovideosink
+ * ]| This will receive the same VP8 stream over two multicast groups and merge them.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, prelude::*, subclass::prelude::*};
+use rtp_types::RtpPacket;
+use smallvec::SmallVec;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::utils::ExtendedSeqnum;
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtphitlessmerge",
+        gst::DebugColorFlags::empty(),
+        Some("RTP Hitless Merge"),
+    )
+});
+
+const DEFAULT_WINDOW_SIZE: u32 = 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    window_size: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_size: DEFAULT_WINDOW_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct LegStats {
+    received: u64,
+    forwarded: u64,
+    duplicates: u64,
+    late: u64,
+    lost: u64,
+    /// Sum and number of the delays compared to the first arrival of each packet.
+    delay_sum: Duration,
+    delay_count: u64,
+    max_delay: Duration,
+}
+
+struct Leg {
+    pad: gst::Pad,
+    id: u32,
+    eos: bool,
+    flushing: bool,
+    stats: LegStats,
+}
+
+/// A packet inside the window, with the legs it arrived on.
+struct SeenPacket {
+    arrival: Instant,
+    legs: SmallVec<[u32; 4]>,
+}
+
+#[derive(Default)]
+struct Source {
+    ext_seqnum: ExtendedSeqnum,
+    max_ext_seqnum: Option<u64>,
+    seen: BTreeMap<u64, SeenPacket>,
+}
+
+#[derive(Default)]
+struct State {
+    legs: Vec<Leg>,
+    next_leg_id: u32,
+    sources: HashMap<u32, Source>,
+    stream_start_sent: bool,
+    segment_sent: bool,
+}
+
+impl State {
+    fn reset(&mut self) {
+        self.sources.clear();
+        self.stream_start_sent = false;
+        self.segment_sent = false;
+        for leg in &mut self.legs {
+            leg.eos = false;
+            leg.flushing = false;
+            leg.stats = LegStats::default();
+        }
+    }
+}
+
+pub struct RtpHitlessMerge {
+    srcpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl RtpHitlessMerge {
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let now = Instant::now();
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, obj = pad, "Failed to map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        let (ssrc, seqnum) = match RtpPacket::parse(&map) {
+            Ok(rtp) => (rtp.ssrc(), rtp.sequence_number()),
+            Err(err) => {
+                gst::trace!(CAT, obj = pad, "Passing through non-RTP packet: {err:?}");
+                drop(map);
+                return self.srcpad.push(buffer);
+            }
+        };
+        drop(map);
+
+        let window_size = self.settings.lock().unwrap().window_size as u64;
+
+        let mut state = self.state.lock().unwrap();
+        let State { legs, sources, .. } = &mut *state;
+
+        let Some(leg_idx) = legs.iter().position(|leg| &leg.pad == pad) else {
+            // Pad was released in the meantime
+            return Err(gst::FlowError::Flushing);
+        };
+        let leg_id = legs[leg_idx].id;
+        legs[leg_idx].stats.received += 1;
+
+        let source = sources.entry(ssrc).or_default();
+        let ext_seqnum = source.ext_seqnum.next(seqnum);
+
+        if source
+            .max_ext_seqnum
+            .is_some_and(|max| ext_seqnum + window_size <= max)
+        {
+            gst::debug!(
+                CAT,
+                obj = pad,
+                "Dropping packet {seqnum} of SSRC {ssrc:08x} outside the window"
+            );
+            legs[leg_idx].stats.late += 1;
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        if let Some(seen) = source.seen.get_mut(&ext_seqnum) {
+            let delay = now.saturating_duration_since(seen.arrival);
+            gst::trace!(
+                CAT,
+                obj = pad,
+                "Dropping duplicate packet {seqnum} of SSRC {ssrc:08x} with delay {delay:?}"
+            );
+
+            let stats = &mut legs[leg_idx].stats;
+            stats.duplicates += 1;
+            if !seen.legs.contains(&leg_id) {
+                seen.legs.push(leg_id);
+                stats.delay_sum += delay;
+                stats.delay_count += 1;
+                stats.max_delay = stats.max_delay.max(delay);
+            }
+
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        gst::trace!(
+            CAT,
+            obj = pad,
+            "Forwarding packet {seqnum} of SSRC {ssrc:08x}"
+        );
+
+        let stats = &mut legs[leg_idx].stats;
+        stats.forwarded += 1;
+        stats.delay_count += 1;
+
+        source.seen.insert(
+            ext_seqnum,
+            SeenPacket {
+                arrival: now,
+                legs: SmallVec::from_slice(&[leg_id]),
+            },
+        );
+
+        let max = source
+            .max_ext_seqnum
+            .map_or(ext_seqnum, |max| max.max(ext_seqnum));
+        source.max_ext_seqnum = Some(max);
+
+        // Packets leaving the window were lost on all legs they did not arrive on
+        while let Some(entry) = source.seen.first_entry() {
+            if *entry.key() + window_size > max {
+                break;
+            }
+
+            let seen = entry.remove();
+            for leg in legs.iter_mut() {
+                if leg.stats.received > 0 && !seen.legs.contains(&leg.id) {
+                    leg.stats.lost += 1;
+                }
+            }
+        }
+
+        drop(state);
+
+        self.srcpad.push(buffer)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        let mut state = self.state.lock().unwrap();
+
+        // All legs carry the same stream so sticky events are only forwarded once
+        match event.view() {
+            gst::EventView::StreamStart(_) => {
+                if std::mem::replace(&mut state.stream_start_sent, true) {
+                    return true;
+                }
+            }
+            gst::EventView::Caps(ev) => {
+                if self.srcpad.current_caps().as_ref() == Some(&ev.caps_owned()) {
+                    return true;
+                }
+            }
+            gst::EventView::Segment(_) => {
+                if std::mem::replace(&mut state.segment_sent, true) {
+                    return true;
+                }
+            }
+            gst::EventView::Eos(_) => {
+                if let Some(leg) = state.legs.iter_mut().find(|leg| &leg.pad == pad) {
+                    leg.eos = true;
+                }
+
+                if !state.legs.iter().all(|leg| leg.eos) {
+                    gst::debug!(CAT, obj = pad, "Waiting for EOS on all legs");
+                    return true;
+                }
+            }
+            // Flushing starts with the first leg and stops with the last one, so downstream
+            // only sees a single flush
+            gst::EventView::FlushStart(_) => {
+                let already_flushing = state.legs.iter().any(|leg| leg.flushing);
+                if let Some(leg) = state.legs.iter_mut().find(|leg| &leg.pad == pad) {
+                    leg.flushing = true;
+                }
+
+                if already_flushing {
+                    return true;
+                }
+            }
+            gst::EventView::FlushStop(_) => {
+                if let Some(leg) = state.legs.iter_mut().find(|leg| &leg.pad == pad) {
+                    leg.eos = false;
+                    leg.flushing = false;
+                }
+
+                if state.legs.iter().any(|leg| leg.flushing) {
+                    gst::debug!(CAT, obj = pad, "Waiting for flush stop on all legs");
+                    return true;
+                }
+
+                // The sticky segment is removed from the srcpad by the flush stop, let the next
+                // one through
+                state.sources.clear();
+                state.segment_sent = false;
+            }
+            _ => (),
+        }
+        drop(state);
+
+        gst::Pad::event_default(pad, Some(&*self.obj()), event)
+    }
+
+    fn stats(&self) -> gst::Structure {
+        let state = self.state.lock().unwrap();
+
+        let legs = state
+            .legs
+            .iter()
+            .map(|leg| {
+                let stats = &leg.stats;
+                let average_delay = if stats.delay_count > 0 {
+                    stats.delay_sum / stats.delay_count as u32
+                } else {
+                    Duration::ZERO
+                };
+
+                gst::Structure::builder("application/x-rtp-hitless-merge-leg-stats")
+                    .field("pad-name", leg.pad.name())
+                    .field("packets-received", stats.received)
+                    .field("packets-forwarded", stats.forwarded)
+                    .field("packets-duplicate", stats.duplicates)
+                    .field("packets-late", stats.late)
+                    .field("packets-lost", stats.lost)
+                    .field("average-delay", average_delay.as_nanos() as u64)
+                    .field("max-delay", stats.max_delay.as_nanos() as u64)
+                    .build()
+                    .to_send_value()
+            })
+            .collect::<Vec<_>>();
+
+        gst::Structure::builder("application/x-rtp-hitless-merge-stats")
+            .field(
+                "packets-forwarded",
+                state
+                    .legs
+                    .iter()
+                    .map(|leg| leg.stats.forwarded)
+                    .sum::<u64>(),
+            )
+            .field(
+                "packets-duplicate",
+                state
+                    .legs
+                    .iter()
+                    .map(|leg| leg.stats.duplicates)
+                    .sum::<u64>(),
+            )
+            .field("legs", gst::Array::from(legs))
+            .build()
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpHitlessMerge {
+    const NAME: &'static str = "GstRtpHitlessMerge";
+    type Type = super::RtpHitlessMerge;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_from_template(&templ).build();
+
+        Self {
+            srcpad,
+            settings: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpHitlessMerge {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        self.obj().add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("window-size")
+                    .nick("Window Size")
+                    .blurb("Number of sequence numbers to remember for detecting duplicates")
+                    .minimum(1)
+                    .maximum(i16::MAX as u32)
+                    .default_value(DEFAULT_WINDOW_SIZE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Various statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "window-size" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.window_size = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "window-size" => {
+                let settings = self.settings.lock().unwrap();
+                settings.window_size.to_value()
+            }
+            "stats" => self.stats().to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpHitlessMerge {}
+
+impl ElementImpl for RtpHitlessMerge {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP Hitless Merge",
+                "Network/RTP",
+                "Merges redundant RTP streams as per SMPTE ST 2022-7",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let caps = gst::Caps::new_empty_simple("application/x-rtp");
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink_%u",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn request_new_pad(
+        &self,
+        templ: &gst::PadTemplate,
+        name: Option<&str>,
+        _caps: Option<&gst::Caps>,
+    ) -> Option<gst::Pad> {
+        let mut state = self.state.lock().unwrap();
+
+        let id = match name.and_then(|name| name.strip_prefix("sink_")) {
+            Some(id) => match id.parse::<u32>() {
+                Ok(id) if state.legs.iter().all(|leg| leg.id != id) => id,
+                _ => {
+                    gst::error!(CAT, imp = self, "Invalid or duplicate pad name {name:?}");
+                    return None;
+                }
+            },
+            None => {
+                let mut id = state.next_leg_id;
+                while state.legs.iter().any(|leg| leg.id == id) {
+                    id += 1;
+                }
+                id
+            }
+        };
+        state.next_leg_id = state.next_leg_id.max(id + 1);
+
+        let pad = gst::Pad::builder_from_template(templ)
+            .name(format!("sink_{id}"))
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.sink_event(pad, event))
+            })
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        state.legs.push(Leg {
+            pad: pad.clone(),
+            id,
+            eos: false,
+            flushing: false,
+            stats: LegStats::default(),
+        });
+        drop(state);
+
+        pad.set_active(true).unwrap();
+        self.obj().add_pad(&pad).unwrap();
+
+        Some(pad)
+    }
+
+    fn release_pad(&self, pad: &gst::Pad) {
+        let mut state = self.state.lock().unwrap();
+        let was_flushing = state.legs.iter().any(|leg| leg.flushing);
+        state.legs.retain(|leg| &leg.pad != pad);
+        let all_eos = !state.legs.is_empty() && state.legs.iter().all(|leg| leg.eos);
+        // Don't leave the srcpad flushing if only the released leg was
+        let stop_flushing = was_flushing && !state.legs.iter().any(|leg| leg.flushing);
+        if stop_flushing {
+            state.sources.clear();
+            state.segment_sent = false;
+        }
+        drop(state);
+
+        pad.set_active(false).unwrap();
+        self.obj().remove_pad(pad).unwrap();
+
+        if stop_flushing {
+            self.srcpad.push_event(gst::event::FlushStop::new(true));
+        }
+        if all_eos {
+            self.srcpad.push_event(gst::event::Eos::new());
+        }
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        let ret = self.parent_change_state(transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            self.state.lock().unwrap().reset();
+        }
+
+        Ok(ret)
+    }
+}
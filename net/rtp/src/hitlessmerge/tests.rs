@@ -0,0 +1,185 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst_check::Harness;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtphitlessmerge test");
+    });
+}
+
+fn rtp_buffer(seqnum: u16) -> gst::Buffer {
+    let data = rtp_types::RtpPacketBuilder::new()
+        .payload_type(96)
+        .ssrc(0x12345678)
+        .sequence_number(seqnum)
+        .timestamp(seqnum as u32 * 3000)
+        .payload([seqnum as u8; 8].as_slice())
+        .write_vec()
+        .unwrap();
+
+    gst::Buffer::from_mut_slice(data)
+}
+
+fn seqnum(buffer: &gst::Buffer) -> u16 {
+    let map = buffer.map_readable().unwrap();
+    rtp_types::RtpPacket::parse(&map).unwrap().sequence_number()
+}
+
+/// Creates a harness for the source pad and the first leg, and one for the second leg.
+fn setup(window_size: u32) -> (gst::Element, Harness, Harness) {
+    init();
+
+    let merge = gst::ElementFactory::make("rtphitlessmerge")
+        .property("window-size", window_size)
+        .build()
+        .unwrap();
+
+    let caps = gst::Caps::builder("application/x-rtp")
+        .field("media", "video")
+        .field("clock-rate", 90_000i32)
+        .field("encoding-name", "VP8")
+        .build();
+
+    let mut h0 = Harness::with_element(&merge, Some("sink_0"), Some("src"));
+    h0.set_src_caps(caps.clone());
+    h0.play();
+
+    let mut h1 = Harness::with_element(&merge, Some("sink_1"), None);
+    h1.set_src_caps(caps);
+    h1.play();
+
+    (merge, h0, h1)
+}
+
+fn leg_stats(merge: &gst::Element) -> Vec<gst::Structure> {
+    let stats = merge.property::<gst::Structure>("stats");
+    stats
+        .get::<gst::Array>("legs")
+        .unwrap()
+        .iter()
+        .map(|s| s.get::<gst::Structure>().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_dedup() {
+    let (merge, mut h0, mut h1) = setup(4);
+
+    // Leg 0 misses packets 3 and 7, leg 1 misses packet 5
+    for n in 0..10 {
+        if n != 3 && n != 7 {
+            h0.push(rtp_buffer(n)).unwrap();
+        }
+        if n != 5 {
+            h1.push(rtp_buffer(n)).unwrap();
+        }
+    }
+
+    let mut seqnums = Vec::new();
+    while let Some(buffer) = h0.try_pull() {
+        seqnums.push(seqnum(&buffer));
+    }
+    assert_eq!(seqnums, (0..10).collect::<Vec<_>>());
+
+    let stats = merge.property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u64>("packets-forwarded").unwrap(), 10);
+    assert_eq!(stats.get::<u64>("packets-duplicate").unwrap(), 7);
+
+    // Only packets that left the window are counted as lost
+    let legs = leg_stats(&merge);
+    assert_eq!(legs.len(), 2);
+    assert_eq!(legs[0].get::<&str>("pad-name").unwrap(), "sink_0");
+    assert_eq!(legs[0].get::<u64>("packets-received").unwrap(), 8);
+    assert_eq!(legs[0].get::<u64>("packets-forwarded").unwrap(), 8);
+    assert_eq!(legs[0].get::<u64>("packets-duplicate").unwrap(), 0);
+    assert_eq!(legs[0].get::<u64>("packets-lost").unwrap(), 1);
+    assert_eq!(legs[1].get::<&str>("pad-name").unwrap(), "sink_1");
+    assert_eq!(legs[1].get::<u64>("packets-received").unwrap(), 9);
+    assert_eq!(legs[1].get::<u64>("packets-forwarded").unwrap(), 2);
+    assert_eq!(legs[1].get::<u64>("packets-duplicate").unwrap(), 7);
+    assert_eq!(legs[1].get::<u64>("packets-lost").unwrap(), 1);
+}
+
+#[test]
+fn test_late() {
+    let (merge, mut h0, mut h1) = setup(4);
+
+    for n in 0..10 {
+        h0.push(rtp_buffer(n)).unwrap();
+    }
+
+    // Packet 2 is outside the window, packet 8 is a duplicate
+    h1.push(rtp_buffer(2)).unwrap();
+    h1.push(rtp_buffer(8)).unwrap();
+
+    let mut seqnums = Vec::new();
+    while let Some(buffer) = h0.try_pull() {
+        seqnums.push(seqnum(&buffer));
+    }
+    assert_eq!(seqnums, (0..10).collect::<Vec<_>>());
+
+    let legs = leg_stats(&merge);
+    assert_eq!(legs[1].get::<u64>("packets-received").unwrap(), 2);
+    assert_eq!(legs[1].get::<u64>("packets-forwarded").unwrap(), 0);
+    assert_eq!(legs[1].get::<u64>("packets-late").unwrap(), 1);
+    assert_eq!(legs[1].get::<u64>("packets-duplicate").unwrap(), 1);
+}
+
+#[test]
+fn test_flush() {
+    let (_merge, mut h0, mut h1) = setup(4);
+
+    for n in 0..4 {
+        h0.push(rtp_buffer(n)).unwrap();
+        h1.push(rtp_buffer(n)).unwrap();
+    }
+    while h0.try_pull().is_some() {}
+    while h0.try_pull_event().is_some() {}
+
+    // A flushing seek flushes all legs, and each of them sends a new segment afterwards
+    let segment = gst::FormattedSegment::<gst::ClockTime>::new();
+    for h in [&mut h0, &mut h1] {
+        assert!(h.push_event(gst::event::FlushStart::new()));
+    }
+    for h in [&mut h0, &mut h1] {
+        assert!(h.push_event(gst::event::FlushStop::new(true)));
+        assert!(h.push_event(gst::event::Segment::new(&segment)));
+    }
+
+    let mut events = Vec::new();
+    while let Some(event) = h0.try_pull_event() {
+        events.push(event.type_());
+    }
+    assert_eq!(
+        events,
+        [
+            gst::EventType::FlushStart,
+            gst::EventType::FlushStop,
+            gst::EventType::Segment
+        ]
+    );
+
+    // The window was reset, so packets with old sequence numbers are forwarded again
+    h1.push(rtp_buffer(0)).unwrap();
+    h0.push(rtp_buffer(0)).unwrap();
+    assert_eq!(seqnum(&h0.pull().unwrap()), 0);
+    assert!(h0.try_pull().is_none());
+    assert!(h0
+        .sinkpad()
+        .unwrap()
+        .sticky_event::<gst::event::Segment>(0)
+        .is_some());
+}
@@ -0,0 +1,1129 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtplayerselector
+ * @see_also: rtprecv, rtpsend, rtpvp8pay2, rtpvp9pay2, rtpav1pay
+ *
+ * Selects the simulcast encoding and the spatial and temporal layers of a VP8, VP9 or AV1 RTP
+ * stream that are forwarded, without decoding and re-encoding the stream. This is the main
+ * building block of a Selective Forwarding Unit (SFU).
+ *
+ * Each simulcast encoding is connected to a separate `sink_%u` request pad. Packets of the
+ * encoding selected via #rtplayerselector:target-encoding are forwarded, the packets of all other
+ * encodings are dropped. Switching to another encoding happens at the next keyframe of the new
+ * encoding, and a keyframe is requested upstream on the corresponding sink pad while waiting for
+ * it.
+ *
+ * Packets of spatial and temporal layers above #rtplayerselector:target-spatial-layer and
+ * #rtplayerselector:target-temporal-layer are dropped. Dropping layers is possible at any frame,
+ * but switching up to a higher layer has to wait until the layer can be decoded again:
+ *
+ * * VP8: at frames of the next temporal layer with the layer sync bit set.
+ * * VP9: temporally at frames with the switching up point bit set, spatially at frames of the next
+ *   spatial layer that are not inter-picture predicted. A keyframe is requested upstream for
+ *   switching up the spatial layer.
+ * * AV1: temporally at frames of the base temporal layer, spatially only at keyframes. The layer
+ *   of a packet is taken from the OBU extension header of the first OBU that has one.
+ *
+ * The forwarded packets form a single contiguous stream: the SSRC of the first forwarded packet
+ * is used for all packets, and sequence numbers, RTP timestamps, VP8/VP9 picture IDs and
+ * TL0PICIDX are rewritten so that there are no gaps because of dropped packets and no jumps when
+ * switching between encodings. Gaps caused by packet loss upstream are preserved. For VP9, the
+ * marker bit is set on the last packet of the highest forwarded spatial layer of each picture.
+ * The marker bit of AV1 packets is not modified.
+ *
+ * The input is expected to be in order, e.g. by placing the element after #rtprecv. Packets that
+ * arrive out of order are dropped.
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 videotestsrc is-live=true ! video/x-raw,width=1280,height=720 ! tee name=t \
+ *     t. ! queue ! vp8enc deadline=1 keyframe-max-dist=60 temporal-scalability-number-layers=2 temporal-scalability-periodicity=2 temporal-scalability-rate-decimator="<2,1>" temporal-scalability-layer-id="<0,1>" temporal-scalability-target-bitrate="<1000000,2000000>" ! rtpvp8pay2 picture-id-mode=15-bit ssrc=1 ! selector.sink_0 \
+ *     t. ! queue ! videoscale ! video/x-raw,width=640,height=360 ! vp8enc deadline=1 keyframe-max-dist=60 ! rtpvp8pay2 picture-id-mode=15-bit ssrc=2 ! selector.sink_1 \
+ *     rtplayerselector name=selector target-encoding=1 ! rtpvp8depay2 ! vp8dec ! videoconvert ! autovideosink
+ * ]| This will encode two simulcast encodings and forward the lower resolution one.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use anyhow::{bail, Context as _};
+use bitstream_io::{BigEndian, BitReader, ByteRead as _, ByteReader};
+use gst::{glib, prelude::*, subclass::prelude::*};
+use rtp_types::{RtpPacket, RtpPacketMut};
+use std::{
+    io::Cursor,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{av1::common as av1, vp8::payload_descriptor as vp8, vp9::payload_descriptor as vp9};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtplayerselector",
+        gst::DebugColorFlags::empty(),
+        Some("RTP Layer Selector"),
+    )
+});
+
+const DEFAULT_TARGET_ENCODING: u32 = 0;
+const DEFAULT_TARGET_SPATIAL_LAYER: u32 = MAX_LAYER;
+const DEFAULT_TARGET_TEMPORAL_LAYER: u32 = MAX_LAYER;
+
+/// Highest layer id that can be signalled by any of the supported codecs
+const MAX_LAYER: u32 = 7;
+
+/// Minimum interval between two keyframe requests
+const KEYFRAME_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    target_encoding: u32,
+    target_spatial_layer: u8,
+    target_temporal_layer: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            target_encoding: DEFAULT_TARGET_ENCODING,
+            target_spatial_layer: DEFAULT_TARGET_SPATIAL_LAYER as u8,
+            target_temporal_layer: DEFAULT_TARGET_TEMPORAL_LAYER as u8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Codec {
+    Vp8,
+    Vp9,
+    Av1,
+}
+
+impl Codec {
+    fn from_caps(caps: &gst::CapsRef) -> Option<Self> {
+        let s = caps.structure(0)?;
+
+        match s.get::<&str>("encoding-name").ok()? {
+            "VP8" => Some(Codec::Vp8),
+            "VP9" => Some(Codec::Vp9),
+            "AV1" => Some(Codec::Av1),
+            _ => None,
+        }
+    }
+}
+
+/// VP8/VP9 picture ID, which is either 7 or 15 bits long.
+#[derive(Debug, Clone, Copy)]
+struct PictureId {
+    value: u16,
+    fifteen_bit: bool,
+}
+
+impl PictureId {
+    fn offset(self, offset: u16) -> Self {
+        let mask = if self.fifteen_bit { 0x7fff } else { 0x7f };
+
+        PictureId {
+            value: self.value.wrapping_add(offset) & mask,
+            fifteen_bit: self.fifteen_bit,
+        }
+    }
+
+    fn write(self, data: &mut [u8]) {
+        if self.fifteen_bit {
+            data[0] = 0x80 | (self.value >> 8) as u8;
+            data[1] = (self.value & 0xff) as u8;
+        } else {
+            data[0] = self.value as u8;
+        }
+    }
+
+    fn len(self) -> usize {
+        if self.fifteen_bit {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+impl From<vp8::PictureId> for PictureId {
+    fn from(value: vp8::PictureId) -> Self {
+        PictureId {
+            value: u16::from(value),
+            fifteen_bit: matches!(value, vp8::PictureId::FifteenBit(_)),
+        }
+    }
+}
+
+impl From<vp9::PictureId> for PictureId {
+    fn from(value: vp9::PictureId) -> Self {
+        PictureId {
+            value: u16::from(value),
+            fifteen_bit: matches!(value, vp9::PictureId::FifteenBit(_)),
+        }
+    }
+}
+
+/// Layer information of a single packet.
+#[derive(Debug, Default)]
+pub(crate) struct PacketInfo {
+    /// The packet starts a new frame, i.e. a new layer of a picture.
+    pub(crate) start_of_frame: bool,
+    /// The packet ends a frame. Only known for VP9.
+    pub(crate) end_of_frame: bool,
+    pub(crate) keyframe: bool,
+    pub(crate) spatial_layer: u8,
+    pub(crate) temporal_layer: u8,
+    /// VP8: layer sync, VP9: switching up point.
+    temporal_switching_point: bool,
+    /// VP9: the frame is not inter-picture predicted.
+    spatial_switching_point: bool,
+    picture_id: Option<PictureId>,
+    /// Offset of the picture ID inside the payload.
+    picture_id_offset: usize,
+    tl0picidx: Option<u8>,
+    /// Offset of the TL0PICIDX inside the payload.
+    tl0picidx_offset: usize,
+}
+
+impl PacketInfo {
+    pub(crate) fn parse(codec: Codec, payload: &[u8]) -> Result<Self, anyhow::Error> {
+        match codec {
+            Codec::Vp8 => Self::parse_vp8(payload),
+            Codec::Vp9 => Self::parse_vp9(payload),
+            Codec::Av1 => Self::parse_av1(payload),
+        }
+    }
+
+    fn parse_vp8(payload: &[u8]) -> Result<Self, anyhow::Error> {
+        let mut cursor = Cursor::new(payload);
+        let mut r = ByteReader::endian(&mut cursor, BigEndian);
+        let descriptor = r.parse::<vp8::PayloadDescriptor>()?;
+        let header_offset = cursor.position() as usize;
+
+        let start_of_frame = descriptor.start_of_partition && descriptor.partition_index == 0;
+        // The first bit of the frame tag is 0 for keyframes
+        let keyframe = start_of_frame
+            && payload
+                .get(header_offset)
+                .is_some_and(|&first| (first & 0x01) == 0);
+
+        // Flags and extension flags are followed by the picture ID and then the TL0PICIDX
+        let picture_id = descriptor.picture_id.map(PictureId::from);
+        let picture_id_offset = 2;
+        let tl0picidx_offset = picture_id_offset + picture_id.map_or(0, PictureId::len);
+
+        Ok(PacketInfo {
+            start_of_frame,
+            end_of_frame: false,
+            keyframe,
+            spatial_layer: 0,
+            temporal_layer: descriptor.temporal_layer_id.map_or(0, |layer| layer.id),
+            temporal_switching_point: descriptor.temporal_layer_id.is_some_and(|layer| layer.sync),
+            spatial_switching_point: false,
+            picture_id,
+            picture_id_offset,
+            tl0picidx: descriptor.temporal_layer_zero_index,
+            tl0picidx_offset,
+        })
+    }
+
+    fn parse_vp9(payload: &[u8]) -> Result<Self, anyhow::Error> {
+        let mut cursor = Cursor::new(payload);
+        let mut r = ByteReader::endian(&mut cursor, BigEndian);
+        let descriptor = r.parse::<vp9::PayloadDescriptor>()?;
+
+        let spatial_layer = descriptor
+            .layer_index
+            .as_ref()
+            .map_or(0, |layer_index| layer_index.spatial_layer_id);
+
+        // Flags are followed by the picture ID and the layer index, which includes the
+        // TL0PICIDX in non-flexible mode
+        let picture_id = descriptor.picture_id.map(PictureId::from);
+        let picture_id_offset = 1;
+        let tl0picidx_offset = picture_id_offset + picture_id.map_or(0, PictureId::len) + 1;
+
+        Ok(PacketInfo {
+            start_of_frame: descriptor.start_of_frame,
+            end_of_frame: descriptor.end_of_frame,
+            keyframe: descriptor.start_of_frame
+                && !descriptor.inter_picture_predicted_frame
+                && spatial_layer == 0,
+            spatial_layer,
+            temporal_layer: descriptor
+                .layer_index
+                .as_ref()
+                .map_or(0, |layer_index| layer_index.temporal_layer_id),
+            temporal_switching_point: descriptor
+                .layer_index
+                .as_ref()
+                .is_some_and(|layer_index| layer_index.switching_point),
+            spatial_switching_point: !descriptor.inter_picture_predicted_frame,
+            picture_id,
+            picture_id_offset,
+            tl0picidx: descriptor
+                .layer_index
+                .as_ref()
+                .and_then(|layer_index| layer_index.temporal_layer_zero_index),
+            tl0picidx_offset,
+        })
+    }
+
+    fn parse_av1(payload: &[u8]) -> Result<Self, anyhow::Error> {
+        let Some((&first, mut rest)) = payload.split_first() else {
+            bail!("Empty payload");
+        };
+        let aggr_header = av1::AggregationHeader::from(first);
+
+        let mut info = PacketInfo {
+            // Packets that continue an OBU from the previous packet belong to the same frame
+            start_of_frame: !aggr_header.leading_fragment,
+            keyframe: aggr_header.start_of_seq,
+            ..Default::default()
+        };
+
+        let mut idx = 0;
+        while !rest.is_empty() {
+            // The last OBU element has no length field if the number of elements is given
+            let len = if aggr_header
+                .obu_count
+                .is_some_and(|count| idx + 1 == count as usize)
+            {
+                rest.len()
+            } else {
+                let mut cursor = Cursor::new(rest);
+                let (len, leb_size) =
+                    av1::parse_leb128(&mut BitReader::endian(&mut cursor, av1::ENDIANNESS))
+                        .context("obu_element_size")?;
+                rest = &rest[leb_size as usize..];
+                len as usize
+            };
+            if len > rest.len() {
+                bail!("OBU element size {len} larger than remaining payload");
+            }
+            let (element, remaining) = rest.split_at(len);
+            rest = remaining;
+
+            // The first element has no OBU header if it continues an OBU
+            if !element.is_empty() && (idx > 0 || !aggr_header.leading_fragment) {
+                let mut cursor = Cursor::new(element);
+                let obu =
+                    av1::UnsizedObu::parse(&mut BitReader::endian(&mut cursor, av1::ENDIANNESS))
+                        .context("obu_header")?;
+                if obu.has_extension {
+                    info.temporal_layer = obu.temporal_id;
+                    info.spatial_layer = obu.spatial_id;
+                    break;
+                }
+            }
+
+            idx += 1;
+        }
+
+        Ok(info)
+    }
+
+    fn rewrite(&self, payload: &mut [u8], picture_id: Option<PictureId>, tl0picidx: Option<u8>) {
+        if let Some(picture_id) = picture_id {
+            picture_id.write(&mut payload[self.picture_id_offset..]);
+        }
+        if let Some(tl0picidx) = tl0picidx {
+            payload[self.tl0picidx_offset] = tl0picidx;
+        }
+    }
+}
+
+struct Encoding {
+    pad: gst::Pad,
+    id: u32,
+    caps: Option<gst::Caps>,
+    codec: Option<Codec>,
+    clock_rate: u32,
+    eos: bool,
+}
+
+/// State of the forwarded stream.
+#[derive(Default)]
+struct Output {
+    /// Id of the encoding that is currently forwarded.
+    encoding: Option<u32>,
+    ssrc: Option<u32>,
+    spatial_layer: u8,
+    temporal_layer: u8,
+    /// Whether the packets of the current frame are forwarded.
+    forward_frame: bool,
+
+    /// Offsets between the values of the current encoding and the forwarded stream.
+    seqnum_offset: u16,
+    rtptime_offset: u32,
+    picture_id_offset: u16,
+    tl0picidx_offset: u8,
+
+    last_in_seqnum: Option<u16>,
+    last_seqnum: Option<u16>,
+    last_rtptime: Option<u32>,
+    last_pts: Option<gst::ClockTime>,
+    last_picture_id: Option<u16>,
+    last_tl0picidx: Option<u8>,
+
+    last_keyframe_request: Option<Instant>,
+}
+
+impl Output {
+    /// Switch to a new encoding, starting with the keyframe described by `info`.
+    fn switch_encoding(
+        &mut self,
+        encoding: u32,
+        info: &PacketInfo,
+        seqnum: u16,
+        rtptime: u32,
+        pts: Option<gst::ClockTime>,
+        clock_rate: u32,
+    ) {
+        self.encoding = Some(encoding);
+        self.spatial_layer = 0;
+        self.temporal_layer = 0;
+        self.last_in_seqnum = None;
+
+        self.seqnum_offset = self
+            .last_seqnum
+            .map_or(0, |last| last.wrapping_add(1).wrapping_sub(seqnum));
+
+        let out_rtptime = match (self.last_rtptime, self.last_pts, pts) {
+            (Some(last), Some(last_pts), Some(pts)) if pts > last_pts => last.wrapping_add(
+                (pts - last_pts)
+                    .nseconds()
+                    .mul_div_floor(clock_rate as u64, *gst::ClockTime::SECOND)
+                    .unwrap_or(0) as u32,
+            ),
+            // Assume 30 frames per second if there's no better information
+            (Some(last), ..) => last.wrapping_add(clock_rate / 30),
+            (None, ..) => rtptime,
+        };
+        self.rtptime_offset = out_rtptime.wrapping_sub(rtptime);
+
+        self.picture_id_offset = match (self.last_picture_id, info.picture_id) {
+            (Some(last), Some(picture_id)) => last.wrapping_add(1).wrapping_sub(picture_id.value),
+            _ => 0,
+        };
+
+        // Keyframes are always part of the base temporal layer so TL0PICIDX is incremented
+        self.tl0picidx_offset = match (self.last_tl0picidx, info.tl0picidx) {
+            (Some(last), Some(tl0picidx)) => last.wrapping_add(1).wrapping_sub(tl0picidx),
+            _ => 0,
+        };
+    }
+
+    /// Select the layers for the frame starting with the packet described by `info` and return
+    /// whether the frame is forwarded.
+    ///
+    /// Returns `true` as second value if a keyframe is needed for switching up.
+    fn start_frame(
+        &mut self,
+        codec: Codec,
+        info: &PacketInfo,
+        settings: &Settings,
+    ) -> (bool, bool) {
+        let target_temporal = settings.target_temporal_layer;
+        let target_spatial = settings.target_spatial_layer;
+
+        if target_temporal < self.temporal_layer {
+            self.temporal_layer = target_temporal;
+        } else if target_temporal > self.temporal_layer {
+            let switch_up = info.keyframe
+                || match codec {
+                    // Layer sync frames only depend on the base layer
+                    Codec::Vp8 => {
+                        info.temporal_switching_point
+                            && info.temporal_layer == self.temporal_layer + 1
+                    }
+                    // Higher layer frames don't depend on frames before switching up points
+                    Codec::Vp9 => {
+                        info.temporal_switching_point && info.temporal_layer <= self.temporal_layer
+                    }
+                    Codec::Av1 => info.temporal_layer == 0,
+                };
+
+            if switch_up {
+                self.temporal_layer = if codec == Codec::Vp8 && !info.keyframe {
+                    info.temporal_layer
+                } else {
+                    target_temporal
+                };
+            }
+        }
+
+        if target_spatial < self.spatial_layer {
+            self.spatial_layer = target_spatial;
+        } else if target_spatial > self.spatial_layer {
+            match codec {
+                Codec::Vp9 => {
+                    if info.spatial_switching_point && info.spatial_layer == self.spatial_layer + 1
+                    {
+                        self.spatial_layer = info.spatial_layer;
+                    }
+                }
+                Codec::Av1 => {
+                    if info.keyframe {
+                        self.spatial_layer = target_spatial;
+                    }
+                }
+                Codec::Vp8 => (),
+            }
+        }
+
+        let need_keyframe =
+            info.spatial_layer > self.spatial_layer && info.spatial_layer <= target_spatial;
+
+        self.forward_frame =
+            info.temporal_layer <= self.temporal_layer && info.spatial_layer <= self.spatial_layer;
+
+        (self.forward_frame, need_keyframe)
+    }
+
+    fn keyframe_request_due(&mut self, now: Instant) -> bool {
+        if self
+            .last_keyframe_request
+            .is_some_and(|last| now.saturating_duration_since(last) < KEYFRAME_REQUEST_INTERVAL)
+        {
+            return false;
+        }
+
+        self.last_keyframe_request = Some(now);
+        true
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Stats {
+    forwarded: u64,
+    dropped: u64,
+    switches: u64,
+    keyframe_requests: u64,
+}
+
+#[derive(Default)]
+struct State {
+    encodings: Vec<Encoding>,
+    next_encoding_id: u32,
+    output: Output,
+    stats: Stats,
+    stream_start_sent: bool,
+    segment_sent: bool,
+}
+
+impl State {
+    fn reset(&mut self) {
+        self.output = Output::default();
+        self.stats = Stats::default();
+        self.stream_start_sent = false;
+        self.segment_sent = false;
+        for encoding in &mut self.encodings {
+            encoding.eos = false;
+        }
+    }
+}
+
+pub struct RtpLayerSelector {
+    srcpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl RtpLayerSelector {
+    fn sink_chain(
+        &self,
+        pad: &gst::Pad,
+        mut buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = *self.settings.lock().unwrap();
+
+        let mut state = self.state.lock().unwrap();
+        let State {
+            encodings,
+            output,
+            stats,
+            ..
+        } = &mut *state;
+
+        let Some(encoding) = encodings.iter().find(|encoding| &encoding.pad == pad) else {
+            // Pad was released in the meantime
+            return Err(gst::FlowError::Flushing);
+        };
+        let Some(codec) = encoding.codec else {
+            gst::error!(CAT, obj = pad, "No supported caps received before buffer");
+            return Err(gst::FlowError::NotNegotiated);
+        };
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, obj = pad, "Failed to map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        let rtp = match RtpPacket::parse(&map) {
+            Ok(rtp) => rtp,
+            Err(err) => {
+                gst::debug!(CAT, obj = pad, "Dropping non-RTP packet: {err:?}");
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        let info = match PacketInfo::parse(codec, rtp.payload()) {
+            Ok(info) => info,
+            Err(err) => {
+                gst::warning!(CAT, obj = pad, "Dropping invalid packet: {err:#}");
+                stats.dropped += 1;
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+        let seqnum = rtp.sequence_number();
+        let rtptime = rtp.timestamp();
+        let ssrc = rtp.ssrc();
+        drop(map);
+
+        gst::trace!(CAT, obj = pad, "Received packet {seqnum}: {info:?}");
+
+        let mut caps = None;
+        let mut request_keyframe = false;
+
+        if output.encoding != Some(encoding.id) {
+            if encoding.id != settings.target_encoding {
+                gst::trace!(CAT, obj = pad, "Dropping packet of inactive encoding");
+                stats.dropped += 1;
+                return Ok(gst::FlowSuccess::Ok);
+            }
+
+            if !info.keyframe {
+                gst::debug!(CAT, obj = pad, "Waiting for keyframe to switch encoding");
+                stats.dropped += 1;
+                if output.keyframe_request_due(Instant::now()) {
+                    stats.keyframe_requests += 1;
+                    drop(state);
+                    self.request_keyframe(pad);
+                }
+                return Ok(gst::FlowSuccess::Ok);
+            }
+
+            gst::info!(
+                CAT,
+                obj = pad,
+                "Switching from encoding {:?} to encoding {}",
+                output.encoding,
+                encoding.id,
+            );
+            output.switch_encoding(
+                encoding.id,
+                &info,
+                seqnum,
+                rtptime,
+                buffer.pts(),
+                encoding.clock_rate,
+            );
+            stats.switches += 1;
+            caps = encoding.caps.as_ref().map(output_caps);
+        } else if output
+            .last_in_seqnum
+            .is_some_and(|last| (seqnum.wrapping_sub(last) as i16) <= 0)
+        {
+            gst::debug!(CAT, obj = pad, "Dropping late packet {seqnum}");
+            stats.dropped += 1;
+            return Ok(gst::FlowSuccess::Ok);
+        }
+        output.last_in_seqnum = Some(seqnum);
+
+        if info.start_of_frame {
+            let (forward, need_keyframe) = output.start_frame(codec, &info, &settings);
+
+            // Dropped pictures are removed from the VP8 picture ID sequence. VP9 receivers
+            // handle gaps via the TL0PICIDX and flexible mode references are relative to the
+            // picture ID, so they are kept there.
+            if !forward && codec == Codec::Vp8 && info.picture_id.is_some() {
+                output.picture_id_offset = output.picture_id_offset.wrapping_sub(1);
+            }
+
+            if need_keyframe && output.keyframe_request_due(Instant::now()) {
+                stats.keyframe_requests += 1;
+                request_keyframe = true;
+            }
+        }
+
+        if !output.forward_frame {
+            gst::trace!(
+                CAT,
+                obj = pad,
+                "Dropping packet {seqnum} of spatial layer {} and temporal layer {}",
+                info.spatial_layer,
+                info.temporal_layer,
+            );
+            output.seqnum_offset = output.seqnum_offset.wrapping_sub(1);
+            stats.dropped += 1;
+            drop(state);
+
+            if request_keyframe {
+                self.request_keyframe(pad);
+            }
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let out_seqnum = seqnum.wrapping_add(output.seqnum_offset);
+        let out_rtptime = rtptime.wrapping_add(output.rtptime_offset);
+        let out_ssrc = *output.ssrc.get_or_insert(ssrc);
+        let out_picture_id = info
+            .picture_id
+            .map(|picture_id| picture_id.offset(output.picture_id_offset));
+        let out_tl0picidx = info
+            .tl0picidx
+            .map(|tl0picidx| tl0picidx.wrapping_add(output.tl0picidx_offset));
+        let set_marker =
+            codec == Codec::Vp9 && info.end_of_frame && info.spatial_layer == output.spatial_layer;
+
+        output.last_seqnum = Some(out_seqnum);
+        output.last_rtptime = Some(out_rtptime);
+        output.last_pts = buffer.pts().or(output.last_pts);
+        if let Some(picture_id) = out_picture_id {
+            output.last_picture_id = Some(picture_id.value);
+        }
+        if out_tl0picidx.is_some() {
+            output.last_tl0picidx = out_tl0picidx;
+        }
+        stats.forwarded += 1;
+        drop(state);
+
+        if request_keyframe {
+            self.request_keyframe(pad);
+        }
+
+        {
+            let buffer = buffer.make_mut();
+            let mut map = buffer.map_writable().map_err(|_| {
+                gst::error!(CAT, obj = pad, "Failed to map buffer writable");
+                gst::FlowError::Error
+            })?;
+            let mut rtp = RtpPacketMut::parse(&mut map).unwrap();
+
+            rtp.set_sequence_number(out_seqnum);
+            rtp.set_timestamp(out_rtptime);
+            rtp.set_ssrc(out_ssrc);
+            if set_marker {
+                rtp.set_marker_bit(true);
+            }
+            info.rewrite(rtp.payload_mut(), out_picture_id, out_tl0picidx);
+        }
+
+        if let Some(caps) = caps {
+            if self.srcpad.current_caps().as_ref() != Some(&caps) {
+                gst::debug!(CAT, imp = self, "Setting caps {caps:?}");
+                self.srcpad.push_event(gst::event::Caps::new(&caps));
+            }
+        }
+
+        gst::trace!(
+            CAT,
+            obj = pad,
+            "Forwarding packet {seqnum} as {out_seqnum} with timestamp {out_rtptime}",
+        );
+
+        self.srcpad.push(buffer)
+    }
+
+    fn request_keyframe(&self, pad: &gst::Pad) {
+        gst::debug!(CAT, obj = pad, "Requesting keyframe from upstream");
+
+        let event = gst_video::UpstreamForceKeyUnitEvent::builder()
+            .all_headers(true)
+            .build();
+        let _ = pad.push_event(event);
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        let mut state = self.state.lock().unwrap();
+
+        // Sticky events are only forwarded once, caps are sent when switching encodings
+        match event.view() {
+            gst::EventView::StreamStart(_) => {
+                if std::mem::replace(&mut state.stream_start_sent, true) {
+                    return true;
+                }
+            }
+            gst::EventView::Caps(ev) => {
+                let caps = ev.caps_owned();
+                let active_encoding = state.output.encoding;
+                let target_encoding = self.settings.lock().unwrap().target_encoding;
+                let Some(encoding) = state.encodings.iter_mut().find(|e| &e.pad == pad) else {
+                    return false;
+                };
+
+                let Some(codec) = Codec::from_caps(&caps) else {
+                    gst::error!(CAT, obj = pad, "Unsupported caps {caps:?}");
+                    return false;
+                };
+                let clock_rate = caps
+                    .structure(0)
+                    .and_then(|s| s.get::<i32>("clock-rate").ok())
+                    .filter(|&clock_rate| clock_rate > 0)
+                    .unwrap_or(90_000) as u32;
+
+                encoding.codec = Some(codec);
+                encoding.clock_rate = clock_rate;
+                encoding.caps = Some(caps.clone());
+                // Until the first switch the caps of the target encoding are used so that
+                // they're sent before the segment
+                let active =
+                    active_encoding.map_or(encoding.id == target_encoding, |id| id == encoding.id);
+                drop(state);
+
+                if active {
+                    let caps = output_caps(&caps);
+                    if self.srcpad.current_caps().as_ref() != Some(&caps) {
+                        return self.srcpad.push_event(gst::event::Caps::new(&caps));
+                    }
+                }
+
+                return true;
+            }
+            gst::EventView::Segment(_) => {
+                if std::mem::replace(&mut state.segment_sent, true) {
+                    return true;
+                }
+            }
+            gst::EventView::Eos(_) => {
+                if let Some(encoding) = state.encodings.iter_mut().find(|e| &e.pad == pad) {
+                    encoding.eos = true;
+                }
+
+                if !state.encodings.iter().all(|encoding| encoding.eos) {
+                    gst::debug!(CAT, obj = pad, "Waiting for EOS on all encodings");
+                    return true;
+                }
+            }
+            gst::EventView::FlushStop(_) => {
+                let State {
+                    encodings, output, ..
+                } = &mut *state;
+                if let Some(encoding) = encodings.iter_mut().find(|e| &e.pad == pad) {
+                    encoding.eos = false;
+                    if output.encoding == Some(encoding.id) {
+                        output.last_in_seqnum = None;
+                    }
+                }
+            }
+            _ => (),
+        }
+        drop(state);
+
+        gst::Pad::event_default(pad, Some(&*self.obj()), event)
+    }
+
+    fn src_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        // Keyframe requests from downstream only concern the forwarded encoding
+        if gst_video::ForceKeyUnitEvent::is(&event) {
+            let state = self.state.lock().unwrap();
+            let active_pad = state
+                .output
+                .encoding
+                .and_then(|id| state.encodings.iter().find(|encoding| encoding.id == id))
+                .map(|encoding| encoding.pad.clone());
+            drop(state);
+
+            return match active_pad {
+                Some(active_pad) => active_pad.push_event(event),
+                None => false,
+            };
+        }
+
+        gst::Pad::event_default(pad, Some(&*self.obj()), event)
+    }
+
+    fn stats(&self) -> gst::Structure {
+        let state = self.state.lock().unwrap();
+        let output = &state.output;
+        let stats = &state.stats;
+
+        gst::Structure::builder("application/x-rtp-layer-selector-stats")
+            .field("packets-forwarded", stats.forwarded)
+            .field("packets-dropped", stats.dropped)
+            .field("encoding-switches", stats.switches)
+            .field("keyframe-requests", stats.keyframe_requests)
+            .field_if_some("encoding", output.encoding)
+            .field_if_some(
+                "spatial-layer",
+                output.encoding.map(|_| output.spatial_layer as u32),
+            )
+            .field_if_some(
+                "temporal-layer",
+                output.encoding.map(|_| output.temporal_layer as u32),
+            )
+            .build()
+    }
+}
+
+/// Caps of the forwarded stream, without the fields that identify a specific encoding
+fn output_caps(caps: &gst::Caps) -> gst::Caps {
+    let mut caps = caps.clone();
+    if let Some(s) = caps.make_mut().structure_mut(0) {
+        s.remove_fields([
+            "ssrc",
+            "rid",
+            "repaired-rid",
+            "timestamp-offset",
+            "seqnum-offset",
+        ]);
+    }
+
+    caps
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpLayerSelector {
+    const NAME: &'static str = "GstRtpLayerSelector";
+    type Type = super::RtpLayerSelector;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_from_template(&templ)
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.src_event(pad, event))
+            })
+            .build();
+
+        Self {
+            srcpad,
+            settings: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpLayerSelector {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        self.obj().add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("target-encoding")
+                    .nick("Target Encoding")
+                    .blurb("Index of the sink pad with the simulcast encoding to forward")
+                    .default_value(DEFAULT_TARGET_ENCODING)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("target-spatial-layer")
+                    .nick("Target Spatial Layer")
+                    .blurb("Highest spatial layer to forward")
+                    .maximum(MAX_LAYER)
+                    .default_value(DEFAULT_TARGET_SPATIAL_LAYER)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("target-temporal-layer")
+                    .nick("Target Temporal Layer")
+                    .blurb("Highest temporal layer to forward")
+                    .maximum(MAX_LAYER)
+                    .default_value(DEFAULT_TARGET_TEMPORAL_LAYER)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Various statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "target-encoding" => {
+                settings.target_encoding = value.get().expect("type checked upstream");
+            }
+            "target-spatial-layer" => {
+                settings.target_spatial_layer =
+                    value.get::<u32>().expect("type checked upstream") as u8;
+            }
+            "target-temporal-layer" => {
+                settings.target_temporal_layer =
+                    value.get::<u32>().expect("type checked upstream") as u8;
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "target-encoding" => {
+                let settings = self.settings.lock().unwrap();
+                settings.target_encoding.to_value()
+            }
+            "target-spatial-layer" => {
+                let settings = self.settings.lock().unwrap();
+                (settings.target_spatial_layer as u32).to_value()
+            }
+            "target-temporal-layer" => {
+                let settings = self.settings.lock().unwrap();
+                (settings.target_temporal_layer as u32).to_value()
+            }
+            "stats" => self.stats().to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpLayerSelector {}
+
+impl ElementImpl for RtpLayerSelector {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP Layer Selector",
+                "Network/RTP",
+                "Selects simulcast encodings and SVC layers of VP8, VP9 and AV1 RTP streams",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let caps = gst::Caps::builder("application/x-rtp")
+                .field("media", "video")
+                .field("encoding-name", gst::List::new(["VP8", "VP9", "AV1"]))
+                .build();
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink_%u",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn request_new_pad(
+        &self,
+        templ: &gst::PadTemplate,
+        name: Option<&str>,
+        _caps: Option<&gst::Caps>,
+    ) -> Option<gst::Pad> {
+        let mut state = self.state.lock().unwrap();
+
+        let id = match name.and_then(|name| name.strip_prefix("sink_")) {
+            Some(id) => match id.parse::<u32>() {
+                Ok(id) if state.encodings.iter().all(|encoding| encoding.id != id) => id,
+                _ => {
+                    gst::error!(CAT, imp = self, "Invalid or duplicate pad name {name:?}");
+                    return None;
+                }
+            },
+            None => {
+                let mut id = state.next_encoding_id;
+                while state.encodings.iter().any(|encoding| encoding.id == id) {
+                    id += 1;
+                }
+                id
+            }
+        };
+        state.next_encoding_id = state.next_encoding_id.max(id + 1);
+
+        let pad = gst::Pad::builder_from_template(templ)
+            .name(format!("sink_{id}"))
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.sink_event(pad, event))
+            })
+            .build();
+
+        state.encodings.push(Encoding {
+            pad: pad.clone(),
+            id,
+            caps: None,
+            codec: None,
+            clock_rate: 90_000,
+            eos: false,
+        });
+        drop(state);
+
+        pad.set_active(true).unwrap();
+        self.obj().add_pad(&pad).unwrap();
+
+        Some(pad)
+    }
+
+    fn release_pad(&self, pad: &gst::Pad) {
+        let mut state = self.state.lock().unwrap();
+        let State {
+            encodings, output, ..
+        } = &mut *state;
+        // The next encoding starts at a keyframe again
+        if encodings
+            .iter()
+            .any(|encoding| &encoding.pad == pad && output.encoding == Some(encoding.id))
+        {
+            output.encoding = None;
+        }
+        state.encodings.retain(|encoding| &encoding.pad != pad);
+        let all_eos =
+            !state.encodings.is_empty() && state.encodings.iter().all(|encoding| encoding.eos);
+        drop(state);
+
+        pad.set_active(false).unwrap();
+        self.obj().remove_pad(pad).unwrap();
+
+        if all_eos {
+            self.srcpad.push_event(gst::event::Eos::new());
+        }
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        let ret = self.parent_change_state(transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            self.state.lock().unwrap().reset();
+        }
+
+        Ok(ret)
+    }
+}
@@ -0,0 +1,248 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gst_check::Harness;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtplayerselector test");
+    });
+}
+
+struct Vp8Packet {
+    ssrc: u32,
+    seqnum: u16,
+    pts: u64,
+    rtptime: u32,
+    picture_id: u16,
+    tl0picidx: u8,
+    temporal_layer: u8,
+    sync: bool,
+    keyframe: bool,
+}
+
+/// Creates a single packet VP8 frame with a 15 bit picture ID, TL0PICIDX and temporal layer id.
+fn vp8_buffer(packet: Vp8Packet) -> gst::Buffer {
+    let payload = [
+        0b1001_0000,
+        0b1110_0000,
+        0x80 | (packet.picture_id >> 8) as u8,
+        (packet.picture_id & 0xff) as u8,
+        packet.tl0picidx,
+        (packet.temporal_layer << 6) | if packet.sync { 0b0010_0000 } else { 0 },
+        if packet.keyframe { 0x00 } else { 0x01 },
+        0,
+        0,
+        0,
+    ];
+
+    let data = rtp_types::RtpPacketBuilder::new()
+        .payload_type(96)
+        .ssrc(packet.ssrc)
+        .sequence_number(packet.seqnum)
+        .timestamp(packet.rtptime)
+        .marker_bit(true)
+        .payload(payload.as_slice())
+        .write_vec()
+        .unwrap();
+
+    let mut buffer = gst::Buffer::from_mut_slice(data);
+    buffer
+        .get_mut()
+        .unwrap()
+        .set_pts(gst::ClockTime::from_mseconds(packet.pts));
+
+    buffer
+}
+
+/// Returns SSRC, sequence number, RTP timestamp, picture ID and TL0PICIDX of a VP8 packet.
+fn vp8_fields(buffer: &gst::Buffer) -> (u32, u16, u32, u16, u8) {
+    let map = buffer.map_readable().unwrap();
+    let rtp = rtp_types::RtpPacket::parse(&map).unwrap();
+    let payload = rtp.payload();
+
+    (
+        rtp.ssrc(),
+        rtp.sequence_number(),
+        rtp.timestamp(),
+        (((payload[2] & 0x7f) as u16) << 8) | payload[3] as u16,
+        payload[4],
+    )
+}
+
+fn caps() -> gst::Caps {
+    gst::Caps::builder("application/x-rtp")
+        .field("media", "video")
+        .field("clock-rate", 90_000i32)
+        .field("encoding-name", "VP8")
+        .build()
+}
+
+#[test]
+fn test_temporal_layer_dropping() {
+    init();
+
+    let selector = gst::ElementFactory::make("rtplayerselector")
+        .property("target-temporal-layer", 0u32)
+        .build()
+        .unwrap();
+
+    let mut h = Harness::with_element(&selector, Some("sink_0"), Some("src"));
+    h.set_src_caps(caps());
+    h.play();
+
+    // Keyframe, TL1 frame, TL0 frame
+    for (seqnum, picture_id, tl0picidx, temporal_layer, keyframe) in [
+        (100, 10, 5, 0, true),
+        (101, 11, 5, 1, false),
+        (102, 12, 6, 0, false),
+    ] {
+        h.push(vp8_buffer(Vp8Packet {
+            ssrc: 0x12345678,
+            seqnum,
+            pts: seqnum as u64 * 33,
+            rtptime: seqnum as u32 * 3000,
+            picture_id,
+            tl0picidx,
+            temporal_layer,
+            sync: false,
+            keyframe,
+        }))
+        .unwrap();
+    }
+
+    assert_eq!(
+        vp8_fields(&h.pull().unwrap()),
+        (0x12345678, 100, 300_000, 10, 5)
+    );
+    assert_eq!(
+        vp8_fields(&h.pull().unwrap()),
+        (0x12345678, 101, 306_000, 11, 6)
+    );
+    assert_eq!(h.buffers_in_queue(), 0);
+
+    // Switching up is only possible at layer sync frames
+    selector.set_property("target-temporal-layer", 1u32);
+    for (seqnum, picture_id, sync) in [(103, 13, false), (104, 14, true)] {
+        h.push(vp8_buffer(Vp8Packet {
+            ssrc: 0x12345678,
+            seqnum,
+            pts: seqnum as u64 * 33,
+            rtptime: seqnum as u32 * 3000,
+            picture_id,
+            tl0picidx: 6,
+            temporal_layer: 1,
+            sync,
+            keyframe: false,
+        }))
+        .unwrap();
+    }
+
+    assert_eq!(
+        vp8_fields(&h.pull().unwrap()),
+        (0x12345678, 102, 312_000, 12, 6)
+    );
+    assert_eq!(h.buffers_in_queue(), 0);
+
+    let stats = selector.property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u64>("packets-forwarded").unwrap(), 3);
+    assert_eq!(stats.get::<u64>("packets-dropped").unwrap(), 2);
+    assert_eq!(stats.get::<u32>("temporal-layer").unwrap(), 1);
+}
+
+#[test]
+fn test_switch_encoding() {
+    init();
+
+    let selector = gst::ElementFactory::make("rtplayerselector")
+        .build()
+        .unwrap();
+
+    let mut h0 = Harness::with_element(&selector, Some("sink_0"), Some("src"));
+    h0.set_src_caps(caps());
+    h0.play();
+
+    let mut h1 = Harness::with_element(&selector, Some("sink_1"), None);
+    h1.set_src_caps(caps());
+    h1.play();
+
+    let packet0 = |seqnum: u16, keyframe| {
+        vp8_buffer(Vp8Packet {
+            ssrc: 0x1000,
+            seqnum,
+            pts: (seqnum - 100) as u64 * 100,
+            rtptime: (seqnum - 100) as u32 * 9000,
+            picture_id: seqnum,
+            tl0picidx: seqnum as u8,
+            temporal_layer: 0,
+            sync: false,
+            keyframe,
+        })
+    };
+    let packet1 = |seqnum: u16, keyframe| {
+        vp8_buffer(Vp8Packet {
+            ssrc: 0x2000,
+            seqnum,
+            pts: (seqnum - 5000) as u64 * 100,
+            rtptime: 1_000_000 + (seqnum - 5000) as u32 * 9000,
+            picture_id: 300 + seqnum - 5000,
+            tl0picidx: 50 + (seqnum - 5000) as u8,
+            temporal_layer: 0,
+            sync: false,
+            keyframe,
+        })
+    };
+
+    h0.push(packet0(100, true)).unwrap();
+    h1.push(packet1(5000, true)).unwrap();
+    assert_eq!(vp8_fields(&h0.pull().unwrap()), (0x1000, 100, 0, 100, 100));
+    assert_eq!(h0.buffers_in_queue(), 0);
+
+    // The old encoding is forwarded until a keyframe of the new encoding arrives
+    selector.set_property("target-encoding", 1u32);
+    h1.push(packet1(5001, false)).unwrap();
+    h0.push(packet0(101, false)).unwrap();
+    assert_eq!(
+        vp8_fields(&h0.pull().unwrap()),
+        (0x1000, 101, 9000, 101, 101)
+    );
+
+    // A keyframe was requested for the new encoding
+    let mut keyframe_requested = false;
+    while let Some(event) = h1.try_pull_upstream_event() {
+        if gst_video::UpstreamForceKeyUnitEvent::parse(&event).is_ok() {
+            keyframe_requested = true;
+        }
+    }
+    assert!(keyframe_requested);
+
+    h1.push(packet1(5002, true)).unwrap();
+    h0.push(packet0(102, false)).unwrap();
+    assert_eq!(
+        vp8_fields(&h0.pull().unwrap()),
+        (0x1000, 102, 18000, 102, 102)
+    );
+    assert_eq!(h0.buffers_in_queue(), 0);
+
+    h1.push(packet1(5003, false)).unwrap();
+    assert_eq!(
+        vp8_fields(&h0.pull().unwrap()),
+        (0x1000, 103, 27000, 103, 103)
+    );
+
+    let stats = selector.property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u32>("encoding").unwrap(), 1);
+    assert_eq!(stats.get::<u64>("encoding-switches").unwrap(), 2);
+    assert_eq!(stats.get::<u64>("keyframe-requests").unwrap(), 1);
+}
@@ -22,32 +22,52 @@ use gst::glib;
 #[macro_use]
 mod utils;
 
+mod dependency_descriptor;
 mod gcc;
+mod hitlessmerge;
+mod layerselector;
 mod rtpbin2;
 
 mod audio_discont;
 mod baseaudiopay;
 mod basedepay;
 mod basepay;
+mod fec;
+mod h26x;
 
 mod ac3;
 mod amr;
 mod av1;
+mod flexfec;
+mod g722;
+mod h264;
+mod h265;
+mod h266;
 mod jpeg;
 mod klv;
+mod lpcm;
 mod mp2t;
 mod mp4a;
 mod mp4g;
+mod mpa;
 mod opus;
 mod pcmau;
+mod red;
+mod smpte291;
+mod speex;
+mod ulpfec;
 mod vp8;
 mod vp9;
+mod vraw;
 
 #[cfg(test)]
 mod tests;
 
 fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    dependency_descriptor::register(plugin)?;
     gcc::register(plugin)?;
+    hitlessmerge::register(plugin)?;
+    layerselector::register(plugin)?;
     rtpbin2::register(plugin)?;
 
     #[cfg(feature = "doc")]
@@ -71,12 +91,30 @@ fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     av1::depay::register(plugin)?;
     av1::pay::register(plugin)?;
 
+    flexfec::dec::register(plugin)?;
+    flexfec::enc::register(plugin)?;
+
+    g722::depay::register(plugin)?;
+    g722::pay::register(plugin)?;
+
+    h264::depay::register(plugin)?;
+    h264::pay::register(plugin)?;
+
+    h265::depay::register(plugin)?;
+    h265::pay::register(plugin)?;
+
+    h266::depay::register(plugin)?;
+    h266::pay::register(plugin)?;
+
     jpeg::depay::register(plugin)?;
     jpeg::pay::register(plugin)?;
 
     klv::depay::register(plugin)?;
     klv::pay::register(plugin)?;
 
+    lpcm::depay::register(plugin)?;
+    lpcm::pay::register(plugin)?;
+
     mp2t::depay::register(plugin)?;
     mp2t::pay::register(plugin)?;
 
@@ -86,12 +124,30 @@ fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     mp4g::depay::register(plugin)?;
     mp4g::pay::register(plugin)?;
 
+    mpa::depay::register(plugin)?;
+    mpa::pay::register(plugin)?;
+
     opus::depay::register(plugin)?;
     opus::pay::register(plugin)?;
 
     pcmau::depay::register(plugin)?;
     pcmau::pay::register(plugin)?;
 
+    red::dec::register(plugin)?;
+    red::enc::register(plugin)?;
+
+    smpte291::depay::register(plugin)?;
+    smpte291::pay::register(plugin)?;
+
+    speex::depay::register(plugin)?;
+    speex::pay::register(plugin)?;
+
+    ulpfec::dec::register(plugin)?;
+    ulpfec::enc::register(plugin)?;
+
+    vraw::depay::register(plugin)?;
+    vraw::pay::register(plugin)?;
+
     vp8::depay::register(plugin)?;
     vp8::pay::register(plugin)?;
 
@@ -0,0 +1,337 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+use std::sync::LazyLock;
+
+use crate::basedepay::RtpBaseDepay2Ext;
+
+#[derive(Default)]
+pub struct RtpLpcmDepay {
+    state: AtomicRefCell<State>,
+}
+
+#[derive(Default)]
+struct State {
+    info: Option<gst_audio::AudioInfo>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtplpcmdepay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP L16/L24 Depayloader"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpLpcmDepay {
+    const ABSTRACT: bool = true;
+    const NAME: &'static str = "GstRtpLpcmDepay2";
+    type Type = super::RtpLpcmDepay;
+    type ParentType = crate::basedepay::RtpBaseDepay2;
+}
+
+impl ObjectImpl for RtpLpcmDepay {}
+
+impl GstObjectImpl for RtpLpcmDepay {}
+
+impl ElementImpl for RtpLpcmDepay {}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpLpcmDepay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["audio"];
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let s = caps.structure(0).unwrap();
+
+        // Static payload types 10 and 11 are L16 at 44.1kHz with 2 and 1 channels respectively
+        let payload = s.get::<i32>("payload").ok();
+        let clock_rate = s.get::<i32>("clock-rate").unwrap_or(44_100);
+
+        let channels = s
+            .get::<&str>("encoding-params")
+            .ok()
+            .and_then(|params| params.parse::<i32>().ok())
+            .or_else(|| s.get::<i32>("channels").ok())
+            .unwrap_or(match payload {
+                Some(10) => 2,
+                _ => 1,
+            });
+
+        if clock_rate <= 0 || channels <= 0 {
+            gst::error!(
+                CAT,
+                imp = self,
+                "Invalid clock-rate {clock_rate} or channels {channels}"
+            );
+            return false;
+        }
+
+        let format = if self.obj().type_() == super::RtpL16Depay::static_type() {
+            gst_audio::AudioFormat::S16be
+        } else {
+            gst_audio::AudioFormat::S24be
+        };
+
+        let info = match gst_audio::AudioInfo::builder(format, clock_rate as u32, channels as u32)
+            .build()
+        {
+            Ok(info) => info,
+            Err(err) => {
+                gst::error!(CAT, imp = self, "Failed to build audio info: {err}");
+                return false;
+            }
+        };
+
+        let src_caps = match info.to_caps() {
+            Ok(caps) => caps,
+            Err(err) => {
+                gst::error!(CAT, imp = self, "Failed to build caps: {err}");
+                return false;
+            }
+        };
+
+        self.state.borrow_mut().info = Some(info);
+
+        self.obj().set_src_caps(&src_caps);
+
+        true
+    }
+
+    fn handle_packet(
+        &self,
+        packet: &crate::basedepay::Packet,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let state = self.state.borrow();
+        // Always set when caps are set
+        let info = state.info.as_ref().unwrap();
+        let bpf = info.bpf() as usize;
+
+        let payload_len = packet.payload().len();
+        let samples = payload_len / bpf;
+        if samples == 0 {
+            gst::warning!(
+                CAT,
+                imp = self,
+                "Dropping packet with too small payload of {payload_len} bytes"
+            );
+            self.obj().drop_packet(packet);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let mut buffer = packet.payload_subbuffer(..samples * bpf);
+        if samples * bpf != payload_len {
+            gst::warning!(
+                CAT,
+                imp = self,
+                "Discarding {} bytes of incomplete audio frame",
+                payload_len - samples * bpf
+            );
+        }
+
+        let buffer_ref = buffer.get_mut().unwrap();
+        buffer_ref.set_duration(
+            (samples as u64)
+                .mul_div_floor(*gst::ClockTime::SECOND, info.rate() as u64)
+                .map(gst::ClockTime::from_nseconds),
+        );
+
+        // mark start of talkspurt with RESYNC
+        if packet.marker_bit() {
+            buffer_ref.set_flags(gst::BufferFlags::RESYNC);
+        }
+
+        gst::trace!(CAT, imp = self, "Finishing buffer {buffer:?}");
+
+        self.obj().queue_buffer(packet.into(), buffer)
+    }
+}
+
+/**
+ * SECTION:element-rtpL16depay2
+ * @see_also: rtpL16pay2, rtpL24pay2, rtpL24depay2
+ *
+ * Extracts 16 bit linear PCM audio from RTP packets as per [RFC 3551][rfc-3551].
+ *
+ * [rfc-3551]: https://www.rfc-editor.org/rfc/rfc3551.html#section-4.5.11
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc caps='application/x-rtp, media=audio, clock-rate=48000, encoding-name=L16, encoding-params=(string)2' ! rtpjitterbuffer latency=50 ! rtpL16depay2 ! audioconvert ! audioresample ! autoaudiosink
+ * ]| This will depayload an incoming RTP 16 bit stereo audio stream. You can use the #rtpL16pay2
+ * element to create such an RTP stream.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+
+#[derive(Default)]
+pub struct RtpL16Depay;
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpL16Depay {
+    const NAME: &'static str = "GstRtpL16Depay2";
+    type Type = super::RtpL16Depay;
+    type ParentType = super::RtpLpcmDepay;
+}
+
+impl ObjectImpl for RtpL16Depay {}
+
+impl GstObjectImpl for RtpL16Depay {}
+
+impl ElementImpl for RtpL16Depay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP L16 Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Depayload 16 bit linear PCM audio from RTP packets (RFC 3551)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder_full()
+                    .structure(
+                        gst::Structure::builder("application/x-rtp")
+                            .field("media", "audio")
+                            .field("payload", gst::List::new([10i32, 11i32]))
+                            .field("clock-rate", 44_100i32)
+                            .build(),
+                    )
+                    .structure(
+                        gst::Structure::builder("application/x-rtp")
+                            .field("media", "audio")
+                            .field("clock-rate", gst::IntRange::new(1i32, i32::MAX))
+                            .field("encoding-name", "L16")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst_audio::AudioCapsBuilder::new_interleaved()
+                    .format(gst_audio::AudioFormat::S16be)
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpL16Depay {}
+
+impl super::RtpLpcmDepayImpl for RtpL16Depay {}
+
+/**
+ * SECTION:element-rtpL24depay2
+ * @see_also: rtpL24pay2, rtpL16pay2, rtpL16depay2
+ *
+ * Extracts 24 bit linear PCM audio from RTP packets as per [RFC 3190][rfc-3190].
+ *
+ * This can be used for receiving AES67 streams.
+ *
+ * [rfc-3190]: https://www.rfc-editor.org/rfc/rfc3190.html#section-4
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc caps='application/x-rtp, media=audio, clock-rate=48000, encoding-name=L24, encoding-params=(string)2' ! rtpjitterbuffer latency=10 ! rtpL24depay2 ! audioconvert ! audioresample ! autoaudiosink
+ * ]| This will depayload an incoming RTP 24 bit stereo audio stream. You can use the #rtpL24pay2
+ * element to create such an RTP stream.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+
+#[derive(Default)]
+pub struct RtpL24Depay;
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpL24Depay {
+    const NAME: &'static str = "GstRtpL24Depay2";
+    type Type = super::RtpL24Depay;
+    type ParentType = super::RtpLpcmDepay;
+}
+
+impl ObjectImpl for RtpL24Depay {}
+
+impl GstObjectImpl for RtpL24Depay {}
+
+impl ElementImpl for RtpL24Depay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP L24 Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Depayload 24 bit linear PCM audio from RTP packets (RFC 3190)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "audio")
+                    .field("clock-rate", gst::IntRange::new(1i32, i32::MAX))
+                    .field("encoding-name", "L24")
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst_audio::AudioCapsBuilder::new_interleaved()
+                    .format(gst_audio::AudioFormat::S24be)
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpL24Depay {}
+
+impl super::RtpLpcmDepayImpl for RtpL24Depay {}
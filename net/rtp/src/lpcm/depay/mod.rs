@@ -0,0 +1,62 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+pub mod imp;
+
+glib::wrapper! {
+    pub struct RtpLpcmDepay(ObjectSubclass<imp::RtpLpcmDepay>)
+        @extends crate::basedepay::RtpBaseDepay2, gst::Element, gst::Object;
+}
+
+pub trait RtpLpcmDepayImpl:
+    crate::basedepay::RtpBaseDepay2Impl + ObjectSubclass<Type: IsA<RtpLpcmDepay>>
+{
+}
+
+unsafe impl<T: RtpLpcmDepayImpl> IsSubclassable<T> for RtpLpcmDepay {
+    fn class_init(class: &mut glib::Class<Self>) {
+        Self::parent_class_init::<T>(class);
+    }
+}
+
+glib::wrapper! {
+    pub struct RtpL16Depay(ObjectSubclass<imp::RtpL16Depay>)
+        @extends RtpLpcmDepay, crate::basedepay::RtpBaseDepay2, gst::Element, gst::Object;
+}
+
+glib::wrapper! {
+    pub struct RtpL24Depay(ObjectSubclass<imp::RtpL24Depay>)
+        @extends RtpLpcmDepay, crate::basedepay::RtpBaseDepay2, gst::Element, gst::Object;
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    #[cfg(feature = "doc")]
+    {
+        use gst::prelude::*;
+
+        // Make internal base class available in docs
+        crate::lpcm::depay::RtpLpcmDepay::static_type()
+            .mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    }
+
+    gst::Element::register(
+        Some(plugin),
+        "rtpL16depay2",
+        gst::Rank::MARGINAL,
+        RtpL16Depay::static_type(),
+    )?;
+    gst::Element::register(
+        Some(plugin),
+        "rtpL24depay2",
+        gst::Rank::MARGINAL,
+        RtpL24Depay::static_type(),
+    )
+}
@@ -0,0 +1,247 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+use std::sync::LazyLock;
+
+use crate::{baseaudiopay::RtpBaseAudioPay2Ext, basepay::RtpBasePay2Ext};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtplpcmpay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP L16/L24 Payloader"),
+    )
+});
+
+#[derive(Default)]
+pub struct RtpLpcmPay;
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpLpcmPay {
+    const ABSTRACT: bool = true;
+    const NAME: &'static str = "GstRtpLpcmPay2";
+    type Type = super::RtpLpcmPay;
+    type ParentType = crate::baseaudiopay::RtpBaseAudioPay2;
+}
+
+impl ObjectImpl for RtpLpcmPay {}
+
+impl GstObjectImpl for RtpLpcmPay {}
+
+impl ElementImpl for RtpLpcmPay {}
+
+impl crate::basepay::RtpBasePay2Impl for RtpLpcmPay {
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let info = match gst_audio::AudioInfo::from_caps(caps) {
+            Ok(info) => info,
+            Err(err) => {
+                gst::error!(CAT, imp = self, "Invalid caps {caps:?}: {err}");
+                return false;
+            }
+        };
+
+        let src_caps = gst::Caps::builder("application/x-rtp")
+            .field("media", "audio")
+            .field(
+                "encoding-name",
+                if self.obj().type_() == super::RtpL16Pay::static_type() {
+                    "L16"
+                } else {
+                    "L24"
+                },
+            )
+            .field("clock-rate", info.rate() as i32)
+            .field("encoding-params", info.channels().to_string())
+            .field("channels", info.channels() as i32)
+            .build();
+
+        self.obj().set_src_caps(&src_caps);
+        self.obj().set_bpf(info.bpf() as usize);
+
+        true
+    }
+}
+
+impl crate::baseaudiopay::RtpBaseAudioPay2Impl for RtpLpcmPay {}
+
+/**
+ * SECTION:element-rtpL16pay2
+ * @see_also: rtpL16depay2, rtpL24pay2, rtpL24depay2
+ *
+ * Payloads 16 bit linear PCM audio into RTP packets as per [RFC 3551][rfc-3551].
+ *
+ * The packet duration can be configured with the #rtpbaseaudiopay2:min-ptime,
+ * #rtpbaseaudiopay2:max-ptime and #rtpbaseaudiopay2:ptime-multiple properties, or via the
+ * `ptime` and `maxptime` fields of the downstream caps.
+ *
+ * [rfc-3551]: https://www.rfc-editor.org/rfc/rfc3551.html#section-4.5.11
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 audiotestsrc wave=ticks ! audio/x-raw,format=S16BE,rate=48000,channels=2 ! rtpL16pay2 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will generate a 16 bit stereo audio test signal and payload it as RTP and send it out
+ * as UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+
+#[derive(Default)]
+pub struct RtpL16Pay;
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpL16Pay {
+    const NAME: &'static str = "GstRtpL16Pay2";
+    type Type = super::RtpL16Pay;
+    type ParentType = super::RtpLpcmPay;
+}
+
+impl ObjectImpl for RtpL16Pay {}
+
+impl GstObjectImpl for RtpL16Pay {}
+
+impl ElementImpl for RtpL16Pay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP L16 Payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload 16 bit linear PCM Audio into RTP packets (RFC 3551)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst_audio::AudioCapsBuilder::new_interleaved()
+                    .format(gst_audio::AudioFormat::S16be)
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "audio")
+                    .field("encoding-name", "L16")
+                    .field("clock-rate", gst::IntRange::new(1, i32::MAX))
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpL16Pay {}
+
+impl crate::baseaudiopay::RtpBaseAudioPay2Impl for RtpL16Pay {}
+
+impl super::RtpLpcmPayImpl for RtpL16Pay {}
+
+/**
+ * SECTION:element-rtpL24pay2
+ * @see_also: rtpL24depay2, rtpL16pay2, rtpL16depay2
+ *
+ * Payloads 24 bit linear PCM audio into RTP packets as per [RFC 3190][rfc-3190].
+ *
+ * Together with a packet duration of 1ms, configured via the #rtpbaseaudiopay2:min-ptime and
+ * #rtpbaseaudiopay2:max-ptime properties or the `ptime` field of the downstream caps, and a
+ * sampling rate of 48kHz this produces AES67 compatible streams.
+ *
+ * [rfc-3190]: https://www.rfc-editor.org/rfc/rfc3190.html#section-4
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 audiotestsrc wave=ticks ! audio/x-raw,format=S24BE,rate=48000,channels=2 ! rtpL24pay2 min-ptime=1000000 max-ptime=1000000 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will generate a 24 bit stereo audio test signal and payload it as RTP with 1ms
+ * packets and send it out as UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+
+#[derive(Default)]
+pub struct RtpL24Pay;
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpL24Pay {
+    const NAME: &'static str = "GstRtpL24Pay2";
+    type Type = super::RtpL24Pay;
+    type ParentType = super::RtpLpcmPay;
+}
+
+impl ObjectImpl for RtpL24Pay {}
+
+impl GstObjectImpl for RtpL24Pay {}
+
+impl ElementImpl for RtpL24Pay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP L24 Payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload 24 bit linear PCM Audio into RTP packets (RFC 3190)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst_audio::AudioCapsBuilder::new_interleaved()
+                    .format(gst_audio::AudioFormat::S24be)
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "audio")
+                    .field("encoding-name", "L24")
+                    .field("clock-rate", gst::IntRange::new(1, i32::MAX))
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpL24Pay {}
+
+impl crate::baseaudiopay::RtpBaseAudioPay2Impl for RtpL24Pay {}
+
+impl super::RtpLpcmPayImpl for RtpL24Pay {}
@@ -0,0 +1,62 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+pub mod imp;
+
+glib::wrapper! {
+    pub struct RtpLpcmPay(ObjectSubclass<imp::RtpLpcmPay>)
+        @extends crate::baseaudiopay::RtpBaseAudioPay2, crate::basepay::RtpBasePay2, gst::Element, gst::Object;
+}
+
+pub trait RtpLpcmPayImpl:
+    crate::baseaudiopay::RtpBaseAudioPay2Impl + ObjectSubclass<Type: IsA<RtpLpcmPay>>
+{
+}
+
+unsafe impl<T: RtpLpcmPayImpl> IsSubclassable<T> for RtpLpcmPay {
+    fn class_init(class: &mut glib::Class<Self>) {
+        Self::parent_class_init::<T>(class);
+    }
+}
+
+glib::wrapper! {
+    pub struct RtpL16Pay(ObjectSubclass<imp::RtpL16Pay>)
+        @extends RtpLpcmPay, crate::baseaudiopay::RtpBaseAudioPay2, crate::basepay::RtpBasePay2, gst::Element, gst::Object;
+}
+
+glib::wrapper! {
+    pub struct RtpL24Pay(ObjectSubclass<imp::RtpL24Pay>)
+        @extends RtpLpcmPay, crate::baseaudiopay::RtpBaseAudioPay2, crate::basepay::RtpBasePay2, gst::Element, gst::Object;
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    #[cfg(feature = "doc")]
+    {
+        use gst::prelude::*;
+
+        // Make internal base class available in docs
+        crate::lpcm::pay::RtpLpcmPay::static_type()
+            .mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    }
+
+    gst::Element::register(
+        Some(plugin),
+        "rtpL16pay2",
+        gst::Rank::MARGINAL,
+        RtpL16Pay::static_type(),
+    )?;
+    gst::Element::register(
+        Some(plugin),
+        "rtpL24pay2",
+        gst::Rank::MARGINAL,
+        RtpL24Pay::static_type(),
+    )
+}
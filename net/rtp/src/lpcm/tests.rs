@@ -0,0 +1,108 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::tests::{run_test_pipeline, ExpectedBuffer, ExpectedPacket, Source};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtplpcm test");
+    });
+}
+
+#[test]
+fn test_l16() {
+    init();
+
+    let src = "audiotestsrc num-buffers=100 samplesperbuffer=400 ! audio/x-raw,format=S16BE,rate=8000,channels=1";
+    let pay = "rtpL16pay2";
+    let depay = "rtpL16depay2";
+
+    let mut expected_pay = Vec::with_capacity(100);
+    for i in 0..100 {
+        expected_pay.push(vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 50))
+            .size(400 * 2 + 12)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .pt(96)
+            .rtp_time(((i * 400) & 0xffff_ffff) as u32)
+            .marker_bit(i == 0)
+            .build()]);
+    }
+
+    let mut expected_depay = Vec::with_capacity(100);
+    for i in 0..100 {
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 50))
+            .size(400 * 2)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::RESYNC
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .build()]);
+    }
+
+    run_test_pipeline(Source::Bin(src), pay, depay, expected_pay, expected_depay);
+}
+
+#[test]
+fn test_l24_aes67() {
+    init();
+
+    // 10ms input buffers are split into 1ms packets as commonly used for AES67
+    let src = "audiotestsrc num-buffers=10 samplesperbuffer=480 ! audio/x-raw,format=S24BE,rate=48000,channels=2";
+    let pay = "rtpL24pay2 min-ptime=1000000 max-ptime=1000000";
+    let depay = "rtpL24depay2";
+
+    let mut expected_pay = Vec::with_capacity(10);
+    for i in 0..10 {
+        let mut packets = Vec::with_capacity(10);
+        for j in 0..10 {
+            let pos = i * 480 + j * 48;
+            packets.push(
+                ExpectedPacket::builder()
+                    .pts(gst::ClockTime::from_mseconds(pos / 48))
+                    .size(48 * 6 + 12)
+                    .flags(if pos == 0 {
+                        gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+                    } else {
+                        gst::BufferFlags::empty()
+                    })
+                    .pt(96)
+                    .rtp_time((pos & 0xffff_ffff) as u32)
+                    .marker_bit(pos == 0)
+                    .build(),
+            );
+        }
+        expected_pay.push(packets);
+    }
+
+    let mut expected_depay = Vec::with_capacity(100);
+    for i in 0..100 {
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(i))
+            .size(48 * 6)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::RESYNC
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .build()]);
+    }
+
+    run_test_pipeline(Source::Bin(src), pay, depay, expected_pay, expected_depay);
+}
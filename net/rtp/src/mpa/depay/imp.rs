@@ -0,0 +1,205 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpmpadepay2
+ * @see_also: rtpmpapay2, mpegaudioparse, lamemp3enc, mpg123audiodec
+ *
+ * Extracts MPEG-1/2 audio (MP1, MP2, MP3) from RTP packets as per [RFC 2250][rfc-2250].
+ *
+ * Fragments of audio frames are forwarded as is and reassembled by a downstream parser.
+ * Fragments whose preceding fragments were lost are dropped.
+ *
+ * [rfc-2250]: https://www.rfc-editor.org/rfc/rfc2250.html#section-3
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc caps='application/x-rtp, media=audio, clock-rate=90000, payload=14' ! rtpjitterbuffer latency=50 ! rtpmpadepay2 ! mpegaudioparse ! mpg123audiodec ! audioconvert ! audioresample ! autoaudiosink
+ * ]| This will depayload an incoming RTP MPEG audio stream. You can use the #rtpmpapay2 and
+ * lamemp3enc elements to create such an RTP stream.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, subclass::prelude::*};
+
+use std::sync::LazyLock;
+
+use crate::{
+    basedepay::RtpBaseDepay2Ext,
+    mpa::pay::imp::{CLOCK_RATE, HEADER_SIZE},
+};
+
+#[derive(Default)]
+struct State {
+    /// RTP timestamp and offset of the next expected fragment of the current frame.
+    next_fragment: Option<(u64, usize)>,
+}
+
+#[derive(Default)]
+pub struct RtpMpaDepay {
+    state: AtomicRefCell<State>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpmpadepay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP MPEG Audio Depayloader"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpMpaDepay {
+    const NAME: &'static str = "GstRtpMpaDepay2";
+    type Type = super::RtpMpaDepay;
+    type ParentType = crate::basedepay::RtpBaseDepay2;
+}
+
+impl ObjectImpl for RtpMpaDepay {}
+
+impl GstObjectImpl for RtpMpaDepay {}
+
+impl ElementImpl for RtpMpaDepay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP MPEG Audio Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Depayload MPEG Audio from RTP packets (RFC 2250)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder_full()
+                    .structure(
+                        gst::Structure::builder("application/x-rtp")
+                            .field("media", "audio")
+                            .field("payload", 14i32)
+                            .field("clock-rate", CLOCK_RATE)
+                            .build(),
+                    )
+                    .structure(
+                        gst::Structure::builder("application/x-rtp")
+                            .field("media", "audio")
+                            .field("encoding-name", "MPA")
+                            .field("clock-rate", CLOCK_RATE)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("audio/mpeg")
+                    .field("mpegversion", 1i32)
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpMpaDepay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["audio"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, _caps: &gst::Caps) -> bool {
+        let src_caps = gst::Caps::builder("audio/mpeg")
+            .field("mpegversion", 1i32)
+            .build();
+
+        self.obj().set_src_caps(&src_caps);
+
+        true
+    }
+
+    fn flush(&self) {
+        self.state.borrow_mut().next_fragment = None;
+    }
+
+    fn handle_packet(
+        &self,
+        packet: &crate::basedepay::Packet,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.borrow_mut();
+
+        let payload = packet.payload();
+        if payload.len() <= HEADER_SIZE {
+            gst::warning!(CAT, imp = self, "Dropping too short packet");
+            state.next_fragment = None;
+            self.obj().drop_packet(packet);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let frag_offset = u16::from_be_bytes([payload[2], payload[3]]) as usize;
+        let ext_timestamp = packet.ext_timestamp();
+
+        if packet.discont() {
+            state.next_fragment = None;
+        }
+
+        // A fragment can only be used if all previous fragments of the frame were received
+        if frag_offset != 0 && state.next_fragment != Some((ext_timestamp, frag_offset)) {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Dropping fragment at offset {frag_offset} because of missing previous fragments"
+            );
+            state.next_fragment = None;
+            self.obj().drop_packet(packet);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        state.next_fragment = Some((ext_timestamp, frag_offset + payload.len() - HEADER_SIZE));
+        drop(state);
+
+        let mut buffer = packet.payload_subbuffer(HEADER_SIZE..);
+
+        // mark start of talkspurt with RESYNC
+        if packet.marker_bit() {
+            buffer
+                .get_mut()
+                .unwrap()
+                .set_flags(gst::BufferFlags::RESYNC);
+        }
+
+        gst::trace!(CAT, imp = self, "Finishing buffer {buffer:?}");
+
+        self.obj().queue_buffer(packet.into(), buffer)
+    }
+}
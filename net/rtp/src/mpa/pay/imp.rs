@@ -0,0 +1,309 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpmpapay2
+ * @see_also: rtpmpadepay2, mpegaudioparse, lamemp3enc, mpg123audiodec
+ *
+ * Payloads MPEG-1/2 audio (MP1, MP2, MP3) into RTP packets as per [RFC 2250][rfc-2250].
+ *
+ * Each audio frame is sent in its own RTP packet, or fragmented over multiple packets if it
+ * does not fit into the MTU.
+ *
+ * [rfc-2250]: https://www.rfc-editor.org/rfc/rfc2250.html#section-3
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 audiotestsrc wave=ticks ! lamemp3enc ! mpegaudioparse ! rtpmpapay2 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will encode an audio test signal as MP3 and payload it as RTP and send it out
+ * as UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+use std::sync::{LazyLock, Mutex};
+
+use crate::{
+    audio_discont::{AudioDiscont, AudioDiscontConfiguration},
+    basepay::RtpBasePay2Ext,
+};
+
+/// RTP clock rate of MPEG audio.
+pub(crate) const CLOCK_RATE: i32 = 90_000;
+
+/// Size of the RFC 2250 MPEG audio specific header.
+pub(crate) const HEADER_SIZE: usize = 4;
+
+#[derive(Default)]
+struct State {
+    rate: u32,
+    samples_per_frame: usize,
+    audio_discont: AudioDiscont,
+}
+
+#[derive(Default, Clone)]
+struct Settings {
+    audio_discont: AudioDiscontConfiguration,
+}
+
+#[derive(Default)]
+pub struct RtpMpaPay {
+    state: AtomicRefCell<State>,
+    settings: Mutex<Settings>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpmpapay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP MPEG Audio Payloader"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpMpaPay {
+    const NAME: &'static str = "GstRtpMpaPay2";
+    type Type = super::RtpMpaPay;
+    type ParentType = crate::basepay::RtpBasePay2;
+}
+
+impl ObjectImpl for RtpMpaPay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> =
+            LazyLock::new(AudioDiscontConfiguration::create_pspecs);
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        if self
+            .settings
+            .lock()
+            .unwrap()
+            .audio_discont
+            .set_property(value, pspec)
+        {
+            return;
+        }
+
+        unimplemented!();
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        if let Some(value) = self.settings.lock().unwrap().audio_discont.property(pspec) {
+            return value;
+        }
+
+        unimplemented!();
+    }
+}
+
+impl GstObjectImpl for RtpMpaPay {}
+
+impl ElementImpl for RtpMpaPay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP MPEG Audio Payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload MPEG Audio into RTP packets (RFC 2250)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("audio/mpeg")
+                    .field("mpegversion", 1i32)
+                    .field("layer", gst::IntRange::new(1i32, 3))
+                    .field("rate", gst::IntRange::new(1i32, i32::MAX))
+                    .field("parsed", true)
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder_full()
+                    .structure(
+                        gst::Structure::builder("application/x-rtp")
+                            .field("media", "audio")
+                            .field("payload", 14i32)
+                            .field("clock-rate", CLOCK_RATE)
+                            .build(),
+                    )
+                    .structure(
+                        gst::Structure::builder("application/x-rtp")
+                            .field("media", "audio")
+                            .field("encoding-name", "MPA")
+                            .field("clock-rate", CLOCK_RATE)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpMpaPay {
+    const DEFAULT_PT: u8 = 14;
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["audio"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let s = caps.structure(0).unwrap();
+
+        let (Ok(layer), Ok(rate)) = (s.get::<i32>("layer"), s.get::<i32>("rate")) else {
+            gst::error!(CAT, imp = self, "No layer or rate in caps {caps:?}");
+            return false;
+        };
+        // 1 = MPEG-1, 2 = MPEG-2, 3 = MPEG-2.5
+        let mpegaudioversion = s.get::<i32>("mpegaudioversion").unwrap_or(1);
+
+        let samples_per_frame = match layer {
+            1 => 384,
+            3 if mpegaudioversion != 1 => 576,
+            _ => 1152,
+        };
+
+        let src_caps = gst::Caps::builder("application/x-rtp")
+            .field("media", "audio")
+            .field("encoding-name", "MPA")
+            .field("clock-rate", CLOCK_RATE)
+            .build();
+
+        self.obj().set_src_caps(&src_caps);
+
+        let mut state = self.state.borrow_mut();
+        state.rate = rate as u32;
+        state.samples_per_frame = samples_per_frame;
+
+        true
+    }
+
+    fn flush(&self) {
+        self.state.borrow_mut().audio_discont.reset();
+    }
+
+    fn handle_buffer(
+        &self,
+        buffer: &gst::Buffer,
+        id: u64,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let mut state = self.state.borrow_mut();
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, imp = self, "Can't map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        if map.is_empty() {
+            gst::warning!(CAT, imp = self, "Dropping empty buffer");
+            self.obj().drop_buffers(..=id);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let pts = buffer.pts().unwrap();
+        let rate = state.rate;
+
+        let num_samples = buffer
+            .duration()
+            .and_then(|duration| {
+                duration
+                    .nseconds()
+                    .mul_div_round(rate as u64, *gst::ClockTime::SECOND)
+            })
+            .map(|num_samples| num_samples as usize)
+            .unwrap_or(state.samples_per_frame);
+
+        // The RTP timestamps are based on the PTS as the 90kHz clock is not a multiple of the
+        // sample rate, so the discontinuity detection is only used for setting the marker bit.
+        let discont = state.audio_discont.process_input(
+            &settings.audio_discont,
+            buffer.flags().contains(gst::BufferFlags::DISCONT),
+            rate,
+            pts,
+            num_samples,
+        );
+        if discont {
+            gst::debug!(CAT, imp = self, "Resyncing because of discontinuity");
+            state.audio_discont.resync(pts, num_samples);
+        }
+
+        let mut marker = state.audio_discont.next_output_offset().is_none();
+
+        let max_payload_size = self.obj().max_payload_size() as usize;
+        if max_payload_size <= HEADER_SIZE {
+            gst::error!(CAT, imp = self, "MTU too small");
+            return Err(gst::FlowError::Error);
+        }
+        let max_fragment_size = max_payload_size - HEADER_SIZE;
+
+        if map.len() > u16::MAX as usize {
+            gst::error!(CAT, imp = self, "Too large frame of {} bytes", map.len());
+            return Err(gst::FlowError::Error);
+        }
+
+        for (idx, fragment) in map.chunks(max_fragment_size).enumerate() {
+            let frag_offset = (idx * max_fragment_size) as u16;
+            let header = [0, 0, (frag_offset >> 8) as u8, (frag_offset & 0xff) as u8];
+
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Queueing fragment of {} bytes at offset {frag_offset}",
+                fragment.len()
+            );
+
+            // All fragments of a frame have the same timestamp
+            self.obj().queue_packet(
+                id.into(),
+                rtp_types::RtpPacketBuilder::new()
+                    .marker_bit(marker)
+                    .payload(header.as_slice())
+                    .payload(fragment),
+            )?;
+
+            marker = false;
+        }
+
+        state.audio_discont.process_output(num_samples);
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
@@ -0,0 +1,158 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::tests::{run_test_pipeline, ExpectedBuffer, ExpectedPacket, Source};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtpmpa test");
+    });
+}
+
+/// Creates MP3 frames of 300 bytes. At 48kHz each frame has 1152 samples, i.e. 24ms.
+fn mpa_buffers(num_buffers: usize) -> (gst::Caps, Vec<gst::Buffer>) {
+    let caps = gst::Caps::builder("audio/mpeg")
+        .field("mpegversion", 1i32)
+        .field("mpegaudioversion", 1i32)
+        .field("layer", 3i32)
+        .field("rate", 48000i32)
+        .field("channels", 2i32)
+        .field("parsed", true)
+        .build();
+
+    let mut buffers = Vec::with_capacity(num_buffers);
+    for i in 0..num_buffers {
+        let mut buffer = gst::Buffer::with_size(300).unwrap();
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(gst::ClockTime::from_mseconds(i as u64 * 24));
+            buffer.set_duration(gst::ClockTime::from_mseconds(24));
+        }
+        buffers.push(buffer);
+    }
+
+    (caps, buffers)
+}
+
+#[test]
+fn test_mpa() {
+    init();
+
+    let (caps, buffers) = mpa_buffers(20);
+    let pay = "rtpmpapay2";
+    let depay = "rtpmpadepay2";
+
+    let mut expected_pay = Vec::with_capacity(20);
+    for i in 0..20 {
+        expected_pay.push(vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 24))
+            .size(316)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .pt(14)
+            .rtp_time((i * 2160) as u32)
+            .marker_bit(i == 0)
+            .build()]);
+    }
+
+    let mut expected_depay = Vec::with_capacity(20);
+    for i in 0..20 {
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 24))
+            .size(300)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::RESYNC
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .build()]);
+    }
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_mpa_fragmented() {
+    init();
+
+    let (caps, buffers) = mpa_buffers(4);
+    // 188 bytes payload per packet, i.e. 184 bytes of audio data after the MPEG audio header
+    let pay = "rtpmpapay2 mtu=200";
+    let depay = "rtpmpadepay2";
+
+    let mut expected_pay = Vec::with_capacity(4);
+    for i in 0..4 {
+        expected_pay.push(vec![
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(i * 24))
+                .size(200)
+                .flags(if i == 0 {
+                    gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+                } else {
+                    gst::BufferFlags::empty()
+                })
+                .pt(14)
+                .rtp_time((i * 2160) as u32)
+                .marker_bit(i == 0)
+                // Drop the first fragment of the third frame
+                .drop(i == 2)
+                .build(),
+            ExpectedPacket::builder()
+                .pts(gst::ClockTime::from_mseconds(i * 24))
+                .size(132)
+                .flags(gst::BufferFlags::empty())
+                .pt(14)
+                .rtp_time((i * 2160) as u32)
+                .marker_bit(false)
+                .build(),
+        ]);
+    }
+
+    // The second fragment of the third frame is dropped by the depayloader
+    let mut expected_depay = Vec::with_capacity(6);
+    for i in [0, 1, 3] {
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 24))
+            .size(184)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::RESYNC
+            } else if i == 3 {
+                gst::BufferFlags::DISCONT
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .build()]);
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 24))
+            .size(116)
+            .flags(gst::BufferFlags::empty())
+            .build()]);
+    }
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
@@ -0,0 +1,426 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpreddec2
+ * @see_also: rtpredenc2, rtpulpfecdec2, rtprecv
+ *
+ * Extract RTP packets from RED packets as per [RFC 2198][rfc-2198].
+ *
+ * The primary block of every RED packet is forwarded as an RTP packet with the payload type of the
+ * block and the sequence number of the RED packet. Redundant blocks are only forwarded if the
+ * packet they are a copy of was not received, and are assumed to belong to the directly
+ * preceding sequence numbers. Packets with other payload types are passed through unchanged.
+ *
+ * The RED payload type is taken from the #rtpreddec2:pt property or otherwise from the input caps
+ * if their encoding name is RED. The output caps for the payload types of the blocks are looked up
+ * in the #rtpreddec2:pt-map. Payload types without an entry, e.g. the ULPFEC payload type, keep
+ * the current caps.
+ *
+ * [rfc-2198]: https://www.rfc-editor.org/rfc/rfc2198.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc port=5004 caps='application/x-rtp,media=audio,payload=100,clock-rate=48000,encoding-name=RED' ! rtpreddec2 pt-map='application/x-rtp2-pt-map,96=(GstCaps)"application/x-rtp,media=audio,payload=96,clock-rate=48000,encoding-name=OPUS"' ! rtpjitterbuffer ! rtpopusdepay2 ! opusdec ! autoaudiosink
+ * ]| This will receive an Opus stream in RED encapsulation.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, prelude::*, subclass::prelude::*};
+use rtp_types::{RtpPacket, RtpPacketBuilder};
+use std::collections::VecDeque;
+use std::sync::{LazyLock, Mutex};
+
+use crate::red::parse_red_payload;
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpreddec2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP RED Decoder"),
+    )
+});
+
+const DEFAULT_PT: u32 = 255;
+/// Number of recently received sequence numbers to remember.
+const MAX_RECEIVED_SEQNUMS: usize = 64;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    pt: u32,
+    pt_map: Option<gst::Structure>,
+}
+
+#[derive(Default)]
+struct State {
+    /// RED payload type from the caps.
+    caps_pt: Option<u8>,
+    /// Payload type of the last caps that were sent downstream.
+    current_pt: Option<u8>,
+    received: VecDeque<u16>,
+    red_packets: u64,
+    redundant_packets: u64,
+}
+
+impl State {
+    fn mark_received(&mut self, seqnum: u16) {
+        self.received.push_back(seqnum);
+        while self.received.len() > MAX_RECEIVED_SEQNUMS {
+            self.received.pop_front();
+        }
+    }
+}
+
+pub struct RtpRedDec {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl RtpRedDec {
+    fn caps_for_pt(settings: &Settings, pt: u8) -> Option<gst::Caps> {
+        settings
+            .pt_map
+            .as_ref()?
+            .get::<gst::Caps>(pt.to_string())
+            .ok()
+    }
+
+    fn sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, imp = self, "Failed to map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        let rtp = match RtpPacket::parse(&map) {
+            Ok(rtp) => rtp,
+            Err(err) => {
+                gst::warning!(CAT, imp = self, "Dropping invalid RTP packet: {err:?}");
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+
+        let red_pt = if settings.pt <= 127 {
+            Some(settings.pt as u8)
+        } else {
+            state.caps_pt
+        };
+        let seqnum = rtp.sequence_number();
+
+        if red_pt != Some(rtp.payload_type()) {
+            state.mark_received(seqnum);
+            drop(state);
+            drop(map);
+            return self.srcpad.push(buffer);
+        }
+
+        let Some((redundant, primary)) = parse_red_payload(rtp.payload()) else {
+            gst::warning!(CAT, imp = self, "Dropping invalid RED packet {seqnum}");
+            return Ok(gst::FlowSuccess::Ok);
+        };
+        state.red_packets += 1;
+
+        let mut packets = vec![];
+        for (i, block) in redundant.iter().enumerate() {
+            let block_seqnum = seqnum.wrapping_sub((redundant.len() - i) as u16);
+            if state.received.contains(&block_seqnum) {
+                continue;
+            }
+
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Using redundant block for missing packet {block_seqnum}"
+            );
+            state.mark_received(block_seqnum);
+            state.redundant_packets += 1;
+            packets.push((
+                block.pt,
+                block_seqnum,
+                rtp.timestamp().wrapping_sub(block.timestamp_offset),
+                false,
+                block.data,
+            ));
+        }
+        state.mark_received(seqnum);
+        packets.push((
+            primary.pt,
+            seqnum,
+            rtp.timestamp(),
+            rtp.marker_bit(),
+            primary.data,
+        ));
+
+        let mut buffers = vec![];
+        for (pt, seqnum, timestamp, marker, data) in packets {
+            let mut builder = RtpPacketBuilder::<&[u8], &[u8]>::new()
+                .payload_type(pt)
+                .ssrc(rtp.ssrc())
+                .sequence_number(seqnum)
+                .timestamp(timestamp)
+                .marker_bit(marker)
+                .payload(data);
+            for csrc in rtp.csrc() {
+                builder = builder.add_csrc(csrc);
+            }
+            if let Some((pattern, data)) = rtp.extension() {
+                builder = builder.extension(pattern, data);
+            }
+
+            let packet = builder.write_vec().map_err(|err| {
+                gst::error!(CAT, imp = self, "Failed to write RTP packet: {err:?}");
+                gst::FlowError::Error
+            })?;
+
+            let caps = if state.current_pt != Some(pt) {
+                Self::caps_for_pt(&settings, pt)
+            } else {
+                None
+            };
+            if caps.is_some() {
+                state.current_pt = Some(pt);
+            }
+
+            let mut packet_buffer = gst::Buffer::from_mut_slice(packet);
+            buffer
+                .copy_into(
+                    packet_buffer.get_mut().unwrap(),
+                    gst::BufferCopyFlags::METADATA,
+                    ..,
+                )
+                .map_err(|err| {
+                    gst::error!(CAT, imp = self, "Failed to copy buffer metadata: {err:?}");
+                    gst::FlowError::Error
+                })?;
+
+            buffers.push((caps, packet_buffer));
+        }
+        drop(state);
+        drop(map);
+
+        for (caps, buffer) in buffers {
+            if let Some(caps) = caps {
+                gst::debug!(CAT, imp = self, "Setting caps {caps:?}");
+                self.srcpad.push_event(gst::event::Caps::new(&caps));
+            }
+            self.srcpad.push(buffer)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        match event.view() {
+            gst::EventView::Caps(caps) => {
+                let s = caps.caps().structure(0);
+                let caps_pt = s
+                    .filter(|s| {
+                        s.get::<&str>("encoding-name")
+                            .is_ok_and(|encoding_name| encoding_name.eq_ignore_ascii_case("RED"))
+                    })
+                    .and_then(|s| s.get::<i32>("payload").ok())
+                    .filter(|pt| (0..=127).contains(pt))
+                    .map(|pt| pt as u8);
+
+                let mut state = self.state.lock().unwrap();
+                state.caps_pt = caps_pt;
+                state.current_pt = None;
+                drop(state);
+
+                gst::Pad::event_default(pad, Some(&*self.obj()), event)
+            }
+            gst::EventView::FlushStop(_) => {
+                self.state.lock().unwrap().received.clear();
+                gst::Pad::event_default(pad, Some(&*self.obj()), event)
+            }
+            _ => gst::Pad::event_default(pad, Some(&*self.obj()), event),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpRedDec {
+    const NAME: &'static str = "GstRtpRedDec2";
+    type Type = super::RtpRedDec;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_from_template(&templ)
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.sink_event(pad, event))
+            })
+            .flags(gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_from_template(&templ)
+            .flags(gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            settings: Mutex::new(Settings {
+                pt: DEFAULT_PT,
+                pt_map: None,
+            }),
+            state: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpRedDec {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("pt")
+                    .nick("Payload Type")
+                    .blurb("The payload type of RED packets (255 = from the caps)")
+                    .maximum(255)
+                    .default_value(DEFAULT_PT)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("pt-map")
+                    .nick("RTP Payload Type Map")
+                    .blurb("Mapping of RTP payload type to caps")
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Various statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "pt" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.pt = value.get().expect("type checked upstream");
+            }
+            "pt-map" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.pt_map = value.get().expect("type checked upstream");
+                drop(settings);
+                self.state.lock().unwrap().current_pt = None;
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "pt" => {
+                let settings = self.settings.lock().unwrap();
+                settings.pt.to_value()
+            }
+            "pt-map" => {
+                let settings = self.settings.lock().unwrap();
+                settings.pt_map.to_value()
+            }
+            "stats" => {
+                let state = self.state.lock().unwrap();
+                gst::Structure::builder("application/x-rtp-reddec-stats")
+                    .field("red-packets", state.red_packets)
+                    .field("redundant-packets", state.redundant_packets)
+                    .build()
+                    .to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpRedDec {}
+
+impl ElementImpl for RtpRedDec {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP RED Decoder",
+                "Codec/Decoder/Network/RTP",
+                "Extracts RTP packets from RED packets (RFC 2198)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let caps = gst::Caps::new_empty_simple("application/x-rtp");
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        let ret = self.parent_change_state(transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            *self.state.lock().unwrap() = State::default();
+        }
+
+        Ok(ret)
+    }
+}
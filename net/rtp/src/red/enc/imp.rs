@@ -0,0 +1,378 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpredenc2
+ * @see_also: rtpreddec2, rtpulpfecenc2, rtpsend
+ *
+ * Encapsulate RTP packets into RED packets as per [RFC 2198][rfc-2198].
+ *
+ * Every packet is sent as the primary block of a RED packet with the configured payload type and
+ * the same sequence number. With the #rtpredenc2:distance property the payload of up to that many
+ * previous packets is included as redundant blocks.
+ *
+ * The output caps are the input caps with the payload type and encoding name of RED so that the
+ * packets can be directly passed to `rtpsend`.
+ *
+ * [rfc-2198]: https://www.rfc-editor.org/rfc/rfc2198.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 audiotestsrc ! opusenc ! rtpopuspay2 ! rtpredenc2 pt=100 distance=1 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will send an Opus stream with one redundant copy of every packet to localhost
+ * port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, prelude::*, subclass::prelude::*};
+use rtp_types::{RtpPacket, RtpPacketBuilder};
+use std::collections::VecDeque;
+use std::sync::{LazyLock, Mutex};
+
+use crate::red::{write_red_payload, Block, MAX_BLOCK_LENGTH, MAX_TIMESTAMP_OFFSET};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpredenc2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP RED Encoder"),
+    )
+});
+
+const DEFAULT_PT: u32 = 255;
+const DEFAULT_DISTANCE: u32 = 0;
+const MAX_DISTANCE: u32 = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    pt: u32,
+    distance: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            pt: DEFAULT_PT,
+            distance: DEFAULT_DISTANCE,
+        }
+    }
+}
+
+/// Previously sent packet that can be included as redundant block.
+struct HistoryPacket {
+    ssrc: u32,
+    seqnum: u16,
+    timestamp: u32,
+    pt: u8,
+    payload: Vec<u8>,
+}
+
+#[derive(Default)]
+struct State {
+    history: VecDeque<HistoryPacket>,
+}
+
+pub struct RtpRedEnc {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl RtpRedEnc {
+    fn sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = *self.settings.lock().unwrap();
+        if settings.pt > 127 {
+            return self.srcpad.push(buffer);
+        }
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, imp = self, "Failed to map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        let rtp = match RtpPacket::parse(&map) {
+            Ok(rtp) => rtp,
+            Err(err) => {
+                gst::warning!(CAT, imp = self, "Dropping invalid RTP packet: {err:?}");
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+
+        // Only consecutive packets of the same SSRC can be used as redundant blocks
+        let mut redundant = vec![];
+        for (i, prev) in state
+            .history
+            .iter()
+            .rev()
+            .take(settings.distance as usize)
+            .enumerate()
+        {
+            let timestamp_offset = rtp.timestamp().wrapping_sub(prev.timestamp);
+            if prev.ssrc != rtp.ssrc()
+                || prev.seqnum != rtp.sequence_number().wrapping_sub(i as u16 + 1)
+                || timestamp_offset > MAX_TIMESTAMP_OFFSET
+                || prev.payload.len() > MAX_BLOCK_LENGTH
+            {
+                break;
+            }
+
+            redundant.push(Block {
+                pt: prev.pt,
+                timestamp_offset,
+                data: &prev.payload,
+            });
+        }
+        redundant.reverse();
+
+        let payload = write_red_payload(
+            &redundant,
+            Block {
+                pt: rtp.payload_type(),
+                timestamp_offset: 0,
+                data: rtp.payload(),
+            },
+        );
+
+        let mut builder = RtpPacketBuilder::<&[u8], &[u8]>::new()
+            .payload_type(settings.pt as u8)
+            .ssrc(rtp.ssrc())
+            .sequence_number(rtp.sequence_number())
+            .timestamp(rtp.timestamp())
+            .marker_bit(rtp.marker_bit())
+            .payload(payload.as_slice());
+        for csrc in rtp.csrc() {
+            builder = builder.add_csrc(csrc);
+        }
+        if let Some((pattern, data)) = rtp.extension() {
+            builder = builder.extension(pattern, data);
+        }
+
+        let red = builder.write_vec().map_err(|err| {
+            gst::error!(CAT, imp = self, "Failed to write RED packet: {err:?}");
+            gst::FlowError::Error
+        })?;
+
+        if settings.distance > 0 {
+            state.history.push_back(HistoryPacket {
+                ssrc: rtp.ssrc(),
+                seqnum: rtp.sequence_number(),
+                timestamp: rtp.timestamp(),
+                pt: rtp.payload_type(),
+                payload: rtp.payload().to_vec(),
+            });
+        }
+        while state.history.len() > settings.distance as usize {
+            state.history.pop_front();
+        }
+        drop(state);
+
+        drop(map);
+        let mut red_buffer = gst::Buffer::from_mut_slice(red);
+        buffer
+            .copy_into(
+                red_buffer.get_mut().unwrap(),
+                gst::BufferCopyFlags::METADATA,
+                ..,
+            )
+            .map_err(|err| {
+                gst::error!(CAT, imp = self, "Failed to copy buffer metadata: {err:?}");
+                gst::FlowError::Error
+            })?;
+
+        self.srcpad.push(red_buffer)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        match event.view() {
+            gst::EventView::Caps(caps) => {
+                let settings = *self.settings.lock().unwrap();
+                if settings.pt > 127 {
+                    return self.srcpad.push_event(event);
+                }
+
+                let mut caps = caps.caps_owned();
+                {
+                    let caps = caps.make_mut();
+                    caps.set("payload", settings.pt as i32);
+                    caps.set("encoding-name", "RED");
+                }
+
+                self.srcpad.push_event(gst::event::Caps::new(&caps))
+            }
+            gst::EventView::FlushStop(_) => {
+                self.state.lock().unwrap().history.clear();
+                gst::Pad::event_default(pad, Some(&*self.obj()), event)
+            }
+            _ => gst::Pad::event_default(pad, Some(&*self.obj()), event),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpRedEnc {
+    const NAME: &'static str = "GstRtpRedEnc2";
+    type Type = super::RtpRedEnc;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_from_template(&templ)
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.sink_event(pad, event))
+            })
+            .flags(gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_from_template(&templ)
+            .flags(gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            settings: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpRedEnc {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("pt")
+                    .nick("Payload Type")
+                    .blurb("The payload type of RED packets (255 = disabled)")
+                    .maximum(255)
+                    .default_value(DEFAULT_PT)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("distance")
+                    .nick("Distance")
+                    .blurb("Number of previous packets to include as redundant blocks")
+                    .maximum(MAX_DISTANCE)
+                    .default_value(DEFAULT_DISTANCE)
+                    .mutable_playing()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "pt" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.pt = value.get().expect("type checked upstream");
+            }
+            "distance" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.distance = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "pt" => {
+                let settings = self.settings.lock().unwrap();
+                settings.pt.to_value()
+            }
+            "distance" => {
+                let settings = self.settings.lock().unwrap();
+                settings.distance.to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpRedEnc {}
+
+impl ElementImpl for RtpRedEnc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP RED Encoder",
+                "Codec/Encoder/Network/RTP",
+                "Encapsulates RTP packets into RED packets (RFC 2198)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let caps = gst::Caps::new_empty_simple("application/x-rtp");
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        let ret = self.parent_change_state(transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            *self.state.lock().unwrap() = State::default();
+        }
+
+        Ok(ret)
+    }
+}
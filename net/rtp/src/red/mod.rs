@@ -0,0 +1,101 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Redundant audio data (RED) encapsulation as specified in RFC 2198.
+//!
+//! A RED packet carries a list of block headers followed by the block data. All but the last
+//! block are redundant copies of previous packets, the last block is the primary data.
+
+pub mod dec;
+pub mod enc;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum timestamp offset of a redundant block.
+pub(crate) const MAX_TIMESTAMP_OFFSET: u32 = (1 << 14) - 1;
+/// Maximum length of a redundant block.
+pub(crate) const MAX_BLOCK_LENGTH: usize = (1 << 10) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Block<'a> {
+    pub pt: u8,
+    /// Timestamp offset relative to the RTP timestamp of the RED packet. Always zero for the
+    /// primary block.
+    pub timestamp_offset: u32,
+    pub data: &'a [u8],
+}
+
+/// Creates the payload of a RED packet.
+///
+/// The timestamp offsets and lengths of the redundant blocks must fit into the block headers.
+pub(crate) fn write_red_payload(redundant: &[Block], primary: Block) -> Vec<u8> {
+    let len = redundant.iter().map(|b| 4 + b.data.len()).sum::<usize>() + 1 + primary.data.len();
+    let mut payload = Vec::with_capacity(len);
+
+    for block in redundant {
+        assert!(block.timestamp_offset <= MAX_TIMESTAMP_OFFSET);
+        assert!(block.data.len() <= MAX_BLOCK_LENGTH);
+
+        let header = 0x8000_0000
+            | ((block.pt as u32 & 0x7f) << 24)
+            | (block.timestamp_offset << 10)
+            | block.data.len() as u32;
+        payload.extend_from_slice(&header.to_be_bytes());
+    }
+    payload.push(primary.pt & 0x7f);
+
+    for block in redundant {
+        payload.extend_from_slice(block.data);
+    }
+    payload.extend_from_slice(primary.data);
+
+    payload
+}
+
+/// Parses the payload of a RED packet into the redundant blocks and the primary block.
+pub(crate) fn parse_red_payload(payload: &[u8]) -> Option<(Vec<Block>, Block)> {
+    let mut headers = vec![];
+    let mut pos = 0;
+
+    let primary_pt = loop {
+        let first = *payload.get(pos)?;
+        if first & 0x80 == 0 {
+            pos += 1;
+            break first & 0x7f;
+        }
+
+        let header = payload.get(pos..pos + 4)?;
+        let header = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        headers.push((
+            ((header >> 24) & 0x7f) as u8,
+            (header >> 10) & MAX_TIMESTAMP_OFFSET,
+            (header & MAX_BLOCK_LENGTH as u32) as usize,
+        ));
+        pos += 4;
+    };
+
+    let mut redundant = Vec::with_capacity(headers.len());
+    for (pt, timestamp_offset, len) in headers {
+        redundant.push(Block {
+            pt,
+            timestamp_offset,
+            data: payload.get(pos..pos + len)?,
+        });
+        pos += len;
+    }
+
+    let primary = Block {
+        pt: primary_pt,
+        timestamp_offset: 0,
+        data: &payload[pos..],
+    };
+
+    Some((redundant, primary))
+}
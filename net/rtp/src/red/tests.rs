@@ -0,0 +1,137 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst_check::Harness;
+
+use crate::red::{parse_red_payload, write_red_payload, Block};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtpred test");
+    });
+}
+
+fn packet(seqnum: u16, timestamp: u32, payload: &[u8]) -> gst::Buffer {
+    let packet = rtp_types::RtpPacketBuilder::new()
+        .payload_type(96)
+        .ssrc(0x12345678)
+        .sequence_number(seqnum)
+        .timestamp(timestamp)
+        .payload(payload)
+        .write_vec()
+        .unwrap();
+
+    gst::Buffer::from_mut_slice(packet)
+}
+
+#[test]
+fn test_red_payload() {
+    let redundant = [
+        Block {
+            pt: 96,
+            timestamp_offset: 1920,
+            data: &[1, 2, 3],
+        },
+        Block {
+            pt: 97,
+            timestamp_offset: 960,
+            data: &[],
+        },
+    ];
+    let primary = Block {
+        pt: 96,
+        timestamp_offset: 0,
+        data: &[4, 5],
+    };
+
+    let payload = write_red_payload(&redundant, primary);
+    assert_eq!(
+        payload,
+        [0xe0, 0x1e, 0x00, 0x03, 0xe1, 0x0f, 0x00, 0x00, 0x60, 1, 2, 3, 4, 5]
+    );
+
+    let (parsed_redundant, parsed_primary) = parse_red_payload(&payload).unwrap();
+    assert_eq!(parsed_redundant, redundant);
+    assert_eq!(parsed_primary, primary);
+
+    // Truncated block data
+    assert!(parse_red_payload(&payload[..9]).is_none());
+}
+
+#[test]
+fn test_red_redundancy() {
+    init();
+
+    let caps = gst::Caps::builder("application/x-rtp")
+        .field("media", "audio")
+        .field("payload", 96i32)
+        .field("clock-rate", 48000i32)
+        .field("encoding-name", "OPUS")
+        .build();
+
+    let mut enc = Harness::new("rtpredenc2");
+    enc.element().unwrap().set_property("pt", 100u32);
+    enc.element().unwrap().set_property("distance", 1u32);
+    enc.set_src_caps(caps.clone());
+    enc.play();
+
+    let packets = (0..4u16)
+        .map(|i| packet(i, 960 * i as u32, &vec![i as u8; 20]))
+        .collect::<Vec<_>>();
+    for packet in &packets {
+        enc.push(packet.clone()).unwrap();
+    }
+
+    let mut sent = vec![];
+    while let Some(buffer) = enc.try_pull() {
+        sent.push(buffer);
+    }
+    assert_eq!(sent.len(), 4);
+
+    let red_caps = enc.sinkpad().unwrap().current_caps().unwrap();
+    let s = red_caps.structure(0).unwrap();
+    assert_eq!(s.get::<i32>("payload").unwrap(), 100);
+    assert_eq!(s.get::<&str>("encoding-name").unwrap(), "RED");
+
+    let mut dec = Harness::new("rtpreddec2");
+    dec.element().unwrap().set_property(
+        "pt-map",
+        gst::Structure::builder("application/x-rtp2-pt-map")
+            .field("96", caps.clone())
+            .build(),
+    );
+    dec.set_src_caps(red_caps);
+    dec.play();
+
+    // The third packet is recovered from the redundant block of the fourth
+    for (i, buffer) in sent.iter().enumerate() {
+        if i == 2 {
+            continue;
+        }
+        dec.push(buffer.clone()).unwrap();
+    }
+
+    for packet in &packets {
+        let buffer = dec.pull().unwrap();
+        let map = buffer.map_readable().unwrap();
+        let expected = packet.map_readable().unwrap();
+        assert_eq!(map.as_slice(), expected.as_slice());
+    }
+    assert!(dec.try_pull().is_none());
+
+    assert_eq!(dec.sinkpad().unwrap().current_caps().unwrap(), caps);
+
+    let stats = dec.element().unwrap().property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u64>("red-packets").unwrap(), 3);
+    assert_eq!(stats.get::<u64>("redundant-packets").unwrap(), 1);
+}
@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! BUNDLE demultiplexing with the RTP header extensions for the media identification (MID) as
+//! specified in RFC 8843 and the RTP stream identifiers (RID) as specified in RFC 8852.
+//!
+//! The senders include the extensions in the first packets of each SSRC so that the receiver can
+//! map the SSRC to the right media section / simulcast layer before any RTCP was received.  RTX
+//! streams carry the RID of the stream they repair, which is used for routing the retransmitted
+//! packets to the right src pad.
+
+use rtp_types::RtpPacket;
+
+use super::twcc::{extension_id_from_caps, header_extension};
+
+pub(crate) const MID_EXTMAP_URI: &str = "urn:ietf:params:rtp-hdrext:sdes:mid";
+pub(crate) const RID_EXTMAP_URI: &str = "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id";
+pub(crate) const REPAIRED_RID_EXTMAP_URI: &str =
+    "urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id";
+
+/// The header extension ids used for the BUNDLE identifiers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BundleExtensionIds {
+    pub mid: Option<u8>,
+    pub rid: Option<u8>,
+    pub repaired_rid: Option<u8>,
+}
+
+impl BundleExtensionIds {
+    /// Retrieve the header extension ids from the `extmap-N` fields of the provided caps
+    pub(crate) fn from_caps(caps: &gst::CapsRef) -> Self {
+        Self {
+            mid: extension_id_from_caps(caps, MID_EXTMAP_URI),
+            rid: extension_id_from_caps(caps, RID_EXTMAP_URI),
+            repaired_rid: extension_id_from_caps(caps, REPAIRED_RID_EXTMAP_URI),
+        }
+    }
+
+    /// Merge the header extension ids of `other` into `self`
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.mid = other.mid.or(self.mid);
+        self.rid = other.rid.or(self.rid);
+        self.repaired_rid = other.repaired_rid.or(self.repaired_rid);
+    }
+
+    /// Read the BUNDLE identifiers from the header extensions of `rtp`
+    pub(crate) fn read(&self, rtp: &RtpPacket) -> BundleIds {
+        let read = |ext_id: Option<u8>| {
+            ext_id
+                .and_then(|ext_id| header_extension(rtp, ext_id))
+                .and_then(read_sdes_string)
+        };
+
+        BundleIds {
+            mid: read(self.mid),
+            rid: read(self.rid),
+            repaired_rid: read(self.repaired_rid),
+        }
+    }
+}
+
+/// The BUNDLE identifiers of a SSRC
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BundleIds {
+    pub mid: Option<String>,
+    pub rid: Option<String>,
+    /// The RID of the stream that this stream is repairing, e.g. via RTX
+    pub repaired_rid: Option<String>,
+}
+
+impl BundleIds {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.mid.is_none() && self.rid.is_none() && self.repaired_rid.is_none()
+    }
+
+    /// Whether the stream with these identifiers is repaired by the RTX stream with the
+    /// identifiers `rtx`.  Without a repaired RID, the RTX stream repairs the stream of its MID
+    /// that has no RID.
+    pub(crate) fn is_repaired_by(&self, rtx: &BundleIds) -> bool {
+        match (&rtx.mid, &rtx.repaired_rid) {
+            (_, Some(repaired_rid)) => {
+                self.rid.as_ref() == Some(repaired_rid)
+                    && (rtx.mid.is_none() || self.mid == rtx.mid)
+            }
+            (Some(mid), None) => self.mid.as_ref() == Some(mid) && self.rid.is_none(),
+            (None, None) => false,
+        }
+    }
+
+    /// Update with the identifiers of `other` that are set.  Identifiers are only included in some
+    /// packets so unset identifiers keep their previous value.
+    ///
+    /// Returns `true` if any identifier changed.
+    pub(crate) fn update(&mut self, other: BundleIds) -> bool {
+        let mut changed = false;
+
+        for (current, new) in [
+            (&mut self.mid, other.mid),
+            (&mut self.rid, other.rid),
+            (&mut self.repaired_rid, other.repaired_rid),
+        ] {
+            if new.is_some() && *current != new {
+                *current = new;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+// The identifiers are SDES strings without terminating NUL bytes, but one-byte header extensions
+// can't have a length of 0 and might be padded
+fn read_sdes_string(data: &[u8]) -> Option<String> {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    let s = std::str::from_utf8(&data[..end]).ok()?;
+    if s.is_empty() {
+        return None;
+    }
+
+    Some(s.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtpbin2::session::tests::init_logs;
+
+    fn generate_rtp_packet_with_ids(mid: &[u8], rid: &[u8]) -> Vec<u8> {
+        let mut ext = vec![0x10 | (mid.len() as u8 - 1)];
+        ext.extend_from_slice(mid);
+        ext.push(0x20 | (rid.len() as u8 - 1));
+        ext.extend_from_slice(rid);
+        while ext.len() % 4 != 0 {
+            ext.push(0);
+        }
+
+        rtp_types::RtpPacketBuilder::<&[u8], &[u8]>::new()
+            .payload_type(96)
+            .ssrc(0x12345678)
+            .sequence_number(0)
+            .extension(0xBEDE, ext.as_slice())
+            .payload([1, 2, 3, 4].as_slice())
+            .write_vec()
+            .unwrap()
+    }
+
+    #[test]
+    fn extension_ids_from_caps() {
+        init_logs();
+
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("payload", 96i32)
+            .field("extmap-1", MID_EXTMAP_URI)
+            .field("extmap-2", gst::Array::new(["", RID_EXTMAP_URI, ""]))
+            .build();
+        let ids = BundleExtensionIds::from_caps(&caps);
+        assert_eq!(
+            ids,
+            BundleExtensionIds {
+                mid: Some(1),
+                rid: Some(2),
+                repaired_rid: None,
+            }
+        );
+
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("payload", 96i32)
+            .build();
+        assert_eq!(
+            BundleExtensionIds::from_caps(&caps),
+            BundleExtensionIds::default()
+        );
+    }
+
+    #[test]
+    fn read_ids() {
+        init_logs();
+        let ext_ids = BundleExtensionIds {
+            mid: Some(1),
+            rid: Some(2),
+            repaired_rid: Some(3),
+        };
+
+        let data = generate_rtp_packet_with_ids(b"video", b"hi");
+        let rtp = RtpPacket::parse(&data).unwrap();
+        let ids = ext_ids.read(&rtp);
+        assert_eq!(
+            ids,
+            BundleIds {
+                mid: Some(String::from("video")),
+                rid: Some(String::from("hi")),
+                repaired_rid: None,
+            }
+        );
+
+        // Unset identifiers keep their value
+        let mut current = BundleIds {
+            mid: Some(String::from("video")),
+            rid: Some(String::from("lo")),
+            repaired_rid: None,
+        };
+        assert!(current.update(ids.clone()));
+        assert_eq!(current, ids);
+        assert!(!current.update(BundleIds::default()));
+        assert_eq!(current, ids);
+    }
+
+    #[test]
+    fn repaired_by() {
+        let ids = |mid: Option<&str>, rid: Option<&str>, repaired_rid: Option<&str>| BundleIds {
+            mid: mid.map(String::from),
+            rid: rid.map(String::from),
+            repaired_rid: repaired_rid.map(String::from),
+        };
+
+        let hi = ids(Some("0"), Some("hi"), None);
+        let lo = ids(Some("0"), Some("lo"), None);
+        let rtx = ids(Some("0"), None, Some("lo"));
+        assert!(!hi.is_repaired_by(&rtx));
+        assert!(lo.is_repaired_by(&rtx));
+        assert!(lo.is_repaired_by(&ids(None, None, Some("lo"))));
+        assert!(!lo.is_repaired_by(&ids(Some("1"), None, Some("lo"))));
+
+        let audio = ids(Some("1"), None, None);
+        assert!(audio.is_repaired_by(&ids(Some("1"), None, None)));
+        assert!(!lo.is_repaired_by(&ids(Some("0"), None, None)));
+        assert!(!audio.is_repaired_by(&BundleIds::default()));
+    }
+}
@@ -97,6 +97,46 @@ mod imp {
             ret.build()
         }
 
+        pub fn set_remb_bitrate(&self, bitrate: u64) {
+            let Some(session) = self.session() else {
+                return;
+            };
+            let mut session = session.lock().unwrap();
+            session
+                .session
+                .set_remb_bitrate(Some(bitrate).filter(|&bitrate| bitrate > 0));
+        }
+
+        pub fn remb_bitrate(&self) -> u64 {
+            let Some(session) = self.session() else {
+                return 0;
+            };
+            let session = session.lock().unwrap();
+            session.session.remb_bitrate().unwrap_or(0)
+        }
+
+        fn remove_key(&self, ssrc: u32) {
+            let Some(session) = self.session() else {
+                return;
+            };
+            session.lock().unwrap().srtp.remove_key(ssrc);
+        }
+
+        fn clear_keys(&self) {
+            let Some(session) = self.session() else {
+                return;
+            };
+            session.lock().unwrap().srtp.clear_keys();
+        }
+
+        fn rollover_counter(&self, ssrc: u32) -> u32 {
+            let Some(session) = self.session() else {
+                return 0;
+            };
+            let session = session.lock().unwrap();
+            session.srtp.rollover_counter(ssrc).unwrap_or(0)
+        }
+
         pub fn stats(&self) -> Option<gst::Structure> {
             let session = self.session()?;
             let session = session.lock().unwrap();
@@ -114,10 +154,18 @@ mod imp {
     impl ObjectImpl for Rtp2Session {
         fn properties() -> &'static [glib::ParamSpec] {
             static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
-                vec![glib::ParamSpecBoxed::builder::<gst::Structure>("pt-map")
-                    .nick("RTP Payload Type Map")
-                    .blurb("Mapping of RTP payload type to caps")
-                    .build()]
+                vec![
+                    glib::ParamSpecBoxed::builder::<gst::Structure>("pt-map")
+                        .nick("RTP Payload Type Map")
+                        .blurb("Mapping of RTP payload type to caps")
+                        .build(),
+                    glib::ParamSpecUInt64::builder("remb-bitrate")
+                        .nick("REMB Bitrate")
+                        .blurb("Receiver estimated maximum bitrate (in bits per second) to send to remote senders with each RTCP packet (0 = disabled)")
+                        .default_value(0)
+                        .mutable_playing()
+                        .build(),
+                ]
             });
 
             PROPERTIES.as_ref()
@@ -126,6 +174,7 @@ mod imp {
         fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
             match pspec.name() {
                 "pt-map" => self.pt_map().to_value(),
+                "remb-bitrate" => self.remb_bitrate().to_value(),
                 "stats" => self.stats().to_value(),
                 _ => unreachable!(),
             }
@@ -138,6 +187,9 @@ mod imp {
                         .get::<Option<gst::Structure>>()
                         .expect("Type checked upstream"),
                 ),
+                "remb-bitrate" => {
+                    self.set_remb_bitrate(value.get::<u64>().expect("Type checked upstream"))
+                }
                 _ => unreachable!(),
             }
         }
@@ -151,6 +203,99 @@ mod imp {
                     glib::subclass::Signal::builder("bye-ssrc")
                         .param_types([u32::static_type()])
                         .build(),
+                    /**
+                     * GstRtp2Session::new-mid-rid:
+                     * @ssrc: The SSRC of the received stream
+                     * @mid: (nullable): The media identification (MID) of @ssrc
+                     * @rid: (nullable): The RTP stream identifier (RID) of @ssrc
+                     * @repaired_rid: (nullable): The RID of the stream repaired by @ssrc
+                     *
+                     * Emitted when the MID or RID of a received SSRC was discovered or changed,
+                     * before the packet is forwarded. The identifiers are read from the header
+                     * extensions configured via `extmap-N` fields in the pt-map or input caps.
+                     *
+                     * The source pad of the SSRC carries them in the `a-mid` and `rid` caps
+                     * fields.
+                     *
+                     * Also emitted when an RTX SSRC was routed to the stream it repairs, with
+                     * the MID and repaired RID of the RTX stream. Retransmitted packets are
+                     * then output on the source pad of the repaired stream.
+                     */
+                    glib::subclass::Signal::builder("new-mid-rid")
+                        .param_types([
+                            u32::static_type(),
+                            String::static_type(),
+                            String::static_type(),
+                            String::static_type(),
+                        ])
+                        .build(),
+                    /**
+                     * GstRtp2Session::request-key:
+                     * @ssrc: The SSRC to request the key for
+                     *
+                     * Request the SRTP key for @ssrc as `application/x-srtp` caps with the same
+                     * `srtp-key`, `srtp-cipher`, `srtp-auth`, `srtcp-cipher`, `srtcp-auth` and
+                     * `roc` fields as used by `srtpenc` and `srtpdec`.
+                     *
+                     * Only emitted for sessions using SRTP, i.e. when #GstRtpSend:srtp is
+                     * enabled or when the input of `rtprecv` has `application/x-srtp` or
+                     * `application/x-srtcp` caps. Emitted once for each sent or received SSRC
+                     * without a key. Packets of an SSRC without a key are dropped until a key is
+                     * provided, e.g. after removing the missing key with
+                     * #GstRtp2Session::remove-key.
+                     *
+                     * Returns: the caps with the key, or %NULL if there is no key for @ssrc
+                     */
+                    glib::subclass::Signal::builder("request-key")
+                        .param_types([u32::static_type()])
+                        .return_type::<Option<gst::Caps>>()
+                        .build(),
+                    /**
+                     * GstRtp2Session::remove-key:
+                     * @ssrc: The SSRC to remove the key of
+                     *
+                     * Remove the SRTP key of @ssrc. The key is requested again with the next
+                     * packet of @ssrc.
+                     */
+                    glib::subclass::Signal::builder("remove-key")
+                        .param_types([u32::static_type()])
+                        .action()
+                        .class_handler(|args| {
+                            let session = args[0].get::<super::Rtp2Session>().expect("signal arg");
+                            let ssrc = args[1].get::<u32>().expect("signal arg");
+                            session.imp().remove_key(ssrc);
+                            None
+                        })
+                        .build(),
+                    /**
+                     * GstRtp2Session::clear-keys:
+                     *
+                     * Remove all SRTP keys, including the keys configured via caps.
+                     */
+                    glib::subclass::Signal::builder("clear-keys")
+                        .action()
+                        .class_handler(|args| {
+                            let session = args[0].get::<super::Rtp2Session>().expect("signal arg");
+                            session.imp().clear_keys();
+                            None
+                        })
+                        .build(),
+                    /**
+                     * GstRtp2Session::get-rollover-counter:
+                     * @ssrc: The SSRC to get the rollover counter of
+                     *
+                     * Returns: the current SRTP rollover counter of @ssrc
+                     */
+                    glib::subclass::Signal::builder("get-rollover-counter")
+                        .param_types([u32::static_type()])
+                        .return_type::<u32>()
+                        .action()
+                        .class_handler(|args| {
+                            let session = args[0].get::<super::Rtp2Session>().expect("signal arg");
+                            let ssrc = args[1].get::<u32>().expect("signal arg");
+                            Some(session.imp().rollover_counter(ssrc).to_value())
+                        })
+                        .build(),
                 ]
             });
 
@@ -1,18 +1,23 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     sync::{Arc, Mutex},
     task::Waker,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use gst::glib;
+use gst::{glib, prelude::*};
 use std::sync::{LazyLock, OnceLock};
 
+use super::bundle::BundleExtensionIds;
 use super::config::Rtp2Session;
-use super::session::{RtpProfile, Session};
-use super::source::ReceivedRb;
+use super::rtx::{rtx_apt_from_caps, RtxSender};
+use super::session::{RtpProfile, SendReply, Session};
+use super::source::{ReceivedRb, ReceivedXr};
+use super::srtp::{self, Srtp, SrtpPolicy};
+use super::twcc::twcc_extension_id_from_caps;
+use super::RUNTIME;
 
 static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     gst::DebugCategory::new(
@@ -22,6 +27,8 @@ static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     )
 });
 
+pub(crate) const DEFAULT_RTX_MAX_SIZE_PACKETS: u32 = 100;
+
 static SHARED_RTP_STATE: OnceLock<Mutex<HashMap<String, SharedRtpState>>> = OnceLock::new();
 
 #[derive(Debug, Clone)]
@@ -142,6 +149,154 @@ impl SharedSession {
             config: Rtp2Session::new(weak_inner),
         }
     }
+
+    /// Request the SRTP key of `ssrc` from the application if it is not known yet.
+    ///
+    /// Must not be called with the inner lock held.
+    fn ensure_srtp_key(&self, ssrc: u32) {
+        if !self.inner.lock().unwrap().srtp.needs_key(ssrc) {
+            return;
+        }
+
+        let caps = self
+            .config
+            .emit_by_name::<Option<gst::Caps>>("request-key", &[&ssrc]);
+        let policy = caps
+            .as_ref()
+            .and_then(|caps| caps.structure(0))
+            .and_then(|s| match SrtpPolicy::from_structure(s) {
+                Ok(policy) => policy,
+                Err(err) => {
+                    gst::warning!(CAT, "Invalid SRTP key for ssrc {ssrc:#010x}: {err}");
+                    None
+                }
+            });
+        gst::debug!(
+            CAT,
+            "Requested SRTP key for ssrc {ssrc:#010x}, found key: {}",
+            policy.is_some()
+        );
+
+        self.inner.lock().unwrap().srtp.set_policy(ssrc, policy);
+    }
+
+    fn srtp_transform(&self, buffer: gst::Buffer, protect: bool) -> Option<gst::Buffer> {
+        if protect && !self.inner.lock().unwrap().srtp_send {
+            return Some(buffer);
+        }
+
+        let Some((ssrc, is_rtcp)) = buffer
+            .map_readable()
+            .ok()
+            .and_then(|map| srtp::packet_ssrc(&map))
+        else {
+            gst::debug!(CAT, "Dropping invalid SRTP packet");
+            return None;
+        };
+
+        self.ensure_srtp_key(ssrc);
+
+        let map = buffer.map_readable().ok()?;
+        let mut inner = self.inner.lock().unwrap();
+        let res = match (protect, is_rtcp) {
+            (true, false) => inner.srtp.protect_rtp(&map),
+            (true, true) => inner.srtp.protect_rtcp(&map),
+            (false, false) => inner.srtp.unprotect_rtp(&map).map(Some),
+            (false, true) => inner.srtp.unprotect_rtcp(&map).map(Some),
+        };
+        drop(inner);
+
+        let data = match res {
+            Ok(Some(data)) => data,
+            Ok(None) => {
+                gst::debug!(
+                    CAT,
+                    "Dropping packet of ssrc {ssrc:#010x} (rtcp: {is_rtcp}) without SRTP key"
+                );
+                return None;
+            }
+            Err(err) => {
+                gst::debug!(
+                    CAT,
+                    "Dropping packet of ssrc {ssrc:#010x} (rtcp: {is_rtcp}): {err}"
+                );
+                return None;
+            }
+        };
+
+        let mut out = gst::Buffer::from_mut_slice(data);
+        buffer
+            .copy_into(out.get_mut().unwrap(), gst::BufferCopyFlags::METADATA, ..)
+            .ok()?;
+        Some(out)
+    }
+
+    /// Push the queued RTX packets downstream of rtpsend.
+    ///
+    /// The packets are pushed from a separate thread that holds the stream lock of rtpsend's
+    /// sinkpad, so that they are serialized with the sent packets and events without waiting for
+    /// the next sent packet, and without blocking the caller on upstream of rtpsend.
+    ///
+    /// Must not be called with the inner lock held.
+    pub(crate) fn schedule_retransmissions(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.retransmissions_scheduled || inner.pending_retransmissions.is_empty() {
+            return;
+        }
+        let Some(sinkpad) = inner.rtp_send_sinkpad.clone() else {
+            return;
+        };
+        inner.retransmissions_scheduled = true;
+        drop(inner);
+
+        let session = self.clone();
+        RUNTIME.spawn_blocking(move || {
+            // The stream lock has to be taken before the inner lock, as in rtpsend's chain
+            // function.
+            let _stream_lock = sinkpad.stream_lock();
+
+            let mut inner = session.inner.lock().unwrap();
+            inner.retransmissions_scheduled = false;
+            let retransmissions = std::mem::take(&mut inner.pending_retransmissions);
+            let srcpad = inner.rtp_send_srcpad.clone();
+            drop(inner);
+
+            let Some(srcpad) = srcpad else {
+                gst::debug!(CAT, "Can't retransmit packets because of missing srcpad");
+                return;
+            };
+
+            gst::debug!(
+                CAT,
+                obj = srcpad,
+                "Pushing {} retransmissions",
+                retransmissions.len()
+            );
+            for buffer in retransmissions {
+                let Some(buffer) = session.srtp_protect(buffer) else {
+                    continue;
+                };
+                if let Err(err) = srcpad.push(buffer) {
+                    gst::warning!(CAT, obj = srcpad, "Failed to push retransmission: {err:?}");
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Protect an outgoing RTP or RTCP packet if the session sends SRTP.
+    ///
+    /// Returns `None` if the packet has to be dropped, e.g. because there is no key for its SSRC.
+    pub(crate) fn srtp_protect(&self, buffer: gst::Buffer) -> Option<gst::Buffer> {
+        self.srtp_transform(buffer, true)
+    }
+
+    /// Authenticate and decrypt an incoming SRTP or SRTCP packet.
+    ///
+    /// Returns `None` if the packet has to be dropped.
+    pub(crate) fn srtp_unprotect(&self, buffer: gst::Buffer) -> Option<gst::Buffer> {
+        self.srtp_transform(buffer, false)
+    }
 }
 
 #[derive(Debug)]
@@ -154,6 +309,19 @@ pub(crate) struct SharedSessionInner {
 
     pub(crate) rtcp_waker: Option<Waker>,
     pub(crate) rtp_send_sinkpad: Option<gst::Pad>,
+    pub(crate) rtp_send_srcpad: Option<gst::Pad>,
+
+    pub(crate) rtx_sender: RtxSender,
+    /// RTX packets waiting to be pushed downstream of rtpsend
+    pub(crate) pending_retransmissions: VecDeque<gst::Buffer>,
+    /// Maximum number of RTX packets waiting to be pushed
+    pub(crate) rtx_max_size_packets: usize,
+    /// Whether pushing the pending RTX packets is already scheduled
+    retransmissions_scheduled: bool,
+
+    pub(crate) srtp: Srtp,
+    /// Whether sent packets are protected with SRTP
+    pub(crate) srtp_send: bool,
 }
 
 impl SharedSessionInner {
@@ -166,23 +334,67 @@ impl SharedSessionInner {
             pt_map: HashMap::default(),
             rtcp_waker: None,
             rtp_send_sinkpad: None,
+            rtp_send_srcpad: None,
+
+            rtx_sender: RtxSender::new(DEFAULT_RTX_MAX_SIZE_PACKETS as usize),
+            pending_retransmissions: VecDeque::new(),
+            rtx_max_size_packets: DEFAULT_RTX_MAX_SIZE_PACKETS as usize,
+            retransmissions_scheduled: false,
+
+            srtp: Srtp::default(),
+            srtp_send: false,
         }
     }
 
     pub fn clear_pt_map(&mut self) {
         self.pt_map.clear();
+        self.session.set_twcc_extension_id(None);
+        self.session
+            .set_bundle_extension_ids(BundleExtensionIds::default());
     }
 
     pub fn add_caps(&mut self, caps: gst::Caps) {
         let Some((pt, clock_rate)) = pt_clock_rate_from_caps(&caps) else {
             return;
         };
+        if let Some(ext_id) = twcc_extension_id_from_caps(&caps) {
+            self.session.set_twcc_extension_id(Some(ext_id));
+        }
         let caps_clone = caps.clone();
         self.pt_map
             .entry(pt)
             .and_modify(move |entry| *entry = caps)
             .or_insert_with(move || caps_clone);
         self.session.set_pt_clock_rate(pt, clock_rate);
+
+        let mut bundle_extension_ids = BundleExtensionIds::default();
+        for caps in self.pt_map.values() {
+            bundle_extension_ids.merge(&BundleExtensionIds::from_caps(caps));
+        }
+        self.session.set_bundle_extension_ids(bundle_extension_ids);
+    }
+
+    /// Configure the SRTP key from `application/x-srtp` or `application/x-srtcp` caps.
+    ///
+    /// The key applies to the SSRC in the `ssrc` field, or to all SSRCs without a specific key.
+    pub(crate) fn add_srtp_caps(&mut self, caps: &gst::CapsRef) {
+        let Some(s) = caps.structure(0) else {
+            return;
+        };
+        let policy = match SrtpPolicy::from_structure(s) {
+            Ok(Some(policy)) => policy,
+            Ok(None) => return,
+            Err(err) => {
+                gst::warning!(CAT, "Invalid SRTP key in caps {caps:?}: {err}");
+                return;
+            }
+        };
+
+        if let Ok(ssrc) = s.get::<u32>("ssrc") {
+            self.srtp.set_policy(ssrc, Some(policy));
+        } else {
+            self.srtp.set_default_policy(Some(policy));
+        }
     }
 
     pub(crate) fn caps_from_pt(&self, pt: u8) -> gst::Caps {
@@ -193,13 +405,101 @@ impl SharedSessionInner {
         )
     }
 
+    /// The RTX payload type configured for retransmitting packets with `pt`
+    pub(crate) fn rtx_pt_from_pt(&self, pt: u8) -> Option<u8> {
+        self.pt_map
+            .values()
+            .filter_map(|caps| rtx_apt_from_caps(caps))
+            .find_map(|(rtx_pt, apt)| (apt == pt).then_some(rtx_pt))
+    }
+
+    /// The original payload type if `pt` is an RTX payload type
+    pub(crate) fn apt_from_rtx_pt(&self, pt: u8) -> Option<u8> {
+        self.pt_map
+            .get(&pt)
+            .and_then(|caps| rtx_apt_from_caps(caps))
+            .map(|(_rtx_pt, apt)| apt)
+    }
+
+    /// Store a sent packet for answering later retransmission requests
+    ///
+    /// Returns `true` if the RTX stream of `ssrc` changed and the caps have to be updated.
+    pub(crate) fn store_sent_packet(
+        &mut self,
+        ssrc: u32,
+        seqnum: u16,
+        rtx_pt: u8,
+        buffer: gst::Buffer,
+    ) -> bool {
+        let session = &self.session;
+        self.rtx_sender
+            .store_packet(ssrc, seqnum, rtx_pt, buffer, || {
+                session.generate_unused_ssrc()
+            })
+    }
+
+    /// Produce the RTX packets for the requested `seqnums` of `ssrc`.
+    ///
+    /// Returns the RTX packets to send and the RTX SSRCs that were newly added to the session.
+    pub(crate) fn retransmit(
+        &mut self,
+        ssrc: u32,
+        seqnums: &[u16],
+        now: Instant,
+    ) -> (Vec<gst::Buffer>, Vec<u32>) {
+        let mut buffers = Vec::new();
+        let mut new_ssrcs = Vec::new();
+
+        for &seqnum in seqnums {
+            let Some(buffer) = self.rtx_sender.retransmit(ssrc, seqnum) else {
+                continue;
+            };
+            let Ok(mapped) = buffer.map_readable() else {
+                continue;
+            };
+            let Ok(rtp) = rtp_types::RtpPacket::parse(&mapped) else {
+                continue;
+            };
+
+            loop {
+                match self.session.handle_send(&rtp, now) {
+                    SendReply::NewSsrc(rtx_ssrc, _pt) => new_ssrcs.push(rtx_ssrc),
+                    SendReply::Passthrough => {
+                        drop(mapped);
+                        buffers.push(buffer);
+                        break;
+                    }
+                    SendReply::Drop | SendReply::SsrcCollision(_) => break,
+                }
+            }
+        }
+
+        (buffers, new_ssrcs)
+    }
+
+    /// Queue RTX packets to be pushed by `SharedSession::schedule_retransmissions()`, dropping
+    /// the oldest ones if too many are already waiting.
+    pub(crate) fn queue_retransmissions(&mut self, buffers: Vec<gst::Buffer>) {
+        self.pending_retransmissions.extend(buffers);
+        let max = self.rtx_max_size_packets;
+        if self.pending_retransmissions.len() > max {
+            let excess = self.pending_retransmissions.len() - max;
+            self.pending_retransmissions.drain(..excess);
+        }
+    }
+
     pub fn pt_map(&self) -> impl Iterator<Item = (u8, &gst::Caps)> + '_ {
         self.pt_map.iter().map(|(&k, v)| (k, v))
     }
 
     pub fn stats(&self) -> gst::Structure {
         let mut session_stats = gst::Structure::builder("application/x-rtpbin2-session-stats")
-            .field("id", self.id as u64);
+            .field("id", self.id as u64)
+            .field("twcc-feedback-sent", self.session.twcc_feedback_sent())
+            .field(
+                "twcc-feedback-received",
+                self.session.twcc_feedback_received(),
+            );
         for ssrc in self.session.ssrcs() {
             if let Some(ls) = self.session.local_send_source_by_ssrc(ssrc) {
                 let mut source_stats =
@@ -215,6 +515,9 @@ impl SharedSessionInner {
                         source_stats = source_stats.field("clock-rate", clock_rate);
                     }
                 }
+                if let Some(bitrate) = ls.remb_bitrate() {
+                    source_stats = source_stats.field("remb-bitrate", bitrate);
+                }
                 if let Some(sr) = ls.last_sent_sr() {
                     source_stats = source_stats
                         .field("sr-ntptime", sr.ntp_timestamp().as_u64())
@@ -245,6 +548,59 @@ impl SharedSessionInner {
                         source_stats = source_stats.field("report-blocks", rbs);
                     }
                 }
+                let xr_rbs = gst::List::new(ls.received_xr().map(
+                    |(
+                        sender_ssrc,
+                        ReceivedXr {
+                            loss_rle,
+                            statistics_summary,
+                            voip_metrics,
+                        },
+                    )| {
+                        let mut s = gst::Structure::builder("application/x-rtcp-xr-report-block")
+                            .field("sender-ssrc", sender_ssrc);
+                        if let Some(rle) = loss_rle {
+                            s = s
+                                .field("rle-packets", rle.received.len() as u32)
+                                .field("rle-packets-lost", rle.lost() as u32);
+                        }
+                        if let Some(summary) = statistics_summary {
+                            s = s
+                                .field("begin-seq", summary.begin_seq as u32)
+                                .field("end-seq", summary.end_seq as u32)
+                                .field("packets-lost", summary.lost_packets)
+                                .field("packets-duplicate", summary.dup_packets)
+                                .field("min-jitter", summary.min_jitter)
+                                .field("max-jitter", summary.max_jitter)
+                                .field("mean-jitter", summary.mean_jitter)
+                                .field("dev-jitter", summary.dev_jitter);
+                        }
+                        if let Some(voip) = voip_metrics {
+                            let ms = |ms: u16| Duration::from_millis(ms as u64).as_nanos() as u64;
+                            s = s
+                                .field("loss-rate", voip.loss_rate as u32)
+                                .field("discard-rate", voip.discard_rate as u32)
+                                .field("burst-density", voip.burst_density as u32)
+                                .field("gap-density", voip.gap_density as u32)
+                                .field("burst-duration", ms(voip.burst_duration))
+                                .field("gap-duration", ms(voip.gap_duration))
+                                .field("round-trip-delay", ms(voip.round_trip_delay))
+                                .field("jitterbuffer-nominal-delay", ms(voip.jb_nominal))
+                                .field("jitterbuffer-maximum-delay", ms(voip.jb_maximum));
+                        }
+                        s.build()
+                    },
+                ));
+                match xr_rbs.len() {
+                    0 => (),
+                    1 => {
+                        source_stats =
+                            source_stats.field("xr-report-blocks", xr_rbs.first().unwrap().clone());
+                    }
+                    _ => {
+                        source_stats = source_stats.field("xr-report-blocks", xr_rbs);
+                    }
+                }
 
                 // TODO: add jitter, packets-lost
                 session_stats = session_stats.field(ls.ssrc().to_string(), source_stats.build());
@@ -328,14 +684,40 @@ impl SharedSessionInner {
                         source_stats = source_stats.field("report-blocks", rbs);
                     }
                 }
+                if let Some(rtt) = rs.xr_round_trip_time() {
+                    source_stats = source_stats.field("xr-round-trip-time", rtt.as_nanos() as u64);
+                }
+                let ids = rs.bundle_ids();
+                source_stats = source_stats
+                    .field_if_some("mid", ids.mid.as_deref())
+                    .field_if_some("rid", ids.rid.as_deref());
+                let loss_pattern = rs.loss_pattern();
+                if !loss_pattern.is_empty() {
+                    let (burst_duration, gap_duration) =
+                        loss_pattern.burst_gap_duration(rs.packet_duration().unwrap_or_default());
+                    source_stats = source_stats
+                        .field("packets-duplicate", rs.packets_duplicated())
+                        .field("loss-rate", loss_pattern.loss_rate() as u32)
+                        .field("burst-density", loss_pattern.burst_density() as u32)
+                        .field("gap-density", loss_pattern.gap_density() as u32)
+                        .field("burst-duration", burst_duration.as_nanos() as u64)
+                        .field("gap-duration", gap_duration.as_nanos() as u64);
+                }
                 session_stats = session_stats.field(rs.ssrc().to_string(), source_stats.build());
             } else if let Some(rr) = self.session.remote_receive_source_by_ssrc(ssrc) {
-                let source_stats = gst::Structure::builder("application/x-rtpbin2-source-stats")
-                    .field("ssrc", rr.ssrc())
-                    .field("sender", false)
-                    .field("local", false)
-                    .build();
-                session_stats = session_stats.field(rr.ssrc().to_string(), source_stats);
+                let mut source_stats =
+                    gst::Structure::builder("application/x-rtpbin2-source-stats")
+                        .field("ssrc", rr.ssrc())
+                        .field("sender", false)
+                        .field("local", false);
+                if let Some(rtt) = rr.xr_round_trip_time() {
+                    source_stats = source_stats.field("xr-round-trip-time", rtt.as_nanos() as u64);
+                }
+                let ids = rr.bundle_ids();
+                source_stats = source_stats
+                    .field_if_some("mid", ids.mid.as_deref())
+                    .field_if_some("rid", ids.rid.as_deref());
+                session_stats = session_stats.field(rr.ssrc().to_string(), source_stats.build());
             }
         }
 
@@ -1,15 +1,34 @@
 use crate::utils::ExtendedSeqnum;
 use rtp_types::RtpPacket;
 use std::cmp::Ordering;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::{Duration, Instant};
 
+// Delay before requesting a missing packet for the first time, allowing for some reordering
+const RTX_REORDER_DELAY: Duration = Duration::from_millis(10);
+// Interval between retransmission requests if no round trip time is known
+const RTX_DEFAULT_RETRY_TIMEOUT: Duration = Duration::from_millis(40);
+const RTX_MIN_RETRY_TIMEOUT: Duration = Duration::from_millis(10);
+const RTX_MAX_RETRIES: u32 = 5;
+// Gaps bigger than this are considered a discontinuity and are not requested or reported as lost
+const RTX_MAX_GAP: u64 = 1000;
+// Multiple of the interarrival jitter that is used as target latency in adaptive mode
+const ADAPTIVE_JITTER_FACTOR: u32 = 4;
+// Latency changes smaller than this are not applied in adaptive mode
+const ADAPTIVE_HYSTERESIS: Duration = Duration::from_millis(10);
+// Minimum interval between a latency change and a following decrease in adaptive mode
+const ADAPTIVE_DECREASE_INTERVAL: Duration = Duration::from_secs(1);
+// Maximum latency decrease per interval in adaptive mode
+const ADAPTIVE_DECREASE_STEP: Duration = Duration::from_millis(20);
+
 #[derive(Debug, Clone, Copy)]
 struct Stats {
     num_late: u64,
     num_lost: u64,
     num_duplicates: u64,
     num_pushed: u64,
+    num_rtx_requests: u64,
+    num_rtx_success: u64,
 }
 
 impl From<Stats> for gst::Structure {
@@ -19,6 +38,8 @@ impl From<Stats> for gst::Structure {
             .field("num-duplicates", stats.num_duplicates)
             .field("num-lost", stats.num_lost)
             .field("num-pushed", stats.num_pushed)
+            .field("num-rtx-requests", stats.num_rtx_requests)
+            .field("num-rtx-success", stats.num_rtx_success)
             .build()
     }
 }
@@ -40,11 +61,109 @@ pub struct JitterBuffer {
     last_input_ts: Option<u64>,
     stats: Stats,
     flushing: bool,
+    // Only set if retransmission requests are enabled
+    retransmission: Option<Retransmission>,
+    // Only set if the latency is adapted to the network conditions
+    adaptive: Option<AdaptiveLatency>,
+    latency_changed: bool,
+    // Whether missing packets are reported with PollResult::Lost
+    do_lost: bool,
+    last_output_pts: Option<u64>,
+    discont_pending: bool,
+}
+
+#[derive(Debug)]
+struct LostPacket {
+    next_request: Instant,
+    num_requests: u32,
+}
+
+#[derive(Debug, Default)]
+struct Retransmission {
+    // Highest extended seqnum seen so far
+    highest_seqnum: Option<u64>,
+    // Missing packets by extended seqnum
+    lost: BTreeMap<u64, LostPacket>,
+}
+
+#[derive(Debug)]
+struct AdaptiveLatency {
+    min: Duration,
+    max: Duration,
+    // Interarrival jitter estimate in nanoseconds as per RFC 3550 section 6.4.1
+    jitter: i64,
+    // Relative transit time of the previous packet in nanoseconds
+    last_transit: Option<i64>,
+    // Extended seqnum, arrival time and PTS of the packet with the highest seqnum so far
+    highest: Option<(u64, Instant, u64)>,
+    // Peak of the additional delay required by reordered packets, decays with every packet
+    // received in order
+    reorder_delay: Duration,
+    last_change: Option<Instant>,
+}
+
+impl AdaptiveLatency {
+    fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            jitter: 0,
+            last_transit: None,
+            highest: None,
+            reorder_delay: Duration::ZERO,
+            last_change: None,
+        }
+    }
+
+    fn update(&mut self, seqnum: u64, pts: u64, now: Instant, base_times: (Instant, u64)) {
+        let (base_instant, base_ts) = base_times;
+
+        let arrival = now.saturating_duration_since(base_instant).as_nanos() as i64;
+        let transit = arrival - (pts as i64 - base_ts as i64);
+        if let Some(last_transit) = self.last_transit {
+            let d = (transit - last_transit).abs();
+            self.jitter += (d - self.jitter) / 16;
+        }
+        self.last_transit = Some(transit);
+
+        match self.highest {
+            Some((highest_seqnum, highest_arrival, highest_pts)) if seqnum < highest_seqnum => {
+                // Compared to the packet with the highest seqnum, this packet would've needed
+                // to be buffered for this much longer
+                let delay = now.saturating_duration_since(highest_arrival)
+                    + Duration::from_nanos(highest_pts.saturating_sub(pts));
+                trace!(
+                    "Reordered packet {seqnum} (highest {highest_seqnum}) required delay {delay:?}"
+                );
+                self.reorder_delay = self.reorder_delay.max(delay);
+            }
+            _ => {
+                self.reorder_delay -= self.reorder_delay / 256;
+                self.highest = Some((seqnum, now, pts));
+            }
+        }
+    }
+
+    fn target_latency(&self) -> Duration {
+        let jitter = Duration::from_nanos(self.jitter.max(0) as u64);
+
+        (jitter * ADAPTIVE_JITTER_FACTOR + self.reorder_delay).clamp(self.min, self.max)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum PollResult {
-    Forward { id: usize, discont: bool },
+    Forward {
+        id: usize,
+        discont: bool,
+    },
+    // The packets starting at `seqnum` were not received in time
+    Lost {
+        seqnum: u64,
+        num_packets: u64,
+        pts: u64,
+        duration: u64,
+    },
     Drop(usize),
     Timeout(Instant),
     Empty,
@@ -107,11 +226,86 @@ impl JitterBuffer {
                 num_lost: 0,
                 num_duplicates: 0,
                 num_pushed: 0,
+                num_rtx_requests: 0,
+                num_rtx_success: 0,
             },
             flushing: true,
+            retransmission: None,
+            adaptive: None,
+            latency_changed: false,
+            do_lost: false,
+            last_output_pts: None,
+            discont_pending: false,
+        }
+    }
+
+    /// Enable or disable tracking of missing packets for retransmission requests
+    pub fn set_retransmission(&mut self, retransmission: bool) {
+        if retransmission != self.retransmission.is_some() {
+            self.retransmission = retransmission.then(Retransmission::default);
         }
     }
 
+    pub fn retransmission_enabled(&self) -> bool {
+        self.retransmission.is_some()
+    }
+
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    /// Enable or disable adapting the latency between `min` and `max` based on the observed
+    /// interarrival jitter and packet reordering
+    pub fn set_adaptive_latency(&mut self, bounds: Option<(Duration, Duration)>) {
+        self.adaptive = bounds.map(|(min, max)| {
+            let max = max.max(min);
+            let latency = self.latency.clamp(min, max);
+            if latency != self.latency {
+                self.latency = latency;
+                self.latency_changed = true;
+            }
+
+            AdaptiveLatency::new(min, max)
+        });
+    }
+
+    /// Returns the new latency if it was changed since the last call
+    pub fn take_latency_changed(&mut self) -> Option<Duration> {
+        std::mem::take(&mut self.latency_changed).then_some(self.latency)
+    }
+
+    /// Enable or disable reporting of lost packets
+    pub fn set_do_lost(&mut self, do_lost: bool) {
+        self.do_lost = do_lost;
+    }
+
+    fn update_adaptive_latency(&mut self, now: Instant) {
+        let Some(ref mut adaptive) = self.adaptive else {
+            return;
+        };
+
+        let target = adaptive.target_latency();
+        let new_latency = if target >= self.latency + ADAPTIVE_HYSTERESIS {
+            target
+        } else if target + ADAPTIVE_HYSTERESIS <= self.latency
+            && adaptive.last_change.map_or(true, |last_change| {
+                now.saturating_duration_since(last_change) >= ADAPTIVE_DECREASE_INTERVAL
+            })
+        {
+            target.max(self.latency.saturating_sub(ADAPTIVE_DECREASE_STEP))
+        } else {
+            return;
+        };
+
+        debug!(
+            "Changing latency from {:?} to {new_latency:?}, jitter {}ns, reorder delay {:?}",
+            self.latency, adaptive.jitter, adaptive.reorder_delay
+        );
+        adaptive.last_change = Some(now);
+        self.latency = new_latency;
+        self.latency_changed = true;
+    }
+
     pub fn queue_serialized_item(&mut self) -> QueueResult {
         let id = self.packet_counter;
         self.packet_counter += 1;
@@ -130,6 +324,11 @@ impl JitterBuffer {
         trace!("Flush changed from {} to {flushing}", self.flushing);
         self.flushing = flushing;
         self.last_output_seqnum = None;
+        self.last_output_pts = None;
+        self.discont_pending = false;
+        if let Some(ref mut retransmission) = self.retransmission {
+            *retransmission = Retransmission::default();
+        }
     }
 
     pub fn queue_packet(&mut self, rtp: &RtpPacket, mut pts: u64, now: Instant) -> QueueResult {
@@ -139,6 +338,7 @@ impl JitterBuffer {
 
         // From this point on we always work with extended sequence numbers
         let seqnum = self.extended_seqnum.next(rtp.sequence_number());
+        let input_pts = pts;
 
         if let Some(ts) = self.last_input_ts {
             pts = pts.max(ts);
@@ -170,6 +370,44 @@ impl JitterBuffer {
 
         self.seqnums.insert(seqnum);
 
+        if let Some(ref mut adaptive) = self.adaptive {
+            // Safe unwrap, the base times were set above
+            adaptive.update(seqnum, input_pts, now, self.base_times.unwrap());
+            self.update_adaptive_latency(now);
+        }
+
+        if let Some(ref mut retransmission) = self.retransmission {
+            if let Some(lost) = retransmission.lost.remove(&seqnum) {
+                if lost.num_requests > 0 {
+                    trace!("Received requested packet {seqnum}");
+                    self.stats.num_rtx_success += 1;
+                }
+            }
+
+            match retransmission.highest_seqnum {
+                Some(highest) if seqnum > highest => {
+                    if seqnum - highest > RTX_MAX_GAP {
+                        debug!("Not requesting retransmission for gap {highest} - {seqnum}");
+                        retransmission.lost.clear();
+                    } else {
+                        for missing in highest + 1..seqnum {
+                            trace!("Packet {missing} is missing");
+                            retransmission.lost.insert(
+                                missing,
+                                LostPacket {
+                                    next_request: now + RTX_REORDER_DELAY,
+                                    num_requests: 0,
+                                },
+                            );
+                        }
+                    }
+                    retransmission.highest_seqnum = Some(seqnum);
+                }
+                Some(_) => (),
+                None => retransmission.highest_seqnum = Some(seqnum),
+            }
+        }
+
         if let Some(last_output_seqnum) = self.last_output_seqnum {
             if last_output_seqnum >= seqnum {
                 debug!(
@@ -245,6 +483,35 @@ impl JitterBuffer {
         if deadline <= duration_since_base_instant {
             debug!("Packet with id {} is ready", item.id);
 
+            if let (true, Some(last_output_seqnum), Some(last_output_pts)) =
+                (self.do_lost, self.last_output_seqnum, self.last_output_pts)
+            {
+                let gap = item.seqnum - last_output_seqnum;
+                if gap > 1 && gap <= RTX_MAX_GAP {
+                    let num_packets = gap - 1;
+                    // Assume the missing packets were evenly spaced between the surrounding ones
+                    let packet_duration = pts.saturating_sub(last_output_pts) / gap;
+
+                    debug!(
+                        "Packets {} to {} are lost",
+                        last_output_seqnum + 1,
+                        item.seqnum - 1
+                    );
+
+                    self.stats.num_lost += num_packets;
+                    self.last_output_seqnum = Some(item.seqnum - 1);
+                    self.last_output_pts = Some(last_output_pts + num_packets * packet_duration);
+                    self.discont_pending = true;
+
+                    return PollResult::Lost {
+                        seqnum: last_output_seqnum + 1,
+                        num_packets,
+                        pts: last_output_pts + packet_duration,
+                        duration: num_packets * packet_duration,
+                    };
+                }
+            }
+
             let discont = match self.last_output_seqnum {
                 None => true,
                 Some(last_output_seq_ext) => {
@@ -255,8 +522,10 @@ impl JitterBuffer {
                     gap != 1
                 }
             };
+            let discont = std::mem::take(&mut self.discont_pending) || discont;
 
             self.last_output_seqnum = Some(item.seqnum);
+            self.last_output_pts = Some(pts);
             // Safe unwrap, we know the queue isn't empty at this point
             let packet = self.items.pop_first().unwrap();
 
@@ -272,8 +541,72 @@ impl JitterBuffer {
         }
     }
 
+    /// Returns the sequence numbers of missing packets that should be requested now.  `rtt` is the
+    /// current round trip time to the sender, if known.
+    pub fn poll_retransmissions(&mut self, now: Instant, rtt: Option<Duration>) -> Vec<u16> {
+        let Some(ref mut retransmission) = self.retransmission else {
+            return vec![];
+        };
+
+        // Packets that were already skipped over can't be output anymore
+        if let Some(last_output_seqnum) = self.last_output_seqnum {
+            retransmission.lost = retransmission.lost.split_off(&(last_output_seqnum + 1));
+        }
+
+        let retry_timeout = rtt
+            .filter(|rtt| !rtt.is_zero())
+            .map_or(RTX_DEFAULT_RETRY_TIMEOUT, |rtt| {
+                rtt.max(RTX_MIN_RETRY_TIMEOUT)
+            });
+
+        let mut ret = vec![];
+        retransmission.lost.retain(|&seqnum, lost| {
+            if lost.next_request > now {
+                return true;
+            }
+            if lost.num_requests >= RTX_MAX_RETRIES {
+                debug!(
+                    "Giving up on packet {seqnum} after {} requests",
+                    lost.num_requests
+                );
+                return false;
+            }
+
+            trace!("Requesting retransmission of packet {seqnum}");
+            lost.num_requests += 1;
+            lost.next_request = now + retry_timeout;
+            ret.push((seqnum & 0xffff) as u16);
+            true
+        });
+        self.stats.num_rtx_requests += ret.len() as u64;
+
+        ret
+    }
+
+    /// The next time retransmissions need to be requested, if any
+    pub fn next_retransmission_time(&self) -> Option<Instant> {
+        self.retransmission
+            .as_ref()?
+            .lost
+            .values()
+            .map(|lost| lost.next_request)
+            .min()
+    }
+
+    /// Whether a retransmission of the packet with `seqnum` was requested and is still awaited
+    pub fn is_retransmission_requested(&self, seqnum: u16) -> bool {
+        self.retransmission.as_ref().is_some_and(|retransmission| {
+            retransmission
+                .lost
+                .iter()
+                .any(|(&ext, lost)| lost.num_requests > 0 && (ext & 0xffff) as u16 == seqnum)
+        })
+    }
+
     pub fn stats(&self) -> gst::Structure {
-        self.stats.into()
+        let mut stats = gst::Structure::from(self.stats);
+        stats.set("latency", self.latency.as_nanos() as u64);
+        stats
     }
 }
 
@@ -667,4 +1000,187 @@ mod tests {
         jb.set_flushing(false);
         assert_eq!(jb.poll(now), PollResult::Empty);
     }
+
+    fn queue(jb: &mut JitterBuffer, seqnum: u16, pts: u64, now: Instant) -> QueueResult {
+        let rtp_data = generate_rtp_packet(0x12345678, seqnum, (pts / 125_000) as u32, 4);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        jb.queue_packet(&packet, pts, now)
+    }
+
+    #[test]
+    fn lost_packets() {
+        let mut jb = JitterBuffer::new(Duration::from_secs(0));
+        jb.set_do_lost(true);
+        jb.set_flushing(false);
+
+        let now = Instant::now();
+
+        let QueueResult::Queued(id_first) = queue(&mut jb, 0, 0, now) else {
+            unreachable!()
+        };
+        let QueueResult::Queued(id_second) = queue(&mut jb, 3, 60_000_000, now) else {
+            unreachable!()
+        };
+
+        assert_eq!(
+            jb.poll(now),
+            PollResult::Forward {
+                id: id_first,
+                discont: true
+            }
+        );
+        assert_eq!(
+            jb.poll(now),
+            PollResult::Lost {
+                seqnum: 1,
+                num_packets: 2,
+                pts: 20_000_000,
+                duration: 40_000_000,
+            }
+        );
+        assert_eq!(
+            jb.poll(now),
+            PollResult::Forward {
+                id: id_second,
+                discont: true
+            }
+        );
+        assert_stats(&jb, 0, 2, 0, 2);
+
+        // A lost packet arriving afterwards is late
+        assert_eq!(queue(&mut jb, 1, 20_000_000, now), QueueResult::Late);
+    }
+
+    #[test]
+    fn adaptive_latency_jitter() {
+        let mut jb = JitterBuffer::new(Duration::from_millis(20));
+        jb.set_adaptive_latency(Some((
+            Duration::from_millis(20),
+            Duration::from_millis(500),
+        )));
+        jb.set_flushing(false);
+        assert_eq!(jb.take_latency_changed(), None);
+
+        let base = Instant::now();
+
+        // Every other packet arrives 30ms late
+        for i in 0..50u64 {
+            let pts = i * 20_000_000;
+            let arrival = base + Duration::from_nanos(pts) + Duration::from_millis(30 * (i % 2));
+            let QueueResult::Queued(_id) = queue(&mut jb, i as u16, pts, arrival) else {
+                unreachable!()
+            };
+        }
+
+        let latency = jb.take_latency_changed().unwrap();
+        assert_eq!(latency, jb.latency());
+        assert!(latency > Duration::from_millis(80), "{latency:?}");
+        assert!(latency <= Duration::from_millis(120), "{latency:?}");
+        assert_eq!(
+            jb.stats().get::<u64>("latency").unwrap(),
+            latency.as_nanos() as u64
+        );
+
+        // With steady arrival, the latency slowly goes back to the minimum
+        for i in 50..550u64 {
+            let pts = i * 20_000_000;
+            let arrival = base + Duration::from_nanos(pts);
+            queue(&mut jb, i as u16, pts, arrival);
+
+            if i == 100 {
+                let decreased = jb.take_latency_changed().unwrap();
+                assert_eq!(decreased, latency - ADAPTIVE_DECREASE_STEP);
+            }
+        }
+
+        assert_eq!(jb.take_latency_changed(), Some(Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn adaptive_latency_reordering() {
+        let mut jb = JitterBuffer::new(Duration::from_millis(10));
+        jb.set_adaptive_latency(Some((Duration::from_millis(10), Duration::from_secs(1))));
+        jb.set_flushing(false);
+
+        let base = Instant::now();
+
+        queue(&mut jb, 0, 0, base);
+        queue(&mut jb, 2, 40_000_000, base + Duration::from_millis(40));
+        assert_eq!(jb.take_latency_changed(), None);
+
+        // Packet 1 arrives 10ms after packet 2 although it should've arrived 20ms before it, and
+        // additionally increases the jitter to 30ms / 16
+        queue(&mut jb, 1, 20_000_000, base + Duration::from_millis(50));
+        assert_eq!(
+            jb.take_latency_changed(),
+            Some(Duration::from_micros(4 * 1_875 + 30_000))
+        );
+
+        // The increased latency is used for outputting packets
+        assert_eq!(
+            jb.poll(base + Duration::from_millis(20)),
+            PollResult::Timeout(base + Duration::from_micros(37_500))
+        );
+    }
+
+    #[test]
+    fn retransmission_requests() {
+        let mut jb = JitterBuffer::new(Duration::from_secs(1));
+        jb.set_retransmission(true);
+        jb.set_flushing(false);
+
+        let mut now = Instant::now();
+
+        for seqnum in [0, 3] {
+            let rtp_data = generate_rtp_packet(0x12345678, seqnum, 0, 4);
+            let packet = RtpPacket::parse(&rtp_data).unwrap();
+            let QueueResult::Queued(_id) = jb.queue_packet(&packet, 0, now) else {
+                unreachable!()
+            };
+        }
+
+        // Missing packets are only requested after some reordering delay
+        assert!(jb.poll_retransmissions(now, None).is_empty());
+        assert!(!jb.is_retransmission_requested(1));
+        assert_eq!(jb.next_retransmission_time(), Some(now + RTX_REORDER_DELAY));
+
+        now += RTX_REORDER_DELAY;
+        assert_eq!(jb.poll_retransmissions(now, None), vec![1, 2]);
+        assert!(jb.is_retransmission_requested(1));
+        assert!(jb.is_retransmission_requested(2));
+        assert_eq!(
+            jb.next_retransmission_time(),
+            Some(now + RTX_DEFAULT_RETRY_TIMEOUT)
+        );
+        assert!(jb.poll_retransmissions(now, None).is_empty());
+
+        // A retransmitted packet arrives and is not requested again
+        let rtp_data = generate_rtp_packet(0x12345678, 1, 0, 4);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        let QueueResult::Queued(_id) = jb.queue_packet(&packet, 0, now) else {
+            unreachable!()
+        };
+        assert!(!jb.is_retransmission_requested(1));
+
+        // Retries are based on the round trip time
+        now += RTX_DEFAULT_RETRY_TIMEOUT;
+        assert_eq!(
+            jb.poll_retransmissions(now, Some(Duration::from_millis(20))),
+            vec![2]
+        );
+        assert_eq!(
+            jb.next_retransmission_time(),
+            Some(now + Duration::from_millis(20))
+        );
+
+        let stats = jb.stats();
+        assert_eq!(stats.get::<u64>("num-rtx-requests").unwrap(), 3);
+        assert_eq!(stats.get::<u64>("num-rtx-success").unwrap(), 1);
+
+        // Once the following packets are output, the missing packet is not requested anymore
+        now += Duration::from_secs(1);
+        while let PollResult::Forward { .. } = jb.poll(now) {}
+        assert!(jb.poll_retransmissions(now, None).is_empty());
+        assert_eq!(jb.next_retransmission_time(), None);
+    }
 }
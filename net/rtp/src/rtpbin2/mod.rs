@@ -3,15 +3,20 @@
 use gst::glib;
 use gst::prelude::*;
 use std::sync::LazyLock;
+mod bundle;
 mod config;
 mod internal;
 mod jitterbuffer;
 mod rtprecv;
 mod rtpsend;
+mod rtx;
 mod session;
 mod source;
+mod srtp;
 mod sync;
 mod time;
+mod twcc;
+mod xr;
 
 glib::wrapper! {
     pub struct RtpSend(ObjectSubclass<rtpsend::RtpSend>) @extends gst::Element, gst::Object;
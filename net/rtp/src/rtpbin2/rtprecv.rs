@@ -12,18 +12,26 @@ use futures::StreamExt;
 use gst::{glib, prelude::*, subclass::prelude::*};
 use std::sync::LazyLock;
 
+use super::bundle::BundleIds;
 use super::internal::{pt_clock_rate_from_caps, GstRustLogger, SharedRtpState, SharedSession};
 use super::jitterbuffer::{self, JitterBuffer};
+use super::rtx;
 use super::session::{
-    KeyUnitRequestType, RecvReply, RequestRemoteKeyUnitReply, RtcpRecvReply, RtpProfile,
-    RTCP_MIN_REPORT_INTERVAL,
+    KeyUnitRequestType, RecvReply, RequestRemoteKeyUnitReply, RequestRetransmissionReply,
+    RtcpRecvReply, RtpProfile, RTCP_MIN_REPORT_INTERVAL,
 };
 use super::source::SourceState;
+use super::srtp;
 use super::sync;
 
 use crate::rtpbin2::RUNTIME;
 
 const DEFAULT_LATENCY: gst::ClockTime = gst::ClockTime::from_mseconds(200);
+const DEFAULT_DO_RETRANSMISSION: bool = false;
+const DEFAULT_ADAPTIVE_LATENCY: bool = false;
+const DEFAULT_MIN_LATENCY: gst::ClockTime = gst::ClockTime::from_mseconds(20);
+const DEFAULT_MAX_LATENCY: gst::ClockTime = gst::ClockTime::from_mseconds(1000);
+const DEFAULT_DO_LOST: bool = false;
 
 static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     gst::DebugCategory::new(
@@ -38,6 +46,11 @@ struct Settings {
     rtp_id: String,
     latency: gst::ClockTime,
     timestamping_mode: sync::TimestampingMode,
+    do_retransmission: bool,
+    adaptive_latency: bool,
+    min_latency: gst::ClockTime,
+    max_latency: gst::ClockTime,
+    do_lost: bool,
 }
 
 impl Default for Settings {
@@ -46,6 +59,11 @@ impl Default for Settings {
             rtp_id: String::from("rtp-id"),
             latency: DEFAULT_LATENCY,
             timestamping_mode: sync::TimestampingMode::default(),
+            do_retransmission: DEFAULT_DO_RETRANSMISSION,
+            adaptive_latency: DEFAULT_ADAPTIVE_LATENCY,
+            min_latency: DEFAULT_MIN_LATENCY,
+            max_latency: DEFAULT_MAX_LATENCY,
+            do_lost: DEFAULT_DO_LOST,
         }
     }
 }
@@ -56,14 +74,27 @@ struct JitterBufferStream {
     store: Arc<Mutex<JitterBufferStore>>,
     sleep: Pin<Box<tokio::time::Sleep>>,
     pending_item: Option<JitterBufferItem>,
+    // Only set if retransmissions are requested for missing packets
+    retransmission: Option<StreamRetransmission>,
+}
+
+#[derive(Debug)]
+struct StreamRetransmission {
+    internal_session: SharedSession,
+    ssrc: u32,
+    max_delay: Duration,
 }
 
 impl JitterBufferStream {
-    fn new(store: Arc<Mutex<JitterBufferStore>>) -> Self {
+    fn new(
+        store: Arc<Mutex<JitterBufferStore>>,
+        retransmission: Option<StreamRetransmission>,
+    ) -> Self {
         Self {
             store,
             sleep: Box::pin(tokio::time::sleep(Duration::from_secs(1))),
             pending_item: None,
+            retransmission,
         }
     }
 }
@@ -82,6 +113,59 @@ impl futures::stream::Stream for JitterBufferStream {
             return Poll::Ready(Some(item));
         }
 
+        if let Some(latency) = self
+            .store
+            .lock()
+            .unwrap()
+            .jitterbuffer
+            .take_latency_changed()
+        {
+            return Poll::Ready(Some(JitterBufferItem::LatencyChanged(latency)));
+        }
+
+        if let Some(ref retransmission) = self.retransmission {
+            // The session lock must not be taken while holding the jitterbuffer store lock
+            let rtt = retransmission
+                .internal_session
+                .inner
+                .lock()
+                .unwrap()
+                .session
+                .round_trip_time(retransmission.ssrc);
+
+            let mut jitterbuffer_store = self.store.lock().unwrap();
+            let lost_seqnums = jitterbuffer_store
+                .jitterbuffer
+                .poll_retransmissions(now, rtt);
+            lowest_wait = jitterbuffer_store.jitterbuffer.next_retransmission_time();
+            drop(jitterbuffer_store);
+
+            if !lost_seqnums.is_empty() {
+                gst::debug!(
+                    CAT,
+                    "Requesting retransmission of {lost_seqnums:?} from ssrc {:#08x}",
+                    retransmission.ssrc
+                );
+                let mut session_inner = retransmission.internal_session.inner.lock().unwrap();
+                let replies = session_inner.session.request_retransmission(
+                    now,
+                    retransmission.ssrc,
+                    lost_seqnums,
+                    retransmission.max_delay,
+                );
+                for reply in replies {
+                    match reply {
+                        RequestRetransmissionReply::TimerReconsideration => {
+                            if let Some(waker) = session_inner.rtcp_waker.take() {
+                                // reconsider timers means that we wake the rtcp task to get a new timeout
+                                waker.wake();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         let mut jitterbuffer_store = self.store.lock().unwrap();
         let mut pending_item = None;
         let mut next_pending_item = None;
@@ -114,6 +198,7 @@ impl futures::stream::Stream for JitterBufferStream {
                     match item {
                         // we don't currently push packet lists into the jitterbuffer
                         JitterBufferItem::PacketList(_list) => unreachable!(),
+                        JitterBufferItem::LatencyChanged(_) => unreachable!(),
                         // forward events and queries as-is
                         JitterBufferItem::Event(_) | JitterBufferItem::Query(_, _) => {
                             if pending_item.is_some() {
@@ -125,7 +210,9 @@ impl futures::stream::Stream for JitterBufferStream {
                         JitterBufferItem::Packet(ref packet) => {
                             match pending_item {
                                 Some(
-                                    JitterBufferItem::Event(_) | JitterBufferItem::Query(_, _),
+                                    JitterBufferItem::Event(_)
+                                    | JitterBufferItem::Query(_, _)
+                                    | JitterBufferItem::LatencyChanged(_),
                                 ) => unreachable!(),
                                 Some(JitterBufferItem::Packet(pending_buffer)) => {
                                     let mut list = gst::BufferList::new();
@@ -148,6 +235,30 @@ impl futures::stream::Stream for JitterBufferStream {
                     }
                     return Poll::Ready(Some(item));
                 }
+                jitterbuffer::PollResult::Lost {
+                    seqnum,
+                    num_packets,
+                    pts,
+                    duration,
+                } => {
+                    gst::debug!(CAT, "Lost {num_packets} packets starting at {seqnum}");
+                    let item = JitterBufferItem::Event(
+                        gst::event::CustomDownstream::builder(
+                            gst::Structure::builder("GstRTPPacketLost")
+                                .field("seqnum", (seqnum & 0xffff) as u32)
+                                .field("timestamp", gst::ClockTime::from_nseconds(pts))
+                                .field("duration", gst::ClockTime::from_nseconds(duration))
+                                .build(),
+                        )
+                        .build(),
+                    );
+                    if pending_item.is_some() {
+                        // but only after sending the previous pending item
+                        next_pending_item = Some(item);
+                        break;
+                    }
+                    return Poll::Ready(Some(item));
+                }
                 jitterbuffer::PollResult::Timeout(timeout) => {
                     if lowest_wait.map_or(true, |lowest_wait| timeout < lowest_wait) {
                         lowest_wait = Some(timeout);
@@ -191,6 +302,8 @@ enum JitterBufferItem {
         std::ptr::NonNull<gst::QueryRef>,
         std::sync::mpsc::SyncSender<bool>,
     ),
+    // Only produced by the stream, never stored
+    LatencyChanged(Duration),
 }
 
 // SAFETY: Need to be able to pass *mut gst::QueryRef
@@ -207,6 +320,8 @@ struct JitterBufferStore {
 struct RtpRecvSrcPad {
     pt: u8,
     ssrc: u32,
+    /// The MID / RID of the stream, used for routing the RTX streams repairing it
+    bundle_ids: BundleIds,
     pad: gst::Pad,
     jitter_buffer_store: Arc<Mutex<JitterBufferStore>>,
 }
@@ -230,7 +345,18 @@ impl RtpRecvSrcPad {
             .build();
 
         let session_inner = session.internal_session.inner.lock().unwrap();
-        let caps = session_inner.caps_from_pt(self.pt);
+        let mut caps = session_inner.caps_from_pt(self.pt);
+        // Allow mapping the pad to the media section / simulcast layer
+        if !self.bundle_ids.is_empty() {
+            let caps = caps.make_mut();
+            let s = caps.structure_mut(0).unwrap();
+            if let Some(ref mid) = self.bundle_ids.mid {
+                s.set("a-mid", mid.as_str());
+            }
+            if let Some(ref rid) = self.bundle_ids.rid {
+                s.set("rid", rid.as_str());
+            }
+        }
         let caps = gst::event::Caps::builder(&caps).seqnum(seqnum).build();
         drop(session_inner);
 
@@ -284,6 +410,7 @@ struct RecvSession {
     rtp_recv_sinkpad: Option<gst::Pad>,
     rtp_recv_sink_group_id: Option<gst::GroupId>,
     rtp_recv_sink_caps: Option<gst::Caps>,
+    rtp_recv_srtp: bool,
     rtp_recv_sink_segment: Option<gst::FormattedSegment<gst::ClockTime>>,
     rtp_recv_sink_seqnum: Option<gst::Seqnum>,
 
@@ -291,8 +418,11 @@ struct RecvSession {
 
     rtp_recv_srcpads: Vec<RtpRecvSrcPad>,
     recv_flow_combiner: Arc<Mutex<gst_base::UniqueFlowCombiner>>,
+    // RTX SSRC to original SSRC
+    rtx_ssrc_map: HashMap<u32, u32>,
 
     rtcp_recv_sinkpad: Option<gst::Pad>,
+    rtcp_recv_srtp: bool,
 }
 
 impl RecvSession {
@@ -305,6 +435,7 @@ impl RecvSession {
             rtp_recv_sinkpad: None,
             rtp_recv_sink_group_id: None,
             rtp_recv_sink_caps: None,
+            rtp_recv_srtp: false,
             rtp_recv_sink_segment: None,
             rtp_recv_sink_seqnum: None,
 
@@ -312,8 +443,10 @@ impl RecvSession {
 
             rtp_recv_srcpads: vec![],
             recv_flow_combiner: Arc::new(Mutex::new(gst_base::UniqueFlowCombiner::new())),
+            rtx_ssrc_map: HashMap::new(),
 
             rtcp_recv_sinkpad: None,
+            rtcp_recv_srtp: false,
         }
     }
 
@@ -329,12 +462,18 @@ impl RecvSession {
         let pad_weak = pad.downgrade();
         let recv_flow_combiner = self.recv_flow_combiner.clone();
         let store = recv_pad.jitter_buffer_store.clone();
+        let ssrc = recv_pad.ssrc;
 
-        {
+        let (retransmission_enabled, latency) = {
             let mut store = store.lock().unwrap();
             store.jitterbuffer.set_flushing(false);
             store.waker.take();
-        }
+            (
+                store.jitterbuffer.retransmission_enabled(),
+                store.jitterbuffer.latency(),
+            )
+        };
+        let internal_session = self.internal_session.clone();
 
         // A task per received ssrc may be a bit excessive.
         // Other options are:
@@ -347,9 +486,15 @@ impl RecvSession {
 
             let recv_flow_combiner = recv_flow_combiner.clone();
             let store = store.clone();
+            let internal_session = internal_session.clone();
+            let retransmission = retransmission_enabled.then(|| StreamRetransmission {
+                internal_session: internal_session.clone(),
+                ssrc,
+                max_delay: latency,
+            });
 
             RUNTIME.block_on(async move {
-                let mut stream = JitterBufferStream::new(store);
+                let mut stream = JitterBufferStream::new(store, retransmission);
                 while let Some(item) = stream.next().await {
                     match item {
                         JitterBufferItem::PacketList(list) => {
@@ -376,6 +521,20 @@ impl RecvSession {
                             let res = pad.peer_query(unsafe { query.as_mut() });
                             let _ = tx.send(res);
                         }
+                        JitterBufferItem::LatencyChanged(latency) => {
+                            gst::debug!(CAT, obj = pad, "Latency changed to {latency:?}");
+                            internal_session
+                                .inner
+                                .lock()
+                                .unwrap()
+                                .session
+                                .set_jitterbuffer_latency(ssrc, latency);
+                            if let Some(element) = pad.parent_element() {
+                                let _ = element.post_message(
+                                    gst::message::Latency::builder().src(&element).build(),
+                                );
+                            }
+                        }
                     }
                 }
             })
@@ -408,6 +567,7 @@ impl RecvSession {
         rtpbin: &RtpRecv,
         pt: u8,
         ssrc: u32,
+        bundle_ids: &BundleIds,
     ) -> (RtpRecvSrcPad, bool) {
         if let Some(pad) = self
             .rtp_recv_srcpads
@@ -459,14 +619,25 @@ impl RecvSession {
 
             let settings = rtpbin.settings.lock().unwrap();
 
+            let adaptive_latency = settings
+                .adaptive_latency
+                .then(|| (settings.min_latency.into(), settings.max_latency.into()));
+
             let recv_pad = RtpRecvSrcPad {
                 pt,
                 ssrc,
+                bundle_ids: bundle_ids.clone(),
                 pad: srcpad.clone(),
                 jitter_buffer_store: Arc::new(Mutex::new(JitterBufferStore {
                     waker: None,
                     store: BTreeMap::new(),
-                    jitterbuffer: JitterBuffer::new(settings.latency.into()),
+                    jitterbuffer: {
+                        let mut jitterbuffer = JitterBuffer::new(settings.latency.into());
+                        jitterbuffer.set_retransmission(settings.do_retransmission);
+                        jitterbuffer.set_adaptive_latency(adaptive_latency);
+                        jitterbuffer.set_do_lost(settings.do_lost);
+                        jitterbuffer
+                    },
                 })),
             };
 
@@ -488,6 +659,12 @@ struct State {
     pads_session_id_map: HashMap<gst::Pad, usize>,
 }
 
+enum RtxPacket {
+    NotRtx,
+    Unwrapped(Vec<u8>),
+    Invalid,
+}
+
 enum RecvRtpBuffer {
     IsRtcp(gst::Buffer),
     SsrcCollision(u32),
@@ -584,7 +761,9 @@ impl RtpRecv {
                 let mut peer_query = gst::query::Latency::new();
 
                 let ret = gst::Pad::query_default(pad, Some(&*self.obj()), &mut peer_query);
-                let our_latency = self.settings.lock().unwrap().latency;
+                let our_latency = self
+                    .src_pad_latency(pad)
+                    .unwrap_or_else(|| self.settings.lock().unwrap().latency);
 
                 let min = if ret {
                     let (_, min, _) = peer_query.result();
@@ -607,6 +786,24 @@ impl RtpRecv {
         }
     }
 
+    /// Current latency of the jitterbuffer of a RTP source pad, which changes over time
+    /// if adaptive latency is enabled
+    fn src_pad_latency(&self, pad: &gst::Pad) -> Option<gst::ClockTime> {
+        let state = self.state.lock().unwrap();
+        let latency = state
+            .sessions
+            .iter()
+            .flat_map(|session| session.rtp_recv_srcpads.iter())
+            .find(|recv| &recv.pad == pad)?
+            .jitter_buffer_store
+            .lock()
+            .unwrap()
+            .jitterbuffer
+            .latency();
+
+        gst::ClockTime::try_from(latency).ok()
+    }
+
     fn iterate_internal_links(&self, pad: &gst::Pad) -> gst::Iterator<gst::Pad> {
         let state = self.state.lock().unwrap();
         if let Some(&id) = state.pads_session_id_map.get(pad) {
@@ -631,6 +828,107 @@ impl RtpRecv {
         gst::Iterator::from_vec(vec![])
     }
 
+    /// Reconstruct the original packet if `rtp` is an RTX packet
+    fn unwrap_rtx(
+        &self,
+        pad: &gst::Pad,
+        session: &mut RecvSession,
+        rtp: &rtp_types::RtpPacket,
+    ) -> RtxPacket {
+        let Some(apt) = session
+            .internal_session
+            .inner
+            .lock()
+            .unwrap()
+            .apt_from_rtx_pt(rtp.payload_type())
+        else {
+            return RtxPacket::NotRtx;
+        };
+
+        let rtx_ssrc = rtp.ssrc();
+        let Some(osn) = rtx::original_seqnum(rtp) else {
+            gst::debug!(
+                CAT,
+                obj = pad,
+                "Dropping RTX packet without original seqnum"
+            );
+            return RtxPacket::Invalid;
+        };
+
+        let ssrc = if let Some(&ssrc) = session.rtx_ssrc_map.get(&rtx_ssrc) {
+            ssrc
+        } else {
+            let rtx_ids = session
+                .internal_session
+                .inner
+                .lock()
+                .unwrap()
+                .session
+                .bundle_extension_ids()
+                .read(rtp);
+
+            // Associate the RTX SSRC with the stream of its MID / repaired RID, otherwise with
+            // the stream that requested this packet, or the only candidate stream if there is a
+            // single one
+            let candidates = session
+                .rtp_recv_srcpads
+                .iter()
+                .filter(|recv| {
+                    recv.pt == apt && !session.rtx_ssrc_map.values().any(|&ssrc| ssrc == recv.ssrc)
+                })
+                .collect::<Vec<_>>();
+            let repaired = candidates
+                .iter()
+                .find(|recv| recv.bundle_ids.is_repaired_by(&rtx_ids));
+            let requested = candidates.iter().find(|recv| {
+                recv.jitter_buffer_store
+                    .lock()
+                    .unwrap()
+                    .jitterbuffer
+                    .is_retransmission_requested(osn)
+            });
+            let ssrc = match (repaired.or(requested), candidates.as_slice()) {
+                (Some(recv), _) => recv.ssrc,
+                (None, [recv]) => recv.ssrc,
+                _ => {
+                    gst::debug!(
+                        CAT,
+                        obj = pad,
+                        "Can't associate RTX ssrc {rtx_ssrc:#08x} with any stream yet"
+                    );
+                    return RtxPacket::Invalid;
+                }
+            };
+            gst::debug!(
+                CAT,
+                obj = pad,
+                "Associated RTX ssrc {rtx_ssrc:#08x} with ssrc {ssrc:#08x}"
+            );
+            session.rtx_ssrc_map.insert(rtx_ssrc, ssrc);
+
+            if repaired.is_some() {
+                session.internal_session.config.emit_by_name::<()>(
+                    "new-mid-rid",
+                    &[&rtx_ssrc, &rtx_ids.mid, &rtx_ids.rid, &rtx_ids.repaired_rid],
+                );
+            }
+
+            ssrc
+        };
+
+        match rtx::unwrap_packet(rtp, apt, ssrc) {
+            Some((osn, data)) => {
+                gst::trace!(
+                    CAT,
+                    obj = pad,
+                    "Received retransmission of packet {osn} for ssrc {ssrc:#08x}"
+                );
+                RtxPacket::Unwrapped(data)
+            }
+            None => RtxPacket::Invalid,
+        }
+    }
+
     fn handle_buffer_locked<const H: usize, const P: usize>(
         &self,
         pad: &gst::Pad,
@@ -703,6 +1001,33 @@ impl RtpRecv {
             }
         };
 
+        match self.unwrap_rtx(pad, session, &rtp) {
+            RtxPacket::NotRtx => (),
+            RtxPacket::Invalid => return Ok(RecvRtpBuffer::Drop),
+            RtxPacket::Unwrapped(data) => {
+                drop(mapped);
+                let mut unwrapped = gst::Buffer::from_mut_slice(data);
+                buffer
+                    .copy_into(
+                        unwrapped.get_mut().unwrap(),
+                        gst::BufferCopyFlags::METADATA,
+                        ..,
+                    )
+                    .map_err(|e| {
+                        gst::error!(CAT, obj = pad, "Failed to copy buffer metadata {e:?}");
+                        gst::FlowError::Error
+                    })?;
+                return self.handle_buffer_locked(
+                    pad,
+                    session,
+                    unwrapped,
+                    now,
+                    items_to_pre_push,
+                    held_buffers,
+                );
+            }
+        }
+
         gst::trace!(CAT, obj = pad, "using arrival time {}", arrival_time);
 
         let internal_session = session.internal_session.clone();
@@ -734,6 +1059,12 @@ impl RtpRecv {
         loop {
             let recv_ret = session_inner.session.handle_recv(&rtp, addr, now);
             gst::trace!(CAT, obj = pad, "session handle_recv ret: {recv_ret:?}");
+            if session_inner.session.schedule_twcc_feedback(now) {
+                if let Some(waker) = session_inner.rtcp_waker.take() {
+                    // send the transport-wide feedback with an early rtcp packet
+                    waker.wake();
+                }
+            }
             match recv_ret {
                 RecvReply::SsrcCollision(ssrc) => return Ok(RecvRtpBuffer::SsrcCollision(ssrc)),
                 RecvReply::NewSsrc(ssrc, _pt) => {
@@ -743,6 +1074,28 @@ impl RtpRecv {
                         .emit_by_name::<()>("new-ssrc", &[&ssrc]);
                     session_inner = internal_session.inner.lock().unwrap();
                 }
+                RecvReply::NewBundleIds(ssrc) => {
+                    let ids = session_inner
+                        .session
+                        .remote_send_source_by_ssrc(ssrc)
+                        .unwrap()
+                        .bundle_ids()
+                        .clone();
+                    gst::debug!(CAT, obj = pad, "ssrc {ssrc} has bundle ids {ids:?}");
+                    for recv in session
+                        .rtp_recv_srcpads
+                        .iter_mut()
+                        .filter(|recv| recv.ssrc == ssrc)
+                    {
+                        recv.bundle_ids = ids.clone();
+                    }
+                    drop(session_inner);
+                    internal_session.config.emit_by_name::<()>(
+                        "new-mid-rid",
+                        &[&ssrc, &ids.mid, &ids.rid, &ids.repaired_rid],
+                    );
+                    session_inner = internal_session.inner.lock().unwrap();
+                }
                 RecvReply::Hold(hold_id) => {
                     let pt = rtp.payload_type();
                     let ssrc = rtp.ssrc();
@@ -751,9 +1104,18 @@ impl RtpRecv {
                         let buf_mut = buffer.make_mut();
                         buf_mut.set_pts(pts);
                     }
-                    let (pad, new_pad) = session.get_or_create_rtp_src(self, pt, ssrc);
+                    let bundle_ids = session_inner
+                        .session
+                        .remote_send_source_by_ssrc(ssrc)
+                        .map(|source| source.bundle_ids().clone())
+                        .unwrap_or_default();
+                    let (pad, new_pad) = session.get_or_create_rtp_src(self, pt, ssrc, &bundle_ids);
                     let jb = pad.jitter_buffer_store.clone();
                     if new_pad {
+                        let latency = jb.lock().unwrap().jitterbuffer.latency();
+                        session_inner
+                            .session
+                            .set_jitterbuffer_latency(ssrc, latency);
                         items_to_pre_push.push(HeldRecvItem::NewPad(pad));
                     }
                     held_buffers.push(HeldRecvBuffer {
@@ -798,9 +1160,18 @@ impl RtpRecv {
                         let buf_mut = buffer.make_mut();
                         buf_mut.set_pts(pts);
                     }
-                    let (pad, new_pad) = session.get_or_create_rtp_src(self, pt, ssrc);
+                    let bundle_ids = session_inner
+                        .session
+                        .remote_send_source_by_ssrc(ssrc)
+                        .map(|source| source.bundle_ids().clone())
+                        .unwrap_or_default();
+                    let (pad, new_pad) = session.get_or_create_rtp_src(self, pt, ssrc, &bundle_ids);
                     let jb = pad.jitter_buffer_store.clone();
                     if new_pad {
+                        let latency = jb.lock().unwrap().jitterbuffer.latency();
+                        session_inner
+                            .session
+                            .set_jitterbuffer_latency(ssrc, latency);
                         items_to_pre_push.push(HeldRecvItem::NewPad(pad));
                     }
                     return Ok(RecvRtpBuffer::Forward((buffer, jb)));
@@ -968,6 +1339,13 @@ impl RtpRecv {
         id: usize,
         mut list: gst::BufferList,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        list.make_mut().foreach_mut(|buffer, _i| {
+            ControlFlow::Continue(self.srtp_unprotect(id, buffer, false))
+        });
+        if list.is_empty() {
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
         let mut state = self.state.lock().unwrap();
         let Some(session) = state.mut_session_by_id(id) else {
             return Err(gst::FlowError::Error);
@@ -1081,6 +1459,10 @@ impl RtpRecv {
         id: usize,
         buffer: gst::Buffer,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let Some(buffer) = self.srtp_unprotect(id, buffer, false) else {
+            return Ok(gst::FlowSuccess::Ok);
+        };
+
         let mut state = self.state.lock().unwrap();
         let Some(session) = state.mut_session_by_id(id) else {
             return Err(gst::FlowError::Error);
@@ -1126,6 +1508,33 @@ impl RtpRecv {
         Ok(gst::FlowSuccess::Ok)
     }
 
+    /// Authenticate and decrypt a received packet if the input of the pad is SRTP.
+    ///
+    /// Returns `None` if the packet has to be dropped.
+    fn srtp_unprotect(
+        &self,
+        id: usize,
+        buffer: gst::Buffer,
+        rtcp_pad: bool,
+    ) -> Option<gst::Buffer> {
+        let state = self.state.lock().unwrap();
+        let Some(session) = state.session_by_id(id) else {
+            return Some(buffer);
+        };
+        let protected = if rtcp_pad {
+            session.rtcp_recv_srtp
+        } else {
+            session.rtp_recv_srtp
+        };
+        if !protected {
+            return Some(buffer);
+        }
+        let internal_session = session.internal_session.clone();
+        drop(state);
+
+        internal_session.srtp_unprotect(buffer)
+    }
+
     fn rtcp_sink_chain(
         &self,
         id: usize,
@@ -1227,6 +1636,106 @@ impl RtpRecv {
                         );
                     }
                 }
+                RtcpRecvReply::RequestRetransmission { ssrc, seqnums } => {
+                    if rtp_send_sinkpad.is_none() {
+                        gst::debug!(
+                            CAT,
+                            imp = self,
+                            "Can't retransmit packets because of missing sinkpad"
+                        );
+                        continue;
+                    }
+                    let mut session_inner = internal_session.inner.lock().unwrap();
+                    let (buffers, new_ssrcs) = session_inner.retransmit(ssrc, &seqnums, now);
+                    gst::debug!(
+                        CAT,
+                        imp = self,
+                        "Queueing retransmission of {} of {} requested packets for ssrc {ssrc:#08x}",
+                        buffers.len(),
+                        seqnums.len()
+                    );
+                    session_inner.queue_retransmissions(buffers);
+                    drop(session_inner);
+
+                    for ssrc in new_ssrcs {
+                        internal_session
+                            .config
+                            .emit_by_name::<()>("new-ssrc", &[&ssrc]);
+                    }
+
+                    internal_session.schedule_retransmissions();
+                }
+                RtcpRecvReply::TwccFeedback(packets) => {
+                    let Some(ref rtp_send_sinkpad) = rtp_send_sinkpad else {
+                        gst::debug!(
+                            CAT,
+                            imp = self,
+                            "Can't send transport-wide feedback event because of missing sinkpad"
+                        );
+                        continue;
+                    };
+                    gst::trace!(
+                        CAT,
+                        imp = self,
+                        "Sending transport-wide feedback for {} packets",
+                        packets.len()
+                    );
+                    let packets = packets
+                        .into_iter()
+                        .map(|packet| {
+                            let mut s = gst::Structure::builder("RTPTWCCPacket")
+                                .field("seqnum", packet.seqnum as u32)
+                                .field("ssrc", packet.ssrc)
+                                .field("payload-type", packet.pt as u32)
+                                .field("size", packet.size as u32)
+                                .field(
+                                    "local-ts",
+                                    gst::ClockTime::from_nseconds(
+                                        packet.local_time.as_nanos() as u64
+                                    ),
+                                )
+                                .field("lost", packet.remote_time.is_none());
+                            if let Some(remote_time) = packet.remote_time {
+                                s = s.field(
+                                    "remote-ts",
+                                    gst::ClockTime::from_nseconds(remote_time.as_nanos() as u64),
+                                );
+                            }
+                            s.build()
+                        })
+                        .collect::<Vec<_>>();
+
+                    let event = gst::event::CustomUpstream::builder(
+                        gst::Structure::builder("RTPTWCCPackets")
+                            .field("packets", glib::ValueArray::new(packets))
+                            .build(),
+                    )
+                    .build();
+                    let _ = rtp_send_sinkpad.push_event(event);
+                }
+                RtcpRecvReply::Remb { bitrate, ssrcs } => {
+                    if let Some(ref rtp_send_sinkpad) = rtp_send_sinkpad {
+                        gst::debug!(
+                            CAT,
+                            imp = self,
+                            "Sending REMB event with bitrate {bitrate} for ssrcs {ssrcs:?}"
+                        );
+                        let event = gst::event::CustomUpstream::builder(
+                            gst::Structure::builder("RTPREMB")
+                                .field("bitrate", bitrate)
+                                .field("ssrcs", gst::Array::new(ssrcs))
+                                .build(),
+                        )
+                        .build();
+                        let _ = rtp_send_sinkpad.push_event(event);
+                    } else {
+                        gst::debug!(
+                            CAT,
+                            imp = self,
+                            "Can't send REMB event because of missing sinkpad"
+                        );
+                    }
+                }
                 RtcpRecvReply::NewCName((cname, ssrc)) => {
                     let mut sync_context = self.sync_context.lock().unwrap();
 
@@ -1354,6 +1863,28 @@ impl RtpRecv {
         true
     }
 
+    fn rtcp_sink_event(&self, pad: &gst::Pad, event: gst::Event, id: usize) -> bool {
+        match event.view() {
+            gst::EventView::Caps(caps) => {
+                let mut state = self.state.lock().unwrap();
+
+                if let Some(session) = state.mut_session_by_id(id) {
+                    let caps = caps.caps();
+                    session.rtcp_recv_srtp = caps
+                        .structure(0)
+                        .is_some_and(|s| s.name() == "application/x-srtcp");
+                    if session.rtcp_recv_srtp {
+                        let mut session_inner = session.internal_session.inner.lock().unwrap();
+                        session_inner.add_srtp_caps(caps);
+                    }
+                }
+
+                true
+            }
+            _ => gst::Pad::event_default(pad, Some(&*self.obj()), event),
+        }
+    }
+
     fn rtp_sink_event(&self, pad: &gst::Pad, mut event: gst::Event, id: usize) -> bool {
         match event.view() {
             gst::EventView::StreamStart(stream_start) => {
@@ -1372,10 +1903,19 @@ impl RtpRecv {
 
                 if let Some((pt, clock_rate)) = pt_clock_rate_from_caps(caps.caps()) {
                     if let Some(session) = state.mut_session_by_id(id) {
-                        let caps = caps.caps_owned();
+                        let caps = caps.caps();
+                        let mut session_inner = session.internal_session.inner.lock().unwrap();
+                        session.rtp_recv_srtp = caps
+                            .structure(0)
+                            .is_some_and(|s| s.name() == "application/x-srtp");
+                        let caps = if session.rtp_recv_srtp {
+                            session_inner.add_srtp_caps(caps);
+                            srtp::rtp_caps_from_srtp_caps(caps)
+                        } else {
+                            caps.to_owned()
+                        };
                         session.rtp_recv_sink_caps = Some(caps.clone());
 
-                        let mut session_inner = session.internal_session.inner.lock().unwrap();
                         session_inner.session.set_pt_clock_rate(pt, clock_rate);
                         session_inner.add_caps(caps);
                     }
@@ -1596,6 +2136,36 @@ impl ObjectImpl for RtpRecv {
                     .default_value(sync::TimestampingMode::default())
                     .mutable_ready()
                     .build(),
+                glib::ParamSpecBoolean::builder("do-retransmission")
+                    .nick("Do Retransmission")
+                    .blurb("Request retransmission of missing packets with NACK feedback. Requires rtp-profile=avpf on the rtpsend element with the same rtp-id")
+                    .default_value(DEFAULT_DO_RETRANSMISSION)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("adaptive-latency")
+                    .nick("Adaptive Latency")
+                    .blurb("Adapt the latency to the observed network jitter and packet reordering")
+                    .default_value(DEFAULT_ADAPTIVE_LATENCY)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("min-latency")
+                    .nick("Minimum latency in ms")
+                    .blurb("Minimum latency in ms if adaptive-latency is enabled")
+                    .default_value(DEFAULT_MIN_LATENCY.mseconds() as u32)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("max-latency")
+                    .nick("Maximum latency in ms")
+                    .blurb("Maximum latency in ms if adaptive-latency is enabled")
+                    .default_value(DEFAULT_MAX_LATENCY.mseconds() as u32)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("do-lost")
+                    .nick("Do Lost")
+                    .blurb("Send a GstRTPPacketLost event downstream for packets that did not arrive in time")
+                    .default_value(DEFAULT_DO_LOST)
+                    .mutable_ready()
+                    .build(),
             ]
         });
 
@@ -1627,6 +2197,30 @@ impl ObjectImpl for RtpRecv {
                     .get::<sync::TimestampingMode>()
                     .expect("Type checked upstream");
             }
+            "do-retransmission" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.do_retransmission = value.get::<bool>().expect("Type checked upstream");
+            }
+            "adaptive-latency" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.adaptive_latency = value.get::<bool>().expect("Type checked upstream");
+            }
+            "min-latency" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.min_latency = gst::ClockTime::from_mseconds(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "max-latency" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.max_latency = gst::ClockTime::from_mseconds(
+                    value.get::<u32>().expect("type checked upstream").into(),
+                );
+            }
+            "do-lost" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.do_lost = value.get::<bool>().expect("Type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -1649,6 +2243,26 @@ impl ObjectImpl for RtpRecv {
                 let settings = self.settings.lock().unwrap();
                 settings.timestamping_mode.to_value()
             }
+            "do-retransmission" => {
+                let settings = self.settings.lock().unwrap();
+                settings.do_retransmission.to_value()
+            }
+            "adaptive-latency" => {
+                let settings = self.settings.lock().unwrap();
+                settings.adaptive_latency.to_value()
+            }
+            "min-latency" => {
+                let settings = self.settings.lock().unwrap();
+                (settings.min_latency.mseconds() as u32).to_value()
+            }
+            "max-latency" => {
+                let settings = self.settings.lock().unwrap();
+                (settings.max_latency.mseconds() as u32).to_value()
+            }
+            "do-lost" => {
+                let settings = self.settings.lock().unwrap();
+                settings.do_lost.to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -1696,8 +2310,13 @@ impl ElementImpl for RtpRecv {
             let rtp_caps = gst::Caps::builder_full()
                 .structure(gst::Structure::builder("application/x-rtp").build())
                 .build();
-            let rtcp_caps = gst::Caps::builder_full()
+            let rtp_sink_caps = gst::Caps::builder_full()
+                .structure(gst::Structure::builder("application/x-rtp").build())
+                .structure(gst::Structure::builder("application/x-srtp").build())
+                .build();
+            let rtcp_sink_caps = gst::Caps::builder_full()
                 .structure(gst::Structure::builder("application/x-rtcp").build())
+                .structure(gst::Structure::builder("application/x-srtcp").build())
                 .build();
 
             vec![
@@ -1705,14 +2324,14 @@ impl ElementImpl for RtpRecv {
                     "rtp_sink_%u",
                     gst::PadDirection::Sink,
                     gst::PadPresence::Request,
-                    &rtp_caps,
+                    &rtp_sink_caps,
                 )
                 .unwrap(),
                 gst::PadTemplate::new(
                     "rtcp_sink_%u",
                     gst::PadDirection::Sink,
                     gst::PadPresence::Request,
-                    &rtcp_caps,
+                    &rtcp_sink_caps,
                 )
                 .unwrap(),
                 gst::PadTemplate::new(
@@ -1833,7 +2452,17 @@ impl ElementImpl for RtpRecv {
                             RtpRecv::catch_panic_pad_function(
                                 parent,
                                 || Err(gst::FlowError::Error),
-                                |this| this.rtcp_sink_chain(id, buffer),
+                                |this| match this.srtp_unprotect(id, buffer, true) {
+                                    Some(buffer) => this.rtcp_sink_chain(id, buffer),
+                                    None => Ok(gst::FlowSuccess::Ok),
+                                },
+                            )
+                        })
+                        .event_function(move |pad, parent, event| {
+                            RtpRecv::catch_panic_pad_function(
+                                parent,
+                                || false,
+                                |this| this.rtcp_sink_event(pad, event, id),
                             )
                         })
                         .iterate_internal_links_function(|pad, parent| {
@@ -1936,6 +2565,7 @@ impl ElementImpl for RtpRecv {
             for id in removed_srcpads_session_ids {
                 if let Some(session) = state.mut_session_by_id(id) {
                     session.rtp_recv_srcpads.clear();
+                    session.rtx_ssrc_map.clear();
                 }
             }
             for id in removed_session_ids {
@@ -1999,6 +2629,7 @@ impl ElementImpl for RtpRecv {
 
                     session.recv_flow_combiner.lock().unwrap().clear();
                     session.rtp_recv_srcpads.clear();
+                    session.rtx_ssrc_map.clear();
                     session.recv_store.clear();
 
                     session.rtp_recv_sink_caps = None;
@@ -11,14 +11,21 @@ use futures::StreamExt;
 use gst::{glib, prelude::*, subclass::prelude::*};
 use std::sync::LazyLock;
 
-use super::internal::{pt_clock_rate_from_caps, GstRustLogger, SharedRtpState, SharedSession};
+use super::internal::{
+    pt_clock_rate_from_caps, GstRustLogger, SharedRtpState, SharedSession,
+    DEFAULT_RTX_MAX_SIZE_PACKETS,
+};
+use super::rtx;
 use super::session::{RtcpSendReply, RtpProfile, SendReply, RTCP_MIN_REPORT_INTERVAL};
 use super::source::SourceState;
+use super::srtp;
 
 use crate::rtpbin2::RUNTIME;
 
 const DEFAULT_MIN_RTCP_INTERVAL: Duration = RTCP_MIN_REPORT_INTERVAL;
 const DEFAULT_REDUCED_SIZE_RTCP: bool = false;
+const DEFAULT_RTCP_XR: bool = false;
+const DEFAULT_SRTP: bool = false;
 
 static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     gst::DebugCategory::new(
@@ -63,6 +70,9 @@ struct Settings {
     min_rtcp_interval: Duration,
     profile: Profile,
     reduced_size_rtcp: bool,
+    rtcp_xr: bool,
+    rtx_max_size_packets: u32,
+    srtp: bool,
 }
 
 impl Default for Settings {
@@ -72,6 +82,9 @@ impl Default for Settings {
             min_rtcp_interval: DEFAULT_MIN_RTCP_INTERVAL,
             profile: Profile::default(),
             reduced_size_rtcp: DEFAULT_REDUCED_SIZE_RTCP,
+            rtcp_xr: DEFAULT_RTCP_XR,
+            rtx_max_size_packets: DEFAULT_RTX_MAX_SIZE_PACKETS,
+            srtp: DEFAULT_SRTP,
         }
     }
 }
@@ -165,6 +178,12 @@ impl SendSession {
         inner
             .session
             .set_reduced_size_rtcp(settings.reduced_size_rtcp);
+        inner.session.set_rtcp_xr(settings.rtcp_xr);
+        inner
+            .rtx_sender
+            .set_max_packets(settings.rtx_max_size_packets as usize);
+        inner.rtx_max_size_packets = settings.rtx_max_size_packets as usize;
+        inner.srtp_send = settings.srtp;
         drop(inner);
 
         Self {
@@ -209,9 +228,10 @@ impl SendSession {
                     continue;
                 };
                 match reply {
-                    RtcpSendReply::Data(data) => {
-                        session.rtcp_send_srcpad.clone().map(|pad| (pad, data))
-                    }
+                    RtcpSendReply::Data(data) => session
+                        .rtcp_send_srcpad
+                        .clone()
+                        .map(|pad| (pad, session.internal_session.clone(), data)),
                     RtcpSendReply::SsrcBye(ssrc) => {
                         session
                             .internal_session
@@ -222,16 +242,18 @@ impl SendSession {
                 }
             };
 
-            if let Some((rtcp_srcpad, data)) = send {
+            if let Some((rtcp_srcpad, internal_session, data)) = send {
                 let acquired = sem.clone().acquire_owned().await;
                 RUNTIME.spawn_blocking(move || {
                     let buffer = gst::Buffer::from_mut_slice(data);
-                    if let Err(e) = rtcp_srcpad.push(buffer) {
-                        gst::warning!(
-                            CAT,
-                            obj = rtcp_srcpad,
-                            "Failed to send rtcp data: flow return {e:?}"
-                        );
+                    if let Some(buffer) = internal_session.srtp_protect(buffer) {
+                        if let Err(e) = rtcp_srcpad.push(buffer) {
+                            gst::warning!(
+                                CAT,
+                                obj = rtcp_srcpad,
+                                "Failed to send rtcp data: flow return {e:?}"
+                            );
+                        }
                     }
                     drop(acquired);
                 });
@@ -356,6 +378,24 @@ impl RtpSend {
                 SendReply::Drop => return Ok(gst::FlowSuccess::Ok),
             }
         }
+        let mut rtx_caps = None;
+        if let Some(rtx_pt) = session_inner.rtx_pt_from_pt(rtp.payload_type()) {
+            if session_inner.store_sent_packet(
+                rtp.ssrc(),
+                rtp.sequence_number(),
+                rtx_pt,
+                buffer.clone(),
+            ) {
+                rtx_caps = session_inner
+                    .rtx_sender
+                    .rtx_info(rtp.ssrc())
+                    .zip(srcpad.current_caps())
+                    .map(|(info, mut caps)| {
+                        rtx::add_rtx_caps_fields(caps.make_mut(), info);
+                        caps
+                    });
+            }
+        }
         // TODO: handle other processing
         drop(mapped);
         drop(session_inner);
@@ -373,6 +413,19 @@ impl RtpSend {
             );
         }
 
+        if let Some(caps) = rtx_caps {
+            gst::debug!(
+                CAT,
+                obj = srcpad,
+                "Announcing RTX stream with caps {caps:?}"
+            );
+            srcpad.push_event(gst::event::Caps::new(&caps));
+        }
+
+        let Some(buffer) = internal_session.srtp_protect(buffer) else {
+            return Ok(gst::FlowSuccess::Ok);
+        };
+
         srcpad.push(buffer)
     }
 
@@ -422,20 +475,53 @@ impl RtpSend {
     fn rtp_sink_event(&self, pad: &gst::Pad, event: gst::Event, id: usize) -> bool {
         match event.view() {
             gst::EventView::Caps(caps) => {
-                if let Some((pt, clock_rate)) = pt_clock_rate_from_caps(caps.caps()) {
-                    let state = self.state.lock().unwrap();
-                    if let Some(session) = state.session_by_id(id) {
-                        let mut session = session.internal_session.inner.lock().unwrap();
-                        session.session.set_pt_clock_rate(pt, clock_rate);
-                        session.add_caps(caps.caps_owned());
+                let mut srcpad_caps = None;
+                let state = self.state.lock().unwrap();
+                if let Some(session) = state.session_by_id(id) {
+                    let mut session_inner = session.internal_session.inner.lock().unwrap();
+                    if let Some((pt, clock_rate)) = pt_clock_rate_from_caps(caps.caps()) {
+                        session_inner.session.set_pt_clock_rate(pt, clock_rate);
+                        session_inner.add_caps(caps.caps_owned());
+                    } else {
+                        gst::warning!(
+                            CAT,
+                            obj = pad,
+                            "input caps are missing payload or clock-rate fields"
+                        );
                     }
-                } else {
-                    gst::warning!(
-                        CAT,
-                        obj = pad,
-                        "input caps are missing payload or clock-rate fields"
+
+                    // Keep announcing the RTX stream of the sent stream, if any
+                    let rtx_info = caps
+                        .caps()
+                        .structure(0)
+                        .and_then(|s| s.get::<u32>("ssrc").ok())
+                        .map_or_else(
+                            || session_inner.rtx_sender.single_rtx_info(),
+                            |ssrc| session_inner.rtx_sender.rtx_info(ssrc),
+                        );
+                    if session_inner.srtp_send || rtx_info.is_some() {
+                        let mut out_caps = if session_inner.srtp_send {
+                            srtp::srtp_caps_from_rtp_caps(caps.caps())
+                        } else {
+                            caps.caps_owned()
+                        };
+                        if let Some(info) = rtx_info {
+                            rtx::add_rtx_caps_fields(out_caps.make_mut(), info);
+                        }
+                        srcpad_caps = session.rtp_send_srcpad.clone().zip(Some(out_caps));
+                    }
+                }
+                drop(state);
+
+                if let Some((srcpad, out_caps)) = srcpad_caps {
+                    gst::debug!(CAT, obj = pad, "Sending caps {out_caps:?}");
+                    return srcpad.push_event(
+                        gst::event::Caps::builder(&out_caps)
+                            .seqnum(event.seqnum())
+                            .build(),
                     );
                 }
+
                 gst::Pad::event_default(pad, Some(&*self.obj()), event)
             }
             gst::EventView::Eos(_eos) => {
@@ -533,6 +619,24 @@ impl ObjectImpl for RtpSend {
                     .default_value(DEFAULT_REDUCED_SIZE_RTCP)
                     .mutable_ready()
                     .build(),
+                glib::ParamSpecBoolean::builder("rtcp-xr")
+                    .nick("RTCP XR")
+                    .blurb("Send RTCP Extended Reports (RFC 3611) with receiver reference times and reception statistics")
+                    .default_value(DEFAULT_RTCP_XR)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("rtx-max-size-packets")
+                    .nick("RTX Max Size Packets")
+                    .blurb("Maximum number of sent packets to keep per SSRC for answering retransmission requests. Only packets with an RTX payload type in the pt-map are kept (0 = disabled)")
+                    .default_value(DEFAULT_RTX_MAX_SIZE_PACKETS)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("srtp")
+                    .nick("SRTP")
+                    .blurb("Protect the sent RTP and RTCP packets with SRTP. Keys are requested with the request-key signal of the session, packets without a key are dropped")
+                    .default_value(DEFAULT_SRTP)
+                    .mutable_ready()
+                    .build(),
             ]
         });
 
@@ -559,6 +663,18 @@ impl ObjectImpl for RtpSend {
                 let mut settings = self.settings.lock().unwrap();
                 settings.reduced_size_rtcp = value.get::<bool>().expect("Type checked upstream");
             }
+            "rtcp-xr" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.rtcp_xr = value.get::<bool>().expect("Type checked upstream");
+            }
+            "rtx-max-size-packets" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.rtx_max_size_packets = value.get::<u32>().expect("Type checked upstream");
+            }
+            "srtp" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.srtp = value.get::<bool>().expect("Type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -585,6 +701,18 @@ impl ObjectImpl for RtpSend {
                 let settings = self.settings.lock().unwrap();
                 settings.reduced_size_rtcp.to_value()
             }
+            "rtcp-xr" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rtcp_xr.to_value()
+            }
+            "rtx-max-size-packets" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rtx_max_size_packets.to_value()
+            }
+            "srtp" => {
+                let settings = self.settings.lock().unwrap();
+                settings.srtp.to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -632,8 +760,13 @@ impl ElementImpl for RtpSend {
             let rtp_caps = gst::Caps::builder_full()
                 .structure(gst::Structure::builder("application/x-rtp").build())
                 .build();
+            let rtp_src_caps = gst::Caps::builder_full()
+                .structure(gst::Structure::builder("application/x-rtp").build())
+                .structure(gst::Structure::builder("application/x-srtp").build())
+                .build();
             let rtcp_caps = gst::Caps::builder_full()
                 .structure(gst::Structure::builder("application/x-rtcp").build())
+                .structure(gst::Structure::builder("application/x-srtcp").build())
                 .build();
 
             vec![
@@ -648,7 +781,7 @@ impl ElementImpl for RtpSend {
                     "rtp_src_%u",
                     gst::PadDirection::Src,
                     gst::PadPresence::Sometimes,
-                    &rtp_caps,
+                    &rtp_src_caps,
                 )
                 .unwrap(),
                 gst::PadTemplate::new(
@@ -675,6 +808,7 @@ impl ElementImpl for RtpSend {
         let mut state = self.state.lock().unwrap();
         let max_session_id = state.max_session_id;
         let rtp_id = settings.rtp_id.clone();
+        let srtp = settings.srtp;
 
         // parse the possibly provided name into a session id or use the default
         let sess_parse = move |name: Option<&str>, prefix, default_id| -> Option<usize> {
@@ -728,7 +862,12 @@ impl ElementImpl for RtpSend {
                                 |this| this.rtp_sink_event(pad, event, id),
                             )
                         })
-                        .flags(gst::PadFlags::PROXY_CAPS)
+                        // SRTP caps are not the same as the input caps
+                        .flags(if srtp {
+                            gst::PadFlags::empty()
+                        } else {
+                            gst::PadFlags::PROXY_CAPS
+                        })
                         .name(format!("rtp_sink_{}", id))
                         .build();
                     let src_templ = self.obj().pad_template("rtp_src_%u").unwrap();
@@ -744,12 +883,10 @@ impl ElementImpl for RtpSend {
                         .build();
                     session.rtp_send_sinkpad = Some(sinkpad.clone());
                     session.rtp_send_srcpad = Some(srcpad.clone());
-                    session
-                        .internal_session
-                        .inner
-                        .lock()
-                        .unwrap()
-                        .rtp_send_sinkpad = Some(sinkpad.clone());
+                    let mut session_inner = session.internal_session.inner.lock().unwrap();
+                    session_inner.rtp_send_sinkpad = Some(sinkpad.clone());
+                    session_inner.rtp_send_srcpad = Some(srcpad.clone());
+                    drop(session_inner);
                     Some((sinkpad, Some(srcpad), id, vec![]))
                 };
 
@@ -792,7 +929,11 @@ impl ElementImpl for RtpSend {
                     let stream_start = gst::event::StreamStart::builder(&stream_id).build();
                     let seqnum = stream_start.seqnum();
 
-                    let caps = gst::Caps::new_empty_simple("application/x-rtcp");
+                    let caps = gst::Caps::new_empty_simple(if srtp {
+                        "application/x-srtcp"
+                    } else {
+                        "application/x-rtcp"
+                    });
                     let caps = gst::event::Caps::builder(&caps).seqnum(seqnum).build();
 
                     let segment = gst::FormattedSegment::<gst::ClockTime>::new();
@@ -855,12 +996,11 @@ impl ElementImpl for RtpSend {
             if let Some(session) = state.mut_session_by_id(id) {
                 if Some(pad) == session.rtp_send_sinkpad.as_ref() {
                     session.rtp_send_sinkpad = None;
-                    session
-                        .internal_session
-                        .inner
-                        .lock()
-                        .unwrap()
-                        .rtp_send_sinkpad = None;
+                    let mut session_inner = session.internal_session.inner.lock().unwrap();
+                    session_inner.rtp_send_sinkpad = None;
+                    session_inner.rtp_send_srcpad = None;
+                    session_inner.pending_retransmissions.clear();
+                    drop(session_inner);
 
                     if let Some(srcpad) = session.rtp_send_srcpad.take() {
                         removed_pads.push(srcpad);
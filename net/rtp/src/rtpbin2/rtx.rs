@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! RTP retransmission (RTX) as specified in RFC 4588.
+//!
+//! Retransmitted packets are sent in session multiplexing mode on a separate SSRC with a separate
+//! payload type.  The payload of an RTX packet is the original sequence number (OSN) followed by
+//! the original payload.
+
+use std::collections::{HashMap, VecDeque};
+
+use rtp_types::{RtpPacket, RtpPacketBuilder};
+
+/// Retrieve the RTX payload type and the associated original payload type from the provided caps,
+/// if they describe an RTX payload type.
+pub(crate) fn rtx_apt_from_caps(caps: &gst::CapsRef) -> Option<(u8, u8)> {
+    let s = caps.structure(0)?;
+    if !s
+        .get::<&str>("encoding-name")
+        .is_ok_and(|encoding_name| encoding_name.eq_ignore_ascii_case("RTX"))
+    {
+        return None;
+    }
+
+    let pt = s.get::<i32>("payload").ok()?;
+    // The associated payload type is a string when the caps were generated from SDP
+    let apt = s
+        .get::<i32>("apt")
+        .ok()
+        .or_else(|| s.get::<u32>("apt").ok().map(|apt| apt as i32))
+        .or_else(|| {
+            s.get::<&str>("apt")
+                .ok()
+                .and_then(|apt| apt.parse::<i32>().ok())
+        })?;
+
+    if !(0..=127).contains(&pt) || !(0..=127).contains(&apt) {
+        return None;
+    }
+
+    Some((pt as u8, apt as u8))
+}
+
+/// Retrieve the original sequence number of an RTX packet
+pub(crate) fn original_seqnum(rtx: &RtpPacket) -> Option<u16> {
+    let payload = rtx.payload();
+    if payload.len() < 2 {
+        return None;
+    }
+
+    Some(u16::from_be_bytes([payload[0], payload[1]]))
+}
+
+/// Construct an RTX packet from the original RTP packet.
+fn wrap_packet(
+    rtp: &RtpPacket,
+    rtx_pt: u8,
+    rtx_ssrc: u32,
+    rtx_seqnum: u16,
+) -> Result<Vec<u8>, rtp_types::RtpWriteError> {
+    let osn = rtp.sequence_number().to_be_bytes();
+    let mut builder = RtpPacketBuilder::<&[u8], &[u8]>::new()
+        .payload_type(rtx_pt)
+        .ssrc(rtx_ssrc)
+        .sequence_number(rtx_seqnum)
+        .timestamp(rtp.timestamp())
+        .marker_bit(rtp.marker_bit())
+        .payload(osn.as_slice())
+        .payload(rtp.payload());
+    for csrc in rtp.csrc() {
+        builder = builder.add_csrc(csrc);
+    }
+    if let Some((pattern, data)) = rtp.extension() {
+        builder = builder.extension(pattern, data);
+    }
+
+    builder.write_vec()
+}
+
+/// Reconstruct the original RTP packet from an RTX packet.  The payload type and SSRC of the
+/// original packet must have been looked up by the caller.
+///
+/// Returns the original sequence number together with the original packet, or `None` if the RTX
+/// packet does not contain an original sequence number.
+pub(crate) fn unwrap_packet(rtx: &RtpPacket, pt: u8, ssrc: u32) -> Option<(u16, Vec<u8>)> {
+    let osn = original_seqnum(rtx)?;
+
+    let mut builder = RtpPacketBuilder::<&[u8], &[u8]>::new()
+        .payload_type(pt)
+        .ssrc(ssrc)
+        .sequence_number(osn)
+        .timestamp(rtx.timestamp())
+        .marker_bit(rtx.marker_bit())
+        .payload(&rtx.payload()[2..]);
+    for csrc in rtx.csrc() {
+        builder = builder.add_csrc(csrc);
+    }
+    if let Some((pattern, data)) = rtx.extension() {
+        builder = builder.extension(pattern, data);
+    }
+
+    builder.write_vec().ok().map(|data| (osn, data))
+}
+
+/// Add the `rtx-ssrc`, `rtx-seqnum-offset` and `rtx-payload` fields describing the RTX stream of
+/// a sent stream to its caps
+pub(crate) fn add_rtx_caps_fields(caps: &mut gst::CapsRef, info: RtxInfo) {
+    for s in caps.iter_mut() {
+        s.set("rtx-ssrc", info.ssrc);
+        s.set("rtx-seqnum-offset", info.seqnum_offset as u32);
+        s.set("rtx-payload", info.pt as i32);
+    }
+}
+
+/// The parameters of the RTX stream of a sent stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RtxInfo {
+    pub(crate) ssrc: u32,
+    pub(crate) seqnum_offset: u16,
+    pub(crate) pt: u8,
+}
+
+#[derive(Debug)]
+struct SentPacket {
+    seqnum: u16,
+    rtx_pt: u8,
+    buffer: gst::Buffer,
+}
+
+#[derive(Debug)]
+struct RtxSendStream {
+    rtx_ssrc: u32,
+    rtx_pt: u8,
+    seqnum_offset: u16,
+    next_rtx_seqnum: u16,
+    history: VecDeque<SentPacket>,
+}
+
+/// Packet history of the locally sent streams for answering retransmission requests.
+#[derive(Debug)]
+pub(crate) struct RtxSender {
+    max_packets: usize,
+    streams: HashMap<u32, RtxSendStream>,
+}
+
+impl RtxSender {
+    pub(crate) fn new(max_packets: usize) -> Self {
+        Self {
+            max_packets,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Set the maximum number of packets to keep per SSRC
+    pub(crate) fn set_max_packets(&mut self, max_packets: usize) {
+        self.max_packets = max_packets;
+        for stream in self.streams.values_mut() {
+            while stream.history.len() > max_packets {
+                stream.history.pop_front();
+            }
+        }
+    }
+
+    /// Store a sent packet for later retransmission with `rtx_pt`.  `new_rtx_ssrc` is called for
+    /// retrieving the RTX SSRC if this is the first packet of `ssrc`.
+    ///
+    /// Returns `true` if the RTX stream of `ssrc` is new or its payload type changed, i.e. if
+    /// the caps of the sent stream have to be updated.
+    pub(crate) fn store_packet(
+        &mut self,
+        ssrc: u32,
+        seqnum: u16,
+        rtx_pt: u8,
+        buffer: gst::Buffer,
+        new_rtx_ssrc: impl FnOnce() -> u32,
+    ) -> bool {
+        if self.max_packets == 0 {
+            return false;
+        }
+
+        let mut changed = false;
+        let stream = self.streams.entry(ssrc).or_insert_with(|| {
+            let rtx_ssrc = new_rtx_ssrc();
+            let seqnum_offset = rand::random();
+            debug!("Using RTX ssrc {rtx_ssrc:#08x} for ssrc {ssrc:#08x}");
+            changed = true;
+            RtxSendStream {
+                rtx_ssrc,
+                rtx_pt,
+                seqnum_offset,
+                next_rtx_seqnum: seqnum_offset,
+                history: VecDeque::new(),
+            }
+        });
+        if stream.rtx_pt != rtx_pt {
+            stream.rtx_pt = rtx_pt;
+            changed = true;
+        }
+
+        while stream.history.len() >= self.max_packets {
+            stream.history.pop_front();
+        }
+        stream.history.push_back(SentPacket {
+            seqnum,
+            rtx_pt,
+            buffer,
+        });
+
+        changed
+    }
+
+    /// The RTX stream used for retransmitting the packets of `ssrc`, if any
+    pub(crate) fn rtx_info(&self, ssrc: u32) -> Option<RtxInfo> {
+        self.streams.get(&ssrc).map(|stream| RtxInfo {
+            ssrc: stream.rtx_ssrc,
+            seqnum_offset: stream.seqnum_offset,
+            pt: stream.rtx_pt,
+        })
+    }
+
+    /// The RTX stream if there is only a single one
+    pub(crate) fn single_rtx_info(&self) -> Option<RtxInfo> {
+        if self.streams.len() != 1 {
+            return None;
+        }
+        self.streams
+            .keys()
+            .next()
+            .and_then(|&ssrc| self.rtx_info(ssrc))
+    }
+
+    /// Produce an RTX packet for the packet with `seqnum` sent on `ssrc`.
+    ///
+    /// Returns `None` if the packet is not in the history anymore.
+    pub(crate) fn retransmit(&mut self, ssrc: u32, seqnum: u16) -> Option<gst::Buffer> {
+        let stream = self.streams.get_mut(&ssrc)?;
+        // Retransmission requests are usually for recently sent packets
+        let Some(sent) = stream
+            .history
+            .iter()
+            .rev()
+            .find(|sent| sent.seqnum == seqnum)
+        else {
+            debug!("Packet {seqnum} of ssrc {ssrc:#08x} not in the history anymore");
+            return None;
+        };
+
+        let mapped = sent.buffer.map_readable().ok()?;
+        let rtp = RtpPacket::parse(&mapped).ok()?;
+        let data = match wrap_packet(&rtp, sent.rtx_pt, stream.rtx_ssrc, stream.next_rtx_seqnum) {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("Failed to create RTX packet for {seqnum} of ssrc {ssrc:#08x}: {err:?}");
+                return None;
+            }
+        };
+        trace!(
+            "Retransmitting packet {seqnum} of ssrc {ssrc:#08x} with seqnum {} on ssrc {:#08x}",
+            stream.next_rtx_seqnum,
+            stream.rtx_ssrc
+        );
+        stream.next_rtx_seqnum = stream.next_rtx_seqnum.wrapping_add(1);
+
+        let mut buffer = gst::Buffer::from_mut_slice(data);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(sent.buffer.pts());
+            buffer.set_dts(sent.buffer.dts());
+        }
+
+        Some(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtpbin2::session::tests::{generate_rtp_packet, init_logs};
+
+    const RTX_PT: u8 = 97;
+
+    #[test]
+    fn apt_from_caps() {
+        init_logs();
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("payload", 97i32)
+            .field("clock-rate", 90000i32)
+            .field("encoding-name", "RTX")
+            .field("apt", "96")
+            .build();
+        assert_eq!(rtx_apt_from_caps(&caps), Some((97, 96)));
+
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("payload", 97i32)
+            .field("clock-rate", 90000i32)
+            .field("encoding-name", "rtx")
+            .field("apt", 96i32)
+            .build();
+        assert_eq!(rtx_apt_from_caps(&caps), Some((97, 96)));
+
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("payload", 96i32)
+            .field("clock-rate", 90000i32)
+            .field("encoding-name", "VP8")
+            .build();
+        assert_eq!(rtx_apt_from_caps(&caps), None);
+    }
+
+    #[test]
+    fn retransmit_and_unwrap() {
+        init_logs();
+        let ssrc = 0x12345678;
+        let rtx_ssrc = 0x87654321;
+
+        let mut sender = RtxSender::new(2);
+        assert!(sender.rtx_info(ssrc).is_none());
+        for seqnum in 10..13 {
+            let changed = sender.store_packet(
+                ssrc,
+                seqnum,
+                RTX_PT,
+                gst::Buffer::from_mut_slice(generate_rtp_packet(ssrc, seqnum, 100, 8)),
+                || rtx_ssrc,
+            );
+            // only the first packet creates the RTX stream
+            assert_eq!(changed, seqnum == 10);
+        }
+        let info = sender.rtx_info(ssrc).unwrap();
+        assert_eq!(info.ssrc, rtx_ssrc);
+        assert_eq!(info.pt, RTX_PT);
+        assert_eq!(sender.single_rtx_info(), Some(info));
+
+        // only the last two packets are kept
+        assert!(sender.retransmit(ssrc, 10).is_none());
+        assert!(sender.retransmit(0x1, 11).is_none());
+
+        let first = sender.retransmit(ssrc, 11).unwrap();
+        let second = sender.retransmit(ssrc, 12).unwrap();
+
+        let first_map = first.map_readable().unwrap();
+        let first_rtx = RtpPacket::parse(&first_map).unwrap();
+        let second_map = second.map_readable().unwrap();
+        let second_rtx = RtpPacket::parse(&second_map).unwrap();
+        assert_eq!(first_rtx.ssrc(), rtx_ssrc);
+        assert_eq!(first_rtx.payload_type(), RTX_PT);
+        assert_eq!(first_rtx.timestamp(), 100);
+        assert_eq!(first_rtx.payload().len(), 2 + 8);
+        assert_eq!(original_seqnum(&first_rtx), Some(11));
+        assert_eq!(first_rtx.sequence_number(), info.seqnum_offset);
+        assert_eq!(
+            second_rtx.sequence_number(),
+            first_rtx.sequence_number().wrapping_add(1)
+        );
+
+        let (osn, data) = unwrap_packet(&second_rtx, 96, ssrc).unwrap();
+        assert_eq!(osn, 12);
+        assert_eq!(data, generate_rtp_packet(ssrc, 12, 100, 8));
+    }
+
+    #[test]
+    fn unwrap_without_osn() {
+        init_logs();
+        let data = generate_rtp_packet(0x12345678, 0, 0, 1);
+        let rtx = RtpPacket::parse(&data).unwrap();
+        assert!(unwrap_packet(&rtx, 96, 0x1).is_none());
+    }
+}
@@ -11,10 +11,13 @@ use rand::prelude::*;
 
 use crate::rtpbin2::source::SourceRecvReply;
 
+use super::bundle::BundleExtensionIds;
 use super::source::{
     LocalReceiveSource, LocalSendSource, RemoteReceiveSource, RemoteSendSource, SourceState,
 };
-use super::time::system_time_to_ntp_time_u64;
+use super::time::{system_time_to_ntp_time_u64, NtpTime};
+use super::twcc::{self, Remb, Twcc, TwccPacket, TwccReceiver, TwccSender};
+use super::xr::{self, Dlrr, Xr, XrBlock};
 
 use gst::prelude::MulDiv;
 
@@ -26,9 +29,14 @@ const RTCP_ADDRESS_CONFLICT_TIMEOUT: Duration = RTCP_MIN_REPORT_INTERVAL.saturat
 // 5% of 8kB/s
 const RTCP_MIN_BANDWIDTH: usize = 400;
 const RTCP_MTU: usize = 1200;
+// Bogus RTCP XR round trip times are ignored
+const XR_MAX_ROUND_TRIP_TIME: Duration = Duration::from_secs(10);
 
 const UDP_IP_OVERHEAD_BYTES: usize = 28;
 
+// Maximum delay for sending transport-wide congestion control feedback
+const TWCC_FEEDBACK_MAX_DELAY: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Default)]
 struct RtcpTimeMembers {
     time: Option<Instant>,
@@ -73,6 +81,7 @@ pub struct Session {
     min_rtcp_interval: Duration,
     profile: RtpProfile,
     reduced_size_rtcp: bool,
+    rtcp_xr: bool,
     // state
     local_senders: HashMap<u32, LocalSendSource>,
     local_receivers: HashMap<u32, LocalReceiveSource>,
@@ -102,6 +111,15 @@ pub struct Session {
     // time for the next early rtcp to be sent
     next_early_rtcp_time: Option<Instant>,
     pending_rtcp_send: VecDeque<RtcpSendReply>,
+
+    // congestion control state
+    twcc_extension_id: Option<u8>,
+    bundle_extension_ids: BundleExtensionIds,
+    twcc_receiver: TwccReceiver,
+    twcc_sender: TwccSender,
+    twcc_feedback_sent: u64,
+    twcc_feedback_received: u64,
+    remb_bitrate: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -122,6 +140,9 @@ pub enum RecvReply {
     Ignore,
     /// A ssrc collision has been detected for the provided ssrc. Sender (us) should change ssrc.
     SsrcCollision(u32),
+    /// The MID and/or RID of the provided ssrc was discovered or changed.  Retrieve them from the
+    /// remote send source before calling recv() again.
+    NewBundleIds(u32),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -148,8 +169,14 @@ pub enum RtcpRecvReply {
     TimerReconsideration,
     /// Request a key unit for the given SSRC of ours
     RequestKeyUnit { ssrcs: Vec<u32>, fir: bool },
+    /// Request retransmission of the given sequence numbers for the given SSRC of ours
+    RequestRetransmission { ssrc: u32, seqnums: Vec<u16> },
     /// A new cname to ssrc mapping was found in a sdes: (cname, ssrc)
     NewCName((String, u32)),
+    /// Transport-wide congestion control feedback was received for packets of ours
+    TwccFeedback(Vec<TwccPacket>),
+    /// A receiver estimated maximum bitrate (in bits per second) was received for the given SSRCs
+    Remb { bitrate: u64, ssrcs: Vec<u32> },
     /// A new RTP to NTP mapping was received for an ssrc: (ssrc, RTP, NTP)
     NewRtpNtp((u32, u32, u64)),
     /// A ssrc has byed
@@ -170,6 +197,12 @@ pub enum RequestRemoteKeyUnitReply {
     TimerReconsideration,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RequestRetransmissionReply {
+    /// RTCP timer needs to be reconsidered.  Call poll_rtcp_send_timeout() to get the new time
+    TimerReconsideration,
+}
+
 impl Session {
     pub fn new() -> Self {
         let cname = generate_cname();
@@ -179,6 +212,7 @@ impl Session {
             min_rtcp_interval: RTCP_MIN_REPORT_INTERVAL,
             profile: RtpProfile::default(),
             reduced_size_rtcp: false,
+            rtcp_xr: false,
             local_senders: HashMap::new(),
             // also known as remote_senders
             local_receivers: HashMap::new(),
@@ -202,6 +236,13 @@ impl Session {
             last_rtcp_handle_time: None,
             is_point_to_point: true,
             pending_rtcp_send: VecDeque::new(),
+            twcc_extension_id: None,
+            bundle_extension_ids: BundleExtensionIds::default(),
+            twcc_receiver: TwccReceiver::default(),
+            twcc_sender: TwccSender::default(),
+            twcc_feedback_sent: 0,
+            twcc_feedback_received: 0,
+            remb_bitrate: None,
         }
     }
 
@@ -220,6 +261,55 @@ impl Session {
         self.reduced_size_rtcp = reduced_size_rtcp;
     }
 
+    /// Set usage of RTCP extended reports (RFC 3611)
+    pub fn set_rtcp_xr(&mut self, rtcp_xr: bool) {
+        self.rtcp_xr = rtcp_xr;
+    }
+
+    /// Set the latency of the jitterbuffer used for the remote sender with `ssrc`.  This is
+    /// reported in RTCP XR VoIP metrics report blocks.
+    pub fn set_jitterbuffer_latency(&mut self, ssrc: u32, latency: Duration) {
+        if let Some(source) = self.remote_senders.get_mut(&ssrc) {
+            source.set_jitterbuffer_latency(latency);
+        }
+    }
+
+    /// Set the RTP header extension id carrying the transport-wide sequence number
+    pub fn set_twcc_extension_id(&mut self, ext_id: Option<u8>) {
+        self.twcc_extension_id = ext_id;
+    }
+
+    /// Set the RTP header extension ids carrying the MID / RID of received streams
+    pub(crate) fn set_bundle_extension_ids(&mut self, ids: BundleExtensionIds) {
+        self.bundle_extension_ids = ids;
+    }
+
+    /// The RTP header extension ids carrying the MID / RID of received streams
+    pub(crate) fn bundle_extension_ids(&self) -> &BundleExtensionIds {
+        &self.bundle_extension_ids
+    }
+
+    /// Set the receiver estimated maximum bitrate (in bits per second) to send to the remote
+    /// senders with each RTCP packet
+    pub fn set_remb_bitrate(&mut self, bitrate: Option<u64>) {
+        self.remb_bitrate = bitrate;
+    }
+
+    /// The receiver estimated maximum bitrate (in bits per second) sent to the remote senders
+    pub fn remb_bitrate(&self) -> Option<u64> {
+        self.remb_bitrate
+    }
+
+    /// The number of sent transport-wide congestion control feedback packets
+    pub fn twcc_feedback_sent(&self) -> u64 {
+        self.twcc_feedback_sent
+    }
+
+    /// The number of received transport-wide congestion control feedback packets
+    pub fn twcc_feedback_received(&self) -> u64 {
+        self.twcc_feedback_received
+    }
+
     fn n_members(&self) -> usize {
         self.bye_state
             .as_ref()
@@ -354,9 +444,29 @@ impl Session {
 
         // TODO: handle CSRCs
 
+        if let Some(seqnum) = self
+            .twcc_extension_id
+            .and_then(|ext_id| twcc::read_twcc_seqnum(rtp, ext_id))
+        {
+            self.twcc_receiver.packet_received(rtp.ssrc(), seqnum, now);
+        }
+
         let clock_rate = self.clock_rate_from_pt(rtp.payload_type());
 
         if let Some(source) = self.remote_senders.get_mut(&rtp.ssrc()) {
+            let mut bundle_ids = self.bundle_extension_ids.read(rtp);
+            // RTX packets are unwrapped before getting here, the repaired RID they carry
+            // identifies the original stream and not this one
+            bundle_ids.repaired_rid = None;
+            if !bundle_ids.is_empty() && source.update_bundle_ids(bundle_ids) {
+                trace!(
+                    "new bundle ids for ssrc {}: {:?}",
+                    rtp.ssrc(),
+                    source.bundle_ids()
+                );
+                return RecvReply::NewBundleIds(rtp.ssrc());
+            }
+
             match source.recv_packet(
                 rtp.payload().len() as u32,
                 now,
@@ -432,6 +542,18 @@ impl Session {
                     rtp.timestamp(),
                     rtp.payload_type(),
                 );
+                if let Some(seqnum) = self
+                    .twcc_extension_id
+                    .and_then(|ext_id| twcc::read_twcc_seqnum(rtp, ext_id))
+                {
+                    self.twcc_sender.packet_sent(
+                        rtp.ssrc(),
+                        rtp.payload_type(),
+                        seqnum,
+                        twcc::packet_size(rtp),
+                        now,
+                    );
+                }
                 SendReply::Passthrough
             } else {
                 trace!("no clock rate for pt:{}, dropping", rtp.payload_type());
@@ -494,6 +616,57 @@ impl Session {
         ret
     }
 
+    fn handle_xr(&mut self, xr: &Xr, from: Option<SocketAddr>, now: Instant, ntp_time: SystemTime) {
+        let sender_ssrc = xr.ssrc();
+        trace!("Received extended report from {sender_ssrc}: {xr:?}");
+
+        for block in xr.blocks() {
+            match block {
+                // The compound packet starts with a SR or RR that already created the source
+                XrBlock::Rrtr(ntp) => {
+                    let last_rr = NtpTime::from(*ntp).as_u32();
+                    if let Some(source) = self.remote_senders.get_mut(&sender_ssrc) {
+                        source.set_rtcp_from(from);
+                        source.set_last_activity(now);
+                        source.set_received_rrtr(last_rr, ntp_time);
+                    } else if let Some(source) = self.remote_receivers.get_mut(&sender_ssrc) {
+                        source.set_rtcp_from(from);
+                        source.set_last_activity(now);
+                        source.set_received_rrtr(last_rr, ntp_time);
+                    }
+                }
+                XrBlock::Dlrr(dlrrs) => {
+                    for dlrr in dlrrs.iter() {
+                        // Only replies to our own receiver reference times are relevant
+                        if !self.local_senders.contains_key(&dlrr.ssrc)
+                            && !self.local_receivers.contains_key(&dlrr.ssrc)
+                        {
+                            continue;
+                        }
+                        let Some(rtt) = xr_round_trip_time(dlrr, ntp_time) else {
+                            continue;
+                        };
+                        trace!("RTCP XR round trip time to {sender_ssrc} is {rtt:?}");
+                        if let Some(source) = self.remote_senders.get_mut(&sender_ssrc) {
+                            source.set_xr_round_trip_time(rtt);
+                        } else if let Some(source) = self.remote_receivers.get_mut(&sender_ssrc) {
+                            source.set_xr_round_trip_time(rtt);
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(source) = block
+                        .media_ssrc()
+                        .and_then(|ssrc| self.local_senders.get_mut(&ssrc))
+                    {
+                        source.add_received_xr_block(sender_ssrc, block);
+                        source.set_last_activity(now);
+                    }
+                }
+            }
+        }
+    }
+
     fn rtcp_reverse_consideration(&mut self, initial_n_members: usize, now: Instant) -> bool {
         let n_members = self.n_members();
         if n_members >= self.p_members() {
@@ -729,9 +902,64 @@ impl Session {
                             // TODO: What to do with the sequence?
                             fir.entries().map(|entry| entry.ssrc()),
                         );
+                    } else if let Ok(remb) = pf.parse_fci::<Remb>() {
+                        let ssrcs = remb
+                            .ssrcs()
+                            .iter()
+                            .copied()
+                            .filter(|ssrc| self.local_senders.contains_key(ssrc))
+                            .collect::<Vec<_>>();
+                        trace!(
+                            "Received REMB of {} bps from {} for ssrcs {ssrcs:?}",
+                            remb.bitrate(),
+                            pf.sender_ssrc()
+                        );
+                        if !ssrcs.is_empty() {
+                            for ssrc in ssrcs.iter() {
+                                let source = self.local_senders.get_mut(ssrc).unwrap();
+                                source.set_remb_bitrate(remb.bitrate());
+                            }
+                            replies.push(RtcpRecvReply::Remb {
+                                bitrate: remb.bitrate(),
+                                ssrcs,
+                            });
+                        }
+                    }
+                }
+                Ok(Packet::TransportFeedback(tf)) => {
+                    if let Ok(nack) = tf.parse_fci::<rtcp_types::Nack>() {
+                        let ssrc = tf.media_ssrc();
+                        if self.local_senders.contains_key(&ssrc) {
+                            let seqnums = nack.entries().collect::<Vec<_>>();
+                            trace!(
+                                "Retransmission requested by {} for ssrc {ssrc}: {seqnums:?}",
+                                tf.sender_ssrc()
+                            );
+                            replies.push(RtcpRecvReply::RequestRetransmission { ssrc, seqnums });
+                        } else {
+                            trace!("Retransmission requested for unknown ssrc {ssrc}");
+                        }
+                    } else if let Ok(twcc) = tf.parse_fci::<Twcc>() {
+                        self.twcc_feedback_received += 1;
+                        let packets = self.twcc_sender.handle_feedback(&twcc);
+                        trace!(
+                            "Received transport-wide feedback from {} for {} packets",
+                            tf.sender_ssrc(),
+                            packets.len()
+                        );
+                        if !packets.is_empty() {
+                            replies.push(RtcpRecvReply::TwccFeedback(packets));
+                        }
+                    }
+                }
+                Ok(Packet::Unknown(unknown)) => {
+                    if unknown.type_() == xr::XR_PACKET_TYPE {
+                        match Xr::parse(unknown.data()) {
+                            Ok(xr) => self.handle_xr(&xr, from, now, ntp_time),
+                            Err(err) => trace!("Failed to parse extended report: {err:?}"),
+                        }
                     }
                 }
-                Ok(Packet::TransportFeedback(_)) | Ok(Packet::Unknown(_)) => (),
                 // TODO: in RFC4585 profile, need to listen for feedback messages and remove any
                 // that we would have sent
                 Err(_) => (),
@@ -1076,6 +1304,67 @@ impl Session {
         rtcp
     }
 
+    fn generate_xr<'a>(
+        &mut self,
+        rtcp: CompoundBuilder<'a>,
+        now: Instant,
+        ntp_now: SystemTime,
+        minimum: bool,
+    ) -> CompoundBuilder<'a> {
+        if !self.rtcp_xr || minimum {
+            return rtcp;
+        }
+
+        let ssrc = self.ensure_internal_send_src();
+        let ntp_time = system_time_to_ntp_time_u64(ntp_now);
+        let mut xr = Xr::builder(ssrc).add_block(XrBlock::Rrtr(ntp_time.as_u64()));
+
+        let dlrrs = self
+            .remote_senders
+            .values()
+            .filter_map(|source| source.received_rrtr().map(|rrtr| (source.ssrc(), rrtr)))
+            .chain(
+                self.remote_receivers
+                    .values()
+                    .filter_map(|source| source.received_rrtr().map(|rrtr| (source.ssrc(), rrtr))),
+            )
+            .map(|(ssrc, (last_rr, received))| {
+                let delay = ntp_now.duration_since(received).unwrap_or_default();
+                Dlrr {
+                    ssrc,
+                    last_rr,
+                    delay_since_last_rr: (delay.as_secs_f64() * 65536.0) as u32,
+                }
+            })
+            .collect::<Vec<_>>();
+        if !dlrrs.is_empty() {
+            xr = xr.add_block(XrBlock::Dlrr(dlrrs));
+        }
+
+        let available = RTCP_MTU.saturating_sub(rtcp.calculate_size().unwrap_or(RTCP_MTU));
+        let remote_ssrcs = self
+            .remote_senders
+            .values()
+            .filter(|source| source.state() == SourceState::Normal)
+            .map(|source| source.ssrc())
+            .collect::<Vec<_>>();
+        for remote_ssrc in remote_ssrcs {
+            let rtt = self.round_trip_time(remote_ssrc);
+            let source = self.remote_senders.get_mut(&remote_ssrc).unwrap();
+            for block in source.generate_xr_blocks(now, rtt) {
+                // Twice the maximum feedback size is kept available for other packets
+                if xr.size_with_block(&block) + 2 * twcc::MAX_FEEDBACK_SIZE < available {
+                    xr = xr.add_block(block);
+                } else {
+                    debug!("No space left for extended report block {block:?}");
+                }
+            }
+        }
+
+        debug!("Generating extended report: {xr:?}");
+        rtcp.add_packet(xr)
+    }
+
     fn generate_pli<'a>(
         &mut self,
         mut rtcp: CompoundBuilder<'a>,
@@ -1120,6 +1409,86 @@ impl Session {
         rtcp
     }
 
+    fn generate_nack<'a>(
+        &mut self,
+        mut rtcp: CompoundBuilder<'a>,
+        _now: Instant,
+    ) -> CompoundBuilder<'a> {
+        if !self
+            .remote_senders
+            .values()
+            .any(|source| source.has_pending_nack())
+        {
+            return rtcp;
+        }
+
+        let ssrc = self.ensure_internal_send_src();
+
+        for source in self.remote_senders.values_mut() {
+            if let Some(nack) = source.generate_nack() {
+                debug!("Generating NACK for sender {}: {:?}", source.ssrc(), nack);
+                rtcp = rtcp.add_packet(
+                    rtcp_types::TransportFeedback::builder_owned(nack)
+                        .sender_ssrc(ssrc)
+                        .media_ssrc(source.ssrc()),
+                );
+            }
+        }
+        rtcp
+    }
+
+    fn generate_remb<'a>(
+        &mut self,
+        rtcp: CompoundBuilder<'a>,
+        _now: Instant,
+    ) -> CompoundBuilder<'a> {
+        let Some(bitrate) = self.remb_bitrate else {
+            return rtcp;
+        };
+        if self.remote_senders.is_empty() {
+            return rtcp;
+        }
+
+        let ssrc = self.ensure_internal_send_src();
+
+        let mut remb = Remb::builder(bitrate);
+        for &media_ssrc in self.remote_senders.keys().take(u8::MAX as usize) {
+            remb = remb.add_ssrc(media_ssrc);
+        }
+        debug!("Generating REMB: {remb:?}");
+        rtcp.add_packet(rtcp_types::PayloadFeedback::builder_owned(remb).sender_ssrc(ssrc))
+    }
+
+    fn generate_twcc<'a>(
+        &mut self,
+        mut rtcp: CompoundBuilder<'a>,
+        _now: Instant,
+    ) -> CompoundBuilder<'a> {
+        if !self.twcc_receiver.has_pending_feedback() {
+            return rtcp;
+        }
+
+        let ssrc = self.ensure_internal_send_src();
+        let media_ssrc = self.twcc_receiver.media_ssrc().unwrap_or(0);
+
+        // Remaining feedback is sent with the next RTCP packet
+        while self.twcc_receiver.has_pending_feedback()
+            && rtcp.calculate_size().unwrap_or(RTCP_MTU) + twcc::MAX_FEEDBACK_SIZE < RTCP_MTU
+        {
+            let Some(twcc) = self.twcc_receiver.generate_feedback() else {
+                break;
+            };
+            trace!("Generating transport-wide feedback: {twcc:?}");
+            self.twcc_feedback_sent += 1;
+            rtcp = rtcp.add_packet(
+                rtcp_types::TransportFeedback::builder_owned(twcc)
+                    .sender_ssrc(ssrc)
+                    .media_ssrc(media_ssrc),
+            );
+        }
+        rtcp
+    }
+
     // RFC 3550 6.3.5
     // FIXME: we should surface this information to the element in order
     // to perform clean up of the sync context
@@ -1235,7 +1604,11 @@ impl Session {
             rtcp = self.generate_sdes(rtcp, is_early);
             rtcp = self.generate_pli(rtcp, now);
             rtcp = self.generate_fir(rtcp, now);
+            rtcp = self.generate_nack(rtcp, now);
+            rtcp = self.generate_remb(rtcp, now);
             rtcp = self.generate_bye(rtcp, now);
+            rtcp = self.generate_xr(rtcp, now, ntp_now, is_early);
+            rtcp = self.generate_twcc(rtcp, now);
 
             let size = rtcp.calculate_size().unwrap();
             // TODO: handle dropping data
@@ -1585,6 +1958,105 @@ impl Session {
 
         replies
     }
+
+    /// Request retransmission of the provided sequence numbers from the remote sender with
+    /// `ssrc`.  Retransmission requests are dropped if they can't be sent within `max_delay`.
+    pub(crate) fn request_retransmission(
+        &mut self,
+        now: Instant,
+        ssrc: u32,
+        seqnums: impl IntoIterator<Item = u16>,
+        max_delay: Duration,
+    ) -> Vec<RequestRetransmissionReply> {
+        let mut replies = Vec::new();
+
+        if !self.remote_senders.contains_key(&ssrc) {
+            trace!("No remote sender with ssrc {ssrc} known");
+            return replies;
+        };
+
+        let res = self.request_early_rtcp(now, max_delay);
+        if res == RequestEarlyRtcpResult::TimerReconsideration {
+            replies.push(RequestRetransmissionReply::TimerReconsideration);
+        }
+
+        if res != RequestEarlyRtcpResult::NotScheduled {
+            let source = self.remote_senders.get_mut(&ssrc).unwrap();
+            source.request_retransmission(seqnums);
+        }
+
+        replies
+    }
+
+    /// The round trip time to the remote source with `ssrc`, if known.  This is only available if
+    /// the remote source sends report blocks for one of our senders, or RTCP XR DLRR report blocks
+    /// for our RTCP XR receiver reference times.
+    pub(crate) fn round_trip_time(&self, ssrc: u32) -> Option<Duration> {
+        self.local_senders
+            .values()
+            .filter_map(|sender| {
+                sender
+                    .received_report_blocks()
+                    .find(|(rb_ssrc, _rb)| *rb_ssrc == ssrc)
+                    .map(|(_ssrc, rb)| rb.round_trip_time())
+            })
+            .find(|rtt| !rtt.is_zero())
+            .or_else(|| {
+                self.remote_senders
+                    .get(&ssrc)
+                    .and_then(|source| source.xr_round_trip_time())
+            })
+            .or_else(|| {
+                self.remote_receivers
+                    .get(&ssrc)
+                    .and_then(|source| source.xr_round_trip_time())
+            })
+    }
+
+    /// Schedule an early RTCP packet for sending pending transport-wide feedback, if possible.
+    ///
+    /// Returns `true` if the RTCP timer needs to be reconsidered.
+    pub(crate) fn schedule_twcc_feedback(&mut self, now: Instant) -> bool {
+        if !self.profile.is_feedback()
+            || !self.twcc_receiver.has_pending_feedback()
+            || self.next_early_rtcp_time.is_some()
+        {
+            return false;
+        }
+
+        self.request_early_rtcp(now, TWCC_FEEDBACK_MAX_DELAY)
+            == RequestEarlyRtcpResult::TimerReconsideration
+    }
+
+    /// Generate a new ssrc that is not used in this session yet
+    pub(crate) fn generate_unused_ssrc(&self) -> u32 {
+        loop {
+            let ssrc = generate_ssrc();
+            if !self.have_ssrc(ssrc) {
+                return ssrc;
+            }
+        }
+    }
+}
+
+// RFC 3611 4.5: round trip time from a DLRR sub-block replying to one of our RRTRs
+fn xr_round_trip_time(dlrr: &Dlrr, ntp_time: SystemTime) -> Option<Duration> {
+    if dlrr.last_rr == 0 {
+        return None;
+    }
+
+    let now = system_time_to_ntp_time_u64(ntp_time).as_u32();
+    let rtt = now
+        .wrapping_sub(dlrr.last_rr)
+        .wrapping_sub(dlrr.delay_since_last_rr);
+    // 16.16 fixed point
+    let rtt = Duration::from_nanos(rtt as u64 * 1_000_000_000 / 65_536);
+    if rtt > XR_MAX_ROUND_TRIP_TIME {
+        trace!("Ignoring bogus RTCP XR round trip time {rtt:?}");
+        return None;
+    }
+
+    Some(rtt)
 }
 
 fn generate_cname() -> String {
@@ -2569,6 +3041,278 @@ pub(crate) mod tests {
         assert_eq!(n_sr_ssrc, 1);
     }
 
+    #[test]
+    fn receive_nack() {
+        init_logs();
+        let mut session = Session::new();
+        session.set_pt_clock_rate(TEST_PT, TEST_CLOCK_RATE);
+        let now = Instant::now();
+        let ntp_now = SystemTime::now();
+        let send_ssrc = 0x11223344;
+        let recv_ssrc = 0x55667788;
+
+        let rtp_data = generate_rtp_packet(send_ssrc, 500, 0, 4);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        assert_eq!(
+            session.handle_send(&packet, now),
+            SendReply::NewSsrc(send_ssrc, TEST_PT)
+        );
+        assert_eq!(session.handle_send(&packet, now), SendReply::Passthrough);
+
+        let mut data = vec![0; 128];
+        let len = Compound::builder()
+            .add_packet(ReceiverReport::builder(recv_ssrc))
+            .add_packet(
+                TransportFeedback::builder_owned(
+                    Nack::builder().add_rtp_sequences([500, 502, 503]),
+                )
+                .sender_ssrc(recv_ssrc)
+                .media_ssrc(send_ssrc),
+            )
+            .add_packet(
+                TransportFeedback::builder_owned(Nack::builder().add_rtp_sequences([10]))
+                    .sender_ssrc(recv_ssrc)
+                    .media_ssrc(0x1),
+            )
+            .write_into(&mut data)
+            .unwrap();
+        let rtcp = Compound::parse(&data[..len]).unwrap();
+        assert_eq!(
+            session.handle_rtcp_recv(rtcp, len, None, now, ntp_now),
+            vec![
+                RtcpRecvReply::NewSsrc(recv_ssrc),
+                RtcpRecvReply::RequestRetransmission {
+                    ssrc: send_ssrc,
+                    seqnums: vec![500, 502, 503]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn send_nack() {
+        init_logs();
+        let mut session = Session::new();
+        session.set_pt_clock_rate(TEST_PT, TEST_CLOCK_RATE);
+        session.set_profile(RtpProfile::Avpf);
+        let now = Instant::now();
+        let ntp_now = SystemTime::now();
+        let ssrc = 0x11223344;
+
+        let rtp_data = generate_rtp_packet(ssrc, 500, 0, 4);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        session_recv_first_packet_disable_probation(&mut session, &packet, now);
+        assert_eq!(
+            session.handle_recv(&packet, None, now),
+            RecvReply::Passthrough
+        );
+
+        // unknown senders can't be asked for retransmissions
+        assert!(session
+            .request_retransmission(now, 0x1, [10], RTCP_MIN_REPORT_INTERVAL)
+            .is_empty());
+
+        // complete first regular rtcp
+        let (rtcp_data, now, ntp_now) = next_rtcp_packet(&mut session, now, ntp_now);
+        let RtcpSendReply::Data(_rtcp_data) = rtcp_data else {
+            unreachable!();
+        };
+
+        assert!(session
+            .request_retransmission(now, ssrc, [502, 503, 502], RTCP_MIN_REPORT_INTERVAL)
+            .is_empty());
+        assert!(session.next_early_rtcp_time.is_some());
+
+        let (rtcp_data, now, ntp_now) = next_rtcp_packet(&mut session, now, ntp_now);
+        let RtcpSendReply::Data(rtcp_data) = rtcp_data else {
+            unreachable!();
+        };
+        let rtcp = Compound::parse(&rtcp_data).unwrap();
+        let mut n_nack = 0;
+        for p in rtcp {
+            trace!("{p:?}");
+            if let Ok(Packet::TransportFeedback(tf)) = p {
+                assert_eq!(tf.media_ssrc(), ssrc);
+                let nack = tf.parse_fci::<Nack>().unwrap();
+                assert_eq!(nack.entries().collect::<Vec<_>>(), vec![502, 503]);
+                n_nack += 1;
+            }
+        }
+        assert_eq!(n_nack, 1);
+
+        // NACKs are only sent once
+        let (rtcp_data, _now, _ntp_now) = next_rtcp_packet(&mut session, now, ntp_now);
+        let RtcpSendReply::Data(rtcp_data) = rtcp_data else {
+            unreachable!();
+        };
+        let rtcp = Compound::parse(&rtcp_data).unwrap();
+        for p in rtcp {
+            assert!(!matches!(p, Ok(Packet::TransportFeedback(_))));
+        }
+    }
+
+    const TEST_TWCC_EXT_ID: u8 = 5;
+
+    fn generate_rtp_packet_with_twcc(ssrc: u32, seq_no: u16, twcc_seqnum: u16) -> Vec<u8> {
+        let [hi, lo] = twcc_seqnum.to_be_bytes();
+        let ext = [(TEST_TWCC_EXT_ID << 4) | 0x1, hi, lo, 0];
+        RtpPacketBuilder::<&[u8], &[u8]>::new()
+            .payload_type(TEST_PT)
+            .ssrc(ssrc)
+            .sequence_number(seq_no)
+            .extension(0xBEDE, ext.as_slice())
+            .payload([1; 4].as_slice())
+            .write_vec()
+            .unwrap()
+    }
+
+    #[test]
+    fn send_twcc_feedback() {
+        init_logs();
+        let mut session = Session::new();
+        session.set_pt_clock_rate(TEST_PT, TEST_CLOCK_RATE);
+        session.set_profile(RtpProfile::Avpf);
+        session.set_twcc_extension_id(Some(TEST_TWCC_EXT_ID));
+        let mut now = Instant::now();
+        let ntp_now = SystemTime::now();
+        let ssrc = 0x11223344;
+
+        let rtp_data = generate_rtp_packet_with_twcc(ssrc, 500, 10);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        session_recv_first_packet_disable_probation(&mut session, &packet, now);
+        assert_eq!(
+            session.handle_recv(&packet, None, now),
+            RecvReply::Passthrough
+        );
+        // transport-wide seqnum 12 is lost
+        for (seq_no, twcc_seqnum) in [(501, 11), (502, 13)] {
+            now += Duration::from_millis(10);
+            let rtp_data = generate_rtp_packet_with_twcc(ssrc, seq_no, twcc_seqnum);
+            let packet = RtpPacket::parse(&rtp_data).unwrap();
+            assert_eq!(
+                session.handle_recv(&packet, None, now),
+                RecvReply::Passthrough
+            );
+        }
+
+        let (rtcp_data, now, ntp_now) = next_rtcp_packet(&mut session, now, ntp_now);
+        let RtcpSendReply::Data(rtcp_data) = rtcp_data else {
+            unreachable!();
+        };
+        let rtcp = Compound::parse(&rtcp_data).unwrap();
+        let mut n_twcc = 0;
+        for p in rtcp {
+            trace!("{p:?}");
+            if let Ok(Packet::TransportFeedback(tf)) = p {
+                assert_eq!(tf.media_ssrc(), ssrc);
+                let twcc = tf.parse_fci::<Twcc>().unwrap();
+                assert_eq!(twcc.base_seqnum(), 10);
+                assert_eq!(twcc.feedback_packet_count(), 0);
+                let statuses = twcc.statuses();
+                assert_eq!(statuses.len(), 4);
+                assert!(matches!(statuses[0], twcc::TwccStatus::Received(_)));
+                // 10ms in 250us units
+                assert_eq!(statuses[1], twcc::TwccStatus::Received(40));
+                assert_eq!(statuses[2], twcc::TwccStatus::NotReceived);
+                assert_eq!(statuses[3], twcc::TwccStatus::Received(40));
+                n_twcc += 1;
+            }
+        }
+        assert_eq!(n_twcc, 1);
+        assert_eq!(session.twcc_feedback_sent(), 1);
+
+        // feedback is only sent once
+        let (rtcp_data, _now, _ntp_now) = next_rtcp_packet(&mut session, now, ntp_now);
+        let RtcpSendReply::Data(rtcp_data) = rtcp_data else {
+            unreachable!();
+        };
+        let rtcp = Compound::parse(&rtcp_data).unwrap();
+        for p in rtcp {
+            assert!(!matches!(p, Ok(Packet::TransportFeedback(_))));
+        }
+    }
+
+    #[test]
+    fn receive_twcc_and_remb() {
+        init_logs();
+        let mut session = Session::new();
+        session.set_pt_clock_rate(TEST_PT, TEST_CLOCK_RATE);
+        session.set_twcc_extension_id(Some(TEST_TWCC_EXT_ID));
+        let mut now = Instant::now();
+        let ntp_now = SystemTime::now();
+        let send_ssrc = 0x11223344;
+        let recv_ssrc = 0x55667788;
+
+        let rtp_data = generate_rtp_packet_with_twcc(send_ssrc, 500, 10);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        assert_eq!(
+            session.handle_send(&packet, now),
+            SendReply::NewSsrc(send_ssrc, TEST_PT)
+        );
+        assert_eq!(session.handle_send(&packet, now), SendReply::Passthrough);
+        now += Duration::from_millis(10);
+        let rtp_data = generate_rtp_packet_with_twcc(send_ssrc, 501, 11);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        assert_eq!(session.handle_send(&packet, now), SendReply::Passthrough);
+        let size = twcc::packet_size(&packet);
+
+        let mut data = vec![0; 128];
+        let len = Compound::builder()
+            .add_packet(ReceiverReport::builder(recv_ssrc))
+            .add_packet(
+                TransportFeedback::builder_owned(
+                    Twcc::builder(10, 1, 0)
+                        .add_status(twcc::TwccStatus::Received(4))
+                        .add_status(twcc::TwccStatus::NotReceived),
+                )
+                .sender_ssrc(recv_ssrc)
+                .media_ssrc(send_ssrc),
+            )
+            .add_packet(
+                PayloadFeedback::builder_owned(Remb::builder(1_000_000).add_ssrc(send_ssrc))
+                    .sender_ssrc(recv_ssrc),
+            )
+            .write_into(&mut data)
+            .unwrap();
+        let rtcp = Compound::parse(&data[..len]).unwrap();
+        assert_eq!(
+            session.handle_rtcp_recv(rtcp, len, None, now, ntp_now),
+            vec![
+                RtcpRecvReply::NewSsrc(recv_ssrc),
+                RtcpRecvReply::TwccFeedback(vec![
+                    TwccPacket {
+                        seqnum: 10,
+                        ssrc: send_ssrc,
+                        pt: TEST_PT,
+                        size,
+                        local_time: Duration::ZERO,
+                        remote_time: Some(Duration::from_millis(65)),
+                    },
+                    TwccPacket {
+                        seqnum: 11,
+                        ssrc: send_ssrc,
+                        pt: TEST_PT,
+                        size,
+                        local_time: Duration::from_millis(10),
+                        remote_time: None,
+                    },
+                ]),
+                RtcpRecvReply::Remb {
+                    bitrate: 1_000_000,
+                    ssrcs: vec![send_ssrc],
+                },
+            ]
+        );
+        assert_eq!(session.twcc_feedback_received(), 1);
+        assert_eq!(
+            session
+                .local_send_source_by_ssrc(send_ssrc)
+                .unwrap()
+                .remb_bitrate(),
+            Some(1_000_000)
+        );
+    }
+
     #[test]
     fn point_to_point() {
         let mut session = Session::new();
@@ -2656,4 +3400,204 @@ pub(crate) mod tests {
         );
         assert!(!session.is_point_to_point);
     }
+
+    fn find_xr(rtcp_data: &[u8]) -> Option<Xr> {
+        let rtcp = Compound::parse(rtcp_data).unwrap();
+        rtcp.filter_map(|p| match p {
+            Ok(Packet::Unknown(unknown)) if unknown.type_() == xr::XR_PACKET_TYPE => {
+                Some(Xr::parse(unknown.data()).unwrap())
+            }
+            _ => None,
+        })
+        .next()
+    }
+
+    #[test]
+    fn rtcp_xr() {
+        init_logs();
+        let mut session = Session::new();
+        session.set_pt_clock_rate(TEST_PT, TEST_CLOCK_RATE);
+        session.set_rtcp_xr(true);
+        let now = Instant::now();
+        let ntp_now = SystemTime::now();
+        let ssrc = 0x11223344;
+
+        let rtp_data = generate_rtp_packet(ssrc, 500, 0, 4);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        session_recv_first_packet_disable_probation(&mut session, &packet, now);
+        assert_eq!(
+            session.handle_recv(&packet, None, now),
+            RecvReply::Passthrough
+        );
+        // seqnum 502 is lost
+        for seqnum in [501, 503] {
+            let rtp_data = generate_rtp_packet(ssrc, seqnum, 0, 4);
+            let packet = RtpPacket::parse(&rtp_data).unwrap();
+            assert_eq!(
+                session.handle_recv(&packet, None, now),
+                RecvReply::Passthrough
+            );
+        }
+
+        let (rtcp_data, now, ntp_now) = next_rtcp_packet(&mut session, now, ntp_now);
+        let RtcpSendReply::Data(rtcp_data) = rtcp_data else {
+            unreachable!();
+        };
+        let internal_ssrc = session.internal_ssrc().unwrap();
+        let xr = find_xr(&rtcp_data).unwrap();
+        assert_eq!(xr.ssrc(), internal_ssrc);
+        let XrBlock::Rrtr(rrtr) = xr.blocks()[0] else {
+            unreachable!();
+        };
+        assert!(xr.blocks().contains(&XrBlock::LossRle(xr::LossRle {
+            ssrc,
+            begin_seq: 500,
+            received: vec![true, true, false, true],
+        })));
+        let summary = xr
+            .blocks()
+            .iter()
+            .find_map(|block| match block {
+                XrBlock::StatisticsSummary(summary) => Some(*summary),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(summary.ssrc, ssrc);
+        assert_eq!(summary.begin_seq, 500);
+        assert_eq!(summary.end_seq, 504);
+        assert_eq!(summary.lost_packets, 1);
+        assert_eq!(summary.dup_packets, 0);
+        assert!(xr
+            .blocks()
+            .iter()
+            .any(|block| matches!(block, XrBlock::VoipMetrics(voip) if voip.ssrc == ssrc)));
+        assert_eq!(
+            session
+                .remote_send_source_by_ssrc(ssrc)
+                .unwrap()
+                .loss_pattern()
+                .lost(),
+            1
+        );
+
+        // The remote side replies to our RRTR 100ms later after holding it for 50ms, and sends
+        // its own RRTR
+        let now = now + Duration::from_millis(100);
+        let ntp_now = ntp_now + Duration::from_millis(100);
+        let remote_ntp = system_time_to_ntp_time_u64(ntp_now).as_u64();
+        let mut data = vec![0; 128];
+        let len = Compound::builder()
+            .add_packet(SenderReport::builder(ssrc).ntp_timestamp(remote_ntp))
+            .add_packet(
+                Xr::builder(ssrc)
+                    .add_block(XrBlock::Rrtr(remote_ntp))
+                    .add_block(XrBlock::Dlrr(vec![Dlrr {
+                        ssrc: internal_ssrc,
+                        last_rr: NtpTime::from(rrtr).as_u32(),
+                        delay_since_last_rr: 65536 / 20,
+                    }])),
+            )
+            .write_into(&mut data)
+            .unwrap();
+        let rtcp = Compound::parse(&data[..len]).unwrap();
+        assert_eq!(
+            session.handle_rtcp_recv(rtcp, len, None, now, ntp_now),
+            vec![RtcpRecvReply::NewRtpNtp((ssrc, 0, remote_ntp))]
+        );
+        let rtt = session.round_trip_time(ssrc).unwrap();
+        assert!(rtt.abs_diff(Duration::from_millis(50)) < Duration::from_millis(1));
+
+        let (rtcp_data, _now, _ntp_now) = next_rtcp_packet(&mut session, now, ntp_now);
+        let RtcpSendReply::Data(rtcp_data) = rtcp_data else {
+            unreachable!();
+        };
+        let xr = find_xr(&rtcp_data).unwrap();
+        let dlrr = xr
+            .blocks()
+            .iter()
+            .find_map(|block| match block {
+                XrBlock::Dlrr(dlrr) => Some(dlrr.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(dlrr.len(), 1);
+        assert_eq!(dlrr[0].ssrc, ssrc);
+        assert_eq!(dlrr[0].last_rr, NtpTime::from(remote_ntp).as_u32());
+    }
+
+    fn generate_rtp_packet_with_bundle_ids(
+        ssrc: u32,
+        seq_no: u16,
+        mid: &str,
+        rid: &str,
+    ) -> Vec<u8> {
+        let mut ext = vec![(1 << 4) | (mid.len() as u8 - 1)];
+        ext.extend_from_slice(mid.as_bytes());
+        ext.push((2 << 4) | (rid.len() as u8 - 1));
+        ext.extend_from_slice(rid.as_bytes());
+        ext.resize(ext.len().next_multiple_of(4), 0);
+        RtpPacketBuilder::<&[u8], &[u8]>::new()
+            .payload_type(TEST_PT)
+            .ssrc(ssrc)
+            .sequence_number(seq_no)
+            .extension(0xBEDE, ext.as_slice())
+            .payload([1; 4].as_slice())
+            .write_vec()
+            .unwrap()
+    }
+
+    #[test]
+    fn receive_bundle_ids() {
+        init_logs();
+        let mut session = Session::new();
+        session.set_pt_clock_rate(TEST_PT, TEST_CLOCK_RATE);
+        session.set_bundle_extension_ids(BundleExtensionIds {
+            mid: Some(1),
+            rid: Some(2),
+            repaired_rid: None,
+        });
+        let now = Instant::now();
+        let ssrc = 0x11223344;
+
+        let rtp_data = generate_rtp_packet_with_bundle_ids(ssrc, 500, "0", "hi");
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        session_recv_first_packet_disable_probation(&mut session, &packet, now);
+        assert_eq!(
+            session.handle_recv(&packet, None, now),
+            RecvReply::NewBundleIds(ssrc)
+        );
+        let ids = session
+            .remote_send_source_by_ssrc(ssrc)
+            .unwrap()
+            .bundle_ids()
+            .clone();
+        assert_eq!(ids.mid.as_deref(), Some("0"));
+        assert_eq!(ids.rid.as_deref(), Some("hi"));
+        assert_eq!(ids.repaired_rid, None);
+        assert_eq!(
+            session.handle_recv(&packet, None, now),
+            RecvReply::Passthrough
+        );
+
+        // Packets without or with unchanged identifiers are passed through
+        let rtp_data = generate_rtp_packet(ssrc, 501, 0, 4);
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        assert_eq!(
+            session.handle_recv(&packet, None, now),
+            RecvReply::Passthrough
+        );
+        let rtp_data = generate_rtp_packet_with_bundle_ids(ssrc, 502, "0", "hi");
+        let packet = RtpPacket::parse(&rtp_data).unwrap();
+        assert_eq!(
+            session.handle_recv(&packet, None, now),
+            RecvReply::Passthrough
+        );
+        assert_eq!(
+            session
+                .remote_send_source_by_ssrc(ssrc)
+                .unwrap()
+                .bundle_ids(),
+            &ids
+        );
+    }
 }
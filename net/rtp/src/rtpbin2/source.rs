@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{BTreeMap, HashMap, VecDeque},
     net::SocketAddr,
     time::{Duration, Instant, SystemTime},
 };
@@ -11,8 +11,10 @@ use rtcp_types::{ReportBlock, ReportBlockBuilder};
 use crate::utils::ExtendedSeqnum;
 
 use super::{
+    bundle::BundleIds,
     session::KeyUnitRequestType,
     time::{system_time_to_ntp_time_u64, NtpTime},
+    xr::{self, LossPattern, LossRle, StatisticsSummary, VoipMetrics, XrBlock},
 };
 
 use gst::prelude::MulDiv;
@@ -103,6 +105,13 @@ pub(crate) struct Source {
     sdes: HashMap<u8, String>,
     last_activity: Instant,
     payload_type: Option<u8>,
+    // Middle 32 bits of the NTP time of the last received RTCP XR receiver reference time and the
+    // local time when it was received
+    received_rrtr: Option<(u32, SystemTime)>,
+    // Round trip time calculated from a RTCP XR DLRR block sent by this source
+    xr_round_trip_time: Option<Duration>,
+    // MID / RID received in RTP header extensions
+    bundle_ids: BundleIds,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -120,6 +129,9 @@ impl Source {
             sdes: HashMap::new(),
             last_activity: Instant::now(),
             payload_type: None,
+            received_rrtr: None,
+            xr_round_trip_time: None,
+            bundle_ids: BundleIds::default(),
         }
     }
 
@@ -137,8 +149,7 @@ pub struct ReceivedRb {
 }
 
 impl ReceivedRb {
-    #[allow(unused)]
-    fn round_trip_time(&self) -> Duration {
+    pub(crate) fn round_trip_time(&self) -> Duration {
         let rb_send_ntp_time = self.rb.last_sr as u64 + self.rb.delay_since_last_sr as u64;
 
         // Can't calculate any round trip time
@@ -167,6 +178,30 @@ impl ReceivedRb {
     }
 }
 
+/// The last RTCP XR report blocks received from a remote receiver about a local sender
+#[derive(Debug, Default)]
+pub(crate) struct ReceivedXr {
+    pub loss_rle: Option<LossRle>,
+    pub statistics_summary: Option<StatisticsSummary>,
+    pub voip_metrics: Option<VoipMetrics>,
+}
+
+/// Reception statistics of a remote sender over the current RTCP XR reporting interval
+#[derive(Debug, Default)]
+struct XrInterval {
+    // First extended seqnum of the interval
+    begin: Option<u64>,
+    start_time: Option<Instant>,
+    // Number of receptions of each extended seqnum in the interval
+    received: BTreeMap<u64, u32>,
+    // Relative transit times between two packets in clock rate units
+    jitter_min: Option<u32>,
+    jitter_max: u32,
+    jitter_sum: f64,
+    jitter_sum_sq: f64,
+    jitter_count: u64,
+}
+
 #[derive(Debug)]
 pub struct LocalSendSource {
     source: Source,
@@ -179,6 +214,8 @@ pub struct LocalSendSource {
     bye_reason: Option<String>,
     last_sent_sr: Option<Sr>,
     last_received_rb: HashMap<u32, ReceivedRb>,
+    last_received_xr: HashMap<u32, ReceivedXr>,
+    remb_bitrate: Option<u64>,
 }
 
 impl LocalSendSource {
@@ -194,6 +231,8 @@ impl LocalSendSource {
             bye_reason: None,
             last_sent_sr: None,
             last_received_rb: HashMap::new(),
+            last_received_xr: HashMap::new(),
+            remb_bitrate: None,
         }
     }
 
@@ -312,6 +351,15 @@ impl LocalSendSource {
         self.bye_reason.as_ref()
     }
 
+    pub(crate) fn set_remb_bitrate(&mut self, bitrate: u64) {
+        self.remb_bitrate = Some(bitrate);
+    }
+
+    /// The last receiver estimated maximum bitrate received for this source
+    pub fn remb_bitrate(&self) -> Option<u64> {
+        self.remb_bitrate
+    }
+
     pub(crate) fn into_receive(self) -> LocalReceiveSource {
         LocalReceiveSource {
             source: self.source,
@@ -348,6 +396,21 @@ impl LocalSendSource {
     pub fn received_report_blocks(&self) -> impl Iterator<Item = (u32, &ReceivedRb)> + '_ {
         self.last_received_rb.iter().map(|(&k, v)| (k, v))
     }
+
+    pub(crate) fn add_received_xr_block(&mut self, sender_ssrc: u32, block: &XrBlock) {
+        let xr = self.last_received_xr.entry(sender_ssrc).or_default();
+        match block {
+            XrBlock::LossRle(rle) => xr.loss_rle = Some(rle.clone()),
+            XrBlock::StatisticsSummary(summary) => xr.statistics_summary = Some(*summary),
+            XrBlock::VoipMetrics(voip) => xr.voip_metrics = Some(*voip),
+            XrBlock::Rrtr(_) | XrBlock::Dlrr(_) => (),
+        }
+    }
+
+    /// The last RTCP XR report blocks received for this source by sender ssrc
+    pub fn received_xr(&self) -> impl Iterator<Item = (u32, &ReceivedXr)> + '_ {
+        self.last_received_xr.iter().map(|(&k, v)| (k, v))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -430,6 +493,16 @@ pub struct RemoteSendSource {
     send_fir_seqnum: u8,
     // Count from the ForceKeyUnitEvent to de-duplicate FIR
     send_fir_count: Option<u32>,
+    // Sequence numbers to request retransmission for with the next RTCP packet
+    send_nack: Vec<u16>,
+
+    // RTCP XR reception statistics
+    xr_interval: XrInterval,
+    loss_pattern: LossPattern,
+    duplicates: u64,
+    // Estimated duration of a single packet from the last reporting interval
+    packet_duration: Option<Duration>,
+    jitterbuffer_latency: Option<Duration>,
 }
 
 // The first time we recev a packet for jitter calculations
@@ -460,6 +533,12 @@ impl RemoteSendSource {
             send_fir: false,
             send_fir_seqnum: 0,
             send_fir_count: None,
+            send_nack: Vec::new(),
+            xr_interval: XrInterval::default(),
+            loss_pattern: LossPattern::default(),
+            duplicates: 0,
+            packet_duration: None,
+            jitterbuffer_latency: None,
         }
     }
 
@@ -696,6 +775,7 @@ impl RemoteSendSource {
         bytes: u32,
     ) {
         /* calculate jitter */
+        let mut transit_diff = None;
         if let Some(clock_rate) = clock_rate {
             let rtparrival =
                 ((now.duration_since(initial_time).as_micros() & 0xffff_ffff_ffff_ffff) as u32)
@@ -703,7 +783,9 @@ impl RemoteSendSource {
                     .unwrap();
             let transit = rtparrival.wrapping_sub(rtp_timestamp);
             let diff = if let Some(existing_transit) = self.transit {
-                existing_transit.abs_diff(transit)
+                let diff = existing_transit.abs_diff(transit);
+                transit_diff = Some(diff);
+                diff
             } else {
                 0
             };
@@ -722,6 +804,201 @@ impl RemoteSendSource {
         self.bitrate.add_entry(bytes as usize, now);
         self.recv_bytes = self.recv_bytes.wrapping_add(bytes as u64);
         self.recv_packets += 1;
+
+        self.xr_packet_received(ext_seqnum, now, transit_diff);
+    }
+
+    fn xr_packet_received(&mut self, ext_seqnum: u64, now: Instant, transit_diff: Option<u32>) {
+        let interval = &mut self.xr_interval;
+        let begin = *interval.begin.get_or_insert(ext_seqnum);
+        interval.start_time.get_or_insert(now);
+
+        if let Some(diff) = transit_diff {
+            interval.jitter_min = Some(interval.jitter_min.map_or(diff, |min| min.min(diff)));
+            interval.jitter_max = interval.jitter_max.max(diff);
+            interval.jitter_sum += diff as f64;
+            interval.jitter_sum_sq += diff as f64 * diff as f64;
+            interval.jitter_count += 1;
+        }
+
+        if ext_seqnum < begin {
+            trace!(
+                "source {} ignoring seqnum {ext_seqnum} from before the XR interval",
+                self.ssrc()
+            );
+            return;
+        }
+        *interval.received.entry(ext_seqnum).or_default() += 1;
+
+        // Only keep the latest packets if the interval grows too big
+        if ext_seqnum - begin >= xr::MAX_INTERVAL_PACKETS {
+            self.advance_xr_interval(ext_seqnum + 1 - xr::MAX_INTERVAL_PACKETS);
+        }
+    }
+
+    // Move the begin of the XR interval to `new_begin` and update the loss pattern with the
+    // packets before that
+    fn advance_xr_interval(&mut self, new_begin: u64) {
+        let interval = &mut self.xr_interval;
+        let Some(begin) = interval.begin else {
+            return;
+        };
+
+        for ext_seqnum in begin..new_begin {
+            let count = interval.received.get(&ext_seqnum).copied().unwrap_or(0);
+            self.loss_pattern.packet(count > 0);
+            self.duplicates += count.saturating_sub(1) as u64;
+        }
+        interval.received = interval.received.split_off(&new_begin);
+        interval.begin = Some(new_begin);
+    }
+
+    /// Generate the RTCP XR loss RLE, statistics summary and VoIP metrics blocks for the packets
+    /// received since the last call and start a new reporting interval.
+    pub(crate) fn generate_xr_blocks(
+        &mut self,
+        now: Instant,
+        round_trip_time: Option<Duration>,
+    ) -> Vec<XrBlock> {
+        let (Some(begin), Some(current)) = (self.xr_interval.begin, self.ext_seqnum.current())
+        else {
+            return vec![];
+        };
+        let end = current + 1;
+        if end <= begin {
+            return vec![];
+        }
+
+        let ssrc = self.ssrc();
+        let interval = &self.xr_interval;
+        let received = (begin..end)
+            .map(|ext_seqnum| interval.received.contains_key(&ext_seqnum))
+            .collect::<Vec<_>>();
+        let lost_packets = received.iter().filter(|&&received| !received).count() as u32;
+        let dup_packets = interval
+            .received
+            .range(begin..end)
+            .map(|(_ext_seqnum, &count)| count.saturating_sub(1))
+            .sum::<u32>();
+
+        let (mean_jitter, dev_jitter) = if interval.jitter_count > 0 {
+            let mean = interval.jitter_sum / interval.jitter_count as f64;
+            let variance = interval.jitter_sum_sq / interval.jitter_count as f64 - mean * mean;
+            (mean as u32, variance.max(0.0).sqrt() as u32)
+        } else {
+            (0, 0)
+        };
+
+        let summary = StatisticsSummary {
+            ssrc,
+            begin_seq: (begin & 0xffff) as u16,
+            end_seq: (end & 0xffff) as u16,
+            lost_packets,
+            dup_packets,
+            min_jitter: interval.jitter_min.unwrap_or(0),
+            max_jitter: interval.jitter_max,
+            mean_jitter,
+            dev_jitter,
+        };
+
+        if let Some(start_time) = interval.start_time {
+            self.packet_duration = Some(now.duration_since(start_time) / (end - begin) as u32);
+        }
+
+        // Packets after `end` that were received out of order stay in the new interval
+        self.advance_xr_interval(end);
+        let received = std::mem::take(&mut self.xr_interval.received);
+        self.xr_interval = XrInterval {
+            begin: Some(end),
+            start_time: Some(now),
+            received,
+            ..Default::default()
+        };
+
+        let to_ms = |dur: Duration| dur.as_millis().min(u16::MAX as u128) as u16;
+        let (burst_duration, gap_duration) = self
+            .loss_pattern
+            .burst_gap_duration(self.packet_duration.unwrap_or_default());
+        let jitterbuffer_latency = self.jitterbuffer_latency.map(to_ms).unwrap_or(0);
+        let voip_metrics = VoipMetrics {
+            ssrc,
+            loss_rate: self.loss_pattern.loss_rate(),
+            discard_rate: 0,
+            burst_density: self.loss_pattern.burst_density(),
+            gap_density: self.loss_pattern.gap_density(),
+            burst_duration: to_ms(burst_duration),
+            gap_duration: to_ms(gap_duration),
+            round_trip_delay: round_trip_time.map(to_ms).unwrap_or(0),
+            end_system_delay: 0,
+            gmin: xr::GMIN,
+            // non-adaptive jitter buffer
+            rx_config: if self.jitterbuffer_latency.is_some() {
+                0x20
+            } else {
+                0
+            },
+            jb_nominal: jitterbuffer_latency,
+            jb_maximum: jitterbuffer_latency,
+            jb_abs_max: jitterbuffer_latency,
+        };
+
+        vec![
+            XrBlock::LossRle(LossRle {
+                ssrc,
+                begin_seq: summary.begin_seq,
+                received,
+            }),
+            XrBlock::StatisticsSummary(summary),
+            XrBlock::VoipMetrics(voip_metrics),
+        ]
+    }
+
+    /// The burst / gap loss pattern of this source.  Only updated if RTCP XR reports are
+    /// generated.
+    pub(crate) fn loss_pattern(&self) -> &LossPattern {
+        &self.loss_pattern
+    }
+
+    /// The total number of duplicate packets that were included in RTCP XR reports
+    pub fn packets_duplicated(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// The estimated duration of a single packet from the last RTCP XR reporting interval
+    pub fn packet_duration(&self) -> Option<Duration> {
+        self.packet_duration
+    }
+
+    pub(crate) fn set_jitterbuffer_latency(&mut self, latency: Duration) {
+        self.jitterbuffer_latency = Some(latency);
+    }
+
+    pub(crate) fn set_received_rrtr(&mut self, last_rr: u32, ntp_time: SystemTime) {
+        self.source.received_rrtr = Some((last_rr, ntp_time));
+    }
+
+    pub(crate) fn received_rrtr(&self) -> Option<(u32, SystemTime)> {
+        self.source.received_rrtr
+    }
+
+    pub(crate) fn set_xr_round_trip_time(&mut self, rtt: Duration) {
+        self.source.xr_round_trip_time = Some(rtt);
+    }
+
+    /// The round trip time calculated from RTCP XR DLRR blocks sent by this source
+    pub fn xr_round_trip_time(&self) -> Option<Duration> {
+        self.source.xr_round_trip_time
+    }
+
+    /// The MID / RID of this source, if any was received
+    pub fn bundle_ids(&self) -> &BundleIds {
+        &self.source.bundle_ids
+    }
+
+    /// Update the MID / RID of this source from a received packet.  Returns `true` if they
+    /// changed.
+    pub(crate) fn update_bundle_ids(&mut self, ids: BundleIds) -> bool {
+        self.source.bundle_ids.update(ids)
     }
 
     pub(crate) fn received_sdes(&mut self, type_: u8, value: &[u8]) {
@@ -958,6 +1235,26 @@ impl RemoteSendSource {
             fir
         }
     }
+
+    pub(crate) fn request_retransmission(&mut self, seqnums: impl IntoIterator<Item = u16>) {
+        for seqnum in seqnums {
+            if !self.send_nack.contains(&seqnum) {
+                self.send_nack.push(seqnum);
+            }
+        }
+    }
+
+    pub(crate) fn has_pending_nack(&self) -> bool {
+        !self.send_nack.is_empty()
+    }
+
+    pub(crate) fn generate_nack(&mut self) -> Option<rtcp_types::NackBuilder> {
+        if self.send_nack.is_empty() {
+            return None;
+        }
+
+        Some(rtcp_types::Nack::builder().add_rtp_sequences(self.send_nack.drain(..)))
+    }
 }
 
 #[derive(Debug)]
@@ -1091,6 +1388,28 @@ impl RemoteReceiveSource {
         self.source.last_activity
     }
 
+    pub(crate) fn set_received_rrtr(&mut self, last_rr: u32, ntp_time: SystemTime) {
+        self.source.received_rrtr = Some((last_rr, ntp_time));
+    }
+
+    pub(crate) fn received_rrtr(&self) -> Option<(u32, SystemTime)> {
+        self.source.received_rrtr
+    }
+
+    pub(crate) fn set_xr_round_trip_time(&mut self, rtt: Duration) {
+        self.source.xr_round_trip_time = Some(rtt);
+    }
+
+    /// The round trip time calculated from RTCP XR DLRR blocks sent by this source
+    pub fn xr_round_trip_time(&self) -> Option<Duration> {
+        self.source.xr_round_trip_time
+    }
+
+    /// The MID / RID of this source, if any was received
+    pub fn bundle_ids(&self) -> &BundleIds {
+        &self.source.bundle_ids
+    }
+
     pub(crate) fn into_send(self) -> RemoteSendSource {
         RemoteSendSource {
             source: self.source,
@@ -1115,6 +1434,12 @@ impl RemoteReceiveSource {
             send_fir: false,
             send_fir_seqnum: 0,
             send_fir_count: None,
+            send_nack: Vec::new(),
+            xr_interval: XrInterval::default(),
+            loss_pattern: LossPattern::default(),
+            duplicates: 0,
+            packet_duration: None,
+            jitterbuffer_latency: None,
         }
     }
 
@@ -0,0 +1,1038 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Secure RTP (SRTP) and Secure RTCP (SRTCP) packet protection.
+//!
+//! Supports the AES counter mode ciphers with HMAC-SHA1 authentication from RFC 3711 and the
+//! AEAD AES-GCM ciphers from RFC 7714. Keys are configured with the same caps fields as used by
+//! the `srtpenc` and `srtpdec` elements.
+
+use std::collections::HashMap;
+
+use aes_gcm::aead::AeadInPlace;
+use aes_gcm::{Aes128Gcm, Aes256Gcm, KeyInit};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+type HmacSha1 = Hmac<Sha1>;
+
+// Key derivation labels (RFC 3711 4.3.1)
+const LABEL_RTP_ENCRYPTION: u8 = 0x00;
+const LABEL_RTCP_ENCRYPTION: u8 = 0x03;
+
+const AUTH_KEY_LEN: usize = 20;
+const AEAD_TAG_LEN: usize = 16;
+const SRTCP_INDEX_LEN: usize = 4;
+const SRTCP_E_FLAG: u32 = 0x8000_0000;
+const REPLAY_WINDOW_SIZE: u64 = 64;
+
+/// Caps fields describing an SRTP key
+const SRTP_CAPS_FIELDS: &[&str] = &[
+    "srtp-key",
+    "srtp-cipher",
+    "srtp-auth",
+    "srtcp-cipher",
+    "srtcp-auth",
+    "roc",
+    "mki",
+];
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SrtpError {
+    #[error("Invalid SRTP key configuration: {0}")]
+    InvalidKey(&'static str),
+
+    #[error("No key for SSRC {0:#010x}")]
+    NoKey(u32),
+
+    #[error("Packet is too short")]
+    Truncated,
+
+    #[error("Packet authentication failed")]
+    AuthenticationFailed,
+
+    #[error("Packet with index {0} was replayed")]
+    Replayed(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SrtpCipher {
+    Null,
+    Aes128Icm,
+    Aes256Icm,
+    Aes128Gcm,
+    Aes256Gcm,
+}
+
+impl SrtpCipher {
+    fn from_caps_str(s: &str) -> Option<Self> {
+        match s {
+            "null" => Some(Self::Null),
+            "aes-128-icm" => Some(Self::Aes128Icm),
+            "aes-256-icm" => Some(Self::Aes256Icm),
+            "aes-128-gcm" => Some(Self::Aes128Gcm),
+            "aes-256-gcm" => Some(Self::Aes256Gcm),
+            _ => None,
+        }
+    }
+
+    fn is_aead(self) -> bool {
+        matches!(self, Self::Aes128Gcm | Self::Aes256Gcm)
+    }
+
+    fn master_key_len(self) -> usize {
+        match self {
+            // the null cipher still derives the authentication key with AES-128
+            Self::Null | Self::Aes128Icm | Self::Aes128Gcm => 16,
+            Self::Aes256Icm | Self::Aes256Gcm => 32,
+        }
+    }
+
+    fn master_salt_len(self) -> usize {
+        if self.is_aead() {
+            12
+        } else {
+            14
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SrtpAuth {
+    Null,
+    HmacSha1_32,
+    HmacSha1_80,
+}
+
+impl SrtpAuth {
+    fn from_caps_str(s: &str) -> Option<Self> {
+        match s {
+            "null" => Some(Self::Null),
+            "hmac-sha1-32" => Some(Self::HmacSha1_32),
+            "hmac-sha1-80" => Some(Self::HmacSha1_80),
+            _ => None,
+        }
+    }
+
+    fn tag_len(self) -> usize {
+        match self {
+            Self::Null => 0,
+            Self::HmacSha1_32 => 4,
+            Self::HmacSha1_80 => 10,
+        }
+    }
+}
+
+/// The master key and the protection profile for an SSRC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SrtpPolicy {
+    rtp_cipher: SrtpCipher,
+    rtp_auth: SrtpAuth,
+    rtcp_cipher: SrtpCipher,
+    rtcp_auth: SrtpAuth,
+    master_key: Vec<u8>,
+    master_salt: Vec<u8>,
+    roc: u32,
+}
+
+impl SrtpPolicy {
+    /// Create a new policy. `key` is the master key followed by the master salt.
+    pub(crate) fn new(
+        rtp_cipher: SrtpCipher,
+        rtp_auth: SrtpAuth,
+        rtcp_cipher: SrtpCipher,
+        rtcp_auth: SrtpAuth,
+        key: &[u8],
+        roc: u32,
+    ) -> Result<Self, SrtpError> {
+        for (cipher, auth) in [(rtp_cipher, rtp_auth), (rtcp_cipher, rtcp_auth)] {
+            if cipher.is_aead() && auth != SrtpAuth::Null {
+                return Err(SrtpError::InvalidKey(
+                    "AEAD ciphers require null authentication",
+                ));
+            }
+        }
+        if rtp_cipher.master_key_len() != rtcp_cipher.master_key_len()
+            || rtp_cipher.master_salt_len() != rtcp_cipher.master_salt_len()
+        {
+            return Err(SrtpError::InvalidKey(
+                "SRTP and SRTCP ciphers must use the same master key size",
+            ));
+        }
+
+        let key_len = rtp_cipher.master_key_len();
+        if key.len() != key_len + rtp_cipher.master_salt_len() {
+            return Err(SrtpError::InvalidKey("wrong master key length"));
+        }
+
+        Ok(Self {
+            rtp_cipher,
+            rtp_auth,
+            rtcp_cipher,
+            rtcp_auth,
+            master_key: key[..key_len].to_vec(),
+            master_salt: key[key_len..].to_vec(),
+            roc,
+        })
+    }
+
+    /// Parse the policy from the `srtp-key`, `srtp-cipher`, `srtp-auth`, `srtcp-cipher`,
+    /// `srtcp-auth` and `roc` fields of `s`.
+    ///
+    /// Returns `None` if there is no `srtp-key` field.
+    pub(crate) fn from_structure(s: &gst::StructureRef) -> Result<Option<Self>, SrtpError> {
+        let Ok(key) = s.get::<gst::Buffer>("srtp-key") else {
+            return Ok(None);
+        };
+        if s.has_field("mki") {
+            return Err(SrtpError::InvalidKey("MKI is not supported"));
+        }
+
+        let cipher = |field, default| {
+            s.get_optional::<&str>(field)
+                .ok()
+                .flatten()
+                .map_or(Some(default), SrtpCipher::from_caps_str)
+                .ok_or(SrtpError::InvalidKey("unknown cipher"))
+        };
+        let auth = |field, default| {
+            s.get_optional::<&str>(field)
+                .ok()
+                .flatten()
+                .map_or(Some(default), SrtpAuth::from_caps_str)
+                .ok_or(SrtpError::InvalidKey("unknown authentication"))
+        };
+
+        let key = key
+            .map_readable()
+            .map_err(|_| SrtpError::InvalidKey("unreadable key"))?;
+
+        Self::new(
+            cipher("srtp-cipher", SrtpCipher::Aes128Icm)?,
+            auth("srtp-auth", SrtpAuth::HmacSha1_80)?,
+            cipher("srtcp-cipher", SrtpCipher::Aes128Icm)?,
+            auth("srtcp-auth", SrtpAuth::HmacSha1_80)?,
+            &key,
+            s.get::<u32>("roc").unwrap_or(0),
+        )
+        .map(Some)
+    }
+}
+
+/// Remove the SRTP key fields from `caps` and turn them into plain RTP caps
+pub(crate) fn rtp_caps_from_srtp_caps(caps: &gst::CapsRef) -> gst::Caps {
+    let mut caps = caps.to_owned();
+    for s in caps.make_mut().iter_mut() {
+        s.set_name("application/x-rtp");
+        s.remove_fields(SRTP_CAPS_FIELDS.iter().copied());
+    }
+    caps
+}
+
+/// Turn plain RTP or RTCP caps into the corresponding SRTP or SRTCP caps
+pub(crate) fn srtp_caps_from_rtp_caps(caps: &gst::CapsRef) -> gst::Caps {
+    let mut caps = caps.to_owned();
+    for s in caps.make_mut().iter_mut() {
+        if s.name() == "application/x-rtcp" {
+            s.set_name("application/x-srtcp");
+        } else {
+            s.set_name("application/x-srtp");
+        }
+    }
+    caps
+}
+
+/// The SSRC of an RTP or RTCP packet and whether it is an RTCP packet (RFC 5761 4)
+pub(crate) fn packet_ssrc(data: &[u8]) -> Option<(u32, bool)> {
+    if data.len() < 8 {
+        return None;
+    }
+
+    if (192..=223).contains(&data[1]) {
+        Some((u32::from_be_bytes(data[4..8].try_into().unwrap()), true))
+    } else if data.len() >= 12 {
+        Some((u32::from_be_bytes(data[8..12].try_into().unwrap()), false))
+    } else {
+        None
+    }
+}
+
+fn rtp_header_len(data: &[u8]) -> Result<usize, SrtpError> {
+    if data.len() < 12 {
+        return Err(SrtpError::Truncated);
+    }
+
+    let mut len = 12 + 4 * (data[0] & 0x0f) as usize;
+    if data[0] & 0x10 != 0 {
+        if data.len() < len + 4 {
+            return Err(SrtpError::Truncated);
+        }
+        let ext_len = u16::from_be_bytes([data[len + 2], data[len + 3]]) as usize;
+        len += 4 + 4 * ext_len;
+    }
+
+    if data.len() < len {
+        return Err(SrtpError::Truncated);
+    }
+
+    Ok(len)
+}
+
+fn apply_aes_ctr(key: &[u8], iv: &[u8; 16], data: &mut [u8]) {
+    match key.len() {
+        16 => Aes128Ctr::new_from_slices(key, iv)
+            .unwrap()
+            .apply_keystream(data),
+        32 => Aes256Ctr::new_from_slices(key, iv)
+            .unwrap()
+            .apply_keystream(data),
+        _ => unreachable!(),
+    }
+}
+
+/// The AES-CM key derivation function with a key derivation rate of 0 (RFC 3711 4.3)
+fn derive_session_key(policy: &SrtpPolicy, label: u8, len: usize) -> Vec<u8> {
+    // The shorter AEAD master salt is padded with zeros (RFC 7714 11)
+    let mut iv = [0u8; 16];
+    iv[..policy.master_salt.len()].copy_from_slice(&policy.master_salt);
+    iv[7] ^= label;
+
+    let mut key = vec![0; len];
+    apply_aes_ctr(&policy.master_key, &iv, &mut key);
+    key
+}
+
+#[derive(Debug)]
+struct SessionKeys {
+    cipher: SrtpCipher,
+    auth: SrtpAuth,
+    enc_key: Vec<u8>,
+    auth_key: Vec<u8>,
+    salt: Vec<u8>,
+}
+
+impl SessionKeys {
+    fn derive(policy: &SrtpPolicy, cipher: SrtpCipher, auth: SrtpAuth, first_label: u8) -> Self {
+        let auth_key = if auth == SrtpAuth::Null {
+            vec![]
+        } else {
+            derive_session_key(policy, first_label + 1, AUTH_KEY_LEN)
+        };
+
+        Self {
+            cipher,
+            auth,
+            enc_key: derive_session_key(policy, first_label, cipher.master_key_len()),
+            auth_key,
+            salt: derive_session_key(policy, first_label + 2, cipher.master_salt_len()),
+        }
+    }
+
+    fn tag_len(&self) -> usize {
+        if self.cipher.is_aead() {
+            AEAD_TAG_LEN
+        } else {
+            self.auth.tag_len()
+        }
+    }
+
+    /// Counter mode encryption or decryption (RFC 3711 4.1.1)
+    fn apply_keystream(&self, ssrc: u32, index: u64, data: &mut [u8]) {
+        if self.cipher == SrtpCipher::Null {
+            return;
+        }
+
+        let mut iv = [0u8; 16];
+        iv[..14].copy_from_slice(&self.salt);
+        for (iv, b) in iv[4..8].iter_mut().zip(ssrc.to_be_bytes()) {
+            *iv ^= b;
+        }
+        for (iv, b) in iv[8..14].iter_mut().zip(&index.to_be_bytes()[2..]) {
+            *iv ^= b;
+        }
+
+        apply_aes_ctr(&self.enc_key, &iv, data);
+    }
+
+    /// The AEAD nonce for SRTP (RFC 7714 8.1) or SRTCP (RFC 7714 9.1)
+    fn aead_nonce(&self, ssrc: u32, index: u64) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[2..6].copy_from_slice(&ssrc.to_be_bytes());
+        nonce[6..12].copy_from_slice(&index.to_be_bytes()[2..]);
+        for (nonce, b) in nonce.iter_mut().zip(&self.salt) {
+            *nonce ^= b;
+        }
+        nonce
+    }
+
+    fn aead_encrypt(&self, nonce: &[u8; 12], aad: &[u8], data: &mut [u8]) -> [u8; AEAD_TAG_LEN] {
+        let nonce = aes_gcm::Nonce::from_slice(nonce);
+        let tag = match self.cipher {
+            SrtpCipher::Aes128Gcm => Aes128Gcm::new_from_slice(&self.enc_key)
+                .unwrap()
+                .encrypt_in_place_detached(nonce, aad, data),
+            SrtpCipher::Aes256Gcm => Aes256Gcm::new_from_slice(&self.enc_key)
+                .unwrap()
+                .encrypt_in_place_detached(nonce, aad, data),
+            _ => unreachable!(),
+        }
+        .expect("packet size within AES-GCM limits");
+
+        let mut ret = [0u8; AEAD_TAG_LEN];
+        ret.copy_from_slice(&tag);
+        ret
+    }
+
+    fn aead_decrypt(
+        &self,
+        nonce: &[u8; 12],
+        aad: &[u8],
+        data: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), SrtpError> {
+        let nonce = aes_gcm::Nonce::from_slice(nonce);
+        let tag = aes_gcm::Tag::from_slice(tag);
+        match self.cipher {
+            SrtpCipher::Aes128Gcm => Aes128Gcm::new_from_slice(&self.enc_key)
+                .unwrap()
+                .decrypt_in_place_detached(nonce, aad, data, tag),
+            SrtpCipher::Aes256Gcm => Aes256Gcm::new_from_slice(&self.enc_key)
+                .unwrap()
+                .decrypt_in_place_detached(nonce, aad, data, tag),
+            _ => unreachable!(),
+        }
+        .map_err(|_| SrtpError::AuthenticationFailed)
+    }
+
+    fn mac(&self, data: &[u8], trailer: &[u8]) -> HmacSha1 {
+        let mut mac = <HmacSha1 as Mac>::new_from_slice(&self.auth_key).unwrap();
+        mac.update(data);
+        mac.update(trailer);
+        mac
+    }
+
+    fn auth_tag(&self, data: &[u8], trailer: &[u8]) -> Vec<u8> {
+        if self.auth == SrtpAuth::Null {
+            return vec![];
+        }
+
+        let tag = self.mac(data, trailer).finalize().into_bytes();
+        tag[..self.auth.tag_len()].to_vec()
+    }
+
+    fn verify(&self, data: &[u8], trailer: &[u8], tag: &[u8]) -> Result<(), SrtpError> {
+        if self.auth == SrtpAuth::Null {
+            return Ok(());
+        }
+
+        self.mac(data, trailer)
+            .verify_truncated_left(tag)
+            .map_err(|_| SrtpError::AuthenticationFailed)
+    }
+}
+
+/// Sliding window of the recently received packet indices (RFC 3711 3.3.2)
+#[derive(Debug, Default)]
+struct ReplayWindow {
+    highest: Option<u64>,
+    // bit n is set if the packet with index `highest - n` was received
+    bitmap: u64,
+}
+
+impl ReplayWindow {
+    fn check(&self, index: u64) -> Result<(), SrtpError> {
+        let Some(highest) = self.highest else {
+            return Ok(());
+        };
+        if index > highest {
+            return Ok(());
+        }
+
+        let delta = highest - index;
+        if delta >= REPLAY_WINDOW_SIZE || self.bitmap & (1 << delta) != 0 {
+            Err(SrtpError::Replayed(index))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn update(&mut self, index: u64) {
+        match self.highest {
+            Some(highest) if index <= highest => self.bitmap |= 1 << (highest - index),
+            Some(highest) => {
+                let shift = index - highest;
+                self.bitmap = if shift >= REPLAY_WINDOW_SIZE {
+                    0
+                } else {
+                    self.bitmap << shift
+                } | 1;
+                self.highest = Some(index);
+            }
+            None => {
+                self.bitmap = 1;
+                self.highest = Some(index);
+            }
+        }
+    }
+}
+
+/// The cryptographic state of a single SSRC
+#[derive(Debug)]
+struct SrtpStream {
+    rtp: SessionKeys,
+    rtcp: SessionKeys,
+    roc: u32,
+    // highest sequence number seen so far
+    s_l: Option<u16>,
+    rtp_replay: ReplayWindow,
+    // next SRTCP index to send
+    srtcp_index: u32,
+    rtcp_replay: ReplayWindow,
+}
+
+impl SrtpStream {
+    fn new(policy: &SrtpPolicy) -> Self {
+        Self {
+            rtp: SessionKeys::derive(
+                policy,
+                policy.rtp_cipher,
+                policy.rtp_auth,
+                LABEL_RTP_ENCRYPTION,
+            ),
+            rtcp: SessionKeys::derive(
+                policy,
+                policy.rtcp_cipher,
+                policy.rtcp_auth,
+                LABEL_RTCP_ENCRYPTION,
+            ),
+            roc: policy.roc,
+            s_l: None,
+            rtp_replay: ReplayWindow::default(),
+            srtcp_index: 0,
+            rtcp_replay: ReplayWindow::default(),
+        }
+    }
+
+    /// Estimate the packet index from the sequence number (RFC 3711 3.3.1)
+    fn estimate_index(&self, seq: u16) -> u64 {
+        let Some(s_l) = self.s_l else {
+            return ((self.roc as u64) << 16) | seq as u64;
+        };
+
+        let v = if s_l < 0x8000 {
+            if seq > s_l && seq - s_l > 0x8000 {
+                self.roc.checked_sub(1).unwrap_or(self.roc)
+            } else {
+                self.roc
+            }
+        } else if s_l - 0x8000 > seq {
+            self.roc.wrapping_add(1)
+        } else {
+            self.roc
+        };
+
+        ((v as u64) << 16) | seq as u64
+    }
+
+    fn update_index(&mut self, index: u64) {
+        let newer = self
+            .s_l
+            .map_or(true, |s_l| index > (((self.roc as u64) << 16) | s_l as u64));
+        if newer {
+            self.roc = (index >> 16) as u32;
+            self.s_l = Some(index as u16);
+        }
+    }
+
+    fn protect_rtp(&mut self, data: &[u8]) -> Result<Vec<u8>, SrtpError> {
+        let header_len = rtp_header_len(data)?;
+        let ssrc = u32::from_be_bytes(data[8..12].try_into().unwrap());
+        let seq = u16::from_be_bytes([data[2], data[3]]);
+        let index = self.estimate_index(seq);
+        self.update_index(index);
+
+        let mut out = Vec::with_capacity(data.len() + self.rtp.tag_len());
+        out.extend_from_slice(data);
+        let (header, payload) = out.split_at_mut(header_len);
+        if self.rtp.cipher.is_aead() {
+            let tag = self
+                .rtp
+                .aead_encrypt(&self.rtp.aead_nonce(ssrc, index), header, payload);
+            out.extend_from_slice(&tag);
+        } else {
+            self.rtp.apply_keystream(ssrc, index, payload);
+            let tag = self
+                .rtp
+                .auth_tag(&out, &((index >> 16) as u32).to_be_bytes());
+            out.extend_from_slice(&tag);
+        }
+
+        Ok(out)
+    }
+
+    fn unprotect_rtp(&mut self, data: &[u8]) -> Result<Vec<u8>, SrtpError> {
+        let header_len = rtp_header_len(data)?;
+        let tag_len = self.rtp.tag_len();
+        if data.len() < header_len + tag_len {
+            return Err(SrtpError::Truncated);
+        }
+        let ssrc = u32::from_be_bytes(data[8..12].try_into().unwrap());
+        let seq = u16::from_be_bytes([data[2], data[3]]);
+        let index = self.estimate_index(seq);
+        self.rtp_replay.check(index)?;
+
+        let (packet, tag) = data.split_at(data.len() - tag_len);
+        let mut out = packet.to_vec();
+        let (header, payload) = out.split_at_mut(header_len);
+        if self.rtp.cipher.is_aead() {
+            self.rtp
+                .aead_decrypt(&self.rtp.aead_nonce(ssrc, index), header, payload, tag)?;
+        } else {
+            self.rtp
+                .verify(packet, &((index >> 16) as u32).to_be_bytes(), tag)?;
+            self.rtp.apply_keystream(ssrc, index, payload);
+        }
+
+        self.rtp_replay.update(index);
+        self.update_index(index);
+
+        Ok(out)
+    }
+
+    fn protect_rtcp(&mut self, data: &[u8]) -> Result<Vec<u8>, SrtpError> {
+        if data.len() < 8 {
+            return Err(SrtpError::Truncated);
+        }
+        let ssrc = u32::from_be_bytes(data[4..8].try_into().unwrap());
+        let index = self.srtcp_index;
+        self.srtcp_index = (index + 1) & !SRTCP_E_FLAG;
+        let e_index = if self.rtcp.cipher == SrtpCipher::Null {
+            index
+        } else {
+            index | SRTCP_E_FLAG
+        };
+
+        let mut out = Vec::with_capacity(data.len() + SRTCP_INDEX_LEN + self.rtcp.tag_len());
+        out.extend_from_slice(data);
+        let (header, payload) = out.split_at_mut(8);
+        if self.rtcp.cipher.is_aead() {
+            let mut aad = [0u8; 8 + SRTCP_INDEX_LEN];
+            aad[..8].copy_from_slice(header);
+            aad[8..].copy_from_slice(&e_index.to_be_bytes());
+            let tag =
+                self.rtcp
+                    .aead_encrypt(&self.rtcp.aead_nonce(ssrc, index as u64), &aad, payload);
+            out.extend_from_slice(&tag);
+            out.extend_from_slice(&e_index.to_be_bytes());
+        } else {
+            self.rtcp.apply_keystream(ssrc, index as u64, payload);
+            out.extend_from_slice(&e_index.to_be_bytes());
+            let tag = self.rtcp.auth_tag(&out, &[]);
+            out.extend_from_slice(&tag);
+        }
+
+        Ok(out)
+    }
+
+    fn unprotect_rtcp(&mut self, data: &[u8]) -> Result<Vec<u8>, SrtpError> {
+        let tag_len = self.rtcp.tag_len();
+        if data.len() < 8 + SRTCP_INDEX_LEN + tag_len {
+            return Err(SrtpError::Truncated);
+        }
+        let ssrc = u32::from_be_bytes(data[4..8].try_into().unwrap());
+
+        let (out, index) = if self.rtcp.cipher.is_aead() {
+            let (rest, e_index) = data.split_at(data.len() - SRTCP_INDEX_LEN);
+            let e_index = u32::from_be_bytes(e_index.try_into().unwrap());
+            let index = e_index & !SRTCP_E_FLAG;
+            self.rtcp_replay.check(index as u64)?;
+
+            let (packet, tag) = rest.split_at(rest.len() - AEAD_TAG_LEN);
+            let nonce = self.rtcp.aead_nonce(ssrc, index as u64);
+            let mut out = packet.to_vec();
+            if e_index & SRTCP_E_FLAG != 0 {
+                let mut aad = [0u8; 8 + SRTCP_INDEX_LEN];
+                aad[..8].copy_from_slice(&packet[..8]);
+                aad[8..].copy_from_slice(&e_index.to_be_bytes());
+                self.rtcp.aead_decrypt(&nonce, &aad, &mut out[8..], tag)?;
+            } else {
+                // unencrypted packets are authenticated as additional data (RFC 7714 9.2)
+                let mut aad = packet.to_vec();
+                aad.extend_from_slice(&e_index.to_be_bytes());
+                self.rtcp.aead_decrypt(&nonce, &aad, &mut [], tag)?;
+            }
+            (out, index)
+        } else {
+            let (authenticated, tag) = data.split_at(data.len() - tag_len);
+            let (packet, e_index) = authenticated.split_at(authenticated.len() - SRTCP_INDEX_LEN);
+            let e_index = u32::from_be_bytes(e_index.try_into().unwrap());
+            let index = e_index & !SRTCP_E_FLAG;
+            self.rtcp_replay.check(index as u64)?;
+            self.rtcp.verify(authenticated, &[], tag)?;
+
+            let mut out = packet.to_vec();
+            if e_index & SRTCP_E_FLAG != 0 {
+                self.rtcp.apply_keystream(ssrc, index as u64, &mut out[8..]);
+            }
+            (out, index)
+        };
+
+        self.rtcp_replay.update(index as u64);
+
+        Ok(out)
+    }
+}
+
+/// The SRTP keys and state of a session
+#[derive(Debug, Default)]
+pub(crate) struct Srtp {
+    // policy for all SSRCs without a specific one
+    default_policy: Option<SrtpPolicy>,
+    // `None` if packets of the SSRC are not protected
+    streams: HashMap<u32, Option<SrtpStream>>,
+}
+
+impl Srtp {
+    pub(crate) fn set_default_policy(&mut self, policy: Option<SrtpPolicy>) {
+        self.default_policy = policy;
+    }
+
+    /// Set the policy of `ssrc`, or mark its packets as not protected if `policy` is `None`
+    pub(crate) fn set_policy(&mut self, ssrc: u32, policy: Option<SrtpPolicy>) {
+        self.streams
+            .insert(ssrc, policy.as_ref().map(SrtpStream::new));
+    }
+
+    /// Whether the key of `ssrc` has to be requested from the application
+    pub(crate) fn needs_key(&self, ssrc: u32) -> bool {
+        self.default_policy.is_none() && !self.streams.contains_key(&ssrc)
+    }
+
+    pub(crate) fn remove_key(&mut self, ssrc: u32) {
+        self.streams.remove(&ssrc);
+    }
+
+    pub(crate) fn clear_keys(&mut self) {
+        self.default_policy = None;
+        self.streams.clear();
+    }
+
+    /// The current rollover counter of `ssrc`
+    pub(crate) fn rollover_counter(&self, ssrc: u32) -> Option<u32> {
+        self.streams
+            .get(&ssrc)
+            .and_then(|stream| stream.as_ref())
+            .map(|stream| stream.roc)
+    }
+
+    fn stream(&mut self, ssrc: u32) -> Option<&mut SrtpStream> {
+        if let Some(ref policy) = self.default_policy {
+            self.streams
+                .entry(ssrc)
+                .or_insert_with(|| Some(SrtpStream::new(policy)));
+        }
+
+        self.streams
+            .get_mut(&ssrc)
+            .and_then(|stream| stream.as_mut())
+    }
+
+    /// Protect an outgoing RTP packet.
+    ///
+    /// Returns `None` if there is no key for the SSRC and the packet is to be sent unprotected.
+    pub(crate) fn protect_rtp(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, SrtpError> {
+        let (ssrc, _) = packet_ssrc(data).ok_or(SrtpError::Truncated)?;
+        self.stream(ssrc)
+            .map(|stream| stream.protect_rtp(data))
+            .transpose()
+    }
+
+    /// Protect an outgoing RTCP packet.
+    ///
+    /// Returns `None` if there is no key for the SSRC and the packet is to be sent unprotected.
+    pub(crate) fn protect_rtcp(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, SrtpError> {
+        let (ssrc, _) = packet_ssrc(data).ok_or(SrtpError::Truncated)?;
+        self.stream(ssrc)
+            .map(|stream| stream.protect_rtcp(data))
+            .transpose()
+    }
+
+    pub(crate) fn unprotect_rtp(&mut self, data: &[u8]) -> Result<Vec<u8>, SrtpError> {
+        let (ssrc, _) = packet_ssrc(data).ok_or(SrtpError::Truncated)?;
+        self.stream(ssrc)
+            .ok_or(SrtpError::NoKey(ssrc))?
+            .unprotect_rtp(data)
+    }
+
+    pub(crate) fn unprotect_rtcp(&mut self, data: &[u8]) -> Result<Vec<u8>, SrtpError> {
+        let (ssrc, _) = packet_ssrc(data).ok_or(SrtpError::Truncated)?;
+        self.stream(ssrc)
+            .ok_or(SrtpError::NoKey(ssrc))?
+            .unprotect_rtcp(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtpbin2::session::tests::init_logs;
+
+    const MASTER_KEY: &str = "E1F97A0D3E018BE0D64FA32C06DE4139";
+    const MASTER_SALT: &str = "0EC675AD498AFEEBB6960B3AABE6";
+
+    fn policy(cipher: SrtpCipher, auth: SrtpAuth) -> SrtpPolicy {
+        let key_len = cipher.master_key_len() + cipher.master_salt_len();
+        let key = (0..key_len as u8).collect::<Vec<_>>();
+        SrtpPolicy::new(cipher, auth, cipher, auth, &key, 0).unwrap()
+    }
+
+    fn generate_rtp_packet(ssrc: u32, seq_no: u16) -> Vec<u8> {
+        rtp_types::RtpPacketBuilder::<&[u8], &[u8]>::new()
+            .payload_type(96)
+            .ssrc(ssrc)
+            .sequence_number(seq_no)
+            .timestamp(1234)
+            .payload([1, 2, 3, 4, 5, 6, 7, 8].as_slice())
+            .write_vec()
+            .unwrap()
+    }
+
+    fn generate_rtcp_packet(ssrc: u32) -> Vec<u8> {
+        let mut data = vec![0; 64];
+        let len = rtcp_types::Compound::builder()
+            .add_packet(rtcp_types::ReceiverReport::builder(ssrc))
+            .add_packet(rtcp_types::Bye::builder().add_source(ssrc))
+            .write_into(&mut data)
+            .unwrap();
+        data.truncate(len);
+        data
+    }
+
+    #[test]
+    fn key_derivation() {
+        // RFC 3711 B.3
+        let key = hex::decode(format!("{MASTER_KEY}{MASTER_SALT}")).unwrap();
+        let policy = SrtpPolicy::new(
+            SrtpCipher::Aes128Icm,
+            SrtpAuth::HmacSha1_80,
+            SrtpCipher::Aes128Icm,
+            SrtpAuth::HmacSha1_80,
+            &key,
+            0,
+        )
+        .unwrap();
+
+        let keys = SessionKeys::derive(
+            &policy,
+            SrtpCipher::Aes128Icm,
+            SrtpAuth::HmacSha1_80,
+            LABEL_RTP_ENCRYPTION,
+        );
+        assert_eq!(
+            keys.enc_key,
+            hex::decode("C61E7A93744F39EE10734AFE3FF7A087").unwrap()
+        );
+        assert_eq!(
+            keys.salt,
+            hex::decode("30CBBC08863D8C85D49DB34A9AE1").unwrap()
+        );
+        assert_eq!(
+            keys.auth_key,
+            hex::decode("CEBE321F6FF7716B6FD4AB49AF256A156D38BAA4").unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_policy() {
+        let key = [0; 30];
+        assert_eq!(
+            SrtpPolicy::new(
+                SrtpCipher::Aes128Icm,
+                SrtpAuth::HmacSha1_80,
+                SrtpCipher::Aes128Icm,
+                SrtpAuth::HmacSha1_80,
+                &key[..29],
+                0,
+            ),
+            Err(SrtpError::InvalidKey("wrong master key length"))
+        );
+        assert!(SrtpPolicy::new(
+            SrtpCipher::Aes128Gcm,
+            SrtpAuth::HmacSha1_80,
+            SrtpCipher::Aes128Gcm,
+            SrtpAuth::Null,
+            &key[..28],
+            0,
+        )
+        .is_err());
+        assert!(SrtpPolicy::new(
+            SrtpCipher::Aes128Icm,
+            SrtpAuth::HmacSha1_80,
+            SrtpCipher::Aes256Icm,
+            SrtpAuth::HmacSha1_80,
+            &key,
+            0,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn policy_from_caps() {
+        init_logs();
+        let key = gst::Buffer::from_slice([0u8; 46]);
+        let caps = gst::Caps::builder("application/x-srtp")
+            .field("payload", 96i32)
+            .field("srtp-key", key)
+            .field("srtp-cipher", "aes-256-icm")
+            .field("srtp-auth", "hmac-sha1-32")
+            .field("srtcp-cipher", "aes-256-icm")
+            .field("srtcp-auth", "hmac-sha1-80")
+            .field("roc", 5u32)
+            .build();
+        let policy = SrtpPolicy::from_structure(caps.structure(0).unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.rtp_cipher, SrtpCipher::Aes256Icm);
+        assert_eq!(policy.rtp_auth, SrtpAuth::HmacSha1_32);
+        assert_eq!(policy.rtcp_auth, SrtpAuth::HmacSha1_80);
+        assert_eq!(policy.roc, 5);
+
+        let rtp_caps = rtp_caps_from_srtp_caps(&caps);
+        let s = rtp_caps.structure(0).unwrap();
+        assert_eq!(s.name(), "application/x-rtp");
+        assert_eq!(s.get::<i32>("payload").unwrap(), 96);
+        assert!(!s.has_field("srtp-key"));
+        assert!(!s.has_field("roc"));
+
+        assert_eq!(SrtpPolicy::from_structure(s).unwrap(), None);
+    }
+
+    #[test]
+    fn rtp_roundtrip() {
+        for (cipher, auth) in [
+            (SrtpCipher::Aes128Icm, SrtpAuth::HmacSha1_80),
+            (SrtpCipher::Aes128Icm, SrtpAuth::HmacSha1_32),
+            (SrtpCipher::Aes256Icm, SrtpAuth::HmacSha1_80),
+            (SrtpCipher::Null, SrtpAuth::HmacSha1_80),
+            (SrtpCipher::Aes128Gcm, SrtpAuth::Null),
+            (SrtpCipher::Aes256Gcm, SrtpAuth::Null),
+        ] {
+            let mut sender = Srtp::default();
+            sender.set_default_policy(Some(policy(cipher, auth)));
+            let mut receiver = Srtp::default();
+            receiver.set_policy(0x12345678, Some(policy(cipher, auth)));
+
+            let rtp = generate_rtp_packet(0x12345678, 100);
+            let srtp = sender.protect_rtp(&rtp).unwrap().unwrap();
+            let tag_len = if cipher.is_aead() {
+                AEAD_TAG_LEN
+            } else {
+                auth.tag_len()
+            };
+            assert_eq!(srtp.len(), rtp.len() + tag_len);
+            // the header is never encrypted
+            assert_eq!(srtp[..12], rtp[..12]);
+            if cipher != SrtpCipher::Null {
+                assert_ne!(srtp[12..rtp.len()], rtp[12..]);
+            }
+            assert_eq!(receiver.unprotect_rtp(&srtp).unwrap(), rtp);
+
+            // replayed
+            assert_eq!(receiver.unprotect_rtp(&srtp), Err(SrtpError::Replayed(100)));
+
+            // modified
+            let mut srtp = sender
+                .protect_rtp(&generate_rtp_packet(0x12345678, 101))
+                .unwrap()
+                .unwrap();
+            srtp[14] ^= 0x01;
+            assert_eq!(
+                receiver.unprotect_rtp(&srtp),
+                Err(SrtpError::AuthenticationFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn rtcp_roundtrip() {
+        for (cipher, auth) in [
+            (SrtpCipher::Aes128Icm, SrtpAuth::HmacSha1_80),
+            (SrtpCipher::Null, SrtpAuth::HmacSha1_32),
+            (SrtpCipher::Aes128Gcm, SrtpAuth::Null),
+            (SrtpCipher::Aes256Gcm, SrtpAuth::Null),
+        ] {
+            let mut sender = Srtp::default();
+            sender.set_policy(0x12345678, Some(policy(cipher, auth)));
+            let mut receiver = Srtp::default();
+            receiver.set_default_policy(Some(policy(cipher, auth)));
+
+            let rtcp = generate_rtcp_packet(0x12345678);
+            for index in 0..3 {
+                let srtcp = sender.protect_rtcp(&rtcp).unwrap().unwrap();
+                assert_eq!(srtcp[..8], rtcp[..8]);
+                assert_eq!(receiver.unprotect_rtcp(&srtcp).unwrap(), rtcp);
+                assert_eq!(
+                    receiver.unprotect_rtcp(&srtcp),
+                    Err(SrtpError::Replayed(index))
+                );
+            }
+
+            let mut srtcp = sender.protect_rtcp(&rtcp).unwrap().unwrap();
+            srtcp[10] ^= 0x01;
+            assert_eq!(
+                receiver.unprotect_rtcp(&srtcp),
+                Err(SrtpError::AuthenticationFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn rollover_counter() {
+        let mut sender = Srtp::default();
+        sender.set_default_policy(Some(policy(SrtpCipher::Aes128Icm, SrtpAuth::HmacSha1_80)));
+        let mut receiver = Srtp::default();
+        receiver.set_default_policy(Some(policy(SrtpCipher::Aes128Icm, SrtpAuth::HmacSha1_80)));
+
+        for seq_no in [0xfffe, 0xffff, 0x0000, 0x0001] {
+            let rtp = generate_rtp_packet(0x1, seq_no);
+            let srtp = sender.protect_rtp(&rtp).unwrap().unwrap();
+            assert_eq!(receiver.unprotect_rtp(&srtp).unwrap(), rtp);
+        }
+        assert_eq!(sender.rollover_counter(0x1), Some(1));
+        assert_eq!(receiver.rollover_counter(0x1), Some(1));
+
+        // a reordered packet from before the rollover
+        let rtp = generate_rtp_packet(0x1, 0xfffd);
+        let mut old_sender = Srtp::default();
+        old_sender.set_default_policy(Some(policy(SrtpCipher::Aes128Icm, SrtpAuth::HmacSha1_80)));
+        let srtp = old_sender.protect_rtp(&rtp).unwrap().unwrap();
+        assert_eq!(receiver.unprotect_rtp(&srtp).unwrap(), rtp);
+        assert_eq!(receiver.rollover_counter(0x1), Some(1));
+    }
+
+    #[test]
+    fn unknown_ssrc() {
+        let mut srtp = Srtp::default();
+        assert!(srtp.needs_key(0x1));
+        srtp.set_policy(0x1, None);
+        assert!(!srtp.needs_key(0x1));
+
+        // packets without a key are sent unprotected but can't be received
+        let rtp = generate_rtp_packet(0x1, 0);
+        assert_eq!(srtp.protect_rtp(&rtp), Ok(None));
+        assert_eq!(srtp.unprotect_rtp(&rtp), Err(SrtpError::NoKey(0x1)));
+
+        srtp.set_policy(
+            0x1,
+            Some(policy(SrtpCipher::Aes128Icm, SrtpAuth::HmacSha1_80)),
+        );
+        assert!(srtp.protect_rtp(&rtp).unwrap().is_some());
+        assert_eq!(srtp.rollover_counter(0x1), Some(0));
+
+        srtp.remove_key(0x1);
+        assert!(srtp.needs_key(0x1));
+        assert_eq!(srtp.rollover_counter(0x1), None);
+    }
+}
@@ -0,0 +1,948 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Transport-wide congestion control (TWCC) as specified in
+//! draft-holmer-rmcat-transport-wide-cc-extensions-01 and receiver estimated maximum bitrate
+//! (REMB) as specified in draft-alvestrand-rmcat-remb-03.
+//!
+//! Both sides read the transport-wide sequence number from the RTP header extension.  The receiver
+//! reports the arrival times of all packets in transport feedback packets and the sender matches
+//! them with its send times for the bandwidth estimation.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use rtcp_types::{
+    FciBuilder, FciFeedbackPacketType, FciParser, RtcpPacketWriter, RtcpParseError, RtcpWriteError,
+};
+use rtp_types::RtpPacket;
+
+use crate::utils::ExtendedSeqnum;
+
+pub(crate) const TWCC_EXTMAP_URI: &str =
+    "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01";
+
+// Reference time unit
+const REFERENCE_TIME_UNIT: Duration = Duration::from_millis(64);
+// Receive delta unit
+const DELTA_UNIT: Duration = Duration::from_micros(250);
+const DELTAS_PER_REFERENCE_TIME_UNIT: i64 = 256;
+
+const STATUS_NOT_RECEIVED: u8 = 0;
+const STATUS_SMALL_DELTA: u8 = 1;
+const STATUS_LARGE_DELTA: u8 = 2;
+
+const MAX_RUN_LENGTH: usize = 0x1fff;
+// Number of 2 bit symbols in a status vector chunk
+const MAX_STATUS_VECTOR_SYMBOLS: usize = 7;
+
+// Feedback for bigger ranges of packets is split into multiple packets
+const MAX_FEEDBACK_PACKETS: u64 = 200;
+/// Upper bound of the size of a transport feedback packet including the RTCP header
+pub(crate) const MAX_FEEDBACK_SIZE: usize = 16
+    + 8
+    + (MAX_FEEDBACK_PACKETS as usize).div_ceil(MAX_STATUS_VECTOR_SYMBOLS) * 2
+    + MAX_FEEDBACK_PACKETS as usize * 2
+    + 3;
+// How many sent packets to remember for matching them with feedback
+const MAX_SENT_PACKETS: usize = 1 << 13;
+
+/// Retrieve the header extension id used for the transport-wide sequence number from the provided
+/// caps.
+pub(crate) fn twcc_extension_id_from_caps(caps: &gst::CapsRef) -> Option<u8> {
+    extension_id_from_caps(caps, TWCC_EXTMAP_URI)
+}
+
+/// Retrieve the header extension id used for the extension with `uri` from the `extmap-N` fields
+/// of the provided caps.
+pub(crate) fn extension_id_from_caps(caps: &gst::CapsRef, uri: &str) -> Option<u8> {
+    let s = caps.structure(0)?;
+
+    s.iter().find_map(|(k, v)| {
+        let ext_id = k.strip_prefix("extmap-")?.parse::<u8>().ok()?;
+        let ext_uri = if let Ok(uri) = v.get::<String>() {
+            uri
+        } else {
+            let arr = v.get::<gst::ArrayRef>().ok()?;
+            arr.get(1).and_then(|v| v.get::<String>().ok())?
+        };
+
+        (ext_uri == uri && ext_id != 0).then_some(ext_id)
+    })
+}
+
+/// Retrieve the transport-wide sequence number from the header extension with `ext_id`
+pub(crate) fn read_twcc_seqnum(rtp: &RtpPacket, ext_id: u8) -> Option<u16> {
+    let data = header_extension(rtp, ext_id)?;
+    if data.len() < 2 {
+        return None;
+    }
+
+    Some(u16::from_be_bytes([data[0], data[1]]))
+}
+
+/// Retrieve the data of the one-byte or two-byte header extension with `ext_id`
+pub(crate) fn header_extension<'a>(rtp: &'a RtpPacket, ext_id: u8) -> Option<&'a [u8]> {
+    let (pattern, mut data) = rtp.extension()?;
+
+    let two_byte = match pattern {
+        0xBEDE => false,
+        x if x >> 4 == 0x100 => true,
+        _ => return None,
+    };
+
+    while !data.is_empty() {
+        let (id, len) = if two_byte {
+            if data[0] == 0 {
+                // Padding
+                data = &data[1..];
+                continue;
+            }
+            if data.len() < 2 {
+                return None;
+            }
+            let (id, len) = (data[0], data[1] as usize);
+            data = &data[2..];
+            (id, len)
+        } else {
+            let b = data[0];
+            data = &data[1..];
+            match b >> 4 {
+                // Padding
+                0 => continue,
+                // Special ID, stop processing
+                15 => return None,
+                id => (id, (b & 0x0f) as usize + 1),
+            }
+        };
+
+        if data.len() < len {
+            return None;
+        }
+        if id == ext_id {
+            return Some(&data[..len]);
+        }
+        data = &data[len..];
+    }
+
+    None
+}
+
+/// The size of the RTP packet including the header
+pub(crate) fn packet_size(rtp: &RtpPacket) -> usize {
+    12 + rtp.csrc().count() * 4
+        + rtp.extension().map_or(0, |(_pattern, data)| 4 + data.len())
+        + rtp.payload().len()
+}
+
+// Extend a 16 bit seqnum to the extended seqnum closest to `reference`
+fn extend_seqnum(seqnum: u16, reference: u64) -> u64 {
+    let ext = (reference & !0xffff) | seqnum as u64;
+    if ext > reference + 0x8000 && ext >= 0x10000 {
+        ext - 0x10000
+    } else if ext + 0x8000 < reference {
+        ext + 0x10000
+    } else {
+        ext
+    }
+}
+
+/// The reception status of a single packet in a transport feedback packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TwccStatus {
+    NotReceived,
+    /// Received with the provided delta to the previous packet (in units of 250µs)
+    Received(i32),
+}
+
+/// Transport-wide congestion control feedback information
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Twcc {
+    base_seqnum: u16,
+    reference_time: i32,
+    feedback_packet_count: u8,
+    statuses: Vec<TwccStatus>,
+}
+
+impl Twcc {
+    pub(crate) fn builder(
+        base_seqnum: u16,
+        reference_time: i32,
+        feedback_packet_count: u8,
+    ) -> TwccBuilder {
+        TwccBuilder {
+            base_seqnum,
+            reference_time,
+            feedback_packet_count,
+            statuses: vec![],
+        }
+    }
+
+    /// The transport-wide sequence number of the first packet in this feedback
+    pub(crate) fn base_seqnum(&self) -> u16 {
+        self.base_seqnum
+    }
+
+    /// The reference time in units of 64ms
+    pub(crate) fn reference_time(&self) -> i32 {
+        self.reference_time
+    }
+
+    pub(crate) fn feedback_packet_count(&self) -> u8 {
+        self.feedback_packet_count
+    }
+
+    /// The reception status of each packet starting at the base sequence number
+    pub(crate) fn statuses(&self) -> &[TwccStatus] {
+        &self.statuses
+    }
+}
+
+impl FciParser<'_> for Twcc {
+    const PACKET_TYPE: FciFeedbackPacketType = FciFeedbackPacketType::TRANSPORT;
+    const FCI_FORMAT: u8 = 15;
+
+    fn parse(data: &[u8]) -> Result<Self, RtcpParseError> {
+        if data.len() < 8 {
+            return Err(RtcpParseError::Truncated {
+                expected: 8,
+                actual: data.len(),
+            });
+        }
+
+        let base_seqnum = u16::from_be_bytes([data[0], data[1]]);
+        let packet_status_count = u16::from_be_bytes([data[2], data[3]]) as usize;
+        // 24 bit signed value
+        let reference_time = i32::from_be_bytes([data[4], data[5], data[6], 0]) >> 8;
+        let feedback_packet_count = data[7];
+
+        let mut offset = 8;
+        let mut symbols = Vec::with_capacity(packet_status_count);
+        while symbols.len() < packet_status_count {
+            if data.len() < offset + 2 {
+                return Err(RtcpParseError::Truncated {
+                    expected: offset + 2,
+                    actual: data.len(),
+                });
+            }
+            let chunk = u16::from_be_bytes([data[offset], data[offset + 1]]);
+            offset += 2;
+
+            let remaining = packet_status_count - symbols.len();
+            if chunk & 0x8000 == 0 {
+                // run length chunk
+                let symbol = ((chunk >> 13) & 0x3) as u8;
+                let run_length = (chunk & 0x1fff) as usize;
+                symbols.extend(std::iter::repeat(symbol).take(run_length.min(remaining)));
+            } else if chunk & 0x4000 == 0 {
+                // status vector chunk with 14 1 bit symbols
+                symbols.extend(
+                    (0..14)
+                        .map(|i| ((chunk >> (13 - i)) & 0x1) as u8)
+                        .take(remaining),
+                );
+            } else {
+                // status vector chunk with 7 2 bit symbols
+                symbols.extend(
+                    (0..7)
+                        .map(|i| ((chunk >> (12 - 2 * i)) & 0x3) as u8)
+                        .take(remaining),
+                );
+            }
+        }
+
+        let mut statuses = Vec::with_capacity(packet_status_count);
+        for symbol in symbols {
+            let status = match symbol {
+                STATUS_SMALL_DELTA => {
+                    if data.len() < offset + 1 {
+                        return Err(RtcpParseError::Truncated {
+                            expected: offset + 1,
+                            actual: data.len(),
+                        });
+                    }
+                    let delta = data[offset] as i32;
+                    offset += 1;
+                    TwccStatus::Received(delta)
+                }
+                STATUS_LARGE_DELTA => {
+                    if data.len() < offset + 2 {
+                        return Err(RtcpParseError::Truncated {
+                            expected: offset + 2,
+                            actual: data.len(),
+                        });
+                    }
+                    let delta = i16::from_be_bytes([data[offset], data[offset + 1]]) as i32;
+                    offset += 2;
+                    TwccStatus::Received(delta)
+                }
+                _ => TwccStatus::NotReceived,
+            };
+            statuses.push(status);
+        }
+
+        Ok(Self {
+            base_seqnum,
+            reference_time,
+            feedback_packet_count,
+            statuses,
+        })
+    }
+}
+
+/// Builder for transport-wide congestion control feedback information
+#[derive(Debug, Clone)]
+pub(crate) struct TwccBuilder {
+    base_seqnum: u16,
+    reference_time: i32,
+    feedback_packet_count: u8,
+    statuses: Vec<TwccStatus>,
+}
+
+impl TwccBuilder {
+    /// Add the reception status of the next packet
+    pub(crate) fn add_status(mut self, status: TwccStatus) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    fn symbol(status: &TwccStatus) -> u8 {
+        match status {
+            TwccStatus::NotReceived => STATUS_NOT_RECEIVED,
+            TwccStatus::Received(delta) if (0..=255).contains(delta) => STATUS_SMALL_DELTA,
+            TwccStatus::Received(_) => STATUS_LARGE_DELTA,
+        }
+    }
+
+    // Encode the packet status chunks, using run length chunks for runs of at least 7 identical
+    // symbols and 2 bit status vector chunks otherwise
+    fn chunks(&self) -> Vec<u16> {
+        let symbols = self.statuses.iter().map(Self::symbol).collect::<Vec<_>>();
+
+        let mut chunks = vec![];
+        let mut i = 0;
+        while i < symbols.len() {
+            let symbol = symbols[i];
+            let run_length = symbols[i..]
+                .iter()
+                .take(MAX_RUN_LENGTH)
+                .take_while(|&&s| s == symbol)
+                .count();
+
+            if run_length >= MAX_STATUS_VECTOR_SYMBOLS {
+                chunks.push(((symbol as u16) << 13) | run_length as u16);
+                i += run_length;
+            } else {
+                let mut chunk = 0xc000;
+                for (j, symbol) in symbols[i..]
+                    .iter()
+                    .take(MAX_STATUS_VECTOR_SYMBOLS)
+                    .enumerate()
+                {
+                    chunk |= (*symbol as u16) << (12 - 2 * j);
+                }
+                chunks.push(chunk);
+                i += MAX_STATUS_VECTOR_SYMBOLS;
+            }
+        }
+
+        chunks
+    }
+
+    fn deltas_size(&self) -> usize {
+        self.statuses
+            .iter()
+            .map(|status| match Self::symbol(status) {
+                STATUS_SMALL_DELTA => 1,
+                STATUS_LARGE_DELTA => 2,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+impl RtcpPacketWriter for TwccBuilder {
+    fn calculate_size(&self) -> Result<usize, RtcpWriteError> {
+        let size = 8 + self.chunks().len() * 2 + self.deltas_size();
+        // padded to 32 bits
+        Ok((size + 3) & !3)
+    }
+
+    fn write_into_unchecked(&self, buf: &mut [u8]) -> usize {
+        buf[0..2].copy_from_slice(&self.base_seqnum.to_be_bytes());
+        buf[2..4].copy_from_slice(&(self.statuses.len() as u16).to_be_bytes());
+        buf[4..7].copy_from_slice(&self.reference_time.to_be_bytes()[1..4]);
+        buf[7] = self.feedback_packet_count;
+
+        let mut offset = 8;
+        for chunk in self.chunks() {
+            buf[offset..offset + 2].copy_from_slice(&chunk.to_be_bytes());
+            offset += 2;
+        }
+
+        for status in self.statuses.iter() {
+            match (Self::symbol(status), status) {
+                (STATUS_SMALL_DELTA, TwccStatus::Received(delta)) => {
+                    buf[offset] = *delta as u8;
+                    offset += 1;
+                }
+                (STATUS_LARGE_DELTA, TwccStatus::Received(delta)) => {
+                    let delta = (*delta).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+                    buf[offset..offset + 2].copy_from_slice(&delta.to_be_bytes());
+                    offset += 2;
+                }
+                _ => (),
+            }
+        }
+
+        while offset % 4 != 0 {
+            buf[offset] = 0;
+            offset += 1;
+        }
+
+        offset
+    }
+
+    fn get_padding(&self) -> Option<u8> {
+        None
+    }
+}
+
+impl FciBuilder<'static> for TwccBuilder {
+    fn format(&self) -> u8 {
+        Twcc::FCI_FORMAT
+    }
+
+    fn supports_feedback_type(&self) -> FciFeedbackPacketType {
+        FciFeedbackPacketType::TRANSPORT
+    }
+}
+
+/// Receiver estimated maximum bitrate information
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Remb {
+    bitrate: u64,
+    ssrcs: Vec<u32>,
+}
+
+impl Remb {
+    pub(crate) fn builder(bitrate: u64) -> RembBuilder {
+        RembBuilder {
+            bitrate,
+            ssrcs: vec![],
+        }
+    }
+
+    /// The estimated maximum bitrate in bits per second
+    pub(crate) fn bitrate(&self) -> u64 {
+        self.bitrate
+    }
+
+    /// The SSRCs the estimate applies to
+    pub(crate) fn ssrcs(&self) -> &[u32] {
+        &self.ssrcs
+    }
+}
+
+impl FciParser<'_> for Remb {
+    const PACKET_TYPE: FciFeedbackPacketType = FciFeedbackPacketType::PAYLOAD;
+    // Application layer feedback
+    const FCI_FORMAT: u8 = 15;
+
+    fn parse(data: &[u8]) -> Result<Self, RtcpParseError> {
+        if data.len() < 8 {
+            return Err(RtcpParseError::Truncated {
+                expected: 8,
+                actual: data.len(),
+            });
+        }
+        if &data[0..4] != b"REMB" {
+            return Err(RtcpParseError::PacketTypeMismatch {
+                actual: data[0],
+                requested: b'R',
+            });
+        }
+
+        let n_ssrcs = data[4] as usize;
+        let exp = data[5] >> 2;
+        let mantissa = u32::from_be_bytes([0, data[5] & 0x3, data[6], data[7]]) as u64;
+        let bitrate = mantissa.checked_shl(exp as u32).unwrap_or(u64::MAX);
+
+        let expected = 8 + n_ssrcs * 4;
+        if data.len() < expected {
+            return Err(RtcpParseError::Truncated {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let ssrcs = data[8..expected]
+            .chunks_exact(4)
+            .map(|ssrc| u32::from_be_bytes(ssrc.try_into().unwrap()))
+            .collect();
+
+        Ok(Self { bitrate, ssrcs })
+    }
+}
+
+/// Builder for receiver estimated maximum bitrate information
+#[derive(Debug, Clone)]
+pub(crate) struct RembBuilder {
+    bitrate: u64,
+    ssrcs: Vec<u32>,
+}
+
+impl RembBuilder {
+    pub(crate) fn add_ssrc(mut self, ssrc: u32) -> Self {
+        self.ssrcs.push(ssrc);
+        self
+    }
+}
+
+impl RtcpPacketWriter for RembBuilder {
+    fn calculate_size(&self) -> Result<usize, RtcpWriteError> {
+        if self.ssrcs.len() > u8::MAX as usize {
+            return Err(RtcpWriteError::TooManySsrcs {
+                count: self.ssrcs.len(),
+                max: u8::MAX as usize,
+            });
+        }
+        Ok(8 + self.ssrcs.len() * 4)
+    }
+
+    fn write_into_unchecked(&self, buf: &mut [u8]) -> usize {
+        // 18 bit mantissa and 6 bit exponent
+        let mut exp = 0;
+        let mut mantissa = self.bitrate;
+        while mantissa > 0x3ffff {
+            mantissa >>= 1;
+            exp += 1;
+        }
+
+        buf[0..4].copy_from_slice(b"REMB");
+        buf[4] = self.ssrcs.len() as u8;
+        buf[5] = (exp << 2) | (mantissa >> 16) as u8;
+        buf[6..8].copy_from_slice(&(mantissa as u16).to_be_bytes());
+        let mut offset = 8;
+        for ssrc in self.ssrcs.iter() {
+            buf[offset..offset + 4].copy_from_slice(&ssrc.to_be_bytes());
+            offset += 4;
+        }
+
+        offset
+    }
+
+    fn get_padding(&self) -> Option<u8> {
+        None
+    }
+}
+
+impl FciBuilder<'static> for RembBuilder {
+    fn format(&self) -> u8 {
+        Remb::FCI_FORMAT
+    }
+
+    fn supports_feedback_type(&self) -> FciFeedbackPacketType {
+        FciFeedbackPacketType::PAYLOAD
+    }
+}
+
+/// Tracks the arrival times of received packets for generating transport feedback
+#[derive(Debug, Default)]
+pub(crate) struct TwccReceiver {
+    ext_seqnum: ExtendedSeqnum,
+    // All times are relative to the first received packet
+    epoch: Option<Instant>,
+    media_ssrc: Option<u32>,
+    // Arrival times by extended seqnum that were not reported yet
+    arrivals: BTreeMap<u64, Instant>,
+    // The first extended seqnum of the next feedback
+    next_base_seqnum: Option<u64>,
+    feedback_packet_count: u8,
+}
+
+impl TwccReceiver {
+    pub(crate) fn packet_received(&mut self, ssrc: u32, seqnum: u16, now: Instant) {
+        let seqnum = self.ext_seqnum.next(seqnum);
+        if self
+            .next_base_seqnum
+            .is_some_and(|next_base_seqnum| seqnum < next_base_seqnum)
+        {
+            trace!("Ignoring late packet {seqnum} that was already reported");
+            return;
+        }
+
+        self.epoch.get_or_insert(now);
+        self.media_ssrc.get_or_insert(ssrc);
+        self.arrivals.entry(seqnum).or_insert(now);
+    }
+
+    /// Whether there are packets that were not reported yet
+    pub(crate) fn has_pending_feedback(&self) -> bool {
+        !self.arrivals.is_empty()
+    }
+
+    /// The media SSRC to put into the feedback packets
+    pub(crate) fn media_ssrc(&self) -> Option<u32> {
+        self.media_ssrc
+    }
+
+    /// Generate the feedback for the packets received since the last call
+    pub(crate) fn generate_feedback(&mut self) -> Option<TwccBuilder> {
+        let epoch = self.epoch?;
+        let (&first_seqnum, _) = self.arrivals.first_key_value()?;
+
+        let base_seqnum = match self.next_base_seqnum {
+            Some(next_base_seqnum) if first_seqnum - next_base_seqnum < MAX_FEEDBACK_PACKETS => {
+                next_base_seqnum
+            }
+            _ => first_seqnum,
+        };
+        let last_seqnum = self
+            .arrivals
+            .range(..base_seqnum + MAX_FEEDBACK_PACKETS)
+            .next_back()
+            .map(|(&seqnum, _)| seqnum)
+            .unwrap();
+
+        // Units of 250µs since the epoch
+        let ticks = |arrival: &Instant| {
+            (arrival.saturating_duration_since(epoch).as_micros() / DELTA_UNIT.as_micros()) as i64
+        };
+        let first_arrival = self.arrivals[&first_seqnum];
+        let reference_time = (first_arrival.saturating_duration_since(epoch).as_micros()
+            / REFERENCE_TIME_UNIT.as_micros()) as i64;
+
+        let mut twcc = Twcc::builder(
+            (base_seqnum & 0xffff) as u16,
+            // 24 bit value
+            (reference_time & 0x7f_ffff) as i32,
+            self.feedback_packet_count,
+        );
+        let mut last_ticks = reference_time * DELTAS_PER_REFERENCE_TIME_UNIT;
+        for seqnum in base_seqnum..=last_seqnum {
+            let status = match self.arrivals.remove(&seqnum) {
+                Some(arrival) => {
+                    let ticks = ticks(&arrival);
+                    let delta = ticks - last_ticks;
+                    last_ticks = ticks;
+                    TwccStatus::Received(delta.clamp(i16::MIN as i64, i16::MAX as i64) as i32)
+                }
+                None => TwccStatus::NotReceived,
+            };
+            twcc = twcc.add_status(status);
+        }
+
+        trace!(
+            "Generated feedback {} for packets {base_seqnum} - {last_seqnum}",
+            self.feedback_packet_count
+        );
+        self.next_base_seqnum = Some(last_seqnum + 1);
+        self.feedback_packet_count = self.feedback_packet_count.wrapping_add(1);
+
+        Some(twcc)
+    }
+}
+
+#[derive(Debug)]
+struct SentPacket {
+    ssrc: u32,
+    pt: u8,
+    size: usize,
+    time: Instant,
+}
+
+/// The transport-wide feedback for a single sent packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwccPacket {
+    pub seqnum: u16,
+    pub ssrc: u32,
+    pub pt: u8,
+    pub size: usize,
+    /// Send time relative to the first sent packet
+    pub local_time: Duration,
+    /// Arrival time on the receiver relative to its reference, or `None` if the packet was lost
+    pub remote_time: Option<Duration>,
+}
+
+/// Remembers sent packets and matches them with received transport feedback
+#[derive(Debug, Default)]
+pub(crate) struct TwccSender {
+    ext_seqnum: ExtendedSeqnum,
+    // All send times are relative to the first sent packet
+    epoch: Option<Instant>,
+    sent: BTreeMap<u64, SentPacket>,
+    last_feedback_packet_count: Option<u8>,
+}
+
+impl TwccSender {
+    pub(crate) fn packet_sent(
+        &mut self,
+        ssrc: u32,
+        pt: u8,
+        seqnum: u16,
+        size: usize,
+        now: Instant,
+    ) {
+        let seqnum = self.ext_seqnum.next(seqnum);
+        self.epoch.get_or_insert(now);
+        self.sent.insert(
+            seqnum,
+            SentPacket {
+                ssrc,
+                pt,
+                size,
+                time: now,
+            },
+        );
+        while self.sent.len() > MAX_SENT_PACKETS {
+            self.sent.pop_first();
+        }
+    }
+
+    /// Match the received feedback with the sent packets
+    pub(crate) fn handle_feedback(&mut self, twcc: &Twcc) -> Vec<TwccPacket> {
+        let (Some(epoch), Some(current)) = (self.epoch, self.ext_seqnum.current()) else {
+            return vec![];
+        };
+
+        if self
+            .last_feedback_packet_count
+            .is_some_and(|count| count == twcc.feedback_packet_count())
+        {
+            trace!(
+                "Ignoring duplicate feedback {}",
+                twcc.feedback_packet_count()
+            );
+            return vec![];
+        }
+        self.last_feedback_packet_count = Some(twcc.feedback_packet_count());
+
+        let base_seqnum = extend_seqnum(twcc.base_seqnum(), current);
+        let mut remote_ticks = twcc.reference_time() as i64 * DELTAS_PER_REFERENCE_TIME_UNIT;
+
+        let mut ret = vec![];
+        for (seqnum, status) in (base_seqnum..).zip(twcc.statuses()) {
+            let remote_time = match status {
+                TwccStatus::NotReceived => None,
+                TwccStatus::Received(delta) => {
+                    remote_ticks += *delta as i64;
+                    Some(DELTA_UNIT * remote_ticks.max(0) as u32)
+                }
+            };
+
+            let Some(sent) = self.sent.get(&seqnum) else {
+                continue;
+            };
+            ret.push(TwccPacket {
+                seqnum: (seqnum & 0xffff) as u16,
+                ssrc: sent.ssrc,
+                pt: sent.pt,
+                size: sent.size,
+                local_time: sent.time.saturating_duration_since(epoch),
+                remote_time,
+            });
+        }
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtpbin2::session::tests::init_logs;
+
+    fn generate_rtp_packet_with_twcc(seqnum: u16, two_byte: bool) -> Vec<u8> {
+        let (pattern, ext) = if two_byte {
+            (0x1000, vec![0, 2, 2, 0xaa, 0xbb, 5, 2].into_iter())
+        } else {
+            (0xBEDE, vec![0, 0x21, 0xaa, 0xbb, 0x51].into_iter())
+        };
+        let ext = ext
+            .chain(seqnum.to_be_bytes())
+            .chain(std::iter::repeat(0))
+            .take(12)
+            .collect::<Vec<_>>();
+        rtp_types::RtpPacketBuilder::<&[u8], &[u8]>::new()
+            .payload_type(96)
+            .ssrc(0x12345678)
+            .sequence_number(0)
+            .extension(pattern, ext.as_slice())
+            .payload([1, 2, 3, 4].as_slice())
+            .write_vec()
+            .unwrap()
+    }
+
+    #[test]
+    fn extension_id_from_caps() {
+        init_logs();
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("payload", 96i32)
+            .field("extmap-1", "urn:ietf:params:rtp-hdrext:sdes:mid")
+            .field("extmap-5", TWCC_EXTMAP_URI)
+            .build();
+        assert_eq!(twcc_extension_id_from_caps(&caps), Some(5));
+
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("payload", 96i32)
+            .field("extmap-3", gst::Array::new(["", TWCC_EXTMAP_URI, ""]))
+            .build();
+        assert_eq!(twcc_extension_id_from_caps(&caps), Some(3));
+
+        let caps = gst::Caps::builder("application/x-rtp")
+            .field("payload", 96i32)
+            .build();
+        assert_eq!(twcc_extension_id_from_caps(&caps), None);
+    }
+
+    #[test]
+    fn read_seqnum() {
+        init_logs();
+        for two_byte in [false, true] {
+            let data = generate_rtp_packet_with_twcc(0x1234, two_byte);
+            let rtp = RtpPacket::parse(&data).unwrap();
+            assert_eq!(read_twcc_seqnum(&rtp, 5), Some(0x1234));
+            assert_eq!(read_twcc_seqnum(&rtp, 6), None);
+        }
+    }
+
+    #[test]
+    fn extend() {
+        assert_eq!(extend_seqnum(10, 0x1_0005), 0x1_000a);
+        assert_eq!(extend_seqnum(0xfffe, 0x1_0005), 0xfffe);
+        assert_eq!(extend_seqnum(2, 0x1_fffe), 0x2_0002);
+        assert_eq!(extend_seqnum(0xfffe, 0x5), 0xfffe);
+    }
+
+    #[test]
+    fn twcc_roundtrip() {
+        init_logs();
+        let mut builder = Twcc::builder(0xfffe, -5, 3);
+        let mut statuses = vec![
+            TwccStatus::Received(4),
+            TwccStatus::NotReceived,
+            TwccStatus::Received(-20),
+            TwccStatus::Received(1000),
+        ];
+        // a long run of small deltas
+        statuses.extend(std::iter::repeat(TwccStatus::Received(1)).take(20));
+        statuses.push(TwccStatus::NotReceived);
+        for status in statuses.iter() {
+            builder = builder.add_status(*status);
+        }
+
+        let size = builder.calculate_size().unwrap();
+        assert_eq!(size % 4, 0);
+        let mut data = vec![0; size];
+        assert_eq!(builder.write_into_unchecked(&mut data), size);
+
+        let twcc = Twcc::parse(&data).unwrap();
+        assert_eq!(twcc.base_seqnum(), 0xfffe);
+        assert_eq!(twcc.reference_time(), -5);
+        assert_eq!(twcc.feedback_packet_count(), 3);
+        assert_eq!(twcc.statuses(), statuses.as_slice());
+    }
+
+    #[test]
+    fn parse_one_bit_status_vector() {
+        init_logs();
+        let data = [
+            0x00, 0x10, 0x00, 0x03, // base seqnum 16, status count 3
+            0x00, 0x00, 0x01, 0x00, // reference time 1, feedback count 0
+            0xa0, 0x00, // status vector chunk, received, not received, received
+            0x02, 0x03, // deltas
+        ];
+        let twcc = Twcc::parse(&data).unwrap();
+        assert_eq!(
+            twcc.statuses(),
+            &[
+                TwccStatus::Received(2),
+                TwccStatus::NotReceived,
+                TwccStatus::Received(3)
+            ]
+        );
+
+        assert!(Twcc::parse(&data[..11]).is_err());
+    }
+
+    #[test]
+    fn remb_roundtrip() {
+        init_logs();
+        let builder = Remb::builder(1_234_567).add_ssrc(0x1).add_ssrc(0x2);
+        let size = builder.calculate_size().unwrap();
+        let mut data = vec![0; size];
+        assert_eq!(builder.write_into_unchecked(&mut data), size);
+
+        let remb = Remb::parse(&data).unwrap();
+        assert_eq!(remb.ssrcs(), &[0x1, 0x2]);
+        // the mantissa only has 18 bits
+        assert!(remb.bitrate() <= 1_234_567);
+        assert!(remb.bitrate() > 1_234_567 - 8);
+    }
+
+    #[test]
+    fn feedback_matches_sent_packets() {
+        init_logs();
+        let now = Instant::now();
+        let mut sender = TwccSender::default();
+        let mut receiver = TwccReceiver::default();
+
+        for seqnum in 0..5u16 {
+            sender.packet_sent(
+                0x1234,
+                96,
+                seqnum,
+                100,
+                now + Duration::from_millis(seqnum as u64),
+            );
+        }
+        // packet 2 is lost
+        for seqnum in [0, 1, 3, 4] {
+            receiver.packet_received(
+                0x1234,
+                seqnum,
+                now + Duration::from_millis(50 + 2 * seqnum as u64),
+            );
+        }
+        assert!(receiver.has_pending_feedback());
+        assert_eq!(receiver.media_ssrc(), Some(0x1234));
+
+        let builder = receiver.generate_feedback().unwrap();
+        assert!(!receiver.has_pending_feedback());
+        let mut data = vec![0; builder.calculate_size().unwrap()];
+        builder.write_into_unchecked(&mut data);
+        let twcc = Twcc::parse(&data).unwrap();
+
+        let packets = sender.handle_feedback(&twcc);
+        assert_eq!(packets.len(), 5);
+        for (packet, seqnum) in packets.iter().zip(0..) {
+            assert_eq!(packet.seqnum, seqnum);
+            assert_eq!(packet.ssrc, 0x1234);
+            assert_eq!(packet.pt, 96);
+            assert_eq!(packet.size, 100);
+            assert_eq!(packet.local_time, Duration::from_millis(seqnum as u64));
+        }
+        assert!(packets[2].remote_time.is_none());
+        // arrival times are relative to the first packet on the receiver
+        assert_eq!(packets[0].remote_time, Some(Duration::ZERO));
+        assert_eq!(packets[4].remote_time, Some(Duration::from_millis(8)));
+
+        // the same feedback is only handled once
+        assert!(sender.handle_feedback(&twcc).is_empty());
+
+        // the next feedback continues after the last reported packet and reports losses
+        receiver.packet_received(0x1234, 7, now + Duration::from_millis(70));
+        let builder = receiver.generate_feedback().unwrap();
+        let mut data = vec![0; builder.calculate_size().unwrap()];
+        builder.write_into_unchecked(&mut data);
+        let twcc = Twcc::parse(&data).unwrap();
+        assert_eq!(twcc.base_seqnum(), 5);
+        assert_eq!(
+            twcc.statuses(),
+            &[
+                TwccStatus::NotReceived,
+                TwccStatus::NotReceived,
+                TwccStatus::Received(80)
+            ]
+        );
+    }
+}
@@ -0,0 +1,741 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! RTCP Extended Reports (XR) as specified in RFC 3611.
+//!
+//! Only the report blocks used by the session are implemented: loss run-length encoding (RLE),
+//! receiver reference time (RRTR), delay since last receiver report (DLRR), statistics summary
+//! and VoIP metrics.  Other report blocks are skipped when parsing.
+
+use std::time::Duration;
+
+use rtcp_types::{RtcpPacketWriter, RtcpParseError, RtcpWriteError};
+
+pub(crate) const XR_PACKET_TYPE: u8 = 207;
+
+const BT_LOSS_RLE: u8 = 1;
+const BT_RRTR: u8 = 4;
+const BT_DLRR: u8 = 5;
+const BT_STATISTICS_SUMMARY: u8 = 6;
+const BT_VOIP_METRICS: u8 = 7;
+
+/// Maximum number of packets covered by a single loss RLE or statistics summary block
+pub(crate) const MAX_INTERVAL_PACKETS: u64 = 0x8000;
+
+/// Minimum number of received packets between two losses for them to be in separate bursts
+pub(crate) const GMIN: u8 = 16;
+
+/// Value of the VoIP metrics fields that are not available
+const UNAVAILABLE: u8 = 127;
+
+/// Loss RLE report block: which packets of an interval were received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LossRle {
+    pub ssrc: u32,
+    pub begin_seq: u16,
+    /// Reception status of each packet starting at `begin_seq`
+    pub received: Vec<bool>,
+}
+
+impl LossRle {
+    /// Number of lost packets in the interval
+    pub(crate) fn lost(&self) -> usize {
+        self.received.iter().filter(|&&received| !received).count()
+    }
+
+    fn chunks(&self) -> Vec<u16> {
+        let mut chunks = Vec::new();
+
+        let mut remaining = self.received.as_slice();
+        while let Some(&first) = remaining.first() {
+            let run = remaining
+                .iter()
+                .take(0x3fff)
+                .take_while(|&&received| received == first)
+                .count();
+
+            if run >= 15 || run == remaining.len() {
+                // run length chunk
+                chunks.push(((first as u16) << 14) | run as u16);
+                remaining = &remaining[run..];
+            } else {
+                // bit vector chunk
+                let mut chunk = 0x8000;
+                for (i, &received) in remaining.iter().take(15).enumerate() {
+                    if received {
+                        chunk |= 1 << (14 - i);
+                    }
+                }
+                chunks.push(chunk);
+                remaining = &remaining[remaining.len().min(15)..];
+            }
+        }
+
+        // padded to 32 bits with a null chunk
+        if chunks.len() % 2 == 1 {
+            chunks.push(0);
+        }
+
+        chunks
+    }
+
+    fn parse(data: &[u8]) -> Result<Self, RtcpParseError> {
+        if data.len() < 12 {
+            return Err(RtcpParseError::Truncated {
+                expected: 12,
+                actual: data.len(),
+            });
+        }
+
+        let ssrc = u32::from_be_bytes(data[4..8].try_into().unwrap());
+        let begin_seq = u16::from_be_bytes([data[8], data[9]]);
+        let end_seq = u16::from_be_bytes([data[10], data[11]]);
+        let n_packets = end_seq.wrapping_sub(begin_seq) as usize;
+
+        let mut received = Vec::with_capacity(n_packets);
+        for chunk in data[12..].chunks_exact(2) {
+            let chunk = u16::from_be_bytes([chunk[0], chunk[1]]);
+            if chunk & 0x8000 != 0 {
+                for i in (0..15).rev() {
+                    received.push(chunk & (1 << i) != 0);
+                }
+            } else {
+                // null chunks have a run length of zero
+                let run = (chunk & 0x3fff) as usize;
+                received.extend(std::iter::repeat(chunk & 0x4000 != 0).take(run));
+            }
+        }
+
+        if received.len() < n_packets {
+            return Err(RtcpParseError::Truncated {
+                expected: n_packets,
+                actual: received.len(),
+            });
+        }
+        received.truncate(n_packets);
+
+        Ok(Self {
+            ssrc,
+            begin_seq,
+            received,
+        })
+    }
+}
+
+/// A single DLRR sub-block as a reply to a RRTR of `ssrc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Dlrr {
+    pub ssrc: u32,
+    /// Middle 32 bits of the NTP timestamp of the last RRTR
+    pub last_rr: u32,
+    /// Delay since the last RRTR in units of 1/65536 seconds
+    pub delay_since_last_rr: u32,
+}
+
+/// Statistics summary report block about an interval of packets
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct StatisticsSummary {
+    pub ssrc: u32,
+    pub begin_seq: u16,
+    pub end_seq: u16,
+    pub lost_packets: u32,
+    pub dup_packets: u32,
+    /// Relative transit time between two packets in RTP clock rate units
+    pub min_jitter: u32,
+    pub max_jitter: u32,
+    pub mean_jitter: u32,
+    pub dev_jitter: u32,
+}
+
+/// VoIP metrics report block.  Fields that can't be measured by the session are not included.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct VoipMetrics {
+    pub ssrc: u32,
+    /// Fraction of lost packets in units of 1/256
+    pub loss_rate: u8,
+    /// Fraction of discarded packets in units of 1/256
+    pub discard_rate: u8,
+    /// Fraction of lost packets within bursts in units of 1/256
+    pub burst_density: u8,
+    /// Fraction of lost packets within gaps in units of 1/256
+    pub gap_density: u8,
+    /// Mean duration of bursts in milliseconds
+    pub burst_duration: u16,
+    /// Mean duration of gaps in milliseconds
+    pub gap_duration: u16,
+    /// Round trip time in milliseconds
+    pub round_trip_delay: u16,
+    /// End system delay in milliseconds
+    pub end_system_delay: u16,
+    pub gmin: u8,
+    pub rx_config: u8,
+    /// Jitter buffer delays in milliseconds
+    pub jb_nominal: u16,
+    pub jb_maximum: u16,
+    pub jb_abs_max: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum XrBlock {
+    LossRle(LossRle),
+    /// Receiver reference time as 64 bit NTP timestamp
+    Rrtr(u64),
+    Dlrr(Vec<Dlrr>),
+    StatisticsSummary(StatisticsSummary),
+    VoipMetrics(VoipMetrics),
+}
+
+impl XrBlock {
+    /// The SSRC of the media source this block reports about, if any
+    pub(crate) fn media_ssrc(&self) -> Option<u32> {
+        match self {
+            XrBlock::LossRle(rle) => Some(rle.ssrc),
+            XrBlock::StatisticsSummary(summary) => Some(summary.ssrc),
+            XrBlock::VoipMetrics(voip) => Some(voip.ssrc),
+            XrBlock::Rrtr(_) | XrBlock::Dlrr(_) => None,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            XrBlock::LossRle(rle) => 12 + rle.chunks().len() * 2,
+            XrBlock::Rrtr(_) => 12,
+            XrBlock::Dlrr(dlrr) => 4 + dlrr.len() * 12,
+            XrBlock::StatisticsSummary(_) => 40,
+            XrBlock::VoipMetrics(_) => 36,
+        }
+    }
+
+    fn write_into(&self, buf: &mut [u8]) -> usize {
+        let size = self.size();
+        buf[2..4].copy_from_slice(&((size / 4 - 1) as u16).to_be_bytes());
+
+        match self {
+            XrBlock::LossRle(rle) => {
+                buf[0] = BT_LOSS_RLE;
+                // no thinning
+                buf[1] = 0;
+                buf[4..8].copy_from_slice(&rle.ssrc.to_be_bytes());
+                buf[8..10].copy_from_slice(&rle.begin_seq.to_be_bytes());
+                let end_seq = rle.begin_seq.wrapping_add(rle.received.len() as u16);
+                buf[10..12].copy_from_slice(&end_seq.to_be_bytes());
+                for (i, chunk) in rle.chunks().into_iter().enumerate() {
+                    buf[12 + i * 2..14 + i * 2].copy_from_slice(&chunk.to_be_bytes());
+                }
+            }
+            XrBlock::Rrtr(ntp) => {
+                buf[0] = BT_RRTR;
+                buf[1] = 0;
+                buf[4..12].copy_from_slice(&ntp.to_be_bytes());
+            }
+            XrBlock::Dlrr(dlrr) => {
+                buf[0] = BT_DLRR;
+                buf[1] = 0;
+                for (i, sub_block) in dlrr.iter().enumerate() {
+                    let buf = &mut buf[4 + i * 12..16 + i * 12];
+                    buf[0..4].copy_from_slice(&sub_block.ssrc.to_be_bytes());
+                    buf[4..8].copy_from_slice(&sub_block.last_rr.to_be_bytes());
+                    buf[8..12].copy_from_slice(&sub_block.delay_since_last_rr.to_be_bytes());
+                }
+            }
+            XrBlock::StatisticsSummary(summary) => {
+                buf[0] = BT_STATISTICS_SUMMARY;
+                // loss, duplicate and jitter reports but no TTL / hop limit
+                buf[1] = 0b1110_0000;
+                buf[4..8].copy_from_slice(&summary.ssrc.to_be_bytes());
+                buf[8..10].copy_from_slice(&summary.begin_seq.to_be_bytes());
+                buf[10..12].copy_from_slice(&summary.end_seq.to_be_bytes());
+                buf[12..16].copy_from_slice(&summary.lost_packets.to_be_bytes());
+                buf[16..20].copy_from_slice(&summary.dup_packets.to_be_bytes());
+                buf[20..24].copy_from_slice(&summary.min_jitter.to_be_bytes());
+                buf[24..28].copy_from_slice(&summary.max_jitter.to_be_bytes());
+                buf[28..32].copy_from_slice(&summary.mean_jitter.to_be_bytes());
+                buf[32..36].copy_from_slice(&summary.dev_jitter.to_be_bytes());
+                buf[36..40].fill(0);
+            }
+            XrBlock::VoipMetrics(voip) => {
+                buf[0] = BT_VOIP_METRICS;
+                buf[1] = 0;
+                buf[4..8].copy_from_slice(&voip.ssrc.to_be_bytes());
+                buf[8] = voip.loss_rate;
+                buf[9] = voip.discard_rate;
+                buf[10] = voip.burst_density;
+                buf[11] = voip.gap_density;
+                buf[12..14].copy_from_slice(&voip.burst_duration.to_be_bytes());
+                buf[14..16].copy_from_slice(&voip.gap_duration.to_be_bytes());
+                buf[16..18].copy_from_slice(&voip.round_trip_delay.to_be_bytes());
+                buf[18..20].copy_from_slice(&voip.end_system_delay.to_be_bytes());
+                // signal level, noise level, residual echo return loss
+                buf[20..23].fill(UNAVAILABLE);
+                buf[23] = voip.gmin;
+                // R factor, external R factor, MOS-LQ, MOS-CQ
+                buf[24..28].fill(UNAVAILABLE);
+                buf[28] = voip.rx_config;
+                buf[29] = 0;
+                buf[30..32].copy_from_slice(&voip.jb_nominal.to_be_bytes());
+                buf[32..34].copy_from_slice(&voip.jb_maximum.to_be_bytes());
+                buf[34..36].copy_from_slice(&voip.jb_abs_max.to_be_bytes());
+            }
+        }
+
+        size
+    }
+
+    fn parse(bt: u8, data: &[u8]) -> Result<Option<Self>, RtcpParseError> {
+        let check_len = |expected: usize| {
+            if data.len() < expected {
+                Err(RtcpParseError::Truncated {
+                    expected,
+                    actual: data.len(),
+                })
+            } else {
+                Ok(())
+            }
+        };
+        let read_u16 = |offset: usize| u16::from_be_bytes([data[offset], data[offset + 1]]);
+        let read_u32 =
+            |offset: usize| u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap());
+
+        let block = match bt {
+            BT_LOSS_RLE => XrBlock::LossRle(LossRle::parse(data)?),
+            BT_RRTR => {
+                check_len(12)?;
+                XrBlock::Rrtr(u64::from_be_bytes(data[4..12].try_into().unwrap()))
+            }
+            BT_DLRR => XrBlock::Dlrr(
+                data[4..]
+                    .chunks_exact(12)
+                    .map(|sub_block| Dlrr {
+                        ssrc: u32::from_be_bytes(sub_block[0..4].try_into().unwrap()),
+                        last_rr: u32::from_be_bytes(sub_block[4..8].try_into().unwrap()),
+                        delay_since_last_rr: u32::from_be_bytes(
+                            sub_block[8..12].try_into().unwrap(),
+                        ),
+                    })
+                    .collect(),
+            ),
+            BT_STATISTICS_SUMMARY => {
+                check_len(40)?;
+                let flags = data[1];
+                let lost = flags & 0x80 != 0;
+                let dup = flags & 0x40 != 0;
+                let jitter = flags & 0x20 != 0;
+                XrBlock::StatisticsSummary(StatisticsSummary {
+                    ssrc: read_u32(4),
+                    begin_seq: read_u16(8),
+                    end_seq: read_u16(10),
+                    lost_packets: if lost { read_u32(12) } else { 0 },
+                    dup_packets: if dup { read_u32(16) } else { 0 },
+                    min_jitter: if jitter { read_u32(20) } else { 0 },
+                    max_jitter: if jitter { read_u32(24) } else { 0 },
+                    mean_jitter: if jitter { read_u32(28) } else { 0 },
+                    dev_jitter: if jitter { read_u32(32) } else { 0 },
+                })
+            }
+            BT_VOIP_METRICS => {
+                check_len(36)?;
+                XrBlock::VoipMetrics(VoipMetrics {
+                    ssrc: read_u32(4),
+                    loss_rate: data[8],
+                    discard_rate: data[9],
+                    burst_density: data[10],
+                    gap_density: data[11],
+                    burst_duration: read_u16(12),
+                    gap_duration: read_u16(14),
+                    round_trip_delay: read_u16(16),
+                    end_system_delay: read_u16(18),
+                    gmin: data[23],
+                    rx_config: data[28],
+                    jb_nominal: read_u16(30),
+                    jb_maximum: read_u16(32),
+                    jb_abs_max: read_u16(34),
+                })
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(block))
+    }
+}
+
+/// A parsed extended report packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Xr {
+    ssrc: u32,
+    blocks: Vec<XrBlock>,
+}
+
+impl Xr {
+    pub(crate) fn builder(ssrc: u32) -> XrBuilder {
+        XrBuilder {
+            ssrc,
+            blocks: vec![],
+        }
+    }
+
+    /// Parse an extended report packet including the RTCP header
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, RtcpParseError> {
+        if data.len() < 8 {
+            return Err(RtcpParseError::Truncated {
+                expected: 8,
+                actual: data.len(),
+            });
+        }
+        if data[1] != XR_PACKET_TYPE {
+            return Err(RtcpParseError::PacketTypeMismatch {
+                actual: data[1],
+                requested: XR_PACKET_TYPE,
+            });
+        }
+
+        let len = (u16::from_be_bytes([data[2], data[3]]) as usize + 1) * 4;
+        if data.len() < len {
+            return Err(RtcpParseError::Truncated {
+                expected: len,
+                actual: data.len(),
+            });
+        }
+        let mut data = &data[..len];
+        if data[0] & 0x20 != 0 {
+            let padding = data[len - 1] as usize;
+            data = &data[..len.saturating_sub(padding).max(8)];
+        }
+
+        let ssrc = u32::from_be_bytes(data[4..8].try_into().unwrap());
+
+        let mut blocks = vec![];
+        let mut data = &data[8..];
+        while data.len() >= 4 {
+            let bt = data[0];
+            let block_len = (u16::from_be_bytes([data[2], data[3]]) as usize + 1) * 4;
+            if data.len() < block_len {
+                return Err(RtcpParseError::Truncated {
+                    expected: block_len,
+                    actual: data.len(),
+                });
+            }
+            if let Some(block) = XrBlock::parse(bt, &data[..block_len])? {
+                blocks.push(block);
+            }
+            data = &data[block_len..];
+        }
+
+        Ok(Self { ssrc, blocks })
+    }
+
+    /// The SSRC of the sender of this extended report
+    pub(crate) fn ssrc(&self) -> u32 {
+        self.ssrc
+    }
+
+    pub(crate) fn blocks(&self) -> &[XrBlock] {
+        &self.blocks
+    }
+}
+
+/// Builder for an extended report packet
+#[derive(Debug, Clone)]
+pub(crate) struct XrBuilder {
+    ssrc: u32,
+    blocks: Vec<XrBlock>,
+}
+
+impl XrBuilder {
+    pub(crate) fn add_block(mut self, block: XrBlock) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// The size of the packet if `block` was added
+    pub(crate) fn size_with_block(&self, block: &XrBlock) -> usize {
+        self.calculate_size().unwrap_or(0) + block.size()
+    }
+}
+
+impl RtcpPacketWriter for XrBuilder {
+    fn calculate_size(&self) -> Result<usize, RtcpWriteError> {
+        Ok(8 + self.blocks.iter().map(XrBlock::size).sum::<usize>())
+    }
+
+    fn write_into_unchecked(&self, buf: &mut [u8]) -> usize {
+        let size = self.calculate_size().unwrap();
+
+        buf[0] = 0x80;
+        buf[1] = XR_PACKET_TYPE;
+        buf[2..4].copy_from_slice(&((size / 4 - 1) as u16).to_be_bytes());
+        buf[4..8].copy_from_slice(&self.ssrc.to_be_bytes());
+
+        let mut offset = 8;
+        for block in self.blocks.iter() {
+            offset += block.write_into(&mut buf[offset..]);
+        }
+
+        offset
+    }
+
+    fn get_padding(&self) -> Option<u8> {
+        None
+    }
+}
+
+/// Burst and gap loss metrics as per RFC 3611 section 4.7.2, calculated with the packet loss
+/// model of RFC 3611 appendix A.2.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LossPattern {
+    // received packets since the last loss
+    pkt: u64,
+    // lost packets in the current burst
+    lost: u64,
+    // state transition counters
+    c11: u64,
+    c13: u64,
+    c14: u64,
+    c22: u64,
+    c23: u64,
+    c33: u64,
+    received_total: u64,
+    lost_total: u64,
+}
+
+impl LossPattern {
+    /// Update the metrics with the reception status of the next packet
+    pub(crate) fn packet(&mut self, received: bool) {
+        if received {
+            self.received_total += 1;
+            self.pkt += 1;
+            return;
+        }
+
+        self.lost_total += 1;
+        if self.pkt >= GMIN as u64 {
+            if self.lost == 1 {
+                self.c14 += 1;
+            } else {
+                self.c13 += 1;
+            }
+            self.lost = 1;
+            self.c11 += self.pkt;
+        } else {
+            self.lost += 1;
+            if self.pkt == 0 {
+                self.c33 += 1;
+            } else {
+                self.c23 += 1;
+                self.c22 += self.pkt - 1;
+            }
+        }
+        self.pkt = 0;
+    }
+
+    /// Whether any packets were processed yet
+    pub(crate) fn is_empty(&self) -> bool {
+        self.received_total == 0 && self.lost_total == 0
+    }
+
+    pub(crate) fn lost(&self) -> u64 {
+        self.lost_total
+    }
+
+    /// Fraction of lost packets in units of 1/256
+    pub(crate) fn loss_rate(&self) -> u8 {
+        let total = self.received_total + self.lost_total;
+        if total == 0 {
+            return 0;
+        }
+        ((self.lost_total * 256) / total).min(255) as u8
+    }
+
+    // Received packets after the last loss are part of the current gap
+    fn c11(&self) -> u64 {
+        if self.pkt >= GMIN as u64 {
+            self.c11 + self.pkt
+        } else {
+            self.c11
+        }
+    }
+
+    /// Fraction of lost packets within bursts in units of 1/256
+    pub(crate) fn burst_density(&self) -> u8 {
+        if self.lost_total == 0 {
+            return 0;
+        }
+
+        let c31 = self.c13 as f64;
+        let c32 = self.c23 as f64;
+        let c33 = self.c33 as f64;
+        let c22 = self.c22 as f64;
+        let c23 = self.c23 as f64;
+
+        let p32 = if c31 + c32 + c33 > 0.0 {
+            c32 / (c31 + c32 + c33)
+        } else {
+            0.0
+        };
+        let p23 = if c22 + c23 < 1.0 {
+            1.0
+        } else {
+            1.0 - c22 / (c22 + c23)
+        };
+
+        ((256.0 * p23 / (p23 + p32)) as u64).min(255) as u8
+    }
+
+    /// Fraction of lost packets within gaps in units of 1/256
+    pub(crate) fn gap_density(&self) -> u8 {
+        let c11 = self.c11();
+        if c11 + self.c14 == 0 {
+            return 0;
+        }
+        ((256 * self.c14) / (c11 + self.c14)).min(255) as u8
+    }
+
+    /// Mean duration of bursts and gaps for the given duration of a single packet
+    pub(crate) fn burst_gap_duration(&self, packet_duration: Duration) -> (Duration, Duration) {
+        let c11 = self.c11();
+        let total = c11 + self.c14 + 2 * self.c13 + self.c22 + 2 * self.c23 + self.c33;
+
+        if self.c13 == 0 {
+            // no transition from a gap into a burst, so all packets are in a single gap or burst
+            let all = packet_duration * (self.received_total + self.lost_total) as u32;
+            if self.lost_total > 0 && c11 == 0 {
+                return (all, Duration::ZERO);
+            } else {
+                return (Duration::ZERO, all);
+            }
+        }
+
+        let gap = (c11 + self.c14 + self.c13) as f64 / self.c13 as f64;
+        let burst = total as f64 / self.c13 as f64 - gap;
+
+        (
+            packet_duration.mul_f64(burst.max(0.0)),
+            packet_duration.mul_f64(gap),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loss_rle_roundtrip() {
+        let mut received = vec![true; 40];
+        received[3] = false;
+        received[20..25].fill(false);
+        received.extend([false; 20]);
+
+        let rle = LossRle {
+            ssrc: 0x12345678,
+            begin_seq: 0xfff0,
+            received,
+        };
+        assert_eq!(rle.lost(), 26);
+
+        let xr = Xr::builder(0x11223344).add_block(XrBlock::LossRle(rle.clone()));
+        let mut data = vec![0; xr.calculate_size().unwrap()];
+        assert_eq!(xr.write_into_unchecked(&mut data), data.len());
+
+        let parsed = Xr::parse(&data).unwrap();
+        assert_eq!(parsed.ssrc(), 0x11223344);
+        assert_eq!(parsed.blocks(), &[XrBlock::LossRle(rle)]);
+    }
+
+    #[test]
+    fn blocks_roundtrip() {
+        let blocks = vec![
+            XrBlock::Rrtr(0x0102030405060708),
+            XrBlock::Dlrr(vec![
+                Dlrr {
+                    ssrc: 1,
+                    last_rr: 2,
+                    delay_since_last_rr: 3,
+                },
+                Dlrr {
+                    ssrc: 4,
+                    last_rr: 5,
+                    delay_since_last_rr: 6,
+                },
+            ]),
+            XrBlock::StatisticsSummary(StatisticsSummary {
+                ssrc: 0x12345678,
+                begin_seq: 100,
+                end_seq: 200,
+                lost_packets: 3,
+                dup_packets: 1,
+                min_jitter: 10,
+                max_jitter: 100,
+                mean_jitter: 50,
+                dev_jitter: 20,
+            }),
+            XrBlock::VoipMetrics(VoipMetrics {
+                ssrc: 0x12345678,
+                loss_rate: 8,
+                burst_density: 128,
+                gap_density: 4,
+                burst_duration: 60,
+                gap_duration: 2000,
+                round_trip_delay: 40,
+                gmin: GMIN,
+                jb_nominal: 200,
+                jb_maximum: 200,
+                jb_abs_max: 200,
+                ..Default::default()
+            }),
+        ];
+
+        let mut xr = Xr::builder(0x11223344);
+        for block in blocks.iter() {
+            xr = xr.add_block(block.clone());
+        }
+        let mut data = vec![0; xr.calculate_size().unwrap()];
+        xr.write_into_unchecked(&mut data);
+
+        let parsed = Xr::parse(&data).unwrap();
+        assert_eq!(parsed.blocks(), blocks.as_slice());
+
+        // unknown blocks are skipped
+        let mut data_with_unknown = data.clone();
+        data_with_unknown.extend([42, 0, 0, 1, 0, 0, 0, 0]);
+        let len = (data_with_unknown.len() / 4 - 1) as u16;
+        data_with_unknown[2..4].copy_from_slice(&len.to_be_bytes());
+        let parsed = Xr::parse(&data_with_unknown).unwrap();
+        assert_eq!(parsed.blocks(), blocks.as_slice());
+
+        // truncated
+        assert!(Xr::parse(&data[..data.len() - 4]).is_err());
+    }
+
+    #[test]
+    fn loss_pattern() {
+        let mut pattern = LossPattern::default();
+        assert!(pattern.is_empty());
+
+        // isolated losses only
+        for i in 0..200 {
+            pattern.packet(i % 50 != 49);
+        }
+        assert_eq!(pattern.lost(), 4);
+        assert_eq!(pattern.loss_rate(), 5);
+        // the first loss is counted as a burst of a single packet
+        assert_eq!(pattern.burst_density(), 255);
+        assert_eq!(pattern.gap_density(), 3);
+
+        // a burst of 4 packets with every second one lost
+        for i in 0..4 {
+            pattern.packet(i % 2 == 1);
+        }
+        for _ in 0..50 {
+            pattern.packet(true);
+        }
+        assert_eq!(pattern.lost(), 6);
+        assert_eq!(pattern.burst_density(), 192);
+
+        let (burst, gap) = pattern.burst_gap_duration(Duration::from_millis(20));
+        assert_eq!(burst, Duration::from_millis(80));
+        assert_eq!(gap, Duration::from_millis(5020));
+    }
+}
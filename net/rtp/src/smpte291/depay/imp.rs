@@ -0,0 +1,258 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpsmpte291depay
+ * @see_also: rtpsmpte291pay, st2038ancdemux, st2038anctocc
+ *
+ * Extracts SMPTE ST 291-1 ancillary data from RTP packets as per [RFC 8331][rfc-8331], which is
+ * also used by SMPTE ST 2110-40.
+ *
+ * Every ANC packet is output as a separate buffer in SMPTE ST 2038 format, with the `MARKER`
+ * flag set on the last buffer of each frame or field. ANC packets of a single field of an
+ * interlaced frame are flagged accordingly via the video buffer flags.
+ *
+ * If downstream only accepts CEA-708 CDP then only the CDP contained in the ancillary data is
+ * output and all other ANC packets are dropped.
+ *
+ * [rfc-8331]: https://www.rfc-editor.org/rfc/rfc8331.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc caps='application/x-rtp, media=video, clock-rate=90000, encoding-name=SMPTE291' ! rtpjitterbuffer latency=50 ! rtpsmpte291depay ! st2038ancdemux ! fakesink
+ * ]| This will depayload an incoming RTP ancillary data stream and split it by DID / SDID.
+ * You can use the #rtpsmpte291pay element to create such an RTP stream.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+use gst_video::prelude::*;
+
+use std::sync::LazyLock;
+
+use crate::{
+    basedepay::{Packet, PacketToBufferRelation, RtpBaseDepay2Ext, TimestampOffset},
+    smpte291::{parse_payload, Field, CEA708_CDP_DID_SDID},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Format {
+    #[default]
+    St2038,
+    Cea708Cdp,
+}
+
+#[derive(Default)]
+struct State {
+    format: Format,
+}
+
+#[derive(Default)]
+pub struct RtpSmpte291Depay {
+    state: AtomicRefCell<State>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpsmpte291depay",
+        gst::DebugColorFlags::empty(),
+        Some("RTP SMPTE ST 291 Ancillary Data Depayloader"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpSmpte291Depay {
+    const NAME: &'static str = "GstRtpSmpte291Depay";
+    type Type = super::RtpSmpte291Depay;
+    type ParentType = crate::basedepay::RtpBaseDepay2;
+}
+
+impl ObjectImpl for RtpSmpte291Depay {}
+
+impl GstObjectImpl for RtpSmpte291Depay {}
+
+impl ElementImpl for RtpSmpte291Depay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP SMPTE ST 291 Ancillary Data Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Depayload SMPTE ST 291 ancillary data from RTP packets (RFC 8331)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("clock-rate", 90_000i32)
+                    .field("encoding-name", "SMPTE291")
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder_full()
+                    .structure(
+                        gst::Structure::builder("meta/x-st-2038")
+                            .field("alignment", "packet")
+                            .build(),
+                    )
+                    .structure(
+                        gst::Structure::builder("closedcaption/x-cea-708")
+                            .field("format", "cdp")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpSmpte291Depay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &[];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, _caps: &gst::Caps) -> bool {
+        let src_pad = self.obj().src_pad().clone();
+        let peer_caps = src_pad.peer_query_caps(Some(&src_pad.pad_template_caps()));
+
+        // Prefer ST 2038 unless downstream only supports CDP
+        let format = match peer_caps.structure(0) {
+            Some(s) if s.name() == "closedcaption/x-cea-708" => Format::Cea708Cdp,
+            _ => Format::St2038,
+        };
+
+        gst::debug!(CAT, imp = self, "Outputting {format:?}");
+
+        let src_caps = match format {
+            Format::St2038 => gst::Caps::builder("meta/x-st-2038")
+                .field("alignment", "packet")
+                .build(),
+            Format::Cea708Cdp => gst::Caps::builder("closedcaption/x-cea-708")
+                .field("format", "cdp")
+                .build(),
+        };
+        self.obj().set_src_caps(&src_caps);
+
+        self.state.borrow_mut().format = format;
+
+        true
+    }
+
+    fn handle_packet(&self, packet: &Packet) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let state = self.state.borrow();
+
+        let (field, anc_packets) = match parse_payload(packet.payload()) {
+            Ok(res) => res,
+            Err(err) => {
+                gst::warning!(CAT, imp = self, "Failed to parse payload: {err:#}");
+                self.obj().drop_packet(packet);
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Parsed {} ANC packets for {field:?}",
+            anc_packets.len()
+        );
+
+        let mut buffers = Vec::with_capacity(anc_packets.len());
+        for anc_packet in &anc_packets {
+            let data = match state.format {
+                Format::St2038 => {
+                    let mut data = Vec::with_capacity(anc_packet.rfc8331_len());
+                    if let Err(err) = anc_packet.write_st2038(&mut data) {
+                        gst::warning!(CAT, imp = self, "Failed to write ANC packet: {err:#}");
+                        continue;
+                    }
+                    data
+                }
+                Format::Cea708Cdp => {
+                    if anc_packet.did_sdid() != CEA708_CDP_DID_SDID {
+                        gst::trace!(
+                            CAT,
+                            imp = self,
+                            "Dropping ANC packet with DID / SDID {:?}",
+                            anc_packet.did_sdid()
+                        );
+                        continue;
+                    }
+                    anc_packet.data()
+                }
+            };
+
+            let mut buffer = gst::Buffer::from_mut_slice(data);
+            {
+                let buffer = buffer.get_mut().unwrap();
+                if field != Field::Progressive {
+                    buffer.set_video_flags(field.video_flags());
+                }
+            }
+            buffers.push(buffer);
+        }
+
+        if buffers.is_empty() {
+            self.obj().drop_packet(packet);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        // The last ANC packet of a frame or field is marked
+        if packet.marker_bit() && state.format == Format::St2038 {
+            let last = buffers.last_mut().unwrap();
+            last.get_mut().unwrap().set_flags(gst::BufferFlags::MARKER);
+        }
+
+        // All ANC packets of a packet have the same timestamp
+        for buffer in buffers {
+            self.obj().queue_buffer(
+                PacketToBufferRelation::SeqnumsWithOffset {
+                    seqnums: packet.ext_seqnum()..=packet.ext_seqnum(),
+                    timestamp_offset: TimestampOffset::Pts(gst::Signed::Positive(
+                        gst::ClockTime::ZERO,
+                    )),
+                },
+                buffer,
+            )?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
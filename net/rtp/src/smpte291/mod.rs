@@ -0,0 +1,364 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! SMPTE ST 291-1 ancillary data as specified in RFC 8331 / SMPTE ST 2110-40.
+//!
+//! Every packet starts with the 16 bit extended sequence number, the length of the ANC data,
+//! the number of ANC packets and the field they belong to. This is followed by the ANC packets,
+//! each of them padded to a multiple of 32 bits.
+//!
+//! Inside GStreamer ANC packets are usually transported in SMPTE ST 2038 format, which contains
+//! the same fields with a slightly different bit layout.
+
+use anyhow::{bail, Context};
+use bitstream_io::{BigEndian, BitRead, BitReader, BitWrite, BitWriter};
+
+pub mod depay;
+pub mod pay;
+
+#[cfg(test)]
+mod tests;
+
+/// Length of the payload header.
+pub(crate) const PAYLOAD_HEADER_LEN: usize = 8;
+
+/// DID / SDID of CEA-708 CDP as per SMPTE ST 334-1.
+pub(crate) const CEA708_CDP_DID_SDID: (u8, u8) = (0x61, 0x01);
+/// Line on which CEA-708 CDP are placed by default.
+pub(crate) const CEA708_CDP_LINE: u16 = 9;
+
+/// Field signalled in the payload header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Field {
+    #[default]
+    Progressive,
+    First,
+    Second,
+}
+
+impl Field {
+    fn to_bits(self) -> u8 {
+        match self {
+            Field::Progressive => 0b00,
+            Field::First => 0b10,
+            Field::Second => 0b11,
+        }
+    }
+
+    fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0b00 => Some(Field::Progressive),
+            0b10 => Some(Field::First),
+            0b11 => Some(Field::Second),
+            _ => None,
+        }
+    }
+
+    /// Field of the buffer according to its video flags.
+    pub fn from_buffer(buffer: &gst::BufferRef) -> Self {
+        use gst_video::prelude::*;
+
+        let flags = buffer.video_flags();
+        if !flags.contains(gst_video::VideoBufferFlags::INTERLACED)
+            || !flags.contains(gst_video::VideoBufferFlags::ONEFIELD)
+        {
+            Field::Progressive
+        } else if flags.contains(gst_video::VideoBufferFlags::TFF) {
+            Field::First
+        } else {
+            Field::Second
+        }
+    }
+
+    /// Video flags for buffers of this field.
+    pub fn video_flags(self) -> gst_video::VideoBufferFlags {
+        match self {
+            Field::Progressive => gst_video::VideoBufferFlags::empty(),
+            Field::First => {
+                gst_video::VideoBufferFlags::INTERLACED | gst_video::VideoBufferFlags::TOP_FIELD
+            }
+            Field::Second => {
+                gst_video::VideoBufferFlags::INTERLACED | gst_video::VideoBufferFlags::BOTTOM_FIELD
+            }
+        }
+    }
+}
+
+/// Extends an 8 bit value to a 10 bit word with the two parity bits.
+fn with_parity(v: u8) -> u16 {
+    if v.count_ones() & 1 == 0 {
+        0x1_00 | v as u16
+    } else {
+        0x2_00 | v as u16
+    }
+}
+
+/// A single ANC packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AncPacket {
+    pub c_not_y_channel: bool,
+    pub line_number: u16,
+    pub horizontal_offset: u16,
+    /// Data stream number, if any. Not available in ST 2038.
+    pub stream_num: Option<u8>,
+    /// 10 bit DID, including parity bits.
+    pub did: u16,
+    /// 10 bit SDID, including parity bits.
+    pub sdid: u16,
+    /// 10 bit user data words, including parity bits.
+    pub user_data: Vec<u16>,
+    /// 10 bit checksum word.
+    pub checksum: u16,
+}
+
+impl AncPacket {
+    /// Creates a new ANC packet with the given 8 bit DID, SDID and data and calculates the
+    /// parity bits and checksum.
+    pub fn new(
+        c_not_y_channel: bool,
+        line_number: u16,
+        horizontal_offset: u16,
+        did: u8,
+        sdid: u8,
+        data: &[u8],
+    ) -> anyhow::Result<Self> {
+        if data.len() > 255 {
+            bail!(
+                "Payload needs to be less than 256 bytes, got {}",
+                data.len()
+            );
+        }
+
+        let did = with_parity(did);
+        let sdid = with_parity(sdid);
+        let user_data = data.iter().map(|&b| with_parity(b)).collect::<Vec<_>>();
+
+        let mut checksum = [did, sdid, with_parity(data.len() as u8)]
+            .into_iter()
+            .chain(user_data.iter().copied())
+            .fold(0u16, |sum, word| sum.wrapping_add(word));
+        checksum &= 0x1_ff;
+        checksum |= ((!(checksum >> 8)) & 0x0_01) << 9;
+
+        Ok(AncPacket {
+            c_not_y_channel,
+            line_number,
+            horizontal_offset,
+            stream_num: None,
+            did,
+            sdid,
+            user_data,
+            checksum,
+        })
+    }
+
+    /// DID and SDID without parity bits.
+    pub fn did_sdid(&self) -> (u8, u8) {
+        ((self.did & 0xff) as u8, (self.sdid & 0xff) as u8)
+    }
+
+    /// User data without parity bits.
+    pub fn data(&self) -> Vec<u8> {
+        self.user_data.iter().map(|&w| (w & 0xff) as u8).collect()
+    }
+
+    /// Parses all ST 2038 ANC packets from `data`, stopping at stuffing bytes.
+    pub fn parse_st2038(data: &[u8]) -> anyhow::Result<Vec<AncPacket>> {
+        let mut r = BitReader::endian(data, BigEndian);
+        let mut packets = Vec::new();
+
+        let mut remaining = data.len();
+        while remaining > 0 {
+            let zeroes = r.read::<u8>(6).context("zero bits")?;
+            if zeroes == 0b11_1111 {
+                // Stuffing bytes
+                break;
+            } else if zeroes != 0 {
+                bail!("Zero bits not zero!");
+            }
+            let c_not_y_channel = r.read_bit().context("c_not_y_channel_flag")?;
+            let line_number = r.read::<u16>(11).context("line number")?;
+            let horizontal_offset = r.read::<u16>(12).context("horizontal offset")?;
+            let did = r.read::<u16>(10).context("DID")?;
+            let sdid = r.read::<u16>(10).context("SDID")?;
+            let data_count = r.read::<u16>(10).context("data count")? & 0xff;
+
+            let mut user_data = Vec::with_capacity(data_count as usize);
+            for _ in 0..data_count {
+                user_data.push(r.read::<u16>(10).context("user data")?);
+            }
+            let checksum = r.read::<u16>(10).context("checksum")?;
+
+            let mut len = 70 + data_count as usize * 10;
+            while len % 8 != 0 {
+                let one = r.read_bit().context("alignment")?;
+                if !one {
+                    bail!("Alignment bits are not ones!");
+                }
+                len += 1;
+            }
+            remaining -= len / 8;
+
+            packets.push(AncPacket {
+                c_not_y_channel,
+                line_number,
+                horizontal_offset,
+                stream_num: None,
+                did,
+                sdid,
+                user_data,
+                checksum,
+            });
+        }
+
+        Ok(packets)
+    }
+
+    /// Writes the ANC packet in ST 2038 format.
+    pub fn write_st2038(&self, out: &mut Vec<u8>) -> anyhow::Result<()> {
+        let mut w = BitWriter::endian(out, BigEndian);
+
+        w.write::<u8>(6, 0b00_0000).context("zero bits")?;
+        w.write_bit(self.c_not_y_channel)
+            .context("c_not_y_channel")?;
+        w.write::<u16>(11, self.line_number)
+            .context("line number")?;
+        w.write::<u16>(12, self.horizontal_offset)
+            .context("horizontal offset")?;
+        self.write_words(&mut w)?;
+
+        while !w.byte_aligned() {
+            w.write_bit(true).context("padding")?;
+        }
+
+        Ok(())
+    }
+
+    /// Length of the ANC packet in RFC 8331 format in bytes.
+    pub fn rfc8331_len(&self) -> usize {
+        let bits = 32 + (4 + self.user_data.len()) * 10;
+        bits.div_ceil(32) * 4
+    }
+
+    fn read_rfc8331(r: &mut impl BitRead) -> anyhow::Result<Self> {
+        let c_not_y_channel = r.read_bit().context("C")?;
+        let line_number = r.read::<u16>(11).context("line number")?;
+        let horizontal_offset = r.read::<u16>(12).context("horizontal offset")?;
+        let s = r.read_bit().context("S")?;
+        let stream_num = r.read::<u8>(7).context("stream num")?;
+        let did = r.read::<u16>(10).context("DID")?;
+        let sdid = r.read::<u16>(10).context("SDID")?;
+        let data_count = r.read::<u16>(10).context("data count")? & 0xff;
+
+        let mut user_data = Vec::with_capacity(data_count as usize);
+        for _ in 0..data_count {
+            user_data.push(r.read::<u16>(10).context("user data")?);
+        }
+        let checksum = r.read::<u16>(10).context("checksum")?;
+
+        let bits = 32 + (4 + data_count as u32) * 10;
+        r.skip(bits.next_multiple_of(32) - bits)
+            .context("word align")?;
+
+        Ok(AncPacket {
+            c_not_y_channel,
+            line_number,
+            horizontal_offset,
+            stream_num: s.then_some(stream_num),
+            did,
+            sdid,
+            user_data,
+            checksum,
+        })
+    }
+
+    fn write_rfc8331(&self, w: &mut impl BitWrite) -> anyhow::Result<()> {
+        w.write_bit(self.c_not_y_channel).context("C")?;
+        w.write::<u16>(11, self.line_number)
+            .context("line number")?;
+        w.write::<u16>(12, self.horizontal_offset)
+            .context("horizontal offset")?;
+        w.write_bit(self.stream_num.is_some()).context("S")?;
+        w.write::<u8>(7, self.stream_num.unwrap_or(0))
+            .context("stream num")?;
+        self.write_words(w)?;
+
+        let bits = 32 + (4 + self.user_data.len() as u32) * 10;
+        for _ in bits..bits.next_multiple_of(32) {
+            w.write_bit(false).context("word align")?;
+        }
+
+        Ok(())
+    }
+
+    fn write_words(&self, w: &mut impl BitWrite) -> anyhow::Result<()> {
+        w.write::<u16>(10, self.did).context("DID")?;
+        w.write::<u16>(10, self.sdid).context("SDID")?;
+        w.write::<u16>(10, with_parity(self.user_data.len() as u8))
+            .context("data count")?;
+        for &word in &self.user_data {
+            w.write::<u16>(10, word).context("user data")?;
+        }
+        w.write::<u16>(10, self.checksum).context("checksum")?;
+
+        Ok(())
+    }
+}
+
+/// Writes the payload header followed by the given ANC packets.
+///
+/// The extended sequence number is always written as zero as it is not known at this point.
+pub(crate) fn write_payload(
+    field: Field,
+    packets: &[AncPacket],
+    out: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    let length = packets.iter().map(AncPacket::rfc8331_len).sum::<usize>();
+    if length > u16::MAX as usize || packets.len() > u8::MAX as usize {
+        bail!("Too many ANC packets");
+    }
+
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&(length as u16).to_be_bytes());
+    out.push(packets.len() as u8);
+    out.extend_from_slice(&[field.to_bits() << 6, 0, 0]);
+
+    let mut w = BitWriter::endian(out, BigEndian);
+    for packet in packets {
+        packet.write_rfc8331(&mut w)?;
+    }
+    debug_assert!(w.byte_aligned());
+
+    Ok(())
+}
+
+/// Parses the payload header and all ANC packets of a payload.
+pub(crate) fn parse_payload(payload: &[u8]) -> anyhow::Result<(Field, Vec<AncPacket>)> {
+    if payload.len() < PAYLOAD_HEADER_LEN {
+        bail!("Payload too small");
+    }
+
+    let length = u16::from_be_bytes([payload[2], payload[3]]) as usize;
+    let anc_count = payload[4];
+    let Some(field) = Field::from_bits(payload[5] >> 6) else {
+        bail!("Invalid field {}", payload[5] >> 6);
+    };
+
+    let Some(data) = payload[PAYLOAD_HEADER_LEN..].get(..length) else {
+        bail!("Payload shorter than signalled length {length}");
+    };
+
+    let mut r = BitReader::endian(data, BigEndian);
+    let mut packets = Vec::with_capacity(anc_count as usize);
+    for _ in 0..anc_count {
+        packets.push(AncPacket::read_rfc8331(&mut r)?);
+    }
+
+    Ok((field, packets))
+}
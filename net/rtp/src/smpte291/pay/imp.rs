@@ -0,0 +1,336 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpsmpte291pay
+ * @see_also: rtpsmpte291depay, st2038ancmux, cctost2038anc
+ *
+ * Payload SMPTE ST 291-1 ancillary data into RTP packets as per [RFC 8331][rfc-8331], which is
+ * also used by SMPTE ST 2110-40.
+ *
+ * The ancillary data can either be provided in SMPTE ST 2038 format, e.g. from `st2038ancmux`,
+ * or as CEA-708 CDP, which is then sent on line 9 of the luma channel as per SMPTE ST 334-1.
+ *
+ * All ANC packets of a frame or field are collected until a buffer with the `MARKER` flag or a
+ * buffer with a different timestamp arrives, and are then sent in as few RTP packets as
+ * possible. The RTP marker bit is set on the last packet of each frame or field.
+ *
+ * Input buffers that are flagged as a single field of an interlaced frame via the video buffer
+ * flags are signalled as such in the RTP packets.
+ *
+ * [rfc-8331]: https://www.rfc-editor.org/rfc/rfc8331.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 filesrc location=captions.mcc ! mccparse ! cctost2038anc ! st2038ancmux ! rtpsmpte291pay ! udpsink host=127.0.0.1 port=5004
+ * ]| This will convert closed captions from an MCC file to ST 2038 ancillary data, payload it
+ * as RTP and send it out via UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+use std::sync::LazyLock;
+
+use crate::{
+    basepay::{PacketToBufferRelation, RtpBasePay2Ext},
+    smpte291::{
+        write_payload, AncPacket, Field, CEA708_CDP_DID_SDID, CEA708_CDP_LINE, PAYLOAD_HEADER_LEN,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Format {
+    #[default]
+    St2038,
+    Cea708Cdp,
+}
+
+/// ANC packets of the current frame or field.
+struct PendingFrame {
+    pts: Option<gst::ClockTime>,
+    field: Field,
+    packets: Vec<AncPacket>,
+    first_id: u64,
+    last_id: u64,
+}
+
+#[derive(Default)]
+struct State {
+    format: Format,
+    pending_frame: Option<PendingFrame>,
+}
+
+#[derive(Default)]
+pub struct RtpSmpte291Pay {
+    state: AtomicRefCell<State>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpsmpte291pay",
+        gst::DebugColorFlags::empty(),
+        Some("RTP SMPTE ST 291 Ancillary Data Payloader"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpSmpte291Pay {
+    const NAME: &'static str = "GstRtpSmpte291Pay";
+    type Type = super::RtpSmpte291Pay;
+    type ParentType = crate::basepay::RtpBasePay2;
+}
+
+impl ObjectImpl for RtpSmpte291Pay {}
+
+impl GstObjectImpl for RtpSmpte291Pay {}
+
+impl ElementImpl for RtpSmpte291Pay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP SMPTE ST 291 Ancillary Data Payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload SMPTE ST 291 ancillary data into RTP packets (RFC 8331)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder_full()
+                    .structure(gst::Structure::new_empty("meta/x-st-2038"))
+                    .structure(
+                        gst::Structure::builder("closedcaption/x-cea-708")
+                            .field("format", "cdp")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("encoding-name", "SMPTE291")
+                    .field("clock-rate", 90_000i32)
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpSmpte291Pay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &[];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let s = caps.structure(0).unwrap();
+
+        let format = if s.name() == "closedcaption/x-cea-708" {
+            Format::Cea708Cdp
+        } else {
+            Format::St2038
+        };
+
+        self.obj().set_src_caps(
+            &gst::Caps::builder("application/x-rtp")
+                .field("media", "video")
+                .field("encoding-name", "SMPTE291")
+                .field("clock-rate", 90_000i32)
+                .build(),
+        );
+
+        self.state.borrow_mut().format = format;
+
+        true
+    }
+
+    fn drain(&self) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.borrow_mut();
+        self.send_pending_frame(&mut state)
+    }
+
+    fn flush(&self) {
+        let mut state = self.state.borrow_mut();
+        state.pending_frame = None;
+    }
+
+    fn handle_buffer(
+        &self,
+        buffer: &gst::Buffer,
+        id: u64,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.borrow_mut();
+
+        let pts = buffer.pts();
+        let field = Field::from_buffer(buffer);
+
+        // Buffers with a different timestamp or field belong to the next frame or field
+        if state
+            .pending_frame
+            .as_ref()
+            .is_some_and(|frame| frame.pts != pts || frame.field != field)
+        {
+            self.send_pending_frame(&mut state)?;
+        }
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, imp = self, "Can't map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        let res = match state.format {
+            Format::St2038 => AncPacket::parse_st2038(&map),
+            Format::Cea708Cdp => AncPacket::new(
+                false,
+                CEA708_CDP_LINE,
+                0,
+                CEA708_CDP_DID_SDID.0,
+                CEA708_CDP_DID_SDID.1,
+                &map,
+            )
+            .map(|packet| vec![packet]),
+        };
+        let packets = match res {
+            Ok(packets) => packets,
+            Err(err) => {
+                gst::warning!(CAT, imp = self, "Failed to parse ancillary data: {err:#}");
+                Vec::new()
+            }
+        };
+        drop(map);
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Queueing {} ANC packets for {field:?} at {}",
+            packets.len(),
+            pts.display(),
+        );
+
+        if let Some(frame) = state.pending_frame.as_mut() {
+            frame.packets.extend(packets);
+            frame.last_id = id;
+        } else if packets.is_empty() {
+            self.obj().drop_buffers(id..=id);
+        } else {
+            state.pending_frame = Some(PendingFrame {
+                pts,
+                field,
+                packets,
+                first_id: id,
+                last_id: id,
+            });
+        }
+
+        // The last buffer of a frame or field is marked
+        if buffer.flags().contains(gst::BufferFlags::MARKER) {
+            self.send_pending_frame(&mut state)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+impl RtpSmpte291Pay {
+    fn send_pending_frame(&self, state: &mut State) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let Some(frame) = state.pending_frame.take() else {
+            return Ok(gst::FlowSuccess::Ok);
+        };
+
+        if frame.packets.is_empty() {
+            self.obj().drop_buffers(..=frame.last_id);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let max_payload_size = self.obj().max_payload_size() as usize;
+
+        let mut payload = Vec::with_capacity(max_payload_size);
+        let mut packets = frame.packets.as_slice();
+        while !packets.is_empty() {
+            // Fill the packet with as many complete ANC packets as fit
+            let mut size = PAYLOAD_HEADER_LEN;
+            let mut count = 0;
+            for packet in packets.iter().take(u8::MAX as usize) {
+                if size + packet.rfc8331_len() > max_payload_size {
+                    break;
+                }
+                size += packet.rfc8331_len();
+                count += 1;
+            }
+
+            if count == 0 {
+                gst::element_imp_error!(
+                    self,
+                    gst::LibraryError::Settings,
+                    ("Configured MTU is too small"),
+                    [
+                        "ANC packet of {} bytes does not fit into maximum payload size {max_payload_size}",
+                        packets[0].rfc8331_len()
+                    ]
+                );
+                return Err(gst::FlowError::Error);
+            }
+
+            let (current, rest) = packets.split_at(count);
+            packets = rest;
+
+            payload.clear();
+            write_payload(frame.field, current, &mut payload).map_err(|err| {
+                gst::error!(CAT, imp = self, "Failed to write payload: {err:#}");
+                gst::FlowError::Error
+            })?;
+
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Sending packet with {count} ANC packets of {} bytes",
+                payload.len()
+            );
+
+            self.obj().queue_packet(
+                PacketToBufferRelation::Ids(frame.first_id..=frame.last_id),
+                rtp_types::RtpPacketBuilder::new()
+                    .marker_bit(packets.is_empty())
+                    .payload(payload.as_slice()),
+            )?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
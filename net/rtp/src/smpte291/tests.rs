@@ -0,0 +1,214 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::smpte291::{parse_payload, write_payload, AncPacket, Field};
+use crate::tests::{run_test_pipeline_and_validate_data, ExpectedBuffer, ExpectedPacket, Source};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtpsmpte291 test");
+    });
+}
+
+const NUM_FRAMES: usize = 3;
+
+fn cdp_data(n: usize) -> Vec<u8> {
+    (0..10).map(|k| (n * 17 + k) as u8).collect()
+}
+
+fn anc_packets(n: usize) -> [AncPacket; 2] {
+    [
+        AncPacket::new(false, 9, 0, 0x61, 0x01, &cdp_data(n)).unwrap(),
+        AncPacket::new(true, 11, 20, 0x41, 0x05, &[n as u8, 0x80, 0xff]).unwrap(),
+    ]
+}
+
+fn st2038_data(packet: &AncPacket) -> Vec<u8> {
+    let mut data = Vec::new();
+    packet.write_st2038(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn test_anc_packet_roundtrip() {
+    let packets = anc_packets(1);
+
+    // 32 bit header and 14 / 7 words of 10 bits, padded to 32 bits
+    assert_eq!(packets[0].rfc8331_len(), 24);
+    assert_eq!(packets[1].rfc8331_len(), 16);
+
+    // Checksum is the 9 bit sum of DID, SDID, data count and user data words
+    let sum = packets[1].did
+        + packets[1].sdid
+        + 0x103
+        + packets[1].user_data.iter().copied().sum::<u16>();
+    assert_eq!(packets[1].checksum & 0x1ff, sum & 0x1ff);
+    assert_eq!(packets[1].checksum >> 9, !(packets[1].checksum >> 8) & 0x01);
+
+    let mut st2038 = st2038_data(&packets[0]);
+    st2038.extend(st2038_data(&packets[1]));
+    assert_eq!(st2038.len(), 22 + 13);
+    // Stuffing bytes at the end are ignored
+    st2038.extend([0xff; 4]);
+    assert_eq!(AncPacket::parse_st2038(&st2038).unwrap(), packets);
+
+    let mut payload = Vec::new();
+    write_payload(Field::Second, &packets, &mut payload).unwrap();
+    assert_eq!(payload.len(), 8 + 24 + 16);
+    assert_eq!(&payload[..8], &[0x00, 0x00, 0x00, 40, 2, 0xc0, 0x00, 0x00]);
+    assert_eq!(
+        parse_payload(&payload).unwrap(),
+        (Field::Second, packets.to_vec())
+    );
+
+    // Truncated payload
+    assert!(parse_payload(&payload[..40]).is_err());
+}
+
+#[test]
+fn test_smpte291_st2038() {
+    init();
+
+    let frame_duration = gst::ClockTime::from_mseconds(40);
+    let caps = gst::Caps::builder("meta/x-st-2038").build();
+
+    let mut buffers = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        let data = anc_packets(n)
+            .iter()
+            .flat_map(st2038_data)
+            .collect::<Vec<_>>();
+        let mut buffer = gst::Buffer::from_mut_slice(data);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(frame_duration * n as u64);
+            buffer.set_flags(gst::BufferFlags::MARKER);
+        }
+        buffers.push(buffer);
+    }
+
+    let mut expected_pay = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        expected_pay.push(vec![ExpectedPacket::builder()
+            .pts(frame_duration * n as u64)
+            .size(12 + 8 + 24 + 16)
+            .flags(if n == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::MARKER
+            })
+            .rtp_time((n * 3600) as u32)
+            .marker_bit(true)
+            .build()]);
+    }
+
+    let mut expected_depay = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        expected_depay.push(vec![
+            ExpectedBuffer::builder()
+                .pts(frame_duration * n as u64)
+                .size(22)
+                .flags(if n == 0 {
+                    gst::BufferFlags::DISCONT
+                } else {
+                    gst::BufferFlags::empty()
+                })
+                .build(),
+            ExpectedBuffer::builder()
+                .pts(frame_duration * n as u64)
+                .size(13)
+                .flags(gst::BufferFlags::MARKER)
+                .build(),
+        ]);
+    }
+
+    run_test_pipeline_and_validate_data(
+        Source::Buffers(caps, buffers),
+        "rtpsmpte291pay",
+        "rtpsmpte291depay",
+        expected_pay,
+        expected_depay,
+        |data, i, j| {
+            if data != st2038_data(&anc_packets(i)[j]) {
+                anyhow::bail!("Unexpected data for ANC packet {j} of frame {i}");
+            }
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn test_smpte291_cdp() {
+    init();
+
+    let frame_duration = gst::ClockTime::from_mseconds(40);
+    let caps = gst::Caps::builder("closedcaption/x-cea-708")
+        .field("format", "cdp")
+        .field("framerate", gst::Fraction::new(25, 1))
+        .build();
+
+    // No marker flags on the input so every frame is only sent once the next one starts
+    let mut buffers = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        let mut buffer = gst::Buffer::from_mut_slice(cdp_data(n));
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(frame_duration * n as u64);
+        }
+        buffers.push(buffer);
+    }
+
+    let mut expected_pay = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        expected_pay.push(vec![ExpectedPacket::builder()
+            .pts(frame_duration * n as u64)
+            .size(12 + 8 + 24)
+            .flags(if n == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::MARKER
+            })
+            .rtp_time((n * 3600) as u32)
+            .marker_bit(true)
+            .build()]);
+    }
+
+    let mut expected_depay = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(frame_duration * n as u64)
+            .size(22)
+            .flags(if n == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::MARKER
+            })
+            .build()]);
+    }
+
+    run_test_pipeline_and_validate_data(
+        Source::Buffers(caps, buffers),
+        "rtpsmpte291pay",
+        "rtpsmpte291depay",
+        expected_pay,
+        expected_depay,
+        |data, i, _| {
+            if data != st2038_data(&anc_packets(i)[0]) {
+                anyhow::bail!("Unexpected data for frame {i}");
+            }
+
+            Ok(())
+        },
+    );
+}
@@ -0,0 +1,158 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpspeexdepay2
+ * @see_also: rtpspeexpay2, speexenc, speexdec
+ *
+ * Extracts Speex encoded audio from RTP packets as per [RFC 5574][rfc-5574].
+ *
+ * As the identification and comment headers are not transmitted over RTP, they are constructed
+ * from the RTP caps and placed in the `streamheader` field of the output caps.
+ *
+ * [rfc-5574]: https://www.rfc-editor.org/rfc/rfc5574.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc caps='application/x-rtp, media=audio, clock-rate=16000, encoding-name=SPEEX, payload=96' ! rtpjitterbuffer latency=50 ! rtpspeexdepay2 ! speexdec ! audioconvert ! audioresample ! autoaudiosink
+ * ]| This will depayload an incoming RTP Speex audio stream. You can use the #rtpspeexpay2 and
+ * speexenc elements to create such an RTP stream.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, subclass::prelude::*};
+
+use std::sync::LazyLock;
+
+use crate::{
+    basedepay::RtpBaseDepay2Ext,
+    speex::{comment_header, identification_header},
+};
+
+#[derive(Default)]
+pub struct RtpSpeexDepay;
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpspeexdepay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP Speex Depayloader"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpSpeexDepay {
+    const NAME: &'static str = "GstRtpSpeexDepay2";
+    type Type = super::RtpSpeexDepay;
+    type ParentType = crate::basedepay::RtpBaseDepay2;
+}
+
+impl ObjectImpl for RtpSpeexDepay {}
+
+impl GstObjectImpl for RtpSpeexDepay {}
+
+impl ElementImpl for RtpSpeexDepay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP Speex Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Depayload Speex Audio from RTP packets (RFC 5574)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "audio")
+                    .field("encoding-name", "SPEEX")
+                    .field("clock-rate", gst::IntRange::new(6000i32, 48000))
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("audio/x-speex").build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpSpeexDepay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["audio"];
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let s = caps.structure(0).unwrap();
+
+        let Some(rate) = s.get::<i32>("clock-rate").ok().filter(|&rate| rate > 0) else {
+            gst::error!(CAT, imp = self, "No valid clock-rate in caps {caps:?}");
+            return false;
+        };
+
+        let mut ident_buffer = gst::Buffer::from_mut_slice(identification_header(rate as u32));
+        ident_buffer
+            .get_mut()
+            .unwrap()
+            .set_flags(gst::BufferFlags::HEADER);
+        let mut comment_buffer = gst::Buffer::from_mut_slice(comment_header());
+        comment_buffer
+            .get_mut()
+            .unwrap()
+            .set_flags(gst::BufferFlags::HEADER);
+
+        let src_caps = gst::Caps::builder("audio/x-speex")
+            .field("rate", rate)
+            .field("channels", 1i32)
+            .field(
+                "streamheader",
+                gst::Array::new([ident_buffer, comment_buffer]),
+            )
+            .build();
+
+        self.obj().set_src_caps(&src_caps);
+
+        true
+    }
+
+    fn handle_packet(
+        &self,
+        packet: &crate::basedepay::Packet,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut buffer = packet.payload_buffer();
+
+        // mark start of talkspurt with RESYNC
+        if packet.marker_bit() {
+            buffer
+                .get_mut()
+                .unwrap()
+                .set_flags(gst::BufferFlags::RESYNC);
+        }
+
+        gst::trace!(CAT, imp = self, "Finishing buffer {buffer:?}");
+
+        self.obj().queue_buffer(packet.into(), buffer)
+    }
+}
@@ -0,0 +1,70 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+pub mod depay;
+pub mod pay;
+
+#[cfg(test)]
+mod tests;
+
+/// Speex mode for the given sample rate: narrowband, wideband or ultra-wideband.
+pub(crate) fn mode_for_rate(rate: u32) -> u32 {
+    if rate > 25000 {
+        2
+    } else if rate > 12500 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Creates the identification header that decoders expect as first packet.
+///
+/// RTP only transports the raw Speex frames so the header is constructed from the RTP caps.
+pub(crate) fn identification_header(rate: u32) -> Vec<u8> {
+    let mode = mode_for_rate(rate);
+
+    let mut header = Vec::with_capacity(80);
+    header.extend_from_slice(b"Speex   ");
+    let mut version = [0u8; 20];
+    version[..6].copy_from_slice(b"1.2rc1");
+    header.extend_from_slice(&version);
+
+    for v in [
+        1i32,        // speex_version_id
+        80,          // header_size
+        rate as i32, // rate
+        mode as i32, // mode
+        4,           // mode_bitstream_version
+        1,           // nb_channels
+        -1,          // bitrate
+        160 << mode, // frame_size
+        0,           // vbr
+        1,           // frames_per_packet
+        0,           // extra_headers
+        0,           // reserved1
+        0,           // reserved2
+    ] {
+        header.extend_from_slice(&v.to_le_bytes());
+    }
+
+    header
+}
+
+/// Creates a comment header without any user comments.
+pub(crate) fn comment_header() -> Vec<u8> {
+    const VENDOR: &[u8] = b"GStreamer rtpspeexdepay2";
+
+    let mut header = Vec::with_capacity(8 + VENDOR.len());
+    header.extend_from_slice(&(VENDOR.len() as u32).to_le_bytes());
+    header.extend_from_slice(VENDOR);
+    header.extend_from_slice(&0u32.to_le_bytes());
+
+    header
+}
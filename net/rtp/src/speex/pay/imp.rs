@@ -0,0 +1,275 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpspeexpay2
+ * @see_also: rtpspeexdepay2, speexenc, speexdec
+ *
+ * Payloads Speex encoded audio into RTP packets as per [RFC 5574][rfc-5574].
+ *
+ * Each input buffer is sent as a single RTP packet. The identification and comment headers of
+ * the stream are not transmitted.
+ *
+ * [rfc-5574]: https://www.rfc-editor.org/rfc/rfc5574.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 audiotestsrc wave=ticks ! audio/x-raw,rate=16000 ! speexenc ! rtpspeexpay2 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will encode an audio test signal as Speex and payload it as RTP and send it out
+ * as UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+
+use std::sync::{LazyLock, Mutex};
+
+use crate::{
+    audio_discont::{AudioDiscont, AudioDiscontConfiguration},
+    basepay::{PacketToBufferRelation, RtpBasePay2Ext, TimestampOffset},
+};
+
+#[derive(Default)]
+struct State {
+    rate: u32,
+    audio_discont: AudioDiscont,
+}
+
+#[derive(Default, Clone)]
+struct Settings {
+    audio_discont: AudioDiscontConfiguration,
+}
+
+#[derive(Default)]
+pub struct RtpSpeexPay {
+    state: AtomicRefCell<State>,
+    settings: Mutex<Settings>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpspeexpay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP Speex Payloader"),
+    )
+});
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpSpeexPay {
+    const NAME: &'static str = "GstRtpSpeexPay2";
+    type Type = super::RtpSpeexPay;
+    type ParentType = crate::basepay::RtpBasePay2;
+}
+
+impl ObjectImpl for RtpSpeexPay {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> =
+            LazyLock::new(AudioDiscontConfiguration::create_pspecs);
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        if self
+            .settings
+            .lock()
+            .unwrap()
+            .audio_discont
+            .set_property(value, pspec)
+        {
+            return;
+        }
+
+        unimplemented!();
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        if let Some(value) = self.settings.lock().unwrap().audio_discont.property(pspec) {
+            return value;
+        }
+
+        unimplemented!();
+    }
+}
+
+impl GstObjectImpl for RtpSpeexPay {}
+
+impl ElementImpl for RtpSpeexPay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP Speex Payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload Speex Audio into RTP packets (RFC 5574)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("audio/x-speex")
+                    .field("rate", gst::IntRange::new(6000i32, 48000))
+                    .field("channels", 1i32)
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "audio")
+                    .field("encoding-name", "SPEEX")
+                    .field("clock-rate", gst::IntRange::new(6000i32, 48000))
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpSpeexPay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["audio"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let s = caps.structure(0).unwrap();
+
+        let Some(rate) = s.get::<i32>("rate").ok().filter(|&rate| rate > 0) else {
+            gst::error!(CAT, imp = self, "No valid rate in caps {caps:?}");
+            return false;
+        };
+
+        let src_caps = gst::Caps::builder("application/x-rtp")
+            .field("media", "audio")
+            .field("encoding-name", "SPEEX")
+            .field("clock-rate", rate)
+            .build();
+
+        self.obj().set_src_caps(&src_caps);
+
+        self.state.borrow_mut().rate = rate as u32;
+
+        true
+    }
+
+    fn flush(&self) {
+        self.state.borrow_mut().audio_discont.reset();
+    }
+
+    fn handle_buffer(
+        &self,
+        buffer: &gst::Buffer,
+        id: u64,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let mut state = self.state.borrow_mut();
+
+        // The identification and comment headers are not transmitted over RTP
+        if buffer.flags().contains(gst::BufferFlags::HEADER) {
+            gst::trace!(CAT, imp = self, "Dropping header buffer {buffer:?}");
+            self.obj().drop_buffers(..=id);
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, imp = self, "Can't map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        if map.len() > self.obj().max_payload_size() as usize {
+            gst::error!(
+                CAT,
+                imp = self,
+                "Speex packet of {} bytes does not fit into the MTU",
+                map.len()
+            );
+            return Err(gst::FlowError::Error);
+        }
+
+        let pts = buffer.pts().unwrap();
+        let rate = state.rate;
+
+        // Speex frames are 20ms long, which is used if the buffer has no duration
+        let num_samples = buffer
+            .duration()
+            .and_then(|duration| {
+                duration
+                    .nseconds()
+                    .mul_div_round(rate as u64, *gst::ClockTime::SECOND)
+            })
+            .map(|num_samples| num_samples as usize)
+            .unwrap_or(rate as usize / 50);
+
+        let discont = state.audio_discont.process_input(
+            &settings.audio_discont,
+            buffer.flags().contains(gst::BufferFlags::DISCONT),
+            rate,
+            pts,
+            num_samples,
+        );
+        if discont {
+            gst::debug!(CAT, imp = self, "Resyncing because of discontinuity");
+            state.audio_discont.resync(pts, num_samples);
+        }
+
+        // Mark the start of a talkspurt / discontinuity
+        let next_out_offset = state.audio_discont.next_output_offset();
+        let marker = next_out_offset.is_none();
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Queueing packet of {} bytes with marker {marker}",
+            map.len()
+        );
+
+        self.obj().queue_packet(
+            PacketToBufferRelation::IdsWithOffset {
+                ids: id..=id,
+                timestamp_offset: match next_out_offset {
+                    Some(next_out_offset) => TimestampOffset::Rtp(next_out_offset),
+                    None => TimestampOffset::Pts(gst::ClockTime::ZERO),
+                },
+            },
+            rtp_types::RtpPacketBuilder::new()
+                .marker_bit(marker)
+                .payload(map.as_slice()),
+        )?;
+
+        state.audio_discont.process_output(num_samples);
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
@@ -0,0 +1,162 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::tests::{run_test_pipeline, ExpectedBuffer, ExpectedPacket, Source};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtpspeex test");
+    });
+}
+
+/// Creates 20ms Speex wideband packets, preceded by the identification and comment headers.
+fn speex_buffers(num_buffers: usize, jump_after: Option<u64>) -> (gst::Caps, Vec<gst::Buffer>) {
+    let caps = gst::Caps::builder("audio/x-speex")
+        .field("rate", 16000i32)
+        .field("channels", 1i32)
+        .build();
+
+    let mut buffers = Vec::with_capacity(num_buffers + 2);
+    for header in [super::identification_header(16000), super::comment_header()] {
+        let mut buffer = gst::Buffer::from_mut_slice(header);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(gst::ClockTime::ZERO);
+            buffer.set_flags(gst::BufferFlags::HEADER);
+        }
+        buffers.push(buffer);
+    }
+
+    let mut pos = 0;
+    for _ in 0..num_buffers {
+        let mut buffer = gst::Buffer::with_size(70).unwrap();
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(gst::ClockTime::from_mseconds(pos));
+            buffer.set_duration(gst::ClockTime::from_mseconds(20));
+        }
+        buffers.push(buffer);
+
+        pos += 20;
+        if Some(pos) == jump_after {
+            pos += 10_000;
+        }
+    }
+
+    (caps, buffers)
+}
+
+#[test]
+fn test_speex() {
+    init();
+
+    let (caps, buffers) = speex_buffers(100, None);
+    let pay = "rtpspeexpay2";
+    let depay = "rtpspeexdepay2";
+
+    let mut expected_pay = Vec::with_capacity(100);
+    for i in 0..100 {
+        expected_pay.push(vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 20))
+            .size(82)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .pt(96)
+            .rtp_time(((i * 320) & 0xffff_ffff) as u32)
+            .marker_bit(i == 0)
+            .build()]);
+    }
+
+    let mut expected_depay = Vec::with_capacity(100);
+    for i in 0..100 {
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(gst::ClockTime::from_mseconds(i * 20))
+            .size(70)
+            .flags(if i == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::RESYNC
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .build()]);
+    }
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
+
+#[test]
+fn test_speex_discont() {
+    init();
+
+    // First 5 buffers are normal, then a 10s jump
+    let (caps, buffers) = speex_buffers(10, Some(100));
+    let pay = "rtpspeexpay2 discont-wait=25000000";
+    let depay = "rtpspeexdepay2";
+
+    let mut expected_pay = Vec::with_capacity(10);
+    let mut pos = 0;
+    for _ in 0..10 {
+        expected_pay.push(vec![ExpectedPacket::builder()
+            .pts(gst::ClockTime::from_mseconds(pos))
+            .size(82)
+            .flags(if pos == 0 {
+                gst::BufferFlags::DISCONT | gst::BufferFlags::MARKER
+            } else if pos == 10_100 {
+                gst::BufferFlags::MARKER
+            } else {
+                gst::BufferFlags::empty()
+            })
+            .pt(96)
+            .rtp_time(((pos * 16) & 0xffff_ffff) as u32)
+            .marker_bit(pos == 0 || pos == 10_100)
+            .build()]);
+
+        pos += 20;
+        if pos == 100 {
+            pos += 10_000;
+        }
+    }
+
+    let mut expected_depay = Vec::with_capacity(10);
+    for packets in &expected_pay {
+        for packet in packets {
+            expected_depay.push(vec![ExpectedBuffer::builder()
+                .pts(packet.pts)
+                .size(70)
+                .flags(if packet.pts.is_zero() {
+                    gst::BufferFlags::DISCONT | gst::BufferFlags::RESYNC
+                } else if packet.flags.contains(gst::BufferFlags::MARKER) {
+                    gst::BufferFlags::RESYNC
+                } else {
+                    gst::BufferFlags::empty()
+                })
+                .build()]);
+        }
+    }
+
+    run_test_pipeline(
+        Source::Buffers(caps, buffers),
+        pay,
+        depay,
+        expected_pay,
+        expected_depay,
+    );
+}
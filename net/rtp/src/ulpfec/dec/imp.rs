@@ -0,0 +1,477 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpulpfecdec2
+ * @see_also: rtpulpfecenc2, rtpreddec2, rtprecv
+ *
+ * Recover lost packets from generic forward error correction packets as per [RFC 5109][rfc-5109].
+ *
+ * FEC packets with the configured payload type are consumed and media packets are forwarded in
+ * sequence number order. When a gap in the sequence numbers is detected, the following packets
+ * are held back until either the missing packets were recovered or the FEC packets for them are
+ * known to be lost.
+ *
+ * As ULPFEC packets share the sequence number space with the media packets, the element is
+ * placed after `rtprecv` (and `rtpreddec2` if RED is used) so that the jitterbuffer sees the
+ * complete sequence and the input is ordered.
+ *
+ * [rfc-5109]: https://www.rfc-editor.org/rfc/rfc5109.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc port=5004 caps='application/x-rtp,media=video,payload=122,clock-rate=90000,encoding-name=RED' ! rtprecv name=rtprecv rtp-id=0 latency=200 \
+ *     rtprecv. ! rtpreddec2 pt-map='application/x-rtp2-pt-map,96=(GstCaps)"application/x-rtp,media=video,payload=96,clock-rate=90000,encoding-name=VP8"' ! rtpulpfecdec2 pt=123 ! rtpvp8depay2 ! vp8dec ! videoconvert ! autovideosink
+ * ]| This will receive a VP8 stream with ULPFEC protection in RED encapsulation.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, prelude::*, subclass::prelude::*};
+use std::sync::{LazyLock, Mutex};
+
+use crate::fec::{packet_seqnum, Decoder};
+use crate::ulpfec::{parse_fec_payload, MAX_PROTECTED_PACKETS};
+use crate::utils::seqnum_distance;
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpulpfecdec2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP ULPFEC Decoder"),
+    )
+});
+
+const DEFAULT_PT: u32 = 255;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    pt: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { pt: DEFAULT_PT }
+    }
+}
+
+struct HeldPacket {
+    seqnum: u16,
+    timestamp: u32,
+    buffer: gst::Buffer,
+}
+
+#[derive(Default)]
+struct State {
+    decoder: Decoder,
+    /// Next expected sequence number.
+    next_seqnum: Option<u16>,
+    /// Sequence numbers that are missing, in order.
+    missing: Vec<u16>,
+    /// Packets held back after a gap.
+    held: Vec<HeldPacket>,
+    /// RTP timestamp and PTS of the last forwarded packet.
+    last_output: Option<(u32, Option<gst::ClockTime>)>,
+}
+
+impl State {
+    /// Tracks the sequence number of any incoming packet, media or FEC.
+    ///
+    /// Returns `false` if the packet is older than the current position and was not missing.
+    fn track_seqnum(&mut self, seqnum: u16) -> bool {
+        let Some(next_seqnum) = self.next_seqnum else {
+            self.next_seqnum = Some(seqnum.wrapping_add(1));
+            return true;
+        };
+
+        let distance = seqnum_distance(seqnum, next_seqnum);
+        if distance.unsigned_abs() as usize > 2 * MAX_PROTECTED_PACKETS {
+            // Discontinuity, nothing can be recovered for the gap anyway
+            self.next_seqnum = Some(seqnum.wrapping_add(1));
+            return true;
+        }
+
+        if distance < 0 {
+            if let Some(idx) = self.missing.iter().position(|&s| s == seqnum) {
+                self.missing.remove(idx);
+                return true;
+            }
+            return false;
+        }
+
+        let mut s = next_seqnum;
+        while s != seqnum {
+            self.missing.push(s);
+            s = s.wrapping_add(1);
+        }
+        self.next_seqnum = Some(seqnum.wrapping_add(1));
+
+        true
+    }
+
+    /// PTS for a recovered packet with the given RTP timestamp.
+    fn pts_for_timestamp(&self, timestamp: u32) -> Option<gst::ClockTime> {
+        self.held
+            .iter()
+            .find(|p| p.timestamp == timestamp)
+            .map(|p| p.buffer.pts())
+            .or_else(|| {
+                self.last_output
+                    .filter(|(ts, _)| *ts == timestamp)
+                    .map(|(_, pts)| pts)
+            })
+            .flatten()
+    }
+
+    fn hold(&mut self, seqnum: u16, timestamp: u32, buffer: gst::Buffer) {
+        self.held.push(HeldPacket {
+            seqnum,
+            timestamp,
+            buffer,
+        });
+    }
+
+    /// Takes all held packets in sequence number order.
+    fn drain(&mut self) -> Vec<gst::Buffer> {
+        self.missing.clear();
+
+        let Some(reference) = self
+            .held
+            .iter()
+            .map(|p| p.seqnum)
+            .min_by(|a, b| seqnum_distance(*a, *b).cmp(&0))
+        else {
+            return vec![];
+        };
+
+        let mut held = std::mem::take(&mut self.held);
+        held.sort_by_key(|p| p.seqnum.wrapping_sub(reference));
+        if let Some(last) = held.last() {
+            self.last_output = Some((last.timestamp, last.buffer.pts()));
+        }
+
+        held.into_iter().map(|p| p.buffer).collect()
+    }
+}
+
+pub struct RtpUlpFecDec {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl RtpUlpFecDec {
+    fn sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = *self.settings.lock().unwrap();
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error!(CAT, imp = self, "Failed to map buffer readable");
+            gst::FlowError::Error
+        })?;
+
+        let rtp = match rtp_types::RtpPacket::parse(&map) {
+            Ok(rtp) => rtp,
+            Err(err) => {
+                gst::warning!(CAT, imp = self, "Dropping invalid RTP packet: {err:?}");
+                return Ok(gst::FlowSuccess::Ok);
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+
+        let seqnum = rtp.sequence_number();
+        let timestamp = rtp.timestamp();
+        let pts = buffer.pts();
+        if !state.track_seqnum(seqnum) {
+            gst::debug!(CAT, imp = self, "Dropping late packet {seqnum}");
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        let (recovered, is_fec) = if settings.pt <= 127 && rtp.payload_type() == settings.pt as u8 {
+            let Some(repair) = parse_fec_payload(rtp.payload(), rtp.ssrc()) else {
+                gst::warning!(CAT, imp = self, "Dropping invalid FEC packet {seqnum}");
+                return Ok(gst::FlowSuccess::Ok);
+            };
+
+            // If the FEC packet protects only packets after a missing packet then the FEC
+            // packets covering it were lost and the packet can't be recovered anymore
+            let first_protected = repair.seqnums[0];
+            let recovered = state.decoder.push_repair(repair);
+            if state
+                .missing
+                .iter()
+                .any(|&missing| seqnum_distance(missing, first_protected) < 0)
+            {
+                gst::debug!(
+                    CAT,
+                    imp = self,
+                    "Giving up on missing packets before {first_protected}"
+                );
+                state
+                    .missing
+                    .retain(|&missing| seqnum_distance(missing, first_protected) >= 0);
+            }
+
+            (recovered, true)
+        } else {
+            (state.decoder.push_media(&map), false)
+        };
+        drop(map);
+
+        if !is_fec {
+            if state.missing.is_empty() && state.held.is_empty() {
+                state.last_output = Some((timestamp, pts));
+                drop(state);
+                self.srcpad.push(buffer)?;
+                state = self.state.lock().unwrap();
+            } else {
+                state.hold(seqnum, timestamp, buffer);
+            }
+        }
+
+        self.handle_recovered(state, recovered, pts)
+    }
+
+    /// Holds recovered packets and forwards all packets once nothing is missing anymore.
+    fn handle_recovered(
+        &self,
+        mut state: std::sync::MutexGuard<State>,
+        recovered: Vec<Vec<u8>>,
+        fallback_pts: Option<gst::ClockTime>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        for packet in recovered {
+            let seqnum = packet_seqnum(&packet);
+            let Some(idx) = state.missing.iter().position(|&s| s == seqnum) else {
+                gst::debug!(
+                    CAT,
+                    imp = self,
+                    "Dropping recovered packet {seqnum}, too late"
+                );
+                continue;
+            };
+            state.missing.remove(idx);
+
+            gst::debug!(CAT, imp = self, "Recovered packet {seqnum}");
+
+            let timestamp = u32::from_be_bytes([packet[4], packet[5], packet[6], packet[7]]);
+            let pts = state.pts_for_timestamp(timestamp).or(fallback_pts);
+            let mut buffer = gst::Buffer::from_mut_slice(packet);
+            buffer.get_mut().unwrap().set_pts(pts);
+            state.hold(seqnum, timestamp, buffer);
+        }
+
+        if !state.missing.is_empty() && state.held.len() > 2 * MAX_PROTECTED_PACKETS {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Giving up on missing packets {:?}",
+                state.missing
+            );
+            state.missing.clear();
+        }
+
+        let buffers = if state.missing.is_empty() {
+            state.drain()
+        } else {
+            vec![]
+        };
+        drop(state);
+
+        self.push_buffers(buffers)
+    }
+
+    fn push_buffers(&self, buffers: Vec<gst::Buffer>) -> Result<gst::FlowSuccess, gst::FlowError> {
+        for buffer in buffers {
+            self.srcpad.push(buffer)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        match event.view() {
+            gst::EventView::FlushStop(_) => {
+                let mut state = self.state.lock().unwrap();
+                let mut decoder = std::mem::take(&mut state.decoder);
+                decoder.reset();
+                *state = State {
+                    decoder,
+                    ..Default::default()
+                };
+            }
+            gst::EventView::Eos(_) => {
+                let buffers = self.state.lock().unwrap().drain();
+                let _ = self.push_buffers(buffers);
+            }
+            _ => (),
+        }
+
+        gst::Pad::event_default(pad, Some(&*self.obj()), event)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpUlpFecDec {
+    const NAME: &'static str = "GstRtpUlpFecDec2";
+    type Type = super::RtpUlpFecDec;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_from_template(&templ)
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.sink_event(pad, event))
+            })
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_from_template(&templ)
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            settings: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpUlpFecDec {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("pt")
+                    .nick("Payload Type")
+                    .blurb("The payload type of FEC packets (255 = disabled)")
+                    .maximum(255)
+                    .default_value(DEFAULT_PT)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Various statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "pt" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.pt = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "pt" => {
+                let settings = self.settings.lock().unwrap();
+                settings.pt.to_value()
+            }
+            "stats" => {
+                let state = self.state.lock().unwrap();
+                let stats = state.decoder.stats();
+                gst::Structure::builder("application/x-rtp-ulpfecdec-stats")
+                    .field("fec-packets", stats.repair_packets)
+                    .field("recovered-packets", stats.recovered)
+                    .field("unrecovered-packets", stats.unrecovered)
+                    .build()
+                    .to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpUlpFecDec {}
+
+impl ElementImpl for RtpUlpFecDec {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP ULPFEC Decoder",
+                "Codec/Decoder/Network/RTP",
+                "Recovers lost packets from ULPFEC packets (RFC 5109)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let caps = gst::Caps::new_empty_simple("application/x-rtp");
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        let ret = self.parent_change_state(transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            *self.state.lock().unwrap() = State::default();
+        }
+
+        Ok(ret)
+    }
+}
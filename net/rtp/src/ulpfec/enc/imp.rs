@@ -0,0 +1,385 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpulpfecenc2
+ * @see_also: rtpulpfecdec2, rtpredenc2, rtpsend
+ *
+ * Generate generic forward error correction packets as per [RFC 5109][rfc-5109].
+ *
+ * For every group of consecutive media packets an ULPFEC packet with the configured payload
+ * type is inserted into the stream. The size of the groups is derived from the
+ * #rtpulpfecenc2:percentage property and limited to 48 packets. As ULPFEC packets share the SSRC
+ * and sequence number space with the media packets, the sequence numbers of the media packets
+ * are rewritten.
+ *
+ * The element is usually followed by `rtpredenc2` so that media and FEC packets are sent with the
+ * same payload type. Otherwise the FEC payload type has to be added to the `pt-map` of the
+ * `rtpsend` session.
+ *
+ * [rfc-5109]: https://www.rfc-editor.org/rfc/rfc5109.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 videotestsrc ! vp8enc ! rtpvp8pay2 ! rtpulpfecenc2 pt=123 percentage=20 ! rtpredenc2 pt=122 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will send a VP8 stream with 20% ULPFEC protection in RED encapsulation to localhost
+ * port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use gst::{glib, prelude::*, subclass::prelude::*};
+use rtp_types::RtpPacketBuilder;
+use std::sync::{LazyLock, Mutex};
+
+use crate::fec::{packet_ssrc, Parity, RTP_HEADER_LEN};
+use crate::ulpfec::{write_fec_payload, MAX_PROTECTED_PACKETS};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpulpfecenc2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP ULPFEC Encoder"),
+    )
+});
+
+const DEFAULT_PT: u32 = 255;
+const DEFAULT_PERCENTAGE: u32 = 0;
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    pt: u32,
+    percentage: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            pt: DEFAULT_PT,
+            percentage: DEFAULT_PERCENTAGE,
+        }
+    }
+}
+
+impl Settings {
+    /// Number of media packets protected by each FEC packet, or `None` if disabled.
+    fn group_size(&self) -> Option<usize> {
+        if self.pt > 127 || self.percentage == 0 {
+            return None;
+        }
+
+        Some((100usize.div_ceil(self.percentage as usize)).clamp(1, MAX_PROTECTED_PACKETS))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Next outgoing sequence number.
+    seqnum: Option<u16>,
+    /// SSRC of the current group.
+    ssrc: u32,
+    /// First sequence number of the current group.
+    base: u16,
+    /// Number of packets in the current group.
+    count: usize,
+    parity: Parity,
+    protected_packets: u64,
+    fec_packets: u64,
+}
+
+impl State {
+    fn reset_group(&mut self) {
+        self.count = 0;
+        self.parity = Parity::default();
+    }
+}
+
+pub struct RtpUlpFecEnc {
+    srcpad: gst::Pad,
+    sinkpad: gst::Pad,
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+}
+
+impl RtpUlpFecEnc {
+    fn sink_chain(
+        &self,
+        _pad: &gst::Pad,
+        mut buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let settings = *self.settings.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
+
+        let fec_buffer = {
+            let buffer_ref = buffer.make_mut();
+            let mut map = buffer_ref.map_writable().map_err(|_| {
+                gst::error!(CAT, imp = self, "Failed to map buffer writable");
+                gst::FlowError::Error
+            })?;
+
+            if map.len() < RTP_HEADER_LEN || map[0] >> 6 != 2 {
+                gst::warning!(CAT, imp = self, "Dropping invalid RTP packet");
+                return Ok(gst::FlowSuccess::Ok);
+            }
+
+            let seqnum = *state
+                .seqnum
+                .get_or_insert_with(|| u16::from_be_bytes([map[2], map[3]]));
+            map[2..4].copy_from_slice(&seqnum.to_be_bytes());
+            state.seqnum = Some(seqnum.wrapping_add(1));
+
+            let ssrc = packet_ssrc(&map);
+            if state.count > 0 && state.ssrc != ssrc {
+                gst::debug!(CAT, imp = self, "SSRC changed, dropping current group");
+                state.reset_group();
+            }
+
+            match settings.group_size() {
+                Some(group_size) => {
+                    if state.count == 0 {
+                        state.ssrc = ssrc;
+                        state.base = seqnum;
+                    }
+                    state.parity.add(&map);
+                    state.count += 1;
+                    state.protected_packets += 1;
+
+                    if state.count >= group_size {
+                        let payload = write_fec_payload(&state.parity, state.base, state.count);
+                        let fec_seqnum = state.seqnum.unwrap();
+                        state.seqnum = Some(fec_seqnum.wrapping_add(1));
+
+                        gst::trace!(
+                            CAT,
+                            imp = self,
+                            "Generated FEC packet {fec_seqnum} protecting {} packets starting at {}",
+                            state.count,
+                            state.base
+                        );
+
+                        let fec = RtpPacketBuilder::<&[u8], &[u8]>::new()
+                            .payload_type(settings.pt as u8)
+                            .ssrc(ssrc)
+                            .sequence_number(fec_seqnum)
+                            .timestamp(u32::from_be_bytes([map[4], map[5], map[6], map[7]]))
+                            .payload(payload.as_slice())
+                            .write_vec()
+                            .map_err(|err| {
+                                gst::error!(CAT, imp = self, "Failed to write FEC packet: {err:?}");
+                                gst::FlowError::Error
+                            })?;
+
+                        state.reset_group();
+                        state.fec_packets += 1;
+
+                        Some(fec)
+                    } else {
+                        None
+                    }
+                }
+                None => {
+                    state.reset_group();
+                    None
+                }
+            }
+        };
+        drop(state);
+
+        let (pts, dts) = (buffer.pts(), buffer.dts());
+        self.srcpad.push(buffer)?;
+
+        if let Some(fec) = fec_buffer {
+            let mut fec_buffer = gst::Buffer::from_mut_slice(fec);
+            {
+                let fec_buffer = fec_buffer.get_mut().unwrap();
+                fec_buffer.set_pts(pts);
+                fec_buffer.set_dts(dts);
+            }
+            self.srcpad.push(fec_buffer)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        gst::log!(CAT, obj = pad, "Handling event {event:?}");
+
+        if let gst::EventView::FlushStop(_) = event.view() {
+            self.state.lock().unwrap().reset_group();
+        }
+
+        gst::Pad::event_default(pad, Some(&*self.obj()), event)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpUlpFecEnc {
+    const NAME: &'static str = "GstRtpUlpFecEnc2";
+    type Type = super::RtpUlpFecEnc;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_from_template(&templ)
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(parent, || false, |this| this.sink_event(pad, event))
+            })
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_from_template(&templ)
+            .flags(gst::PadFlags::PROXY_CAPS | gst::PadFlags::PROXY_ALLOCATION)
+            .build();
+
+        Self {
+            srcpad,
+            sinkpad,
+            settings: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for RtpUlpFecEnc {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("pt")
+                    .nick("Payload Type")
+                    .blurb("The payload type of FEC packets (255 = disabled)")
+                    .maximum(255)
+                    .default_value(DEFAULT_PT)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("percentage")
+                    .nick("Percentage")
+                    .blurb("Amount of FEC packets in percent of the media packets (0 = disabled)")
+                    .maximum(100)
+                    .default_value(DEFAULT_PERCENTAGE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Various statistics")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "pt" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.pt = value.get().expect("type checked upstream");
+            }
+            "percentage" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.percentage = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "pt" => {
+                let settings = self.settings.lock().unwrap();
+                settings.pt.to_value()
+            }
+            "percentage" => {
+                let settings = self.settings.lock().unwrap();
+                settings.percentage.to_value()
+            }
+            "stats" => {
+                let state = self.state.lock().unwrap();
+                gst::Structure::builder("application/x-rtp-ulpfecenc-stats")
+                    .field("protected-packets", state.protected_packets)
+                    .field("fec-packets", state.fec_packets)
+                    .build()
+                    .to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for RtpUlpFecEnc {}
+
+impl ElementImpl for RtpUlpFecEnc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP ULPFEC Encoder",
+                "Codec/Encoder/Network/RTP",
+                "Generates ULPFEC packets (RFC 5109)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let caps = gst::Caps::new_empty_simple("application/x-rtp");
+
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &caps,
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        let ret = self.parent_change_state(transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            *self.state.lock().unwrap() = State::default();
+        }
+
+        Ok(ret)
+    }
+}
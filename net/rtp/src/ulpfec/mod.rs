@@ -0,0 +1,102 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Generic forward error correction (ULPFEC) as specified in RFC 5109.
+//!
+//! FEC packets are sent with the SSRC of the protected stream, usually encapsulated in RED
+//! (RFC 2198), and share the sequence number space of the media packets. Only the level 0
+//! protection is produced and used.
+
+use crate::fec::{seqnums_from_mask, Parity, RepairPacket};
+
+pub mod dec;
+pub mod enc;
+
+#[cfg(test)]
+mod tests;
+
+/// Length of the FEC header.
+const FEC_HEADER_LEN: usize = 10;
+
+/// Maximum number of consecutive media packets a single FEC packet can protect.
+pub(crate) const MAX_PROTECTED_PACKETS: usize = 48;
+
+/// Creates the payload of a FEC packet protecting `count` consecutive packets starting at `base`.
+pub(crate) fn write_fec_payload(parity: &Parity, base: u16, count: usize) -> Vec<u8> {
+    assert!((1..=MAX_PROTECTED_PACKETS).contains(&count));
+
+    let long_mask = count > 16;
+    let mask_bits = if long_mask { 48 } else { 16 };
+    let mask = ((1u64 << count) - 1) << (mask_bits - count);
+
+    let mut payload = Vec::with_capacity(FEC_HEADER_LEN + 2 + mask_bits / 8 + parity.payload.len());
+    // E bit is always zero
+    payload.push(((long_mask as u8) << 6) | (parity.first_byte & 0x3f));
+    payload.push(parity.second_byte);
+    payload.extend_from_slice(&base.to_be_bytes());
+    payload.extend_from_slice(&parity.timestamp.to_be_bytes());
+    payload.extend_from_slice(&parity.length.to_be_bytes());
+
+    // Level 0 header protecting all of every packet
+    payload.extend_from_slice(&(parity.payload.len() as u16).to_be_bytes());
+    payload.extend_from_slice(&mask.to_be_bytes()[8 - mask_bits / 8..]);
+    payload.extend_from_slice(&parity.payload);
+
+    payload
+}
+
+/// Parses the payload of a FEC packet with the given SSRC.
+///
+/// Returns `None` if the payload is invalid or protects no packets.
+pub(crate) fn parse_fec_payload(payload: &[u8], ssrc: u32) -> Option<RepairPacket> {
+    if payload.len() < FEC_HEADER_LEN + 4 {
+        return None;
+    }
+
+    // Extension flag is reserved for future use
+    if payload[0] & 0x80 != 0 {
+        return None;
+    }
+
+    let long_mask = payload[0] & 0x40 != 0;
+    let base = u16::from_be_bytes([payload[2], payload[3]]);
+    let timestamp = u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]);
+    let length = u16::from_be_bytes([payload[8], payload[9]]);
+
+    let level0 = &payload[FEC_HEADER_LEN..];
+    let protection_length = u16::from_be_bytes([level0[0], level0[1]]) as usize;
+    let (mask, mask_bits, header_len) = if long_mask {
+        if level0.len() < 8 {
+            return None;
+        }
+        let mut mask = [0u8; 8];
+        mask[2..].copy_from_slice(&level0[2..8]);
+        (u64::from_be_bytes(mask), 48, 8)
+    } else {
+        (u16::from_be_bytes([level0[2], level0[3]]) as u64, 16, 4)
+    };
+
+    let data = level0.get(header_len..header_len + protection_length)?;
+    let seqnums = seqnums_from_mask(base, mask, mask_bits).collect::<Vec<_>>();
+    if seqnums.is_empty() {
+        return None;
+    }
+
+    Some(RepairPacket {
+        ssrc,
+        seqnums,
+        parity: Parity {
+            first_byte: payload[0] & 0x3f,
+            second_byte: payload[1],
+            timestamp,
+            length,
+            payload: data.to_vec(),
+        },
+    })
+}
@@ -0,0 +1,176 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use gst_check::Harness;
+
+use crate::fec::Parity;
+use crate::ulpfec::{parse_fec_payload, write_fec_payload};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtpulpfec test");
+    });
+}
+
+fn packet(seqnum: u16, timestamp: u32, marker: bool, payload: &[u8]) -> gst::Buffer {
+    let packet = rtp_types::RtpPacketBuilder::new()
+        .payload_type(96)
+        .ssrc(0x12345678)
+        .sequence_number(seqnum)
+        .timestamp(timestamp)
+        .marker_bit(marker)
+        .payload(payload)
+        .write_vec()
+        .unwrap();
+
+    gst::Buffer::from_mut_slice(packet)
+}
+
+fn parse(buffer: &gst::Buffer) -> (u8, u16, Vec<u8>) {
+    let map = buffer.map_readable().unwrap();
+    let rtp = rtp_types::RtpPacket::parse(&map).unwrap();
+    (rtp.payload_type(), rtp.sequence_number(), map.to_vec())
+}
+
+#[test]
+fn test_fec_payload() {
+    let mut parity = Parity::default();
+    parity.add(&parse(&packet(10, 100, true, &[1, 2, 3])).2);
+
+    for count in [1, 16, 17, 48] {
+        let payload = write_fec_payload(&parity, 65530, count);
+        let repair = parse_fec_payload(&payload, 0x12345678).unwrap();
+        assert_eq!(repair.ssrc, 0x12345678);
+        assert_eq!(repair.parity.payload, parity.payload);
+        assert_eq!(repair.parity.length, parity.length);
+        assert_eq!(repair.seqnums.len(), count);
+        assert_eq!(repair.seqnums[0], 65530);
+        assert_eq!(
+            *repair.seqnums.last().unwrap(),
+            65530u16.wrapping_add(count as u16 - 1)
+        );
+    }
+
+    assert!(parse_fec_payload(&[0u8; 8], 0x12345678).is_none());
+}
+
+#[test]
+fn test_ulpfec_recovery() {
+    init();
+
+    let caps = "application/x-rtp,media=video,payload=96,clock-rate=90000,encoding-name=VP8";
+
+    let mut enc = Harness::new("rtpulpfecenc2");
+    enc.element().unwrap().set_property("pt", 123u32);
+    enc.element().unwrap().set_property("percentage", 25u32);
+    enc.set_src_caps_str(caps);
+    enc.play();
+
+    for i in 0..8u16 {
+        enc.push(packet(
+            100 + i,
+            3000 * (i as u32 / 2),
+            i % 2 == 1,
+            &vec![i as u8; 10 + i as usize],
+        ))
+        .unwrap();
+    }
+
+    // One FEC packet after every 4 media packets, all with consecutive sequence numbers
+    let mut sent = vec![];
+    while let Some(buffer) = enc.try_pull() {
+        sent.push(buffer);
+    }
+    assert_eq!(sent.len(), 10);
+    for (i, buffer) in sent.iter().enumerate() {
+        let (pt, seqnum, _) = parse(buffer);
+        assert_eq!(seqnum, 100 + i as u16);
+        assert_eq!(pt, if i == 4 || i == 9 { 123 } else { 96 });
+    }
+
+    let stats = enc.element().unwrap().property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u64>("protected-packets").unwrap(), 8);
+    assert_eq!(stats.get::<u64>("fec-packets").unwrap(), 2);
+
+    let mut dec = Harness::new("rtpulpfecdec2");
+    dec.element().unwrap().set_property("pt", 123u32);
+    dec.set_src_caps_str(caps);
+    dec.play();
+
+    // Lose one media packet of each group
+    for (i, buffer) in sent.iter().enumerate() {
+        if i == 1 || i == 8 {
+            continue;
+        }
+        dec.push(buffer.clone()).unwrap();
+    }
+
+    let expected = sent
+        .iter()
+        .map(parse)
+        .filter(|(pt, _, _)| *pt == 96)
+        .collect::<Vec<_>>();
+    let mut received = vec![];
+    while let Some(buffer) = dec.try_pull() {
+        received.push(parse(&buffer));
+    }
+    assert_eq!(received, expected);
+
+    let stats = dec.element().unwrap().property::<gst::Structure>("stats");
+    assert_eq!(stats.get::<u64>("fec-packets").unwrap(), 2);
+    assert_eq!(stats.get::<u64>("recovered-packets").unwrap(), 2);
+    assert_eq!(stats.get::<u64>("unrecovered-packets").unwrap(), 0);
+}
+
+#[test]
+fn test_ulpfec_unrecoverable() {
+    init();
+
+    let caps = "application/x-rtp,media=video,payload=96,clock-rate=90000,encoding-name=VP8";
+
+    let mut enc = Harness::new("rtpulpfecenc2");
+    enc.element().unwrap().set_property("pt", 123u32);
+    enc.element().unwrap().set_property("percentage", 50u32);
+    enc.set_src_caps_str(caps);
+    enc.play();
+
+    for i in 0..4u16 {
+        enc.push(packet(i, 0, false, &[i as u8; 4])).unwrap();
+    }
+
+    let mut sent = vec![];
+    while let Some(buffer) = enc.try_pull() {
+        sent.push(buffer);
+    }
+    assert_eq!(sent.len(), 6);
+
+    let mut dec = Harness::new("rtpulpfecdec2");
+    dec.element().unwrap().set_property("pt", 123u32);
+    dec.set_src_caps_str(caps);
+    dec.play();
+
+    // A media packet and the FEC packet of the first group are lost, the following packets are
+    // held back until the FEC packet of the second group arrives
+    for (i, buffer) in sent.iter().enumerate() {
+        if i == 1 || i == 2 {
+            continue;
+        }
+        dec.push(buffer.clone()).unwrap();
+    }
+
+    let mut received = vec![];
+    while let Some(buffer) = dec.try_pull() {
+        received.push(parse(&buffer).1);
+    }
+    assert_eq!(received, vec![0, 3, 4]);
+}
@@ -11,7 +11,7 @@ mod bool_decoder;
 pub mod depay;
 mod frame_header;
 pub mod pay;
-mod payload_descriptor;
+pub(crate) mod payload_descriptor;
 
 #[cfg(test)]
 mod tests;
@@ -10,7 +10,7 @@
 pub mod depay;
 mod frame_header;
 pub mod pay;
-mod payload_descriptor;
+pub(crate) mod payload_descriptor;
 
 #[cfg(test)]
 mod tests;
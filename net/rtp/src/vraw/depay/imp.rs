@@ -0,0 +1,387 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpvrawdepay2
+ * @see_also: rtpvrawpay2
+ *
+ * Extracts uncompressed video from RTP packets as per [RFC 4175][rfc-4175].
+ *
+ * A frame is output once its last packet, signalled by the marker bit, was received. If packets
+ * are lost, the incomplete frame is output once the first packet of the next frame arrives.
+ *
+ * For interlaced streams the two fields are combined into one interleaved frame with the first
+ * field in the even lines.
+ *
+ * [rfc-4175]: https://www.rfc-editor.org/rfc/rfc4175.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 udpsrc caps='application/x-rtp, media=video, clock-rate=90000, encoding-name=RAW, sampling=YCbCr-4:2:2, depth=(string)10, width=(string)1280, height=(string)720, colorimetry=BT709-2' ! rtpjitterbuffer latency=50 ! rtpvrawdepay2 ! videoconvert ! autovideosink
+ * ]| This will depayload an incoming RTP raw video stream. You can use the #rtpvrawpay2
+ * element to create such an RTP stream.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+use gst_video::prelude::*;
+use std::cmp;
+
+use std::sync::LazyLock;
+
+use crate::{
+    basedepay::{Packet, PacketToBufferRelation, RtpBaseDepay2Ext},
+    vraw::{colorimetry_from_sdp, parse_headers, PixelGroup, MAX_DIMENSION},
+};
+
+struct PendingFrame {
+    data: Vec<u8>,
+    /// Extended RTP timestamp of the first field.
+    ext_timestamp: u64,
+    /// Start extended seqnum.
+    start_ext_seqnum: u64,
+    /// Last extended seqnum.
+    end_ext_seqnum: u64,
+}
+
+#[derive(Default)]
+struct State {
+    info: Option<gst_video::VideoInfo>,
+    pgroup: Option<PixelGroup>,
+
+    /// Currently pending frame, if any.
+    pending_frame: Option<PendingFrame>,
+}
+
+#[derive(Default)]
+pub struct RtpVrawDepay {
+    state: AtomicRefCell<State>,
+}
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpvrawdepay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP Raw Video Depayloader"),
+    )
+});
+
+/// Parses a dimension that is either signalled as string, as in the SDP, or as integer.
+fn parse_caps_u32(s: &gst::StructureRef, name: &str) -> Option<u32> {
+    s.get::<&str>(name)
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .or_else(|| s.get::<i32>(name).ok().and_then(|v| u32::try_from(v).ok()))
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpVrawDepay {
+    const NAME: &'static str = "GstRtpVrawDepay2";
+    type Type = super::RtpVrawDepay;
+    type ParentType = crate::basedepay::RtpBaseDepay2;
+}
+
+impl ObjectImpl for RtpVrawDepay {}
+
+impl GstObjectImpl for RtpVrawDepay {}
+
+impl ElementImpl for RtpVrawDepay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP Raw Video Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Depayload raw video from RTP packets (RFC 4175)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("clock-rate", 90_000i32)
+                    .field("encoding-name", "RAW")
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst_video::VideoCapsBuilder::new()
+                    .format_list(PixelGroup::formats())
+                    .width_range(1..=MAX_DIMENSION as i32)
+                    .height_range(1..=MAX_DIMENSION as i32)
+                    .field(
+                        "interlace-mode",
+                        gst::List::new(["progressive", "interleaved"]),
+                    )
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basedepay::RtpBaseDepay2Impl for RtpVrawDepay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["video"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        let s = caps.structure(0).unwrap();
+
+        let Some(sampling) = s.get::<&str>("sampling").ok() else {
+            gst::error!(CAT, imp = self, "No sampling in caps {caps:?}");
+            return false;
+        };
+        let Some(depth) = parse_caps_u32(s, "depth") else {
+            gst::error!(CAT, imp = self, "No depth in caps {caps:?}");
+            return false;
+        };
+        let Some(pgroup) = PixelGroup::from_sampling(sampling, depth) else {
+            gst::error!(
+                CAT,
+                imp = self,
+                "Unsupported sampling {sampling} with depth {depth}"
+            );
+            return false;
+        };
+
+        let (Some(width), Some(height)) = (parse_caps_u32(s, "width"), parse_caps_u32(s, "height"))
+        else {
+            gst::error!(CAT, imp = self, "No valid width / height in caps {caps:?}");
+            return false;
+        };
+        if !(1..=MAX_DIMENSION).contains(&width) || !(1..=MAX_DIMENSION).contains(&height) {
+            gst::error!(CAT, imp = self, "Unsupported resolution {width}x{height}");
+            return false;
+        }
+
+        let mut builder = gst_video::VideoInfo::builder(pgroup.format, width, height);
+
+        if s.has_field("interlace") {
+            builder = builder.interlace_mode(gst_video::VideoInterlaceMode::Interleaved);
+        }
+
+        let colorimetry = s
+            .get::<&str>("colorimetry")
+            .ok()
+            .and_then(colorimetry_from_sdp);
+        if let Some(ref colorimetry) = colorimetry {
+            builder = builder.colorimetry(colorimetry);
+        }
+
+        if let Some(framerate_str) = s
+            .get::<&str>("a-framerate")
+            .ok()
+            .or_else(|| s.get::<&str>("x-framerate").ok())
+        {
+            // Theoretically only `.` is allowed as decimal point but thanks to C formatting
+            // functions being locale dependent, a lot of code out there puts a comma.
+            let framerate_str = framerate_str.replace(',', ".");
+            if let Some(framerate) = framerate_str
+                .parse::<f64>()
+                .ok()
+                .and_then(gst::Fraction::approximate_f64)
+            {
+                builder = builder.fps(framerate);
+            } else {
+                gst::warning!(
+                    CAT,
+                    imp = self,
+                    "Failed to parse 'a-framerate' attribute: {framerate_str}"
+                );
+            }
+        }
+
+        let info = match builder.build() {
+            Ok(info) => info,
+            Err(err) => {
+                gst::error!(CAT, imp = self, "Failed to create video info: {err}");
+                return false;
+            }
+        };
+
+        let src_caps = match info.to_caps() {
+            Ok(caps) => caps,
+            Err(err) => {
+                gst::error!(CAT, imp = self, "Failed to create caps: {err}");
+                return false;
+            }
+        };
+
+        gst::debug!(CAT, imp = self, "Setting caps {src_caps:?}");
+        self.obj().set_src_caps(&src_caps);
+
+        let mut state = self.state.borrow_mut();
+        if state.info.as_ref() != Some(&info) {
+            state.pending_frame = None;
+        }
+        state.info = Some(info);
+        state.pgroup = Some(pgroup);
+
+        true
+    }
+
+    fn drain(&self) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.borrow_mut();
+        self.finish_pending_frame(&mut state)
+    }
+
+    fn flush(&self) {
+        let mut state = self.state.borrow_mut();
+        state.pending_frame = None;
+    }
+
+    fn handle_packet(&self, packet: &Packet) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.borrow_mut();
+
+        let payload = packet.payload();
+        let Some((_ext_seqnum, headers, data_offset)) = parse_headers(payload) else {
+            gst::warning!(CAT, imp = self, "Failed to parse line headers");
+            self.obj().drop_packet(packet);
+            return Ok(gst::FlowSuccess::Ok);
+        };
+
+        gst::trace!(CAT, imp = self, "Parsed line headers {headers:?}");
+
+        // Always set when caps are set
+        let info = state.info.clone().unwrap();
+        let pgroup = state.pgroup.unwrap();
+        let interlaced = info.interlace_mode() != gst_video::VideoInterlaceMode::Progressive;
+        let second_field = interlaced && headers[0].field;
+
+        // A new frame starts with the first field, or the frame, with a new timestamp
+        if state
+            .pending_frame
+            .as_ref()
+            .is_some_and(|frame| !second_field && frame.ext_timestamp != packet.ext_timestamp())
+        {
+            gst::debug!(CAT, imp = self, "Finishing incomplete frame");
+            self.finish_pending_frame(&mut state)?;
+        }
+
+        if state.pending_frame.is_none() {
+            if second_field {
+                gst::trace!(CAT, imp = self, "Waiting for start of frame");
+                self.obj().drop_packet(packet);
+                return Ok(gst::FlowSuccess::Ok);
+            }
+
+            state.pending_frame = Some(PendingFrame {
+                data: vec![0; info.size()],
+                ext_timestamp: packet.ext_timestamp(),
+                start_ext_seqnum: packet.ext_seqnum(),
+                end_ext_seqnum: packet.ext_seqnum(),
+            });
+        }
+
+        let pending_frame = state.pending_frame.as_mut().unwrap();
+        pending_frame.end_ext_seqnum = packet.ext_seqnum();
+
+        let stride = info.stride()[0] as usize;
+        let plane_offset = info.offset()[0];
+        let line_size = pgroup.line_size(info.width());
+
+        let mut segments = &payload[data_offset..];
+        for header in &headers {
+            let length = header.length as usize;
+            if segments.len() < length {
+                gst::warning!(CAT, imp = self, "Line segment {header:?} is truncated");
+                break;
+            }
+            let (segment, rest) = segments.split_at(length);
+            segments = rest;
+
+            let line = if interlaced {
+                header.line as u32 * 2 + header.field as u32
+            } else {
+                header.line as u32
+            };
+            let offset = (header.offset as u32 / pgroup.pixels) as usize * pgroup.size;
+            if line >= info.height() || offset >= line_size {
+                gst::warning!(CAT, imp = self, "Line segment {header:?} outside the frame");
+                continue;
+            }
+
+            let length = cmp::min(length, line_size - offset);
+            let start = plane_offset + line as usize * stride + offset;
+            pending_frame.data[start..][..length].copy_from_slice(&segment[..length]);
+        }
+
+        // The marker bit is set on the last packet of the frame, or of each field
+        if packet.marker_bit() && (!interlaced || second_field) {
+            self.finish_pending_frame(&mut state)?;
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+impl RtpVrawDepay {
+    fn finish_pending_frame(&self, state: &mut State) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let Some(pending_frame) = state.pending_frame.take() else {
+            return Ok(gst::FlowSuccess::Ok);
+        };
+
+        // Always set when caps are set
+        let info = state.info.as_ref().unwrap();
+
+        let mut buffer = gst::Buffer::from_mut_slice(pending_frame.data);
+        {
+            let buffer = buffer.get_mut().unwrap();
+
+            let fps = info.fps();
+            if fps.numer() > 0 {
+                buffer.set_duration(
+                    gst::ClockTime::SECOND.mul_div_floor(fps.denom() as u64, fps.numer() as u64),
+                );
+            }
+
+            if info.interlace_mode() != gst_video::VideoInterlaceMode::Progressive {
+                buffer.set_video_flags(gst_video::VideoBufferFlags::TFF);
+            }
+        }
+
+        gst::trace!(CAT, imp = self, "Finishing buffer {buffer:?}");
+
+        self.obj().queue_buffer(
+            PacketToBufferRelation::Seqnums(
+                pending_frame.start_ext_seqnum..=pending_frame.end_ext_seqnum,
+            ),
+            buffer,
+        )
+    }
+}
@@ -0,0 +1,190 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Uncompressed video as specified in RFC 4175.
+//!
+//! Every packet starts with the 16 bit extended sequence number, followed by one or more line
+//! headers and then the data of the line segments described by these headers. Line segments
+//! always consist of complete pixel groups.
+
+pub mod depay;
+pub mod pay;
+
+#[cfg(test)]
+mod tests;
+
+/// Length of the extended sequence number at the start of every payload.
+pub(crate) const EXT_SEQNUM_LEN: usize = 2;
+/// Length of a single line header.
+pub(crate) const LINE_HEADER_LEN: usize = 6;
+/// Maximum line number and pixel offset that can be signalled in a line header.
+pub(crate) const MAX_DIMENSION: u32 = (1 << 15) - 1;
+
+/// Pixel group layout of a supported raw video format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PixelGroup {
+    pub format: gst_video::VideoFormat,
+    /// Value of the `sampling` SDP parameter.
+    pub sampling: &'static str,
+    /// Value of the `depth` SDP parameter.
+    pub depth: u32,
+    /// Size of one pixel group in bytes.
+    pub size: usize,
+    /// Number of horizontal pixels per pixel group.
+    pub pixels: u32,
+}
+
+impl PixelGroup {
+    /// All formats whose memory layout is identical to the RFC 4175 pixel groups.
+    pub const ALL: &'static [PixelGroup] = &[
+        PixelGroup {
+            format: gst_video::VideoFormat::Rgb,
+            sampling: "RGB",
+            depth: 8,
+            size: 3,
+            pixels: 1,
+        },
+        PixelGroup {
+            format: gst_video::VideoFormat::Rgba,
+            sampling: "RGBA",
+            depth: 8,
+            size: 4,
+            pixels: 1,
+        },
+        PixelGroup {
+            format: gst_video::VideoFormat::Bgr,
+            sampling: "BGR",
+            depth: 8,
+            size: 3,
+            pixels: 1,
+        },
+        PixelGroup {
+            format: gst_video::VideoFormat::Bgra,
+            sampling: "BGRA",
+            depth: 8,
+            size: 4,
+            pixels: 1,
+        },
+        PixelGroup {
+            format: gst_video::VideoFormat::Uyvy,
+            sampling: "YCbCr-4:2:2",
+            depth: 8,
+            size: 4,
+            pixels: 2,
+        },
+        PixelGroup {
+            format: gst_video::VideoFormat::Uyvp,
+            sampling: "YCbCr-4:2:2",
+            depth: 10,
+            size: 5,
+            pixels: 2,
+        },
+    ];
+
+    pub fn from_format(format: gst_video::VideoFormat) -> Option<PixelGroup> {
+        Self::ALL.iter().find(|p| p.format == format).copied()
+    }
+
+    pub fn from_sampling(sampling: &str, depth: u32) -> Option<PixelGroup> {
+        Self::ALL
+            .iter()
+            .find(|p| p.sampling == sampling && p.depth == depth)
+            .copied()
+    }
+
+    pub fn formats() -> impl Iterator<Item = gst_video::VideoFormat> {
+        Self::ALL.iter().map(|p| p.format)
+    }
+
+    pub fn is_yuv(&self) -> bool {
+        self.sampling.starts_with("YCbCr")
+    }
+
+    /// Number of bytes of one line with the given width.
+    pub fn line_size(&self, width: u32) -> usize {
+        width.div_ceil(self.pixels) as usize * self.size
+    }
+}
+
+/// Converts the colorimetry to the value of the `colorimetry` SDP parameter.
+pub(crate) fn colorimetry_to_sdp(colorimetry: &gst_video::VideoColorimetry) -> &'static str {
+    match colorimetry.matrix() {
+        gst_video::VideoColorMatrix::Bt601 => "BT601-5",
+        gst_video::VideoColorMatrix::Smpte240m => "SMPTE240M",
+        gst_video::VideoColorMatrix::Bt2020 => "BT2020",
+        _ => "BT709-2",
+    }
+}
+
+/// Converts the value of the `colorimetry` SDP parameter to a colorimetry.
+pub(crate) fn colorimetry_from_sdp(colorimetry: &str) -> Option<gst_video::VideoColorimetry> {
+    let colorimetry = match colorimetry {
+        "BT601-5" => "bt601",
+        "BT709-2" => "bt709",
+        "SMPTE240M" => "smpte240m",
+        "BT2020" => "bt2020",
+        _ => return None,
+    };
+
+    colorimetry.parse().ok()
+}
+
+/// Header describing one line segment of a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LineHeader {
+    /// Length of the segment in bytes.
+    pub length: u16,
+    /// Second field of an interlaced frame.
+    pub field: bool,
+    /// Line number inside the frame or field.
+    pub line: u16,
+    /// Offset of the first pixel of the segment inside the line.
+    pub offset: u16,
+}
+
+/// Writes the extended sequence number and the line headers of a packet.
+pub(crate) fn write_headers(ext_seqnum: u16, headers: &[LineHeader], out: &mut Vec<u8>) {
+    out.extend_from_slice(&ext_seqnum.to_be_bytes());
+    for (i, header) in headers.iter().enumerate() {
+        let continuation = i + 1 < headers.len();
+        out.extend_from_slice(&header.length.to_be_bytes());
+        out.extend_from_slice(&(((header.field as u16) << 15) | header.line).to_be_bytes());
+        out.extend_from_slice(&(((continuation as u16) << 15) | header.offset).to_be_bytes());
+    }
+}
+
+/// Parses the extended sequence number and the line headers of a packet.
+///
+/// Returns the headers and the offset of the first line segment.
+pub(crate) fn parse_headers(payload: &[u8]) -> Option<(u16, Vec<LineHeader>, usize)> {
+    let ext_seqnum = u16::from_be_bytes(payload.get(..EXT_SEQNUM_LEN)?.try_into().unwrap());
+
+    let mut headers = Vec::new();
+    let mut pos = EXT_SEQNUM_LEN;
+    loop {
+        let header = payload.get(pos..pos + LINE_HEADER_LEN)?;
+        pos += LINE_HEADER_LEN;
+
+        let length = u16::from_be_bytes([header[0], header[1]]);
+        let line = u16::from_be_bytes([header[2], header[3]]);
+        let offset = u16::from_be_bytes([header[4], header[5]]);
+        headers.push(LineHeader {
+            length,
+            field: line & 0x8000 != 0,
+            line: line & 0x7fff,
+            offset: offset & 0x7fff,
+        });
+
+        if offset & 0x8000 == 0 {
+            break;
+        }
+    }
+
+    Some((ext_seqnum, headers, pos))
+}
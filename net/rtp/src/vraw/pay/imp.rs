@@ -0,0 +1,337 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-rtpvrawpay2
+ * @see_also: rtpvrawdepay2
+ *
+ * Payload uncompressed video into RTP packets as per [RFC 4175][rfc-4175].
+ *
+ * Each packet contains as many complete pixel groups as fit into the MTU, possibly spanning
+ * multiple lines. Interlaced frames are sent as two fields, with the second field having an RTP
+ * timestamp half a frame duration later than the first one.
+ *
+ * Only formats whose memory layout is identical to the RFC 4175 pixel groups are supported,
+ * which includes 8 bit RGB and 8 / 10 bit 4:2:2 YCbCr (`UYVY` and `UYVP`).
+ *
+ * [rfc-4175]: https://www.rfc-editor.org/rfc/rfc4175.html
+ *
+ * ## Example pipeline
+ *
+ * |[
+ * gst-launch-1.0 videotestsrc ! video/x-raw,width=1280,height=720,format=UYVP ! rtpvrawpay2 ! udpsink host=127.0.0.1 port=5004
+ * ]| This will create and payload a 10 bit 4:2:2 video stream with a test pattern and send it
+ * out via UDP to localhost port 5004.
+ *
+ * Since: plugins-rs-0.14.0
+ */
+use atomic_refcell::AtomicRefCell;
+use gst::{glib, prelude::*, subclass::prelude::*};
+use smallvec::SmallVec;
+use std::cmp;
+
+use std::sync::LazyLock;
+
+use crate::{
+    basepay::{PacketToBufferRelation, RtpBasePay2Ext, TimestampOffset},
+    vraw::{
+        colorimetry_to_sdp, write_headers, LineHeader, PixelGroup, EXT_SEQNUM_LEN, LINE_HEADER_LEN,
+        MAX_DIMENSION,
+    },
+};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "rtpvrawpay2",
+        gst::DebugColorFlags::empty(),
+        Some("RTP Raw Video Payloader"),
+    )
+});
+
+#[derive(Default)]
+struct State {
+    info: Option<gst_video::VideoInfo>,
+    pgroup: Option<PixelGroup>,
+}
+
+#[derive(Default)]
+pub struct RtpVrawPay {
+    state: AtomicRefCell<State>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpVrawPay {
+    const NAME: &'static str = "GstRtpVrawPay2";
+    type Type = super::RtpVrawPay;
+    type ParentType = crate::basepay::RtpBasePay2;
+}
+
+impl ObjectImpl for RtpVrawPay {}
+
+impl GstObjectImpl for RtpVrawPay {}
+
+impl ElementImpl for RtpVrawPay {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> = LazyLock::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP Raw Video payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payload raw video into RTP packets (RFC 4175)",
+                "agent <agent@local>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: LazyLock<Vec<gst::PadTemplate>> = LazyLock::new(|| {
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst_video::VideoCapsBuilder::new()
+                    .format_list(PixelGroup::formats())
+                    .width_range(1..=MAX_DIMENSION as i32)
+                    .height_range(1..=MAX_DIMENSION as i32)
+                    .field(
+                        "interlace-mode",
+                        gst::List::new(["progressive", "interleaved"]),
+                    )
+                    .build(),
+            )
+            .unwrap();
+
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::builder("application/x-rtp")
+                    .field("media", "video")
+                    .field("encoding-name", "RAW")
+                    .field("clock-rate", 90_000i32)
+                    .build(),
+            )
+            .unwrap();
+
+            vec![src_pad_template, sink_pad_template]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl crate::basepay::RtpBasePay2Impl for RtpVrawPay {
+    const ALLOWED_META_TAGS: &'static [&'static str] = &["video"];
+
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), gst::ErrorMessage> {
+        *self.state.borrow_mut() = State::default();
+
+        Ok(())
+    }
+
+    fn set_sink_caps(&self, caps: &gst::Caps) -> bool {
+        gst::debug!(CAT, imp = self, "received caps {caps:?}");
+
+        let info = match gst_video::VideoInfo::from_caps(caps) {
+            Ok(info) => info,
+            Err(err) => {
+                gst::error!(CAT, imp = self, "Invalid caps {caps:?}: {err}");
+                return false;
+            }
+        };
+
+        let Some(pgroup) = PixelGroup::from_format(info.format()) else {
+            gst::error!(CAT, imp = self, "Unsupported format {:?}", info.format());
+            return false;
+        };
+
+        if info.width() % pgroup.pixels != 0 {
+            gst::error!(
+                CAT,
+                imp = self,
+                "Width {} is not a multiple of {} pixels",
+                info.width(),
+                pgroup.pixels
+            );
+            return false;
+        }
+
+        let mut caps_builder = gst::Caps::builder("application/x-rtp")
+            .field("media", "video")
+            .field("encoding-name", "RAW")
+            .field("clock-rate", 90_000i32)
+            .field("sampling", pgroup.sampling)
+            .field("depth", pgroup.depth.to_string())
+            .field("width", info.width().to_string())
+            .field("height", info.height().to_string());
+
+        if pgroup.is_yuv() {
+            caps_builder =
+                caps_builder.field("colorimetry", colorimetry_to_sdp(&info.colorimetry()));
+        }
+
+        if info.interlace_mode() != gst_video::VideoInterlaceMode::Progressive {
+            caps_builder = caps_builder.field("interlace", "true");
+        }
+
+        let framerate = info.fps();
+        if framerate.numer() > 0 {
+            caps_builder = caps_builder.field(
+                "a-framerate",
+                format!(
+                    "{}",
+                    (framerate.numer() as f64 / (framerate.denom() as f64))
+                ),
+            );
+        }
+
+        self.obj().set_src_caps(&caps_builder.build());
+
+        let mut state = self.state.borrow_mut();
+        state.info = Some(info);
+        state.pgroup = Some(pgroup);
+
+        true
+    }
+
+    fn handle_buffer(
+        &self,
+        buffer: &gst::Buffer,
+        id: u64,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let state = self.state.borrow();
+        // Set together with the caps
+        let info = state.info.as_ref().unwrap();
+        let pgroup = state.pgroup.unwrap();
+
+        // Line segment lengths are limited to 16 bits
+        let max_payload_size = cmp::min(self.obj().max_payload_size() as usize, u16::MAX as usize);
+        if max_payload_size < EXT_SEQNUM_LEN + LINE_HEADER_LEN + pgroup.size {
+            gst::error!(CAT, imp = self, "Too small MTU configured for stream");
+            gst::element_imp_error!(
+                self,
+                gst::LibraryError::Settings,
+                ["Too small MTU configured for stream"]
+            );
+            return Err(gst::FlowError::Error);
+        }
+
+        let frame =
+            gst_video::VideoFrameRef::from_buffer_ref_readable(buffer, info).map_err(|_| {
+                gst::element_imp_error!(
+                    self,
+                    gst::ResourceError::Read,
+                    ["Failed to map buffer readable"]
+                );
+
+                gst::FlowError::Error
+            })?;
+
+        let data = frame.plane_data(0).unwrap();
+        let stride = frame.plane_stride()[0] as usize;
+        let width = info.width();
+        let height = info.height();
+
+        let interlaced = info.interlace_mode() != gst_video::VideoInterlaceMode::Progressive;
+        let (fields, field_duration) = if interlaced {
+            let frame_duration = buffer.duration().or_else(|| {
+                let fps = info.fps();
+                if fps.numer() > 0 {
+                    gst::ClockTime::SECOND.mul_div_floor(fps.denom() as u64, fps.numer() as u64)
+                } else {
+                    None
+                }
+            });
+            (2, frame_duration.map_or(gst::ClockTime::ZERO, |d| d / 2))
+        } else {
+            (1, gst::ClockTime::ZERO)
+        };
+
+        gst::trace!(
+            CAT,
+            imp = self,
+            "Payloading frame {width}x{height} with {fields} fields"
+        );
+
+        let mut headers = SmallVec::<[LineHeader; 8]>::new();
+        let mut header_buffer = Vec::with_capacity(EXT_SEQNUM_LEN + 8 * LINE_HEADER_LEN);
+        for field in 0..fields {
+            // The first field contains the even lines, the second field the odd lines
+            let field_lines = (height + fields - 1 - field) / fields;
+
+            let mut line = 0;
+            let mut offset = 0;
+            while line < field_lines {
+                headers.clear();
+
+                let mut remaining = max_payload_size - EXT_SEQNUM_LEN;
+                while line < field_lines && remaining >= LINE_HEADER_LEN + pgroup.size {
+                    let max_pgroups = ((remaining - LINE_HEADER_LEN) / pgroup.size) as u32;
+                    let pgroups = cmp::min(max_pgroups, (width - offset) / pgroup.pixels);
+                    let length = pgroups as usize * pgroup.size;
+
+                    headers.push(LineHeader {
+                        length: length as u16,
+                        field: field == 1,
+                        line: line as u16,
+                        offset: offset as u16,
+                    });
+                    remaining -= LINE_HEADER_LEN + length;
+
+                    offset += pgroups * pgroup.pixels;
+                    if offset == width {
+                        offset = 0;
+                        line += 1;
+                    }
+                }
+
+                header_buffer.clear();
+                write_headers(0, &headers, &mut header_buffer);
+
+                let mut packet = rtp_types::RtpPacketBuilder::new()
+                    .marker_bit(line == field_lines)
+                    .payload(header_buffer.as_slice());
+
+                for header in &headers {
+                    let frame_line = header.line as usize * fields as usize + field as usize;
+                    let start = frame_line * stride
+                        + (header.offset as u32 / pgroup.pixels) as usize * pgroup.size;
+                    packet = packet.payload(&data[start..][..header.length as usize]);
+                }
+
+                gst::trace!(
+                    CAT,
+                    imp = self,
+                    "Writing packet with line headers {headers:?}"
+                );
+
+                self.obj().queue_packet(
+                    if field == 0 {
+                        id.into()
+                    } else {
+                        PacketToBufferRelation::IdsWithOffset {
+                            ids: id..=id,
+                            timestamp_offset: TimestampOffset::Pts(field_duration),
+                        }
+                    },
+                    packet,
+                )?;
+            }
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
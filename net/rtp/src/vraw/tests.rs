@@ -0,0 +1,187 @@
+//
+// Copyright (C) 2026 agent <agent@local>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v2.0.
+// If a copy of the MPL was not distributed with this file, You can obtain one at
+// <https://mozilla.org/MPL/2.0/>.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::tests::{run_test_pipeline_and_validate_data, ExpectedBuffer, ExpectedPacket, Source};
+use crate::vraw::{parse_headers, write_headers, LineHeader};
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        crate::plugin_register_static().expect("rtpvraw test");
+    });
+}
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 8;
+const NUM_FRAMES: usize = 5;
+
+fn frame_data(n: usize, size: usize) -> Vec<u8> {
+    (0..size).map(|k| (n * 31 + k) as u8).collect()
+}
+
+/// Payloads and depayloads `NUM_FRAMES` frames with `lines_per_packet` complete lines in every
+/// packet.
+fn run_vraw_test(format: &str, line_size: usize, lines_per_packet: usize, interlaced: bool) {
+    let frame_size = line_size * HEIGHT;
+    let frame_duration = gst::ClockTime::from_mseconds(40);
+
+    let caps = gst::Caps::builder("video/x-raw")
+        .field("format", format)
+        .field("width", WIDTH as i32)
+        .field("height", HEIGHT as i32)
+        .field("framerate", gst::Fraction::new(25, 1))
+        .field(
+            "interlace-mode",
+            if interlaced {
+                "interleaved"
+            } else {
+                "progressive"
+            },
+        )
+        .build();
+
+    let mut buffers = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        let mut buffer = gst::Buffer::from_mut_slice(frame_data(n, frame_size));
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(frame_duration * n as u64);
+            buffer.set_duration(frame_duration);
+        }
+        buffers.push(buffer);
+    }
+
+    let packet_size = 12 + 2 + lines_per_packet * (6 + line_size);
+    let pay = format!("rtpvrawpay2 mtu={packet_size}");
+    let depay = "rtpvrawdepay2";
+
+    let fields = if interlaced { 2 } else { 1 };
+    let packets_per_field = HEIGHT / fields / lines_per_packet;
+
+    let mut expected_pay = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        let mut packets = Vec::with_capacity(fields * packets_per_field);
+        for field in 0..fields {
+            for i in 0..packets_per_field {
+                let marker = i == packets_per_field - 1;
+                let mut flags = gst::BufferFlags::empty();
+                if n == 0 && field == 0 && i == 0 {
+                    flags |= gst::BufferFlags::DISCONT;
+                }
+                if marker {
+                    flags |= gst::BufferFlags::MARKER;
+                }
+
+                packets.push(
+                    ExpectedPacket::builder()
+                        .pts(frame_duration * n as u64 + frame_duration / 2 * field as u64)
+                        .size(packet_size)
+                        .flags(flags)
+                        .rtp_time((n * 3600 + field * 1800) as u32)
+                        .marker_bit(marker)
+                        .build(),
+                );
+            }
+        }
+        expected_pay.push(packets);
+    }
+
+    let video_flags = if interlaced {
+        gst::BufferFlags::from_bits_retain(gst_video::VideoBufferFlags::TFF.bits())
+    } else {
+        gst::BufferFlags::empty()
+    };
+
+    let mut expected_depay = Vec::with_capacity(NUM_FRAMES);
+    for n in 0..NUM_FRAMES {
+        expected_depay.push(vec![ExpectedBuffer::builder()
+            .pts(frame_duration * n as u64)
+            .duration(frame_duration)
+            .size(frame_size)
+            .flags(if n == 0 {
+                gst::BufferFlags::DISCONT | video_flags
+            } else {
+                video_flags
+            })
+            .build()]);
+    }
+
+    run_test_pipeline_and_validate_data(
+        Source::Buffers(caps, buffers),
+        &pay,
+        depay,
+        expected_pay,
+        expected_depay,
+        |data, i, _| {
+            if data != frame_data(i, frame_size) {
+                anyhow::bail!("Unexpected data for frame {i}");
+            }
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn test_line_headers() {
+    let headers = [
+        LineHeader {
+            length: 1200,
+            field: false,
+            line: 1079,
+            offset: 1600,
+        },
+        LineHeader {
+            length: 100,
+            field: true,
+            line: 0,
+            offset: 0,
+        },
+    ];
+
+    let mut data = Vec::new();
+    write_headers(0x1234, &headers, &mut data);
+    assert_eq!(
+        data,
+        [0x12, 0x34, 0x04, 0xb0, 0x04, 0x37, 0x86, 0x40, 0x00, 0x64, 0x80, 0x00, 0x00, 0x00]
+    );
+    data.extend_from_slice(&[0; 4]);
+
+    let (ext_seqnum, parsed, offset) = parse_headers(&data).unwrap();
+    assert_eq!(ext_seqnum, 0x1234);
+    assert_eq!(parsed, headers);
+    assert_eq!(offset, 14);
+
+    // Continuation bit set on the last header
+    assert!(parse_headers(&data[..8]).is_none());
+}
+
+#[test]
+fn test_vraw_uyvy() {
+    init();
+
+    run_vraw_test("UYVY", WIDTH * 2, 1, false);
+}
+
+#[test]
+fn test_vraw_uyvp_multiple_lines() {
+    init();
+
+    run_vraw_test("UYVP", WIDTH * 5 / 2, 2, false);
+}
+
+#[test]
+fn test_vraw_interlaced() {
+    init();
+
+    run_vraw_test("UYVY", WIDTH * 2, 2, true);
+}
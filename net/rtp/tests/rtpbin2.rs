@@ -9,7 +9,7 @@
 
 use std::sync::{atomic::AtomicUsize, Arc, Mutex};
 
-use gst::{prelude::*, Caps};
+use gst::{glib, prelude::*, Caps};
 use gst_check::Harness;
 use rtp_types::*;
 
@@ -173,6 +173,195 @@ fn test_send_list() {
     send_check_stats(&mut h, PACKETS_TEST_1);
 }
 
+fn send_srtp_init(with_key: bool) -> gst_check::Harness {
+    init();
+
+    let id = next_element_counter();
+
+    let elem = gst::ElementFactory::make("rtpsend")
+        .property("rtp-id", id.to_string())
+        .property("srtp", true)
+        .build()
+        .unwrap();
+    let mut h = Harness::with_element(&elem, Some("rtp_sink_0"), Some("rtp_src_0"));
+
+    let session = elem.emit_by_name::<glib::Object>("get-session", &[&0u32]);
+    session.connect("request-key", false, move |args| {
+        let ssrc = args[1].get::<u32>().unwrap();
+        assert_eq!(ssrc, TEST_SSRC);
+        let caps = with_key.then(|| {
+            Caps::builder("application/x-srtp")
+                .field("srtp-key", gst::Buffer::from_mut_slice(vec![7u8; 30]))
+                .field("srtp-cipher", "aes-128-icm")
+                .field("srtp-auth", "hmac-sha1-80")
+                .field("srtcp-cipher", "aes-128-icm")
+                .field("srtcp-auth", "hmac-sha1-80")
+                .build()
+        });
+        Some(caps.to_value())
+    });
+
+    h.play();
+
+    let caps = Caps::builder("application/x-rtp")
+        .field("media", "audio")
+        .field("payload", TEST_PT as i32)
+        .field("clock-rate", TEST_CLOCK_RATE as i32)
+        .field("encoding-name", "custom-test")
+        .build();
+    h.set_src_caps(caps);
+
+    h
+}
+
+#[test]
+fn test_send_srtp() {
+    let mut h = send_srtp_init(true);
+
+    send_push(&mut h, PACKETS_TEST_1, false);
+    for packet in PACKETS_TEST_1 {
+        let buffer = h.pull().unwrap();
+        let mapped = buffer.map_readable().unwrap();
+        let rtp = rtp_types::RtpPacket::parse(&mapped).unwrap();
+        assert_eq!(rtp.sequence_number(), packet.seq_no);
+        assert_eq!(rtp.ssrc(), TEST_SSRC);
+        // encrypted payload and 10 bytes authentication tag
+        assert_eq!(rtp.payload().len(), packet.payload_len + 10);
+        assert_ne!(
+            &rtp.payload()[..packet.payload_len],
+            &vec![4; packet.payload_len]
+        );
+    }
+
+    let caps = h.sinkpad().unwrap().current_caps().unwrap();
+    let s = caps.structure(0).unwrap();
+    assert_eq!(s.name(), "application/x-srtp");
+    assert_eq!(s.get::<i32>("payload").unwrap(), TEST_PT as i32);
+}
+
+#[test]
+fn test_send_srtp_without_key() {
+    let mut h = send_srtp_init(false);
+
+    send_push(&mut h, PACKETS_TEST_1, false);
+    // packets are never sent in the clear
+    assert!(h.try_pull().is_none());
+}
+
+/// An rtpsend harness whose session has an RTX payload type for `TEST_PT`, returned with the
+/// rtp-id of the element
+fn send_init_rtx() -> (gst_check::Harness, usize) {
+    init();
+
+    let id = next_element_counter();
+
+    let elem = gst::ElementFactory::make("rtpsend")
+        .property("rtp-id", id.to_string())
+        .build()
+        .unwrap();
+    let mut h = Harness::with_element(&elem, Some("rtp_sink_0"), Some("rtp_src_0"));
+
+    let session = elem.emit_by_name::<glib::Object>("get-session", &[&0u32]);
+    session.set_property(
+        "pt-map",
+        gst::Structure::builder("application/x-rtp2-pt-map")
+            .field(
+                "97",
+                Caps::builder("application/x-rtp")
+                    .field("media", "audio")
+                    .field("payload", 97i32)
+                    .field("clock-rate", TEST_CLOCK_RATE as i32)
+                    .field("encoding-name", "RTX")
+                    .field("apt", TEST_PT as i32)
+                    .build(),
+            )
+            .build(),
+    );
+
+    h.play();
+
+    let caps = Caps::builder("application/x-rtp")
+        .field("media", "audio")
+        .field("payload", TEST_PT as i32)
+        .field("clock-rate", TEST_CLOCK_RATE as i32)
+        .field("encoding-name", "custom-test")
+        .build();
+    h.set_src_caps(caps);
+
+    (h, id)
+}
+
+#[test]
+fn test_send_rtx_caps() {
+    let (mut h, _id) = send_init_rtx();
+
+    send_push(&mut h, PACKETS_TEST_1, false);
+    send_pull(&mut h, PACKETS_TEST_1);
+
+    let caps = h.sinkpad().unwrap().current_caps().unwrap();
+    let s = caps.structure(0).unwrap();
+    assert_eq!(s.name(), "application/x-rtp");
+    assert_eq!(s.get::<i32>("payload").unwrap(), TEST_PT as i32);
+    assert_eq!(s.get::<i32>("rtx-payload").unwrap(), 97);
+    assert_ne!(s.get::<u32>("rtx-ssrc").unwrap(), TEST_SSRC);
+    assert!(s.has_field_with_type("rtx-seqnum-offset", u32::static_type()));
+}
+
+#[test]
+fn test_send_rtx_retransmission() {
+    use rtcp_types::*;
+
+    let (mut h, id) = send_init_rtx();
+
+    send_push(&mut h, PACKETS_TEST_1, false);
+    send_pull(&mut h, PACKETS_TEST_1);
+
+    let caps = h.sinkpad().unwrap().current_caps().unwrap();
+    let rtx_ssrc = caps.structure(0).unwrap().get::<u32>("rtx-ssrc").unwrap();
+
+    let recv = gst::ElementFactory::make("rtprecv")
+        .property("rtp-id", id.to_string())
+        .build()
+        .unwrap();
+    let mut h_rtcp = Harness::with_element(&recv, Some("rtcp_sink_0"), None);
+    h_rtcp.play();
+    h_rtcp.set_src_caps(Caps::builder("application/x-rtcp").build());
+
+    let mut data = vec![0; 128];
+    let len = Compound::builder()
+        .add_packet(ReceiverReport::builder(0x55667788))
+        .add_packet(
+            TransportFeedback::builder_owned(
+                Nack::builder().add_rtp_sequences([PACKETS_TEST_1[0].seq_no]),
+            )
+            .sender_ssrc(0x55667788)
+            .media_ssrc(TEST_SSRC),
+        )
+        .write_into(&mut data)
+        .unwrap();
+    data.truncate(len);
+    h_rtcp.push(gst::Buffer::from_mut_slice(data)).unwrap();
+
+    // The NACK arrives after the last sent packet, the retransmission must still go out without
+    // waiting for another packet
+    let buffer = h.pull().unwrap();
+    let mapped = buffer.map_readable().unwrap();
+    let rtp = rtp_types::RtpPacket::parse(&mapped).unwrap();
+    assert_eq!(rtp.ssrc(), rtx_ssrc);
+    assert_eq!(rtp.payload_type(), 97);
+    assert_eq!(&rtp.payload()[..2], &PACKETS_TEST_1[0].seq_no.to_be_bytes());
+    drop(mapped);
+    assert!(h.try_pull().is_none());
+
+    let next = PacketInfo {
+        seq_no: 502,
+        rtp_ts: 40,
+        payload_len: 5,
+    };
+    send_push(&mut h, [next], false);
+    send_pull(&mut h, [next]);
+}
+
 #[test]
 fn test_send_benchmark() {
     init();
@@ -705,3 +894,263 @@ fn recv_release_sink_pad() {
     elem.release_request_pad(&sinkpad);
     elem.set_state(gst::State::Null).unwrap();
 }
+
+const MID_EXT_ID: u8 = 1;
+const RID_EXT_ID: u8 = 2;
+const REPAIRED_RID_EXT_ID: u8 = 3;
+const RTX_PT: u8 = 97;
+
+fn bundle_caps(pt: u8) -> gst::Caps {
+    let mut caps = Caps::builder("application/x-rtp")
+        .field("media", "video")
+        .field("payload", pt as i32)
+        .field("clock-rate", 90000i32)
+        .field("extmap-1", "urn:ietf:params:rtp-hdrext:sdes:mid")
+        .field("extmap-2", "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id")
+        .field(
+            "extmap-3",
+            "urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id",
+        );
+    caps = if pt == RTX_PT {
+        caps.field("encoding-name", "RTX")
+            .field("apt", TEST_PT as i32)
+    } else {
+        caps.field("encoding-name", "custom-test")
+    };
+    caps.build()
+}
+
+fn bundle_rtp_buffer(
+    ssrc: u32,
+    pt: u8,
+    seqno: u16,
+    extensions: &[(u8, &str)],
+    payload: &[u8],
+) -> gst::Buffer {
+    let mut ext = vec![];
+    for (id, value) in extensions {
+        ext.push((id << 4) | (value.len() as u8 - 1));
+        ext.extend_from_slice(value.as_bytes());
+    }
+    while ext.len() % 4 != 0 {
+        ext.push(0);
+    }
+
+    let data = RtpPacketBuilder::<&[u8], &[u8]>::new()
+        .ssrc(ssrc)
+        .payload_type(pt)
+        .sequence_number(seqno)
+        .timestamp(seqno as u32 * 3000)
+        .extension(0xBEDE, ext.as_slice())
+        .payload(payload)
+        .write_vec()
+        .unwrap();
+    let mut buffer = gst::Buffer::from_mut_slice(data);
+    buffer
+        .get_mut()
+        .unwrap()
+        .set_dts(gst::ClockTime::from_mseconds(seqno as u64));
+    buffer
+}
+
+/// A buffer output by rtprecv: the MID and RID of its src pad, its SSRC and sequence number
+type BundleOutput = (Option<String>, Option<String>, u32, u16);
+
+/// Set up a rtprecv receiving bundled streams, with a channel receiving the output buffers
+fn bundle_receive_init() -> (
+    gst::Element,
+    gst::Pad,
+    std::sync::mpsc::Receiver<BundleOutput>,
+) {
+    init();
+
+    let id = next_element_counter();
+
+    let elem = gst::ElementFactory::make("rtprecv")
+        .property("rtp-id", id.to_string())
+        .property("latency", 0u32)
+        .build()
+        .unwrap();
+    let (sender, recv) = std::sync::mpsc::sync_channel(16);
+    elem.connect_pad_added(move |_elem, pad| {
+        let sender = sender.clone();
+        let other_pad = gst::Pad::builder(gst::PadDirection::Sink)
+            .chain_function(move |pad, _parent, buffer| {
+                let caps = pad.current_caps().unwrap();
+                let s = caps.structure(0).unwrap();
+                let map = buffer.map_readable().unwrap();
+                let rtp = RtpPacket::parse(&map).unwrap();
+                sender
+                    .send((
+                        s.get::<String>("a-mid").ok(),
+                        s.get::<String>("rid").ok(),
+                        rtp.ssrc(),
+                        rtp.sequence_number(),
+                    ))
+                    .unwrap();
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build();
+        other_pad.set_active(true).unwrap();
+        pad.link(&other_pad).unwrap();
+    });
+    elem.set_state(gst::State::Playing).unwrap();
+
+    let sinkpad = elem.request_pad_simple("rtp_sink_0").unwrap();
+    let session = elem.emit_by_name::<glib::Object>("get-session", &[&0u32]);
+    session.set_property(
+        "pt-map",
+        gst::Structure::builder("application/x-rtp2-pt-map")
+            .field(TEST_PT.to_string(), bundle_caps(TEST_PT))
+            .field(RTX_PT.to_string(), bundle_caps(RTX_PT))
+            .build(),
+    );
+    sinkpad.send_event(gst::event::StreamStart::new("bundle"));
+    sinkpad.send_event(gst::event::Caps::new(&bundle_caps(TEST_PT)));
+    sinkpad.send_event(gst::event::Segment::new(&gst::FormattedSegment::<
+        gst::ClockTime,
+    >::new()));
+
+    (elem, sinkpad, recv)
+}
+
+fn bundle_receive_pull(
+    recv: &std::sync::mpsc::Receiver<BundleOutput>,
+    n_buffers: usize,
+) -> Vec<BundleOutput> {
+    let mut output = (0..n_buffers)
+        .map(|_| {
+            recv.recv_timeout(std::time::Duration::from_secs(5))
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    output.sort();
+    output
+}
+
+#[test]
+fn test_receive_bundle_mid() {
+    let (elem, sinkpad, recv) = bundle_receive_init();
+
+    let mids = Arc::new(Mutex::new(vec![]));
+    let session = elem.emit_by_name::<glib::Object>("get-session", &[&0u32]);
+    session.connect("new-mid-rid", false, {
+        let mids = mids.clone();
+        move |args| {
+            let ssrc = args[1].get::<u32>().unwrap();
+            let mid = args[2].get::<Option<String>>().unwrap();
+            mids.lock().unwrap().push((ssrc, mid));
+            None
+        }
+    });
+
+    // Two media sections with the same payload type over one session
+    for seqno in [100, 101] {
+        for (ssrc, mid) in [(0x1000, "0"), (0x2000, "1")] {
+            sinkpad
+                .chain(bundle_rtp_buffer(
+                    ssrc,
+                    TEST_PT,
+                    seqno,
+                    &[(MID_EXT_ID, mid)],
+                    &[4; 8],
+                ))
+                .unwrap();
+        }
+    }
+
+    let output = bundle_receive_pull(&recv, 4);
+    assert_eq!(
+        output,
+        [
+            (Some("0".to_string()), None, 0x1000, 100),
+            (Some("0".to_string()), None, 0x1000, 101),
+            (Some("1".to_string()), None, 0x2000, 100),
+            (Some("1".to_string()), None, 0x2000, 101),
+        ]
+    );
+
+    let src_pads = elem.src_pads();
+    assert_eq!(src_pads.len(), 2);
+    let mut pad_mids = src_pads
+        .iter()
+        .map(|pad| {
+            pad.current_caps()
+                .unwrap()
+                .structure(0)
+                .unwrap()
+                .get::<String>("a-mid")
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    pad_mids.sort();
+    assert_eq!(pad_mids, ["0", "1"]);
+
+    let mut mids = mids.lock().unwrap().clone();
+    mids.sort();
+    assert_eq!(
+        mids,
+        [
+            (0x1000, Some("0".to_string())),
+            (0x2000, Some("1".to_string()))
+        ]
+    );
+
+    elem.release_request_pad(&sinkpad);
+    elem.set_state(gst::State::Null).unwrap();
+}
+
+#[test]
+fn test_receive_bundle_repaired_rid() {
+    let (elem, sinkpad, recv) = bundle_receive_init();
+
+    // Two simulcast layers of the same media section
+    for seqno in [100, 101] {
+        for (ssrc, rid) in [(0x1000, "hi"), (0x2000, "lo")] {
+            sinkpad
+                .chain(bundle_rtp_buffer(
+                    ssrc,
+                    TEST_PT,
+                    seqno,
+                    &[(MID_EXT_ID, "0"), (RID_EXT_ID, rid)],
+                    &[4; 8],
+                ))
+                .unwrap();
+        }
+    }
+    let output = bundle_receive_pull(&recv, 4);
+    assert_eq!(
+        output
+            .iter()
+            .map(|o| (o.1.as_deref(), o.2))
+            .collect::<Vec<_>>(),
+        [
+            (Some("hi"), 0x1000),
+            (Some("hi"), 0x1000),
+            (Some("lo"), 0x2000),
+            (Some("lo"), 0x2000),
+        ]
+    );
+
+    // Without a pending retransmission request, the RTX stream can only be routed by the RID
+    // of the stream it repairs
+    let mut payload = 102u16.to_be_bytes().to_vec();
+    payload.extend_from_slice(&[4; 8]);
+    sinkpad
+        .chain(bundle_rtp_buffer(
+            0x3000,
+            RTX_PT,
+            5000,
+            &[(MID_EXT_ID, "0"), (REPAIRED_RID_EXT_ID, "lo")],
+            &payload,
+        ))
+        .unwrap();
+    let output = bundle_receive_pull(&recv, 1);
+    assert_eq!(
+        output,
+        [(Some("0".to_string()), Some("lo".to_string()), 0x2000, 102)]
+    );
+
+    elem.release_request_pad(&sinkpad);
+    elem.set_state(gst::State::Null).unwrap();
+}
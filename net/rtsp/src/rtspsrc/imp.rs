@@ -11,13 +11,14 @@
 //
 // https://www.rfc-editor.org/rfc/rfc2326.html
 
-use std::collections::{btree_set::BTreeSet, HashMap};
+use std::collections::{btree_set::BTreeSet, HashMap, VecDeque};
 use std::convert::TryFrom;
 use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 use std::num::NonZeroUsize;
 use std::pin::Pin;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::Result;
@@ -27,7 +28,7 @@ use futures::{Sink, SinkExt, Stream, StreamExt};
 use socket2::Socket;
 use tokio::net::{TcpStream, UdpSocket};
 use tokio::runtime;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::task::JoinHandle;
 use tokio::time;
 
@@ -59,6 +60,11 @@ const DEFAULT_PROTOCOLS: &str = "udp-mcast,udp,tcp";
 // Equal to MTU + 8 by default to avoid incorrectly detecting an MTU sized buffer as having
 // possibly overflown our receive buffer, and triggering a doubling of the buffer sizes.
 const DEFAULT_RECEIVE_MTU: u32 = 1500 + 8;
+const DEFAULT_DO_RTSP_KEEP_ALIVE: bool = true;
+const DEFAULT_RECONNECT_ATTEMPTS: i32 = 0;
+const DEFAULT_RECONNECT_DELAY: gst::ClockTime = gst::ClockTime::from_seconds(1);
+// https://datatracker.ietf.org/doc/html/rfc2326#section-12.37
+const DEFAULT_SESSION_TIMEOUT: u64 = 60;
 
 const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
 const MAX_BIND_PORT_RETRY: u16 = 100;
@@ -100,6 +106,9 @@ struct Settings {
     protocols: Vec<RtspProtocol>,
     timeout: gst::ClockTime,
     receive_mtu: u32,
+    do_rtsp_keep_alive: bool,
+    reconnect_attempts: i32,
+    reconnect_delay: gst::ClockTime,
 }
 
 impl Default for Settings {
@@ -110,16 +119,41 @@ impl Default for Settings {
             timeout: DEFAULT_TIMEOUT,
             protocols: parse_protocols_str(DEFAULT_PROTOCOLS).unwrap(),
             receive_mtu: DEFAULT_RECEIVE_MTU,
+            do_rtsp_keep_alive: DEFAULT_DO_RTSP_KEEP_ALIVE,
+            reconnect_attempts: DEFAULT_RECONNECT_ATTEMPTS,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
         }
     }
 }
 
+impl Settings {
+    fn can_reconnect(&self, attempt: u32) -> bool {
+        match u32::try_from(self.reconnect_attempts) {
+            Ok(max) => attempt < max,
+            // -1 means unlimited
+            Err(_) => true,
+        }
+    }
+}
+
+// How the RTSP task goes on after a session failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Recovery {
+    // Set up the streams again over TCP
+    FallbackToTcp,
+    // Connect again with the same protocols
+    Reconnect,
+    // Post the error
+    GiveUp,
+}
+
 #[derive(Debug)]
 enum Commands {
     Play,
     //Pause,
     Teardown(Option<oneshot::Sender<()>>),
     Data(rtsp_types::Data<Body>),
+    TransportError(RtspError),
 }
 
 #[derive(Debug, Default)]
@@ -143,10 +177,23 @@ pub enum RtspError {
     UnexpectedMessage(&'static str, rtsp_types::Message<Body>),
     #[error("Invalid RTSP message")]
     InvalidMessage(&'static str),
+    #[error("No data received within the timeout")]
+    DataTimeout(gst::ClockTime),
     #[error("Fatal error")]
     Fatal(String),
 }
 
+impl RtspError {
+    // Errors caused by the network or the server going away, which might go away after
+    // reconnecting. Everything else is most likely a configuration or protocol problem.
+    fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            RtspError::IOGeneric(_) | RtspError::Read(_) | RtspError::DataTimeout(_)
+        )
+    }
+}
+
 pub(crate) static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     gst::DebugCategory::new(
         "rtspsrc2",
@@ -308,6 +355,26 @@ impl ObjectImpl for RtspSrc {
                     .default_value(DEFAULT_TIMEOUT.into())
                     .mutable_ready()
                     .build(),
+                glib::ParamSpecBoolean::builder("do-rtsp-keep-alive")
+                    .nick("Do RTSP Keep Alive")
+                    .blurb("Send GET_PARAMETER or OPTIONS requests to keep the RTSP session alive")
+                    .default_value(DEFAULT_DO_RTSP_KEEP_ALIVE)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecInt::builder("reconnect-attempts")
+                    .nick("Reconnect attempts")
+                    .blurb("Number of times to reconnect after losing the connection to the server (0 = disabled, -1 = unlimited)")
+                    .minimum(-1)
+                    .default_value(DEFAULT_RECONNECT_ATTEMPTS)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt64::builder("reconnect-delay")
+                    .nick("Reconnect delay")
+                    .blurb("Time to wait before each reconnection attempt, in nanoseconds")
+                    .maximum(gst::ClockTime::MAX.into())
+                    .default_value(DEFAULT_RECONNECT_DELAY.into())
+                    .mutable_ready()
+                    .build(),
             ]
         });
 
@@ -348,6 +415,21 @@ impl ObjectImpl for RtspSrc {
                 settings.timeout = timeout;
                 Ok(())
             }
+            "do-rtsp-keep-alive" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.do_rtsp_keep_alive = value.get().expect("type checked upstream");
+                Ok(())
+            }
+            "reconnect-attempts" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.reconnect_attempts = value.get().expect("type checked upstream");
+                Ok(())
+            }
+            "reconnect-delay" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.reconnect_delay = value.get().expect("type checked upstream");
+                Ok(())
+            }
             name => unimplemented!("Property '{name}'"),
         };
 
@@ -392,6 +474,18 @@ impl ObjectImpl for RtspSrc {
                 let settings = self.settings.lock().unwrap();
                 settings.timeout.to_value()
             }
+            "do-rtsp-keep-alive" => {
+                let settings = self.settings.lock().unwrap();
+                settings.do_rtsp_keep_alive.to_value()
+            }
+            "reconnect-attempts" => {
+                let settings = self.settings.lock().unwrap();
+                settings.reconnect_attempts.to_value()
+            }
+            "reconnect-delay" => {
+                let settings = self.settings.lock().unwrap();
+                settings.reconnect_delay.to_value()
+            }
             name => unimplemented!("Property '{name}'"),
         }
     }
@@ -553,13 +647,8 @@ impl RtspSrc {
         }
 
         let join_handle = RUNTIME.spawn(async move {
-            gst::info!(CAT, "Connecting to {url} ..");
-            let hostname_port =
-                format!("{}:{}", url.host_str().unwrap(), url.port().unwrap_or(554));
-
-            // TODO: Add TLS support
-            let s = match TcpStream::connect(hostname_port).await {
-                Ok(s) => s,
+            let (stream, sink) = match connect(&url).await {
+                Ok(v) => v,
                 Err(err) => {
                     gst::element_imp_error!(
                         task_src,
@@ -569,14 +658,6 @@ impl RtspSrc {
                     return;
                 }
             };
-            let _ = s.set_nodelay(true);
-
-            gst::info!(CAT, "Connected!");
-
-            let (read, write) = s.into_split();
-
-            let stream = Box::pin(super::tcp_message::async_read(read, MAX_MESSAGE_SIZE).fuse());
-            let sink = Box::pin(super::tcp_message::async_write(write));
 
             let mut state = RtspTaskState::new(url, stream, sink);
 
@@ -584,12 +665,7 @@ impl RtspSrc {
             gst::info!(CAT, "Exited rtsp_task");
 
             // Cleanup after stopping
-            for h in &state.handles {
-                h.abort();
-            }
-            for h in state.handles {
-                let _ = h.await;
-            }
+            state.stop_transport_tasks().await;
             let obj = task_src.obj();
             for e in obj.iterate_sorted() {
                 let Ok(e) = e else {
@@ -667,6 +743,16 @@ impl RtspSrc {
         caps: &gst::Caps,
         manager: &RtspManager,
     ) -> Result<gst_app::AppSrc> {
+        let obj = self.obj();
+        // After a reconnection we keep the elements and src pads of the previous connection, so
+        // that downstream doesn't notice anything other than a discontinuity.
+        if let Some(appsrc) = obj
+            .by_name(&format!("rtp_appsrc_{rtpsession_n}"))
+            .and_then(|e| e.downcast::<gst_app::AppSrc>().ok())
+        {
+            appsrc.set_caps(Some(caps));
+            return Ok(appsrc);
+        }
         let callbacks = gst_app::AppSrcCallbacks::builder()
             .enough_data(|appsrc| {
                 gst::warning!(CAT, "appsrc {} is overrunning: enough data!", appsrc.name());
@@ -685,7 +771,6 @@ impl RtspSrc {
             .callbacks(callbacks)
             .is_live(true)
             .build();
-        let obj = self.obj();
         obj.add(&appsrc)?;
         appsrc
             .static_pad("src")
@@ -707,6 +792,13 @@ impl RtspSrc {
         rtpsession_n: usize,
         manager: &RtspManager,
     ) -> Result<gst_app::AppSrc> {
+        if let Some(appsrc) = self
+            .obj()
+            .by_name(&format!("rtcp_appsrc_{rtpsession_n}"))
+            .and_then(|e| e.downcast::<gst_app::AppSrc>().ok())
+        {
+            return Ok(appsrc);
+        }
         let appsrc = gst_app::AppSrc::builder()
             .name(format!("rtcp_appsrc_{rtpsession_n}"))
             .format(gst::Format::Time)
@@ -743,6 +835,16 @@ impl RtspSrc {
             .new_sample(on_rtcp)
             .build();
 
+        if let Some(rtcp_appsink) = self
+            .obj()
+            .by_name(&format!("rtcp_appsink_{rtpsession_n}"))
+            .and_then(|e| e.downcast::<gst_app::AppSink>().ok())
+        {
+            // The lower transport might have changed, so the RTCP has to go somewhere else now
+            rtcp_appsink.set_callbacks(cbs);
+            return Ok(());
+        }
+
         let rtcp_appsink = gst_app::AppSink::builder()
             .name(format!("rtcp_appsink_{rtpsession_n}"))
             .sync(false)
@@ -786,10 +888,164 @@ impl RtspSrc {
         state: &mut RtspTaskState,
         mut cmd_rx: mpsc::Receiver<Commands>,
     ) -> Result<()> {
-        let cmd_tx = self.cmd_queue();
-
         let settings = { self.settings.lock().unwrap().clone() };
 
+        let manager = RtspManager::new(std::env::var("USE_RTP2").is_ok_and(|s| s == "1"));
+
+        let obj = self.obj();
+        manager
+            .add_to(obj.upcast_ref::<gst::Bin>())
+            .expect("Adding the manager cannot fail");
+
+        // Expose RTP srcpads
+        manager.recv.connect_pad_added(|manager, pad| {
+            if pad.direction() != gst::PadDirection::Src {
+                return;
+            }
+            let Some(obj) = manager
+                .parent()
+                .and_then(|o| o.downcast::<gst::Element>().ok())
+            else {
+                return;
+            };
+            let name = pad.name();
+            match *name.split('_').collect::<Vec<_>>() {
+                // rtpbin and rtp2
+                ["recv", "rtp", "src", stream_id, ssrc, pt]
+                | ["rtp", "src", stream_id, ssrc, pt] => {
+                    if stream_id.parse::<u32>().is_err() {
+                        gst::info!(CAT, "Ignoring srcpad with invalid stream id: {name}");
+                        return;
+                    };
+                    gst::info!(CAT, "Setting rtpbin pad {} as ghostpad target", name);
+                    let srcpad = obj
+                        .static_pad(&format!("stream_{}", stream_id))
+                        .expect("ghostpad should've been available already");
+                    let ghostpad = srcpad
+                        .downcast::<gst::GhostPad>()
+                        .expect("rtspsrc src pads are ghost pads");
+                    if let Err(err) = ghostpad.set_target(Some(pad)) {
+                        gst::element_error!(
+                            obj,
+                            gst::ResourceError::Failed,
+                            (
+                                "Failed to set ghostpad {} target {}: {err:?}",
+                                ghostpad.name(),
+                                name
+                            ),
+                            ["pt: {pt}, ssrc: {ssrc}"]
+                        );
+                    }
+                }
+                _ => {
+                    gst::info!(CAT, "Ignoring unknown srcpad: {name}");
+                }
+            }
+        });
+
+        let mut protocols = settings.protocols.clone();
+        let mut attempt = 0;
+        let mut reconnect_err = None;
+        loop {
+            let err = match reconnect_err.take() {
+                Some(err) => err,
+                None => match self
+                    .rtsp_session(state, &settings, &protocols, &manager, &mut cmd_rx)
+                    .await
+                {
+                    Ok(()) => return Ok(()),
+                    Err(err) => err,
+                },
+            };
+
+            // Any successful PLAY resets the reconnection counter
+            if std::mem::take(&mut state.established) {
+                attempt = 0;
+            }
+
+            let recovery = state.recovery(&err, &settings, &protocols, attempt);
+
+            state.stop_transport_tasks().await;
+            state.teardown_session().await;
+
+            // Nothing from the old connection is relevant anymore, but we must not lose commands
+            // from the element itself.
+            while let Ok(cmd) = cmd_rx.try_recv() {
+                match cmd {
+                    Commands::Teardown(tx) => {
+                        if let Some(tx) = tx {
+                            let _ = tx.send(());
+                        }
+                        return Ok(());
+                    }
+                    Commands::Play => state.play_requested = true,
+                    Commands::Data(_) | Commands::TransportError(_) => {}
+                }
+            }
+
+            if recovery == Recovery::FallbackToTcp {
+                gst::element_imp_warning!(
+                    self,
+                    gst::ResourceError::Read,
+                    [
+                        "Could not receive any UDP packets for {}, trying TCP",
+                        settings.timeout
+                    ]
+                );
+                protocols = vec![RtspProtocol::Tcp];
+            } else if recovery == Recovery::Reconnect {
+                attempt += 1;
+                gst::element_imp_warning!(
+                    self,
+                    gst::ResourceError::Read,
+                    ["Lost connection to the RTSP server, reconnecting (attempt {attempt})"],
+                    ["{err:#?}"]
+                );
+                if !state.reconnecting {
+                    state.reconnecting = true;
+                    self.post_start("reconnect", "Reconnecting to the server");
+                }
+                tokio::select! {
+                    _ = time::sleep(Duration::from_nanos(settings.reconnect_delay.nseconds())) => {}
+                    Some(cmd) = cmd_rx.recv() => match cmd {
+                        Commands::Teardown(tx) => {
+                            gst::info!(CAT, "Received Teardown command while reconnecting");
+                            self.post_cancelled("reconnect", "Reconnection cancelled");
+                            if let Some(tx) = tx {
+                                let _ = tx.send(());
+                            }
+                            return Ok(());
+                        }
+                        Commands::Play => state.play_requested = true,
+                        Commands::Data(_) | Commands::TransportError(_) => {}
+                    },
+                }
+            } else {
+                if state.reconnecting {
+                    self.post_cancelled("reconnect", "Giving up reconnecting");
+                }
+                return Err(err);
+            }
+
+            if let Err(err) = state.reconnect().await {
+                gst::warning!(CAT, "Failed to reconnect: {err:?}");
+                reconnect_err = Some(err.into());
+            }
+        }
+    }
+
+    async fn rtsp_session(
+        &self,
+        state: &mut RtspTaskState,
+        settings: &Settings,
+        protocols: &[RtspProtocol],
+        manager: &RtspManager,
+        cmd_rx: &mut mpsc::Receiver<Commands>,
+    ) -> Result<()> {
+        let cmd_tx = self.cmd_queue();
+        let timeout = settings.timeout;
+        let receive_mtu = settings.receive_mtu;
+
         // OPTIONS
         state.options().await?;
 
@@ -803,18 +1059,14 @@ impl RtspSrc {
                 .setup(
                     &mut session,
                     settings.port_start,
-                    &settings.protocols,
+                    protocols,
                     TransportMode::Play,
                 )
                 .await?
         };
-        let manager = RtspManager::new(std::env::var("USE_RTP2").is_ok_and(|s| s == "1"));
+        state.session = session.clone();
 
         let obj = self.obj();
-        manager
-            .add_to(obj.upcast_ref::<gst::Bin>())
-            .expect("Adding the manager cannot fail");
-
         let mut tcp_interleave_appsrcs = HashMap::new();
         for (rtpsession_n, p) in state.setup_params.iter_mut().enumerate() {
             let (tx, rx) = mpsc::channel(1);
@@ -874,27 +1126,36 @@ impl RtspSrc {
                         }
                     };
 
-                    let rtp_appsrc = self.make_rtp_appsrc(rtpsession_n, &p.caps, &manager)?;
+                    let rtp_appsrc = self.make_rtp_appsrc(rtpsession_n, &p.caps, manager)?;
                     p.rtp_appsrc = Some(rtp_appsrc.clone());
                     // Spawn RTP udp receive task
+                    let cmd_tx = cmd_tx.clone();
+                    let playing = state.playing.subscribe();
+                    let udp_received = state.udp_received.clone();
                     state.handles.push(RUNTIME.spawn(async move {
-                        udp_rtp_task(
+                        let err = udp_rtp_task(
                             &rtp_socket,
                             rtp_appsrc,
-                            settings.timeout,
-                            settings.receive_mtu,
+                            timeout,
+                            receive_mtu,
                             None,
+                            playing,
+                            udp_received,
                         )
-                        .await
+                        .await;
+                        let _ = cmd_tx.send(Commands::TransportError(err)).await;
                     }));
 
                     // Spawn RTCP udp send/recv task
                     if let Some(rtcp_socket) = rtcp_socket {
                         let rtcp_dest = rtcp_port.and_then(|p| Some(SocketAddr::new(*dest, p)));
-                        let rtcp_appsrc = self.make_rtcp_appsrc(rtpsession_n, &manager)?;
-                        self.make_rtcp_appsink(rtpsession_n, &manager, on_rtcp)?;
+                        let rtcp_appsrc = self.make_rtcp_appsrc(rtpsession_n, manager)?;
+                        self.make_rtcp_appsink(rtpsession_n, manager, on_rtcp)?;
+                        let cmd_tx = cmd_tx.clone();
                         state.handles.push(RUNTIME.spawn(async move {
-                            udp_rtcp_task(&rtcp_socket, rtcp_appsrc, rtcp_dest, true, rx).await
+                            let err =
+                                udp_rtcp_task(&rtcp_socket, rtcp_appsrc, rtcp_dest, true, rx).await;
+                            let _ = cmd_tx.send(Commands::TransportError(err)).await;
                         }));
                     }
                 }
@@ -927,44 +1188,58 @@ impl RtspSrc {
                     };
 
                     // Spawn RTP udp receive task
-                    let rtp_appsrc = self.make_rtp_appsrc(rtpsession_n, &p.caps, &manager)?;
+                    let rtp_appsrc = self.make_rtp_appsrc(rtpsession_n, &p.caps, manager)?;
                     p.rtp_appsrc = Some(rtp_appsrc.clone());
+                    let cmd_tx = cmd_tx.clone();
+                    let playing = state.playing.subscribe();
+                    let udp_received = state.udp_received.clone();
                     state.handles.push(RUNTIME.spawn(async move {
-                        udp_rtp_task(
+                        let err = udp_rtp_task(
                             &rtp_socket,
                             rtp_appsrc,
-                            settings.timeout,
-                            settings.receive_mtu,
+                            timeout,
+                            receive_mtu,
                             rtp_sender_addr,
+                            playing,
+                            udp_received,
                         )
-                        .await
+                        .await;
+                        let _ = cmd_tx.send(Commands::TransportError(err)).await;
                     }));
 
                     // Spawn RTCP udp send/recv task
                     if let Some(rtcp_socket) = rtcp_socket {
-                        let rtcp_appsrc = self.make_rtcp_appsrc(rtpsession_n, &manager)?;
-                        self.make_rtcp_appsink(rtpsession_n, &manager, on_rtcp)?;
+                        let rtcp_appsrc = self.make_rtcp_appsrc(rtpsession_n, manager)?;
+                        self.make_rtcp_appsink(rtpsession_n, manager, on_rtcp)?;
+                        let cmd_tx = cmd_tx.clone();
                         state.handles.push(RUNTIME.spawn(async move {
-                            udp_rtcp_task(&rtcp_socket, rtcp_appsrc, rtcp_sender_addr, false, rx)
-                                .await
+                            let err = udp_rtcp_task(
+                                &rtcp_socket,
+                                rtcp_appsrc,
+                                rtcp_sender_addr,
+                                false,
+                                rx,
+                            )
+                            .await;
+                            let _ = cmd_tx.send(Commands::TransportError(err)).await;
                         }));
                     }
                 }
                 RtspTransportInfo::Tcp {
                     channels: (rtp_channel, rtcp_channel),
                 } => {
-                    let rtp_appsrc = self.make_rtp_appsrc(rtpsession_n, &p.caps, &manager)?;
+                    let rtp_appsrc = self.make_rtp_appsrc(rtpsession_n, &p.caps, manager)?;
                     p.rtp_appsrc = Some(rtp_appsrc.clone());
                     tcp_interleave_appsrcs.insert(*rtp_channel, rtp_appsrc);
 
                     if let Some(rtcp_channel) = rtcp_channel {
                         // RTCP SR
-                        let rtcp_appsrc = self.make_rtcp_appsrc(rtpsession_n, &manager)?;
+                        let rtcp_appsrc = self.make_rtcp_appsrc(rtpsession_n, manager)?;
                         tcp_interleave_appsrcs.insert(*rtcp_channel, rtcp_appsrc.clone());
                         // RTCP RR
                         let rtcp_channel = *rtcp_channel;
                         let cmd_tx = cmd_tx.clone();
-                        self.make_rtcp_appsink(rtpsession_n, &manager, move |appsink| {
+                        self.make_rtcp_appsink(rtpsession_n, manager, move |appsink| {
                             on_rtcp_tcp(appsink, cmd_tx.clone(), rtcp_channel)
                         })?;
                     }
@@ -972,59 +1247,37 @@ impl RtspSrc {
             }
         }
 
-        obj.no_more_pads();
+        if !state.pads_complete {
+            obj.no_more_pads();
+            state.pads_complete = true;
+        }
 
-        // Expose RTP srcpads
-        manager.recv.connect_pad_added(|manager, pad| {
-            if pad.direction() != gst::PadDirection::Src {
-                return;
-            }
-            let Some(obj) = manager
-                .parent()
-                .and_then(|o| o.downcast::<gst::Element>().ok())
-            else {
-                return;
-            };
-            let name = pad.name();
-            match *name.split('_').collect::<Vec<_>>() {
-                // rtpbin and rtp2
-                ["recv", "rtp", "src", stream_id, ssrc, pt]
-                | ["rtp", "src", stream_id, ssrc, pt] => {
-                    if stream_id.parse::<u32>().is_err() {
-                        gst::info!(CAT, "Ignoring srcpad with invalid stream id: {name}");
-                        return;
-                    };
-                    gst::info!(CAT, "Setting rtpbin pad {} as ghostpad target", name);
-                    let srcpad = obj
-                        .static_pad(&format!("stream_{}", stream_id))
-                        .expect("ghostpad should've been available already");
-                    let ghostpad = srcpad
-                        .downcast::<gst::GhostPad>()
-                        .expect("rtspsrc src pads are ghost pads");
-                    if let Err(err) = ghostpad.set_target(Some(pad)) {
-                        gst::element_error!(
-                            obj,
-                            gst::ResourceError::Failed,
-                            (
-                                "Failed to set ghostpad {} target {}: {err:?}",
-                                ghostpad.name(),
-                                name
-                            ),
-                            ["pt: {pt}, ssrc: {ssrc}"]
-                        );
-                    }
-                }
-                _ => {
-                    gst::info!(CAT, "Ignoring unknown srcpad: {name}");
-                }
-            }
-        });
+        let data_timeout = Duration::from_nanos(timeout.nseconds());
+        let data_deadline = time::sleep(data_timeout);
+        tokio::pin!(data_deadline);
 
-        let mut expected_response: Option<(Method, u32)> = None;
+        let keep_alive_period = state.keep_alive_interval();
+        let mut keep_alive =
+            time::interval_at(time::Instant::now() + keep_alive_period, keep_alive_period);
+        keep_alive.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+
+        // RTSP 1.0 responses are always sent in the order of the requests
+        let mut expected_responses: VecDeque<(Method, u32)> = VecDeque::new();
+
+        // We were already playing before reconnecting
+        if state.play_requested {
+            let Some(s) = &session else {
+                return Err(RtspError::InvalidMessage("Can't PLAY, no SETUP").into());
+            };
+            let cseq = state.play(s).await?;
+            expected_responses.push_back((Method::Play, cseq));
+            state.playing.send_replace(true);
+        }
         loop {
             tokio::select! {
                 msg = state.stream.next() => match msg {
                     Some(Ok(rtsp_types::Message::Data(data))) => {
+                        data_deadline.as_mut().reset(time::Instant::now() + data_timeout);
                         let Some(appsrc) = tcp_interleave_appsrcs.get(&data.channel_id()) else {
                             gst::warning!(CAT,
                                 "ignored data of size {}: unknown channel {}",
@@ -1053,7 +1306,7 @@ impl RtspSrc {
                     }
                     Some(Ok(rtsp_types::Message::Response(rsp))) => {
                         gst::debug!(CAT, "<-- {rsp:#?}");
-                        let Some((expected, cseq)) = &expected_response else {
+                        let Some((expected, cseq)) = expected_responses.pop_front() else {
                             continue;
                         };
                         let Some(s) = &session else {
@@ -1061,22 +1314,33 @@ impl RtspSrc {
                         };
                         match expected {
                             Method::Play => {
-                                state.play_response(&rsp, *cseq, s).await?;
+                                state.play_response(&rsp, cseq, s).await?;
+                                state.established = true;
+                                state.ever_established = true;
                                 self.post_complete("request", "PLAY response received");
+                                if state.reconnecting {
+                                    state.reconnecting = false;
+                                    self.post_complete("reconnect", "Reconnected");
+                                }
+                            }
+                            Method::Teardown => state.teardown_response(&rsp, cseq, s).await?,
+                            Method::GetParameter | Method::Options => {
+                                state.keep_alive_response(&rsp, cseq, expected)
                             }
-                            Method::Teardown => state.teardown_response(&rsp, *cseq, s).await?,
                             m => unreachable!("BUG: unexpected response method: {m:?}"),
                         };
                     }
                     Some(Err(e)) => {
-                        // TODO: reconnect or ignore if UDP sockets are still receiving data
-                        gst::error!(CAT, "I/O error: {e:?}, quitting");
-                        return Err(gst::FlowError::Error.into());
+                        gst::error!(CAT, "I/O error: {e:?}");
+                        return Err(RtspError::from(e).into());
                     }
                     None => {
-                        // TODO: reconnect or ignore if UDP sockets are still receiving data
-                        gst::error!(CAT, "TCP connection EOF, quitting");
-                        return Err(gst::FlowError::Eos.into());
+                        gst::error!(CAT, "TCP connection EOF");
+                        return Err(RtspError::from(std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            "RTSP connection closed",
+                        ))
+                        .into());
                     }
                 },
                 Some(cmd) = cmd_rx.recv() => match cmd {
@@ -1088,7 +1352,10 @@ impl RtspSrc {
                         let cseq = state.play(s).await.inspect_err(|_err| {
                             self.post_cancelled("request", "PLAY request cancelled");
                         })?;
-                        expected_response = Some((Method::Play, cseq));
+                        expected_responses.push_back((Method::Play, cseq));
+                        state.play_requested = true;
+                        state.playing.send_replace(true);
+                        data_deadline.as_mut().reset(time::Instant::now() + data_timeout);
                     },
                     Commands::Teardown(tx) => {
                         gst::info!(CAT, "Received Teardown command");
@@ -1096,6 +1363,7 @@ impl RtspSrc {
                             return Err(RtspError::InvalidMessage("Can't TEARDOWN, no SETUP").into());
                         };
                         let _ = state.teardown(s).await;
+                        state.session = None;
                         if let Some(tx) = tx {
                             let _ = tx.send(());
                         }
@@ -1107,7 +1375,23 @@ impl RtspSrc {
                         state.sink.send(Message::Data(data)).await?;
                         gst::debug!(CAT, "Sent RTCP RR over TCP");
                     }
+                    Commands::TransportError(err) => {
+                        gst::error!(CAT, "Transport error: {err:?}");
+                        return Err(err.into());
+                    }
                 },
+                _ = keep_alive.tick(), if settings.do_rtsp_keep_alive => {
+                    let Some(s) = &session else {
+                        continue;
+                    };
+                    let method = state.keep_alive_method.clone();
+                    let cseq = state.keep_alive(s).await?;
+                    expected_responses.push_back((method, cseq));
+                }
+                () = &mut data_deadline, if state.play_requested && !tcp_interleave_appsrcs.is_empty() => {
+                    gst::error!(CAT, "No data received over TCP for {timeout}");
+                    return Err(RtspError::DataTimeout(timeout).into());
+                }
                 else => {
                     gst::error!(CAT, "No select statement matched, breaking loop");
                     break;
@@ -1220,6 +1504,21 @@ struct RtspTaskState {
 
     setup_params: Vec<RtspSetupParams>,
     handles: Vec<JoinHandle<()>>,
+
+    session: Option<Session>,
+    session_timeout: Option<u64>,
+    keep_alive_method: Method,
+    // Lets the UDP tasks know when they can start expecting data
+    playing: watch::Sender<bool>,
+    play_requested: bool,
+    // PLAY succeeded on the current connection
+    established: bool,
+    // PLAY succeeded on any connection
+    ever_established: bool,
+    // An RTP packet was received over UDP on any connection
+    udp_received: Arc<AtomicBool>,
+    reconnecting: bool,
+    pads_complete: bool,
 }
 
 struct RtspSetupParams {
@@ -1242,9 +1541,97 @@ impl RtspTaskState {
             sink,
             setup_params: Vec::new(),
             handles: Vec::new(),
+            session: None,
+            session_timeout: None,
+            keep_alive_method: Method::Options,
+            playing: watch::Sender::new(false),
+            play_requested: false,
+            established: false,
+            ever_established: false,
+            udp_received: Arc::new(AtomicBool::new(false)),
+            reconnecting: false,
+            pads_complete: false,
+        }
+    }
+
+    async fn reconnect(&mut self) -> Result<(), RtspError> {
+        let (stream, sink) = connect(&self.url).await?;
+        self.stream = stream;
+        self.sink = sink;
+        self.content_base_or_location = None;
+        self.aggregate_control = None;
+        self.sdp = None;
+        self.session_timeout = None;
+        Ok(())
+    }
+
+    async fn stop_transport_tasks(&mut self) {
+        for h in &self.handles {
+            h.abort();
+        }
+        for h in self.handles.drain(..) {
+            let _ = h.await;
+        }
+        self.setup_params.clear();
+        self.playing.send_replace(false);
+    }
+
+    // Best effort, the connection is probably gone already, but if it isn't the server shouldn't
+    // keep sending us data for the old session.
+    async fn teardown_session(&mut self) {
+        let Some(session) = self.session.take() else {
+            return;
+        };
+        if let Err(err) = time::timeout(Duration::from_secs(1), self.teardown(&session)).await {
+            gst::debug!(CAT, "Timed out sending TEARDOWN: {err:?}");
+        }
+    }
+
+    fn uses_udp(&self) -> bool {
+        self.setup_params
+            .iter()
+            .any(|p| p.transport.to_protocol() != RtspProtocol::Tcp)
+    }
+
+    // Decides how to go on after the session failed with `err`, `attempt` reconnection attempts
+    // after the last successful PLAY
+    fn recovery(
+        &self,
+        err: &anyhow::Error,
+        settings: &Settings,
+        protocols: &[RtspProtocol],
+        attempt: u32,
+    ) -> Recovery {
+        let err = err.downcast_ref::<RtspError>();
+
+        // Not a single UDP packet made it, most likely because of a firewall or NAT that drops
+        // them. When UDP worked before this is a connection problem instead. This only happens
+        // once, as TCP is used from then on, so it doesn't count as a reconnection attempt.
+        if matches!(err, Some(RtspError::DataTimeout(_)))
+            && self.uses_udp()
+            && !self.udp_received.load(Ordering::Relaxed)
+            && protocols.contains(&RtspProtocol::Tcp)
+            && protocols.iter().any(|p| *p != RtspProtocol::Tcp)
+        {
+            return Recovery::FallbackToTcp;
+        }
+
+        if !err.is_some_and(RtspError::is_recoverable) || !settings.can_reconnect(attempt) {
+            return Recovery::GiveUp;
+        }
+
+        if self.ever_established {
+            Recovery::Reconnect
+        } else {
+            Recovery::GiveUp
         }
     }
 
+    fn keep_alive_interval(&self) -> Duration {
+        let timeout = self.session_timeout.unwrap_or(DEFAULT_SESSION_TIMEOUT);
+        Duration::from_secs((timeout / 2).max(1))
+    }
+
     fn check_response(
         rsp: &Response<Body>,
         cseq: u32,
@@ -1325,6 +1712,13 @@ impl RtspTaskState {
             ));
         };
 
+        // GET_PARAMETER is the preferred way of keeping the session alive, but it's optional
+        self.keep_alive_method = if methods.contains(&Method::GetParameter) {
+            Method::GetParameter
+        } else {
+            Method::Options
+        };
+
         let needed = [
             Method::Describe,
             Method::Setup,
@@ -1621,6 +2015,10 @@ impl RtspTaskState {
             let new_session = rsp
                 .typed_header::<Session>()?
                 .ok_or(RtspError::InvalidMessage("No session in SETUP response"))?;
+            if let Some(timeout) = new_session.1 {
+                gst::debug!(CAT, "Session timeout: {timeout}s");
+                self.session_timeout = Some(timeout);
+            }
             // Manually strip timeout field: https://github.com/sdroege/rtsp-types/issues/24
             session.replace(Session(new_session.0, None));
             let mut parsed_transport = if let Some(transports) = rsp.typed_header::<Transports>()? {
@@ -1749,6 +2147,37 @@ impl RtspTaskState {
         Ok(())
     }
 
+    async fn keep_alive(&mut self, session: &Session) -> Result<u32, RtspError> {
+        self.cseq += 1;
+        let request_uri = self.aggregate_control.as_ref().unwrap_or(&self.url).clone();
+        let req = Request::builder(self.keep_alive_method.clone(), self.version)
+            .typed_header::<CSeq>(&self.cseq.into())
+            .header(USER_AGENT, DEFAULT_USER_AGENT)
+            .request_uri(request_uri)
+            .typed_header::<Session>(session);
+
+        let req = req.build(Body::default());
+        gst::debug!(CAT, "-->> {req:#?}");
+        self.sink.send(req.into()).await?;
+        Ok(self.cseq)
+    }
+
+    fn keep_alive_response(&mut self, rsp: &Response<Body>, cseq: u32, method: Method) {
+        // The server not liking our keep-alive is no reason to stop streaming, and it might still
+        // have refreshed the session timeout.
+        if let Err(err) = Self::check_response(rsp, cseq, method.clone(), None) {
+            if method == Method::GetParameter {
+                gst::warning!(
+                    CAT,
+                    "GET_PARAMETER keep-alive failed: {err:?}, using OPTIONS instead"
+                );
+                self.keep_alive_method = Method::Options;
+            } else {
+                gst::warning!(CAT, "{method:?} keep-alive failed: {err:?}");
+            }
+        }
+    }
+
     async fn teardown(&mut self, session: &Session) -> Result<u32, RtspError> {
         self.cseq += 1;
         let request_uri = self.aggregate_control.as_ref().unwrap_or(&self.url).clone();
@@ -1775,6 +2204,24 @@ impl RtspTaskState {
     }
 }
 
+async fn connect(url: &Url) -> Result<(RtspStream, RtspSink), std::io::Error> {
+    gst::info!(CAT, "Connecting to {url} ..");
+    let hostname_port = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap_or(554));
+
+    // TODO: Add TLS support
+    let s = TcpStream::connect(hostname_port).await?;
+    let _ = s.set_nodelay(true);
+
+    gst::info!(CAT, "Connected!");
+
+    let (read, write) = s.into_split();
+
+    let stream = Box::pin(super::tcp_message::async_read(read, MAX_MESSAGE_SIZE).fuse());
+    let sink = Box::pin(super::tcp_message::async_write(write));
+
+    Ok((stream, sink))
+}
+
 fn bind_port(port: u16, is_ipv4: bool) -> Result<UdpSocket, std::io::Error> {
     let domain = if is_ipv4 {
         socket2::Domain::IPV4
@@ -1887,35 +2334,28 @@ async fn udp_rtp_task(
     timeout: gst::ClockTime,
     receive_mtu: u32,
     sender_addr: Option<SocketAddr>,
-) {
-    let t = Duration::from_secs(timeout.into());
+    mut playing: watch::Receiver<bool>,
+    received: Arc<AtomicBool>,
+) -> RtspError {
+    // The server won't send anything before PLAY, so don't time out before that
+    if playing.wait_for(|playing| *playing).await.is_err() {
+        return RtspError::Fatal("RTSP task went away".to_string());
+    }
+
+    let t = Duration::from_nanos(timeout.nseconds());
     let sender_addr = match sender_addr {
         Some(addr) => addr,
         // Server didn't give us a Transport header or its Transport header didn't specify the
         // server port, so we don't know the sender port from which we will get data till we get
         // the first packet here.
-        None => {
-            let ret = match time::timeout(t, socket.peek_sender()).await {
-                Ok(Ok(addr)) => Ok(addr),
-                Ok(Err(_elapsed)) => Err(format!(
-                    "No data after {} seconds, exiting",
-                    timeout.seconds()
-                )),
-                Err(err) => Err(format!("UDP socket was closed: {err:?}")),
-            };
-            match ret {
-                Ok(addr) => addr,
-                Err(err) => {
-                    gst::element_error!(
-                        appsrc,
-                        gst::ResourceError::Failed,
-                        ("{}", err),
-                        ["{:#?}", socket]
-                    );
-                    return;
-                }
+        None => match time::timeout(t, socket.peek_sender()).await {
+            Ok(Ok(addr)) => addr,
+            Ok(Err(err)) => return RtspError::IOGeneric(err),
+            Err(_elapsed) => {
+                gst::warning!(CAT, "No data after {timeout}");
+                return RtspError::DataTimeout(timeout);
             }
-        }
+        },
     };
     gst::info!(CAT, "Receiving from address {sender_addr:?}");
     let gio_addr = {
@@ -1929,12 +2369,12 @@ async fn udp_rtp_task(
     config.set_params(caps.as_ref(), size, 2, 0);
     pool.set_config(config).unwrap();
     pool.set_active(true).unwrap();
-    let error = loop {
+    loop {
         let Ok(buffer) = pool.acquire_buffer(None) else {
-            break "Failed to acquire buffer".to_string();
+            break RtspError::Fatal("Failed to acquire buffer".to_string());
         };
         let Ok(mut map) = buffer.into_mapped_buffer_writable() else {
-            break "Failed to map buffer writable".to_string();
+            break RtspError::Fatal("Failed to map buffer writable".to_string());
         };
         match time::timeout(t, socket.recv_from(map.as_mut_slice())).await {
             Ok(Ok((len, addr))) => {
@@ -1949,16 +2389,21 @@ async fn udp_rtp_task(
                     );
                     size = (size * 2).min(UDP_PACKET_MAX_SIZE);
                     if let Err(err) = pool.set_active(false) {
-                        break format!("Failed to deactivate buffer pool: {err:?}");
+                        break RtspError::Fatal(format!(
+                            "Failed to deactivate buffer pool: {err:?}"
+                        ));
                     }
                     pool = gst::BufferPool::new();
                     let mut config = pool.config();
                     config.set_params(caps.as_ref(), size, 2, 0);
                     pool.set_config(config).unwrap();
                     if let Err(err) = pool.set_active(true) {
-                        break format!("Failed to reallocate buffer pool: {err:?}");
+                        break RtspError::Fatal(format!(
+                            "Failed to reallocate buffer pool: {err:?}"
+                        ));
                     }
                 }
+                received.store(true, Ordering::Relaxed);
                 let t = appsrc.current_running_time();
                 let mut buffer = map.into_buffer();
                 let bufref = buffer.make_mut();
@@ -1967,21 +2412,19 @@ async fn udp_rtp_task(
                 gst_net::NetAddressMeta::add(bufref, &gio_addr);
                 gst::trace!(CAT, "received RTP packet from {addr:?}");
                 if let Err(err) = appsrc.push_buffer(buffer) {
-                    break format!("UDP buffer push failed: {err:?}");
+                    break RtspError::Fatal(format!("UDP buffer push failed: {err:?}"));
                 }
             }
-            Ok(Err(_elapsed)) => {
-                break format!("No data after {} seconds, exiting", timeout.seconds())
+            Ok(Err(err)) => {
+                gst::warning!(CAT, "UDP socket {socket:?} was closed: {err:?}");
+                break RtspError::IOGeneric(err);
+            }
+            Err(_elapsed) => {
+                gst::warning!(CAT, "No data after {timeout}");
+                break RtspError::DataTimeout(timeout);
             }
-            Err(err) => break format!("UDP socket was closed: {err:?}"),
         };
-    };
-    gst::element_error!(
-        appsrc,
-        gst::ResourceError::Failed,
-        ("{}", error),
-        ["{:#?}", socket]
-    );
+    }
 }
 
 async fn udp_rtcp_task(
@@ -1990,10 +2433,10 @@ async fn udp_rtcp_task(
     mut sender_addr: Option<SocketAddr>,
     is_multicast: bool,
     mut rx: mpsc::Receiver<MappedBuffer<Readable>>,
-) {
+) -> RtspError {
     let mut buf = vec![0; UDP_PACKET_MAX_SIZE as usize];
     let mut cache: LruCache<_, _> = LruCache::new(NonZeroUsize::new(RTCP_ADDR_CACHE_SIZE).unwrap());
-    let error = loop {
+    loop {
         tokio::select! {
             send_rtcp = rx.recv() => match send_rtcp {
                 // The server either didn't specify a server_port for RTCP, or if the server didn't
@@ -2003,7 +2446,8 @@ async fn udp_rtcp_task(
                         Ok(_) => gst::debug!(CAT, "Sent RTCP RR packet"),
                         Err(err) => {
                             rx.close();
-                            break format!("RTCP send error: {err:?}, stopping task");
+                            gst::warning!(CAT, "RTCP send error: {err:?}, stopping task");
+                            break RtspError::IOGeneric(err);
                         }
                     }
                 } else {
@@ -2011,7 +2455,9 @@ async fn udp_rtcp_task(
                 },
                 None => {
                     rx.close();
-                    break format!("UDP socket {socket:?} closed, no more RTCP will be sent");
+                    break RtspError::Fatal(format!(
+                        "UDP socket {socket:?} closed, no more RTCP will be sent"
+                    ));
                 }
             },
             recv_rtcp = socket.recv_from(&mut buf) => match recv_rtcp {
@@ -2036,19 +2482,16 @@ async fn udp_rtcp_task(
                     });
                     gst_net::NetAddressMeta::add(bufref, gio_addr);
                     if let Err(err) = appsrc.push_buffer(buffer) {
-                        break format!("UDP buffer push failed: {err:?}");
+                        break RtspError::Fatal(format!("UDP buffer push failed: {err:?}"));
                     }
                 }
-                Err(err) => break format!("UDP socket was closed: {err:?}"),
+                Err(err) => {
+                    gst::warning!(CAT, "UDP socket {socket:?} was closed: {err:?}");
+                    break RtspError::IOGeneric(err);
+                }
             },
         }
-    };
-    gst::element_error!(
-        appsrc,
-        gst::ResourceError::Failed,
-        ("{}", error),
-        ["{:#?}", socket]
-    );
+    }
 }
 
 #[glib::object_subclass]
@@ -2058,3 +2501,176 @@ impl ObjectSubclass for RtspSrc {
     type ParentType = gst::Bin;
     type Interfaces = (gst::URIHandler,);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_state() -> (
+        RtspTaskState,
+        futures::channel::mpsc::UnboundedReceiver<Message<Body>>,
+    ) {
+        gst::init().unwrap();
+
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let stream: RtspStream = Box::pin(futures::stream::empty());
+        let sink: RtspSink = Box::pin(tx.sink_map_err(std::io::Error::other));
+        let state = RtspTaskState::new(Url::parse("rtsp://localhost/test").unwrap(), stream, sink);
+
+        (state, rx)
+    }
+
+    fn add_udp_stream(state: &mut RtspTaskState) {
+        state.setup_params.push(RtspSetupParams {
+            control_url: state.url.clone(),
+            transport: RtspTransportInfo::Udp {
+                source: None,
+                server_port: None,
+                client_port: None,
+                sockets: None,
+            },
+            rtp_appsrc: None,
+            caps: gst::Caps::new_empty_simple("application/x-rtp"),
+        });
+    }
+
+    fn settings(reconnect_attempts: i32) -> Settings {
+        Settings {
+            reconnect_attempts,
+            ..Default::default()
+        }
+    }
+
+    fn data_timeout() -> anyhow::Error {
+        RtspError::DataTimeout(DEFAULT_TIMEOUT).into()
+    }
+
+    fn connection_lost() -> anyhow::Error {
+        RtspError::from(std::io::Error::from(std::io::ErrorKind::UnexpectedEof)).into()
+    }
+
+    #[test]
+    fn test_udp_fallback() {
+        let (mut state, _rx) = task_state();
+        add_udp_stream(&mut state);
+        state.ever_established = true;
+        let protocols = settings(1).protocols;
+
+        assert_eq!(
+            state.recovery(&data_timeout(), &settings(1), &protocols, 0),
+            Recovery::FallbackToTcp
+        );
+        // Falling back doesn't count as a reconnection attempt, it also happens with the
+        // default of no reconnection or when all attempts were used
+        assert_eq!(
+            state.recovery(&data_timeout(), &settings(0), &protocols, 0),
+            Recovery::FallbackToTcp
+        );
+        assert_eq!(
+            state.recovery(&data_timeout(), &settings(1), &protocols, 1),
+            Recovery::FallbackToTcp
+        );
+        // TCP is not allowed
+        assert_eq!(
+            state.recovery(&data_timeout(), &settings(1), &[RtspProtocol::Udp], 0),
+            Recovery::Reconnect
+        );
+
+        // UDP worked and then stopped, this is a connection problem
+        state.udp_received.store(true, Ordering::Relaxed);
+        assert_eq!(
+            state.recovery(&data_timeout(), &settings(1), &protocols, 0),
+            Recovery::Reconnect
+        );
+        assert_eq!(
+            state.recovery(&data_timeout(), &settings(0), &protocols, 0),
+            Recovery::GiveUp
+        );
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let (mut state, _rx) = task_state();
+        let protocols = [RtspProtocol::Tcp];
+
+        // Never connected, most likely a configuration problem
+        assert_eq!(
+            state.recovery(&connection_lost(), &settings(1), &protocols, 0),
+            Recovery::GiveUp
+        );
+
+        state.ever_established = true;
+        assert_eq!(
+            state.recovery(&connection_lost(), &settings(0), &protocols, 0),
+            Recovery::GiveUp
+        );
+        assert_eq!(
+            state.recovery(&connection_lost(), &settings(2), &protocols, 1),
+            Recovery::Reconnect
+        );
+        assert_eq!(
+            state.recovery(&connection_lost(), &settings(2), &protocols, 2),
+            Recovery::GiveUp
+        );
+        assert_eq!(
+            state.recovery(&connection_lost(), &settings(-1), &protocols, 1000),
+            Recovery::Reconnect
+        );
+        // Data not arriving over TCP
+        assert_eq!(
+            state.recovery(&data_timeout(), &settings(1), &protocols, 0),
+            Recovery::Reconnect
+        );
+
+        let fatal: anyhow::Error = RtspError::Fatal("SETUP request failed".to_string()).into();
+        assert_eq!(
+            state.recovery(&fatal, &settings(-1), &protocols, 0),
+            Recovery::GiveUp
+        );
+    }
+
+    #[test]
+    fn test_keep_alive() {
+        let (mut state, mut rx) = task_state();
+        let session = Session("test".to_string(), None);
+
+        assert_eq!(
+            state.keep_alive_interval(),
+            Duration::from_secs(DEFAULT_SESSION_TIMEOUT / 2)
+        );
+        state.session_timeout = Some(10);
+        assert_eq!(state.keep_alive_interval(), Duration::from_secs(5));
+        state.session_timeout = Some(1);
+        assert_eq!(state.keep_alive_interval(), Duration::from_secs(1));
+
+        state.keep_alive_method = Method::GetParameter;
+        let cseq = RUNTIME.block_on(state.keep_alive(&session)).unwrap();
+        let Ok(Some(Message::Request(req))) = rx.try_next() else {
+            panic!("no keep-alive request sent");
+        };
+        assert!(matches!(req.method(), Method::GetParameter));
+        assert_eq!(req.typed_header::<CSeq>().unwrap().map(|v| *v), Some(cseq));
+
+        let rsp = Response::builder(Version::V1_0, StatusCode::Ok)
+            .typed_header::<CSeq>(&cseq.into())
+            .build(Body::default());
+        state.keep_alive_response(&rsp, cseq, Method::GetParameter);
+        assert_eq!(state.keep_alive_method, Method::GetParameter);
+
+        // Servers not supporting GET_PARAMETER are kept alive with OPTIONS
+        let cseq = RUNTIME.block_on(state.keep_alive(&session)).unwrap();
+        let rsp = Response::builder(Version::V1_0, StatusCode::NotImplemented)
+            .typed_header::<CSeq>(&cseq.into())
+            .build(Body::default());
+        state.keep_alive_response(&rsp, cseq, Method::GetParameter);
+        assert_eq!(state.keep_alive_method, Method::Options);
+
+        rx.try_next().unwrap();
+
+        RUNTIME.block_on(state.keep_alive(&session)).unwrap();
+        let Ok(Some(Message::Request(req))) = rx.try_next() else {
+            panic!("no keep-alive request sent");
+        };
+        assert!(matches!(req.method(), Method::Options));
+    }
+}
@@ -21,6 +21,8 @@
  * * RTCP-based A/V sync
  * * Lower transport selection and priority (NEW!)
  *   - Also supports different lower transports for each SETUP
+ * * Session keep-alive, fallback from UDP to TCP, and reconnection
+ *   - See the `do-rtsp-keep-alive`, `reconnect-attempts` and `reconnect-delay` properties
  *
  * Some missing features:
  * * SET_PARAMETER/GET_PARAMETER messages
@@ -0,0 +1,305 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gst::prelude::*;
+
+const SESSION_ID: &str = "test-session";
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrsrtsp::plugin_register_static().expect("rtsp test");
+    });
+}
+
+// A request received by the fake server on the `conn`th connection
+#[derive(Debug)]
+struct ServerRequest {
+    conn: usize,
+    method: String,
+    transport: Option<String>,
+}
+
+// Minimal RTSP server serving one L16 audio stream. SETUP requests offering UDP are accepted but
+// no UDP packet is ever sent, TCP interleaved sessions receive RTP packets once playing.
+fn spawn_server(get_parameter: bool) -> (String, mpsc::Receiver<ServerRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let location = format!("rtsp://{}/test", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for (conn, stream) in listener.incoming().enumerate() {
+            let Ok(stream) = stream else {
+                break;
+            };
+            let tx = tx.clone();
+            thread::spawn(move || serve_connection(conn, stream, get_parameter, tx));
+        }
+    });
+
+    (location, rx)
+}
+
+fn serve_connection(
+    conn: usize,
+    mut stream: TcpStream,
+    get_parameter: bool,
+    tx: mpsc::Sender<ServerRequest>,
+) {
+    stream
+        .set_read_timeout(Some(Duration::from_millis(20)))
+        .unwrap();
+
+    let mut buf = Vec::new();
+    let mut interleaved = false;
+    let mut playing = false;
+    let mut seqnum = 0u16;
+    let mut last_packet = Instant::now();
+
+    loop {
+        if playing && interleaved && last_packet.elapsed() >= Duration::from_millis(20) {
+            last_packet = Instant::now();
+            if stream.write_all(&interleaved_rtp_packet(seqnum)).is_err() {
+                return;
+            }
+            seqnum = seqnum.wrapping_add(1);
+        }
+
+        let mut chunk = [0u8; 4096];
+        match stream.read(&mut chunk) {
+            Ok(0) => return,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => return,
+        }
+
+        loop {
+            // Skip RTCP sent by the client over the interleaved channels
+            if buf.first() == Some(&b'$') {
+                if buf.len() < 4 {
+                    break;
+                }
+                let len = 4 + u16::from_be_bytes([buf[2], buf[3]]) as usize;
+                if buf.len() < len {
+                    break;
+                }
+                buf.drain(..len);
+                continue;
+            }
+
+            let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+                break;
+            };
+            let request = String::from_utf8(buf.drain(..end + 4).collect()).unwrap();
+            let mut lines = request.lines();
+            let method = lines
+                .next()
+                .and_then(|line| line.split(' ').next())
+                .unwrap()
+                .to_string();
+            let header = |name: &str| {
+                request.lines().skip(1).find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    key.eq_ignore_ascii_case(name)
+                        .then(|| value.trim().to_string())
+                })
+            };
+            let cseq = header("CSeq").unwrap();
+            let transport = header("Transport");
+
+            let mut response = format!("RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\n");
+            let mut body = String::new();
+            match method.as_str() {
+                "OPTIONS" => {
+                    let methods = if get_parameter {
+                        "OPTIONS, DESCRIBE, SETUP, PLAY, TEARDOWN, GET_PARAMETER"
+                    } else {
+                        "OPTIONS, DESCRIBE, SETUP, PLAY, TEARDOWN"
+                    };
+                    response.push_str(&format!("Public: {methods}\r\n"));
+                }
+                "DESCRIBE" => {
+                    body = concat!(
+                        "v=0\r\n",
+                        "o=- 0 0 IN IP4 127.0.0.1\r\n",
+                        "s=Test\r\n",
+                        "c=IN IP4 127.0.0.1\r\n",
+                        "t=0 0\r\n",
+                        "m=audio 0 RTP/AVP 96\r\n",
+                        "a=rtpmap:96 L16/48000/1\r\n",
+                        "a=control:stream=0\r\n",
+                    )
+                    .to_string();
+                    response.push_str("Content-Type: application/sdp\r\n");
+                }
+                "SETUP" => {
+                    let offered = transport.as_deref().unwrap();
+                    // Prefer UDP, like most servers do
+                    let selected = offered
+                        .split(',')
+                        .find(|t| t.contains("client_port="))
+                        .map(|t| format!("{t};server_port=50000-50001"))
+                        .or_else(|| {
+                            offered
+                                .split(',')
+                                .find(|t| t.contains("TCP"))
+                                .map(str::to_string)
+                        })
+                        .unwrap();
+                    interleaved = !selected.contains("client_port=");
+                    response.push_str(&format!(
+                        "Transport: {selected}\r\nSession: {SESSION_ID};timeout=2\r\n"
+                    ));
+                }
+                "PLAY" => {
+                    playing = true;
+                    response.push_str(&format!("Session: {SESSION_ID}\r\nRange: npt=now-\r\n"));
+                }
+                _ => {
+                    response.push_str(&format!("Session: {SESSION_ID}\r\n"));
+                }
+            }
+            response.push_str(&format!("Content-Length: {}\r\n\r\n{body}", body.len()));
+            if stream.write_all(response.as_bytes()).is_err() {
+                return;
+            }
+
+            let _ = tx.send(ServerRequest {
+                conn,
+                method,
+                transport,
+            });
+        }
+    }
+}
+
+fn interleaved_rtp_packet(seqnum: u16) -> Vec<u8> {
+    let mut packet = vec![b'$', 0, 0, 16];
+    packet.extend_from_slice(&[0x80, 96]);
+    packet.extend_from_slice(&seqnum.to_be_bytes());
+    packet.extend_from_slice(&(seqnum as u32 * 2).to_be_bytes());
+    packet.extend_from_slice(&0x1234_5678u32.to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0]);
+    packet
+}
+
+// Runs rtspsrc2 against `location` until `done` returns true for the requests received so far,
+// and returns those requests together with the number of RTP appsrcs the element ended up with
+fn run_pipeline(
+    location: &str,
+    protocols: &str,
+    requests: &mpsc::Receiver<ServerRequest>,
+    done: impl Fn(&[ServerRequest]) -> bool,
+) -> (Vec<ServerRequest>, u32) {
+    let pipeline = gst::Pipeline::new();
+    let src = gst::ElementFactory::make("rtspsrc2")
+        .property("location", location)
+        .property("protocols", protocols)
+        .property("timeout", gst::ClockTime::from_seconds(1).nseconds())
+        .build()
+        .unwrap();
+    let sink = gst_app::AppSink::builder().sync(false).build();
+    pipeline.add_many([&src, sink.upcast_ref()]).unwrap();
+
+    let (pad_tx, pad_rx) = mpsc::channel();
+    let sinkpad = sink.static_pad("sink").unwrap();
+    src.connect_pad_added(move |_, pad| {
+        let _ = pad.link(&sinkpad);
+        let _ = pad_tx.send(pad.name());
+    });
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let mut received = Vec::new();
+    while !done(&received) {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match requests.recv_timeout(timeout) {
+            Ok(request) => received.push(request),
+            Err(_) => panic!("Timed out, received {received:#?}"),
+        }
+    }
+
+    let sample = sink.try_pull_sample(gst::ClockTime::from_seconds(5));
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    assert!(sample.is_some(), "No buffer received");
+    let pads = pad_rx.try_iter().collect::<Vec<_>>();
+    assert_eq!(pads, ["stream_0"]);
+
+    let bin = src.downcast_ref::<gst::Bin>().unwrap();
+    let appsrcs = bin
+        .children()
+        .iter()
+        .filter(|e| e.name().starts_with("rtp_appsrc_"))
+        .count() as u32;
+
+    (received, appsrcs)
+}
+
+#[test]
+fn test_udp_fallback_to_tcp() {
+    init();
+
+    let (location, requests) = spawn_server(false);
+    // The default of no reconnection attempts still allows falling back to TCP
+    let (received, appsrcs) = run_pipeline(&location, "udp,tcp", &requests, |received| {
+        // Wait for the first keep-alive after PLAY on the TCP connection
+        received
+            .iter()
+            .skip_while(|r| !(r.conn == 1 && r.method == "PLAY"))
+            .any(|r| r.conn == 1 && r.method == "OPTIONS")
+    });
+
+    let setups = received
+        .iter()
+        .filter(|r| r.method == "SETUP")
+        .collect::<Vec<_>>();
+    assert_eq!(setups.len(), 2, "{received:#?}");
+    assert_eq!(setups[0].conn, 0);
+    assert!(setups[0]
+        .transport
+        .as_ref()
+        .unwrap()
+        .contains("client_port="));
+    // The stream is set up again over TCP only on a new connection
+    assert_eq!(setups[1].conn, 1);
+    let transport = setups[1].transport.as_ref().unwrap();
+    assert!(transport.contains("RTP/AVP/TCP"), "{transport}");
+    assert!(transport.contains("interleaved=0-1"), "{transport}");
+    assert!(!transport.contains("client_port="), "{transport}");
+
+    // Server without GET_PARAMETER support is kept alive with OPTIONS
+    assert!(!received.iter().any(|r| r.method == "GET_PARAMETER"));
+
+    // The appsrc and src pad of the first connection are reused
+    assert_eq!(appsrcs, 1);
+}
+
+#[test]
+fn test_keep_alive_get_parameter() {
+    init();
+
+    let (location, requests) = spawn_server(true);
+    let (received, appsrcs) = run_pipeline(&location, "tcp", &requests, |received| {
+        received.iter().any(|r| r.method == "GET_PARAMETER")
+    });
+
+    assert!(received.iter().all(|r| r.conn == 0), "{received:#?}");
+    let options = received.iter().filter(|r| r.method == "OPTIONS").count();
+    assert_eq!(options, 1, "{received:#?}");
+    assert_eq!(appsrcs, 1);
+}
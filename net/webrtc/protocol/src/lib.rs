@@ -61,6 +61,10 @@ pub struct PeerStatus {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub peer_id: Option<String>,
+    /// The room the peer belongs to, peers without a room share the default one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub room: Option<String>,
 }
 
 impl PeerStatus {
@@ -81,6 +85,27 @@ pub struct StartSessionMessage {
     pub peer_id: String,
     /// An offer if the consumer peer wants the producer to answer
     pub offer: Option<String>,
+    /// The room the producer is expected to be in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub room: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+/// Ask the server for the producers of a room
+pub struct ListRoomMessage {
+    /// The room to list
+    pub room: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+/// Authenticate a peer
+pub struct AuthenticateMessage {
+    /// Token authenticating the peer, either presented when connecting
+    /// or sent by the peer itself
+    pub token: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -141,6 +166,9 @@ pub struct EndSessionMessage {
 pub enum IncomingMessage {
     /// Internal message to let know about new peers
     NewPeer,
+    /// Authenticate with a token, sent by the server on behalf of peers that
+    /// presented one when connecting, or by the peers themselves
+    Authenticate(AuthenticateMessage),
     /// Set current peer status
     SetPeerStatus(PeerStatus),
     /// Start a session with a producer peer
@@ -151,4 +179,6 @@ pub enum IncomingMessage {
     Peer(PeerMessage),
     /// Retrieve the current list of producers
     List,
+    /// Retrieve the current list of producers of a room
+    ListRoom(ListRoomMessage),
 }
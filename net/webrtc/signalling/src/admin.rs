@@ -0,0 +1,610 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::auth::Authenticator;
+use crate::server::Server;
+use gst_plugin_webrtc_protocol as p;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use warp::{Filter, Reply};
+
+const METRICS_PREFIX: &str = "gst_webrtc_signalling";
+
+/// A peer connected to the signalling server, as exposed by the admin API
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+    pub id: String,
+    pub roles: Vec<p::PeerRole>,
+    pub meta: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    /// Seconds since the UNIX epoch
+    pub connected_at: u64,
+}
+
+/// A session between two peers, as exposed by the admin API
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub id: String,
+    pub producer: String,
+    pub consumer: String,
+    /// Seconds since the UNIX epoch
+    pub started_at: u64,
+}
+
+#[derive(Default, Debug)]
+struct MonitorState {
+    peers: HashMap<String, PeerInfo>,
+    sessions: HashMap<String, SessionInfo>,
+    connections: u64,
+    sessions_started: u64,
+    messages_received: BTreeMap<&'static str, u64>,
+    messages_sent: BTreeMap<&'static str, u64>,
+    errors: u64,
+}
+
+/// Keeps track of the peers, sessions and traffic of a
+/// [`Handler`](crate::handlers::Handler), cloning it gives another
+/// handle to the same data
+#[derive(Default, Debug, Clone)]
+pub struct Monitor {
+    state: Arc<Mutex<MonitorState>>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn incoming_type(msg: &p::IncomingMessage) -> &'static str {
+    match msg {
+        p::IncomingMessage::NewPeer => "newPeer",
+        p::IncomingMessage::Authenticate(_) => "authenticate",
+        p::IncomingMessage::SetPeerStatus(_) => "setPeerStatus",
+        p::IncomingMessage::StartSession(_) => "startSession",
+        p::IncomingMessage::EndSession(_) => "endSession",
+        p::IncomingMessage::Peer(_) => "peer",
+        p::IncomingMessage::List => "list",
+        p::IncomingMessage::ListRoom(_) => "listRoom",
+    }
+}
+
+fn outgoing_type(msg: &p::OutgoingMessage) -> &'static str {
+    match msg {
+        p::OutgoingMessage::Welcome { .. } => "welcome",
+        p::OutgoingMessage::PeerStatusChanged(_) => "peerStatusChanged",
+        p::OutgoingMessage::StartSession { .. } => "startSession",
+        p::OutgoingMessage::SessionStarted { .. } => "sessionStarted",
+        p::OutgoingMessage::EndSession(_) => "endSession",
+        p::OutgoingMessage::Peer(_) => "peer",
+        p::OutgoingMessage::List { .. } => "list",
+        p::OutgoingMessage::Error { .. } => "error",
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {METRICS_PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {METRICS_PREFIX}_{name} {kind}");
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn connection_accepted(&self) {
+        self.state.lock().unwrap().connections += 1;
+    }
+
+    pub(crate) fn peer_added(&self, peer_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        // A peer may authenticate again on the same connection, keep its
+        // status
+        if state.peers.contains_key(peer_id) {
+            return;
+        }
+
+        state.peers.insert(
+            peer_id.to_string(),
+            PeerInfo {
+                id: peer_id.to_string(),
+                roles: vec![],
+                meta: None,
+                room: None,
+                connected_at: now(),
+            },
+        );
+    }
+
+    pub(crate) fn peer_status_changed(&self, peer_id: &str, status: &p::PeerStatus) {
+        if let Some(peer) = self.state.lock().unwrap().peers.get_mut(peer_id) {
+            peer.roles = status.roles.clone();
+            peer.meta = status.meta.clone();
+            peer.room = status.room.clone();
+        }
+    }
+
+    pub(crate) fn peer_removed(&self, peer_id: &str) {
+        self.state.lock().unwrap().peers.remove(peer_id);
+    }
+
+    pub(crate) fn session_started(&self, session_id: &str, producer_id: &str, consumer_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.sessions_started += 1;
+        state.sessions.insert(
+            session_id.to_string(),
+            SessionInfo {
+                id: session_id.to_string(),
+                producer: producer_id.to_string(),
+                consumer: consumer_id.to_string(),
+                started_at: now(),
+            },
+        );
+    }
+
+    pub(crate) fn session_ended(&self, session_id: &str) {
+        self.state.lock().unwrap().sessions.remove(session_id);
+    }
+
+    pub(crate) fn message_received(&self, msg: &p::IncomingMessage) {
+        *self
+            .state
+            .lock()
+            .unwrap()
+            .messages_received
+            .entry(incoming_type(msg))
+            .or_default() += 1;
+    }
+
+    pub(crate) fn message_sent(&self, msg: &p::OutgoingMessage) {
+        *self
+            .state
+            .lock()
+            .unwrap()
+            .messages_sent
+            .entry(outgoing_type(msg))
+            .or_default() += 1;
+    }
+
+    pub(crate) fn error(&self) {
+        self.state.lock().unwrap().errors += 1;
+    }
+
+    /// The connected peers, oldest first
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        let mut peers = self
+            .state
+            .lock()
+            .unwrap()
+            .peers
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        peers.sort_by(|a, b| (a.connected_at, &a.id).cmp(&(b.connected_at, &b.id)));
+        peers
+    }
+
+    /// Whether `peer_id` was welcomed and is still connected
+    pub fn has_peer(&self, peer_id: &str) -> bool {
+        self.state.lock().unwrap().peers.contains_key(peer_id)
+    }
+
+    /// The ongoing sessions, oldest first
+    pub fn sessions(&self) -> Vec<SessionInfo> {
+        let mut sessions = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        sessions.sort_by(|a, b| (a.started_at, &a.id).cmp(&(b.started_at, &b.id)));
+        sessions
+    }
+
+    /// Render the metrics in the Prometheus text exposition format
+    pub fn render_metrics(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+
+        header(&mut out, "peers", "gauge", "Number of connected peers");
+        let _ = writeln!(out, "{METRICS_PREFIX}_peers {}", state.peers.len());
+
+        header(
+            &mut out,
+            "peers_by_role",
+            "gauge",
+            "Number of connected peers with a given role",
+        );
+        for (role, label) in [
+            (p::PeerRole::Producer, "producer"),
+            (p::PeerRole::Listener, "listener"),
+        ] {
+            let count = state
+                .peers
+                .values()
+                .filter(|peer| peer.roles.contains(&role))
+                .count();
+            let _ = writeln!(
+                out,
+                "{METRICS_PREFIX}_peers_by_role{{role=\"{label}\"}} {count}"
+            );
+        }
+
+        header(&mut out, "sessions", "gauge", "Number of ongoing sessions");
+        let _ = writeln!(out, "{METRICS_PREFIX}_sessions {}", state.sessions.len());
+
+        header(
+            &mut out,
+            "connections_total",
+            "counter",
+            "Number of accepted WebSocket connections, authenticated or not",
+        );
+        let _ = writeln!(
+            out,
+            "{METRICS_PREFIX}_connections_total {}",
+            state.connections
+        );
+
+        header(
+            &mut out,
+            "sessions_started_total",
+            "counter",
+            "Number of sessions that were started",
+        );
+        let _ = writeln!(
+            out,
+            "{METRICS_PREFIX}_sessions_started_total {}",
+            state.sessions_started
+        );
+
+        for (name, help, counts) in [
+            (
+                "messages_received_total",
+                "Number of messages received from peers",
+                &state.messages_received,
+            ),
+            (
+                "messages_sent_total",
+                "Number of messages sent to peers",
+                &state.messages_sent,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            for (msg_type, count) in counts {
+                let _ = writeln!(
+                    out,
+                    "{METRICS_PREFIX}_{name}{{type=\"{msg_type}\"}} {count}"
+                );
+            }
+        }
+
+        header(
+            &mut out,
+            "errors_total",
+            "counter",
+            "Number of errors reported to peers",
+        );
+        let _ = writeln!(out, "{METRICS_PREFIX}_errors_total {}", state.errors);
+
+        out
+    }
+}
+
+/// Check the `Authorization` header of an admin API request
+fn authorize(
+    authenticator: Option<&dyn Authenticator>,
+    authorization: Option<&str>,
+) -> Result<(), warp::http::StatusCode> {
+    let Some(authenticator) = authenticator else {
+        return Err(warp::http::StatusCode::FORBIDDEN);
+    };
+
+    let token = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    match authenticator.authenticate(token) {
+        Ok(permissions) if permissions.admin => Ok(()),
+        Ok(_) => Err(warp::http::StatusCode::FORBIDDEN),
+        Err(_) => Err(warp::http::StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// The routes of the admin HTTP endpoint:
+///
+/// * `GET /metrics`: metrics in the Prometheus text format
+/// * `GET /api/peers`: JSON list of the connected peers
+/// * `GET /api/sessions`: JSON list of the ongoing sessions
+/// * `POST /api/peers/<peer-id>/kick`: close the connection of a peer
+///
+/// The `/api` routes require an `Authorization: Bearer <token>` header
+/// with a token `authenticator` grants the `admin` permission to, and
+/// are refused if there is no `authenticator`.
+pub fn routes(
+    server: Server,
+    monitor: Monitor,
+    authenticator: Option<Arc<dyn Authenticator>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let metrics = {
+        let monitor = monitor.clone();
+        warp::path!("metrics").and(warp::get()).map(move || {
+            warp::reply::with_header(
+                monitor.render_metrics(),
+                "content-type",
+                "text/plain; version=0.0.4",
+            )
+        })
+    };
+
+    let peers = {
+        let monitor = monitor.clone();
+        let authenticator = authenticator.clone();
+        warp::path!("api" / "peers")
+            .and(warp::get())
+            .and(warp::header::optional::<String>("authorization"))
+            .map(move |authorization: Option<String>| {
+                match authorize(authenticator.as_deref(), authorization.as_deref()) {
+                    Ok(()) => warp::reply::json(&monitor.peers()).into_response(),
+                    Err(status) => status.into_response(),
+                }
+            })
+    };
+
+    let sessions = {
+        let authenticator = authenticator.clone();
+        warp::path!("api" / "sessions")
+            .and(warp::get())
+            .and(warp::header::optional::<String>("authorization"))
+            .map(move |authorization: Option<String>| {
+                match authorize(authenticator.as_deref(), authorization.as_deref()) {
+                    Ok(()) => warp::reply::json(&monitor.sessions()).into_response(),
+                    Err(status) => status.into_response(),
+                }
+            })
+    };
+
+    let kick = warp::path!("api" / "peers" / String / "kick")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .map(move |peer_id: String, authorization: Option<String>| {
+            if let Err(status) = authorize(authenticator.as_deref(), authorization.as_deref()) {
+                return status;
+            }
+
+            if server.kick(&peer_id) {
+                warp::http::StatusCode::NO_CONTENT
+            } else {
+                warp::http::StatusCode::NOT_FOUND
+            }
+        });
+
+    metrics.or(peers).or(sessions).or(kick)
+}
+
+/// Bind the admin HTTP endpoint to `addr`, returning the bound address
+/// and the future serving it
+pub fn serve(
+    addr: impl Into<SocketAddr>,
+    server: Server,
+    monitor: Monitor,
+    authenticator: Option<Arc<dyn Authenticator>>,
+) -> Result<(SocketAddr, impl Future<Output = ()>), warp::Error> {
+    warp::serve(routes(server, monitor, authenticator)).try_bind_ephemeral(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::Handler;
+    use async_tungstenite::tungstenite::Message as WsMessage;
+    use futures::prelude::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    async fn wait_for(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("condition not reached");
+    }
+
+    fn has_line(body: &[u8], line: &str) -> bool {
+        std::str::from_utf8(body)
+            .unwrap()
+            .lines()
+            .any(|l| l == line)
+    }
+
+    #[test]
+    fn test_peer_added_twice() {
+        let monitor = Monitor::new();
+
+        monitor.peer_added("peer");
+        monitor.peer_status_changed(
+            "peer",
+            &p::PeerStatus {
+                roles: vec![p::PeerRole::Producer],
+                ..Default::default()
+            },
+        );
+        monitor.peer_added("peer");
+
+        let peers = monitor.peers();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].roles, vec![p::PeerRole::Producer]);
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let monitor = Monitor::new();
+        let monitor_clone = monitor.clone();
+        let mut server =
+            Server::spawn(move |stream| Handler::new(stream).with_monitor(monitor_clone))
+                .with_monitor(monitor.clone());
+        let tokens = crate::auth::StaticTokens::from_json(
+            r#"{"admin-secret": {"rooms": [], "admin": true}, "producer-secret": {"rooms": null, "produce": true}}"#,
+        )
+        .unwrap();
+        let routes = routes(server.clone(), monitor.clone(), Some(Arc::new(tokens)));
+
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+        let (peer_id, client) = futures::join!(
+            server.accept_async(server_stream),
+            async_tungstenite::tokio::client_async("ws://localhost", client_stream),
+        );
+        let peer_id = peer_id.unwrap();
+        let (mut ws, _) = client.unwrap();
+
+        let welcome = ws.next().await.unwrap().unwrap();
+        let welcome =
+            serde_json::from_str::<serde_json::Value>(welcome.to_text().unwrap()).unwrap();
+        assert_eq!(welcome, json!({"type": "welcome", "peerId": peer_id}));
+
+        for msg in [
+            json!({"type": "setPeerStatus", "roles": ["producer"], "meta": null}),
+            json!({"type": "newPeer"}),
+        ] {
+            ws.send(WsMessage::text(msg.to_string())).await.unwrap();
+        }
+        wait_for(|| {
+            monitor
+                .render_metrics()
+                .contains("messages_received_total{type=\"newPeer\"} 2")
+        })
+        .await;
+
+        let res = warp::test::request().path("/metrics").reply(&routes).await;
+        assert_eq!(res.status(), warp::http::StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], "text/plain; version=0.0.4");
+        for line in [
+            "gst_webrtc_signalling_peers 1",
+            "gst_webrtc_signalling_peers_by_role{role=\"producer\"} 1",
+            "gst_webrtc_signalling_connections_total 1",
+        ] {
+            assert!(has_line(res.body(), line), "{line} not in {:?}", res.body());
+        }
+
+        // Listing peers and sessions requires a token with the admin permission
+        for path in ["/api/peers", "/api/sessions"] {
+            for (authorization, status) in [
+                (None, warp::http::StatusCode::UNAUTHORIZED),
+                (
+                    Some("Bearer producer-secret"),
+                    warp::http::StatusCode::FORBIDDEN,
+                ),
+            ] {
+                let mut req = warp::test::request().path(path);
+                if let Some(authorization) = authorization {
+                    req = req.header("Authorization", authorization);
+                }
+                let res = req.reply(&routes).await;
+                assert_eq!(res.status(), status);
+            }
+        }
+
+        let res = warp::test::request()
+            .path("/api/peers")
+            .header("Authorization", "Bearer admin-secret")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status(), warp::http::StatusCode::OK);
+        let peers = serde_json::from_slice::<serde_json::Value>(res.body()).unwrap();
+        assert_eq!(peers[0]["id"], peer_id.as_str());
+        assert_eq!(peers[0]["roles"], json!(["producer"]));
+        assert!(peers[0].get("room").is_none());
+
+        let res = warp::test::request()
+            .path("/api/sessions")
+            .header("Authorization", "Bearer admin-secret")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status(), warp::http::StatusCode::OK);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(res.body()).unwrap(),
+            json!([])
+        );
+
+        let kick_path = format!("/api/peers/{peer_id}/kick");
+
+        // Kicking requires a POST
+        let res = warp::test::request().path(&kick_path).reply(&routes).await;
+        assert!(res.status().is_client_error());
+        assert_eq!(monitor.peers().len(), 1);
+
+        // Kicking requires a token with the admin permission
+        for (authorization, status) in [
+            (None, warp::http::StatusCode::UNAUTHORIZED),
+            (
+                Some("Bearer intruder"),
+                warp::http::StatusCode::UNAUTHORIZED,
+            ),
+            (
+                Some("Bearer producer-secret"),
+                warp::http::StatusCode::FORBIDDEN,
+            ),
+        ] {
+            let mut req = warp::test::request().method("POST").path(&kick_path);
+            if let Some(authorization) = authorization {
+                req = req.header("Authorization", authorization);
+            }
+            let res = req.reply(&routes).await;
+            assert_eq!(res.status(), status);
+        }
+        assert_eq!(monitor.peers().len(), 1);
+
+        let res = warp::test::request()
+            .method("POST")
+            .path(&kick_path)
+            .header("Authorization", "Bearer admin-secret")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status(), warp::http::StatusCode::NO_CONTENT);
+
+        // The connection is closed and the handler forgets about the peer
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(Ok(msg)) = ws.next().await {
+                if msg.is_close() {
+                    break;
+                }
+            }
+        })
+        .await
+        .expect("connection not closed");
+        wait_for(|| monitor.peers().is_empty()).await;
+
+        let res = warp::test::request()
+            .method("POST")
+            .path(&kick_path)
+            .header("Authorization", "Bearer admin-secret")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status(), warp::http::StatusCode::NOT_FOUND);
+
+        // Without an authenticator, the API is disabled
+        let routes = super::routes(server.clone(), monitor.clone(), None);
+        let res = warp::test::request()
+            .method("POST")
+            .path(&kick_path)
+            .header("Authorization", "Bearer admin-secret")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status(), warp::http::StatusCode::FORBIDDEN);
+        let res = warp::test::request()
+            .path("/api/peers")
+            .header("Authorization", "Bearer admin-secret")
+            .reply(&routes)
+            .await;
+        assert_eq!(res.status(), warp::http::StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What an authenticated peer is allowed to do, nothing unless granted.
+/// The `rooms` field is required when deserializing, `null` granting
+/// access to any room.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Permissions {
+    /// The rooms the peer can join, any room if `None`. Peers that
+    /// did not pick a room are in the default room, only accessible
+    /// when this is `None`.
+    #[serde(deserialize_with = "deserialize_rooms")]
+    pub rooms: Option<Vec<String>>,
+    /// Whether the peer can register as a producer
+    #[serde(default)]
+    pub produce: bool,
+    /// Whether the peer can list producers and start sessions with them
+    #[serde(default)]
+    pub consume: bool,
+    /// Whether the peer can register as a listener
+    #[serde(default)]
+    pub listen: bool,
+    /// Maximum number of concurrent sessions of the peer, overrides the
+    /// limit of the handler
+    #[serde(default)]
+    pub max_sessions: Option<usize>,
+    /// Whether the token can be used on the admin HTTP endpoint to kick
+    /// peers
+    #[serde(default)]
+    pub admin: bool,
+}
+
+// Unlike the other fields, a missing `rooms` is an error rather than
+// `None`, so that a token can't grant access to every room by omission
+fn deserialize_rooms<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+    Option::deserialize(deserializer)
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            rooms: Some(vec![]),
+            produce: false,
+            consume: false,
+            listen: false,
+            max_sessions: None,
+            admin: false,
+        }
+    }
+}
+
+impl Permissions {
+    /// Permissions of the peers when authentication is disabled, every
+    /// role in any room
+    pub fn all() -> Self {
+        Self {
+            rooms: None,
+            produce: true,
+            consume: true,
+            listen: true,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the peer can join `room`, `None` being the default room
+    pub fn can_join(&self, room: Option<&str>) -> bool {
+        match (&self.rooms, room) {
+            (None, _) => true,
+            (Some(rooms), Some(room)) => rooms.iter().any(|r| r == room),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AuthError {
+    #[error("missing token")]
+    MissingToken,
+    #[error("unknown token")]
+    UnknownToken,
+    #[error("invalid token: {0}")]
+    InvalidToken(#[from] jsonwebtoken::errors::Error),
+}
+
+/// Checks the tokens presented by peers, either in the `Authorization`
+/// header or the `token` query parameter when connecting, or in a
+/// `newPeer` message
+pub trait Authenticator: Send + Sync {
+    fn authenticate(&self, token: Option<&str>) -> Result<Permissions, AuthError>;
+}
+
+impl<T: Authenticator + ?Sized> Authenticator for Arc<T> {
+    fn authenticate(&self, token: Option<&str>) -> Result<Permissions, AuthError> {
+        (**self).authenticate(token)
+    }
+}
+
+/// Accepts a fixed list of tokens
+#[derive(Debug, Default)]
+pub struct StaticTokens {
+    tokens: HashMap<String, Permissions>,
+}
+
+impl StaticTokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `token`, granting `permissions`
+    pub fn add_token(&mut self, token: impl Into<String>, permissions: Permissions) {
+        self.tokens.insert(token.into(), permissions);
+    }
+
+    /// Parse a JSON object mapping tokens to their permissions, e.g.
+    /// `{"secret": {"rooms": null, "produce": true}, "viewer": {"rooms": ["lobby"], "consume": true}}`
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            tokens: serde_json::from_str(json)?,
+        })
+    }
+}
+
+impl Authenticator for StaticTokens {
+    fn authenticate(&self, token: Option<&str>) -> Result<Permissions, AuthError> {
+        let token = token.ok_or(AuthError::MissingToken)?;
+
+        self.tokens
+            .get(token)
+            .cloned()
+            .ok_or(AuthError::UnknownToken)
+    }
+}
+
+/// Verifies JSON Web Tokens, the permissions are read from the claims
+/// with the same names as the [`Permissions`] fields. Tokens must have
+/// `exp` and `rooms` claims.
+pub struct Jwt {
+    key: jsonwebtoken::DecodingKey,
+    validation: jsonwebtoken::Validation,
+}
+
+impl Jwt {
+    /// Verify HS256 tokens signed with `secret`
+    pub fn with_secret(secret: &[u8]) -> Self {
+        Self {
+            key: jsonwebtoken::DecodingKey::from_secret(secret),
+            validation: jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256),
+        }
+    }
+
+    /// Verify RS256 tokens with the DER encoded PKCS#1 RSA public key, as
+    /// output by `openssl rsa -pubin -RSAPublicKey_out -outform DER`
+    pub fn with_rsa_der(der: &[u8]) -> Self {
+        Self {
+            key: jsonwebtoken::DecodingKey::from_rsa_der(der),
+            validation: jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::RS256),
+        }
+    }
+
+    /// Only accept tokens whose `aud` claim contains `audience`
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.validation.set_audience(&[audience]);
+        self
+    }
+}
+
+impl Authenticator for Jwt {
+    fn authenticate(&self, token: Option<&str>) -> Result<Permissions, AuthError> {
+        let token = token.ok_or(AuthError::MissingToken)?;
+
+        Ok(jsonwebtoken::decode::<Permissions>(token, &self.key, &self.validation)?.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_tokens() {
+        let auth = StaticTokens::from_json(
+            r#"{"admin": {"rooms": [], "admin": true}, "viewer": {"rooms": ["lobby"], "consume": true, "maxSessions": 2}, "producer": {"rooms": null, "produce": true}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            auth.authenticate(Some("admin")).unwrap(),
+            Permissions {
+                admin: true,
+                ..Default::default()
+            }
+        );
+
+        let viewer = auth.authenticate(Some("viewer")).unwrap();
+        assert!(!viewer.admin);
+        assert!(!viewer.produce);
+        assert!(viewer.consume);
+        assert!(!viewer.listen);
+        assert_eq!(viewer.max_sessions, Some(2));
+        assert!(viewer.can_join(Some("lobby")));
+        assert!(!viewer.can_join(Some("backstage")));
+        assert!(!viewer.can_join(None));
+
+        let producer = auth.authenticate(Some("producer")).unwrap();
+        assert!(producer.produce);
+        assert!(!producer.consume);
+        assert!(producer.can_join(Some("backstage")));
+        assert!(producer.can_join(None));
+
+        // Tokens have to say which rooms they grant access to
+        assert!(StaticTokens::from_json(r#"{"secret": {"produce": true}}"#).is_err());
+
+        assert!(matches!(
+            auth.authenticate(Some("intruder")),
+            Err(AuthError::UnknownToken)
+        ));
+        assert!(matches!(
+            auth.authenticate(None),
+            Err(AuthError::MissingToken)
+        ));
+    }
+
+    #[test]
+    fn test_jwt() {
+        #[derive(serde::Serialize)]
+        struct Claims {
+            exp: u64,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            rooms: Option<Vec<String>>,
+            listen: bool,
+        }
+
+        let exp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 60;
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &Claims {
+                exp,
+                rooms: Some(vec!["lobby".to_string()]),
+                listen: true,
+            },
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        let permissions = Jwt::with_secret(b"secret")
+            .authenticate(Some(&token))
+            .unwrap();
+        assert_eq!(
+            permissions,
+            Permissions {
+                rooms: Some(vec!["lobby".to_string()]),
+                listen: true,
+                ..Default::default()
+            }
+        );
+
+        // A token without a `rooms` claim grants nothing
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &Claims {
+                exp,
+                rooms: None,
+                listen: true,
+            },
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        assert!(matches!(
+            Jwt::with_secret(b"secret").authenticate(Some(&token)),
+            Err(AuthError::InvalidToken(_))
+        ));
+
+        assert!(matches!(
+            Jwt::with_secret(b"other secret").authenticate(Some(&token)),
+            Err(AuthError::InvalidToken(_))
+        ));
+        assert!(matches!(
+            Jwt::with_secret(b"secret").authenticate(Some("not a token")),
+            Err(AuthError::InvalidToken(_))
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use clap::Parser;
+use gst_plugin_webrtc_signalling::admin::{self, Monitor};
+use gst_plugin_webrtc_signalling::auth::{Authenticator, Jwt, StaticTokens};
 use gst_plugin_webrtc_signalling::handlers::Handler;
 use gst_plugin_webrtc_signalling::server::{Server, ServerError};
 use tokio::io::AsyncReadExt;
@@ -8,6 +10,8 @@ use tokio::task;
 use tracing_subscriber::prelude::*;
 
 use anyhow::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::fs;
 use tokio::net::TcpListener;
@@ -18,6 +22,7 @@ const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
+#[clap(group(clap::ArgGroup::new("jwt_key").args(["jwt_secret", "jwt_public_key"])))]
 /// Program arguments
 struct Args {
     /// Address to listen on
@@ -32,6 +37,32 @@ struct Args {
     /// password to TLS certificate
     #[clap(long)]
     cert_password: Option<String>,
+    /// JSON file mapping the accepted tokens to their permissions
+    #[clap(long, conflicts_with_all = ["jwt_secret", "jwt_public_key"])]
+    tokens_file: Option<String>,
+    /// Secret used to verify HS256 JSON Web Tokens
+    #[clap(long, conflicts_with = "jwt_public_key")]
+    jwt_secret: Option<String>,
+    /// DER file with the RSA public key used to verify RS256 JSON Web Tokens
+    #[clap(long)]
+    jwt_public_key: Option<String>,
+    /// Audience JSON Web Tokens must have been issued for
+    #[clap(long, requires = "jwt_key")]
+    jwt_audience: Option<String>,
+    /// Seconds peers have to authenticate before being disconnected, when
+    /// tokens are required
+    #[clap(long, default_value_t = 10)]
+    auth_timeout: u64,
+    /// Maximum number of concurrent sessions per peer
+    #[clap(long)]
+    max_sessions: Option<usize>,
+    /// Address the admin HTTP endpoint listens on
+    #[clap(long, default_value = "127.0.0.1")]
+    admin_host: String,
+    /// Port of the admin HTTP endpoint serving Prometheus metrics on
+    /// /metrics and the admin API on /api, disabled if unset
+    #[clap(long)]
+    admin_port: Option<u16>,
 }
 
 fn initialize_logging(envvar_name: &str) -> Result<(), Error> {
@@ -56,7 +87,41 @@ fn initialize_logging(envvar_name: &str) -> Result<(), Error> {
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     let args = Args::parse();
-    let server = Server::spawn(Handler::new);
+
+    let jwt = match (args.jwt_secret, args.jwt_public_key) {
+        (Some(secret), _) => Some(Jwt::with_secret(secret.as_bytes())),
+        (None, Some(path)) => Some(Jwt::with_rsa_der(&fs::read(path).await?)),
+        (None, None) => None,
+    }
+    .map(|jwt| match args.jwt_audience.as_deref() {
+        Some(audience) => jwt.with_audience(audience),
+        None => jwt,
+    });
+    let authenticator: Option<Arc<dyn Authenticator>> = match args.tokens_file {
+        Some(path) => Some(Arc::new(StaticTokens::from_json(
+            &fs::read_to_string(path).await?,
+        )?)),
+        None => jwt.map(|jwt| Arc::new(jwt) as Arc<dyn Authenticator>),
+    };
+    let max_sessions = args.max_sessions;
+    let auth_timeout = authenticator
+        .is_some()
+        .then(|| Duration::from_secs(args.auth_timeout));
+    let monitor = Monitor::new();
+
+    let monitor_clone = monitor.clone();
+    let authenticator_clone = authenticator.clone();
+    let server = Server::spawn(move |stream| {
+        let mut handler = Handler::new(stream).with_monitor(monitor_clone);
+        if let Some(authenticator) = authenticator_clone {
+            handler = handler.with_authenticator(authenticator);
+        }
+        if let Some(max_sessions) = max_sessions {
+            handler = handler.with_max_sessions(max_sessions);
+        }
+        handler
+    })
+    .with_monitor(monitor.clone());
 
     initialize_logging("WEBRTCSINK_SIGNALLING_SERVER_LOG")?;
 
@@ -84,12 +149,22 @@ async fn main() -> Result<(), Error> {
 
     info!("Listening on: {}", addr);
 
+    if let Some(admin_port) = args.admin_port {
+        let admin_addr: SocketAddr = format!("{}:{}", args.admin_host, admin_port).parse()?;
+        let (admin_addr, admin_server) =
+            admin::serve(admin_addr, server.clone(), monitor.clone(), authenticator)?;
+        info!("Admin endpoint listening on: {}", admin_addr);
+        task::spawn(admin_server);
+    }
+
     while let Ok((stream, address)) = listener.accept().await {
         let mut server_clone = server.clone();
+        let monitor_clone = monitor.clone();
         info!("Accepting connection from {}", address);
 
-        if let Some(acceptor) = acceptor.clone() {
-            tokio::spawn(async move {
+        let acceptor = acceptor.clone();
+        task::spawn(async move {
+            let peer_id = if let Some(acceptor) = acceptor {
                 match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
                     Ok(Ok(stream)) => server_clone.accept_async(stream).await,
                     Ok(Err(err)) => {
@@ -101,10 +176,20 @@ async fn main() -> Result<(), Error> {
                         Err(ServerError::TLSHandshakeTimeout(elapsed))
                     }
                 }
-            });
-        } else {
-            task::spawn(async move { server_clone.accept_async(stream).await });
-        }
+            } else {
+                server_clone.accept_async(stream).await
+            }?;
+
+            // Peers are only welcomed once authenticated
+            if let Some(auth_timeout) = auth_timeout {
+                tokio::time::sleep(auth_timeout).await;
+                if !monitor_clone.has_peer(&peer_id) && server_clone.kick(&peer_id) {
+                    warn!("{} did not authenticate after {:?}", address, auth_timeout);
+                }
+            }
+
+            Ok::<_, ServerError>(peer_id)
+        });
     }
 
     Ok(())
@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use crate::admin::Monitor;
+use crate::auth::{Authenticator, Permissions};
 use anyhow::{anyhow, Error};
 use anyhow::{bail, Context};
 use futures::prelude::*;
@@ -44,6 +46,10 @@ pin_project! {
         sessions: HashMap<String, Session>,
         consumer_sessions: HashMap<String, HashSet<String>>,
         producer_sessions: HashMap<String, HashSet<String>>,
+        authenticator: Option<Box<dyn Authenticator>>,
+        permissions: HashMap<PeerId, Permissions>,
+        max_sessions: Option<usize>,
+        monitor: Monitor,
     }
 }
 
@@ -60,6 +66,71 @@ impl Handler {
             sessions: Default::default(),
             consumer_sessions: Default::default(),
             producer_sessions: Default::default(),
+            authenticator: None,
+            permissions: Default::default(),
+            max_sessions: None,
+            monitor: Default::default(),
+        }
+    }
+
+    /// Require peers to authenticate, peers are otherwise allowed to do anything
+    pub fn with_authenticator(mut self, authenticator: impl Authenticator + 'static) -> Self {
+        self.authenticator = Some(Box::new(authenticator));
+        self
+    }
+
+    /// Limit the number of concurrent sessions of each peer, unless their
+    /// permissions say otherwise
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = Some(max_sessions);
+        self
+    }
+
+    /// Keep `monitor` up to date with the peers, sessions and traffic
+    pub fn with_monitor(mut self, monitor: Monitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    fn peer_permissions(&self, peer_id: &str) -> Result<Permissions, Error> {
+        match self.permissions.get(peer_id) {
+            Some(permissions) => Ok(permissions.clone()),
+            None if self.authenticator.is_none() => Ok(Permissions::all()),
+            None => bail!("Peer '{peer_id}' is not authenticated"),
+        }
+    }
+
+    fn check_session_limit(
+        &self,
+        peer_id: &str,
+        permissions: &Permissions,
+        sessions: &HashMap<String, HashSet<String>>,
+    ) -> Result<(), Error> {
+        if let Some(max_sessions) = permissions.max_sessions.or(self.max_sessions) {
+            if sessions.get(peer_id).map_or(0, HashSet::len) >= max_sessions {
+                bail!("Peer '{peer_id}' reached its limit of {max_sessions} sessions");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Notify the listeners of the room of `status` that it changed
+    fn notify_listeners(&mut self, peer_id: &str, status: &PeerStatus) {
+        for (id, peer) in &self.peers {
+            if !peer.listening() || peer.room != status.room {
+                continue;
+            }
+
+            self.items.push_back((
+                id.to_string(),
+                p::OutgoingMessage::PeerStatusChanged(p::PeerStatus {
+                    peer_id: Some(peer_id.to_string()),
+                    roles: status.roles.clone(),
+                    meta: status.meta.clone(),
+                    room: status.room.clone(),
+                }),
+            ));
         }
     }
 
@@ -70,27 +141,53 @@ impl Handler {
         msg: p::IncomingMessage,
     ) -> Result<(), Error> {
         match msg {
-            p::IncomingMessage::NewPeer => {
-                self.peers.insert(peer_id.to_string(), Default::default());
-                self.items.push_back((
-                    peer_id.into(),
-                    p::OutgoingMessage::Welcome {
-                        peer_id: peer_id.to_string(),
-                    },
-                ));
-
-                Ok(())
+            p::IncomingMessage::NewPeer => self.new_peer(peer_id, None),
+            p::IncomingMessage::Authenticate(message) => {
+                self.new_peer(peer_id, Some(&message.token))
             }
             p::IncomingMessage::SetPeerStatus(status) => self.set_peer_status(peer_id, &status),
-            p::IncomingMessage::StartSession(message) => {
-                self.start_session(&message.peer_id, peer_id, message.offer.as_deref())
-            }
+            p::IncomingMessage::StartSession(message) => self.start_session(
+                &message.peer_id,
+                peer_id,
+                message.offer.as_deref(),
+                message.room.as_deref(),
+            ),
             p::IncomingMessage::Peer(peermsg) => self.handle_peer_message(peer_id, peermsg),
-            p::IncomingMessage::List => self.list_producers(peer_id),
+            p::IncomingMessage::List => self.list_producers(peer_id, None),
+            p::IncomingMessage::ListRoom(message) => {
+                self.list_producers(peer_id, Some(message.room))
+            }
             p::IncomingMessage::EndSession(msg) => self.end_session(peer_id, &msg.session_id),
         }
     }
 
+    fn new_peer(&mut self, peer_id: &str, token: Option<&str>) -> Result<(), Error> {
+        if let Some(authenticator) = &self.authenticator {
+            let permissions = authenticator
+                .authenticate(token)
+                .map_err(|err| anyhow!("Authentication failed: {err}"))?;
+            self.permissions.insert(peer_id.to_string(), permissions);
+        }
+
+        // A known peer authenticating again keeps its status and sessions
+        // as far as its new permissions allow
+        if self.peers.contains_key(peer_id) {
+            self.enforce_permissions(peer_id)?;
+            return Ok(());
+        }
+
+        self.peers.insert(peer_id.to_string(), Default::default());
+        self.monitor.peer_added(peer_id);
+        self.items.push_back((
+            peer_id.into(),
+            p::OutgoingMessage::Welcome {
+                peer_id: peer_id.to_string(),
+            },
+        ));
+
+        Ok(())
+    }
+
     fn handle_peer_message(&mut self, peer_id: &str, peermsg: p::PeerMessage) -> Result<(), Error> {
         let session_id = &peermsg.session_id;
         let session = self
@@ -141,6 +238,94 @@ impl Handler {
         }
     }
 
+    /// End a session on behalf of the server, letting both peers know
+    fn revoke_session(&mut self, session_id: &str) {
+        let Some(producer_id) = self
+            .sessions
+            .get(session_id)
+            .map(|session| session.producer.clone())
+        else {
+            return;
+        };
+
+        if let Err(e) = self.end_session(&producer_id, session_id) {
+            error!("Could not end session {session_id}: {e:?}");
+            return;
+        }
+
+        self.items.push_back((
+            producer_id,
+            p::OutgoingMessage::EndSession(p::EndSessionMessage {
+                session_id: session_id.to_string(),
+            }),
+        ));
+    }
+
+    /// Reset the status and end the sessions of a peer that its
+    /// permissions no longer allow
+    #[instrument(level = "debug", skip(self))]
+    fn enforce_permissions(&mut self, peer_id: &str) -> Result<(), Error> {
+        let permissions = self.peer_permissions(peer_id)?;
+        let status = self
+            .peers
+            .get(peer_id)
+            .context(anyhow!("Peer '{peer_id}' hasn't been welcomed"))?
+            .clone();
+
+        let status_allowed = (!status.producing() || permissions.produce)
+            && (!status.listening() || permissions.listen)
+            && (status.roles.is_empty() || permissions.can_join(status.room.as_deref()));
+
+        let mut revoked = vec![];
+        if !status_allowed {
+            revoked.extend(self.producer_sessions.remove(peer_id).unwrap_or_default());
+        }
+        for session_id in self.consumer_sessions.get(peer_id).into_iter().flatten() {
+            let producer_room = self
+                .sessions
+                .get(session_id)
+                .and_then(|session| self.peers.get(&session.producer))
+                .and_then(|producer| producer.room.as_deref());
+            if !permissions.consume || !permissions.can_join(producer_room) {
+                revoked.push(session_id.clone());
+            }
+        }
+        for session_id in revoked {
+            info!(peer_id = %peer_id, session_id = %session_id, "session no longer allowed");
+            self.revoke_session(&session_id);
+        }
+
+        if !status_allowed {
+            info!(peer_id = %peer_id, "status no longer allowed, resetting it");
+
+            self.notify_listeners(
+                peer_id,
+                &PeerStatus {
+                    roles: Default::default(),
+                    ..status.clone()
+                },
+            );
+
+            let status = PeerStatus {
+                peer_id: Some(peer_id.to_string()),
+                meta: status.meta,
+                ..Default::default()
+            };
+            self.peers.insert(peer_id.to_string(), status.clone());
+            self.monitor.peer_status_changed(peer_id, &status);
+            self.items.push_back((
+                peer_id.to_string(),
+                p::OutgoingMessage::Error {
+                    details: format!(
+                        "Status of peer '{peer_id}' was reset as its permissions changed"
+                    ),
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
     #[instrument(level = "debug", skip(self))]
     /// Remove a peer, this can cause sessions to be ended
     fn remove_peer(&mut self, peer_id: &str) {
@@ -150,21 +335,18 @@ impl Handler {
             _ => return,
         };
 
+        self.permissions.remove(peer_id);
+        self.monitor.peer_removed(peer_id);
         self.stop_producer(peer_id);
         self.stop_consumer(peer_id);
 
-        for (id, p) in self.peers.iter() {
-            if !p.listening() {
-                continue;
-            }
-
-            let message = p::OutgoingMessage::PeerStatusChanged(PeerStatus {
+        self.notify_listeners(
+            peer_id,
+            &PeerStatus {
                 roles: Default::default(),
-                meta: peer_status.meta.clone(),
-                peer_id: Some(peer_id.to_string()),
-            });
-            self.items.push_back((id.to_string(), message));
-        }
+                ..peer_status
+            },
+        );
     }
 
     #[instrument(level = "debug", skip(self))]
@@ -174,6 +356,7 @@ impl Handler {
             .sessions
             .remove(session_id)
             .with_context(|| format!("Session {session_id} doesn't exist"))?;
+        self.monitor.session_ended(session_id);
 
         self.consumer_sessions
             .entry(session.consumer.clone())
@@ -197,9 +380,19 @@ impl Handler {
         Ok(())
     }
 
-    /// List producer peers
+    /// List the producer peers of a room, the room of the peer by default
     #[instrument(level = "debug", skip(self))]
-    fn list_producers(&mut self, peer_id: &str) -> Result<(), Error> {
+    fn list_producers(&mut self, peer_id: &str, room: Option<String>) -> Result<(), Error> {
+        let permissions = self.peer_permissions(peer_id)?;
+        if !permissions.consume && !permissions.listen {
+            bail!("Peer '{peer_id}' is not allowed to list producers");
+        }
+
+        let room = room.or_else(|| self.peers.get(peer_id).and_then(|peer| peer.room.clone()));
+        if !permissions.can_join(room.as_deref()) {
+            bail!("Peer '{peer_id}' is not allowed in room {room:?}");
+        }
+
         self.items.push_back((
             peer_id.to_string(),
             p::OutgoingMessage::List {
@@ -207,7 +400,7 @@ impl Handler {
                     .peers
                     .iter()
                     .filter_map(|(peer_id, peer)| {
-                        peer.producing().then_some(p::Peer {
+                        (peer.producing() && peer.room == room).then_some(p::Peer {
                             id: peer_id.clone(),
                             meta: peer.meta.clone(),
                         })
@@ -233,28 +426,61 @@ impl Handler {
             return Ok(());
         }
 
-        if old_status.producing() && !status.producing() {
+        let permissions = self.peer_permissions(peer_id)?;
+        if status.producing() && !permissions.produce {
+            bail!("Peer '{peer_id}' is not allowed to produce");
+        }
+        if status.listening() && !permissions.listen {
+            bail!("Peer '{peer_id}' is not allowed to listen");
+        }
+        if !permissions.can_join(status.room.as_deref()) {
+            bail!("Peer '{peer_id}' is not allowed in room {:?}", status.room);
+        }
+
+        let old_status = old_status.clone();
+        let room_changed = old_status.room != status.room;
+
+        if old_status.producing() && (!status.producing() || room_changed) {
             self.stop_producer(peer_id);
         }
 
-        let mut status = status.clone();
-        status.peer_id = Some(peer_id.to_string());
-        self.peers.insert(peer_id.to_string(), status.clone());
-        for (id, peer) in &self.peers {
-            if !peer.listening() {
-                continue;
+        // Leaving a room also ends the sessions with its producers
+        if room_changed {
+            let session_ids = self
+                .consumer_sessions
+                .get(peer_id)
+                .into_iter()
+                .flatten()
+                .filter(|session_id| {
+                    self.sessions
+                        .get(*session_id)
+                        .and_then(|session| self.peers.get(&session.producer))
+                        .is_some_and(|producer| producer.room == old_status.room)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            for session_id in session_ids {
+                self.revoke_session(&session_id);
             }
+        }
 
-            self.items.push_back((
-                id.to_string(),
-                p::OutgoingMessage::PeerStatusChanged(p::PeerStatus {
-                    peer_id: Some(peer_id.to_string()),
-                    roles: status.roles.clone(),
-                    meta: status.meta.clone(),
-                }),
-            ));
+        // Let the listeners of the previous room know the peer left it
+        if room_changed && !old_status.roles.is_empty() {
+            self.notify_listeners(
+                peer_id,
+                &PeerStatus {
+                    roles: Default::default(),
+                    ..old_status
+                },
+            );
         }
 
+        let mut status = status.clone();
+        status.peer_id = Some(peer_id.to_string());
+        self.peers.insert(peer_id.to_string(), status.clone());
+        self.monitor.peer_status_changed(peer_id, &status);
+        self.notify_listeners(peer_id, &status);
+
         info!(peer_id = %peer_id, "registered as a producer");
 
         Ok(())
@@ -267,8 +493,14 @@ impl Handler {
         producer_id: &str,
         consumer_id: &str,
         offer: Option<&str>,
+        room: Option<&str>,
     ) -> Result<(), Error> {
-        self.peers.get(producer_id).map_or_else(
+        let consumer_permissions = self.peer_permissions(consumer_id)?;
+        if !consumer_permissions.consume {
+            bail!("Peer '{consumer_id}' is not allowed to consume");
+        }
+
+        let producer = self.peers.get(producer_id).map_or_else(
             || Err(anyhow!("No producer with ID: '{producer_id}'")),
             |peer| {
                 if !peer.producing() {
@@ -282,10 +514,21 @@ impl Handler {
             },
         )?;
 
+        // Producers of other rooms are not visible
+        if room.is_some_and(|room| producer.room.as_deref() != Some(room))
+            || !consumer_permissions.can_join(producer.room.as_deref())
+        {
+            bail!("No producer with ID: '{producer_id}'");
+        }
+
         self.peers
             .get(consumer_id)
             .map_or_else(|| Err(anyhow!("No consumer with ID: '{consumer_id}'")), Ok)?;
 
+        self.check_session_limit(consumer_id, &consumer_permissions, &self.consumer_sessions)?;
+        let producer_permissions = self.peer_permissions(producer_id)?;
+        self.check_session_limit(producer_id, &producer_permissions, &self.producer_sessions)?;
+
         let session_id = uuid::Uuid::new_v4().to_string();
         self.sessions.insert(
             session_id.clone(),
@@ -295,6 +538,8 @@ impl Handler {
                 producer: producer_id.to_string(),
             },
         );
+        self.monitor
+            .session_started(&session_id, producer_id, consumer_id);
         self.consumer_sessions
             .entry(consumer_id.to_string())
             .or_default()
@@ -333,13 +578,16 @@ impl Stream for Handler {
             let this = self.as_mut().project();
 
             if let Some(item) = this.items.pop_front() {
+                this.monitor.message_sent(&item.1);
                 break Poll::Ready(Some(item));
             }
 
             match ready!(this.stream.poll_next(cx)) {
                 Some((peer_id, msg)) => {
                     if let Some(msg) = msg {
+                        self.monitor.message_received(&msg);
                         if let Err(err) = self.as_mut().handle(&peer_id, msg) {
+                            self.monitor.error();
                             self.items.push_back((
                                 peer_id.to_string(),
                                 p::OutgoingMessage::Error {
@@ -399,6 +647,7 @@ mod tests {
                 roles: vec![p::PeerRole::Producer],
                 meta: None,
                 peer_id: None,
+                room: None,
             })),
         ))
         .await
@@ -416,6 +665,7 @@ mod tests {
             meta: Some(json!({"display-name":"foobar".to_string()})),
             roles: vec![p::PeerRole::Producer],
             peer_id: None,
+            room: None,
         });
 
         tx.send(("producer".to_string(), Some(message)))
@@ -463,6 +713,7 @@ mod tests {
             roles: vec![p::PeerRole::Listener],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("listener".to_string(), Some(message)))
             .await
@@ -476,6 +727,7 @@ mod tests {
                 "display-name": "foobar".to_string(),
             })),
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -491,7 +743,8 @@ mod tests {
                 meta: Some(json!({
                         "display-name": Some("foobar".to_string()),
                     }
-                ))
+                )),
+                room: None,
             })
         );
     }
@@ -507,6 +760,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -517,6 +771,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -558,6 +813,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -568,6 +824,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -603,6 +860,7 @@ mod tests {
             roles: vec![p::PeerRole::Listener],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("listener".to_string(), Some(message)))
             .await
@@ -642,6 +900,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -652,6 +911,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -698,6 +958,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -708,6 +969,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -748,6 +1010,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -758,6 +1021,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -803,6 +1067,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -813,6 +1078,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -878,6 +1144,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -888,6 +1155,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -941,6 +1209,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -951,6 +1220,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1030,6 +1300,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -1040,6 +1311,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1090,6 +1362,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1116,6 +1389,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -1126,6 +1400,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1159,6 +1434,7 @@ mod tests {
             roles: vec![],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -1185,6 +1461,7 @@ mod tests {
             roles: vec![p::PeerRole::Listener],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("listener".to_string(), Some(message)))
             .await
@@ -1196,6 +1473,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -1217,6 +1495,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1251,6 +1530,7 @@ mod tests {
             roles: vec![],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -1289,6 +1569,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -1297,6 +1578,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1322,6 +1604,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer],
             meta: Some(json!( {"display-name": "foobar".to_string() })),
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -1332,6 +1615,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1354,6 +1638,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
 
         tx.send(("consumer".to_string(), Some(message)))
@@ -1385,6 +1670,7 @@ mod tests {
             roles: vec![p::PeerRole::Producer, p::PeerRole::Listener],
             meta: None,
             peer_id: None,
+            room: None,
         });
         tx.send(("producer".to_string(), Some(message)))
             .await
@@ -1404,6 +1690,7 @@ mod tests {
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
             offer: None,
+            room: None,
         });
         tx.send(("producer-consumer".to_string(), Some(message)))
             .await
@@ -1441,4 +1728,505 @@ mod tests {
             .get(&session0_id)
             .expect("Session should remain");
     }
+
+    async fn set_status(
+        tx: &mut mpsc::UnboundedSender<(String, Option<p::IncomingMessage>)>,
+        peer_id: &str,
+        roles: Vec<p::PeerRole>,
+        room: Option<&str>,
+    ) {
+        let message = p::IncomingMessage::SetPeerStatus(p::PeerStatus {
+            roles,
+            meta: None,
+            peer_id: None,
+            room: room.map(String::from),
+        });
+        tx.send((peer_id.to_string(), Some(message))).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_authentication() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut tokens = crate::auth::StaticTokens::new();
+        tokens.add_token("secret", Permissions::all());
+        let mut handler = Handler::new(Box::pin(rx)).with_authenticator(tokens);
+
+        for message in [
+            p::IncomingMessage::NewPeer,
+            p::IncomingMessage::Authenticate(p::AuthenticateMessage {
+                token: "wrong".to_string(),
+            }),
+        ] {
+            tx.send(("peer".to_string(), Some(message))).await.unwrap();
+            let (peer_id, sent_message) = handler.next().await.unwrap();
+            assert_eq!(peer_id, "peer");
+            assert!(matches!(sent_message, p::OutgoingMessage::Error { .. }));
+        }
+
+        let message = p::IncomingMessage::List;
+        tx.send(("peer".to_string(), Some(message))).await.unwrap();
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::Error {
+                details: "Peer 'peer' is not authenticated".into()
+            }
+        );
+
+        tx.send((
+            "peer".to_string(),
+            Some(p::IncomingMessage::Authenticate(p::AuthenticateMessage {
+                token: "secret".to_string(),
+            })),
+        ))
+        .await
+        .unwrap();
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::Welcome {
+                peer_id: "peer".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_peer_twice() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let monitor = Monitor::new();
+        let mut handler = Handler::new(Box::pin(rx)).with_monitor(monitor.clone());
+
+        new_peer(&mut tx, &mut handler, "producer").await;
+        set_status(&mut tx, "producer", vec![p::PeerRole::Producer], None).await;
+
+        tx.send(("producer".to_string(), Some(p::IncomingMessage::NewPeer)))
+            .await
+            .unwrap();
+
+        let message = p::IncomingMessage::List;
+        tx.send(("listener".to_string(), Some(message)))
+            .await
+            .unwrap();
+
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "listener");
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::List {
+                producers: vec![p::Peer {
+                    id: "producer".to_string(),
+                    meta: None,
+                }]
+            }
+        );
+
+        let peers = monitor.peers();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].roles, vec![p::PeerRole::Producer]);
+    }
+
+    #[tokio::test]
+    async fn test_permissions() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut tokens = crate::auth::StaticTokens::new();
+        tokens.add_token(
+            "viewer",
+            Permissions {
+                rooms: Some(vec!["lobby".to_string()]),
+                consume: true,
+                listen: true,
+                ..Default::default()
+            },
+        );
+        let mut handler = Handler::new(Box::pin(rx)).with_authenticator(tokens);
+
+        tx.send((
+            "viewer".to_string(),
+            Some(p::IncomingMessage::Authenticate(p::AuthenticateMessage {
+                token: "viewer".to_string(),
+            })),
+        ))
+        .await
+        .unwrap();
+        handler.next().await.unwrap();
+
+        set_status(
+            &mut tx,
+            "viewer",
+            vec![p::PeerRole::Producer],
+            Some("lobby"),
+        )
+        .await;
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::Error {
+                details: "Peer 'viewer' is not allowed to produce".into()
+            }
+        );
+
+        set_status(&mut tx, "viewer", vec![p::PeerRole::Listener], None).await;
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::Error {
+                details: "Peer 'viewer' is not allowed in room None".into()
+            }
+        );
+
+        let message = p::IncomingMessage::ListRoom(p::ListRoomMessage {
+            room: "backstage".to_string(),
+        });
+        tx.send(("viewer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::Error {
+                details: "Peer 'viewer' is not allowed in room Some(\"backstage\")".into()
+            }
+        );
+
+        set_status(
+            &mut tx,
+            "viewer",
+            vec![p::PeerRole::Listener],
+            Some("lobby"),
+        )
+        .await;
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "viewer");
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::PeerStatusChanged(p::PeerStatus {
+                roles: vec![p::PeerRole::Listener],
+                meta: None,
+                peer_id: Some("viewer".to_string()),
+                room: Some("lobby".to_string()),
+            })
+        );
+    }
+
+    async fn authenticate(
+        tx: &mut mpsc::UnboundedSender<(String, Option<p::IncomingMessage>)>,
+        peer_id: &str,
+        token: &str,
+    ) {
+        let message = p::IncomingMessage::Authenticate(p::AuthenticateMessage {
+            token: token.to_string(),
+        });
+        tx.send((peer_id.to_string(), Some(message))).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reauthentication() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut tokens = crate::auth::StaticTokens::new();
+        tokens.add_token("all", Permissions::all());
+        tokens.add_token(
+            "backstage",
+            Permissions {
+                rooms: Some(vec!["backstage".to_string()]),
+                consume: true,
+                ..Default::default()
+            },
+        );
+        let monitor = Monitor::new();
+        let mut handler = Handler::new(Box::pin(rx))
+            .with_authenticator(tokens)
+            .with_monitor(monitor.clone());
+
+        for peer_id in ["producer", "consumer"] {
+            authenticate(&mut tx, peer_id, "all").await;
+            handler.next().await.unwrap();
+        }
+        set_status(
+            &mut tx,
+            "producer",
+            vec![p::PeerRole::Producer],
+            Some("lobby"),
+        )
+        .await;
+
+        let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
+            peer_id: "producer".to_string(),
+            offer: None,
+            room: None,
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (_, sent_message) = handler.next().await.unwrap();
+        let session_id = match sent_message {
+            p::OutgoingMessage::SessionStarted { session_id, .. } => session_id,
+            _ => panic!("SessionStarted message missing"),
+        };
+        handler.next().await.unwrap();
+
+        // The consumer is no longer allowed in the room of the producer
+        authenticate(&mut tx, "consumer", "backstage").await;
+        for peer_id in ["consumer", "producer"] {
+            assert_eq!(
+                handler.next().await.unwrap(),
+                (
+                    peer_id.to_string(),
+                    p::OutgoingMessage::EndSession(p::EndSessionMessage {
+                        session_id: session_id.clone(),
+                    })
+                )
+            );
+        }
+        assert!(monitor.sessions().is_empty());
+
+        // The producer is no longer allowed to produce
+        authenticate(&mut tx, "producer", "backstage").await;
+        assert_eq!(
+            handler.next().await.unwrap(),
+            (
+                "producer".to_string(),
+                p::OutgoingMessage::Error {
+                    details: "Status of peer 'producer' was reset as its permissions changed"
+                        .into()
+                }
+            )
+        );
+
+        let peers = monitor.peers();
+        assert_eq!(peers.len(), 2);
+        assert!(peers.iter().all(|peer| peer.roles.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_leave_room() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut handler = Handler::new(Box::pin(rx));
+
+        new_peer(&mut tx, &mut handler, "producer").await;
+        new_peer(&mut tx, &mut handler, "consumer").await;
+        set_status(&mut tx, "producer", vec![p::PeerRole::Producer], Some("a")).await;
+        set_status(&mut tx, "consumer", vec![p::PeerRole::Listener], Some("a")).await;
+        handler.next().await.unwrap();
+
+        let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
+            peer_id: "producer".to_string(),
+            offer: None,
+            room: None,
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (_, sent_message) = handler.next().await.unwrap();
+        let session_id = match sent_message {
+            p::OutgoingMessage::SessionStarted { session_id, .. } => session_id,
+            _ => panic!("SessionStarted message missing"),
+        };
+        handler.next().await.unwrap();
+
+        // Leaving the room of the producer ends the session
+        set_status(&mut tx, "consumer", vec![p::PeerRole::Listener], Some("b")).await;
+        for peer_id in ["consumer", "producer"] {
+            assert_eq!(
+                handler.next().await.unwrap(),
+                (
+                    peer_id.to_string(),
+                    p::OutgoingMessage::EndSession(p::EndSessionMessage {
+                        session_id: session_id.clone(),
+                    })
+                )
+            );
+        }
+        assert!(handler.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rooms() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut handler = Handler::new(Box::pin(rx));
+
+        new_peer(&mut tx, &mut handler, "listener").await;
+        new_peer(&mut tx, &mut handler, "producer-a").await;
+        new_peer(&mut tx, &mut handler, "producer-b").await;
+        new_peer(&mut tx, &mut handler, "consumer").await;
+
+        set_status(&mut tx, "listener", vec![p::PeerRole::Listener], Some("a")).await;
+        handler.next().await.unwrap();
+
+        // Listeners are only notified about the peers of their room
+        set_status(
+            &mut tx,
+            "producer-b",
+            vec![p::PeerRole::Producer],
+            Some("b"),
+        )
+        .await;
+        set_status(
+            &mut tx,
+            "producer-a",
+            vec![p::PeerRole::Producer],
+            Some("a"),
+        )
+        .await;
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "listener");
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::PeerStatusChanged(p::PeerStatus {
+                roles: vec![p::PeerRole::Producer],
+                meta: None,
+                peer_id: Some("producer-a".to_string()),
+                room: Some("a".to_string()),
+            })
+        );
+
+        let message = p::IncomingMessage::ListRoom(p::ListRoomMessage {
+            room: "b".to_string(),
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "consumer");
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::List {
+                producers: vec![p::Peer {
+                    id: "producer-b".to_string(),
+                    meta: None,
+                }]
+            }
+        );
+
+        // The consumer is in the default room
+        let message = p::IncomingMessage::List;
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert_eq!(sent_message, p::OutgoingMessage::List { producers: vec![] });
+
+        let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
+            peer_id: "producer-b".to_string(),
+            offer: None,
+            room: Some("a".to_string()),
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::Error {
+                details: "No producer with ID: 'producer-b'".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_limit() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut handler = Handler::new(Box::pin(rx)).with_max_sessions(1);
+
+        new_peer(&mut tx, &mut handler, "producer").await;
+        set_status(&mut tx, "producer", vec![p::PeerRole::Producer], None).await;
+        new_peer(&mut tx, &mut handler, "consumer").await;
+
+        for _ in 0..2 {
+            let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
+                peer_id: "producer".to_string(),
+                offer: None,
+                room: None,
+            });
+            tx.send(("consumer".to_string(), Some(message)))
+                .await
+                .unwrap();
+        }
+
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert!(matches!(
+            sent_message,
+            p::OutgoingMessage::SessionStarted { .. }
+        ));
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert!(matches!(
+            sent_message,
+            p::OutgoingMessage::StartSession { .. }
+        ));
+
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "consumer");
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::Error {
+                details: "Peer 'consumer' reached its limit of 1 sessions".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_monitor() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let monitor = Monitor::new();
+        let mut handler = Handler::new(Box::pin(rx)).with_monitor(monitor.clone());
+
+        new_peer(&mut tx, &mut handler, "producer").await;
+        set_status(&mut tx, "producer", vec![p::PeerRole::Producer], None).await;
+        new_peer(&mut tx, &mut handler, "consumer").await;
+
+        let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
+            peer_id: "producer".to_string(),
+            offer: None,
+            room: None,
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let _ = handler.next().await.unwrap();
+        let _ = handler.next().await.unwrap();
+
+        let peers = monitor.peers();
+        assert_eq!(peers.len(), 2);
+        let producer = peers.iter().find(|peer| peer.id == "producer").unwrap();
+        assert_eq!(producer.roles, vec![p::PeerRole::Producer]);
+
+        let sessions = monitor.sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].producer, "producer");
+        assert_eq!(sessions[0].consumer, "consumer");
+
+        let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
+            peer_id: "unknown".to_string(),
+            offer: None,
+            room: None,
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (_, sent_message) = handler.next().await.unwrap();
+        assert!(matches!(sent_message, p::OutgoingMessage::Error { .. }));
+
+        tx.send(("consumer".to_string(), None)).await.unwrap();
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "producer");
+        assert!(matches!(sent_message, p::OutgoingMessage::EndSession(_)));
+
+        assert_eq!(monitor.peers().len(), 1);
+        assert!(monitor.sessions().is_empty());
+
+        let metrics = monitor.render_metrics();
+        for line in [
+            "gst_webrtc_signalling_peers 1",
+            "gst_webrtc_signalling_peers_by_role{role=\"producer\"} 1",
+            "gst_webrtc_signalling_sessions 0",
+            "gst_webrtc_signalling_sessions_started_total 1",
+            "gst_webrtc_signalling_messages_received_total{type=\"startSession\"} 2",
+            "gst_webrtc_signalling_messages_sent_total{type=\"welcome\"} 2",
+            "gst_webrtc_signalling_errors_total 1",
+        ] {
+            assert!(
+                metrics.lines().any(|l| l == line),
+                "{line} not in {metrics}"
+            );
+        }
+    }
 }
@@ -1,4 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub mod admin;
+pub mod auth;
 pub mod handlers;
 pub mod server;
@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use crate::admin::Monitor;
 use anyhow::Error;
+use async_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
 use async_tungstenite::tungstenite::{Message as WsMessage, Utf8Bytes};
 use futures::channel::mpsc;
 use futures::prelude::*;
@@ -26,6 +28,7 @@ struct State {
 #[derive(Clone)]
 pub struct Server {
     state: Arc<Mutex<State>>,
+    monitor: Monitor,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -38,6 +41,25 @@ pub enum ServerError {
     TLSHandshakeTimeout(#[from] tokio::time::error::Elapsed),
 }
 
+/// Extract the token authenticating a peer from the `Authorization` header,
+/// or from the `token` query parameter for clients that can't set headers
+fn token_from_request(request: &Request) -> Option<String> {
+    if let Some(token) = request
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        return Some(token.trim().to_string());
+    }
+
+    request.uri().query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned())
+    })
+}
+
 impl Server {
     #[instrument(level = "debug", skip(factory))]
     pub fn spawn<
@@ -94,7 +116,16 @@ impl Server {
             }
         });
 
-        Self { state }
+        Self {
+            state,
+            monitor: Default::default(),
+        }
+    }
+
+    /// Count the accepted connections in `monitor`
+    pub fn with_monitor(mut self, monitor: Monitor) -> Self {
+        self.monitor = monitor;
+        self
     }
 
     #[instrument(level = "debug", skip(state))]
@@ -114,12 +145,56 @@ impl Server {
         }
     }
 
+    /// Close the connection of a peer, returns `false` if there is no
+    /// such peer
+    #[instrument(level = "debug", skip(self))]
+    pub fn kick(&self, peer_id: &str) -> bool {
+        let (peer, tx) = {
+            let mut state = self.state.lock().unwrap();
+            match state.peers.remove(peer_id) {
+                Some(peer) => (peer, state.tx.clone()),
+                None => return false,
+            }
+        };
+
+        info!(peer_id = %peer_id, "kicking peer");
+
+        // The connection may not be closed cleanly by the peer, stop
+        // receiving right away and let the handler know it left
+        peer.receive_task_handle.abort();
+
+        let peer_id = peer_id.to_string();
+        let mut sender = peer.sender;
+        let send_task_handle = peer.send_task_handle;
+        task::spawn(async move {
+            if let Some(mut tx) = tx {
+                let _ = tx.send((peer_id.clone(), None)).await;
+            }
+
+            sender.close_channel();
+            if let Err(err) = send_task_handle.await {
+                trace!(peer_id = %peer_id, "Error while joining send task: {}", err);
+            }
+        });
+
+        true
+    }
+
     #[instrument(level = "debug", skip(self, stream))]
     pub async fn accept_async<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
         &mut self,
         stream: S,
     ) -> Result<String, ServerError> {
-        let ws = match async_tungstenite::tokio::accept_async(stream).await {
+        let mut token = None;
+        let ws = match async_tungstenite::tokio::accept_hdr_async(
+            stream,
+            |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+                token = token_from_request(request);
+                Ok(response)
+            },
+        )
+        .await
+        {
             Ok(ws) => ws,
             Err(err) => {
                 warn!("Error during the websocket handshake: {}", err);
@@ -129,6 +204,7 @@ impl Server {
 
         let this_id = uuid::Uuid::new_v4().to_string();
         info!(this_id = %this_id, "New WebSocket connection");
+        self.monitor.connection_accepted();
 
         // 1000 is completely arbitrary, we simply don't want infinite piling
         // up of messages as with unbounded
@@ -176,17 +252,18 @@ impl Server {
         let state_clone = self.state.clone();
         let receive_task_handle = task::spawn(async move {
             if let Some(tx) = tx.as_mut() {
+                // Peers that presented a token are authenticated right away
+                let msg = match token {
+                    Some(token) => serde_json::json!({
+                        "type": "authenticate",
+                        "token": token,
+                    }),
+                    None => serde_json::json!({
+                        "type": "newPeer",
+                    }),
+                };
                 if let Err(err) = tx
-                    .send((
-                        this_id_clone.clone(),
-                        Some(
-                            serde_json::json!({
-                                "type": "newPeer",
-                            })
-                            .to_string()
-                            .into(),
-                        ),
-                    ))
+                    .send((this_id_clone.clone(), Some(msg.to_string().into())))
                     .await
                 {
                     warn!(this = %this_id_clone, "Error handling message: {:?}", err);
@@ -236,3 +313,27 @@ impl Server {
         Ok(this_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_from_request() {
+        let request = Request::builder()
+            .uri("/?foo=bar&token=a%2Bb%3D%3D")
+            .body(())
+            .unwrap();
+        assert_eq!(token_from_request(&request).as_deref(), Some("a+b=="));
+
+        let request = Request::builder()
+            .uri("/?token=query")
+            .header("Authorization", "Bearer header")
+            .body(())
+            .unwrap();
+        assert_eq!(token_from_request(&request).as_deref(), Some("header"));
+
+        let request = Request::builder().uri("/").body(()).unwrap();
+        assert_eq!(token_from_request(&request), None);
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use crate::{
-    signaller::{Signallable, SignallableImpl},
+    signaller::{Signallable, SignallableImpl, WebRTCSignallerRole},
     webrtcsink::JanusVRSignallerState,
     RUNTIME,
 };
@@ -18,6 +18,8 @@ use gst::subclass::prelude::*;
 use http::Uri;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::mem;
 use std::ops::ControlFlow;
 use std::sync::LazyLock;
 use std::sync::Mutex;
@@ -100,6 +102,11 @@ struct AttachPluginMsg {
     apisecret: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+struct SubscribeStream {
+    feed: JanusId,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 struct RoomRequestBody {
     request: String,
@@ -109,6 +116,8 @@ struct RoomRequestBody {
     id: Option<JanusId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     display: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    streams: Option<Vec<SubscribeStream>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -144,6 +153,15 @@ struct PublishMsg {
     jsep: Jsep,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+struct DetachMsg {
+    janus: String,
+    transaction: String,
+    session_id: u64,
+    handle_id: u64,
+    apisecret: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 struct Candidate {
     candidate: String,
@@ -169,7 +187,10 @@ enum OutgoingMessage {
     AttachPlugin(AttachPluginMsg),
     RoomRequest(RoomRequestMsg),
     Publish(PublishMsg),
+    // `start` requests answering a subscriber offer share the `publish` layout
+    Start(PublishMsg),
     Trickle(TrickleMsg),
+    Detach(DetachMsg),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -196,10 +217,25 @@ struct InnerSlowLink {
     lost: u64,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct Publisher {
+    id: JanusId,
+    display: Option<String>,
+    #[serde(default)]
+    dummy: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct RoomJoined {
     room: JanusId,
     id: JanusId,
+    #[serde(default)]
+    publishers: Vec<Publisher>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RoomAttached {
+    room: JanusId,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -207,6 +243,10 @@ struct RoomEvent {
     room: Option<JanusId>,
     error_code: Option<i32>,
     error: Option<String>,
+    publishers: Option<Vec<Publisher>>,
+    // Either the ID of the publisher or "ok" when we are the one unpublishing / leaving
+    unpublished: Option<JanusId>,
+    leaving: Option<JanusId>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -232,6 +272,7 @@ struct SlowLink {
 #[serde(tag = "videoroom", rename_all = "kebab-case")]
 enum VideoRoomData {
     Joined(RoomJoined),
+    Attached(RoomAttached),
     Event(RoomEvent),
     Destroyed(RoomDestroyed),
     Talking(RoomTalking),
@@ -263,6 +304,7 @@ struct SuccessMsg {
 struct EventMsg {
     transaction: Option<String>,
     session_id: Option<u64>,
+    sender: Option<u64>,
     plugindata: Option<PluginData>,
     jsep: Option<Jsep>,
 }
@@ -281,6 +323,13 @@ enum JsonReply {
     SlowLink(InnerSlowLink),
 }
 
+/// A subscription to a publisher of the room, only used with the consumer role
+struct Subscription {
+    feed: JanusId,
+    handle_id: u64,
+    started: bool,
+}
+
 #[derive(Default)]
 struct State {
     ws_sender: Option<mpsc::Sender<OutgoingMessage>>,
@@ -292,6 +341,10 @@ struct State {
     room_id: Option<JanusId>,
     feed_id: Option<JanusId>,
     leave_room_rx: Option<tokio::sync::oneshot::Receiver<()>>,
+    // Subscriptions, indexed by session ID
+    subscriptions: HashMap<String, Subscription>,
+    // Feeds waiting for their plugin handle to be attached, indexed by transaction
+    pending_subscriptions: HashMap<String, JanusId>,
 }
 
 #[derive(Clone)]
@@ -319,6 +372,23 @@ impl Default for Settings {
 #[properties(wrapper_type = super::JanusVRSignaller)]
 pub struct Signaller {
     state: Mutex<State>,
+    /**
+     * GstJanusVRWebRTCSignaller:role:
+     *
+     * With the `Producer` role, the signaller publishes a feed in the room.
+     * With the `Consumer` role, it subscribes to the feed set with `feed-id`,
+     * or to all the publishers of the room if unset, including the ones
+     * joining later on.
+     *
+     * Since: plugins-rs-0.14.0
+     */
+    #[property(
+        get,
+        construct_only,
+        builder(WebRTCSignallerRole::Producer),
+        blurb = "Whether the signaller publishes to, or subscribes from, the room"
+    )]
+    role: Mutex<WebRTCSignallerRole>,
     #[property(name="manual-sdp-munging", default = false, get = |_| false, type = bool, blurb = "Whether the signaller manages SDP munging itself")]
     #[property(name="janus-endpoint", get, set, type = String, member = janus_endpoint, blurb = "The Janus server endpoint to POST SDP offer to")]
     #[property(name="display-name", get, set, type = String, member = display_name, blurb = "The name of the publisher in the Janus Video Room")]
@@ -333,6 +403,10 @@ impl Signaller {
             .emit_by_name::<()>("error", &[&format!("Error: {msg}")]);
     }
 
+    fn is_consumer(&self) -> bool {
+        *self.role.lock().unwrap() == WebRTCSignallerRole::Consumer
+    }
+
     async fn connect(&self) -> Result<(), Error> {
         let settings = self.settings.lock().unwrap().clone();
         use tungstenite::client::IntoClientRequest;
@@ -478,6 +552,20 @@ impl Signaller {
                         );
                         self.set_session_id(data.id);
                         self.attach_plugin();
+                    } else if let Some(feed) = success.transaction.and_then(|transaction| {
+                        self.state
+                            .lock()
+                            .unwrap()
+                            .pending_subscriptions
+                            .remove(&transaction)
+                    }) {
+                        gst::trace!(
+                            CAT,
+                            imp = self,
+                            "Attached subscriber handle {} for feed {feed}",
+                            data.id
+                        );
+                        self.join_as_subscriber(feed, data.id);
                     } else {
                         gst::trace!(
                             CAT,
@@ -493,6 +581,25 @@ impl Signaller {
             JsonReply::Event(event) => {
                 if let Some(PluginData::VideoRoom { data: plugindata }) = event.plugindata {
                     match plugindata {
+                        VideoRoomData::Joined(joined) if self.is_consumer() => {
+                            // Our own ID in the room, only used to leave it
+                            self.state.lock().unwrap().feed_id = Some(joined.id);
+
+                            gst::trace!(
+                                CAT,
+                                imp = self,
+                                "Joined room {:?} successfully, publishers: {:?}",
+                                joined.room,
+                                joined.publishers
+                            );
+
+                            self.obj().emit_by_name::<()>(
+                                "state-updated",
+                                &[&JanusVRSignallerState::RoomJoined],
+                            );
+
+                            self.subscribe(joined.publishers);
+                        }
                         VideoRoomData::Joined(joined) => {
                             let feed_id_changed = {
                                 let mut feed_id_changed = false;
@@ -526,7 +633,47 @@ impl Signaller {
 
                             self.session_requested();
                         }
+                        VideoRoomData::Attached(attached) => {
+                            gst::trace!(
+                                CAT,
+                                imp = self,
+                                "Subscribed in room {}, handle: {:?}",
+                                attached.room,
+                                event.sender
+                            );
+
+                            match (event.sender, event.jsep) {
+                                (Some(handle_id), Some(jsep)) if jsep.r#type == "offer" => {
+                                    self.handle_offer(handle_id, jsep.sdp);
+                                }
+                                _ => {
+                                    gst::warning!(
+                                        CAT,
+                                        imp = self,
+                                        "Subscription attached without an offer"
+                                    );
+                                }
+                            }
+                        }
                         VideoRoomData::Event(room_event) => {
+                            if let (Some(code), Some(reason)) =
+                                (room_event.error_code, room_event.error.as_ref())
+                            {
+                                if let Some(session_id) =
+                                    event.sender.and_then(|sender| self.subscription_id(sender))
+                                {
+                                    // Only this subscription failed, e.g. the publisher
+                                    // left in the meantime
+                                    gst::warning!(
+                                        CAT,
+                                        imp = self,
+                                        "Subscription {session_id} failed, code: {code}, reason: {reason}"
+                                    );
+                                    self.end_subscription(&session_id);
+                                    return;
+                                }
+                            }
+
                             if room_event.error_code.is_some() && room_event.error.is_some() {
                                 self.raise_error(format!(
                                     "code: {}, reason: {}",
@@ -536,6 +683,19 @@ impl Signaller {
                                 return;
                             }
 
+                            if self.is_consumer() {
+                                if let Some(publishers) = room_event.publishers {
+                                    self.subscribe(publishers);
+                                }
+
+                                for feed in [room_event.unpublished, room_event.leaving]
+                                    .into_iter()
+                                    .flatten()
+                                {
+                                    self.end_subscription(&feed.to_string());
+                                }
+                            }
+
                             if let Some(jsep) = event.jsep {
                                 if jsep.r#type == "answer" {
                                     gst::trace!(CAT, imp = self, "Session requested successfully");
@@ -571,7 +731,24 @@ impl Signaller {
             JsonReply::Error(error) => {
                 self.raise_error(format!("code: {}, reason: {}", error.code, error.reason))
             }
-            JsonReply::HangUp(hangup) => self.raise_error(format!("hangup: {}", hangup.reason)),
+            JsonReply::HangUp(hangup) => {
+                let subscription = match &hangup.sender {
+                    JanusId::Num(sender) => self.subscription_id(*sender),
+                    JanusId::Str(_) => None,
+                };
+
+                if let Some(session_id) = subscription {
+                    gst::info!(
+                        CAT,
+                        imp = self,
+                        "Subscription {session_id} hung up: {}",
+                        hangup.reason
+                    );
+                    self.end_subscription(&session_id);
+                } else {
+                    self.raise_error(format!("hangup: {}", hangup.reason))
+                }
+            }
             // ignore for now
             JsonReply::Ack | JsonReply::Media | JsonReply::SlowLink(_) => {}
         }
@@ -665,7 +842,9 @@ impl Signaller {
                 state.session_id.unwrap(),
                 state.handle_id.unwrap(),
                 state.room_id.clone().unwrap(),
-                settings.feed_id.clone(),
+                // When subscribing, `feed-id` is the publisher we subscribe to
+                // so let Janus pick our own ID
+                settings.feed_id.clone().filter(|_| !self.is_consumer()),
                 settings.display_name.clone(),
                 settings.secret_key.clone(),
             )
@@ -682,10 +861,159 @@ impl Signaller {
                 room,
                 id: feed_id,
                 display,
+                streams: None,
             },
         }));
     }
 
+    fn subscription_id(&self, handle_id: u64) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .subscriptions
+            .iter()
+            .find(|(_, subscription)| subscription.handle_id == handle_id)
+            .map(|(session_id, _)| session_id.clone())
+    }
+
+    /// Attaches a new plugin handle for each of the `publishers` we want to subscribe to
+    fn subscribe(&self, publishers: Vec<Publisher>) {
+        let (session_id, apisecret, transactions) = {
+            let mut state = self.state.lock().unwrap();
+            let settings = self.settings.lock().unwrap();
+
+            let Some(session_id) = state.session_id else {
+                return;
+            };
+
+            let mut transactions = vec![];
+            for publisher in publishers {
+                if publisher.dummy
+                    || state.feed_id.as_ref() == Some(&publisher.id)
+                    || settings
+                        .feed_id
+                        .as_ref()
+                        .is_some_and(|feed_id| feed_id != &publisher.id)
+                    || state.subscriptions.contains_key(&publisher.id.to_string())
+                    || state
+                        .pending_subscriptions
+                        .values()
+                        .any(|feed| feed == &publisher.id)
+                {
+                    continue;
+                }
+
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Subscribing to publisher {} ({:?})",
+                    publisher.id,
+                    publisher.display
+                );
+
+                let transaction = transaction_id();
+                state
+                    .pending_subscriptions
+                    .insert(transaction.clone(), publisher.id);
+                transactions.push(transaction);
+            }
+
+            (session_id, settings.secret_key.clone(), transactions)
+        };
+
+        for transaction in transactions {
+            self.send(OutgoingMessage::AttachPlugin(AttachPluginMsg {
+                janus: "attach".to_string(),
+                transaction,
+                plugin: "janus.plugin.videoroom".to_string(),
+                session_id,
+                apisecret: apisecret.clone(),
+            }));
+        }
+    }
+
+    fn join_as_subscriber(&self, feed: JanusId, handle_id: u64) {
+        let (transaction, session_id, room, apisecret) = {
+            let mut state = self.state.lock().unwrap();
+            let settings = self.settings.lock().unwrap();
+
+            state.subscriptions.insert(
+                feed.to_string(),
+                Subscription {
+                    feed: feed.clone(),
+                    handle_id,
+                    started: false,
+                },
+            );
+
+            (
+                state.transaction_id.clone().unwrap(),
+                state.session_id.unwrap(),
+                state.room_id.clone().unwrap(),
+                settings.secret_key.clone(),
+            )
+        };
+        self.send(OutgoingMessage::RoomRequest(RoomRequestMsg {
+            janus: "message".to_string(),
+            transaction,
+            session_id,
+            handle_id,
+            apisecret,
+            body: RoomRequestBody {
+                request: "join".to_string(),
+                ptype: "subscriber".to_string(),
+                room,
+                id: None,
+                display: None,
+                streams: Some(vec![SubscribeStream { feed }]),
+            },
+        }));
+    }
+
+    /// Releases the plugin handle of a subscription, returns whether its session was started
+    fn unsubscribe(&self, session_id: &str) -> bool {
+        let (subscription, transaction, janus_session_id, apisecret) = {
+            let mut state = self.state.lock().unwrap();
+            let settings = self.settings.lock().unwrap();
+
+            let Some(subscription) = state.subscriptions.remove(session_id) else {
+                return false;
+            };
+
+            (
+                subscription,
+                state.transaction_id.clone().unwrap(),
+                state.session_id.unwrap(),
+                settings.secret_key.clone(),
+            )
+        };
+
+        gst::info!(
+            CAT,
+            imp = self,
+            "Unsubscribing from publisher {}",
+            subscription.feed
+        );
+
+        self.send(OutgoingMessage::Detach(DetachMsg {
+            janus: "detach".to_string(),
+            transaction,
+            session_id: janus_session_id,
+            handle_id: subscription.handle_id,
+            apisecret,
+        }));
+
+        subscription.started
+    }
+
+    /// Ends the session of a subscription, once it went away on the Janus side
+    fn end_subscription(&self, session_id: &str) {
+        if self.unsubscribe(session_id) {
+            let _ = self
+                .obj()
+                .emit_by_name::<bool>("session-ended", &[&session_id]);
+        }
+    }
+
     fn leave_room(&self) {
         let mut state = self.state.lock().unwrap();
         let (transaction, session_id, handle_id, room, feed_id, display, apisecret) = {
@@ -721,6 +1049,7 @@ impl Signaller {
                     room,
                     id: Some(feed_id),
                     display,
+                    streams: None,
                 },
             });
             RUNTIME.spawn(glib::clone!(
@@ -774,8 +1103,43 @@ impl Signaller {
         }));
     }
 
-    fn trickle(&self, candidate: &str, sdp_m_line_index: u32) {
-        let (transaction, session_id, handle_id, apisecret) = {
+    fn start(&self, session_id: &str, answer: &gst_webrtc::WebRTCSessionDescription) {
+        let (transaction, janus_session_id, handle_id, apisecret) = {
+            let state = self.state.lock().unwrap();
+            let settings = self.settings.lock().unwrap();
+
+            let Some(subscription) = state.subscriptions.get(session_id) else {
+                gst::warning!(CAT, imp = self, "No subscription for session {session_id}");
+                return;
+            };
+
+            (
+                state.transaction_id.clone().unwrap(),
+                state.session_id.unwrap(),
+                subscription.handle_id,
+                settings.secret_key.clone(),
+            )
+        };
+        let sdp_data = answer.sdp().as_text().unwrap();
+        self.send(OutgoingMessage::Start(PublishMsg {
+            janus: "message".to_string(),
+            transaction,
+            session_id: janus_session_id,
+            handle_id,
+            apisecret,
+            body: PublishBody {
+                request: "start".to_string(),
+            },
+            jsep: Jsep {
+                sdp: sdp_data,
+                trickle: Some(true),
+                r#type: "answer".to_string(),
+            },
+        }));
+    }
+
+    fn trickle(&self, session_id: &str, candidate: &str, sdp_m_line_index: u32) {
+        let (transaction, janus_session_id, handle_id, apisecret) = {
             let state = self.state.lock().unwrap();
             let settings = self.settings.lock().unwrap();
 
@@ -784,17 +1148,27 @@ impl Signaller {
                 return;
             }
 
+            let handle_id = if self.is_consumer() {
+                let Some(subscription) = state.subscriptions.get(session_id) else {
+                    gst::warning!(CAT, imp = self, "No subscription for session {session_id}");
+                    return;
+                };
+                subscription.handle_id
+            } else {
+                state.handle_id.unwrap()
+            };
+
             (
                 state.transaction_id.clone().unwrap(),
                 state.session_id.unwrap(),
-                state.handle_id.unwrap(),
+                handle_id,
                 settings.secret_key.clone(),
             )
         };
         self.send(OutgoingMessage::Trickle(TrickleMsg {
             janus: "trickle".to_string(),
             transaction,
-            session_id,
+            session_id: janus_session_id,
             handle_id,
             apisecret,
             candidate: Candidate {
@@ -815,6 +1189,43 @@ impl Signaller {
         );
     }
 
+    fn handle_offer(&self, handle_id: u64, sdp: String) {
+        let Some((session_id, feed, started)) = ({
+            let mut state = self.state.lock().unwrap();
+            state
+                .subscriptions
+                .iter_mut()
+                .find(|(_, subscription)| subscription.handle_id == handle_id)
+                .map(|(session_id, subscription)| {
+                    let started = mem::replace(&mut subscription.started, true);
+                    (session_id.clone(), subscription.feed.clone(), started)
+                })
+        }) else {
+            gst::warning!(CAT, imp = self, "Got offer for unknown handle {handle_id}");
+            return;
+        };
+
+        match gst_sdp::SDPMessage::parse_buffer(sdp.as_bytes()) {
+            Ok(offer_sdp) => {
+                let offer = gst_webrtc::WebRTCSessionDescription::new(
+                    gst_webrtc::WebRTCSDPType::Offer,
+                    offer_sdp,
+                );
+
+                if !started {
+                    self.obj()
+                        .emit_by_name::<()>("session-started", &[&session_id, &feed.to_string()]);
+                }
+
+                self.obj()
+                    .emit_by_name::<()>("session-description", &[&session_id, &offer]);
+            }
+            Err(err) => {
+                self.raise_error(format!("Could not parse offer SDP: {err}"));
+            }
+        }
+    }
+
     fn handle_answer(&self, sdp: String) {
         match gst_sdp::SDPMessage::parse_buffer(sdp.as_bytes()) {
             Ok(ans_sdp) => {
@@ -852,25 +1263,30 @@ impl SignallableImpl for Signaller {
         });
     }
 
-    fn send_sdp(&self, _session_id: &str, offer: &gst_webrtc::WebRTCSessionDescription) {
+    fn send_sdp(&self, session_id: &str, sdp: &gst_webrtc::WebRTCSessionDescription) {
         gst::info!(
             CAT,
             imp = self,
-            "sending SDP offer to peer: {:?}",
-            offer.sdp().as_text()
+            "sending SDP {:?} to peer: {:?}",
+            sdp.type_(),
+            sdp.sdp().as_text()
         );
 
-        self.publish(offer);
+        if self.is_consumer() {
+            self.start(session_id, sdp);
+        } else {
+            self.publish(sdp);
+        }
     }
 
     fn add_ice(
         &self,
-        _session_id: &str,
+        session_id: &str,
         candidate: &str,
         sdp_m_line_index: u32,
         _sdp_mid: Option<String>,
     ) {
-        self.trickle(candidate, sdp_m_line_index);
+        self.trickle(session_id, candidate, sdp_m_line_index);
     }
 
     fn stop(&self) {
@@ -906,10 +1322,16 @@ impl SignallableImpl for Signaller {
         state.session_id = None;
         state.handle_id = None;
         state.transaction_id = None;
+        state.subscriptions.clear();
+        state.pending_subscriptions.clear();
     }
 
-    fn end_session(&self, _session_id: &str) {
-        self.leave_room();
+    fn end_session(&self, session_id: &str) {
+        if self.is_consumer() {
+            self.unsubscribe(session_id);
+        } else {
+            self.leave_room();
+        }
     }
 }
 
@@ -946,7 +1368,7 @@ impl ObjectImpl for Signaller {
 }
 
 // below are Signaller subclasses implementing properties whose type depends of the Janus ID format (u64 or string).
-// User can control which signaller is used by setting the `use-string-ids` construct property on `janusvrwebrtcsink`.
+// User can control which signaller is used by setting the `use-string-ids` construct property on `janusvrwebrtcsink` or `janusvrwebrtcsrc`.
 
 // each object needs to live in its own module as the code generated by the Properties macro is not namespaced
 pub mod signaller_u64 {
@@ -1130,3 +1552,263 @@ pub mod signaller_str {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::janusvr_signaller::JanusVRSignallerU64;
+    use crate::signaller::SignallableExt;
+    use async_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use serde_json::{json, Value};
+
+    const OFFER: &str = "v=0\r\n\
+        o=- 0 0 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        t=0 0\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+        a=mid:0\r\n\
+        a=sendonly\r\n";
+
+    const CANDIDATE: &str = "candidate:1 1 UDP 2130706431 192.0.2.1 5000 typ host";
+
+    /// Mocked Janus server accepting a single client, forwarding its requests
+    /// and sending back the replies it is given
+    fn mock_janus() -> (
+        u16,
+        std::sync::mpsc::Receiver<Value>,
+        futures::channel::mpsc::UnboundedSender<Value>,
+    ) {
+        let listener = RUNTIME
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let (requests_tx, requests_rx) = std::sync::mpsc::channel();
+        let (replies_tx, mut replies_rx) = futures::channel::mpsc::unbounded::<Value>();
+
+        RUNTIME.spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let ws = async_tungstenite::tokio::accept_hdr_async(
+                stream,
+                |_request: &Request, mut response: Response| -> Result<Response, ErrorResponse> {
+                    response.headers_mut().append(
+                        "Sec-WebSocket-Protocol",
+                        http::HeaderValue::from_static("janus-protocol"),
+                    );
+                    Ok(response)
+                },
+            )
+            .await
+            .unwrap();
+            let (mut ws_sink, mut ws_stream) = ws.split();
+
+            loop {
+                tokio::select! {
+                    msg = ws_stream.next() => match msg {
+                        Some(Ok(WsMessage::Text(msg))) => {
+                            let _ = requests_tx.send(serde_json::from_str::<Value>(&msg).unwrap());
+                        }
+                        Some(Ok(_)) => (),
+                        _ => break,
+                    },
+                    reply = replies_rx.next() => match reply {
+                        Some(reply) => {
+                            ws_sink.send(WsMessage::text(reply.to_string())).await.unwrap();
+                        }
+                        None => break,
+                    },
+                }
+            }
+        });
+
+        (port, requests_rx, replies_tx)
+    }
+
+    #[test]
+    fn subscriber() {
+        gst::init().unwrap();
+
+        let (port, requests, replies) = mock_janus();
+        let request = |janus: &str| -> Value {
+            loop {
+                let request = requests
+                    .recv_timeout(Duration::from_secs(5))
+                    .expect("no request from the signaller");
+                if request["janus"] != "keepalive" {
+                    assert_eq!(request["janus"], janus, "unexpected request {request}");
+                    return request;
+                }
+            }
+        };
+        let reply = |reply: Value| replies.unbounded_send(reply).unwrap();
+
+        let signaller = JanusVRSignallerU64::new_consumer();
+        signaller.set_property("janus-endpoint", format!("ws://127.0.0.1:{port}"));
+        signaller.set_property("room-id", 1234u64);
+
+        let (events_tx, events) = std::sync::mpsc::channel::<(&str, String, String)>();
+        let events_tx_clone = events_tx.clone();
+        signaller.connect_closure(
+            "session-started",
+            false,
+            glib::closure!(move |_signaller: &JanusVRSignallerU64,
+                                 session_id: &str,
+                                 peer_id: &str| {
+                events_tx_clone
+                    .send(("started", session_id.to_string(), peer_id.to_string()))
+                    .unwrap();
+            }),
+        );
+        let events_tx_clone = events_tx.clone();
+        signaller.connect_closure(
+            "session-description",
+            false,
+            glib::closure!(
+                move |_signaller: &JanusVRSignallerU64,
+                      session_id: &str,
+                      desc: &gst_webrtc::WebRTCSessionDescription| {
+                    assert_eq!(desc.type_(), gst_webrtc::WebRTCSDPType::Offer);
+                    events_tx_clone
+                        .send((
+                            "description",
+                            session_id.to_string(),
+                            desc.sdp().as_text().unwrap(),
+                        ))
+                        .unwrap();
+                }
+            ),
+        );
+        signaller.connect_closure(
+            "session-ended",
+            false,
+            glib::closure!(
+                move |_signaller: &JanusVRSignallerU64, session_id: &str| -> bool {
+                    events_tx
+                        .send(("ended", session_id.to_string(), String::new()))
+                        .unwrap();
+                    true
+                }
+            ),
+        );
+        let event = || events.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        signaller.start();
+
+        let req = request("create");
+        reply(json!({
+            "janus": "success",
+            "transaction": req["transaction"],
+            "data": { "id": 1 },
+        }));
+
+        let req = request("attach");
+        reply(json!({
+            "janus": "success",
+            "session_id": 1,
+            "transaction": req["transaction"],
+            "data": { "id": 10 },
+        }));
+
+        // Join the room to be notified of the publishers, without using the feed ID
+        let req = request("message");
+        assert_eq!(req["handle_id"], 10);
+        assert_eq!(req["body"]["request"], "join");
+        assert_eq!(req["body"]["ptype"], "publisher");
+        assert!(req["body"].get("id").is_none());
+        reply(json!({
+            "janus": "event",
+            "session_id": 1,
+            "sender": 10,
+            "transaction": req["transaction"],
+            "plugindata": {
+                "plugin": "janus.plugin.videoroom",
+                "data": {
+                    "videoroom": "joined",
+                    "room": 1234,
+                    "id": 7,
+                    "publishers": [{ "id": 42, "display": "alice" }],
+                },
+            },
+        }));
+
+        // Subscribe to the publisher with a dedicated handle
+        let req = request("attach");
+        reply(json!({
+            "janus": "success",
+            "session_id": 1,
+            "transaction": req["transaction"],
+            "data": { "id": 11 },
+        }));
+
+        let req = request("message");
+        assert_eq!(req["handle_id"], 11);
+        assert_eq!(req["body"]["request"], "join");
+        assert_eq!(req["body"]["ptype"], "subscriber");
+        assert_eq!(req["body"]["streams"], json!([{ "feed": 42 }]));
+        reply(json!({
+            "janus": "event",
+            "session_id": 1,
+            "sender": 11,
+            "transaction": req["transaction"],
+            "plugindata": {
+                "plugin": "janus.plugin.videoroom",
+                "data": { "videoroom": "attached", "room": 1234 },
+            },
+            "jsep": { "type": "offer", "sdp": OFFER },
+        }));
+
+        assert_eq!(event(), ("started", "42".to_string(), "42".to_string()));
+        assert_eq!(
+            event(),
+            ("description", "42".to_string(), OFFER.to_string())
+        );
+
+        // The answer and the candidates are sent on the subscriber handle
+        let answer = gst_webrtc::WebRTCSessionDescription::new(
+            gst_webrtc::WebRTCSDPType::Answer,
+            gst_sdp::SDPMessage::parse_buffer(OFFER.as_bytes()).unwrap(),
+        );
+        signaller.send_sdp("42", &answer);
+        let req = request("message");
+        assert_eq!(req["handle_id"], 11);
+        assert_eq!(req["body"]["request"], "start");
+        assert_eq!(req["jsep"]["type"], "answer");
+
+        signaller.add_ice("42", CANDIDATE, 0, None);
+        let req = request("trickle");
+        assert_eq!(req["handle_id"], 11);
+        assert_eq!(req["candidate"]["candidate"], CANDIDATE);
+
+        // A publisher joining later on is subscribed to as well
+        reply(json!({
+            "janus": "event",
+            "session_id": 1,
+            "sender": 10,
+            "plugindata": {
+                "plugin": "janus.plugin.videoroom",
+                "data": {
+                    "videoroom": "event",
+                    "room": 1234,
+                    "publishers": [{ "id": 43, "display": "bob" }],
+                },
+            },
+        }));
+        request("attach");
+
+        // Publishers leaving end their session
+        reply(json!({
+            "janus": "event",
+            "session_id": 1,
+            "sender": 10,
+            "plugindata": {
+                "plugin": "janus.plugin.videoroom",
+                "data": { "videoroom": "event", "room": 1234, "unpublished": 42 },
+            },
+        }));
+        let req = request("detach");
+        assert_eq!(req["handle_id"], 11);
+        assert_eq!(event(), ("ended", "42".to_string(), String::new()));
+
+        signaller.stop();
+    }
+}
@@ -0,0 +1,1521 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::{Signallable, SignallableImpl, WebRTCSignallerRole};
+use crate::utils::gvalue_to_json;
+use crate::RUNTIME;
+use anyhow::{anyhow, Error};
+use async_tungstenite::tungstenite;
+use async_tungstenite::tungstenite::client::IntoClientRequest;
+use async_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use futures::channel::mpsc;
+use futures::prelude::*;
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::{task, time::timeout};
+use tungstenite::Message as WsMessage;
+use url::Url;
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "webrtc-json-signaller",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC JSON signaller"),
+    )
+});
+
+const DEFAULT_URI: &str = "ws://127.0.0.1:8443";
+
+const DEFAULT_START_SESSION_TEMPLATE: &str = r#"{"type":"start-session","peer-id":"${peer-id}"}"#;
+const DEFAULT_SDP_TEMPLATE: &str =
+    r#"{"type":"sdp","session-id":"${session-id}","sdp-type":"${sdp-type}","sdp":"${sdp}"}"#;
+const DEFAULT_ICE_TEMPLATE: &str = r#"{"type":"ice","session-id":"${session-id}","candidate":"${candidate}","sdp-m-line-index":"${sdp-m-line-index}","sdp-mid":"${sdp-mid}"}"#;
+const DEFAULT_END_SESSION_TEMPLATE: &str = r#"{"type":"end-session","session-id":"${session-id}"}"#;
+
+const DEFAULT_SESSION_REQUESTED_MATCH: &str = "/type=session-requested";
+const DEFAULT_SESSION_STARTED_MATCH: &str = "/type=session-started";
+const DEFAULT_SDP_MATCH: &str = "/type=sdp";
+const DEFAULT_ICE_MATCH: &str = "/type=ice";
+const DEFAULT_END_SESSION_MATCH: &str = "/type=end-session";
+const DEFAULT_ERROR_MATCH: &str = "/type=error";
+
+const DEFAULT_SESSION_ID_POINTER: &str = "/session-id";
+const DEFAULT_PEER_ID_POINTER: &str = "/peer-id";
+const DEFAULT_SDP_POINTER: &str = "/sdp";
+const DEFAULT_SDP_TYPE_POINTER: &str = "/sdp-type";
+const DEFAULT_CANDIDATE_POINTER: &str = "/candidate";
+const DEFAULT_SDP_M_LINE_INDEX_POINTER: &str = "/sdp-m-line-index";
+const DEFAULT_SDP_MID_POINTER: &str = "/sdp-mid";
+const DEFAULT_ERROR_POINTER: &str = "/details";
+
+/// A JSON message in which `${name}` placeholders are replaced by the
+/// value of the variable `name`
+#[derive(Debug, Clone)]
+struct Template {
+    text: String,
+    value: Value,
+}
+
+impl FromStr for Template {
+    type Err = serde_json::Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            text: text.to_string(),
+            value: serde_json::from_str(text)?,
+        })
+    }
+}
+
+impl Template {
+    fn render(&self, variables: &HashMap<String, Value>) -> Value {
+        render_value(&self.value, variables)
+    }
+}
+
+fn render_value(value: &Value, variables: &HashMap<String, Value>) -> Value {
+    match value {
+        Value::String(s) => render_string(s, variables),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| render_value(value, variables))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), render_value(value, variables)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Strings only made of a placeholder are replaced by the variable, keeping
+/// its JSON type, placeholders are otherwise interpolated as text. Unknown
+/// placeholders are left untouched.
+fn render_string(s: &str, variables: &HashMap<String, Value>) -> Value {
+    if let Some(value) = s
+        .strip_prefix("${")
+        .and_then(|name| name.strip_suffix('}'))
+        .and_then(|name| variables.get(name))
+    {
+        return value.clone();
+    }
+
+    let mut rendered = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+
+        rendered.push_str(&rest[..start]);
+        match variables.get(&rest[start + 2..start + len]) {
+            Some(Value::String(value)) => rendered.push_str(value),
+            Some(Value::Null) => (),
+            Some(value) => rendered.push_str(&value.to_string()),
+            None => rendered.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    rendered.push_str(rest);
+
+    Value::String(rendered)
+}
+
+/// Selects the incoming messages holding a value at a JSON pointer,
+/// optionally equal to a given value: `<pointer>` or `<pointer>=<value>`
+#[derive(Debug, Clone, PartialEq)]
+struct MessageMatch {
+    pointer: String,
+    value: Option<String>,
+}
+
+impl FromStr for MessageMatch {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pointer, value) = match s.split_once('=') {
+            Some((pointer, value)) => (pointer, Some(value.to_string())),
+            None => (s, None),
+        };
+
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Err(anyhow!("Invalid JSON pointer {pointer}"));
+        }
+
+        Ok(Self {
+            pointer: pointer.to_string(),
+            value,
+        })
+    }
+}
+
+impl fmt::Display for MessageMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}={value}", self.pointer),
+            None => f.write_str(&self.pointer),
+        }
+    }
+}
+
+impl MessageMatch {
+    fn matches(&self, msg: &Value) -> bool {
+        match (
+            msg.pointer(&self.pointer).and_then(value_to_string),
+            &self.value,
+        ) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(value), Some(expected)) => value == *expected,
+        }
+    }
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MessageKind {
+    Error,
+    EndSession,
+    SessionRequested,
+    SessionStarted,
+    Sdp,
+    Ice,
+}
+
+#[derive(Debug, Clone)]
+struct Templates {
+    register: Option<Template>,
+    start_session: Option<Template>,
+    sdp: Option<Template>,
+    ice: Option<Template>,
+    end_session: Option<Template>,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self {
+            register: None,
+            start_session: DEFAULT_START_SESSION_TEMPLATE.parse().ok(),
+            sdp: DEFAULT_SDP_TEMPLATE.parse().ok(),
+            ice: DEFAULT_ICE_TEMPLATE.parse().ok(),
+            end_session: DEFAULT_END_SESSION_TEMPLATE.parse().ok(),
+        }
+    }
+}
+
+impl Templates {
+    fn get_mut(&mut self, property: &str) -> Option<&mut Option<Template>> {
+        Some(match property {
+            "register-template" => &mut self.register,
+            "start-session-template" => &mut self.start_session,
+            "sdp-template" => &mut self.sdp,
+            "ice-template" => &mut self.ice,
+            "end-session-template" => &mut self.end_session,
+            _ => return None,
+        })
+    }
+}
+
+/// How incoming messages are identified and their fields extracted
+#[derive(Debug, Clone)]
+struct Rules {
+    session_requested: Option<MessageMatch>,
+    session_started: Option<MessageMatch>,
+    sdp: Option<MessageMatch>,
+    ice: Option<MessageMatch>,
+    end_session: Option<MessageMatch>,
+    error: Option<MessageMatch>,
+    session_id_pointer: Option<String>,
+    peer_id_pointer: Option<String>,
+    sdp_pointer: Option<String>,
+    sdp_type_pointer: Option<String>,
+    candidate_pointer: Option<String>,
+    sdp_m_line_index_pointer: Option<String>,
+    sdp_mid_pointer: Option<String>,
+    error_pointer: Option<String>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            session_requested: DEFAULT_SESSION_REQUESTED_MATCH.parse().ok(),
+            session_started: DEFAULT_SESSION_STARTED_MATCH.parse().ok(),
+            sdp: DEFAULT_SDP_MATCH.parse().ok(),
+            ice: DEFAULT_ICE_MATCH.parse().ok(),
+            end_session: DEFAULT_END_SESSION_MATCH.parse().ok(),
+            error: DEFAULT_ERROR_MATCH.parse().ok(),
+            session_id_pointer: Some(DEFAULT_SESSION_ID_POINTER.to_string()),
+            peer_id_pointer: Some(DEFAULT_PEER_ID_POINTER.to_string()),
+            sdp_pointer: Some(DEFAULT_SDP_POINTER.to_string()),
+            sdp_type_pointer: Some(DEFAULT_SDP_TYPE_POINTER.to_string()),
+            candidate_pointer: Some(DEFAULT_CANDIDATE_POINTER.to_string()),
+            sdp_m_line_index_pointer: Some(DEFAULT_SDP_M_LINE_INDEX_POINTER.to_string()),
+            sdp_mid_pointer: Some(DEFAULT_SDP_MID_POINTER.to_string()),
+            error_pointer: Some(DEFAULT_ERROR_POINTER.to_string()),
+        }
+    }
+}
+
+impl Rules {
+    fn match_mut(&mut self, property: &str) -> Option<&mut Option<MessageMatch>> {
+        Some(match property {
+            "session-requested-match" => &mut self.session_requested,
+            "session-started-match" => &mut self.session_started,
+            "sdp-match" => &mut self.sdp,
+            "ice-match" => &mut self.ice,
+            "end-session-match" => &mut self.end_session,
+            "error-match" => &mut self.error,
+            _ => return None,
+        })
+    }
+
+    fn pointer_mut(&mut self, property: &str) -> Option<&mut Option<String>> {
+        Some(match property {
+            "session-id-pointer" => &mut self.session_id_pointer,
+            "peer-id-pointer" => &mut self.peer_id_pointer,
+            "sdp-pointer" => &mut self.sdp_pointer,
+            "sdp-type-pointer" => &mut self.sdp_type_pointer,
+            "candidate-pointer" => &mut self.candidate_pointer,
+            "sdp-m-line-index-pointer" => &mut self.sdp_m_line_index_pointer,
+            "sdp-mid-pointer" => &mut self.sdp_mid_pointer,
+            "error-pointer" => &mut self.error_pointer,
+            _ => return None,
+        })
+    }
+
+    /// The kind of the first rule matching `msg`, session lifecycle rules
+    /// taking precedence over the SDP and ICE ones
+    fn kind(&self, msg: &Value) -> Option<MessageKind> {
+        [
+            (&self.error, MessageKind::Error),
+            (&self.end_session, MessageKind::EndSession),
+            (&self.session_requested, MessageKind::SessionRequested),
+            (&self.session_started, MessageKind::SessionStarted),
+            (&self.sdp, MessageKind::Sdp),
+            (&self.ice, MessageKind::Ice),
+        ]
+        .into_iter()
+        .find(|(rule, _)| rule.as_ref().is_some_and(|rule| rule.matches(msg)))
+        .map(|(_, kind)| kind)
+    }
+
+    fn extract(msg: &Value, pointer: &Option<String>) -> Option<String> {
+        pointer
+            .as_deref()
+            .and_then(|pointer| msg.pointer(pointer))
+            .and_then(value_to_string)
+    }
+}
+
+struct Settings {
+    uri: Url,
+    role: WebRTCSignallerRole,
+    producer_peer_id: Option<String>,
+    headers: Option<gst::Structure>,
+    variables: Option<gst::Structure>,
+    templates: Templates,
+    rules: Rules,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            uri: Url::from_str(DEFAULT_URI).unwrap(),
+            role: Default::default(),
+            producer_peer_id: None,
+            headers: None,
+            variables: None,
+            templates: Default::default(),
+            rules: Default::default(),
+        }
+    }
+}
+
+struct Session {
+    peer_id: String,
+    sent_offer: bool,
+}
+
+#[derive(Default)]
+struct State {
+    /// Sender for the websocket messages
+    websocket_sender: Option<mpsc::Sender<Value>>,
+    connect_task_handle: Option<task::JoinHandle<()>>,
+    send_task_handle: Option<task::JoinHandle<Result<(), Error>>>,
+    receive_task_handle: Option<task::JoinHandle<()>>,
+    meta: Value,
+    sessions: HashMap<String, Session>,
+}
+
+#[derive(Default)]
+pub struct JsonSignaller {
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+impl JsonSignaller {
+    async fn connect(&self) -> Result<(), Error> {
+        let (uri, headers) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.uri.clone(), settings.headers.clone())
+        };
+
+        gst::info!(CAT, imp = self, "connecting to {uri}");
+
+        let mut req = uri.into_client_request()?;
+        if let Some(headers) = headers {
+            let req_headers = req.headers_mut();
+            for (key, value) in headers.iter() {
+                let Ok(Ok(value)) = value.transform::<String>().map(|v| v.get::<String>()) else {
+                    gst::warning!(
+                        CAT,
+                        imp = self,
+                        "Failed to convert header '{key}' to string ('{value:?}')"
+                    );
+                    continue;
+                };
+
+                req_headers.insert(
+                    HeaderName::from_bytes(key.as_bytes())?,
+                    HeaderValue::from_str(&value)?,
+                );
+            }
+        }
+
+        let (ws, _) = timeout(
+            Duration::from_secs(20),
+            async_tungstenite::tokio::connect_async(req),
+        )
+        .await??;
+
+        gst::info!(CAT, imp = self, "connected");
+
+        let (mut ws_sink, mut ws_stream) = ws.split();
+
+        // 1000 is completely arbitrary, we simply don't want infinite piling
+        // up of messages as with unbounded
+        let (websocket_sender, mut websocket_receiver) = mpsc::channel::<Value>(1000);
+        let send_task_handle = RUNTIME.spawn(glib::clone!(
+            #[to_owned(rename_to = this)]
+            self,
+            async move {
+                let mut res = Ok(());
+                while let Some(msg) = websocket_receiver.next().await {
+                    gst::log!(CAT, imp = this, "Sending websocket message {msg}");
+                    res = ws_sink.send(WsMessage::text(msg.to_string())).await;
+
+                    if let Err(ref err) = res {
+                        gst::error!(CAT, imp = this, "Quitting send loop: {err}");
+                        break;
+                    }
+                }
+
+                gst::debug!(CAT, imp = this, "Done sending");
+
+                let _ = ws_sink.close().await;
+
+                res.map_err(Into::into)
+            }
+        ));
+
+        let receive_task_handle = RUNTIME.spawn(glib::clone!(
+            #[to_owned(rename_to = this)]
+            self,
+            async move {
+                while let Some(msg) = tokio_stream::StreamExt::next(&mut ws_stream).await {
+                    if let ControlFlow::Break(_) = this.handle_message(msg) {
+                        break;
+                    }
+                }
+
+                gst::info!(CAT, imp = this, "Stopped websocket receiving");
+            }
+        ));
+
+        let meta = self
+            .obj()
+            .emit_by_name::<Option<gst::Structure>>("request-meta", &[])
+            .and_then(|meta| gvalue_to_json(&meta.to_value()))
+            .unwrap_or_default();
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.websocket_sender = Some(websocket_sender);
+            state.send_task_handle = Some(send_task_handle);
+            state.receive_task_handle = Some(receive_task_handle);
+            state.meta = meta;
+        }
+
+        let (register, start_session, role, producer_peer_id) = {
+            let settings = self.settings.lock().unwrap();
+            (
+                settings.templates.register.clone(),
+                settings.templates.start_session.clone(),
+                settings.role,
+                settings.producer_peer_id.clone(),
+            )
+        };
+
+        self.send_template(register, &[]);
+
+        if role == WebRTCSignallerRole::Consumer {
+            self.send_template(start_session, &[("peer-id", Value::from(producer_peer_id))]);
+        }
+
+        Ok(())
+    }
+
+    /// Renders `template` with the configured variables and `variables`
+    /// and sends the resulting message
+    fn send_template(&self, template: Option<Template>, variables: &[(&str, Value)]) {
+        let Some(template) = template else {
+            return;
+        };
+
+        let mut all_variables = self
+            .settings
+            .lock()
+            .unwrap()
+            .variables
+            .as_ref()
+            .map(|variables| {
+                variables
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        gvalue_to_json(value).map(|value| (name.to_string(), value))
+                    })
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+        all_variables.insert("meta".to_string(), self.state.lock().unwrap().meta.clone());
+        all_variables.extend(
+            variables
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone())),
+        );
+
+        self.send(template.render(&all_variables));
+    }
+
+    fn send(&self, msg: Value) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(sender) = state.websocket_sender.as_mut() {
+            if let Err(err) = sender.try_send(msg) {
+                drop(state);
+                self.obj()
+                    .emit_by_name::<()>("error", &[&format!("Error: {}", err)]);
+            }
+        }
+    }
+
+    fn parse_sdp(&self, sdp: &str) -> Option<gst_sdp::SDPMessage> {
+        match gst_sdp::SDPMessage::parse_buffer(sdp.as_bytes()) {
+            Ok(sdp) => Some(sdp),
+            Err(err) => {
+                self.obj()
+                    .emit_by_name::<()>("error", &[&format!("Error parsing SDP: {sdp} {err:?}")]);
+                None
+            }
+        }
+    }
+
+    /// ID of the session an incoming message is about, falling back to the
+    /// only running session for protocols without session IDs
+    fn session_id(&self, msg: &Value, rules: &Rules) -> Option<String> {
+        Rules::extract(msg, &rules.session_id_pointer).or_else(|| {
+            let state = self.state.lock().unwrap();
+            let mut session_ids = state.sessions.keys();
+            match (session_ids.next(), session_ids.next()) {
+                (Some(session_id), None) => Some(session_id.clone()),
+                _ => None,
+            }
+        })
+    }
+
+    fn handle_message(&self, msg: Result<WsMessage, tungstenite::Error>) -> ControlFlow<()> {
+        match msg {
+            Ok(WsMessage::Text(msg)) => {
+                gst::trace!(CAT, imp = self, "Received message {}", msg);
+
+                match serde_json::from_str::<Value>(&msg) {
+                    Ok(msg) => self.handle_json_message(&msg),
+                    Err(err) => {
+                        gst::warning!(CAT, imp = self, "Ignoring invalid message {msg}: {err}")
+                    }
+                }
+            }
+            Ok(WsMessage::Close(reason)) => {
+                gst::info!(CAT, imp = self, "websocket connection closed: {:?}", reason);
+                return ControlFlow::Break(());
+            }
+            Ok(_) => (),
+            Err(err) => {
+                self.obj()
+                    .emit_by_name::<()>("error", &[&format!("Error receiving: {}", err)]);
+                return ControlFlow::Break(());
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn handle_sdp(&self, session_id: &str, sdp: &str, msg: &Value, rules: &Rules) {
+        let Some(sdp) = self.parse_sdp(sdp) else {
+            return;
+        };
+
+        // Without an explicit type, the description replies to ours
+        let sdp_type = match Rules::extract(msg, &rules.sdp_type_pointer)
+            .map(|sdp_type| sdp_type.to_lowercase())
+            .as_deref()
+        {
+            Some("offer") => gst_webrtc::WebRTCSDPType::Offer,
+            Some("answer") => gst_webrtc::WebRTCSDPType::Answer,
+            _ => {
+                let state = self.state.lock().unwrap();
+                if state
+                    .sessions
+                    .get(session_id)
+                    .is_some_and(|session| session.sent_offer)
+                {
+                    gst_webrtc::WebRTCSDPType::Answer
+                } else {
+                    gst_webrtc::WebRTCSDPType::Offer
+                }
+            }
+        };
+
+        let desc = gst_webrtc::WebRTCSessionDescription::new(sdp_type, sdp);
+        self.obj()
+            .emit_by_name::<()>("session-description", &[&session_id, &desc]);
+    }
+
+    fn handle_json_message(&self, msg: &Value) {
+        let rules = self.settings.lock().unwrap().rules.clone();
+
+        let Some(kind) = rules.kind(msg) else {
+            gst::debug!(CAT, imp = self, "Ignoring unmatched message {msg}");
+            return;
+        };
+
+        match kind {
+            MessageKind::Error => {
+                let details =
+                    Rules::extract(msg, &rules.error_pointer).unwrap_or_else(|| msg.to_string());
+
+                self.obj().emit_by_name::<()>(
+                    "error",
+                    &[&format!("Error message from server: {details}")],
+                );
+            }
+            MessageKind::EndSession => {
+                let Some(session_id) = self.session_id(msg, &rules) else {
+                    gst::warning!(CAT, imp = self, "No session to end for {msg}");
+                    return;
+                };
+
+                self.state.lock().unwrap().sessions.remove(&session_id);
+
+                gst::info!(CAT, imp = self, "Session {session_id} ended");
+
+                self.obj()
+                    .emit_by_name::<bool>("session-ended", &[&session_id]);
+            }
+            MessageKind::SessionRequested => {
+                let peer_id = Rules::extract(msg, &rules.peer_id_pointer);
+                let session_id = Rules::extract(msg, &rules.session_id_pointer)
+                    .or_else(|| peer_id.clone())
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+                let peer_id = peer_id.unwrap_or_else(|| session_id.clone());
+
+                let offer = match Rules::extract(msg, &rules.sdp_pointer) {
+                    Some(sdp) => {
+                        let Some(sdp) = self.parse_sdp(&sdp) else {
+                            return;
+                        };
+
+                        Some(gst_webrtc::WebRTCSessionDescription::new(
+                            gst_webrtc::WebRTCSDPType::Offer,
+                            sdp,
+                        ))
+                    }
+                    None => None,
+                };
+
+                self.state.lock().unwrap().sessions.insert(
+                    session_id.clone(),
+                    Session {
+                        peer_id: peer_id.clone(),
+                        sent_offer: false,
+                    },
+                );
+
+                self.obj()
+                    .emit_by_name::<()>("session-requested", &[&session_id, &peer_id, &offer]);
+            }
+            MessageKind::SessionStarted => {
+                let peer_id = Rules::extract(msg, &rules.peer_id_pointer)
+                    .or_else(|| self.settings.lock().unwrap().producer_peer_id.clone());
+                let session_id = Rules::extract(msg, &rules.session_id_pointer)
+                    .or_else(|| peer_id.clone())
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+                let peer_id = peer_id.unwrap_or_else(|| session_id.clone());
+
+                self.state.lock().unwrap().sessions.insert(
+                    session_id.clone(),
+                    Session {
+                        peer_id: peer_id.clone(),
+                        sent_offer: false,
+                    },
+                );
+
+                self.obj()
+                    .emit_by_name::<()>("session-started", &[&session_id, &peer_id]);
+
+                // Servers may start sessions by sending their offer
+                if let Some(sdp) = Rules::extract(msg, &rules.sdp_pointer) {
+                    self.handle_sdp(&session_id, &sdp, msg, &rules);
+                }
+            }
+            MessageKind::Sdp => {
+                let Some(session_id) = self.session_id(msg, &rules) else {
+                    gst::warning!(CAT, imp = self, "No session for SDP message {msg}");
+                    return;
+                };
+                let Some(sdp) = Rules::extract(msg, &rules.sdp_pointer) else {
+                    gst::warning!(CAT, imp = self, "No SDP in message {msg}");
+                    return;
+                };
+
+                self.handle_sdp(&session_id, &sdp, msg, &rules);
+            }
+            MessageKind::Ice => {
+                let Some(session_id) = self.session_id(msg, &rules) else {
+                    gst::warning!(CAT, imp = self, "No session for ICE message {msg}");
+                    return;
+                };
+                let Some(candidate) = Rules::extract(msg, &rules.candidate_pointer) else {
+                    gst::warning!(CAT, imp = self, "No candidate in message {msg}");
+                    return;
+                };
+                let Some(sdp_m_line_index) = Rules::extract(msg, &rules.sdp_m_line_index_pointer)
+                    .and_then(|index| index.parse::<u32>().ok())
+                else {
+                    gst::warning!(CAT, imp = self, "No SDP m-line index in message {msg}");
+                    return;
+                };
+                let sdp_mid = Rules::extract(msg, &rules.sdp_mid_pointer);
+
+                self.obj().emit_by_name::<()>(
+                    "handle-ice",
+                    &[&session_id, &sdp_m_line_index, &sdp_mid, &candidate],
+                );
+            }
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for JsonSignaller {
+    const NAME: &'static str = "GstJsonWebRTCSignaller";
+    type Type = super::JsonSignaller;
+    type ParentType = glib::Object;
+    type Interfaces = (Signallable,);
+}
+
+impl ObjectImpl for JsonSignaller {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPS: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecBoolean::builder("manual-sdp-munging")
+                    .nick("Manual SDP munging")
+                    .blurb("Whether the signaller manages SDP munging itself")
+                    .default_value(false)
+                    .read_only()
+                    .build(),
+                glib::ParamSpecString::builder("uri")
+                    .nick("Signaller URI")
+                    .blurb("URI of the WebSocket signalling server")
+                    .default_value(Some(DEFAULT_URI))
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("role", WebRTCSignallerRole::Consumer)
+                    .nick("Role")
+                    .blurb("Role within the session, consumers request a session with the producer once connected")
+                    .build(),
+                glib::ParamSpecString::builder("producer-peer-id")
+                    .nick("Producer peer id")
+                    .blurb("The peer id of the producer consumers request a session with")
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("headers")
+                    .nick("HTTP headers")
+                    .blurb("HTTP headers sent during the connection handshake")
+                    .build(),
+                /**
+                 * GstJsonWebRTCSignaller:variables:
+                 *
+                 * Values of the placeholders of the message templates, in
+                 * addition to the ones describing the message being sent.
+                 */
+                glib::ParamSpecBoxed::builder::<gst::Structure>("variables")
+                    .nick("Variables")
+                    .blurb("Values of additional placeholders of the message templates")
+                    .build(),
+                /**
+                 * GstJsonWebRTCSignaller:register-template:
+                 *
+                 * JSON message sent once connected to the server, nothing is
+                 * sent when unset.
+                 *
+                 * In all the templates, JSON strings only made of a `${name}`
+                 * placeholder are replaced by the value of the variable with its
+                 * JSON type, placeholders are otherwise interpolated as text.
+                 * `${meta}` holds the metadata of the peer.
+                 */
+                glib::ParamSpecString::builder("register-template")
+                    .nick("Register template")
+                    .blurb("JSON message sent once connected to the server")
+                    .build(),
+                /**
+                 * GstJsonWebRTCSignaller:start-session-template:
+                 *
+                 * JSON message sent by consumers once connected to request a
+                 * session with the `${peer-id}` producer.
+                 */
+                glib::ParamSpecString::builder("start-session-template")
+                    .nick("Start session template")
+                    .blurb("JSON message sent by consumers to request a session with ${peer-id}")
+                    .default_value(Some(DEFAULT_START_SESSION_TEMPLATE))
+                    .build(),
+                /**
+                 * GstJsonWebRTCSignaller:sdp-template:
+                 *
+                 * JSON message carrying the `${sdp}` of type `${sdp-type}`
+                 * (`offer` or `answer`) of session `${session-id}` with
+                 * `${peer-id}`.
+                 */
+                glib::ParamSpecString::builder("sdp-template")
+                    .nick("SDP template")
+                    .blurb("JSON message sending ${sdp} of type ${sdp-type} for ${session-id}")
+                    .default_value(Some(DEFAULT_SDP_TEMPLATE))
+                    .build(),
+                /**
+                 * GstJsonWebRTCSignaller:ice-template:
+                 *
+                 * JSON message carrying the ICE `${candidate}` for the
+                 * `${sdp-m-line-index}` and `${sdp-mid}` media of session
+                 * `${session-id}` with `${peer-id}`.
+                 */
+                glib::ParamSpecString::builder("ice-template")
+                    .nick("ICE template")
+                    .blurb("JSON message sending ${candidate} for ${sdp-m-line-index} and ${sdp-mid} of ${session-id}")
+                    .default_value(Some(DEFAULT_ICE_TEMPLATE))
+                    .build(),
+                glib::ParamSpecString::builder("end-session-template")
+                    .nick("End session template")
+                    .blurb("JSON message ending ${session-id} with ${peer-id}")
+                    .default_value(Some(DEFAULT_END_SESSION_TEMPLATE))
+                    .build(),
+                /**
+                 * GstJsonWebRTCSignaller:session-requested-match:
+                 *
+                 * Rule selecting the incoming messages requesting a session
+                 * from producers, optionally with an offer.
+                 *
+                 * Rules are either a JSON pointer, matching messages holding a
+                 * value at that location, or `<pointer>=<value>` matching
+                 * messages holding that value. The first rule matching a
+                 * message in the order of errors, session end, session request,
+                 * session start, SDP and ICE selects how it is handled, messages
+                 * matching none are ignored.
+                 */
+                glib::ParamSpecString::builder("session-requested-match")
+                    .nick("Session requested match")
+                    .blurb("Rule selecting the messages requesting a session")
+                    .default_value(Some(DEFAULT_SESSION_REQUESTED_MATCH))
+                    .build(),
+                glib::ParamSpecString::builder("session-started-match")
+                    .nick("Session started match")
+                    .blurb("Rule selecting the messages starting the session of consumers, optionally with an offer")
+                    .default_value(Some(DEFAULT_SESSION_STARTED_MATCH))
+                    .build(),
+                glib::ParamSpecString::builder("sdp-match")
+                    .nick("SDP match")
+                    .blurb("Rule selecting the messages carrying an SDP")
+                    .default_value(Some(DEFAULT_SDP_MATCH))
+                    .build(),
+                glib::ParamSpecString::builder("ice-match")
+                    .nick("ICE match")
+                    .blurb("Rule selecting the messages carrying an ICE candidate")
+                    .default_value(Some(DEFAULT_ICE_MATCH))
+                    .build(),
+                glib::ParamSpecString::builder("end-session-match")
+                    .nick("End session match")
+                    .blurb("Rule selecting the messages ending a session")
+                    .default_value(Some(DEFAULT_END_SESSION_MATCH))
+                    .build(),
+                glib::ParamSpecString::builder("error-match")
+                    .nick("Error match")
+                    .blurb("Rule selecting the error messages")
+                    .default_value(Some(DEFAULT_ERROR_MATCH))
+                    .build(),
+                /**
+                 * GstJsonWebRTCSignaller:session-id-pointer:
+                 *
+                 * JSON pointer to the session ID of incoming messages. Messages
+                 * without it refer to the only running session, new sessions
+                 * are then identified by the ID of their peer.
+                 */
+                glib::ParamSpecString::builder("session-id-pointer")
+                    .nick("Session ID pointer")
+                    .blurb("JSON pointer to the session ID of incoming messages")
+                    .default_value(Some(DEFAULT_SESSION_ID_POINTER))
+                    .build(),
+                glib::ParamSpecString::builder("peer-id-pointer")
+                    .nick("Peer ID pointer")
+                    .blurb("JSON pointer to the peer ID of incoming session requests and starts")
+                    .default_value(Some(DEFAULT_PEER_ID_POINTER))
+                    .build(),
+                glib::ParamSpecString::builder("sdp-pointer")
+                    .nick("SDP pointer")
+                    .blurb("JSON pointer to the SDP of incoming messages")
+                    .default_value(Some(DEFAULT_SDP_POINTER))
+                    .build(),
+                /**
+                 * GstJsonWebRTCSignaller:sdp-type-pointer:
+                 *
+                 * JSON pointer to the type of incoming SDPs, `offer` or
+                 * `answer`. SDPs without type are answers to our offers and
+                 * offers otherwise.
+                 */
+                glib::ParamSpecString::builder("sdp-type-pointer")
+                    .nick("SDP type pointer")
+                    .blurb("JSON pointer to the type of incoming SDPs")
+                    .default_value(Some(DEFAULT_SDP_TYPE_POINTER))
+                    .build(),
+                glib::ParamSpecString::builder("candidate-pointer")
+                    .nick("Candidate pointer")
+                    .blurb("JSON pointer to the ICE candidate of incoming messages")
+                    .default_value(Some(DEFAULT_CANDIDATE_POINTER))
+                    .build(),
+                glib::ParamSpecString::builder("sdp-m-line-index-pointer")
+                    .nick("SDP m-line index pointer")
+                    .blurb("JSON pointer to the SDP m-line index of incoming ICE candidates")
+                    .default_value(Some(DEFAULT_SDP_M_LINE_INDEX_POINTER))
+                    .build(),
+                glib::ParamSpecString::builder("sdp-mid-pointer")
+                    .nick("SDP mid pointer")
+                    .blurb("JSON pointer to the media ID of incoming ICE candidates")
+                    .default_value(Some(DEFAULT_SDP_MID_POINTER))
+                    .build(),
+                glib::ParamSpecString::builder("error-pointer")
+                    .nick("Error pointer")
+                    .blurb("JSON pointer to the details of incoming errors")
+                    .default_value(Some(DEFAULT_ERROR_POINTER))
+                    .build(),
+            ]
+        });
+
+        PROPS.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        let name = pspec.name();
+
+        match name {
+            "uri" => {
+                let uri = value.get::<&str>().expect("type checked upstream");
+                match Url::from_str(uri) {
+                    Ok(uri) => settings.uri = uri,
+                    Err(err) => gst::error!(CAT, imp = self, "Couldn't set URI: {err:?}"),
+                }
+            }
+            "role" => {
+                settings.role = value
+                    .get::<WebRTCSignallerRole>()
+                    .expect("type checked upstream")
+            }
+            "producer-peer-id" => {
+                settings.producer_peer_id = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+            }
+            "headers" => {
+                settings.headers = value
+                    .get::<Option<gst::Structure>>()
+                    .expect("type checked upstream")
+            }
+            "variables" => {
+                settings.variables = value
+                    .get::<Option<gst::Structure>>()
+                    .expect("type checked upstream")
+            }
+            _ => {
+                let value = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+
+                if let Some(template) = settings.templates.get_mut(name) {
+                    match value.as_deref().map(Template::from_str).transpose() {
+                        Ok(value) => *template = value,
+                        Err(err) => gst::error!(CAT, imp = self, "Invalid {name}: {err}"),
+                    }
+                } else if let Some(rule) = settings.rules.match_mut(name) {
+                    match value.as_deref().map(MessageMatch::from_str).transpose() {
+                        Ok(value) => *rule = value,
+                        Err(err) => gst::error!(CAT, imp = self, "Invalid {name}: {err}"),
+                    }
+                } else if let Some(pointer) = settings.rules.pointer_mut(name) {
+                    *pointer = value;
+                } else {
+                    unimplemented!()
+                }
+            }
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let mut settings = self.settings.lock().unwrap();
+        let name = pspec.name();
+
+        match name {
+            "manual-sdp-munging" => false.to_value(),
+            "uri" => settings.uri.to_string().to_value(),
+            "role" => settings.role.to_value(),
+            "producer-peer-id" => settings.producer_peer_id.to_value(),
+            "headers" => settings.headers.to_value(),
+            "variables" => settings.variables.to_value(),
+            _ => {
+                if let Some(template) = settings.templates.get_mut(name) {
+                    template
+                        .as_ref()
+                        .map(|template| template.text.clone())
+                        .to_value()
+                } else if let Some(rule) = settings.rules.match_mut(name) {
+                    rule.as_ref().map(|rule| rule.to_string()).to_value()
+                } else if let Some(pointer) = settings.rules.pointer_mut(name) {
+                    pointer.to_value()
+                } else {
+                    unimplemented!()
+                }
+            }
+        }
+    }
+}
+
+impl SignallableImpl for JsonSignaller {
+    fn start(&self) {
+        gst::info!(CAT, imp = self, "Starting");
+
+        let mut state = self.state.lock().unwrap();
+        let connect_task_handle = RUNTIME.spawn(glib::clone!(
+            #[to_owned(rename_to = this)]
+            self,
+            async move {
+                if let Err(err) = this.connect().await {
+                    this.obj()
+                        .emit_by_name::<()>("error", &[&format!("Error connecting: {}", err)]);
+                }
+            }
+        ));
+
+        state.connect_task_handle = Some(connect_task_handle);
+    }
+
+    fn stop(&self) {
+        gst::info!(CAT, imp = self, "Stopping now");
+
+        let mut state = self.state.lock().unwrap();
+
+        // First make sure the connect task is stopped if it is still
+        // running
+        let connect_task_handle = state.connect_task_handle.take();
+        if let Some(handle) = connect_task_handle {
+            RUNTIME.block_on(async move {
+                handle.abort();
+                let _ = handle.await;
+            });
+        }
+
+        let send_task_handle = state.send_task_handle.take();
+        let receive_task_handle = state.receive_task_handle.take();
+        if let Some(mut sender) = state.websocket_sender.take() {
+            RUNTIME.block_on(async move {
+                sender.close_channel();
+
+                if let Some(handle) = send_task_handle {
+                    if let Err(err) = handle.await {
+                        gst::warning!(CAT, imp = self, "Error while joining send task: {}", err);
+                    }
+                }
+
+                if let Some(handle) = receive_task_handle {
+                    handle.abort();
+                    let _ = handle.await;
+                }
+            });
+        }
+
+        state.sessions.clear();
+        state.meta = Value::Null;
+    }
+
+    fn send_sdp(&self, session_id: &str, sdp: &gst_webrtc::WebRTCSessionDescription) {
+        gst::debug!(CAT, imp = self, "Sending SDP {sdp:#?}");
+
+        let is_offer = sdp.type_() == gst_webrtc::WebRTCSDPType::Offer;
+        let peer_id = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .get_mut(session_id)
+            .map(|session| {
+                session.sent_offer |= is_offer;
+                session.peer_id.clone()
+            });
+        let template = self.settings.lock().unwrap().templates.sdp.clone();
+
+        self.send_template(
+            template,
+            &[
+                ("session-id", Value::from(session_id)),
+                ("peer-id", Value::from(peer_id)),
+                (
+                    "sdp-type",
+                    Value::from(if is_offer { "offer" } else { "answer" }),
+                ),
+                ("sdp", Value::from(sdp.sdp().as_text().unwrap())),
+            ],
+        );
+    }
+
+    fn add_ice(
+        &self,
+        session_id: &str,
+        candidate: &str,
+        sdp_m_line_index: u32,
+        sdp_mid: Option<String>,
+    ) {
+        gst::debug!(
+            CAT,
+            imp = self,
+            "Adding ice candidate {candidate:?} for {sdp_m_line_index:?} on session {session_id}"
+        );
+
+        let peer_id = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .get(session_id)
+            .map(|session| session.peer_id.clone());
+        let template = self.settings.lock().unwrap().templates.ice.clone();
+
+        self.send_template(
+            template,
+            &[
+                ("session-id", Value::from(session_id)),
+                ("peer-id", Value::from(peer_id)),
+                ("candidate", Value::from(candidate)),
+                ("sdp-m-line-index", Value::from(sdp_m_line_index)),
+                ("sdp-mid", Value::from(sdp_mid)),
+            ],
+        );
+    }
+
+    fn end_session(&self, session_id: &str) {
+        gst::debug!(CAT, imp = self, "Signalling session done {}", session_id);
+
+        let peer_id = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .remove(session_id)
+            .map(|session| session.peer_id);
+        let template = self.settings.lock().unwrap().templates.end_session.clone();
+
+        self.send_template(
+            template,
+            &[
+                ("session-id", Value::from(session_id)),
+                ("peer-id", Value::from(peer_id)),
+            ],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_signaller::JsonSignaller;
+    use crate::signaller::SignallableExt;
+    use serde_json::json;
+
+    const SDP: &str = "v=0\r\n\
+        o=- 0 0 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        t=0 0\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+        a=mid:0\r\n\
+        a=sendonly\r\n";
+
+    const CANDIDATE: &str = "candidate:1 1 UDP 2130706431 192.0.2.1 5000 typ host";
+
+    /// Local WebSocket server accepting a single client and echoing the
+    /// messages it sends, which are also forwarded to the returned channel
+    fn echo_server() -> (u16, std::sync::mpsc::Receiver<Value>) {
+        let listener = RUNTIME
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let (messages_tx, messages_rx) = std::sync::mpsc::channel();
+
+        RUNTIME.spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = async_tungstenite::tokio::accept_async(stream)
+                .await
+                .unwrap();
+
+            while let Some(Ok(msg)) = ws.next().await {
+                if let WsMessage::Text(ref text) = msg {
+                    let _ = messages_tx.send(serde_json::from_str::<Value>(text).unwrap());
+                    if ws.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        (port, messages_rx)
+    }
+
+    /// Records the signals emitted by `signaller` as (signal, session ID,
+    /// details) tuples
+    fn record_events(
+        signaller: &JsonSignaller,
+    ) -> std::sync::mpsc::Receiver<(&'static str, String, String)> {
+        let (events_tx, events) = std::sync::mpsc::channel();
+
+        let events_tx_clone = events_tx.clone();
+        signaller.connect_closure(
+            "session-requested",
+            false,
+            glib::closure!(
+                move |_signaller: &JsonSignaller,
+                      session_id: &str,
+                      peer_id: &str,
+                      offer: Option<&gst_webrtc::WebRTCSessionDescription>| {
+                    let offer = offer.map(|offer| offer.sdp().as_text().unwrap());
+                    events_tx_clone
+                        .send((
+                            "requested",
+                            session_id.to_string(),
+                            format!("{peer_id} {offer:?}"),
+                        ))
+                        .unwrap();
+                }
+            ),
+        );
+        let events_tx_clone = events_tx.clone();
+        signaller.connect_closure(
+            "session-started",
+            false,
+            glib::closure!(
+                move |_signaller: &JsonSignaller, session_id: &str, peer_id: &str| {
+                    events_tx_clone
+                        .send(("started", session_id.to_string(), peer_id.to_string()))
+                        .unwrap();
+                }
+            ),
+        );
+        let events_tx_clone = events_tx.clone();
+        signaller.connect_closure(
+            "session-description",
+            false,
+            glib::closure!(
+                move |_signaller: &JsonSignaller,
+                      session_id: &str,
+                      desc: &gst_webrtc::WebRTCSessionDescription| {
+                    events_tx_clone
+                        .send((
+                            "description",
+                            session_id.to_string(),
+                            format!("{:?} {}", desc.type_(), desc.sdp().as_text().unwrap()),
+                        ))
+                        .unwrap();
+                }
+            ),
+        );
+        let events_tx_clone = events_tx.clone();
+        signaller.connect_closure(
+            "handle-ice",
+            false,
+            glib::closure!(move |_signaller: &JsonSignaller,
+                                 session_id: &str,
+                                 sdp_m_line_index: u32,
+                                 sdp_mid: Option<String>,
+                                 candidate: &str| {
+                events_tx_clone
+                    .send((
+                        "ice",
+                        session_id.to_string(),
+                        format!("{sdp_m_line_index} {sdp_mid:?} {candidate}"),
+                    ))
+                    .unwrap();
+            }),
+        );
+        signaller.connect_closure(
+            "session-ended",
+            false,
+            glib::closure!(
+                move |_signaller: &JsonSignaller, session_id: &str| -> bool {
+                    events_tx
+                        .send(("ended", session_id.to_string(), String::new()))
+                        .unwrap();
+                    true
+                }
+            ),
+        );
+
+        events
+    }
+
+    #[test]
+    fn render_template() {
+        let template = Template::from_str(
+            r#"{"index":"${index}","text":"m=${index} ${missing}","mids":["${mid}","${mid}0"]}"#,
+        )
+        .unwrap();
+        let variables = HashMap::from([
+            ("index".to_string(), json!(1)),
+            ("mid".to_string(), Value::Null),
+        ]);
+
+        assert_eq!(
+            template.render(&variables),
+            json!({ "index": 1, "text": "m=1 ${missing}", "mids": [null, "0"] })
+        );
+    }
+
+    #[test]
+    fn message_match() {
+        let rule = MessageMatch::from_str("/type=sdp").unwrap();
+        assert!(rule.matches(&json!({ "type": "sdp" })));
+        assert!(!rule.matches(&json!({ "type": "ice" })));
+        assert_eq!(rule.to_string(), "/type=sdp");
+
+        let rule = MessageMatch::from_str("/data/index=1").unwrap();
+        assert!(rule.matches(&json!({ "data": { "index": 1 } })));
+
+        let rule = MessageMatch::from_str("/data/sdp").unwrap();
+        assert!(rule.matches(&json!({ "data": { "sdp": "v=0" } })));
+        assert!(!rule.matches(&json!({ "data": { "sdp": null } })));
+
+        assert!(MessageMatch::from_str("type=sdp").is_err());
+    }
+
+    #[test]
+    fn consumer() {
+        gst::init().unwrap();
+
+        let (port, messages) = echo_server();
+        let message = || {
+            messages
+                .recv_timeout(Duration::from_secs(5))
+                .expect("no message from the signaller")
+        };
+
+        let signaller = JsonSignaller::new(WebRTCSignallerRole::Consumer);
+        signaller.set_property("uri", format!("ws://127.0.0.1:{port}"));
+        signaller.set_property("producer-peer-id", "producer");
+        signaller.set_property(
+            "variables",
+            gst::Structure::builder("variables")
+                .field("token", "secret")
+                .build(),
+        );
+        signaller.set_property(
+            "register-template",
+            r#"{"type":"register","token":"${token}"}"#,
+        );
+        // The echoed session request starts the session
+        signaller.set_property(
+            "start-session-template",
+            r#"{"type":"session-started","session-id":"session-${peer-id}","peer-id":"${peer-id}"}"#,
+        );
+
+        let events = record_events(&signaller);
+        let event = || events.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        signaller.start();
+
+        assert_eq!(message(), json!({ "type": "register", "token": "secret" }));
+        assert_eq!(
+            message(),
+            json!({ "type": "session-started", "session-id": "session-producer", "peer-id": "producer" })
+        );
+        assert_eq!(
+            event(),
+            (
+                "started",
+                "session-producer".to_string(),
+                "producer".to_string()
+            )
+        );
+
+        let answer = gst_webrtc::WebRTCSessionDescription::new(
+            gst_webrtc::WebRTCSDPType::Answer,
+            gst_sdp::SDPMessage::parse_buffer(SDP.as_bytes()).unwrap(),
+        );
+        signaller.send_sdp("session-producer", &answer);
+        assert_eq!(
+            message(),
+            json!({ "type": "sdp", "session-id": "session-producer", "sdp-type": "answer", "sdp": SDP })
+        );
+        assert_eq!(
+            event(),
+            (
+                "description",
+                "session-producer".to_string(),
+                format!("Answer {SDP}")
+            )
+        );
+
+        signaller.add_ice("session-producer", CANDIDATE, 1, Some("1".to_string()));
+        assert_eq!(
+            message(),
+            json!({
+                "type": "ice",
+                "session-id": "session-producer",
+                "candidate": CANDIDATE,
+                "sdp-m-line-index": 1,
+                "sdp-mid": "1",
+            })
+        );
+        assert_eq!(
+            event(),
+            (
+                "ice",
+                "session-producer".to_string(),
+                format!("1 Some(\"1\") {CANDIDATE}")
+            )
+        );
+
+        signaller.end_session("session-producer");
+        assert_eq!(
+            message(),
+            json!({ "type": "end-session", "session-id": "session-producer" })
+        );
+        assert_eq!(
+            event(),
+            ("ended", "session-producer".to_string(), String::new())
+        );
+
+        signaller.stop();
+    }
+
+    #[test]
+    fn producer_without_session_ids() {
+        gst::init().unwrap();
+
+        let (port, messages) = echo_server();
+        let message = || {
+            messages
+                .recv_timeout(Duration::from_secs(5))
+                .expect("no message from the signaller")
+        };
+
+        let signaller = JsonSignaller::new(WebRTCSignallerRole::Producer);
+        signaller.set_property("uri", format!("ws://127.0.0.1:{port}"));
+        signaller.set_property(
+            "variables",
+            gst::Structure::builder("variables")
+                .field("offer", SDP)
+                .build(),
+        );
+        // The echoed registration requests a session with an offer
+        signaller.set_property(
+            "register-template",
+            r#"{"action":"call","data":{"from":"viewer","sdp":"${offer}"}}"#,
+        );
+        signaller.set_property("session-requested-match", "/action=call");
+        signaller.set_property("sdp-match", "/action=sdp");
+        signaller.set_property("ice-match", "/action=candidate");
+        signaller.set_property("session-id-pointer", None::<String>);
+        signaller.set_property("peer-id-pointer", "/data/from");
+        signaller.set_property("sdp-pointer", "/data/sdp");
+        signaller.set_property("candidate-pointer", "/data/candidate");
+        signaller.set_property("sdp-m-line-index-pointer", "/data/index");
+        signaller.set_property(
+            "sdp-template",
+            r#"{"action":"${sdp-type}","to":"${peer-id}","data":{"sdp":"${sdp}"}}"#,
+        );
+        signaller.set_property(
+            "ice-template",
+            r#"{"action":"candidate","data":{"candidate":"${candidate}","index":"${sdp-m-line-index}"}}"#,
+        );
+
+        let events = record_events(&signaller);
+        let event = || events.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        signaller.start();
+
+        assert_eq!(
+            message(),
+            json!({ "action": "call", "data": { "from": "viewer", "sdp": SDP } })
+        );
+        assert_eq!(
+            event(),
+            (
+                "requested",
+                "viewer".to_string(),
+                format!("viewer {:?}", Some(SDP))
+            )
+        );
+
+        // The echoed answer isn't matched by any rule
+        let answer = gst_webrtc::WebRTCSessionDescription::new(
+            gst_webrtc::WebRTCSDPType::Answer,
+            gst_sdp::SDPMessage::parse_buffer(SDP.as_bytes()).unwrap(),
+        );
+        signaller.send_sdp("viewer", &answer);
+        assert_eq!(
+            message(),
+            json!({ "action": "answer", "to": "viewer", "data": { "sdp": SDP } })
+        );
+
+        // Messages without session ID refer to the only session
+        signaller.add_ice("viewer", CANDIDATE, 0, None);
+        assert_eq!(
+            message(),
+            json!({ "action": "candidate", "data": { "candidate": CANDIDATE, "index": 0 } })
+        );
+        assert_eq!(
+            event(),
+            ("ice", "viewer".to_string(), format!("0 None {CANDIDATE}"))
+        );
+
+        signaller.stop();
+    }
+}
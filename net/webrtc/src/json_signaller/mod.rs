@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::{Signallable, WebRTCSignallerRole};
+use gst::glib;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct JsonSignaller(ObjectSubclass<imp::JsonSignaller>) @implements Signallable;
+}
+
+impl JsonSignaller {
+    pub fn new(role: WebRTCSignallerRole) -> Self {
+        glib::Object::builder().property("role", role).build()
+    }
+}
+
+impl Default for JsonSignaller {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
@@ -18,6 +18,7 @@ use tokio::runtime;
 mod aws_kvs_signaller;
 #[cfg(feature = "janus")]
 mod janusvr_signaller;
+mod json_signaller;
 #[cfg(feature = "livekit")]
 mod livekit_signaller;
 pub mod signaller;
@@ -44,6 +44,7 @@ pub struct Settings {
     headers: Option<gst::Structure>,
     insecure_tls: bool,
     connect_to_first_producer: bool,
+    room: Option<String>,
 }
 
 impl Default for Settings {
@@ -56,6 +57,7 @@ impl Default for Settings {
             headers: None,
             insecure_tls: DEFAULT_INSECURE_TLS,
             connect_to_first_producer: false,
+            room: None,
         }
     }
 }
@@ -268,22 +270,28 @@ impl Signaller {
     fn set_status(&self, meta: &Option<serde_json::Value>, peer_id: &str) {
         self.state.lock().unwrap().client_id = Some(peer_id.to_string());
 
-        let role = self.settings.lock().unwrap().role;
+        let (role, room) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.role, settings.room.clone())
+        };
         self.send(p::IncomingMessage::SetPeerStatus(match role {
             super::WebRTCSignallerRole::Consumer => p::PeerStatus {
                 meta: meta.clone(),
                 peer_id: Some(peer_id.to_string()),
                 roles: vec![],
+                room,
             },
             super::WebRTCSignallerRole::Producer => p::PeerStatus {
                 meta: meta.clone(),
                 peer_id: Some(peer_id.to_string()),
                 roles: vec![p::PeerRole::Producer],
+                room,
             },
             super::WebRTCSignallerRole::Listener => p::PeerStatus {
                 meta: meta.clone(),
                 peer_id: Some(peer_id.to_string()),
                 roles: vec![p::PeerRole::Listener],
+                room,
             },
         }));
 
@@ -350,6 +358,7 @@ impl Signaller {
             self.send(p::IncomingMessage::StartSession(p::StartSessionMessage {
                 peer_id: target_producer.clone(),
                 offer: None,
+                room: self.settings.lock().unwrap().room.clone(),
             }));
 
             gst::info!(
@@ -383,6 +392,7 @@ impl Signaller {
                             meta,
                             roles,
                             peer_id,
+                            ..
                         }) => {
                             let meta = meta.and_then(|m| match m {
                                 serde_json::Value::Object(v) => Some(serialize_json_object(&v)),
@@ -643,6 +653,19 @@ impl ObjectImpl for Signaller {
                     .default_value(DEFAULT_INSECURE_TLS)
                     .flags(glib::ParamFlags::READWRITE)
                     .build(),
+                /**
+                 * GstWebRTCSignaller:room:
+                 *
+                 * The room to join on the signalling server. Producers are only
+                 * visible to the peers of their room.
+                 *
+                 * Since: plugins-rs-0.14.0
+                 */
+                glib::ParamSpecString::builder("room")
+                    .nick("Room")
+                    .blurb("The room to join on the signaller server")
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
             ]
         });
 
@@ -690,6 +713,11 @@ impl ObjectImpl for Signaller {
                 self.settings.lock().unwrap().insecure_tls =
                     value.get::<bool>().expect("type checked upstream")
             }
+            "room" => {
+                self.settings.lock().unwrap().room = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+            }
             _ => unimplemented!(),
         }
     }
@@ -715,6 +743,7 @@ impl ObjectImpl for Signaller {
             "client-id" => self.state.lock().unwrap().client_id.to_value(),
             "headers" => settings.headers.to_value(),
             "insecure-tls" => settings.insecure_tls.to_value(),
+            "room" => settings.room.to_value(),
             _ => unimplemented!(),
         }
     }
@@ -1,6 +1,7 @@
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     ops::Deref,
+    str::FromStr,
     sync::atomic::{AtomicBool, Ordering},
 };
 
@@ -464,15 +465,13 @@ impl Codec {
         let encoder = Self::get_encoder_for_caps(caps, encoders);
         let payloader = Self::get_payloader_for_codec(name, payloaders);
 
-        let encoding_info = if let (Some(encoder), Some(payloader)) = (encoder, payloader) {
-            Some(EncodingInfo {
-                encoder: Some(encoder),
-                payloader,
-                output_filter: None,
-            })
-        } else {
-            None
-        };
+        // Without an encoder, streams that are already encoded can still
+        // be payloaded
+        let encoding_info = payloader.map(|payloader| EncodingInfo {
+            encoder,
+            payloader,
+            output_filter: None,
+        });
 
         Self {
             caps: caps.clone(),
@@ -529,7 +528,15 @@ impl Codec {
     }
 
     pub fn can_encode(&self) -> bool {
-        self.encoding_info.is_some()
+        self.encoding_info
+            .as_ref()
+            .is_some_and(|info| self.is_raw || info.encoder.is_some())
+    }
+
+    /// Whether a stream already encoded in the format described by `caps`
+    /// can be sent with this codec
+    pub fn can_payload(&self, caps: &gst::Caps) -> bool {
+        self.encoding_info.is_some() && self.caps.can_intersect(caps)
     }
 
     pub fn set_pt(&mut self, pt: i32) {
@@ -829,9 +836,7 @@ impl Codecs {
     }
 
     pub fn find_for_payloadable_caps(&self, caps: &gst::Caps) -> Option<Codec> {
-        self.iter()
-            .find(|codec| codec.caps.can_intersect(caps) && codec.encoding_info.is_some())
-            .cloned()
+        self.iter().find(|codec| codec.can_payload(caps)).cloned()
     }
 }
 
@@ -1000,6 +1005,92 @@ pub fn cleanup_codec_caps(mut caps: gst::Caps) -> gst::Caps {
     caps
 }
 
+/// The H.264 profile signalled by an SDP `profile-level-id`, named as in the
+/// `profile` field of `video/x-h264` caps
+pub fn h264_profile_from_profile_level_id(profile_level_id: &str) -> Option<&'static str> {
+    if profile_level_id.len() != 6 {
+        return None;
+    }
+
+    let profile_idc = u8::from_str_radix(profile_level_id.get(0..2)?, 16).ok()?;
+    let constraint_flags = u8::from_str_radix(profile_level_id.get(2..4)?, 16).ok()?;
+
+    Some(match profile_idc {
+        66 if constraint_flags & 0x40 != 0 => "constrained-baseline",
+        66 => "baseline",
+        77 => "main",
+        88 => "extended",
+        100 if constraint_flags & 0x0c == 0x0c => "constrained-high",
+        100 if constraint_flags & 0x08 != 0 => "progressive-high",
+        100 => "high",
+        110 => "high-10",
+        122 => "high-4:2:2",
+        244 => "high-4:4:4",
+        _ => return None,
+    })
+}
+
+/// Whether a H.264 stream encoded with `stream_profile` can be decoded by a
+/// peer that offered `profile_level_id`
+pub fn is_h264_profile_compatible(stream_profile: &str, profile_level_id: &str) -> bool {
+    let Some(offered_profile) = h264_profile_from_profile_level_id(profile_level_id) else {
+        return false;
+    };
+
+    stream_profile == offered_profile
+        || match stream_profile {
+            "constrained-baseline" => ["baseline", "main", "high"].contains(&offered_profile),
+            "main" | "constrained-high" | "progressive-high" => offered_profile == "high",
+            _ => false,
+        }
+}
+
+/// The SDP `profile-level-id` of an already encoded H.264 stream, read from
+/// the SPS in the `codec_data` of @s if any, from its `profile` and `level`
+/// fields otherwise
+pub fn h264_profile_level_id_from_caps(s: &gst::StructureRef) -> Option<String> {
+    // The avcC record holds the profile_idc, constraint flags and level_idc
+    // of the SPS right after its version byte
+    if let Ok(codec_data) = s.get::<gst::Buffer>("codec_data") {
+        let map = codec_data.map_readable().ok()?;
+        if map.len() >= 4 && map[0] == 1 {
+            return Some(format!("{:02x}{:02x}{:02x}", map[1], map[2], map[3]));
+        }
+    }
+
+    let (profile_idc, mut constraint_flags) = match s.get::<&str>("profile").ok()? {
+        "constrained-baseline" => (66u8, 0xe0u8),
+        "baseline" => (66, 0x00),
+        "main" => (77, 0x00),
+        "extended" => (88, 0x00),
+        "high" => (100, 0x00),
+        "constrained-high" => (100, 0x0c),
+        "progressive-high" => (100, 0x08),
+        "high-10" => (110, 0x00),
+        "high-4:2:2" => (122, 0x00),
+        "high-4:4:4" => (244, 0x00),
+        _ => return None,
+    };
+
+    let level_idc = match s.get::<&str>("level").ok()? {
+        // Level 1b is signalled with constraint_set3_flag in the profiles
+        // below high, with level_idc 9 otherwise
+        "1b" if profile_idc < 100 => {
+            constraint_flags |= 0x10;
+            11
+        }
+        "1b" => 9,
+        level => {
+            let (major, minor) = level.split_once('.').unwrap_or((level, "0"));
+            major.parse::<u8>().ok()? * 10 + minor.parse::<u8>().ok()?
+        }
+    };
+
+    Some(format!(
+        "{profile_idc:02x}{constraint_flags:02x}{level_idc:02x}"
+    ))
+}
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -1051,6 +1142,20 @@ pub fn find_smallest_available_ext_id(ids: impl IntoIterator<Item = u32>) -> u32
     (1..).find(|&num| !used_numbers.contains(&num)).unwrap()
 }
 
+/// Returns the ID `media` maps the RTP header extension `uri` to
+pub fn find_extension_id(media: &gst_sdp::SDPMediaRef, uri: &str) -> Option<u32> {
+    media
+        .attributes()
+        .filter(|attr| attr.key() == "extmap")
+        .find_map(|attr| {
+            // extmap:<id>[/<direction>] <uri> [<attributes>]
+            let mut tokens = attr.value()?.split_whitespace();
+            let id = tokens.next()?.split('/').next()?.parse::<u32>().ok()?;
+
+            (tokens.next()? == uri).then_some(id)
+        })
+}
+
 #[derive(Clone, Debug)]
 enum CoerceTarget {
     Undefined,
@@ -1300,10 +1405,234 @@ pub fn serialize_meta(buffer: &gst::BufferRef, forward_metas: &HashSet<String>)
     ret
 }
 
+/// Sub-protocol of the data channels whose messages start with the header
+/// written by [`data_channel_message`], followed by `;` and the caps if any
+const DATA_CHANNEL_TIMESTAMPED_PROTOCOL: &str = "x-gst-timestamped";
+
+/// Builds the configuration of a data channel carrying application data,
+/// the caps are advertised as the sub-protocol so that the receiver can
+/// restore them, prefixed with [`DATA_CHANNEL_TIMESTAMPED_PROTOCOL`] if
+/// @timestamped
+pub fn data_channel_config(
+    ordered: bool,
+    reliable: bool,
+    caps: Option<&gst::Caps>,
+    timestamped: bool,
+) -> gst::Structure {
+    let mut config = gst::Structure::builder("config")
+        .field("ordered", ordered)
+        .build();
+
+    if !reliable {
+        config.set("max-retransmits", 0i32);
+    }
+
+    let protocol = match (caps, timestamped) {
+        (Some(caps), false) => Some(caps.to_string()),
+        (Some(caps), true) => Some(format!("{DATA_CHANNEL_TIMESTAMPED_PROTOCOL};{caps}")),
+        (None, true) => Some(DATA_CHANNEL_TIMESTAMPED_PROTOCOL.to_string()),
+        (None, false) => None,
+    };
+    if let Some(protocol) = protocol {
+        config.set("protocol", protocol);
+    }
+
+    config
+}
+
+/// Splits the sub-protocol of a data channel into the caps it advertises
+/// and whether its messages are timestamped
+fn split_data_channel_protocol(protocol: &str) -> (Option<&str>, bool) {
+    match protocol.strip_prefix(DATA_CHANNEL_TIMESTAMPED_PROTOCOL) {
+        Some("") => (None, true),
+        Some(rest) if rest.starts_with(';') => (Some(&rest[1..]), true),
+        _ => (Some(protocol), false),
+    }
+}
+
+/// The caps of the application data received over a data channel, from its
+/// sub-protocol, `application/octet-stream` if it doesn't hold fixed caps
+pub fn data_channel_caps(protocol: Option<&str>) -> gst::Caps {
+    protocol
+        .and_then(|protocol| split_data_channel_protocol(protocol).0)
+        .and_then(|caps| gst::Caps::from_str(caps).ok())
+        .filter(|caps| caps.is_fixed() && caps.structure(0).is_some_and(|s| s.name().contains('/')))
+        .unwrap_or_else(|| gst::Caps::new_empty_simple("application/octet-stream"))
+}
+
+/// Whether the messages received over a data channel start with the header
+/// written by [`data_channel_message`], as negotiated by its sub-protocol
+pub fn data_channel_is_timestamped(protocol: Option<&str>) -> bool {
+    protocol.is_some_and(|protocol| split_data_channel_protocol(protocol).1)
+}
+
+/// Size of the header prepended to the application data sent over data
+/// channels: the PTS, DTS and duration of the buffer in nanoseconds, all
+/// bits set when unset, followed by the buffer flags, all big endian
+const DATA_CHANNEL_HEADER_SIZE: usize = 28;
+
+/// Builds the data channel message carrying @buffer, its timestamps are
+/// converted to running times with @segment so that the receiver can
+/// restore them
+pub fn data_channel_message(
+    buffer: &gst::BufferRef,
+    segment: Option<&gst::FormattedSegment<gst::ClockTime>>,
+) -> Result<glib::Bytes, Error> {
+    let map = buffer.map_readable().context("Mapping buffer readable")?;
+
+    let running_time = |ts: Option<gst::ClockTime>| match segment {
+        Some(segment) => segment.to_running_time(ts),
+        None => ts,
+    };
+
+    let mut data = Vec::with_capacity(DATA_CHANNEL_HEADER_SIZE + map.len());
+    for ts in [
+        running_time(buffer.pts()),
+        running_time(buffer.dts()),
+        buffer.duration(),
+    ] {
+        data.extend_from_slice(&ts.map_or(u64::MAX, gst::ClockTime::nseconds).to_be_bytes());
+    }
+    data.extend_from_slice(&buffer.flags().bits().to_be_bytes());
+    data.extend_from_slice(&map);
+
+    Ok(glib::Bytes::from_owned(data))
+}
+
+/// Restores the buffer carried by a data channel message built with
+/// [`data_channel_message`], None if @data is too short to hold its header
+pub fn buffer_from_data_channel_message(data: &[u8]) -> Option<gst::Buffer> {
+    if data.len() < DATA_CHANNEL_HEADER_SIZE {
+        return None;
+    }
+
+    let (header, payload) = data.split_at(DATA_CHANNEL_HEADER_SIZE);
+    let timestamp = |offset: usize| {
+        let ns = u64::from_be_bytes(header[offset..offset + 8].try_into().unwrap());
+        (ns != u64::MAX).then(|| gst::ClockTime::from_nseconds(ns))
+    };
+    let flags = u32::from_be_bytes(header[24..28].try_into().unwrap());
+
+    let mut buffer = gst::Buffer::from_slice(payload.to_vec());
+    {
+        let buffer = buffer.get_mut().unwrap();
+        buffer.set_pts(timestamp(0));
+        buffer.set_dts(timestamp(8));
+        buffer.set_duration(timestamp(16));
+        buffer
+            .set_flags(gst::BufferFlags::from_bits_truncate(flags) - gst::BufferFlags::TAG_MEMORY);
+    }
+
+    Some(buffer)
+}
+
+/// Maps the running times of the sender of a data channel to running times
+/// of the receiver: the first message with a PTS is mapped to the running
+/// time at which it was received, the following ones keep their distance
+/// to it
+#[derive(Debug, Default)]
+pub struct DataChannelTimeMapping(Option<(gst::ClockTime, gst::ClockTime)>);
+
+impl DataChannelTimeMapping {
+    /// Maps the timestamps of @buffer, received at the running time @now
+    pub fn map(&mut self, buffer: &mut gst::BufferRef, now: Option<gst::ClockTime>) {
+        if self.0.is_none() {
+            self.0 = buffer.pts().zip(now);
+        }
+
+        let Some((remote, local)) = self.0 else {
+            buffer.set_pts(now);
+            buffer.set_dts(gst::ClockTime::NONE);
+            return;
+        };
+
+        let map = |ts: Option<gst::ClockTime>| {
+            ts.and_then(|ts| match ts.checked_sub(remote) {
+                Some(diff) => local.checked_add(diff),
+                None => local.checked_sub(remote - ts),
+            })
+        };
+        buffer.set_pts(map(buffer.pts()));
+        buffer.set_dts(map(buffer.dts()));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_h264_profile_compatibility() {
+        assert_eq!(
+            h264_profile_from_profile_level_id("42e01f"),
+            Some("constrained-baseline")
+        );
+        assert_eq!(
+            h264_profile_from_profile_level_id("42001f"),
+            Some("baseline")
+        );
+        assert_eq!(h264_profile_from_profile_level_id("4d001f"), Some("main"));
+        assert_eq!(
+            h264_profile_from_profile_level_id("640c1f"),
+            Some("constrained-high")
+        );
+        assert_eq!(h264_profile_from_profile_level_id("640032"), Some("high"));
+        assert_eq!(h264_profile_from_profile_level_id("64003"), None);
+        assert_eq!(h264_profile_from_profile_level_id("zz0032"), None);
+
+        assert!(is_h264_profile_compatible("constrained-baseline", "42001f"));
+        assert!(is_h264_profile_compatible("constrained-baseline", "640032"));
+        assert!(is_h264_profile_compatible("main", "640032"));
+        assert!(!is_h264_profile_compatible("high", "640c1f"));
+        assert!(!is_h264_profile_compatible("high", "42e01f"));
+        assert!(!is_h264_profile_compatible("main", "42e01f"));
+    }
+
+    #[test]
+    fn test_h264_profile_level_id_from_caps() {
+        gst::init().unwrap();
+
+        let caps = |profile: &str, level: &str| {
+            gst::Structure::builder("video/x-h264")
+                .field("profile", profile)
+                .field("level", level)
+                .build()
+        };
+
+        assert_eq!(
+            h264_profile_level_id_from_caps(&caps("constrained-baseline", "3.1")).as_deref(),
+            Some("42e01f")
+        );
+        assert_eq!(
+            h264_profile_level_id_from_caps(&caps("high", "5")).as_deref(),
+            Some("640032")
+        );
+        assert_eq!(
+            h264_profile_level_id_from_caps(&caps("main", "1b")).as_deref(),
+            Some("4d100b")
+        );
+        assert_eq!(
+            h264_profile_level_id_from_caps(&caps("high", "1b")).as_deref(),
+            Some("640009")
+        );
+        assert_eq!(h264_profile_level_id_from_caps(&caps("high", "x")), None);
+        assert_eq!(
+            h264_profile_level_id_from_caps(&gst::Structure::new_empty("video/x-h264")),
+            None
+        );
+
+        // The SPS in the codec data wins over the caps fields
+        let mut s = caps("high", "5");
+        s.set(
+            "codec_data",
+            gst::Buffer::from_slice([1u8, 0x4d, 0x40, 0x28, 0xff, 0xe1]),
+        );
+        assert_eq!(
+            h264_profile_level_id_from_caps(&s).as_deref(),
+            Some("4d4028")
+        );
+    }
+
     #[test]
     fn test_deserialize_array() -> Result<(), String> {
         let arr = serde_json::from_str::<serde_json::Value>("[1, -1, 1.0]").unwrap();
@@ -1427,4 +1756,118 @@ mod tests {
         .into_iter()
         .try_for_each(|(input, expected)| test_find_smallest_available_ext_id_case(input, expected))
     }
+
+    #[test]
+    fn test_data_channel_caps() {
+        gst::init().unwrap();
+
+        let caps = gst::Caps::builder("meta/x-klv")
+            .field("parsed", true)
+            .build();
+        let config = data_channel_config(false, false, Some(&caps), false);
+        assert!(!config.get::<bool>("ordered").unwrap());
+        assert_eq!(config.get::<i32>("max-retransmits").unwrap(), 0);
+        assert_eq!(data_channel_caps(config.get::<&str>("protocol").ok()), caps);
+        assert!(!data_channel_is_timestamped(
+            config.get::<&str>("protocol").ok()
+        ));
+
+        let config = data_channel_config(true, true, Some(&caps), true);
+        assert_eq!(data_channel_caps(config.get::<&str>("protocol").ok()), caps);
+        assert!(data_channel_is_timestamped(
+            config.get::<&str>("protocol").ok()
+        ));
+
+        let config = data_channel_config(true, true, None, false);
+        assert!(config.get::<bool>("ordered").unwrap());
+        assert!(!config.has_field("max-retransmits"));
+        assert!(!config.has_field("protocol"));
+        assert!(!data_channel_is_timestamped(None));
+
+        let config = data_channel_config(true, true, None, true);
+        assert_eq!(
+            data_channel_caps(config.get::<&str>("protocol").ok()),
+            gst::Caps::new_empty_simple("application/octet-stream")
+        );
+        assert!(data_channel_is_timestamped(
+            config.get::<&str>("protocol").ok()
+        ));
+
+        let octet_stream = gst::Caps::new_empty_simple("application/octet-stream");
+        assert_eq!(data_channel_caps(None), octet_stream);
+        assert_eq!(data_channel_caps(Some("telemetry")), octet_stream);
+        assert_eq!(
+            data_channel_caps(Some("application/x-telemetry, rate=[ 1, 10 ]")),
+            octet_stream
+        );
+    }
+
+    #[test]
+    fn test_data_channel_message() {
+        gst::init().unwrap();
+
+        let mut buffer = gst::Buffer::from_slice([1u8, 2, 3, 4]);
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(gst::ClockTime::from_seconds(11));
+            buffer.set_duration(gst::ClockTime::from_mseconds(40));
+            buffer.set_flags(gst::BufferFlags::DELTA_UNIT | gst::BufferFlags::MARKER);
+        }
+
+        let mut segment = gst::FormattedSegment::<gst::ClockTime>::new();
+        segment.set_start(gst::ClockTime::from_seconds(10));
+
+        let message = data_channel_message(&buffer, Some(&segment)).unwrap();
+        let restored = buffer_from_data_channel_message(&message).unwrap();
+        assert_eq!(restored.pts(), Some(gst::ClockTime::from_seconds(1)));
+        assert_eq!(restored.dts(), None);
+        assert_eq!(restored.duration(), Some(gst::ClockTime::from_mseconds(40)));
+        assert_eq!(
+            restored.flags(),
+            gst::BufferFlags::DELTA_UNIT | gst::BufferFlags::MARKER
+        );
+        assert_eq!(restored.map_readable().unwrap().as_slice(), &[1, 2, 3, 4]);
+
+        assert!(buffer_from_data_channel_message(&[0; 4]).is_none());
+    }
+
+    #[test]
+    fn test_data_channel_time_mapping() {
+        gst::init().unwrap();
+
+        let timestamped = |pts: Option<u64>, dts: Option<u64>| {
+            let mut buffer = gst::Buffer::new();
+            {
+                let buffer = buffer.get_mut().unwrap();
+                buffer.set_pts(pts.map(gst::ClockTime::from_mseconds));
+                buffer.set_dts(dts.map(gst::ClockTime::from_mseconds));
+            }
+            buffer
+        };
+        let ms = gst::ClockTime::from_mseconds;
+
+        let mut mapping = DataChannelTimeMapping::default();
+
+        // Messages are timestamped on reception until one has a PTS
+        let mut buffer = timestamped(None, Some(10));
+        mapping.map(buffer.get_mut().unwrap(), Some(ms(500)));
+        assert_eq!(buffer.pts(), Some(ms(500)));
+        assert_eq!(buffer.dts(), None);
+
+        let mut buffer = timestamped(Some(10_000), Some(9_900));
+        mapping.map(buffer.get_mut().unwrap(), Some(ms(600)));
+        assert_eq!(buffer.pts(), Some(ms(600)));
+        assert_eq!(buffer.dts(), Some(ms(500)));
+
+        // Later messages keep their distance to the first one, whenever
+        // they are received
+        let mut buffer = timestamped(Some(10_100), None);
+        mapping.map(buffer.get_mut().unwrap(), Some(ms(900)));
+        assert_eq!(buffer.pts(), Some(ms(700)));
+        assert_eq!(buffer.dts(), None);
+
+        let mut buffer = timestamped(Some(9_000), None);
+        mapping.map(buffer.get_mut().unwrap(), Some(ms(1_000)));
+        assert_eq!(buffer.pts(), None);
+    }
 }
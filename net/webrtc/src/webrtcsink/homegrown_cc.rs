@@ -412,8 +412,11 @@ impl CongestionController {
 
         let fec_percentage = (fec_ratio * 50f64) as u32;
 
-        for encoder in encoders.iter_mut() {
-            if encoder.set_bitrate(element, target_bitrate).is_ok() {
+        let mut bitrates = vec![target_bitrate; encoders.len()];
+        VideoEncoder::distribute_simulcast_bitrates(encoders, &mut bitrates);
+
+        for (encoder, bitrate) in encoders.iter_mut().zip(bitrates) {
+            if encoder.set_bitrate(element, bitrate).is_ok() {
                 encoder
                     .transceiver
                     .set_property("fec-percentage", fec_percentage);
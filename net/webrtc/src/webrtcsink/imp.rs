@@ -27,11 +27,15 @@ use std::sync::LazyLock;
 
 use std::ops::DerefMut;
 use std::ops::Mul;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{mpsc, Arc, Condvar, Mutex};
 
 use super::homegrown_cc::CongestionController;
+use super::simulcast::{self, SimulcastLayer, RTP_STREAM_ID_URI};
+use super::svc;
 use super::{
-    WebRTCSinkCongestionControl, WebRTCSinkError, WebRTCSinkMitigationMode, WebRTCSinkPad,
+    WebRTCSinkCongestionControl, WebRTCSinkDataPad, WebRTCSinkError, WebRTCSinkMitigationMode,
+    WebRTCSinkPad, WebRTCSinkScalabilityMode,
 };
 use crate::signaller::{prelude::*, Signallable, Signaller, WebRTCSignallerRole};
 use crate::{utils, RUNTIME};
@@ -55,6 +59,9 @@ const RTP_TWCC_URI: &str =
     "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01";
 
 const TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// How long layers of a scalable stream are dropped before giving up if
+/// no packets were dropped, as the stream then doesn't signal its layers
+const SVC_LAYER_SIGNALLING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 const DEFAULT_STUN_SERVER: Option<&str> = Some("stun://stun.l.google.com:19302");
 const DEFAULT_MIN_BITRATE: u32 = 1000;
@@ -130,6 +137,8 @@ struct Settings {
     #[cfg(feature = "web_server")]
     web_server_host_addr: url::Url,
     forward_metas: HashSet<String>,
+    simulcast_layers: Vec<SimulcastLayer>,
+    scalability_mode: WebRTCSinkScalabilityMode,
 }
 
 #[derive(Debug, Clone)]
@@ -277,24 +286,86 @@ struct WebRTCPad {
     stream_name: Option<String>,
     /// The payload selected in the answer, None at first
     payload: Option<i32>,
+    /// The simulcast layers offered for this pad along with their ssrc,
+    /// empty when not sending simulcast
+    simulcast_layers: Vec<(SimulcastLayer, u32)>,
+    /// The scalability mode offered for this pad, None when not sending
+    /// a scalable stream
+    scalability_mode: WebRTCSinkScalabilityMode,
 }
 
 /// Wrapper around GStreamer encoder element, keeps track of factory
 /// name in order to provide a unified set / get bitrate API, also
 /// tracks a raw capsfilter used to resize / decimate the input video
-/// stream according to the bitrate, thresholds hardcoded for now
+/// stream according to the bitrate, thresholds hardcoded for now.
+///
+/// Streams that are already encoded have no encoder, the element is
+/// then the appsrc feeding the payloader and congestion is mitigated
+/// by only sending keyframes.
 pub struct VideoEncoder {
     factory_name: String,
     codec_name: String,
     element: gst::Element,
-    filter: gst::Element,
+    filter: Option<gst::Element>,
     halved_framerate: gst::Fraction,
-    video_info: gst_video::VideoInfo,
+    video_info: Option<gst_video::VideoInfo>,
     session_id: String,
     mitigation_mode: WebRTCSinkMitigationMode,
     pub transceiver: gst_webrtc::WebRTCRTPTransceiver,
     /// name of the sink pad feeding this encoder
     stream_name: String,
+    /// Set when this encoder produces one layer of a simulcast stream
+    simulcast_layer: Option<SimulcastLayerEncoder>,
+    /// Set when the input stream is already encoded
+    passthrough: Option<PassthroughEncoder>,
+    /// Set when this encoder produces a scalable stream
+    svc: Option<SvcEncoder>,
+}
+
+/// State of an encoder producing a simulcast layer, which resolution
+/// is fixed and which congestion control can pause altogether
+struct SimulcastLayerEncoder {
+    rid: String,
+    scale_resolution_down_by: f64,
+    min_bitrate: u32,
+    max_bitrate: u32,
+    /// Drops the input of the encoder while the layer is disabled
+    valve: gst::Element,
+    enabled: bool,
+}
+
+/// State of an already encoded stream, which bitrate is measured and
+/// which delta units can be dropped to reduce it
+struct PassthroughEncoder {
+    shared: Arc<PassthroughShared>,
+    /// When the bitrate was last measured, and the byte count back then
+    last_measurement: Option<(std::time::Instant, u64)>,
+    /// The measured bitrate, or the start bitrate until measured
+    bitrate: i32,
+}
+
+/// State of an encoder producing a scalable stream, which enhancement
+/// layers are dropped before the encoder bitrate is lowered
+struct SvcEncoder {
+    mode: WebRTCSinkScalabilityMode,
+    /// Drops the packets of the layers above the selected ones
+    selector: gst::Element,
+    /// The selected temporal layer
+    temporal_layer: u8,
+    /// Whether the payloaded stream signals its layers, which is known
+    /// once the selector dropped packets or failed to for too long
+    layers_signalled: Option<bool>,
+    /// Since when layers are dropped while the selector didn't drop any
+    /// packet yet
+    dropping_since: Option<std::time::Instant>,
+}
+
+/// State shared with the probe counting and dropping buffers
+#[derive(Default)]
+struct PassthroughShared {
+    bytes: AtomicU64,
+    drop_delta_units: AtomicBool,
+    waiting_for_keyframe: AtomicBool,
 }
 
 struct SessionInner {
@@ -328,6 +399,8 @@ struct SessionInner {
 
     navigation_handler: Option<NavigationEventHandler>,
     control_events_handler: Option<ControlRequestHandler>,
+    /// The data channels of our data pads, by pad name
+    data_channels: HashMap<String, DataChannelSender>,
 }
 
 #[derive(Clone)]
@@ -365,7 +438,10 @@ struct State {
     codec_discovery_done: bool,
     audio_serial: u32,
     video_serial: u32,
+    data_serial: u32,
     streams: HashMap<String, InputStream>,
+    /// Pads accepting application data, sent over data channels
+    data_streams: HashMap<String, DataStream>,
     discoveries: HashMap<String, Vec<DiscoveryInfo>>,
     signaller_signals: Option<SignallerSignals>,
     finalizing_sessions: Arc<(Mutex<HashSet<String>>, Condvar)>,
@@ -502,6 +578,19 @@ struct NavigationEventHandler((Option<glib::SignalHandlerId>, WebRTCDataChannel)
 #[derive(Debug)]
 struct ControlRequestHandler((Option<glib::SignalHandlerId>, WebRTCDataChannel));
 
+// Structure to send the buffers of a data pad over a WebRTCDataChannel
+#[derive(Debug)]
+struct DataChannelSender(WebRTCDataChannel);
+
+/// Wrapper around our data sink pads
+#[derive(Debug)]
+struct DataStream {
+    sink_pad: WebRTCSinkDataPad,
+    /// Advertised as the protocol of the data channels, consumers are only
+    /// accepted once known
+    caps: Option<gst::Caps>,
+}
+
 /// Our instance structure
 #[derive(Default)]
 pub struct BaseWebRTCSink {
@@ -551,6 +640,8 @@ impl Default for Settings {
             #[cfg(feature = "web_server")]
             web_server_host_addr: url::Url::parse(DEFAULT_WEB_SERVER_HOST_ADDR).unwrap(),
             forward_metas: HashSet::new(),
+            simulcast_layers: Vec::new(),
+            scalability_mode: WebRTCSinkScalabilityMode::None,
         }
     }
 }
@@ -566,7 +657,9 @@ impl Default for State {
             codec_discovery_done: false,
             audio_serial: 0,
             video_serial: 0,
+            data_serial: 0,
             streams: HashMap::new(),
+            data_streams: HashMap::new(),
             discoveries: HashMap::new(),
             signaller_signals: Default::default(),
             finalizing_sessions: Arc::new((Mutex::new(HashSet::new()), Condvar::new())),
@@ -1020,16 +1113,230 @@ impl VideoEncoder {
             factory_name: encoding_elements.encoder.as_ref()?.factory()?.name().into(),
             codec_name: codec_name.to_string(),
             element: encoding_elements.encoder.as_ref()?.clone(),
-            filter: encoding_elements.raw_filter.as_ref()?.clone(),
+            filter: Some(encoding_elements.raw_filter.as_ref()?.clone()),
             halved_framerate,
-            video_info,
+            video_info: Some(video_info),
             session_id: session_id.to_string(),
             mitigation_mode: WebRTCSinkMitigationMode::NONE,
             transceiver,
             stream_name,
+            simulcast_layer: None,
+            passthrough: None,
+            svc: None,
         })
     }
 
+    /// Wraps the appsrc feeding an already encoded stream to the payloader,
+    /// `start_bitrate` being assumed until the bitrate can be measured
+    fn new_passthrough(
+        appsrc: &gst::Element,
+        start_bitrate: i32,
+        session_id: &str,
+        codec_name: &str,
+        transceiver: gst_webrtc::WebRTCRTPTransceiver,
+        stream_name: String,
+    ) -> Self {
+        let shared = Arc::new(PassthroughShared::default());
+
+        appsrc.static_pad("src").unwrap().add_probe(
+            gst::PadProbeType::BUFFER,
+            glib::clone!(
+                #[strong]
+                shared,
+                move |_pad, info| {
+                    let Some(buffer) = info.buffer() else {
+                        return gst::PadProbeReturn::Ok;
+                    };
+
+                    // Dropped buffers are counted too, the measured bitrate is
+                    // the one of the complete stream
+                    shared
+                        .bytes
+                        .fetch_add(buffer.size() as u64, Ordering::Relaxed);
+
+                    if !buffer.flags().contains(gst::BufferFlags::DELTA_UNIT) {
+                        shared.waiting_for_keyframe.store(false, Ordering::SeqCst);
+                        gst::PadProbeReturn::Ok
+                    } else if shared.drop_delta_units.load(Ordering::SeqCst)
+                        || shared.waiting_for_keyframe.load(Ordering::SeqCst)
+                    {
+                        gst::PadProbeReturn::Drop
+                    } else {
+                        gst::PadProbeReturn::Ok
+                    }
+                }
+            ),
+        );
+
+        Self {
+            factory_name: "appsrc".to_string(),
+            codec_name: codec_name.to_string(),
+            element: appsrc.clone(),
+            filter: None,
+            halved_framerate: gst::Fraction::new(0, 1),
+            video_info: None,
+            session_id: session_id.to_string(),
+            mitigation_mode: WebRTCSinkMitigationMode::NONE,
+            transceiver,
+            stream_name,
+            simulcast_layer: None,
+            passthrough: Some(PassthroughEncoder {
+                shared,
+                last_measurement: None,
+                bitrate: start_bitrate,
+            }),
+            svc: None,
+        }
+    }
+
+    /// Makes this encoder produce the given simulcast layer, `max_bitrate`
+    /// being used when the layer doesn't define its own
+    fn set_simulcast_layer(
+        &mut self,
+        layer: &SimulcastLayer,
+        valve: gst::Element,
+        max_bitrate: u32,
+    ) {
+        let max_bitrate = layer.max_bitrate.unwrap_or(max_bitrate);
+
+        self.simulcast_layer = Some(SimulcastLayerEncoder {
+            rid: layer.rid.clone(),
+            scale_resolution_down_by: layer.scale_resolution_down_by,
+            min_bitrate: layer
+                .min_bitrate
+                .unwrap_or(max_bitrate / 2)
+                .min(max_bitrate),
+            max_bitrate,
+            valve,
+            enabled: true,
+        });
+    }
+
+    /// Makes this encoder produce a scalable stream, which layers are
+    /// selected by `selector` once congestion lowers the bitrate below
+    /// what the encoder needs to produce them
+    fn set_svc(&mut self, mode: WebRTCSinkScalabilityMode, selector: gst::Element) {
+        let temporal_layers = mode.temporal_layers().unwrap_or(1);
+
+        self.svc = Some(SvcEncoder {
+            mode,
+            selector,
+            temporal_layer: temporal_layers - 1,
+            layers_signalled: None,
+            dropping_since: None,
+        });
+    }
+
+    /// Selects the layers of a scalable stream to send for `bitrate`, and
+    /// returns the bitrate of the encoder for the selected layers to use
+    /// `bitrate`. None if congestion has to be mitigated as for other
+    /// encoders because the stream doesn't signal its layers.
+    fn select_svc_layers(&mut self, element: &super::BaseWebRTCSink, bitrate: i32) -> Option<i32> {
+        let svc = self.svc.as_mut()?;
+
+        if svc.layers_signalled == Some(false) {
+            return None;
+        }
+
+        if svc.layers_signalled.is_none() {
+            let dropped = svc
+                .selector
+                .property::<gst::Structure>("stats")
+                .get::<u64>("packets-dropped")
+                .unwrap_or(0);
+
+            if dropped > 0 {
+                svc.layers_signalled = Some(true);
+            } else if svc
+                .dropping_since
+                .is_some_and(|since| since.elapsed() > SVC_LAYER_SIGNALLING_TIMEOUT)
+            {
+                gst::warning!(
+                    CAT,
+                    obj = element,
+                    "session {}: no packets of {} were dropped, the stream doesn't signal its layers",
+                    self.session_id,
+                    self.stream_name
+                );
+
+                svc.selector.set_property("target-temporal-layer", 7u32);
+                svc.layers_signalled = Some(false);
+                return None;
+            }
+        }
+
+        let (temporal_layer, share) = svc::select_layers_for_bitrate(svc.mode, bitrate);
+
+        if svc.temporal_layer != temporal_layer {
+            gst::debug!(
+                CAT,
+                obj = element,
+                "session {}: sending layers up to T{} of {}",
+                self.session_id,
+                temporal_layer,
+                self.stream_name
+            );
+
+            svc.selector
+                .set_property("target-temporal-layer", temporal_layer as u32);
+            svc.temporal_layer = temporal_layer;
+        }
+
+        if share < 1.0 {
+            svc.dropping_since
+                .get_or_insert_with(std::time::Instant::now);
+            self.mitigation_mode = WebRTCSinkMitigationMode::LAYERS_DROPPED;
+        } else {
+            svc.dropping_since = None;
+            self.mitigation_mode = WebRTCSinkMitigationMode::NONE;
+        }
+
+        Some((bitrate as f64 / share) as i32)
+    }
+
+    /// Redistributes the bitrates computed for each encoder over the
+    /// simulcast layers of each stream: the budget of all the layers of a
+    /// stream is pooled and handed out to the layers with the lowest
+    /// resolution first. Layers that should be disabled get a bitrate of 0.
+    pub(crate) fn distribute_simulcast_bitrates(encoders: &[VideoEncoder], bitrates: &mut [i32]) {
+        let mut streams: HashMap<&str, Vec<usize>> = HashMap::new();
+
+        for (idx, encoder) in encoders.iter().enumerate() {
+            if encoder.simulcast_layer.is_some() {
+                streams
+                    .entry(encoder.stream_name.as_str())
+                    .or_default()
+                    .push(idx);
+            }
+        }
+
+        for mut indices in streams.into_values() {
+            let layer = |idx: usize| encoders[idx].simulcast_layer.as_ref().unwrap();
+
+            indices.sort_by(|a, b| {
+                layer(*b)
+                    .scale_resolution_down_by
+                    .total_cmp(&layer(*a).scale_resolution_down_by)
+            });
+
+            let budget = indices
+                .iter()
+                .map(|idx| bitrates[*idx].max(0) as u32)
+                .sum::<u32>();
+            let limits = indices
+                .iter()
+                .map(|idx| (layer(*idx).min_bitrate, layer(*idx).max_bitrate))
+                .collect::<Vec<_>>();
+
+            for (idx, bitrate) in indices
+                .iter()
+                .zip(simulcast::allocate_bitrates(budget, &limits))
+            {
+                bitrates[*idx] = bitrate.map_or(0, |bitrate| bitrate as i32);
+            }
+        }
+    }
+
     fn is_bitrate_supported(factory_name: &str) -> bool {
         matches!(
             factory_name,
@@ -1054,6 +1361,10 @@ impl VideoEncoder {
     }
 
     fn bitrate(&self) -> Result<i32, WebRTCSinkError> {
+        if let Some(passthrough) = self.passthrough.as_ref() {
+            return Ok(passthrough.bitrate);
+        }
+
         let bitrate = match self.factory_name.as_str() {
             "vp8enc" | "vp9enc" => self.element.property::<i32>("target-bitrate"),
             "av1enc" => (self.element.property::<u32>("target-bitrate") * 1000) as i32,
@@ -1069,11 +1380,11 @@ impl VideoEncoder {
         Ok(bitrate)
     }
 
-    fn scale_height_round_2(&self, height: i32) -> i32 {
+    fn scale_height_round_2(video_info: &gst_video::VideoInfo, height: i32) -> i32 {
         let ratio = gst_video::calculate_display_ratio(
-            self.video_info.width(),
-            self.video_info.height(),
-            self.video_info.par(),
+            video_info.width(),
+            video_info.height(),
+            video_info.par(),
             gst::Fraction::new(1, 1),
         )
         .unwrap();
@@ -1086,8 +1397,43 @@ impl VideoEncoder {
     pub(crate) fn set_bitrate(
         &mut self,
         element: &super::BaseWebRTCSink,
-        bitrate: i32,
+        mut bitrate: i32,
     ) -> Result<(), WebRTCSinkError> {
+        if let Some(layer) = self.simulcast_layer.as_mut() {
+            if bitrate <= 0 {
+                if layer.enabled {
+                    gst::debug!(
+                        CAT,
+                        obj = element,
+                        "session {}: disabling simulcast layer {} of {}",
+                        self.session_id,
+                        layer.rid,
+                        self.stream_name
+                    );
+
+                    layer.valve.set_property("drop", true);
+                    layer.enabled = false;
+                }
+
+                return Ok(());
+            }
+
+            bitrate = bitrate.min(layer.max_bitrate as i32);
+        }
+
+        if self.passthrough.is_some() {
+            self.set_passthrough_bitrate(element, bitrate);
+            return Ok(());
+        }
+
+        // The enhancement layers of scalable streams are dropped first, the
+        // encoder bitrate being raised so that the remaining layers use the
+        // available bitrate
+        let svc_bitrate = self.select_svc_layers(element, bitrate);
+        if let Some(svc_bitrate) = svc_bitrate {
+            bitrate = svc_bitrate;
+        }
+
         match self.factory_name.as_str() {
             "vp8enc" | "vp9enc" => self.element.set_property("target-bitrate", bitrate),
             "av1enc" => self
@@ -1105,14 +1451,56 @@ impl VideoEncoder {
             _ => return Err(WebRTCSinkError::BitrateNotSupported),
         }
 
-        let current_caps = self.filter.property::<gst::Caps>("caps");
+        if let Some(mode) = self.svc.as_ref().map(|svc| svc.mode) {
+            svc::set_layer_bitrates(&self.element, mode, bitrate);
+        }
+
+        if svc_bitrate.is_some() {
+            return Ok(());
+        }
+
+        // The resolution of simulcast layers is fixed, congestion is instead
+        // mitigated by disabling layers
+        if let Some(layer) = self.simulcast_layer.as_mut() {
+            if !layer.enabled {
+                gst::debug!(
+                    CAT,
+                    obj = element,
+                    "session {}: enabling simulcast layer {} of {} with bitrate {}",
+                    self.session_id,
+                    layer.rid,
+                    self.stream_name,
+                    bitrate
+                );
+
+                layer.valve.set_property("drop", false);
+                layer.enabled = true;
+
+                // The consumer needs a keyframe to resume decoding the layer
+                let srcpad = self.element.static_pad("src").unwrap();
+                srcpad.send_event(
+                    gst_video::UpstreamForceKeyUnitEvent::builder()
+                        .all_headers(true)
+                        .build(),
+                );
+            }
+
+            return Ok(());
+        }
+
+        let (Some(filter), Some(video_info)) = (self.filter.as_ref(), self.video_info.as_ref())
+        else {
+            return Ok(());
+        };
+
+        let current_caps = filter.property::<gst::Caps>("caps");
         let mut s = current_caps.structure(0).unwrap().to_owned();
 
         // Hardcoded thresholds, may be tuned further in the future, and
         // adapted according to the codec in use
         if bitrate < 500000 {
-            let height = 360i32.min(self.video_info.height() as i32);
-            let width = self.scale_height_round_2(height);
+            let height = 360i32.min(video_info.height() as i32);
+            let width = Self::scale_height_round_2(video_info, height);
 
             s.set("height", height);
             s.set("width", width);
@@ -1124,8 +1512,8 @@ impl VideoEncoder {
             self.mitigation_mode =
                 WebRTCSinkMitigationMode::DOWNSAMPLED | WebRTCSinkMitigationMode::DOWNSCALED;
         } else if bitrate < 1000000 {
-            let height = 360i32.min(self.video_info.height() as i32);
-            let width = self.scale_height_round_2(height);
+            let height = 360i32.min(video_info.height() as i32);
+            let width = Self::scale_height_round_2(video_info, height);
 
             s.set("height", height);
             s.set("width", width);
@@ -1133,8 +1521,8 @@ impl VideoEncoder {
 
             self.mitigation_mode = WebRTCSinkMitigationMode::DOWNSCALED;
         } else if bitrate < 2000000 {
-            let height = 720i32.min(self.video_info.height() as i32);
-            let width = self.scale_height_round_2(height);
+            let height = 720i32.min(video_info.height() as i32);
+            let width = Self::scale_height_round_2(video_info, height);
 
             s.set("height", height);
             s.set("width", width);
@@ -1164,12 +1552,82 @@ impl VideoEncoder {
                 self.element
             );
 
-            self.filter.set_property("caps", caps);
+            filter.set_property("caps", caps);
         }
 
         Ok(())
     }
 
+    /// An already encoded stream can't be rate controlled, when it doesn't
+    /// fit in `bitrate` only its keyframes are sent
+    fn set_passthrough_bitrate(&mut self, element: &super::BaseWebRTCSink, bitrate: i32) {
+        let passthrough = self.passthrough.as_mut().unwrap();
+
+        let bytes = passthrough.shared.bytes.load(Ordering::Relaxed);
+        let now = std::time::Instant::now();
+        match passthrough.last_measurement {
+            Some((last, last_bytes)) => {
+                let elapsed = now.duration_since(last);
+                if elapsed >= std::time::Duration::from_secs(1) {
+                    passthrough.bitrate =
+                        ((bytes - last_bytes) as f64 * 8. / elapsed.as_secs_f64()) as i32;
+                    passthrough.last_measurement = Some((now, bytes));
+                }
+            }
+            None => passthrough.last_measurement = Some((now, bytes)),
+        }
+
+        let drop_delta_units = passthrough.shared.drop_delta_units.load(Ordering::SeqCst);
+
+        if bitrate < passthrough.bitrate {
+            if !drop_delta_units {
+                gst::debug!(
+                    CAT,
+                    obj = element,
+                    "session {}: {} bitrate {} above target {}, only sending keyframes",
+                    self.session_id,
+                    self.stream_name,
+                    passthrough.bitrate,
+                    bitrate
+                );
+
+                passthrough
+                    .shared
+                    .drop_delta_units
+                    .store(true, Ordering::SeqCst);
+                self.mitigation_mode = WebRTCSinkMitigationMode::KEYFRAMES_ONLY;
+            }
+        } else if drop_delta_units {
+            gst::debug!(
+                CAT,
+                obj = element,
+                "session {}: {} bitrate {} fits in target {}, sending all frames",
+                self.session_id,
+                self.stream_name,
+                passthrough.bitrate,
+                bitrate
+            );
+
+            // Delta units are only sent again from the next keyframe on
+            passthrough
+                .shared
+                .waiting_for_keyframe
+                .store(true, Ordering::SeqCst);
+            passthrough
+                .shared
+                .drop_delta_units
+                .store(false, Ordering::SeqCst);
+            self.mitigation_mode = WebRTCSinkMitigationMode::NONE;
+
+            let srcpad = self.element.static_pad("src").unwrap();
+            srcpad.send_event(
+                gst_video::UpstreamForceKeyUnitEvent::builder()
+                    .all_headers(true)
+                    .build(),
+            );
+        }
+    }
+
     fn gather_stats(&self) -> gst::Structure {
         gst::Structure::builder("application/x-webrtcsink-video-encoder-stats")
             .field("bitrate", self.bitrate().unwrap_or(0i32))
@@ -1179,6 +1637,20 @@ impl VideoEncoder {
                 "fec-percentage",
                 self.transceiver.property::<u32>("fec-percentage"),
             )
+            .field_if_some(
+                "rid",
+                self.simulcast_layer
+                    .as_ref()
+                    .map(|layer| layer.rid.as_str()),
+            )
+            .field_if_some(
+                "enabled",
+                self.simulcast_layer.as_ref().map(|layer| layer.enabled),
+            )
+            .field_if_some(
+                "temporal-layer",
+                self.svc.as_ref().map(|svc| svc.temporal_layer as u32),
+            )
             .build()
     }
 }
@@ -1191,8 +1663,11 @@ impl State {
             format!("removing-session-{}-", session.id),
         );
 
-        for ssrc in session.webrtc_pads.keys() {
-            session.links.remove(ssrc);
+        for webrtc_pad in session.webrtc_pads.values() {
+            session.links.remove(&webrtc_pad.ssrc);
+            for (_, ssrc) in &webrtc_pad.simulcast_layers {
+                session.links.remove(ssrc);
+            }
         }
 
         let stats_collection_handle = session.stats_collection_handle.take();
@@ -1239,6 +1714,10 @@ impl State {
         self.signaller_state == SignallerState::Stopped
             && element.current_state() >= gst::State::Paused
             && self.codec_discovery_done
+            && self
+                .data_streams
+                .values()
+                .all(|stream| stream.caps.is_some())
     }
 
     fn queue_discovery(&mut self, stream_name: &str, discovery_info: DiscoveryInfo) {
@@ -1293,6 +1772,7 @@ impl SessionInner {
             stats_collection_handle: None,
             navigation_handler: None,
             control_events_handler: None,
+            data_channels: HashMap::new(),
         }
     }
 
@@ -1369,9 +1849,167 @@ impl SessionInner {
             }
         };
 
-        let appsrc = make_element("appsrc", Some(stream_name))?;
+        let sdp = self.sdp.as_ref().unwrap();
+        let sdp_media = sdp.media(webrtc_pad.media_idx).unwrap();
+
+        let mut global_caps = gst::Caps::new_empty_simple("application/x-unknown");
+
+        sdp.attributes_to_caps(global_caps.get_mut().unwrap())
+            .unwrap();
+        sdp_media
+            .attributes_to_caps(global_caps.get_mut().unwrap())
+            .unwrap();
+
+        let mut caps = sdp_media
+            .caps_from_media(payload)
+            .unwrap()
+            .intersect(&global_caps);
+
+        // An already encoded stream can't be made to match the level picked
+        // by the consumer, signal the actual one
+        if codec.name == "H264" && !has_raw_caps(&webrtc_pad.in_caps) {
+            let profile_level_id = webrtc_pad
+                .in_caps
+                .structure(0)
+                .and_then(utils::h264_profile_level_id_from_caps);
+            if let Some(s) = caps.make_mut().structure_mut(0) {
+                match profile_level_id {
+                    Some(profile_level_id) => s.set("profile-level-id", profile_level_id),
+                    None => s.remove_field("profile-level-id"),
+                }
+            }
+        }
+
+        // Only send the layers the consumer accepted, identified by the
+        // extension ID it picked for the rid
+        let mut layers = Vec::new();
+        if !webrtc_pad.simulcast_layers.is_empty() {
+            let accepted_rids = simulcast::accepted_rids(sdp_media);
+
+            if let Some(rid_ext_id) = simulcast::rid_extension_id(sdp_media) {
+                layers.extend(
+                    webrtc_pad
+                        .simulcast_layers
+                        .iter()
+                        .filter(|(layer, _)| accepted_rids.contains(&layer.rid))
+                        .map(|(layer, ssrc)| (layer, *ssrc, rid_ext_id)),
+                );
+            }
+
+            if layers.is_empty() {
+                gst::warning!(
+                    CAT,
+                    obj = element,
+                    "Consumer {} did not accept simulcast for media {}, sending a single stream",
+                    self.peer_id,
+                    webrtc_pad.media_idx
+                );
+            }
+        }
+
+        // At this point, the peer has provided its answer, and we want to
+        // let the payloader / encoder perform negotiation according to that.
+        //
+        // This means we need to unset our codec preferences, as they would now
+        // conflict with what the peer actually requested (see webrtcbin's
+        // caps query implementation), and instead install a capsfilter downstream
+        // of the payloader with caps constructed from the relevant SDP media.
+        let transceiver = webrtc_pad
+            .pad
+            .property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver");
+        transceiver.set_property("codec-preferences", None::<gst::Caps>);
+
+        if layers.is_empty() {
+            // The layers of a scalable stream are described with the
+            // dependency descriptor, if the consumer accepted it
+            let svc = (webrtc_pad.scalability_mode != WebRTCSinkScalabilityMode::None
+                && svc::is_supported_codec(&codec.name))
+            .then(|| {
+                (
+                    webrtc_pad.scalability_mode,
+                    utils::find_extension_id(sdp_media, svc::RTP_DEPENDENCY_DESCRIPTOR_URI),
+                )
+            });
+
+            return self.connect_payload_chain(
+                element,
+                producer,
+                webrtc_pad,
+                &codec,
+                &caps,
+                None,
+                svc,
+                &webrtc_pad.pad,
+            );
+        }
+
+        // All the layers are sent through the same webrtcbin pad
+        let funnel = make_element("rtpfunnel", None)?;
+        self.pipeline.add(&funnel).unwrap();
+        funnel
+            .static_pad("src")
+            .unwrap()
+            .link(&webrtc_pad.pad)
+            .with_context(|| format!("Connecting input stream for {}", self.peer_id))?;
+
+        for layer in layers {
+            let sinkpad = funnel
+                .request_pad_simple("sink_%u")
+                .ok_or_else(|| anyhow!("Failed to request pad from rtpfunnel"))?;
+
+            self.connect_payload_chain(
+                element,
+                producer,
+                webrtc_pad,
+                &codec,
+                &caps,
+                Some(layer),
+                None,
+                &sinkpad,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds the chain consuming an InputStream and feeding `sinkpad` with
+    /// RTP packets, `layer` holding the simulcast layer to produce along
+    /// with its ssrc and the ID of the rid extension, if any, and `svc` the
+    /// scalability mode to encode with along with the ID of the dependency
+    /// descriptor extension, if any
+    #[allow(clippy::too_many_arguments)]
+    fn connect_payload_chain(
+        &mut self,
+        element: &super::BaseWebRTCSink,
+        producer: &StreamProducer,
+        webrtc_pad: &WebRTCPad,
+        codec: &Codec,
+        caps: &gst::Caps,
+        layer: Option<(&SimulcastLayer, u32, u32)>,
+        svc: Option<(WebRTCSinkScalabilityMode, Option<u32>)>,
+        sinkpad: &gst::Pad,
+    ) -> Result<(), Error> {
+        let stream_name = webrtc_pad.stream_name.as_ref().unwrap();
+        let ssrc = layer.map_or(webrtc_pad.ssrc, |(_, ssrc, _)| ssrc);
+
+        let appsrc = match layer {
+            Some((layer, ..)) => {
+                make_element("appsrc", Some(&format!("{stream_name}_{}", layer.rid)))?
+            }
+            None => make_element("appsrc", Some(stream_name))?,
+        };
         self.pipeline.add(&appsrc).unwrap();
 
+        let valve = match layer {
+            Some(_) => {
+                let valve = make_element("valve", None)?;
+                self.pipeline.add(&valve).unwrap();
+                appsrc.link(&valve)?;
+                Some(valve)
+            }
+            None => None,
+        };
+
         let pay_filter = make_element("capsfilter", None)?;
         self.pipeline.add(&pay_filter).unwrap();
 
@@ -1383,82 +2021,148 @@ impl SessionInner {
         } = PayloadChainBuilder::new(
             &webrtc_pad.in_caps,
             &output_caps,
-            &codec,
+            codec,
             element.emit_by_name::<Option<gst::Element>>(
                 "request-encoded-filter",
                 &[&Some(&self.peer_id), &stream_name, &codec.caps],
             ),
         )
-        .build(&self.pipeline, &appsrc)?;
+        .build(&self.pipeline, valve.as_ref().unwrap_or(&appsrc))?;
 
-        if let Some(ref enc) = encoding_chain.encoder {
-            element.emit_by_name::<bool>("encoder-setup", &[&self.peer_id, &stream_name, &enc]);
+        if let (Some((layer, ..)), Some(raw_filter)) = (layer, encoding_chain.raw_filter.as_ref()) {
+            let video_info = gst_video::VideoInfo::from_caps(&webrtc_pad.in_caps)?;
+            let (width, height) = layer.scaled_size(video_info.width(), video_info.height());
+
+            let mut raw_caps = raw_filter.property::<gst::Caps>("caps");
+            {
+                let raw_caps = raw_caps.make_mut();
+                raw_caps.set("width", width);
+                raw_caps.set("height", height);
+            }
+            raw_filter.set_property("caps", raw_caps);
         }
 
-        let sdp = self.sdp.as_ref().unwrap();
-        let sdp_media = sdp.media(webrtc_pad.media_idx).unwrap();
+        // The layers are only described to the consumer when the encoder
+        // actually produces them
+        let svc = svc.filter(|(mode, _)| {
+            let configured = encoding_chain
+                .encoder
+                .as_ref()
+                .is_some_and(|enc| svc::configure_encoder(enc, *mode));
 
-        let mut global_caps = gst::Caps::new_empty_simple("application/x-unknown");
+            if !configured {
+                gst::warning!(
+                    CAT,
+                    obj = element,
+                    "Encoder can't be configured for scalability mode {}, sending a single layer",
+                    mode.as_str()
+                );
+            }
 
-        sdp.attributes_to_caps(global_caps.get_mut().unwrap())
-            .unwrap();
-        sdp_media
-            .attributes_to_caps(global_caps.get_mut().unwrap())
-            .unwrap();
+            configured
+        });
 
-        let caps = sdp_media
-            .caps_from_media(payload)
-            .unwrap()
-            .intersect(&global_caps);
+        if let Some(ref enc) = encoding_chain.encoder {
+            element.emit_by_name::<bool>("encoder-setup", &[&self.peer_id, &stream_name, &enc]);
+        }
+
+        // Drops the enhancement layers of scalable streams under congestion
+        let layer_selector = match svc {
+            Some(_) => {
+                let selector = make_element("rtplayerselector", None)?;
+                self.pipeline.add(&selector).unwrap();
+                Some(selector)
+            }
+            None => None,
+        };
 
         element.imp().configure_payloader(
             &self.peer_id,
             stream_name,
             &payloader,
-            &codec,
-            Some(webrtc_pad.ssrc),
-            Some(&caps),
+            codec,
+            Some(ssrc),
+            Some(caps),
             ExtensionConfigurationType::Skip,
         )?;
 
-        // At this point, the peer has provided its answer, and we want to
-        // let the payloader / encoder perform negotiation according to that.
-        //
-        // This means we need to unset our codec preferences, as they would now
-        // conflict with what the peer actually requested (see webrtcbin's
-        // caps query implementation), and instead install a capsfilter downstream
-        // of the payloader with caps constructed from the relevant SDP media.
-        let transceiver = webrtc_pad
-            .pad
-            .property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver");
-        transceiver.set_property("codec-preferences", None::<gst::Caps>);
+        if let Some((layer, _, rid_ext_id)) = layer {
+            let Some(rid_extension) =
+                gst_rtp::RTPHeaderExtension::create_from_uri(RTP_STREAM_ID_URI)
+            else {
+                anyhow::bail!("Failed to add RTP stream ID extension, make sure 'gst-plugins-good:rtpmanager' is installed");
+            };
+
+            rid_extension.set_id(rid_ext_id);
+            rid_extension.set_property("rid", &layer.rid);
+            payloader.emit_by_name::<()>("add-extension", &[&rid_extension]);
+        }
+
+        if let Some((mode, Some(dd_ext_id))) = svc {
+            match gst_rtp::RTPHeaderExtension::create_from_uri(svc::RTP_DEPENDENCY_DESCRIPTOR_URI)
+            {
+                Some(dd_extension) => {
+                    dd_extension.set_id(dd_ext_id);
+                    dd_extension.set_property("scalability-mode", mode.as_str());
+                    payloader.emit_by_name::<()>("add-extension", &[&dd_extension]);
+                }
+                None => gst::warning!(
+                    CAT,
+                    obj = element,
+                    "Failed to add dependency descriptor extension, make sure 'gst-plugins-rs:rsrtp' is installed"
+                ),
+            }
+        }
 
         let s = caps.structure(0).unwrap();
         let mut filtered_s = gst::Structure::new_empty("application/x-rtp");
 
         filtered_s.extend(s.iter().filter_map(|(key, value)| {
-            if key.starts_with("a-") {
+            if key.starts_with("a-") || key.starts_with("rid-") {
                 None
             } else {
                 Some((key, value.to_owned()))
             }
         }));
-        filtered_s.set("ssrc", webrtc_pad.ssrc);
+        filtered_s.set("ssrc", ssrc);
 
         let caps = gst::Caps::builder_full().structure(filtered_s).build();
 
         pay_filter.set_property("caps", caps);
 
         if codec.is_video() {
-            let video_info = gst_video::VideoInfo::from_caps(&webrtc_pad.in_caps)?;
-            if let Some(mut enc) = VideoEncoder::new(
-                &encoding_chain,
-                video_info,
-                &self.id,
-                codec.caps.structure(0).unwrap().name(),
-                transceiver,
-                stream_name.clone(),
-            ) {
+            let transceiver = webrtc_pad
+                .pad
+                .property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver");
+            let codec_name = codec.caps.structure(0).unwrap().name();
+            let enc = if has_raw_caps(&webrtc_pad.in_caps) {
+                VideoEncoder::new(
+                    &encoding_chain,
+                    gst_video::VideoInfo::from_caps(&webrtc_pad.in_caps)?,
+                    &self.id,
+                    codec_name,
+                    transceiver,
+                    stream_name.clone(),
+                )
+            } else {
+                Some(VideoEncoder::new_passthrough(
+                    &appsrc,
+                    self.cc_info.start_bitrate as i32,
+                    &self.id,
+                    codec_name,
+                    transceiver,
+                    stream_name.clone(),
+                ))
+            };
+
+            if let Some(mut enc) = enc {
+                if let (Some((layer, ..)), Some(valve)) = (layer, valve) {
+                    enc.set_simulcast_layer(layer, valve, self.cc_info.max_bitrate);
+                }
+                if let (Some((mode, _)), Some(selector)) = (svc, layer_selector.as_ref()) {
+                    enc.set_svc(mode, selector.clone());
+                }
+
                 match self.cc_info.heuristic {
                     WebRTCSinkCongestionControl::Disabled => {
                         // If congestion control is disabled, we simply use the highest
@@ -1501,17 +2205,25 @@ impl SessionInner {
             .sync_children_states()
             .with_context(|| format!("Connecting input stream for {}", self.peer_id))?;
 
-        encoding_chain.pay_filter.link(&pay_filter)?;
+        match layer_selector {
+            Some(selector) => {
+                encoding_chain
+                    .pay_filter
+                    .link_pads(None, &selector, Some("sink_%u"))?;
+                selector.link(&pay_filter)?;
+            }
+            None => encoding_chain.pay_filter.link(&pay_filter)?,
+        }
 
         let srcpad = pay_filter.static_pad("src").unwrap();
 
         srcpad
-            .link(&webrtc_pad.pad)
+            .link(sinkpad)
             .with_context(|| format!("Connecting input stream for {}", self.peer_id))?;
 
         match producer.add_consumer(&appsrc) {
             Ok(link) => {
-                self.links.insert(webrtc_pad.ssrc, link);
+                self.links.insert(ssrc, link);
                 Ok(())
             }
             Err(err) => Err(anyhow!("Could not link producer: {:?}", err)),
@@ -1679,6 +2391,40 @@ impl Drop for ControlRequestHandler {
     }
 }
 
+impl DataChannelSender {
+    fn new(element: &super::BaseWebRTCSink, webrtcbin: &gst::Element, stream: &DataStream) -> Self {
+        let pad = &stream.sink_pad;
+        let label = pad.property::<String>("label");
+        let config = utils::data_channel_config(
+            pad.property("ordered"),
+            pad.property("reliable"),
+            stream.caps.as_ref(),
+            pad.property("timestamped"),
+        );
+
+        gst::info!(
+            CAT,
+            obj = element,
+            "Creating data channel {label} for {}: {config}",
+            pad.name()
+        );
+
+        Self(webrtcbin.emit_by_name::<WebRTCDataChannel>("create-data-channel", &[&label, &config]))
+    }
+
+    fn is_open(&self) -> bool {
+        self.0
+            .property::<gst_webrtc::WebRTCDataChannelState>("ready-state")
+            == gst_webrtc::WebRTCDataChannelState::Open
+    }
+}
+
+impl Drop for DataChannelSender {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
 /// How to configure RTP extensions for payloaders, if at all
 enum ExtensionConfigurationType {
     /// Skip configuration, do not add any extensions
@@ -1907,7 +2653,11 @@ impl BaseWebRTCSink {
         loop {
             let ret = fastrand::u32(..);
 
-            if !webrtc_pads.contains_key(&ret) {
+            if !webrtc_pads.contains_key(&ret)
+                && !webrtc_pads
+                    .values()
+                    .any(|pad| pad.simulcast_layers.iter().any(|(_, ssrc)| *ssrc == ret))
+            {
                 gst::trace!(CAT, imp = self, "Selected ssrc {}", ret);
                 return ret;
             }
@@ -1955,6 +2705,8 @@ impl BaseWebRTCSink {
                 ssrc,
                 stream_name: None,
                 payload: None,
+                simulcast_layers: Vec::new(),
+                scalability_mode: WebRTCSinkScalabilityMode::None,
             },
         );
     }
@@ -2002,11 +2754,93 @@ impl BaseWebRTCSink {
             None => stream.out_caps.as_ref().unwrap().to_owned(),
         };
 
+        // Simulcast layers are only offered for video we encode ourselves
+        let mut simulcast_layers: Vec<(SimulcastLayer, u32)> = Vec::new();
+        if media.is_none() && stream.is_video && has_raw_caps(stream.in_caps.as_ref().unwrap()) {
+            for layer in &settings.simulcast_layers {
+                let layer_ssrc = loop {
+                    let layer_ssrc = self.generate_ssrc(webrtc_pads);
+                    if layer_ssrc != ssrc
+                        && !simulcast_layers
+                            .iter()
+                            .any(|(_, other)| *other == layer_ssrc)
+                    {
+                        break layer_ssrc;
+                    }
+                };
+
+                simulcast_layers.push((layer.clone(), layer_ssrc));
+            }
+        }
+
+        // Scalable streams are likewise only offered for video we encode
+        // ourselves, and when not sending simulcast
+        let scalability_mode = if media.is_none()
+            && stream.is_video
+            && has_raw_caps(stream.in_caps.as_ref().unwrap())
+            && simulcast_layers.is_empty()
+        {
+            settings.scalability_mode
+        } else {
+            WebRTCSinkScalabilityMode::None
+        };
+
         if payloader_caps.is_empty() {
             self.request_inactive_webrtcbin_pad(webrtcbin, webrtc_pads, stream.is_video);
         } else {
             let payloader_caps_mut = payloader_caps.make_mut();
-            payloader_caps_mut.set("ssrc", ssrc);
+
+            if simulcast_layers.is_empty() {
+                payloader_caps_mut.set("ssrc", ssrc);
+            } else {
+                // Layers are identified by their rid rather than by their
+                // ssrc, the rid being carried in an RTP header extension
+                let rid_ext_id = utils::find_smallest_available_ext_id(
+                    payloader_caps_mut
+                        .structure(0)
+                        .unwrap()
+                        .iter()
+                        .filter_map(|(key, _)| key.strip_prefix("extmap-")?.parse::<u32>().ok()),
+                );
+                payloader_caps_mut.set(format!("extmap-{rid_ext_id}"), RTP_STREAM_ID_URI);
+
+                for (layer, _) in &simulcast_layers {
+                    payloader_caps_mut.set(format!("rid-{}", layer.rid), "send");
+                }
+                payloader_caps_mut.set(
+                    "a-simulcast",
+                    format!(
+                        "send {}",
+                        simulcast_layers
+                            .iter()
+                            .map(|(layer, _)| layer.rid.as_str())
+                            .join(";")
+                    ),
+                );
+            }
+
+            // The layers of scalable codecs are described by the
+            // dependency descriptor
+            if scalability_mode != WebRTCSinkScalabilityMode::None {
+                let dd_ext_id = utils::find_smallest_available_ext_id(
+                    payloader_caps_mut.iter().flat_map(|s| {
+                        s.iter()
+                            .filter_map(|(key, _)| key.strip_prefix("extmap-")?.parse::<u32>().ok())
+                            .collect::<Vec<_>>()
+                    }),
+                );
+
+                for s in payloader_caps_mut.iter_mut() {
+                    if s.get::<&str>("encoding-name")
+                        .is_ok_and(svc::is_supported_codec)
+                    {
+                        s.set(
+                            format!("extmap-{dd_ext_id}"),
+                            svc::RTP_DEPENDENCY_DESCRIPTOR_URI,
+                        );
+                    }
+                }
+            }
 
             if self.settings.lock().unwrap().do_clock_signalling {
                 // Add RFC7273 attributes when using an NTP or PTP clock
@@ -2115,6 +2949,8 @@ impl BaseWebRTCSink {
                     ssrc,
                     stream_name: Some(stream.sink_pad.name().to_string()),
                     payload: None,
+                    simulcast_layers,
+                    scalability_mode,
                 },
             );
         }
@@ -2568,6 +3404,10 @@ impl BaseWebRTCSink {
         // required elsewhere to make this work in all cases (eg when we create
         // the offer).
 
+        // Streams that are already encoded can only be payloaded, and are
+        // only suitable if the remote can decode them as is
+        let is_raw_input = has_raw_caps(in_caps);
+
         let mut ordered_codecs_and_caps: Vec<(gst::Caps, Vec<(Codec, gst::Caps)>)> = user_caps
             .iter()
             .map(|s| ([s.to_owned()].into_iter().collect(), Vec::new()))
@@ -2592,8 +3432,46 @@ impl BaseWebRTCSink {
             let encoding_name = s.get::<String>("encoding-name").unwrap();
 
             if let Some(mut codec) = Codecs::find(&encoding_name) {
-                if !codec.can_encode() {
-                    continue;
+                if is_raw_input {
+                    if !codec.can_encode() {
+                        continue;
+                    }
+                } else {
+                    if !codec.can_payload(in_caps) {
+                        continue;
+                    }
+
+                    if codec.name == "H264" {
+                        let stream_profile = in_caps
+                            .structure(0)
+                            .and_then(|s| s.get::<&str>("profile").ok());
+                        let profile_level_id = s.get::<&str>("profile-level-id").ok();
+
+                        if let (Some(stream_profile), Some(profile_level_id)) =
+                            (stream_profile, profile_level_id)
+                        {
+                            if !utils::is_h264_profile_compatible(stream_profile, profile_level_id)
+                            {
+                                gst::debug!(
+                                    CAT,
+                                    imp = self,
+                                    "Skipping payload {payload}, {stream_profile} stream \
+                                     not compatible with profile-level-id {profile_level_id}"
+                                );
+                                continue;
+                            }
+                        }
+
+                        // The stream can't be re-encoded to match the offered
+                        // level, signal the actual one
+                        match in_caps
+                            .structure(0)
+                            .and_then(utils::h264_profile_level_id_from_caps)
+                        {
+                            Some(profile_level_id) => s.set("profile-level-id", profile_level_id),
+                            None => s.remove_field("profile-level-id"),
+                        }
+                    }
                 }
 
                 codec.set_pt(payload);
@@ -3414,6 +4292,19 @@ impl BaseWebRTCSink {
                     }
                 }
 
+                {
+                    let state = this.state.lock().unwrap();
+                    if let Some(session) = state.sessions.get(&session_id) {
+                        let mut session = session.0.lock().unwrap();
+                        for (name, stream) in &state.data_streams {
+                            session.data_channels.insert(
+                                name.clone(),
+                                DataChannelSender::new(&element, &webrtcbin, stream),
+                            );
+                        }
+                    }
+                }
+
                 // This is intentionally emitted with the pipeline in the Ready state,
                 // so that application code can create data channels at the correct
                 // moment.
@@ -3559,9 +4450,11 @@ impl BaseWebRTCSink {
                 &[&session.peer_id, &(encoders_bitrate as i32), &s],
             );
 
-            for encoder in session.encoders.iter_mut() {
-                let defined_encoder_bitrate =
-                    match updated_bitrates.get::<i32>(&encoder.stream_name) {
+            let mut encoder_bitrates = session
+                .encoders
+                .iter()
+                .map(
+                    |encoder| match updated_bitrates.get::<i32>(&encoder.stream_name) {
                         Ok(bitrate) => {
                             gst::log!(
                                 CAT,
@@ -3581,8 +4474,15 @@ impl BaseWebRTCSink {
                             );
                             encoder_bitrate
                         }
-                    };
+                    },
+                )
+                .collect::<Vec<_>>();
 
+            VideoEncoder::distribute_simulcast_bitrates(&session.encoders, &mut encoder_bitrates);
+
+            for (encoder, defined_encoder_bitrate) in
+                session.encoders.iter_mut().zip(encoder_bitrates)
+            {
                 if encoder
                     .set_bitrate(&self.obj(), defined_encoder_bitrate)
                     .is_ok()
@@ -4023,7 +4923,7 @@ impl BaseWebRTCSink {
 
             codecs
                 .iter()
-                .filter(|codec| codec.is_video() == is_video)
+                .filter(|codec| codec.is_video() == is_video && codec.can_encode())
                 .map(|codec| {
                     self.run_discovery_pipeline(
                         &name,
@@ -4208,7 +5108,7 @@ impl BaseWebRTCSink {
                             if encoder.stream_name == stream_name {
                                 encoder.halved_framerate =
                                     video_info.fps().mul(gst::Fraction::new(1, 2));
-                                encoder.video_info = video_info.clone();
+                                encoder.video_info = Some(video_info.clone());
                             }
                         }
                     }
@@ -4349,6 +5249,116 @@ impl BaseWebRTCSink {
 
         gst::ProxyPad::chain_default(pad, Some(&*self.obj()), buffer)
     }
+
+    fn data_chain(
+        &self,
+        pad: &gst::Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let channels = {
+            let state = self.state.lock().unwrap();
+            state
+                .sessions
+                .values()
+                .filter_map(|session| {
+                    let session = session.0.lock().unwrap();
+                    session
+                        .data_channels
+                        .get(pad.name().as_str())
+                        .filter(|sender| sender.is_open())
+                        .map(|sender| sender.0.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        if channels.is_empty() {
+            gst::trace!(CAT, obj = pad, "No open data channel, dropping {buffer:?}");
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        // The receiver was told whether to expect the header when the data
+        // channels were created, the property can't change since then
+        let data = if pad.property::<bool>("timestamped") {
+            let segment = pad.sticky_event::<gst::event::Segment>(0);
+            let segment = segment
+                .as_ref()
+                .and_then(|event| event.segment().downcast_ref::<gst::ClockTime>());
+            utils::data_channel_message(&buffer, segment).map_err(|err| {
+                gst::error!(CAT, obj = pad, "Failed to serialize {buffer:?}: {err:?}");
+                gst::FlowError::Error
+            })?
+        } else {
+            let map = buffer.map_readable().map_err(|_| {
+                gst::error!(CAT, obj = pad, "Failed to map {buffer:?} readable");
+                gst::FlowError::Error
+            })?;
+            glib::Bytes::from(map.as_slice())
+        };
+
+        for channel in channels {
+            channel.send_data(Some(&data));
+        }
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn data_sink_event(
+        &self,
+        pad: &gst::Pad,
+        element: &super::BaseWebRTCSink,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        match event.view() {
+            EventView::Caps(e) => {
+                gst::info!(CAT, obj = pad, "Received caps event {:?}", e);
+
+                let signaller = self.settings.lock().unwrap().signaller.clone();
+                let mut state = self.state.lock().unwrap();
+
+                if let Some(stream) = state.data_streams.get_mut(pad.name().as_str()) {
+                    if stream.caps.is_some() && !state.sessions.is_empty() {
+                        gst::warning!(
+                            CAT,
+                            obj = pad,
+                            "Caps changed to {}, the protocol of the open data channels is not updated",
+                            e.caps()
+                        );
+                    }
+                    stream.caps = Some(e.caps_owned());
+                }
+
+                // Consumers are waiting for the caps of the data pads
+                if state.should_start_signaller(element) {
+                    state.signaller_state = SignallerState::Started;
+                    drop(state);
+                    signaller.start();
+                }
+            }
+            EventView::Eos(_) => {
+                gst::info!(CAT, obj = pad, "Received EOS, closing data channels");
+
+                // Closing the data channels lets the consumers know no more
+                // data will be sent, done after releasing the state lock
+                let _senders = self.remove_data_channels(pad.name().as_str());
+            }
+            _ => (),
+        }
+
+        gst::Pad::event_default(pad, Some(element), event)
+    }
+
+    /// Removes the data channels of the data pad @name from all sessions,
+    /// they are closed once the returned senders are dropped
+    fn remove_data_channels(&self, name: &str) -> Vec<DataChannelSender> {
+        let state = self.state.lock().unwrap();
+        state
+            .sessions
+            .values()
+            .filter_map(|session| session.0.lock().unwrap().data_channels.remove(name))
+            .collect()
+    }
 }
 
 #[glib::object_subclass]
@@ -4626,6 +5636,56 @@ impl ObjectImpl for BaseWebRTCSink {
                     .default_value(DEFAULT_FORWARD_METAS)
                     .mutable_playing()
                     .build(),
+                /**
+                 * GstBaseWebRTCSink:simulcast-layers:
+                 *
+                 * Encoding ladder to offer for raw video streams, each layer
+                 * being described by a structure such as:
+                 *
+                 * ``` text
+                 * layer, rid=h, scale-resolution-down-by=1.0, max-bitrate=(uint)2500000, min-bitrate=(uint)1000000
+                 * ```
+                 *
+                 * When set, one encoder is created per layer accepted by the
+                 * consumer, and the layers are advertised with `a=rid` and
+                 * `a=simulcast` in the offer. Congestion control then serves
+                 * the layers with the lowest resolution first, and pauses a
+                 * layer when the bitrate left for it falls below its
+                 * `min-bitrate` (half of `max-bitrate` by default).
+                 *
+                 * Since: plugins-rs-0.14.0
+                 */
+                gst::ParamSpecArray::builder("simulcast-layers")
+                    .nick("Simulcast layers")
+                    .blurb("Simulcast layers to offer for video streams, as structures with rid, scale-resolution-down-by, max-bitrate and min-bitrate fields")
+                    .element_spec(&glib::ParamSpecBoxed::builder::<gst::Structure>("layer")
+                        .nick("Simulcast layer")
+                        .blurb("One layer of the simulcast encoding ladder")
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstBaseWebRTCSink:scalability-mode:
+                 *
+                 * Temporal layers to encode raw video streams with when VP9
+                 * is negotiated, ignored when
+                 * #GstBaseWebRTCSink:simulcast-layers is set.
+                 *
+                 * The layers are described to the consumer with the
+                 * dependency descriptor RTP header extension, and congestion
+                 * control first drops enhancement layers before lowering the
+                 * bitrate of the encoder. Only vp9enc can be configured for
+                 * temporal layers, a single layer is sent with other
+                 * encoders.
+                 *
+                 * Since: plugins-rs-0.14.0
+                 */
+                glib::ParamSpecEnum::builder_with_default("scalability-mode", WebRTCSinkScalabilityMode::None)
+                    .nick("Scalability mode")
+                    .blurb("Temporal layers of scalable VP9 video streams")
+                    .mutable_ready()
+                    .build(),
             ]
         });
 
@@ -4767,6 +5827,22 @@ impl ObjectImpl for BaseWebRTCSink {
                     .map(String::from)
                     .collect();
             }
+            "simulcast-layers" => {
+                let mut settings = self.settings.lock().unwrap();
+                let layers = value.get::<gst::Array>().expect("type checked upstream");
+                match SimulcastLayer::from_array(&layers) {
+                    Ok(layers) => settings.simulcast_layers = layers,
+                    Err(err) => {
+                        gst::error!(CAT, imp = self, "Invalid simulcast layers: {err}");
+                    }
+                }
+            }
+            "scalability-mode" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.scalability_mode = value
+                    .get::<WebRTCSinkScalabilityMode>()
+                    .expect("type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -4869,6 +5945,19 @@ impl ObjectImpl for BaseWebRTCSink {
                 let settings = self.settings.lock().unwrap();
                 settings.forward_metas.iter().join(",").to_value()
             }
+            "simulcast-layers" => {
+                let settings = self.settings.lock().unwrap();
+                settings
+                    .simulcast_layers
+                    .iter()
+                    .map(|layer| layer.to_structure().to_send_value())
+                    .collect::<gst::Array>()
+                    .to_value()
+            }
+            "scalability-mode" => {
+                let settings = self.settings.lock().unwrap();
+                settings.scalability_mode.to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -5151,7 +6240,16 @@ impl ElementImpl for BaseWebRTCSink {
             )
             .unwrap();
 
-            vec![video_pad_template, audio_pad_template]
+            let data_pad_template = gst::PadTemplate::with_gtype(
+                "data_%u",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &gst::Caps::new_any(),
+                WebRTCSinkDataPad::static_type(),
+            )
+            .unwrap();
+
+            vec![video_pad_template, audio_pad_template, data_pad_template]
         });
 
         PAD_TEMPLATES.as_ref()
@@ -5175,6 +6273,42 @@ impl ElementImpl for BaseWebRTCSink {
 
         let mut state = self.state.lock().unwrap();
 
+        if templ.name().starts_with("data_") {
+            let name = format!("data_{}", state.data_serial);
+            state.data_serial += 1;
+
+            let sink_pad = gst::PadBuilder::<WebRTCSinkDataPad>::from_template(templ)
+                .name(name.as_str())
+                .chain_function(|pad, parent, buffer| {
+                    BaseWebRTCSink::catch_panic_pad_function(
+                        parent,
+                        || Err(gst::FlowError::Error),
+                        |this| this.data_chain(pad.upcast_ref(), buffer),
+                    )
+                })
+                .event_function(|pad, parent, event| {
+                    BaseWebRTCSink::catch_panic_pad_function(
+                        parent,
+                        || false,
+                        |this| this.data_sink_event(pad.upcast_ref(), &this.obj(), event),
+                    )
+                })
+                .build();
+
+            sink_pad.set_active(true).unwrap();
+            element.add_pad(&sink_pad).unwrap();
+
+            state.data_streams.insert(
+                name,
+                DataStream {
+                    sink_pad: sink_pad.clone(),
+                    caps: None,
+                },
+            );
+
+            return Some(sink_pad.upcast());
+        }
+
         let serial;
 
         let (name, is_video) = if templ.name().starts_with("video_") {
@@ -5229,6 +6363,25 @@ impl ElementImpl for BaseWebRTCSink {
         Some(sink_pad.upcast())
     }
 
+    fn release_pad(&self, pad: &gst::Pad) {
+        if !pad.is::<WebRTCSinkDataPad>() {
+            self.parent_release_pad(pad);
+            return;
+        }
+
+        gst::debug!(CAT, obj = pad, "Releasing data pad");
+
+        self.state
+            .lock()
+            .unwrap()
+            .data_streams
+            .remove(pad.name().as_str());
+        drop(self.remove_data_channels(pad.name().as_str()));
+
+        let _ = pad.set_active(false);
+        let _ = self.obj().remove_pad(pad);
+    }
+
     fn change_state(
         &self,
         transition: gst::StateChange,
@@ -5838,6 +6991,65 @@ pub(super) mod whip {
     }
 }
 
+#[cfg(feature = "whip")]
+pub(super) mod whep {
+    use super::*;
+    use crate::whip_signaller::WhepServerSignaller;
+
+    #[derive(Default)]
+    pub struct WhepServerSink {}
+
+    impl ObjectImpl for WhepServerSink {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let element = self.obj();
+            let ws = element
+                .upcast_ref::<crate::webrtcsink::BaseWebRTCSink>()
+                .imp();
+
+            let _ = ws.set_signaller(WhepServerSignaller::default().upcast());
+
+            let settings = ws.settings.lock().unwrap();
+            element
+                .bind_property("stun-server", &settings.signaller, "stun-server")
+                .build();
+            element
+                .bind_property("turn-servers", &settings.signaller, "turn-servers")
+                .build();
+        }
+    }
+
+    impl GstObjectImpl for WhepServerSink {}
+
+    impl ElementImpl for WhepServerSink {
+        fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+            static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> =
+                LazyLock::new(|| {
+                    gst::subclass::ElementMetadata::new(
+                        "WhepServerSink",
+                        "Sink/Network/WebRTC",
+                        "WebRTC sink element using WHEP Server as the signaller",
+                        "agent <agent@local>",
+                    )
+                });
+
+            Some(&*ELEMENT_METADATA)
+        }
+    }
+
+    impl BinImpl for WhepServerSink {}
+
+    impl BaseWebRTCSinkImpl for WhepServerSink {}
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for WhepServerSink {
+        const NAME: &'static str = "GstWhepServerSink";
+        type Type = crate::webrtcsink::WhepServerSink;
+        type ParentType = crate::webrtcsink::BaseWebRTCSink;
+    }
+}
+
 #[cfg(feature = "livekit")]
 pub(super) mod livekit {
     use super::*;
@@ -6004,3 +7216,53 @@ pub(super) mod janus {
         type ParentType = crate::webrtcsink::BaseWebRTCSink;
     }
 }
+
+pub(super) mod json {
+    use super::*;
+    use crate::json_signaller::JsonSignaller;
+
+    #[derive(Default)]
+    pub struct JsonWebRTCSink {}
+
+    impl ObjectImpl for JsonWebRTCSink {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let element = self.obj();
+            let ws = element
+                .upcast_ref::<crate::webrtcsink::BaseWebRTCSink>()
+                .imp();
+
+            let _ = ws.set_signaller(JsonSignaller::new(WebRTCSignallerRole::Producer).upcast());
+        }
+    }
+
+    impl GstObjectImpl for JsonWebRTCSink {}
+
+    impl ElementImpl for JsonWebRTCSink {
+        fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+            static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> =
+                LazyLock::new(|| {
+                    gst::subclass::ElementMetadata::new(
+                        "JsonWebRTCSink",
+                        "Sink/Network/WebRTC",
+                        "WebRTC sink with a signaller configured by JSON message templates",
+                        "agent <agent@local>",
+                    )
+                });
+
+            Some(&*ELEMENT_METADATA)
+        }
+    }
+
+    impl BinImpl for JsonWebRTCSink {}
+
+    impl BaseWebRTCSinkImpl for JsonWebRTCSink {}
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for JsonWebRTCSink {
+        const NAME: &'static str = "GstJsonWebRTCSink";
+        type Type = crate::webrtcsink::JsonWebRTCSink;
+        type ParentType = crate::webrtcsink::BaseWebRTCSink;
+    }
+}
@@ -38,6 +38,8 @@ mod homegrown_cc;
 
 mod imp;
 mod pad;
+mod simulcast;
+mod svc;
 
 glib::wrapper! {
     pub struct BaseWebRTCSink(ObjectSubclass<imp::BaseWebRTCSink>) @extends gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
@@ -47,6 +49,10 @@ glib::wrapper! {
     pub struct WebRTCSinkPad(ObjectSubclass<pad::WebRTCSinkPad>) @extends gst::GhostPad, gst::ProxyPad, gst::Pad, gst::Object;
 }
 
+glib::wrapper! {
+    pub struct WebRTCSinkDataPad(ObjectSubclass<pad::WebRTCSinkDataPad>) @extends gst::Pad, gst::Object;
+}
+
 glib::wrapper! {
     pub struct WebRTCSink(ObjectSubclass<imp::WebRTCSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
 }
@@ -61,6 +67,11 @@ glib::wrapper! {
     pub struct WhipWebRTCSink(ObjectSubclass<imp::whip::WhipWebRTCSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
 }
 
+#[cfg(feature = "whip")]
+glib::wrapper! {
+    pub struct WhepServerSink(ObjectSubclass<imp::whep::WhepServerSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
+}
+
 #[cfg(feature = "livekit")]
 glib::wrapper! {
     pub struct LiveKitWebRTCSink(ObjectSubclass<imp::livekit::LiveKitWebRTCSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
@@ -71,6 +82,10 @@ glib::wrapper! {
     pub struct JanusVRWebRTCSink(ObjectSubclass<imp::janus::JanusVRWebRTCSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
 }
 
+glib::wrapper! {
+    pub struct JsonWebRTCSink(ObjectSubclass<imp::json::JsonWebRTCSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum WebRTCSinkError {
     #[error("no session with id")]
@@ -130,6 +145,23 @@ enum WebRTCSinkMitigationMode {
     DOWNSCALED = 0b00000001,
     #[flags_value(name = "Lowered framerate", nick = "downsampled")]
     DOWNSAMPLED = 0b00000010,
+    #[flags_value(name = "Only keyframes sent", nick = "keyframes-only")]
+    KEYFRAMES_ONLY = 0b00000100,
+    #[flags_value(name = "Enhancement layers dropped", nick = "layers-dropped")]
+    LAYERS_DROPPED = 0b00001000,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstWebRTCSinkScalabilityMode")]
+pub enum WebRTCSinkScalabilityMode {
+    #[default]
+    #[enum_value(name = "None: a single layer is sent", nick = "none")]
+    None,
+    #[enum_value(name = "L1T2: two temporal layers", nick = "L1T2")]
+    L1T2,
+    #[enum_value(name = "L1T3: three temporal layers", nick = "L1T3")]
+    L1T3,
 }
 
 #[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
@@ -154,8 +186,10 @@ pub enum JanusVRSignallerState {
 
 pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     WebRTCSinkPad::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    WebRTCSinkDataPad::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
     BaseWebRTCSink::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
     WebRTCSinkCongestionControl::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    WebRTCSinkScalabilityMode::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
     gst::Element::register(
         Some(plugin),
         "webrtcsink",
@@ -176,6 +210,30 @@ pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
         gst::Rank::NONE,
         WhipWebRTCSink::static_type(),
     )?;
+    /**
+     * element-whepserversink:
+     *
+     * `whepserversink` serves its input streams to [WHEP] viewers: it runs
+     * an HTTP server at the `host-addr` of its signaller, answers the offers
+     * POSTed to `/whep/endpoint`, and exposes each viewer session as a
+     * resource under `/whep/resource/` on which trickled ICE candidates can
+     * be PATCHed and that can be DELETEd to end the session.
+     *
+     * ```bash
+     * $ gst-launch-1.0 videotestsrc ! whepserversink signaller::host-addr=http://127.0.0.1:8190
+     * ```
+     *
+     * [WHEP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whep/
+     *
+     * Since: plugins-rs-0.14.0
+     */
+    #[cfg(feature = "whip")]
+    gst::Element::register(
+        Some(plugin),
+        "whepserversink",
+        gst::Rank::NONE,
+        WhepServerSink::static_type(),
+    )?;
     #[cfg(feature = "livekit")]
     gst::Element::register(
         Some(plugin),
@@ -251,5 +309,48 @@ pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     #[cfg(feature = "janus")]
     JanusVRSignallerState::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
 
+    /**
+     * element-jsonwebrtcsink:
+     *
+     * `jsonwebrtcsink` integrates with WebSocket signalling servers exchanging
+     * JSON messages, without writing a dedicated signaller: the messages it
+     * sends are described by templates and the incoming messages are
+     * identified and parsed with [JSON pointers].
+     *
+     * In the `signaller::*-template` properties, JSON strings only made of a
+     * `${name}` placeholder are replaced by the value of the variable, keeping
+     * its type, and placeholders are interpolated as text otherwise. Besides
+     * `${session-id}`, `${peer-id}`, `${sdp}`, `${sdp-type}`, `${candidate}`,
+     * `${sdp-m-line-index}` and `${sdp-mid}`, the fields of
+     * `signaller::variables` can be used, for example for credentials.
+     *
+     * The `signaller::*-match` properties select the incoming messages
+     * requesting a session, carrying SDPs or ICE candidates, ending sessions
+     * or reporting errors, either as a JSON pointer that must be present in
+     * the message or as `<pointer>=<value>`. The `signaller::*-pointer`
+     * properties locate the fields of these messages.
+     *
+     * ```bash
+     * $ gst-launch-1.0 videotestsrc ! jsonwebrtcsink \
+     *     signaller::uri=wss://signalling.example.com/ws \
+     *     signaller::variables="variables,token=secret" \
+     *     signaller::register-template='{"op":"join","token":"${token}"}' \
+     *     signaller::session-requested-match=/op=viewer \
+     *     signaller::session-id-pointer=/viewer/id \
+     *     signaller::sdp-match=/op=answer \
+     *     signaller::sdp-template='{"op":"${sdp-type}","to":"${session-id}","sdp":"${sdp}"}'
+     * ```
+     *
+     * [JSON pointers]: https://datatracker.ietf.org/doc/html/rfc6901
+     *
+     * Since: plugins-rs-0.14.0
+     */
+    gst::Element::register(
+        Some(plugin),
+        "jsonwebrtcsink",
+        gst::Rank::NONE,
+        JsonWebRTCSink::static_type(),
+    )?;
+
     Ok(())
 }
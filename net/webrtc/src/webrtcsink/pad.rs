@@ -55,3 +55,101 @@ impl GstObjectImpl for WebRTCSinkPad {}
 impl PadImpl for WebRTCSinkPad {}
 impl ProxyPadImpl for WebRTCSinkPad {}
 impl GhostPadImpl for WebRTCSinkPad {}
+
+const DEFAULT_ORDERED: bool = true;
+const DEFAULT_RELIABLE: bool = true;
+const DEFAULT_TIMESTAMPED: bool = false;
+
+pub struct WebRTCSinkDataPad {
+    settings: Mutex<DataSettings>,
+}
+
+#[derive(Debug)]
+struct DataSettings {
+    label: Option<String>,
+    ordered: bool,
+    reliable: bool,
+    timestamped: bool,
+}
+
+impl Default for WebRTCSinkDataPad {
+    fn default() -> Self {
+        Self {
+            settings: Mutex::new(DataSettings {
+                label: None,
+                ordered: DEFAULT_ORDERED,
+                reliable: DEFAULT_RELIABLE,
+                timestamped: DEFAULT_TIMESTAMPED,
+            }),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for WebRTCSinkDataPad {
+    const NAME: &'static str = "GstWebRTCSinkDataPad";
+    type Type = super::WebRTCSinkDataPad;
+    type ParentType = gst::Pad;
+}
+
+impl ObjectImpl for WebRTCSinkDataPad {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPS: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecString::builder("label")
+                    .flags(glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_READY)
+                    .blurb("Label of the data channel, defaults to the name of the pad")
+                    .build(),
+                glib::ParamSpecBoolean::builder("ordered")
+                    .default_value(DEFAULT_ORDERED)
+                    .flags(glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_READY)
+                    .blurb("Whether the data channel delivers the buffers in order")
+                    .build(),
+                glib::ParamSpecBoolean::builder("reliable")
+                    .default_value(DEFAULT_RELIABLE)
+                    .flags(glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_READY)
+                    .blurb("Whether the data channel retransmits lost buffers")
+                    .build(),
+                glib::ParamSpecBoolean::builder("timestamped")
+                    .default_value(DEFAULT_TIMESTAMPED)
+                    .flags(glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_READY)
+                    .blurb("Prepend the timestamps and flags of the buffers to the messages, only understood by webrtcsrc")
+                    .build(),
+            ]
+        });
+        PROPS.as_ref()
+    }
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "label" => {
+                settings.label = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+            }
+            "ordered" => settings.ordered = value.get::<bool>().expect("type checked upstream"),
+            "reliable" => settings.reliable = value.get::<bool>().expect("type checked upstream"),
+            "timestamped" => {
+                settings.timestamped = value.get::<bool>().expect("type checked upstream")
+            }
+            name => panic!("no writable property {name:?}"),
+        }
+    }
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "label" => settings
+                .label
+                .clone()
+                .unwrap_or_else(|| self.obj().name().to_string())
+                .to_value(),
+            "ordered" => settings.ordered.to_value(),
+            "reliable" => settings.reliable.to_value(),
+            "timestamped" => settings.timestamped.to_value(),
+            name => panic!("no readable property {name:?}"),
+        }
+    }
+}
+
+impl GstObjectImpl for WebRTCSinkDataPad {}
+impl PadImpl for WebRTCSinkDataPad {}
@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use anyhow::{anyhow, bail, Error};
+use gst::prelude::*;
+
+pub const RTP_STREAM_ID_URI: &str = "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id";
+
+/// Configuration of one layer of a simulcast video stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulcastLayer {
+    /// RTP stream identifier of the layer, as signalled with `a=rid`
+    pub rid: String,
+    /// Factor by which the input resolution is divided for this layer
+    pub scale_resolution_down_by: f64,
+    /// Maximum bitrate of the layer, the congestion control budget of the
+    /// session is used if not set
+    pub max_bitrate: Option<u32>,
+    /// Bitrate below which the layer is disabled by congestion control,
+    /// half of the maximum bitrate if not set
+    pub min_bitrate: Option<u32>,
+}
+
+impl SimulcastLayer {
+    /// Parses a layer from a structure such as
+    /// `layer, rid=h, scale-resolution-down-by=1.0, max-bitrate=2500000`
+    pub fn from_structure(s: &gst::StructureRef) -> Result<Self, Error> {
+        let rid = s
+            .get::<String>("rid")
+            .map_err(|err| anyhow!("Layer without valid rid: {err}"))?;
+
+        // RFC 8851: rid-id = 1*(alpha-numeric / "-" / "_")
+        if rid.is_empty()
+            || !rid
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!("Invalid rid {rid:?}");
+        }
+
+        let scale_resolution_down_by = s
+            .get_optional::<f64>("scale-resolution-down-by")
+            .map_err(|err| anyhow!("Invalid scale-resolution-down-by: {err}"))?
+            .unwrap_or(1.0);
+        if scale_resolution_down_by < 1.0 {
+            bail!("scale-resolution-down-by must be at least 1.0, got {scale_resolution_down_by}");
+        }
+
+        let max_bitrate = s
+            .get_optional::<u32>("max-bitrate")
+            .map_err(|err| anyhow!("Invalid max-bitrate: {err}"))?;
+        let min_bitrate = s
+            .get_optional::<u32>("min-bitrate")
+            .map_err(|err| anyhow!("Invalid min-bitrate: {err}"))?;
+
+        Ok(Self {
+            rid,
+            scale_resolution_down_by,
+            max_bitrate,
+            min_bitrate,
+        })
+    }
+
+    pub fn to_structure(&self) -> gst::Structure {
+        gst::Structure::builder("layer")
+            .field("rid", &self.rid)
+            .field("scale-resolution-down-by", self.scale_resolution_down_by)
+            .field_if_some("max-bitrate", self.max_bitrate)
+            .field_if_some("min-bitrate", self.min_bitrate)
+            .build()
+    }
+
+    /// Parses the value of the `simulcast-layers` property
+    pub fn from_array(array: &gst::ArrayRef) -> Result<Vec<Self>, Error> {
+        let mut layers: Vec<Self> = Vec::with_capacity(array.len());
+
+        for value in array.iter() {
+            let s = value
+                .get::<gst::Structure>()
+                .map_err(|err| anyhow!("Layer is not a structure: {err}"))?;
+            let layer = Self::from_structure(&s)?;
+
+            if layers.iter().any(|other| other.rid == layer.rid) {
+                bail!("Duplicated rid {}", layer.rid);
+            }
+
+            layers.push(layer);
+        }
+
+        Ok(layers)
+    }
+
+    /// Size of this layer for the given input size, rounded to even values as
+    /// required by most encoders
+    pub fn scaled_size(&self, width: u32, height: u32) -> (i32, i32) {
+        let scale = |v: u32| {
+            let scaled = (v as f64 / self.scale_resolution_down_by).round() as i32;
+            (scaled.max(2) + 1) & !1
+        };
+
+        (scale(width), scale(height))
+    }
+}
+
+/// Distributes `bitrate` over layers ordered from the lowest to the highest
+/// quality, each described by its minimum and maximum bitrate.
+///
+/// Lower layers are served first, and a layer is only enabled if the bitrate
+/// left after serving the layers below covers its minimum bitrate. The lowest
+/// layer is always enabled. Disabled layers are returned as `None`.
+pub fn allocate_bitrates(bitrate: u32, layers: &[(u32, u32)]) -> Vec<Option<u32>> {
+    let mut remaining = bitrate;
+    let mut enabled = true;
+
+    layers
+        .iter()
+        .enumerate()
+        .map(|(idx, &(min_bitrate, max_bitrate))| {
+            enabled = enabled && (idx == 0 || remaining >= min_bitrate);
+            if !enabled {
+                return None;
+            }
+
+            let layer_bitrate = remaining.min(max_bitrate);
+            remaining -= layer_bitrate;
+
+            Some(layer_bitrate)
+        })
+        .collect()
+}
+
+/// Returns the ID the remote peer mapped the RTP stream ID extension to
+pub fn rid_extension_id(media: &gst_sdp::SDPMediaRef) -> Option<u32> {
+    crate::utils::find_extension_id(media, RTP_STREAM_ID_URI)
+}
+
+/// Returns the rids the remote peer accepted to receive
+pub fn accepted_rids(media: &gst_sdp::SDPMediaRef) -> Vec<String> {
+    media
+        .attributes()
+        .filter(|attr| attr.key() == "rid")
+        .filter_map(|attr| {
+            // rid:<rid-id> <direction> [<restrictions>]
+            let mut tokens = attr.value()?.split_whitespace();
+            let rid = tokens.next()?;
+
+            (tokens.next()? == "recv").then(|| rid.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_layers() {
+        gst::init().unwrap();
+
+        let array = gst::Array::new([
+            "layer, rid=l, scale-resolution-down-by=4.0, max-bitrate=(uint)300000"
+                .parse::<gst::Structure>()
+                .unwrap()
+                .to_send_value(),
+            "layer, rid=h"
+                .parse::<gst::Structure>()
+                .unwrap()
+                .to_send_value(),
+        ]);
+
+        let layers = SimulcastLayer::from_array(&array).unwrap();
+        assert_eq!(
+            layers,
+            vec![
+                SimulcastLayer {
+                    rid: "l".into(),
+                    scale_resolution_down_by: 4.0,
+                    max_bitrate: Some(300_000),
+                    min_bitrate: None,
+                },
+                SimulcastLayer {
+                    rid: "h".into(),
+                    scale_resolution_down_by: 1.0,
+                    max_bitrate: None,
+                    min_bitrate: None,
+                },
+            ]
+        );
+        assert_eq!(layers[0].scaled_size(1280, 720), (320, 180));
+        assert_eq!(
+            layers[0].to_structure().get::<u32>("max-bitrate").unwrap(),
+            300_000
+        );
+
+        for invalid in ["layer, rid=\"a b\"", "layer, scale-resolution-down-by=2.0"] {
+            let array =
+                gst::Array::new([invalid.parse::<gst::Structure>().unwrap().to_send_value()]);
+            assert!(SimulcastLayer::from_array(&array).is_err(), "{invalid}");
+        }
+
+        let duplicated = "layer, rid=h".parse::<gst::Structure>().unwrap();
+        let array = gst::Array::new([duplicated.to_send_value(), duplicated.to_send_value()]);
+        assert!(SimulcastLayer::from_array(&array).is_err());
+    }
+
+    #[test]
+    fn allocate() {
+        let layers = [
+            (150_000, 300_000),
+            (500_000, 1_000_000),
+            (1_250_000, 2_500_000),
+        ];
+
+        assert_eq!(
+            allocate_bitrates(100_000, &layers),
+            vec![Some(100_000), None, None]
+        );
+        assert_eq!(
+            allocate_bitrates(700_000, &layers),
+            vec![Some(300_000), None, None]
+        );
+        assert_eq!(
+            allocate_bitrates(1_200_000, &layers),
+            vec![Some(300_000), Some(900_000), None]
+        );
+        assert_eq!(
+            allocate_bitrates(2_600_000, &layers),
+            vec![Some(300_000), Some(1_000_000), Some(1_300_000)]
+        );
+        assert_eq!(
+            allocate_bitrates(10_000_000, &layers),
+            vec![Some(300_000), Some(1_000_000), Some(2_500_000)]
+        );
+    }
+
+    #[test]
+    fn parse_answer() {
+        gst::init().unwrap();
+
+        let sdp = gst_sdp::SDPMessage::parse_buffer(
+            b"v=0\r\n\
+              o=- 0 0 IN IP4 127.0.0.1\r\n\
+              s=-\r\n\
+              t=0 0\r\n\
+              m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+              a=extmap:3 http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01\r\n\
+              a=extmap:4/recvonly urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id\r\n\
+              a=rid:l recv\r\n\
+              a=rid:h recv max-width=1280\r\n\
+              a=rid:m send\r\n",
+        )
+        .unwrap();
+        let media = sdp.media(0).unwrap();
+
+        assert_eq!(rid_extension_id(media), Some(4));
+        assert_eq!(accepted_rids(media), vec!["l", "h"]);
+    }
+}
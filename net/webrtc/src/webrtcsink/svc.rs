@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+
+use super::WebRTCSinkScalabilityMode;
+
+pub const RTP_DEPENDENCY_DESCRIPTOR_URI: &str =
+    "https://aomediacodec.github.io/av1-rtp-spec/#dependency-descriptor-rtp-header-extension";
+
+impl WebRTCSinkScalabilityMode {
+    /// Number of temporal layers, None when not scalable
+    pub fn temporal_layers(self) -> Option<u8> {
+        match self {
+            Self::None => None,
+            Self::L1T2 => Some(2),
+            Self::L1T3 => Some(3),
+        }
+    }
+
+    /// The scalability mode identifier as defined by WebRTC-SVC
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "L1T1",
+            Self::L1T2 => "L1T2",
+            Self::L1T3 => "L1T3",
+        }
+    }
+}
+
+/// Whether streams of the codec can be sent as scalable streams, which
+/// requires an encoder [`configure_encoder`] knows about
+pub fn is_supported_codec(codec_name: &str) -> bool {
+    codec_name == "VP9"
+}
+
+/// Share of the stream bitrate used by each temporal layer along with the
+/// layers below it
+fn temporal_shares(temporal_layers: u8) -> &'static [f64] {
+    match temporal_layers {
+        1 => &[1.0],
+        2 => &[0.6, 1.0],
+        _ => &[0.4, 0.6, 1.0],
+    }
+}
+
+/// Selects the highest temporal layer that uses at most `ratio` of the
+/// stream bitrate, the base layer if none does. Returns the selected layer
+/// along with its share of the stream bitrate.
+pub fn select_layers(mode: WebRTCSinkScalabilityMode, ratio: f64) -> (u8, f64) {
+    let Some(temporal_layers) = mode.temporal_layers() else {
+        return (0, 1.0);
+    };
+
+    let temporal_shares = temporal_shares(temporal_layers);
+
+    let mut selected = (0, temporal_shares[0]);
+    for (temporal_layer, share) in temporal_shares.iter().enumerate() {
+        if *share <= ratio && *share > selected.1 {
+            selected = (temporal_layer as u8, *share);
+        }
+    }
+
+    selected
+}
+
+/// Bitrate the encoder needs to produce all the layers of `mode` at an
+/// acceptable quality. Hardcoded like the thresholds used to lower the
+/// resolution of other streams, may be tuned further in the future.
+fn required_bitrate(mode: WebRTCSinkScalabilityMode) -> u32 {
+    match mode.temporal_layers() {
+        None => 0,
+        Some(_) => 500_000,
+    }
+}
+
+/// Selects the layers to send with the estimated available `bitrate`.
+/// All the layers are sent as long as `bitrate` is enough for the encoder
+/// to produce them, enhancement layers are only dropped once congestion
+/// lowers the estimate below that.
+pub fn select_layers_for_bitrate(mode: WebRTCSinkScalabilityMode, bitrate: i32) -> (u8, f64) {
+    let required = required_bitrate(mode).max(1);
+
+    select_layers(mode, bitrate as f64 / required as f64)
+}
+
+/// Configures `enc` to produce the layers of `mode`, returns false if the
+/// encoder can't produce them
+pub fn configure_encoder(enc: &gst::Element, mode: WebRTCSinkScalabilityMode) -> bool {
+    let Some(temporal_layers) = mode.temporal_layers() else {
+        return true;
+    };
+
+    if !enc
+        .factory()
+        .is_some_and(|factory| factory.name() == "vp9enc")
+    {
+        return false;
+    }
+
+    let (periodicity, decimator, layer_id) = match temporal_layers {
+        2 => (2i32, "<2,1>", "<0,1>"),
+        _ => (4i32, "<4,2,1>", "<0,2,1,2>"),
+    };
+
+    enc.set_property("temporal-scalability-number-layers", temporal_layers as i32);
+    enc.set_property("temporal-scalability-periodicity", periodicity);
+    enc.set_property_from_str("temporal-scalability-rate-decimator", decimator);
+    enc.set_property_from_str("temporal-scalability-layer-id", layer_id);
+
+    true
+}
+
+/// Distributes `bitrate` over the temporal layers of encoders that need
+/// a target bitrate per layer
+pub fn set_layer_bitrates(enc: &gst::Element, mode: WebRTCSinkScalabilityMode, bitrate: i32) {
+    let Some(temporal_layers) = mode.temporal_layers() else {
+        return;
+    };
+
+    if enc
+        .factory()
+        .is_some_and(|factory| factory.name() == "vp9enc")
+    {
+        let bitrates = temporal_shares(temporal_layers)
+            .iter()
+            .map(|share| ((bitrate as f64 * share) as i32).to_string())
+            .collect::<Vec<_>>();
+
+        enc.set_property_from_str(
+            "temporal-scalability-target-bitrate",
+            &format!("<{}>", bitrates.join(",")),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_layers_by_bitrate() {
+        let mode = WebRTCSinkScalabilityMode::L1T3;
+        assert_eq!(select_layers(mode, 1.0), (2, 1.0));
+        assert_eq!(select_layers(mode, 0.8), (1, 0.6));
+        assert_eq!(select_layers(mode, 0.5), (0, 0.4));
+        // The base layer is always selected
+        assert_eq!(select_layers(mode, 0.1), (0, 0.4));
+
+        assert_eq!(
+            select_layers(WebRTCSinkScalabilityMode::None, 0.1),
+            (0, 1.0)
+        );
+    }
+
+    #[test]
+    fn healthy_low_bitrate_keeps_all_layers() {
+        // A link far below the maximum bitrate but with enough bandwidth for
+        // the encoder to produce all the layers doesn't drop any
+        assert_eq!(
+            select_layers_for_bitrate(WebRTCSinkScalabilityMode::L1T3, 600_000),
+            (2, 1.0)
+        );
+
+        // Enhancement layers are dropped once the estimate falls below what
+        // the encoder needs for them
+        assert_eq!(
+            select_layers_for_bitrate(WebRTCSinkScalabilityMode::L1T3, 250_000),
+            (0, 0.4)
+        );
+    }
+}
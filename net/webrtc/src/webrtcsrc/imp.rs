@@ -16,6 +16,7 @@ use std::mem;
 use std::str::FromStr;
 use std::sync::atomic::AtomicU16;
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::sync::LazyLock;
 use std::sync::Mutex;
 use url::Url;
@@ -23,6 +24,7 @@ use url::Url;
 const DEFAULT_STUN_SERVER: Option<&str> = Some("stun://stun.l.google.com:19302");
 const DEFAULT_ENABLE_DATA_CHANNEL_NAVIGATION: bool = false;
 const DEFAULT_ENABLE_CONTROL_DATA_CHANNEL: bool = false;
+const DEFAULT_ENABLE_DATA_CHANNEL_PADS: bool = false;
 const DEFAULT_DO_RETRANSMISSION: bool = true;
 
 static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
@@ -42,6 +44,7 @@ struct Settings {
     audio_codecs: Vec<Codec>,
     enable_data_channel_navigation: bool,
     enable_control_data_channel: bool,
+    enable_data_channel_pads: bool,
     do_retransmission: bool,
 }
 
@@ -143,6 +146,21 @@ impl ObjectImpl for BaseWebRTCSrc {
                    .default_value(DEFAULT_ENABLE_CONTROL_DATA_CHANNEL)
                    .mutable_ready()
                    .build(),
+               /**
+                * GstBaseWebRTCSrc:enable-data-channel-pads:
+                *
+                * Expose the application data received over data channels on src pads,
+                * for instance the data sent from the data pads of webrtcsink. The
+                * `input` and `control` data channels are not exposed.
+                *
+                * Since: plugins-rs-0.14.0
+                */
+               glib::ParamSpecBoolean::builder("enable-data-channel-pads")
+                   .nick("Enable data channel pads")
+                   .blurb("Expose the application data received over data channels on src pads")
+                   .default_value(DEFAULT_ENABLE_DATA_CHANNEL_PADS)
+                   .mutable_ready()
+                   .build(),
                glib::ParamSpecBoolean::builder("do-retransmission")
                    .nick("Enable retransmission")
                    .blurb("Send retransmission events upstream when a packet is late")
@@ -211,6 +229,10 @@ impl ObjectImpl for BaseWebRTCSrc {
                 let mut settings = self.settings.lock().unwrap();
                 settings.enable_control_data_channel = value.get::<bool>().unwrap();
             }
+            "enable-data-channel-pads" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.enable_data_channel_pads = value.get::<bool>().unwrap();
+            }
             "do-retransmission" => {
                 let mut settings = self.settings.lock().unwrap();
                 settings.do_retransmission = value.get::<bool>().unwrap();
@@ -251,6 +273,10 @@ impl ObjectImpl for BaseWebRTCSrc {
                 let settings = self.settings.lock().unwrap();
                 settings.enable_control_data_channel.to_value()
             }
+            "enable-data-channel-pads" => {
+                let settings = self.settings.lock().unwrap();
+                settings.enable_data_channel_pads.to_value()
+            }
             "do-retransmission" => self.settings.lock().unwrap().do_retransmission.to_value(),
             name => panic!("{} getter not implemented", name),
         }
@@ -320,6 +346,7 @@ impl Default for Settings {
                 .collect(),
             enable_data_channel_navigation: DEFAULT_ENABLE_DATA_CHANNEL_NAVIGATION,
             enable_control_data_channel: DEFAULT_ENABLE_CONTROL_DATA_CHANNEL,
+            enable_data_channel_pads: DEFAULT_ENABLE_DATA_CHANNEL_PADS,
             do_retransmission: DEFAULT_DO_RETRANSMISSION,
         }
     }
@@ -483,6 +510,7 @@ impl Session {
     // The target pad for the session's bin ghostpad will be
     //      - the decoder's srcpad, if decoder is needed
     //      - otherwise, encoded filter's srcpad, if requested
+    //      - otherwise, the parser's srcpad, if downstream wants encoded caps
     //      - otherwise, webrtcbin's src pad.
     fn handle_webrtc_src_pad(
         &mut self,
@@ -527,7 +555,17 @@ impl Session {
                 unreachable!()
             };
 
-            let caps_with_raw = [caps.clone(), raw_caps.clone()]
+            // Downstream can also get the depayloaded stream, e.g. to record
+            // it without transcoding
+            let encoded_caps = caps
+                .iter()
+                .filter_map(|s| s.get::<&str>("encoding-name").ok())
+                .filter_map(Codecs::find)
+                .filter(|codec| !codec.is_raw)
+                .map(|codec| codec.caps)
+                .collect::<gst::Caps>();
+
+            let caps_with_raw = [caps.clone(), encoded_caps.clone(), raw_caps.clone()]
                 .into_iter()
                 .collect::<gst::Caps>();
 
@@ -535,6 +573,8 @@ impl Session {
             if let Some(first_struct) = downstream_caps.structure(0) {
                 if first_struct.has_name(raw_caps.structure(0).unwrap().name()) {
                     srcpad.imp().set_needs_decoding(true)
+                } else if encoded_caps.iter().any(|s| first_struct.has_name(s.name())) {
+                    srcpad.imp().set_needs_depayloading(true)
                 }
             }
         }
@@ -728,6 +768,75 @@ impl Session {
                         .link(&sinkpad)
                         .expect("webrtcbin ! decodebin3 linking failed");
                 }
+            } else if srcpad.imp().needs_depayloading() {
+                let parsebin = gst::ElementFactory::make("parsebin")
+                    .build()
+                    .expect("parsebin needs to be present!");
+
+                // Only start outputting on a keyframe, and ask the peer for a
+                // new one on packet loss so recordings don't stay broken
+                parsebin.connect_closure(
+                    "deep-element-added",
+                    false,
+                    glib::closure!(move |_parsebin: gst::Element,
+                                         _bin: gst::Bin,
+                                         e: gst::Element| {
+                        if !e
+                            .factory()
+                            .is_some_and(|f| f.has_type(gst::ElementFactoryType::DEPAYLOADER))
+                        {
+                            return;
+                        }
+
+                        for property in ["request-keyframe", "wait-for-keyframe"] {
+                            if e.has_property_with_type(property, bool::static_type()) {
+                                e.set_property(property, true);
+                            }
+                        }
+                    }),
+                );
+
+                gst::debug!(
+                    CAT,
+                    obj = element,
+                    "Depayloading for {}",
+                    srcpad.imp().stream_id()
+                );
+
+                bin.add(&parsebin).unwrap();
+
+                if let Some(encoded_filter) = encoded_filter {
+                    let filter_sink_pad = encoded_filter
+                        .static_pad("sink")
+                        .expect("encoded filter must expose a static sink pad");
+                    let filter_src_pad = encoded_filter
+                        .static_pad("src")
+                        .expect("encoded filter must expose a static src pad");
+
+                    bin.add(&encoded_filter).unwrap();
+
+                    parsebin.connect_pad_added(move |_, pad| {
+                        pad.link(&filter_sink_pad)
+                            .expect("parsebin ! encoded_filter linking failed");
+                    });
+
+                    encoded_filter.sync_state_with_parent().unwrap();
+                    ghostpad.set_target(Some(&filter_src_pad)).unwrap();
+                } else {
+                    parsebin.connect_pad_added(glib::clone!(
+                        #[weak]
+                        ghostpad,
+                        move |_parsebin, pad| {
+                            ghostpad.set_target(Some(pad)).unwrap();
+                        }
+                    ));
+                }
+
+                webrtcbin_pad
+                    .link(&parsebin.static_pad("sink").unwrap())
+                    .expect("webrtcbin ! parsebin linking failed");
+
+                parsebin.sync_state_with_parent().unwrap();
             } else {
                 gst::debug!(
                     CAT,
@@ -994,9 +1103,34 @@ impl Session {
         signaller.send_sdp(&self.id, &answer);
     }
 
-    fn on_data_channel(&mut self, data_channel: glib::Object, element: &super::BaseWebRTCSrc) {
+    /// Returns the data channel if it carries application data to expose on
+    /// a src pad, rather than navigation events and control requests
+    fn on_data_channel(
+        &mut self,
+        data_channel: glib::Object,
+        element: &super::BaseWebRTCSrc,
+    ) -> Option<WebRTCDataChannel> {
         gst::info!(CAT, obj = element, "Received data channel {data_channel:?}");
-        self.data_channel = data_channel.dynamic_cast::<WebRTCDataChannel>().ok();
+        let data_channel = data_channel.dynamic_cast::<WebRTCDataChannel>().ok();
+
+        let carries_application_data = element
+            .imp()
+            .settings
+            .lock()
+            .unwrap()
+            .enable_data_channel_pads
+            && data_channel.as_ref().is_some_and(|channel| {
+                !matches!(
+                    channel.property::<Option<String>>("label").as_deref(),
+                    Some("input") | Some("control")
+                )
+            });
+        if carries_application_data {
+            return data_channel;
+        }
+
+        self.data_channel = data_channel;
+        None
     }
 
     fn on_ice_candidate(
@@ -1291,6 +1425,148 @@ impl BaseWebRTCSrc {
         true
     }
 
+    /// Pushes the messages received over @data_channel from an appsrc in the
+    /// session's bin, exposed on a `data_%s` src pad
+    fn expose_data_channel(
+        &self,
+        bin: &gst::Bin,
+        session_id: &str,
+        data_channel: &WebRTCDataChannel,
+    ) -> Result<(), Error> {
+        let obj = self.obj();
+        let label = data_channel
+            .property::<Option<String>>("label")
+            .unwrap_or_default();
+        let protocol = data_channel.property::<Option<String>>("protocol");
+        let caps = utils::data_channel_caps(protocol.as_deref());
+        let timestamped = utils::data_channel_is_timestamped(protocol.as_deref());
+        let name = format!("data_{session_id}_{label}");
+
+        gst::info!(
+            CAT,
+            imp = self,
+            "Exposing data channel {label} on {name} with caps {caps}, timestamped: {timestamped}"
+        );
+
+        let appsrc = gst_app::AppSrc::builder()
+            .caps(&caps)
+            .format(gst::Format::Time)
+            .is_live(true)
+            .build();
+        bin.add(&appsrc)?;
+
+        let ghostpad = gst::GhostPad::builder_with_target(&appsrc.static_pad("src").unwrap())?
+            .proxy_pad_chain_function(glib::clone!(
+                #[weak(rename_to = this)]
+                self,
+                #[to_owned]
+                session_id,
+                #[upgrade_or_panic]
+                move |pad, parent, buffer| {
+                    let padret = gst::ProxyPad::chain_default(pad, parent, buffer);
+                    let state = this.state.lock().unwrap();
+                    let Some(session) = state.sessions.get(&session_id) else {
+                        gst::error!(CAT, imp = this, "session {session_id:?} does not exist");
+                        return padret;
+                    };
+                    let f = session.flow_combiner.lock().unwrap().update_flow(padret);
+                    f
+                }
+            ))
+            .build();
+        bin.add_pad(&ghostpad)?;
+
+        let srcpad = gst::GhostPad::builder_from_template(&obj.pad_template("data_%s").unwrap())
+            .name(name.as_str())
+            .build()
+            .downcast::<WebRTCSrcPad>()
+            .unwrap();
+        srcpad.imp().set_stream_id(&name);
+        srcpad.set_target(Some(&ghostpad))?;
+        obj.add_pad(&srcpad)?;
+
+        let mapping = Arc::new(Mutex::new(utils::DataChannelTimeMapping::default()));
+        data_channel.connect_closure(
+            "on-message-data",
+            false,
+            glib::closure!(
+                #[watch]
+                appsrc,
+                #[to_owned]
+                mapping,
+                move |_channel: &WebRTCDataChannel, data: Option<glib::Bytes>| {
+                    let Some(data) = data else {
+                        return;
+                    };
+
+                    if !timestamped {
+                        let mut buffer = gst::Buffer::from_slice(data);
+                        buffer
+                            .get_mut()
+                            .unwrap()
+                            .set_pts(appsrc.current_running_time());
+                        let _ = appsrc.push_buffer(buffer);
+                        return;
+                    }
+
+                    // The header of the message holds the timestamps and
+                    // flags of the buffer sent by webrtcsink, in the running
+                    // time of the sender
+                    match utils::buffer_from_data_channel_message(&data) {
+                        Some(mut buffer) => {
+                            mapping
+                                .lock()
+                                .unwrap()
+                                .map(buffer.get_mut().unwrap(), appsrc.current_running_time());
+                            let _ = appsrc.push_buffer(buffer);
+                        }
+                        None => {
+                            gst::warning!(
+                                CAT,
+                                obj = appsrc,
+                                "Dropping data channel message of {} bytes without header",
+                                data.len()
+                            );
+                        }
+                    }
+                }
+            ),
+        );
+        data_channel.connect_closure(
+            "on-message-string",
+            false,
+            glib::closure!(
+                #[watch]
+                appsrc,
+                move |_channel: &WebRTCDataChannel, msg: Option<String>| {
+                    if let Some(msg) = msg {
+                        let mut buffer = gst::Buffer::from_slice(msg.into_bytes());
+                        buffer
+                            .get_mut()
+                            .unwrap()
+                            .set_pts(appsrc.current_running_time());
+                        let _ = appsrc.push_buffer(buffer);
+                    }
+                }
+            ),
+        );
+        data_channel.connect_closure(
+            "on-close",
+            false,
+            glib::closure!(
+                #[watch]
+                appsrc,
+                move |_channel: &WebRTCDataChannel| {
+                    let _ = appsrc.end_of_stream();
+                }
+            ),
+        );
+
+        appsrc.sync_state_with_parent()?;
+
+        Ok(())
+    }
+
     fn maybe_start_signaller(&self) {
         let obj = self.obj();
         let mut state = self.state.lock().unwrap();
@@ -1443,6 +1719,8 @@ impl BaseWebRTCSrc {
             glib::closure!(
                 #[weak(rename_to = this)]
                 self,
+                #[weak]
+                bin,
                 #[to_owned]
                 session_id,
                 move |_webrtcbin: gst::Bin, data_channel: glib::Object| {
@@ -1452,7 +1730,19 @@ impl BaseWebRTCSrc {
                         gst::error!(CAT, imp = this, "session {session_id:?} not found");
                         return;
                     };
-                    session.on_data_channel(data_channel, &this.obj());
+                    let data_channel = session.on_data_channel(data_channel, &this.obj());
+                    drop(state);
+
+                    if let Some(data_channel) = data_channel {
+                        if let Err(err) = this.expose_data_channel(&bin, &session_id, &data_channel)
+                        {
+                            gst::warning!(
+                                CAT,
+                                imp = this,
+                                "Failed to expose data channel {data_channel:?}: {err:?}"
+                            );
+                        }
+                    }
                 }
             ),
         );
@@ -1531,6 +1821,14 @@ impl ElementImpl for BaseWebRTCSrc {
                     WebRTCSrcPad::static_type(),
                 )
                 .unwrap(),
+                gst::PadTemplate::with_gtype(
+                    "data_%s",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Sometimes,
+                    &gst::Caps::new_any(),
+                    WebRTCSrcPad::static_type(),
+                )
+                .unwrap(),
             ]
         });
 
@@ -1860,6 +2158,134 @@ pub(super) mod whip {
     }
 }
 
+#[cfg(feature = "janus")]
+pub(super) mod janus {
+    use super::*;
+    use crate::janusvr_signaller::{JanusVRSignallerStr, JanusVRSignallerU64};
+
+    #[derive(Debug, Clone, Default)]
+    struct JanusSettings {
+        use_string_ids: bool,
+    }
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = crate::webrtcsrc::JanusVRWebRTCSrc)]
+    pub struct JanusVRWebRTCSrc {
+        /**
+         * GstJanusVRWebRTCSrc:use-string-ids:
+         *
+         * By default Janus uses `u64` ids to identify the room, the feed, etc.
+         * But it can be changed to strings using the `strings_ids` option in `janus.plugin.videoroom.jcfg`.
+         * In such case, `janusvrwebrtcsrc` has to be created using `use-string-ids=true` so its signaller
+         * uses the right types for such ids and properties.
+         *
+         * Since: plugins-rs-0.14.0
+         */
+        #[property(name="use-string-ids", get, construct_only, type = bool, member = use_string_ids, blurb = "Use strings instead of u64 for Janus IDs, see strings_ids config option in janus.plugin.videoroom.jcfg")]
+        settings: Mutex<JanusSettings>,
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for JanusVRWebRTCSrc {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let settings = self.settings.lock().unwrap();
+            let element = self.obj();
+            let ws = element
+                .upcast_ref::<crate::webrtcsrc::BaseWebRTCSrc>()
+                .imp();
+
+            let signaller: Signallable = if settings.use_string_ids {
+                JanusVRSignallerStr::new_consumer().upcast()
+            } else {
+                JanusVRSignallerU64::new_consumer().upcast()
+            };
+
+            let _ = ws.set_signaller(signaller);
+        }
+    }
+
+    impl GstObjectImpl for JanusVRWebRTCSrc {}
+
+    impl BinImpl for JanusVRWebRTCSrc {}
+
+    impl ElementImpl for JanusVRWebRTCSrc {
+        fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+            static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> =
+                LazyLock::new(|| {
+                    gst::subclass::ElementMetadata::new(
+                        "JanusVRWebRTCSrc",
+                        "Source/Network/WebRTC",
+                        "WebRTC source with Janus Video Room signaller",
+                        "agent <agent@local>",
+                    )
+                });
+
+            Some(&*ELEMENT_METADATA)
+        }
+    }
+
+    impl BaseWebRTCSrcImpl for JanusVRWebRTCSrc {}
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for JanusVRWebRTCSrc {
+        const NAME: &'static str = "GstJanusVRWebRTCSrc";
+        type Type = crate::webrtcsrc::JanusVRWebRTCSrc;
+        type ParentType = crate::webrtcsrc::BaseWebRTCSrc;
+    }
+}
+
+pub(super) mod json {
+    use super::*;
+    use crate::json_signaller::JsonSignaller;
+    use crate::signaller::WebRTCSignallerRole;
+
+    #[derive(Default)]
+    pub struct JsonWebRTCSrc {}
+
+    impl ObjectImpl for JsonWebRTCSrc {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let element = self.obj();
+            let ws = element
+                .upcast_ref::<crate::webrtcsrc::BaseWebRTCSrc>()
+                .imp();
+
+            let _ = ws.set_signaller(JsonSignaller::new(WebRTCSignallerRole::Consumer).upcast());
+        }
+    }
+
+    impl GstObjectImpl for JsonWebRTCSrc {}
+
+    impl BinImpl for JsonWebRTCSrc {}
+
+    impl ElementImpl for JsonWebRTCSrc {
+        fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+            static ELEMENT_METADATA: LazyLock<gst::subclass::ElementMetadata> =
+                LazyLock::new(|| {
+                    gst::subclass::ElementMetadata::new(
+                        "JsonWebRTCSrc",
+                        "Source/Network/WebRTC",
+                        "WebRTC source with a signaller configured by JSON message templates",
+                        "agent <agent@local>",
+                    )
+                });
+
+            Some(&*ELEMENT_METADATA)
+        }
+    }
+
+    impl BaseWebRTCSrcImpl for JsonWebRTCSrc {}
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for JsonWebRTCSrc {
+        const NAME: &'static str = "GstJsonWebRTCSrc";
+        type Type = crate::webrtcsrc::JsonWebRTCSrc;
+        type ParentType = crate::webrtcsrc::BaseWebRTCSrc;
+    }
+}
+
 #[cfg(feature = "livekit")]
 pub(super) mod livekit {
     use super::*;
@@ -54,11 +54,20 @@ glib::wrapper! {
     pub struct WhipServerSrc(ObjectSubclass<imp::whip::WhipServerSrc>) @extends BaseWebRTCSrc, gst::Bin, gst::Element, gst::Object, @implements gst::URIHandler, gst::ChildProxy;
 }
 
+#[cfg(feature = "janus")]
+glib::wrapper! {
+    pub struct JanusVRWebRTCSrc(ObjectSubclass<imp::janus::JanusVRWebRTCSrc>) @extends BaseWebRTCSrc, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy;
+}
+
 #[cfg(feature = "livekit")]
 glib::wrapper! {
     pub struct LiveKitWebRTCSrc(ObjectSubclass<imp::livekit::LiveKitWebRTCSrc>) @extends BaseWebRTCSrc, gst::Bin, gst::Element, gst::Object, gst::ChildProxy;
 }
 
+glib::wrapper! {
+    pub struct JsonWebRTCSrc(ObjectSubclass<imp::json::JsonWebRTCSrc>) @extends BaseWebRTCSrc, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy;
+}
+
 glib::wrapper! {
     pub struct WebRTCSrcPad(ObjectSubclass<pad::WebRTCSrcPad>) @extends gst::GhostPad, gst::ProxyPad, gst::Pad, gst::Object;
 }
@@ -90,6 +99,66 @@ pub fn register(plugin: Option<&gst::Plugin>) -> Result<(), glib::BoolError> {
         WhipServerSrc::static_type(),
     )?;
 
+    #[cfg(feature = "janus")]
+    /**
+     * element-janusvrwebrtcsrc:
+     *
+     * The `janusvrwebrtcsrc` element plays the streams published in a
+     * [Video Room](https://janus.conf.meetecho.com/docs/videoroom) of the
+     * [Janus Gateway](https://github.com/meetecho/janus-gateway), for example by
+     * #janusvrwebrtcsink.
+     *
+     * By default the element subscribes to all the publishers of the room,
+     * including the ones joining after it, and exposes their streams on
+     * per-publisher source pads. Use `signaller::feed-id` to only subscribe to a
+     * single publisher:
+     *
+     * ```shell
+     * gst-launch-1.0 janusvrwebrtcsrc signaller::room-id=1234 signaller::feed-id=5678 \
+     *     ! videoconvert ! autovideosink
+     * ```
+     *
+     * The Janus endpoint can be changed with `signaller::janus-endpoint`, it
+     * defaults to `ws://127.0.0.1:8188`.
+     *
+     * As for #janusvrwebrtcsink, `use-string-ids=true` must be used if Janus is
+     * configured with the `string_ids` option.
+     *
+     * Since: plugins-rs-0.14.0
+     */
+    gst::Element::register(
+        plugin,
+        "janusvrwebrtcsrc",
+        gst::Rank::NONE,
+        JanusVRWebRTCSrc::static_type(),
+    )?;
+
+    /**
+     * element-jsonwebrtcsrc:
+     *
+     * `jsonwebrtcsrc` is the source counterpart of #jsonwebrtcsink: once
+     * connected it sends `signaller::start-session-template` to request a
+     * session with `signaller::producer-peer-id` and plays the streams of the
+     * session started by the server.
+     *
+     * ```bash
+     * $ gst-launch-1.0 jsonwebrtcsrc signaller::uri=wss://signalling.example.com/ws \
+     *     signaller::producer-peer-id=camera \
+     *     signaller::start-session-template='{"op":"watch","camera":"${peer-id}"}' \
+     *     signaller::session-started-match=/op=offer \
+     *     signaller::sdp-template='{"op":"answer","sdp":"${sdp}"}' \
+     *     ! videoconvert ! autovideosink
+     * ```
+     *
+     * Since: plugins-rs-0.14.0
+     */
+    gst::Element::register(
+        plugin,
+        "jsonwebrtcsrc",
+        gst::Rank::NONE,
+        JsonWebRTCSrc::static_type(),
+    )?;
+
     #[cfg(feature = "livekit")]
     /**
      * element-livekitwebrtcsrc:
@@ -11,6 +11,7 @@ use std::sync::Mutex;
 #[derive(Default)]
 pub struct WebRTCSrcPad {
     needs_raw: AtomicBool,
+    needs_depayloading: AtomicBool,
     stream_id: Mutex<Option<String>>,
     webrtcbin_pad: Mutex<Option<gst::glib::WeakRef<gst::Pad>>>,
 }
@@ -24,6 +25,15 @@ impl WebRTCSrcPad {
         self.needs_raw.load(Ordering::SeqCst)
     }
 
+    pub fn set_needs_depayloading(&self, encoded_wanted: bool) {
+        self.needs_depayloading
+            .store(encoded_wanted, Ordering::SeqCst);
+    }
+
+    pub fn needs_depayloading(&self) -> bool {
+        self.needs_depayloading.load(Ordering::SeqCst)
+    }
+
     pub fn set_stream_id(&self, stream_id: &str) {
         *self.stream_id.lock().unwrap() = Some(stream_id.to_string());
     }
@@ -1187,3 +1187,777 @@ impl ObjectImpl for WhipServer {
         }
     }
 }
+
+// WHEP server implementation
+
+const WHEP_ROOT: &str = "whep";
+
+#[derive(Debug, Default)]
+struct WhepSession {
+    /// Sends the answer to the pending POST request of the session
+    sdp_answer: Option<mpsc::Sender<Option<SDPMessage>>>,
+    /// Media IDs of the offer, used to map trickled candidates to m-lines
+    mids: Vec<Option<String>>,
+}
+
+#[derive(Debug)]
+struct WhepServerSettings {
+    stun_server: Option<String>,
+    turn_servers: gst::Array,
+    host_addr: Url,
+    timeout: u32,
+    shutdown_signal: Option<tokio::sync::oneshot::Sender<()>>,
+    server_handle: Option<tokio::task::JoinHandle<()>>,
+    sessions: HashMap<String, WhepSession>,
+}
+
+impl Default for WhepServerSettings {
+    fn default() -> Self {
+        Self {
+            host_addr: Url::parse(DEFAULT_HOST_ADDR).unwrap(),
+            stun_server: DEFAULT_STUN_SERVER.map(String::from),
+            turn_servers: gst::Array::new(Vec::new() as Vec<glib::SendValue>),
+            timeout: DEFAULT_TIMEOUT,
+            shutdown_signal: None,
+            server_handle: None,
+            sessions: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WhepServer {
+    settings: Mutex<WhepServerSettings>,
+}
+
+/// Extracts the candidates of a trickle ICE SDP fragment (RFC 8840), along
+/// with the index and media ID of the m-line they belong to. `mids` lists the
+/// media IDs of the offer, candidates of a section without a known media ID
+/// are mapped according to the position of the section in the fragment.
+fn parse_trickle_ice_sdpfrag(
+    fragment: &str,
+    mids: &[Option<String>],
+) -> Vec<(u32, Option<String>, String)> {
+    let mut candidates = Vec::new();
+    let mut section_idx: Option<usize> = None;
+    let mut mid: Option<String> = None;
+
+    for line in fragment.lines().map(str::trim) {
+        if line.starts_with("m=") {
+            section_idx = Some(section_idx.map_or(0, |idx| idx + 1));
+            mid = None;
+        } else if let Some(value) = line.strip_prefix("a=mid:") {
+            mid = Some(value.to_string());
+        } else if let Some(candidate) = line
+            .strip_prefix("a=")
+            .filter(|attr| attr.starts_with("candidate:"))
+        {
+            let mline_idx = mid
+                .as_deref()
+                .and_then(|mid| mids.iter().position(|other| other.as_deref() == Some(mid)))
+                .or(section_idx)
+                .unwrap_or(0);
+
+            candidates.push((mline_idx as u32, mid.clone(), candidate.to_string()));
+        }
+    }
+
+    candidates
+}
+
+impl WhepServer {
+    pub fn on_webrtcbin_ready(&self) -> RustClosure {
+        glib::closure!(|signaller: &super::WhepServerSignaller,
+                        session_id: &str,
+                        webrtcbin: &gst::Element| {
+            webrtcbin.connect_notify(
+                Some("ice-gathering-state"),
+                glib::clone!(
+                    #[weak]
+                    signaller,
+                    #[to_owned]
+                    session_id,
+                    move |webrtcbin, _pspec| {
+                        let state =
+                            webrtcbin.property::<WebRTCICEGatheringState>("ice-gathering-state");
+
+                        if state != WebRTCICEGatheringState::Complete {
+                            return;
+                        }
+
+                        gst::info!(
+                            CAT,
+                            obj = signaller,
+                            "ICE gathering complete for {session_id}"
+                        );
+
+                        // The answer is only sent once all our candidates are
+                        // gathered as WHEP doesn't support trickling them
+                        let answer = webrtcbin
+                            .property::<Option<WebRTCSessionDescription>>("local-description")
+                            .map(|answer_desc| answer_desc.sdp().to_owned());
+
+                        let tx = {
+                            let mut settings = signaller.imp().settings.lock().unwrap();
+                            settings
+                                .sessions
+                                .get_mut(&session_id)
+                                .and_then(|session| session.sdp_answer.take())
+                        };
+
+                        let Some(tx) = tx else {
+                            gst::debug!(
+                                CAT,
+                                obj = signaller,
+                                "No pending request for session {session_id}"
+                            );
+                            return;
+                        };
+
+                        RUNTIME.spawn(glib::clone!(
+                            #[strong]
+                            signaller,
+                            async move {
+                                if let Err(e) = tx.send(answer).await {
+                                    gst::error!(CAT, obj = signaller, "Failed to send SDP {e}");
+                                }
+                            }
+                        ));
+                    }
+                ),
+            );
+        })
+    }
+
+    fn link_headers(&self) -> HeaderMap {
+        let settings = self.settings.lock().unwrap();
+        let mut links = HeaderMap::new();
+
+        let ice_servers = settings.stun_server.iter().cloned().chain(
+            settings
+                .turn_servers
+                .iter()
+                .filter_map(|turn_server| turn_server.get::<String>().ok()),
+        );
+
+        for ice_server in ice_servers {
+            match build_link_header(ice_server.as_str()) {
+                Ok(link) => {
+                    links.append(LINK, HeaderValue::from_str(link.as_str()).unwrap());
+                }
+                Err(e) => {
+                    gst::error!(CAT, imp = self, "Failed to parse {ice_server:?} : {e:?}");
+                }
+            }
+        }
+
+        links
+    }
+
+    fn status_response(status: StatusCode) -> http::Response<Body> {
+        http::Response::builder()
+            .status(status)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    async fn post_handler(
+        &self,
+        body: warp::hyper::body::Bytes,
+    ) -> Result<http::Response<warp::hyper::Body>, warp::Rejection> {
+        let offer_sdp = match gst_sdp::SDPMessage::parse_buffer(body.as_ref()) {
+            Ok(offer_sdp) => offer_sdp,
+            Err(err) => {
+                gst::error!(CAT, imp = self, "Could not parse offer SDP: {err}");
+                return Ok(Self::status_response(StatusCode::BAD_REQUEST));
+            }
+        };
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let (tx, mut rx) = mpsc::channel::<Option<SDPMessage>>(1);
+        let wait_timeout = {
+            let mut settings = self.settings.lock().unwrap();
+            settings.sessions.insert(
+                session_id.clone(),
+                WhepSession {
+                    sdp_answer: Some(tx),
+                    mids: offer_sdp
+                        .medias()
+                        .map(|media| media.attribute_val("mid").map(String::from))
+                        .collect(),
+                },
+            );
+            settings.timeout
+        };
+
+        let offer =
+            gst_webrtc::WebRTCSessionDescription::new(gst_webrtc::WebRTCSDPType::Offer, offer_sdp);
+
+        // The viewer is both the peer and the session
+        self.obj().emit_by_name::<()>(
+            "session-requested",
+            &[&session_id, &session_id, &Some(offer)],
+        );
+
+        let canceller = Mutex::new(None);
+        let answer = match wait_async(&canceller, rx.recv(), wait_timeout).await {
+            Ok(Some(Some(answer))) => answer.as_text().map_err(|e| format!("{e:?}")),
+            Ok(_) => Err("No SDP answer for session".to_string()),
+            Err(WaitError::FutureAborted) => Err("Aborted".to_string()),
+            Err(WaitError::FutureError(err)) => Err(err.to_string()),
+        };
+
+        let answer = match answer {
+            Ok(answer) => answer,
+            Err(err) => {
+                gst::error!(CAT, imp = self, "Failed to answer {session_id}: {err}");
+
+                if self
+                    .settings
+                    .lock()
+                    .unwrap()
+                    .sessions
+                    .remove(&session_id)
+                    .is_some()
+                {
+                    self.obj()
+                        .emit_by_name::<bool>("session-ended", &[&session_id.as_str()]);
+                }
+
+                let res = http::Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::from(err))
+                    .unwrap();
+
+                return Ok(res);
+            }
+        };
+
+        gst::debug!(CAT, imp = self, "Answering {session_id} with {answer}");
+
+        let resource_url = "/".to_owned() + WHEP_ROOT + "/" + RESOURCE_PATH + "/" + &session_id;
+        let mut res = http::Response::builder()
+            .status(StatusCode::CREATED)
+            .header(CONTENT_TYPE, CONTENT_SDP)
+            .header("location", resource_url)
+            .body(Body::from(answer))
+            .unwrap();
+
+        res.headers_mut().extend(self.link_headers());
+
+        Ok(res)
+    }
+
+    async fn patch_handler(
+        &self,
+        id: String,
+        body: warp::hyper::body::Bytes,
+    ) -> Result<http::Response<warp::hyper::Body>, warp::Rejection> {
+        let mids = match self.settings.lock().unwrap().sessions.get(&id) {
+            Some(session) => session.mids.clone(),
+            None => return Ok(Self::status_response(StatusCode::NOT_FOUND)),
+        };
+
+        let Ok(fragment) = std::str::from_utf8(body.as_ref()) else {
+            return Ok(Self::status_response(StatusCode::BAD_REQUEST));
+        };
+
+        // FIXME: ICE restarts are not supported, new credentials are ignored
+        for (mline_idx, mid, candidate) in parse_trickle_ice_sdpfrag(fragment, &mids) {
+            gst::trace!(
+                CAT,
+                imp = self,
+                "Session {id}: adding candidate {candidate} for m-line {mline_idx}"
+            );
+
+            self.obj().emit_by_name::<()>(
+                "handle-ice",
+                &[&id.as_str(), &mline_idx, &mid, &candidate.as_str()],
+            );
+        }
+
+        Ok(Self::status_response(StatusCode::NO_CONTENT))
+    }
+
+    async fn delete_handler(
+        &self,
+        id: String,
+    ) -> Result<http::Response<warp::hyper::Body>, warp::Rejection> {
+        // Dropping the session also fails its pending POST request, if any
+        if self.settings.lock().unwrap().sessions.remove(&id).is_none() {
+            return Ok(Self::status_response(StatusCode::NOT_FOUND));
+        }
+
+        self.obj()
+            .emit_by_name::<bool>("session-ended", &[&id.as_str()]);
+        gst::info!(CAT, imp = self, "Ended session {id}");
+
+        Ok(Self::status_response(StatusCode::OK))
+    }
+
+    async fn options_handler(&self) -> Result<http::Response<warp::hyper::Body>, warp::Rejection> {
+        let mut res = http::Response::builder()
+            .header("Access-Post", CONTENT_SDP)
+            .body(Body::empty())
+            .unwrap();
+
+        res.headers_mut().extend(self.link_headers());
+
+        Ok(res)
+    }
+
+    fn serve(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let mut settings = self.settings.lock().unwrap();
+        let addr = match settings.host_addr.socket_addrs(|| None) {
+            Ok(v) => {
+                gst::info!(CAT, imp = self, "using {:?} as address", v[0]);
+                v[0]
+            }
+            Err(e) => {
+                gst::error!(CAT, imp = self, "error getting addr from uri  {e:?}");
+                self.obj()
+                    .emit_by_name::<()>("error", &[&format!("Unable to start WHEP Server: {e:?}")]);
+                return None;
+            }
+        };
+
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        settings.shutdown_signal = Some(tx);
+        drop(settings);
+
+        let prefix = warp::path(WHEP_ROOT);
+
+        // POST /endpoint
+        let post_filter = warp::post()
+            .and(warp::path(ENDPOINT_PATH))
+            .and(warp::path::end())
+            .and(warp::header::exact(CONTENT_TYPE.as_str(), CONTENT_SDP))
+            .and(warp::body::bytes())
+            .and_then(glib::clone!(
+                #[weak(rename_to = self_)]
+                self,
+                #[upgrade_or_panic]
+                move |body| async move { self_.post_handler(body).await }
+            ));
+
+        // OPTIONS /endpoint
+        let options_filter = warp::options()
+            .and(warp::path(ENDPOINT_PATH))
+            .and(warp::path::end())
+            .and_then(glib::clone!(
+                #[weak(rename_to = self_)]
+                self,
+                #[upgrade_or_panic]
+                move || async move { self_.options_handler().await }
+            ));
+
+        // PATCH /resource/:id
+        let patch_filter = warp::patch()
+            .and(warp::path(RESOURCE_PATH))
+            .and(warp::path::param::<String>())
+            .and(warp::path::end())
+            .and(warp::header::exact(
+                CONTENT_TYPE.as_str(),
+                CONTENT_TRICKLE_ICE,
+            ))
+            .and(warp::body::bytes())
+            .and_then(glib::clone!(
+                #[weak(rename_to = self_)]
+                self,
+                #[upgrade_or_panic]
+                move |id, body| async move { self_.patch_handler(id, body).await }
+            ));
+
+        // DELETE /resource/:id
+        let delete_filter = warp::delete()
+            .and(warp::path(RESOURCE_PATH))
+            .and(warp::path::param::<String>())
+            .and(warp::path::end())
+            .and_then(glib::clone!(
+                #[weak(rename_to = self_)]
+                self,
+                #[upgrade_or_panic]
+                move |id| async move { self_.delete_handler(id).await }
+            ));
+
+        let api = prefix
+            .and(post_filter)
+            .or(prefix.and(options_filter))
+            .or(prefix.and(patch_filter))
+            .or(prefix.and(delete_filter));
+
+        let s = warp::serve(api);
+        let jh = RUNTIME.spawn(async move {
+            let (_, server) = s.bind_with_graceful_shutdown(addr, async move {
+                match rx.await {
+                    Ok(_) => gst::debug!(CAT, "Server shut down signal received"),
+                    Err(e) => gst::error!(CAT, "{e:?}: Sender dropped"),
+                }
+            });
+
+            server.await;
+            gst::debug!(CAT, "Stopped the server task...");
+        });
+
+        gst::debug!(CAT, imp = self, "Started the server...");
+        Some(jh)
+    }
+
+    fn set_host_addr(&self, host_addr: &str) -> Result<(), url::ParseError> {
+        let mut settings = self.settings.lock().unwrap();
+        settings.host_addr = Url::parse(host_addr)?;
+        Ok(())
+    }
+}
+
+impl SignallableImpl for WhepServer {
+    fn start(&self) {
+        gst::info!(CAT, imp = self, "starting the WHEP server");
+        let jh = self.serve();
+        let mut settings = self.settings.lock().unwrap();
+        settings.server_handle = jh;
+    }
+
+    fn stop(&self) {
+        let mut settings = self.settings.lock().unwrap();
+
+        let handle = settings.server_handle.take();
+        let tx = settings.shutdown_signal.take();
+        settings.sessions.clear();
+        drop(settings);
+
+        if let Some(tx) = tx {
+            if tx.send(()).is_err() {
+                gst::error!(
+                    CAT,
+                    imp = self,
+                    "Failed to send shutdown signal. Receiver dropped"
+                );
+            }
+        }
+
+        if let Some(handle) = handle {
+            gst::debug!(CAT, imp = self, "Await server handle to join");
+            RUNTIME.block_on(async {
+                if let Err(e) = handle.await {
+                    gst::error!(CAT, imp = self, "Failed to join server handle: {e:?}");
+                };
+            });
+        }
+
+        gst::info!(CAT, imp = self, "stopped the WHEP server");
+    }
+
+    fn end_session(&self, session_id: &str) {
+        gst::info!(CAT, imp = self, "Session {session_id} ended");
+        // The viewer is not notified, it will notice through ICE
+        self.settings.lock().unwrap().sessions.remove(session_id);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for WhepServer {
+    const NAME: &'static str = "GstWhepServerSignaller";
+    type Type = super::WhepServerSignaller;
+    type ParentType = glib::Object;
+    type Interfaces = (Signallable,);
+}
+
+impl ObjectImpl for WhepServer {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecBoolean::builder("manual-sdp-munging")
+                    .nick("Manual SDP munging")
+                    .blurb("Whether the signaller manages SDP munging itself")
+                    .default_value(false)
+                    .read_only()
+                    .build(),
+                glib::ParamSpecString::builder("host-addr")
+                    .nick("Host address")
+                    .blurb("The the host address of the WHEP endpoint e.g., http://127.0.0.1:8080")
+                    .default_value(DEFAULT_HOST_ADDR)
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
+                glib::ParamSpecString::builder("stun-server")
+                    .nick("STUN Server")
+                    .blurb("The STUN server of the form stun://hostname:port")
+                    .default_value(DEFAULT_STUN_SERVER)
+                    .build(),
+                gst::ParamSpecArray::builder("turn-servers")
+                    .nick("List of TURN Servers to use")
+                    .blurb("The TURN servers of the form <\"turn(s)://username:password@host:port\", \"turn(s)://username1:password1@host1:port1\">")
+                    .element_spec(&glib::ParamSpecString::builder("turn-server")
+                        .nick("TURN Server")
+                        .blurb("The TURN server of the form turn(s)://username:password@host:port.")
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("timeout")
+                    .nick("Timeout")
+                    .blurb("Value in seconds to timeout WHEP endpoint requests (0 = No timeout).")
+                    .maximum(3600)
+                    .default_value(DEFAULT_TIMEOUT)
+                    .build(),
+            ]
+        });
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "host-addr" => {
+                if let Err(e) =
+                    self.set_host_addr(value.get::<&str>().expect("type checked upstream"))
+                {
+                    gst::error!(CAT, "Couldn't set the host address as {e:?}, fallback to the default value {DEFAULT_HOST_ADDR:?}");
+                }
+            }
+            "stun-server" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.stun_server = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+            }
+            "turn-servers" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.turn_servers = value.get::<gst::Array>().expect("type checked upstream")
+            }
+            "timeout" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.timeout = value.get().unwrap();
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "manual-sdp-munging" => false.to_value(),
+            "host-addr" => settings.host_addr.to_string().to_value(),
+            "stun-server" => settings.stun_server.to_value(),
+            "turn-servers" => settings.turn_servers.to_value(),
+            "timeout" => settings.timeout.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whip_signaller::WhepServerSignaller;
+
+    const OFFER: &str = "v=0\r\n\
+        o=- 0 0 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        t=0 0\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+        a=mid:0\r\n\
+        a=recvonly\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+        a=mid:1\r\n\
+        a=recvonly\r\n";
+
+    const CANDIDATE: &str = "candidate:1 1 UDP 2130706431 192.0.2.1 5000 typ host";
+
+    #[test]
+    fn trickle_ice_sdpfrag() {
+        let mids = [Some("0".to_string()), Some("1".to_string())];
+
+        let fragment = format!(
+            "a=ice-ufrag:abcd\r\n\
+            a=ice-pwd:0123456789abcdef\r\n\
+            m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+            a=mid:1\r\n\
+            a={CANDIDATE}\r\n\
+            a=end-of-candidates\r\n"
+        );
+        assert_eq!(
+            parse_trickle_ice_sdpfrag(&fragment, &mids),
+            vec![(1, Some("1".to_string()), CANDIDATE.to_string())]
+        );
+
+        // Without media IDs, candidates are mapped by position
+        let fragment = format!(
+            "m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+            a={CANDIDATE}\r\n\
+            m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+            a={CANDIDATE}\r\n"
+        );
+        assert_eq!(
+            parse_trickle_ice_sdpfrag(&fragment, &mids),
+            vec![
+                (0, None, CANDIDATE.to_string()),
+                (1, None, CANDIDATE.to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn whep_server() {
+        gst::init().unwrap();
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let signaller = WhepServerSignaller::default();
+        signaller.set_property("host-addr", format!("http://127.0.0.1:{port}"));
+
+        let (offer_tx, offer_rx) = std::sync::mpsc::channel();
+        signaller.connect_closure(
+            "session-requested",
+            false,
+            glib::closure!(
+                move |_signaller: &WhepServerSignaller,
+                      session_id: &str,
+                      peer_id: &str,
+                      offer: Option<&WebRTCSessionDescription>| {
+                    assert_eq!(session_id, peer_id);
+                    offer_tx
+                        .send((
+                            session_id.to_string(),
+                            offer.unwrap().sdp().as_text().unwrap(),
+                        ))
+                        .unwrap();
+                }
+            ),
+        );
+
+        let (ice_tx, ice_rx) = std::sync::mpsc::channel();
+        signaller.connect_closure(
+            "handle-ice",
+            false,
+            glib::closure!(move |_signaller: &WhepServerSignaller,
+                                 session_id: &str,
+                                 mline_idx: u32,
+                                 mid: Option<String>,
+                                 candidate: &str| {
+                ice_tx
+                    .send((
+                        session_id.to_string(),
+                        mline_idx,
+                        mid,
+                        candidate.to_string(),
+                    ))
+                    .unwrap();
+            }),
+        );
+
+        let (ended_tx, ended_rx) = std::sync::mpsc::channel();
+        signaller.connect_closure(
+            "session-ended",
+            false,
+            glib::closure!(
+                move |_signaller: &WhepServerSignaller, session_id: &str| -> bool {
+                    ended_tx.send(session_id.to_string()).unwrap();
+                    true
+                }
+            ),
+        );
+
+        signaller.start();
+
+        RUNTIME.block_on(async {
+            let client = reqwest::Client::new();
+            let base_url = format!("http://127.0.0.1:{port}/whep");
+            let timeout = std::time::Duration::from_secs(5);
+
+            // Wait for the server to be up
+            let mut retries = 0;
+            let res = loop {
+                match client
+                    .request(reqwest::Method::OPTIONS, format!("{base_url}/endpoint"))
+                    .send()
+                    .await
+                {
+                    Ok(res) => break res,
+                    Err(err) if retries < 50 => {
+                        gst::debug!(CAT, "Server not up yet: {err}");
+                        retries += 1;
+                        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    }
+                    Err(err) => panic!("Server not reachable: {err}"),
+                }
+            };
+            assert_eq!(res.status(), StatusCode::OK);
+            assert!(res.headers().get(reqwest::header::LINK).is_some());
+
+            let res = client
+                .post(format!("{base_url}/endpoint"))
+                .header(reqwest::header::CONTENT_TYPE, CONTENT_SDP)
+                .body("not an SDP")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+            // Nothing answers in this test, the request stays pending until
+            // the session is deleted
+            let post = RUNTIME.spawn(
+                client
+                    .post(format!("{base_url}/endpoint"))
+                    .header(reqwest::header::CONTENT_TYPE, CONTENT_SDP)
+                    .body(OFFER)
+                    .send(),
+            );
+
+            let (session_id, offer) = offer_rx.recv_timeout(timeout).unwrap();
+            assert!(offer.contains("a=mid:1"));
+
+            let res = client
+                .patch(format!("{base_url}/resource/{session_id}"))
+                .header(reqwest::header::CONTENT_TYPE, CONTENT_TRICKLE_ICE)
+                .body(format!(
+                    "m=video 9 UDP/TLS/RTP/SAVPF 96\r\na=mid:1\r\na={CANDIDATE}\r\n"
+                ))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::NO_CONTENT);
+            assert_eq!(
+                ice_rx.recv_timeout(timeout).unwrap(),
+                (
+                    session_id.clone(),
+                    1,
+                    Some("1".to_string()),
+                    CANDIDATE.to_string()
+                )
+            );
+
+            let res = client
+                .patch(format!("{base_url}/resource/unknown"))
+                .header(reqwest::header::CONTENT_TYPE, CONTENT_TRICKLE_ICE)
+                .body(format!("a={CANDIDATE}\r\n"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+            let res = client
+                .delete(format!("{base_url}/resource/{session_id}"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(ended_rx.recv_timeout(timeout).unwrap(), session_id);
+
+            let res = post.await.unwrap().unwrap();
+            assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+            let res = client
+                .delete(format!("{base_url}/resource/{session_id}"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        });
+
+        signaller.stop();
+    }
+}
@@ -13,6 +13,10 @@ glib::wrapper! {
     pub struct WhipServerSignaller(ObjectSubclass<imp::WhipServer>) @implements Signallable;
 }
 
+glib::wrapper! {
+    pub struct WhepServerSignaller(ObjectSubclass<imp::WhepServer>) @implements Signallable;
+}
+
 impl Default for WhipClientSignaller {
     fn default() -> Self {
         glib::Object::new()
@@ -26,3 +30,11 @@ impl Default for WhipServerSignaller {
         sig
     }
 }
+
+impl Default for WhepServerSignaller {
+    fn default() -> Self {
+        let sig: WhepServerSignaller = glib::Object::new();
+        sig.connect_closure("webrtcbin-ready", false, sig.imp().on_webrtcbin_ready());
+        sig
+    }
+}
@@ -0,0 +1,336 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use gst::{glib, prelude::*};
+
+const SESSION_ID: &str = "session";
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtc test");
+    });
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Relays the session descriptions and ICE candidates of the signallers of
+/// `sink` and `src` to each other, as a signalling server would. Returns a
+/// receiver notified when each signaller is started.
+fn connect_signallers(sink: &gst::Element, src: &gst::Element) -> mpsc::Receiver<()> {
+    let sink_signaller = sink.property::<glib::Object>("signaller");
+    let src_signaller = src.property::<glib::Object>("signaller");
+
+    // Messages are relayed from a thread of their own, in order, as the
+    // elements may hold their locks while sending them
+    let (task_sender, task_receiver) = mpsc::channel::<Task>();
+    std::thread::spawn(move || {
+        for task in task_receiver {
+            task();
+        }
+    });
+
+    let (started_sender, started_receiver) = mpsc::channel();
+
+    for (signaller, peer) in [
+        (&sink_signaller, &src_signaller),
+        (&src_signaller, &sink_signaller),
+    ] {
+        let started_sender = started_sender.clone();
+        signaller.connect("start", false, move |_| {
+            let _ = started_sender.send(());
+            Some(true.to_value())
+        });
+        signaller.connect("stop", false, |_| Some(true.to_value()));
+        signaller.connect("end-session", false, |_| Some(true.to_value()));
+
+        let task_sender_clone = task_sender.clone();
+        let peer_clone = peer.clone();
+        signaller.connect("send-session-description", false, move |args| {
+            let session_id = args[1].get::<String>().unwrap();
+            let desc = args[2]
+                .get::<gst_webrtc::WebRTCSessionDescription>()
+                .unwrap();
+            let peer = peer_clone.clone();
+            let _ = task_sender_clone.send(Box::new(move || {
+                peer.emit_by_name::<()>("session-description", &[&session_id, &desc]);
+            }));
+            Some(true.to_value())
+        });
+
+        let task_sender = task_sender.clone();
+        let peer = peer.clone();
+        signaller.connect("send-ice", false, move |args| {
+            let session_id = args[1].get::<String>().unwrap();
+            let candidate = args[2].get::<String>().unwrap();
+            let sdp_m_line_index = args[3].get::<u32>().unwrap();
+            let sdp_mid = args[4].get::<Option<String>>().unwrap();
+            let peer = peer.clone();
+            let _ = task_sender.send(Box::new(move || {
+                peer.emit_by_name::<()>(
+                    "handle-ice",
+                    &[&session_id, &sdp_m_line_index, &sdp_mid, &candidate],
+                );
+            }));
+            Some(true.to_value())
+        });
+    }
+
+    started_receiver
+}
+
+/// Starts a session between `sink` and `src` once both signallers were
+/// started
+fn start_session(sink: &gst::Element, src: &gst::Element, started: &mpsc::Receiver<()>) {
+    for _ in 0..2 {
+        started
+            .recv_timeout(Duration::from_secs(30))
+            .expect("signaller not started");
+    }
+
+    src.property::<glib::Object>("signaller")
+        .emit_by_name::<()>("session-started", &[&SESSION_ID, &"producer"]);
+    sink.property::<glib::Object>("signaller")
+        .emit_by_name::<()>(
+            "session-requested",
+            &[
+                &SESSION_ID,
+                &"consumer",
+                &None::<gst_webrtc::WebRTCSessionDescription>,
+            ],
+        );
+}
+
+/// Sends the buffers of an appsrc over a data pad of webrtcsink to
+/// webrtcsrc, returning the first two samples received on its data pad with
+/// the index of the buffer each carries. The buffers hold their index, with
+/// a PTS of `100ms * index`.
+fn receive_data_channel_sample(timestamped: bool) -> (gst::Sample, u64, gst::Sample, u64) {
+    let pipeline = gst::parse::launch(
+        "videotestsrc is-live=true ! video/x-raw,width=320,height=240 ! \
+         webrtcsink name=ws video-caps=video/x-vp8 congestion-control=disabled do-fec=false \
+         appsrc name=data format=time is-live=true caps=application/x-test ! ws.data_%u \
+         webrtcsrc name=wsrc enable-data-channel-pads=true \
+         appsink name=data_sink sync=false",
+    )
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    let sink = pipeline.by_name("ws").unwrap();
+    let src = pipeline.by_name("wsrc").unwrap();
+    sink.set_property("stun-server", None::<String>);
+    src.set_property("stun-server", None::<String>);
+
+    let appsrc = pipeline
+        .by_name("data")
+        .unwrap()
+        .downcast::<gst_app::AppSrc>()
+        .unwrap();
+    let appsink = pipeline
+        .by_name("data_sink")
+        .unwrap()
+        .downcast::<gst_app::AppSink>()
+        .unwrap();
+
+    appsrc
+        .static_pad("src")
+        .unwrap()
+        .peer()
+        .unwrap()
+        .set_property("timestamped", timestamped);
+
+    let pipeline_weak = pipeline.downgrade();
+    let appsink_pad = appsink.static_pad("sink").unwrap();
+    src.connect_pad_added(move |_src, pad| {
+        if pad.name().starts_with("data_") {
+            pad.link(&appsink_pad).unwrap();
+            return;
+        }
+
+        let Some(pipeline) = pipeline_weak.upgrade() else {
+            return;
+        };
+        let fakesink = gst::ElementFactory::make("fakesink")
+            .property("async", false)
+            .build()
+            .unwrap();
+        pipeline.add(&fakesink).unwrap();
+        fakesink.sync_state_with_parent().unwrap();
+        pad.link(&fakesink.static_pad("sink").unwrap()).unwrap();
+    });
+
+    let started = connect_signallers(&sink, &src);
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    let push = |i: u64| {
+        let mut buffer = gst::Buffer::from_slice(i.to_be_bytes());
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(gst::ClockTime::from_mseconds(100 * i));
+            buffer.set_duration(gst::ClockTime::from_mseconds(100));
+            buffer.set_flags(gst::BufferFlags::DELTA_UNIT);
+        }
+        appsrc.push_buffer(buffer).unwrap();
+    };
+    let index = |sample: &gst::Sample| {
+        u64::from_be_bytes(
+            sample
+                .buffer()
+                .unwrap()
+                .map_readable()
+                .unwrap()
+                .as_slice()
+                .try_into()
+                .unwrap(),
+        )
+    };
+
+    // Consumers are only accepted once the data pad received its caps
+    push(0);
+    start_session(&sink, &src, &started);
+
+    // Buffers are dropped until the data channel is open
+    let mut next = 1;
+    let first = (next..300)
+        .find_map(|i| {
+            push(i);
+            next = i + 1;
+            appsink.try_pull_sample(gst::ClockTime::from_mseconds(100))
+        })
+        .expect("no data received");
+    let first_index = index(&first);
+
+    // The sample is timestamped in the running time of webrtcsrc
+    let now = src.current_running_time().unwrap();
+    assert!(first.buffer().unwrap().pts().unwrap() <= now);
+
+    push(next + 10);
+    let second = appsink
+        .try_pull_sample(gst::ClockTime::from_seconds(5))
+        .expect("no more data received");
+    let second_index = index(&second);
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    (first, first_index, second, second_index)
+}
+
+#[test]
+fn test_data_channel_plain() {
+    init();
+
+    let (first, _, second, _) = receive_data_channel_sample(false);
+
+    let caps = first.caps().unwrap();
+    assert_eq!(caps.structure(0).unwrap().name(), "application/x-test");
+
+    // Only the payload is sent, as other peers would
+    for sample in [&first, &second] {
+        let buffer = sample.buffer().unwrap();
+        assert_eq!(buffer.size(), 8);
+        assert!(buffer.pts().is_some());
+        assert_eq!(buffer.duration(), None);
+        assert!(!buffer.flags().contains(gst::BufferFlags::DELTA_UNIT));
+    }
+}
+
+#[test]
+fn test_data_channel_timestamps() {
+    init();
+
+    let (first, first_index, second, second_index) = receive_data_channel_sample(true);
+
+    let caps = first.caps().unwrap();
+    assert_eq!(caps.structure(0).unwrap().name(), "application/x-test");
+
+    for sample in [&first, &second] {
+        let buffer = sample.buffer().unwrap();
+        assert_eq!(buffer.size(), 8);
+        assert_eq!(buffer.dts(), None);
+        assert_eq!(buffer.duration(), Some(gst::ClockTime::from_mseconds(100)));
+        assert!(buffer.flags().contains(gst::BufferFlags::DELTA_UNIT));
+    }
+
+    // The buffers keep the distance between their timestamps
+    assert_eq!(
+        second.buffer().unwrap().pts().unwrap() - first.buffer().unwrap().pts().unwrap(),
+        gst::ClockTime::from_mseconds(100 * (second_index - first_index))
+    );
+}
+
+#[test]
+fn test_encoded_output() {
+    init();
+
+    let pipeline = gst::parse::launch(
+        "videotestsrc is-live=true ! video/x-raw,width=320,height=240 ! \
+         webrtcsink name=ws video-caps=video/x-vp8 congestion-control=disabled do-fec=false \
+         webrtcsrc name=wsrc \
+         appsink name=video_sink sync=false caps=video/x-vp8",
+    )
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    let sink = pipeline.by_name("ws").unwrap();
+    let src = pipeline.by_name("wsrc").unwrap();
+    sink.set_property("stun-server", None::<String>);
+    src.set_property("stun-server", None::<String>);
+
+    let appsink = pipeline
+        .by_name("video_sink")
+        .unwrap()
+        .downcast::<gst_app::AppSink>()
+        .unwrap();
+
+    // Downstream only accepts encoded caps, so the stream is only depayloaded
+    let appsink_pad = appsink.static_pad("sink").unwrap();
+    src.connect_pad_added(move |_src, pad| {
+        if pad.name().starts_with("video_") {
+            pad.link(&appsink_pad).unwrap();
+        }
+    });
+
+    let started = connect_signallers(&sink, &src);
+    pipeline.set_state(gst::State::Playing).unwrap();
+    start_session(&sink, &src, &started);
+
+    let sample = appsink
+        .try_pull_sample(gst::ClockTime::from_seconds(30))
+        .expect("no video received");
+
+    let caps = sample.caps().unwrap();
+    assert_eq!(caps.structure(0).unwrap().name(), "video/x-vp8");
+
+    // Output starts on a keyframe, with timestamps from the RTP clock
+    let buffer = sample.buffer().unwrap();
+    assert!(!buffer.flags().contains(gst::BufferFlags::DELTA_UNIT));
+    let pts = buffer.pts().expect("no PTS");
+
+    let sample = appsink
+        .try_pull_sample(gst::ClockTime::from_seconds(5))
+        .expect("no more video received");
+    assert!(sample.buffer().unwrap().pts().expect("no PTS") > pts);
+
+    let src_bin = src.downcast_ref::<gst::Bin>().unwrap();
+    let factories = src_bin
+        .iterate_recurse()
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|element| element.factory())
+        .collect::<Vec<_>>();
+    assert!(factories
+        .iter()
+        .any(|factory| factory.has_type(gst::ElementFactoryType::DEPAYLOADER)));
+    assert!(!factories
+        .iter()
+        .any(|factory| factory.has_type(gst::ElementFactoryType::DECODER)));
+
+    pipeline.set_state(gst::State::Null).unwrap();
+}